@@ -48,6 +48,18 @@ impl ResponseBody<'_> {
     }
 }
 
+impl Drop for ResponseBody<'_> {
+    fn drop(&mut self) {
+        // connection with unread response body left on wire can not be reused.
+        #[cfg(feature = "http1")]
+        if let Self::H1(ref mut body) = *self {
+            if !body.is_eof() {
+                body.conn().destroy_on_drop()
+            }
+        }
+    }
+}
+
 impl fmt::Debug for ResponseBody<'_> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match *self {
@@ -15,6 +15,8 @@ pub struct ClientBuilder {
     connector_builder: TlsConnectorBuilder,
     resolver: Resolver,
     pool_capacity: usize,
+    pool_max_per_host: usize,
+    pool_idle_timeout: Duration,
     timeout_config: TimeoutConfig,
     local_addr: Option<SocketAddr>,
     max_http_version: Version,
@@ -26,6 +28,8 @@ impl Default for ClientBuilder {
             connector_builder: TlsConnectorBuilder::Default,
             resolver: Resolver::default(),
             pool_capacity: 128,
+            pool_max_per_host: 128,
+            pool_idle_timeout: Duration::from_secs(600),
             timeout_config: TimeoutConfig::default(),
             local_addr: None,
             max_http_version: Version::HTTP_3,
@@ -132,6 +136,28 @@ impl ClientBuilder {
         self
     }
 
+    /// Set max number of connections to the same host that can be used at the same time.
+    /// Request exceeding the limit waits for connection to be returned to pool.
+    /// Multiplexed http/2 and http/3 connection is shared by requests and not limited.
+    ///
+    /// Default to 128
+    ///
+    /// # Panics:
+    /// When pass 0 as max connections per host.
+    pub fn set_pool_max_per_host(mut self, max: usize) -> Self {
+        assert_ne!(max, 0);
+        self.pool_max_per_host = max;
+        self
+    }
+
+    /// Set duration an idle connection can stay in pool before it's dropped.
+    ///
+    /// Default to 600 seconds.
+    pub fn set_pool_idle_timeout(mut self, dur: Duration) -> Self {
+        self.pool_idle_timeout = dur;
+        self
+    }
+
     /// Set max http version client would be used.
     ///
     /// Default to Http/3
@@ -227,7 +253,7 @@ impl ClientBuilder {
                 };
 
                 Client {
-                    pool: Pool::with_capacity(self.pool_capacity),
+                    pool: Pool::new(self.pool_capacity, self.pool_max_per_host, self.pool_idle_timeout),
                     connector: Connector::default(),
                     resolver: self.resolver,
                     timeout_config: self.timeout_config,
@@ -240,7 +266,7 @@ impl ClientBuilder {
 
             #[cfg(not(feature = "http3"))]
             Client {
-                pool: Pool::with_capacity(self.pool_capacity),
+                pool: Pool::new(self.pool_capacity, self.pool_max_per_host, self.pool_idle_timeout),
                 connector: Connector::default(),
                 resolver: self.resolver,
                 timeout_config: self.timeout_config,
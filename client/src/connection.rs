@@ -157,7 +157,8 @@ impl From<crate::h3::Connection> for Connection {
 #[doc(hidden)]
 #[derive(PartialEq, Eq, Debug, Clone, Hash)]
 pub enum ConnectionKey {
-    Regular(Authority),
+    Tcp(Authority),
+    Tls(Authority),
     #[cfg(unix)]
     Unix(AuthorityWithPath),
 }
@@ -182,10 +183,29 @@ impl Hash for AuthorityWithPath {
     }
 }
 
+impl ConnectionKey {
+    // construct uri connecting to the same destination as the key.
+    #[cfg(feature = "http1")]
+    pub(crate) fn to_uri(&self) -> xitca_http::http::Uri {
+        let builder = xitca_http::http::Uri::builder();
+        let builder = match *self {
+            Self::Tcp(ref authority) => builder.scheme("http").authority(authority.clone()).path_and_query("/"),
+            Self::Tls(ref authority) => builder.scheme("https").authority(authority.clone()).path_and_query("/"),
+            #[cfg(unix)]
+            Self::Unix(ref uri) => builder
+                .scheme("unix")
+                .authority(uri.authority.clone())
+                .path_and_query(uri.path_and_query.clone()),
+        };
+        builder.build().expect("uri from ConnectionKey must be valid")
+    }
+}
+
 impl From<&Uri<'_>> for ConnectionKey {
     fn from(uri: &Uri<'_>) -> Self {
         match *uri {
-            Uri::Tcp(uri) | Uri::Tls(uri) => ConnectionKey::Regular(uri.authority().unwrap().clone()),
+            Uri::Tcp(uri) => ConnectionKey::Tcp(uri.authority().unwrap().clone()),
+            Uri::Tls(uri) => ConnectionKey::Tls(uri.authority().unwrap().clone()),
             #[cfg(unix)]
             Uri::Unix(uri) => ConnectionKey::Unix(AuthorityWithPath {
                 authority: uri.authority().unwrap().clone(),
@@ -1,5 +1,4 @@
 use std::{
-    io,
     ops::DerefMut,
     pin::Pin,
     task::{ready, Context, Poll},
@@ -28,6 +27,11 @@ impl<C> ResponseBody<C> {
     pub(crate) fn conn(&mut self) -> &mut C {
         &mut self.conn
     }
+
+    // body is fully read and connection is ready for the next request.
+    pub(crate) fn is_eof(&self) -> bool {
+        matches!(self.decoder, TransferCoding::Eof | TransferCoding::Length(0))
+    }
 }
 
 impl<C> Stream for ResponseBody<C>
@@ -47,10 +51,10 @@ where
                     let n = ready!(poll_read_buf(Pin::new(&mut *this.conn), cx, &mut this.buf))?;
 
                     if n == 0 {
-                        return Poll::Ready(Some(Err(io::Error::from(io::ErrorKind::UnexpectedEof).into())));
+                        return Poll::Ready(Some(Err(BodyError::Disconnected)));
                     }
                 }
-                ChunkResult::Err(e) => return Poll::Ready(Some(Err(e.into()))),
+                ChunkResult::Err(e) => return Poll::Ready(Some(Err(BodyError::Parse(e.into())))),
                 _ => return Poll::Ready(None),
             }
         }
@@ -20,10 +20,15 @@ use crate::{
 
 use super::context::Context;
 
+/// Send request and decode response head.
+///
+/// When `retry` is Some the encoded request head is stored in it and the same request can be sent
+/// again with [resend] on a new connection.
 pub(crate) async fn send<S, B, E>(
     stream: &mut S,
     date: DateTimeHandle<'_>,
     mut req: http::Request<B>,
+    retry: Option<&mut Option<Bytes>>,
 ) -> Result<(http::Response<()>, BytesMut, TransferCoding, bool), Error>
 where
     S: AsyncRead + AsyncWrite + Unpin,
@@ -59,7 +64,14 @@ where
     let encoder = ctx.encode_head(&mut buf, parts, &body)?;
 
     // send request head for potential intermediate handling like expect header.
-    stream.write_all_buf(&mut buf).await?;
+    match retry {
+        // keep encoded head for retry. the write buffer is handed over without copying.
+        Some(retry) => {
+            let head = retry.insert(buf.split().freeze());
+            stream.write_all(head).await?;
+        }
+        None => stream.write_all_buf(&mut buf).await?,
+    }
     stream.flush().await?;
 
     // TODO: concurrent read write is needed in case server decide to do two way
@@ -75,7 +87,37 @@ where
         buf.clear();
     }
 
-    // read response head and get body decoder.
+    recv(stream, &mut ctx, buf, is_head_method).await
+}
+
+/// Send encoded request head stored by [send] again. Request body must be empty.
+pub(crate) async fn resend<S>(
+    stream: &mut S,
+    date: DateTimeHandle<'_>,
+    head: Bytes,
+    is_head_method: bool,
+) -> Result<(http::Response<()>, BytesMut, TransferCoding, bool), Error>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
+    let mut ctx = Context::<128>::new(&date);
+
+    stream.write_all(&head).await?;
+    stream.flush().await?;
+
+    recv(stream, &mut ctx, BytesMut::new(), is_head_method).await
+}
+
+// read response head and get body decoder.
+async fn recv<S, const HEADER_LIMIT: usize>(
+    stream: &mut S,
+    ctx: &mut Context<'_, '_, HEADER_LIMIT>,
+    mut buf: BytesMut,
+    is_head_method: bool,
+) -> Result<(http::Response<()>, BytesMut, TransferCoding, bool), Error>
+where
+    S: AsyncRead + AsyncWrite + Unpin,
+{
     loop {
         let n = stream.read_buf(&mut buf).await?;
 
@@ -130,7 +172,7 @@ where
         }
 
         // body is finished. encode eof and clean up.
-        encoder.encode_eof(buf);
+        encoder.encode_eof(buf)?;
         stream.write_all_buf(buf).await?;
     }
 
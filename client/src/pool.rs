@@ -2,18 +2,23 @@ use std::{
     collections::{HashMap, VecDeque},
     hash::Hash,
     ops::{Deref, DerefMut},
-    sync::Mutex,
+    sync::{Arc, Mutex},
     time::{Duration, Instant},
 };
 
-use tokio::sync::{Semaphore, SemaphorePermit};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore, SemaphorePermit};
 
 use crate::{connection::Multiplex, error::Error};
 
 #[doc(hidden)]
 pub struct Pool<K, C> {
     conns: Mutex<HashMap<K, Value<C>>>,
+    // permits of connections per host. multiplexed connection does not take host permit. entry
+    // is removed when it's last permit holder is dropped.
+    hosts: Mutex<HashMap<K, Arc<Semaphore>>>,
     permits: Semaphore,
+    max_per_host: usize,
+    idle_timeout: Duration,
 }
 
 enum Value<C> {
@@ -26,19 +31,29 @@ where
     K: Eq + Hash + Clone,
     C: Multiplex,
 {
-    pub(crate) fn with_capacity(size: usize) -> Self {
+    pub(crate) fn new(size: usize, max_per_host: usize, idle_timeout: Duration) -> Self {
         Self {
             conns: Mutex::new(HashMap::new()),
+            hosts: Mutex::new(HashMap::new()),
             permits: Semaphore::new(size),
+            max_per_host,
+            idle_timeout,
         }
     }
 
     pub(crate) async fn acquire(&self, key: impl Into<K>) -> Result<Conn<'_, K, C>, Error> {
+        let key = key.into();
+
+        // host permit is acquired before pool permit so a host at it's limit does not occupy
+        // permit other hosts can make use of.
+        let host_permit = match self.host_permits(&key) {
+            Some(permits) => Some(permits.acquire_owned().await.unwrap()),
+            None => None,
+        };
+
         // permit is needed to operate on pool.
         let permit = self.permits.acquire().await.unwrap();
 
-        let key = key.into();
-
         let conn = {
             let mut conns = self.conns.lock().unwrap();
 
@@ -47,11 +62,11 @@ where
                 Some(Value::NonMultiplexable(queue)) => loop {
                     match queue.pop_front() {
                         // drop connection that are expired.
-                        Some(conn) if conn.state.is_expired() => drop(conn),
+                        Some(conn) if conn.state.is_expired(self.idle_timeout) => drop(conn),
                         conn => break conn,
                     }
                 },
-                Some(Value::Multiplexable(conn)) if conn.state.is_expired() => {
+                Some(Value::Multiplexable(conn)) if conn.state.is_expired(self.idle_timeout) => {
                     conns.remove(&key);
                     None
                 }
@@ -65,9 +80,23 @@ where
             key,
             conn,
             permit,
+            host_permit,
             destroy_on_drop: false,
         })
     }
+
+    // return None when key has a multiplexed connection that can be shared.
+    fn host_permits(&self, key: &K) -> Option<Arc<Semaphore>> {
+        if let Some(Value::Multiplexable(_)) = self.conns.lock().unwrap().get(key) {
+            return None;
+        }
+
+        let mut hosts = self.hosts.lock().unwrap();
+        let permits = hosts
+            .entry(key.clone())
+            .or_insert_with(|| Arc::new(Semaphore::new(self.max_per_host)));
+        Some(permits.clone())
+    }
 }
 
 pub struct Conn<'a, K, C>
@@ -79,6 +108,7 @@ where
     key: K,
     conn: Option<PooledConn<C>>,
     permit: SemaphorePermit<'a>,
+    host_permit: Option<OwnedSemaphorePermit>,
     destroy_on_drop: bool,
 }
 
@@ -126,6 +156,17 @@ where
         });
     }
 
+    /// Drop connection that turns out to be unusable. A new one can be added afterwards.
+    #[cfg(feature = "http1")]
+    pub(crate) fn discard(&mut self) {
+        self.conn = None;
+    }
+
+    #[cfg(feature = "http1")]
+    pub(crate) fn key(&self) -> &K {
+        &self.key
+    }
+
     #[cfg(any(feature = "http1", feature = "http2", feature = "http3"))]
     pub(crate) fn destroy_on_drop(&mut self) {
         self.destroy_on_drop = true;
@@ -144,10 +185,10 @@ where
 {
     fn drop(&mut self) {
         if let Some(mut conn) = self.conn.take() {
-            let want_drop = conn.state.is_expired() || self.destroy_on_drop;
+            let want_drop = conn.state.is_expired(self.pool.idle_timeout) || self.destroy_on_drop;
             let mut conns = self.pool.conns.lock().unwrap();
             match conns.get_mut(&self.key) {
-                Some(Value::NonMultiplexable(_)) | None if want_drop => return,
+                Some(Value::NonMultiplexable(_)) | None if want_drop => {}
                 Some(Value::NonMultiplexable(queue)) => {
                     conn.state.update_idle();
                     queue.push_back(conn);
@@ -167,6 +208,15 @@ where
 
             let _ = self.permit;
         }
+
+        if let Some(permit) = self.host_permit.take() {
+            let mut hosts = self.pool.hosts.lock().unwrap();
+            drop(permit);
+            // the map is the only holder when no connection or waiter of the host is alive.
+            if hosts.get(&self.key).is_some_and(|p| Arc::strong_count(p) == 1) {
+                hosts.remove(&self.key);
+            }
+        }
     }
 }
 
@@ -209,8 +259,8 @@ impl ConnState {
         self.idle_since = Instant::now();
     }
 
-    fn is_expired(&self) -> bool {
-        self.born.elapsed() > Duration::from_secs(3600) || self.idle_since.elapsed() > Duration::from_secs(600)
+    fn is_expired(&self, idle_timeout: Duration) -> bool {
+        self.born.elapsed() > Duration::from_secs(3600) || self.idle_since.elapsed() > idle_timeout
     }
 }
 
@@ -226,3 +276,40 @@ impl<C: Multiplex> Multiplex for PooledConn<C> {
         self.conn.is_multiplexable()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct Dummy;
+
+    impl Multiplex for Dummy {
+        fn multiplex(&mut self) -> Self {
+            unreachable!("Dummy is not multiplexable")
+        }
+
+        fn is_multiplexable(&self) -> bool {
+            false
+        }
+    }
+
+    #[tokio::test]
+    async fn evict_host_permits() {
+        let pool = Pool::<&str, Dummy>::new(8, 2, Duration::from_secs(60));
+
+        let conn1 = pool.acquire("a").await.unwrap();
+        let conn2 = pool.acquire("a").await.unwrap();
+        let conn3 = pool.acquire("b").await.unwrap();
+        assert_eq!(pool.hosts.lock().unwrap().len(), 2);
+
+        drop(conn3);
+        assert!(!pool.hosts.lock().unwrap().contains_key("b"));
+
+        // host permits are kept as long as there is a holder.
+        drop(conn1);
+        assert!(pool.hosts.lock().unwrap().contains_key("a"));
+
+        drop(conn2);
+        assert!(pool.hosts.lock().unwrap().is_empty());
+    }
+}
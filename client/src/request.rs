@@ -21,6 +21,9 @@ use crate::{
 #[cfg(any(feature = "http1", feature = "http2", feature = "http3"))]
 use crate::{connection::Connection, error::TimeoutError, timeout::Timeout};
 
+#[cfg(feature = "http1")]
+use crate::connection::Multiplex;
+
 /// crate level HTTP request type.
 pub struct Request<'a, B = Once<Bytes>> {
     /// HTTP request type from [http] crate.
@@ -188,6 +191,16 @@ impl<'a, B> Request<'a, B> {
             conn.add(c);
         }
 
+        // pooled http/1 connection can be closed by server at any time. idempotent request without
+        // body is retried once on a new connection when the pooled one is found closed. encoded
+        // request head is kept for sending it again.
+        #[cfg(feature = "http1")]
+        let mut retry = None;
+        #[cfg(feature = "http1")]
+        let is_retryable = !conn_is_none && !conn.is_multiplexable() && is_retryable(&req);
+        #[cfg(feature = "http1")]
+        let is_head_method = *req.method() == Method::HEAD;
+
         let date = client.date_service.handle();
 
         timer
@@ -200,18 +213,26 @@ impl<'a, B> Request<'a, B> {
                 if matches!(req.version(), Version::HTTP_2 | Version::HTTP_3) {
                     *req.version_mut() = Version::HTTP_11
                 }
-                crate::h1::proto::send(stream, date, req).timeout(timer.as_mut()).await
+                crate::h1::proto::send(stream, date, req, is_retryable.then_some(&mut retry))
+                    .timeout(timer.as_mut())
+                    .await
             }
             #[cfg(feature = "http1")]
             Connection::Tls(ref mut stream) => {
                 if matches!(req.version(), Version::HTTP_2 | Version::HTTP_3) {
                     *req.version_mut() = Version::HTTP_11
                 }
-                crate::h1::proto::send(stream, date, req).timeout(timer.as_mut()).await
+                crate::h1::proto::send(stream, date, req, is_retryable.then_some(&mut retry))
+                    .timeout(timer.as_mut())
+                    .await
             }
             #[cfg(feature = "http1")]
             #[cfg(unix)]
-            Connection::Unix(ref mut stream) => crate::h1::proto::send(stream, date, req).timeout(timer.as_mut()).await,
+            Connection::Unix(ref mut stream) => {
+                crate::h1::proto::send(stream, date, req, is_retryable.then_some(&mut retry))
+                    .timeout(timer.as_mut())
+                    .await
+            }
             #[cfg(feature = "http2")]
             Connection::H2(ref mut stream) => {
                 *req.version_mut() = Version::HTTP_2;
@@ -254,6 +275,34 @@ impl<'a, B> Request<'a, B> {
             _ => panic!("http1 feature is not enabled in Cargo.toml"),
         };
 
+        #[cfg(feature = "http1")]
+        let res = match (res, retry) {
+            (Ok(Err(crate::h1::Error::Io(_))), Some(head)) => {
+                conn.discard();
+
+                timer
+                    .as_mut()
+                    .reset(Instant::now() + client.timeout_config.resolve_timeout);
+
+                let uri = conn.key().to_uri();
+                let mut connect = Connect::new(Uri::try_parse(&uri)?);
+                let c = client
+                    .make_connection(&mut connect, &mut timer, Version::HTTP_11)
+                    .await?;
+                conn.add(c);
+
+                timer
+                    .as_mut()
+                    .reset(Instant::now() + client.timeout_config.request_timeout);
+
+                let date = client.date_service.handle();
+                crate::h1::proto::resend(&mut *conn, date, head, is_head_method)
+                    .timeout(timer.as_mut())
+                    .await
+            }
+            (res, _) => res,
+        };
+
         #[cfg(feature = "http1")]
         match res {
             Ok(Ok((res, buf, decoder, is_close))) => {
@@ -278,3 +327,12 @@ impl<'a, B> Request<'a, B> {
         }
     }
 }
+
+// only idempotent request without body can be sent again.
+#[cfg(feature = "http1")]
+fn is_retryable<B>(req: &http::Request<B>) -> bool
+where
+    B: Stream,
+{
+    req.method().is_idempotent() && req.body().size_hint().1 == Some(0)
+}
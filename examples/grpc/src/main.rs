@@ -1,19 +1,21 @@
 //! A Http/2 server handling low level grpc call.
 
-use futures_util::TryStreamExt;
+use std::net::TcpListener;
+
+use futures_util::StreamExt;
 use prost::Message;
 use xitca_http::{
-    body::Once,
-    bytes::{BufMut, Bytes, BytesMut},
+    body::{Once, ResponseBody},
+    bytes::Bytes,
     h2::RequestBody,
-    http::{
-        const_header_value::GRPC,
-        header::{HeaderName, HeaderValue, CONTENT_TYPE, TRAILER},
-        IntoResponse, Request, RequestExt, Response,
+    http::{Request, RequestExt, Response},
+    util::{
+        grpc::{self, Code, MessageBody, MessageStream, Status},
+        service::{route::post, Router},
     },
-    util::service::{route::post, Router},
     HttpServiceBuilder,
 };
+use xitca_server::ServerFuture;
 use xitca_service::fn_service;
 
 mod hello_world {
@@ -24,45 +26,96 @@ fn main() -> std::io::Result<()> {
     tracing_subscriber::fmt()
         .with_env_filter("xitca=info,[xitca-logger]=trace")
         .init();
+    server(TcpListener::bind("localhost:50051")?).wait()
+}
+
+fn server(listener: TcpListener) -> ServerFuture {
     let factory = || {
-        let route = Router::new().insert("/helloworld.Greeter/SayHello", post(fn_service(grpc)));
+        let route = Router::new().insert(
+            "/helloworld.Greeter/SayHello",
+            post(fn_service(grpc)).guard(grpc::is_grpc),
+        );
         HttpServiceBuilder::h2(route)
     };
-    xitca_server::Builder::new()
-        .bind("http/2", "localhost:50051", factory)?
-        .build()
-        .wait()
+    xitca_server::Builder::new().listen("http/2", listener, factory).build()
 }
 
-async fn grpc(mut req: Request<RequestExt<RequestBody>>) -> Result<Response<Once<Bytes>>, anyhow::Error> {
-    let body = req.body_mut();
+async fn grpc(
+    req: Request<RequestExt<RequestBody>>,
+) -> Result<Response<ResponseBody<MessageBody<Once<Bytes>>>>, anyhow::Error> {
+    let mut messages = MessageStream::new(req.into_body());
 
-    let mut buf = BytesMut::new();
+    let msg = match messages.next().await {
+        Some(Ok(msg)) => msg,
+        Some(Err(e)) => return Ok(grpc::error_response(e.into())),
+        None => {
+            let status = Status::new(Code::InvalidArgument, "missing request message");
+            return Ok(grpc::error_response(status));
+        }
+    };
 
-    while let Some(bytes) = body.try_next().await? {
-        buf.extend_from_slice(bytes.as_ref());
-    }
+    let msg = match hello_world::HelloRequest::decode(msg.data) {
+        Ok(msg) => msg,
+        Err(e) => return Ok(grpc::error_response(Status::new(Code::InvalidArgument, e.to_string()))),
+    };
 
-    let msg: hello_world::HelloRequest = Message::decode(buf.split_off(5))?;
+    let reply = hello_world::HelloReply { response: msg.request }.encode_to_vec();
 
-    Message::encode(&hello_world::HelloReply { response: msg.request }, &mut buf)?;
+    Ok(grpc::response(Once::new(Bytes::from(reply)), Status::ok()))
+}
 
-    let len = buf.len() - 5;
+#[cfg(test)]
+mod test {
+    use tonic::{codec::ProstCodec, codegen::http::uri::PathAndQuery, transport::Channel};
 
-    (&mut buf[1..5]).put_u32(len as u32);
+    use super::*;
 
-    let mut res = req.into_response(buf.freeze());
+    #[tokio::test]
+    async fn tonic_client() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let mut server = server(listener);
 
-    #[allow(clippy::declare_interior_mutable_const)]
-    {
-        const GRPC_STATUS_NAME: HeaderName = HeaderName::from_static("grpc-status");
-        const GRPC_STATUS_VALUE: HeaderValue = HeaderValue::from_static("0");
-        const GRPC_STATUS_NAME_AS_VALUE: HeaderValue = HeaderValue::from_static("grpc-status");
+        let channel = Channel::from_shared(format!("http://{addr}"))
+            .unwrap()
+            .connect()
+            .await
+            .unwrap();
+        let mut client = tonic::client::Grpc::new(channel);
 
-        res.headers_mut().insert(CONTENT_TYPE, GRPC);
-        res.headers_mut().insert(GRPC_STATUS_NAME, GRPC_STATUS_VALUE);
-        res.headers_mut().insert(TRAILER, GRPC_STATUS_NAME_AS_VALUE);
-    }
+        let hello = hello_world::Hello {
+            name: String::from("xitca"),
+            n: 996,
+            ..Default::default()
+        };
+        let req = hello_world::HelloRequest {
+            request: Some(hello.clone()),
+        };
+
+        client.ready().await.unwrap();
+        let res = client
+            .unary::<_, hello_world::HelloReply, _>(
+                tonic::Request::new(req),
+                PathAndQuery::from_static("/helloworld.Greeter/SayHello"),
+                ProstCodec::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(res.into_inner().response, Some(hello));
 
-    Ok(res)
+        // unknown method is answered by router with http error and tonic maps it to grpc status.
+        client.ready().await.unwrap();
+        let status = client
+            .unary::<_, hello_world::HelloReply, _>(
+                tonic::Request::new(hello_world::HelloRequest::default()),
+                PathAndQuery::from_static("/helloworld.Greeter/SayBye"),
+                ProstCodec::default(),
+            )
+            .await
+            .unwrap_err();
+        assert_ne!(status.code(), tonic::Code::Ok);
+
+        server.handle().unwrap().stop(false);
+        server.await.unwrap();
+    }
 }
@@ -1,47 +1,52 @@
 use core::str::FromStr;
 
 use http::{
-    header::{HeaderValue, IF_MODIFIED_SINCE, IF_UNMODIFIED_SINCE},
+    header::{HeaderValue, IF_MATCH, IF_MODIFIED_SINCE, IF_NONE_MATCH, IF_UNMODIFIED_SINCE},
     Request,
 };
 use httpdate::HttpDate;
 
-use super::{buf::buf_write_header, error::ServeError, runtime::Meta};
+use super::{buf::buf_write_header, error::ServeError, etag, runtime::Meta};
 
-pub(super) fn mod_date_check<Ext, M>(req: &Request<Ext>, meta: &mut M) -> Result<Option<HttpDate>, ServeError>
+// evaluate conditional request headers in the order of RFC 9110 section 13.2.2. date headers are
+// ignored when their entity tag counter parts are present.
+pub(super) fn mod_date_check<Ext, M>(
+    req: &Request<Ext>,
+    meta: &mut M,
+    etag: Option<&HeaderValue>,
+) -> Result<Option<HttpDate>, ServeError>
 where
     M: Meta,
 {
-    let mod_date = match meta.modified() {
-        Some(modified) => HttpDate::from(modified),
-        None => {
-            #[cold]
-            #[inline(never)]
-            fn precondition_check<Ext>(req: &Request<Ext>) -> Result<Option<HttpDate>, ServeError> {
-                if req.headers().contains_key(IF_UNMODIFIED_SINCE) {
-                    Err(ServeError::PreconditionFailed)
-                } else {
-                    Ok(None)
-                }
-            }
+    let headers = req.headers();
 
-            return precondition_check(req);
-        }
-    };
+    let if_match = headers.contains_key(IF_MATCH);
+    if if_match && !etag::is_match(headers.get_all(IF_MATCH), etag, false) {
+        return Err(ServeError::PreconditionFailed);
+    }
+
+    let mod_date = meta.modified().map(HttpDate::from);
 
-    if let Some(ref date) = to_http_date(req.headers().get(IF_UNMODIFIED_SINCE)) {
-        if date < &mod_date {
-            return Err(ServeError::PreconditionFailed);
+    if !if_match {
+        if let Some(ref date) = to_http_date(headers.get(IF_UNMODIFIED_SINCE)) {
+            match mod_date {
+                Some(ref mod_date) if date >= mod_date => {}
+                _ => return Err(ServeError::PreconditionFailed),
+            }
         }
     }
 
-    if let Some(ref date) = to_http_date(req.headers().get(IF_MODIFIED_SINCE)) {
-        if date >= &mod_date {
+    if headers.contains_key(IF_NONE_MATCH) {
+        if etag::is_match(headers.get_all(IF_NONE_MATCH), etag, true) {
+            return Err(ServeError::NotModified);
+        }
+    } else if let (Some(ref date), Some(ref mod_date)) = (to_http_date(headers.get(IF_MODIFIED_SINCE)), mod_date) {
+        if date >= mod_date {
             return Err(ServeError::NotModified);
         }
     }
 
-    Ok(Some(mod_date))
+    Ok(mod_date)
 }
 
 fn to_http_date(header: Option<&HeaderValue>) -> Option<HttpDate> {
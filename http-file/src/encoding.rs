@@ -0,0 +1,113 @@
+use std::path::{Path, PathBuf};
+
+use http::{
+    header::{HeaderValue, ACCEPT_ENCODING},
+    Request,
+};
+
+/// Content coding of pre-compressed sibling file served by [ServeDir](crate::ServeDir).
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Encoding {
+    /// brotli compressed file with `.br` extension appended to it's original file name.
+    Br,
+    /// gzip compressed file with `.gz` extension appended to it's original file name.
+    Gzip,
+}
+
+impl Encoding {
+    /// default order of looking up pre-compressed files. brotli is preferred over gzip.
+    pub const DEFAULT_ORDER: [Encoding; 2] = [Encoding::Br, Encoding::Gzip];
+
+    pub(crate) const fn as_str(&self) -> &'static str {
+        match *self {
+            Self::Br => "br",
+            Self::Gzip => "gzip",
+        }
+    }
+
+    pub(crate) const fn header_value(&self) -> HeaderValue {
+        HeaderValue::from_static(self.as_str())
+    }
+
+    // path of pre-compressed sibling file. e.g. app.js -> app.js.br
+    pub(crate) fn sibling(&self, path: &Path) -> PathBuf {
+        let ext = match *self {
+            Self::Br => ".br",
+            Self::Gzip => ".gz",
+        };
+        let mut path = path.as_os_str().to_owned();
+        path.push(ext);
+        path.into()
+    }
+
+    fn is_coding(&self, coding: &str) -> bool {
+        match *self {
+            Self::Br => coding.eq_ignore_ascii_case("br"),
+            Self::Gzip => coding.eq_ignore_ascii_case("gzip") || coding.eq_ignore_ascii_case("x-gzip"),
+        }
+    }
+
+    // check if encoding is acceptable according to request's Accept-Encoding headers.
+    // coding with q=0 is not acceptable and explicit coding takes precedence over wildcard.
+    pub(crate) fn is_accepted<Ext>(&self, req: &Request<Ext>) -> bool {
+        let mut wildcard = false;
+        for (coding, q) in req
+            .headers()
+            .get_all(ACCEPT_ENCODING)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .map(parse_coding)
+        {
+            if self.is_coding(coding) {
+                return q > 0.0;
+            }
+            if coding == "*" {
+                wildcard = q > 0.0;
+            }
+        }
+        wildcard
+    }
+}
+
+fn parse_coding(s: &str) -> (&str, f32) {
+    let mut params = s.split(';');
+    let coding = params.next().unwrap_or_default().trim();
+    let q = params
+        .filter_map(|p| {
+            let (k, v) = p.split_once('=')?;
+            k.trim().eq_ignore_ascii_case("q").then(|| v.trim().parse().ok())?
+        })
+        .next()
+        .unwrap_or(1.0);
+    (coding, q)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn req(accept_encoding: &str) -> Request<()> {
+        Request::builder()
+            .header(ACCEPT_ENCODING, accept_encoding)
+            .body(())
+            .unwrap()
+    }
+
+    #[test]
+    fn accepted() {
+        assert!(Encoding::Br.is_accepted(&req("gzip, deflate, br")));
+        assert!(Encoding::Gzip.is_accepted(&req("x-gzip")));
+        assert!(Encoding::Gzip.is_accepted(&req("*")));
+        assert!(!Encoding::Br.is_accepted(&req("gzip;q=1.0, br;q=0")));
+        assert!(!Encoding::Br.is_accepted(&req("br; q=0.0, *")));
+        assert!(!Encoding::Gzip.is_accepted(&req("identity")));
+        assert!(!Encoding::Gzip.is_accepted(&Request::new(())));
+    }
+
+    #[test]
+    fn sibling() {
+        assert_eq!(Encoding::Br.sibling(Path::new("a/app.js")), Path::new("a/app.js.br"));
+        assert_eq!(Encoding::Gzip.sibling(Path::new("app.js")), Path::new("app.js.gz"));
+    }
+}
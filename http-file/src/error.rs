@@ -8,6 +8,8 @@ use http::{
     HeaderValue, Request, Response, StatusCode,
 };
 
+use xitca_http::util::range::ContentRange;
+
 use super::buf::buf_write_header;
 
 /// high level error types for serving file.
@@ -85,7 +87,7 @@ impl ServeError {
             Self::PreconditionFailed => *res.status_mut() = StatusCode::PRECONDITION_FAILED,
             Self::RangeNotSatisfied(size) => {
                 *res.status_mut() = StatusCode::RANGE_NOT_SATISFIABLE;
-                let val = buf_write_header!(0, "bytes */{size}");
+                let val = buf_write_header!(0, "{}", ContentRange::Unsatisfied(size));
                 res.headers_mut().insert(CONTENT_RANGE, val);
             }
             Self::NotFound => *res.status_mut() = StatusCode::NOT_FOUND,
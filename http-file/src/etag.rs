@@ -0,0 +1,33 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use http::header::{GetAll, HeaderValue};
+
+use super::{buf::buf_write_header, encoding::Encoding};
+
+// strong entity tag derived from modified time and size of served file. encoding of
+// pre-compressed variant is appended so every representation of the same path has distinct tag.
+pub(super) fn etag(modified: Option<SystemTime>, len: u64, encoding: Option<Encoding>) -> Option<HeaderValue> {
+    let dur = modified?.duration_since(UNIX_EPOCH).ok()?;
+    let (secs, nanos) = (dur.as_secs(), dur.subsec_nanos());
+    Some(match encoding {
+        Some(enc) => buf_write_header!(0, "\"{secs:x}.{nanos:x}-{len:x}-{}\"", enc.as_str()),
+        None => buf_write_header!(0, "\"{secs:x}.{nanos:x}-{len:x}\""),
+    })
+}
+
+// check if any tag from If-Match/If-None-Match headers matches current entity tag. `*` matches
+// any existing file. weak comparison ignores the weak indicator of tags.
+pub(super) fn is_match(headers: GetAll<'_, HeaderValue>, etag: Option<&HeaderValue>, weak: bool) -> bool {
+    let etag = etag.and_then(|v| v.to_str().ok());
+    headers
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .any(|tag| match (tag, etag) {
+            ("*", _) => true,
+            (tag, Some(etag)) if weak => tag.trim_start_matches("W/") == etag.trim_start_matches("W/"),
+            (tag, Some(etag)) => !tag.starts_with("W/") && tag == etag,
+            (_, None) => false,
+        })
+}
@@ -7,9 +7,11 @@ pub mod runtime;
 mod buf;
 mod chunk;
 mod date;
+mod encoding;
 mod error;
+mod etag;
 
-pub use self::{chunk::ChunkReader, error::ServeError};
+pub use self::{chunk::ChunkReader, encoding::Encoding, error::ServeError};
 
 use std::{
     io::SeekFrom,
@@ -17,10 +19,14 @@ use std::{
 };
 
 use http::{
-    header::{HeaderValue, ACCEPT_RANGES, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, LAST_MODIFIED, RANGE},
+    header::{
+        HeaderValue, ACCEPT_ENCODING, ACCEPT_RANGES, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE,
+        ETAG, LAST_MODIFIED, RANGE, VARY,
+    },
     Method, Request, Response, StatusCode,
 };
 use mime_guess::mime;
+use xitca_http::util::range::{parse_range, resolve_ranges, ContentRange};
 
 use self::{
     buf::buf_write_header,
@@ -32,6 +38,7 @@ use self::{
 pub struct ServeDir<FS: AsyncFs = runtime::TokioFs> {
     chunk_size: usize,
     base_path: PathBuf,
+    precompressed: Vec<Encoding>,
     async_fs: FS,
 }
 
@@ -40,6 +47,7 @@ pub struct ServeDir<FS: AsyncFs = runtime::TokioFs> {
 pub struct ServeDir<FS: AsyncFs> {
     chunk_size: usize,
     base_path: PathBuf,
+    precompressed: Vec<Encoding>,
     async_fs: FS,
 }
 
@@ -66,6 +74,7 @@ impl<FS: AsyncFs> ServeDir<FS> {
         Self {
             chunk_size: 4096,
             base_path: path.into(),
+            precompressed: Vec::new(),
             async_fs,
         }
     }
@@ -78,6 +87,29 @@ impl<FS: AsyncFs> ServeDir<FS> {
         self
     }
 
+    /// serve pre-compressed sibling file (`name.ext.br` and `name.ext.gz`) when it exists and it's
+    /// encoding is accepted by client. brotli is preferred over gzip.
+    ///
+    /// pre-compressed file is served with `Content-Encoding` header and `Content-Type` of it's
+    /// uncompressed file. range request on it is ignored and the full file is served.
+    pub fn precompressed(&mut self) -> &mut Self {
+        self.precompressed_order(Encoding::DEFAULT_ORDER)
+    }
+
+    /// like [ServeDir::precompressed] but pre-compressed files are looked up in given order.
+    ///
+    /// # Examples
+    /// ```rust
+    /// # use http_file::{Encoding, ServeDir};
+    /// let mut dir = ServeDir::new("sample");
+    /// // prefer gzip over brotli.
+    /// dir.precompressed_order([Encoding::Gzip, Encoding::Br]);
+    /// ```
+    pub fn precompressed_order(&mut self, order: impl IntoIterator<Item = Encoding>) -> &mut Self {
+        self.precompressed = order.into_iter().collect();
+        self
+    }
+
     /// try to find a matching file from given input request and generate http response with stream
     /// reader of matched file.
     ///
@@ -106,40 +138,65 @@ impl<FS: AsyncFs> ServeDir<FS> {
             .first_raw()
             .unwrap_or_else(|| mime::APPLICATION_OCTET_STREAM.as_ref());
 
-        let mut file = self.async_fs.open(path).await?;
+        let (mut file, encoding) = self.open(req, path).await?;
 
-        let modified = date::mod_date_check(req, &mut file)?;
+        let etag = etag::etag(file.modified(), file.len(), encoding);
+
+        let modified = date::mod_date_check(req, &mut file, etag.as_ref())?;
 
         let mut res = Response::new(());
 
         let mut size = file.len();
 
-        if let Some(range) = req
+        // byte range of compressed file is not meaningful to client expecting the uncompressed one.
+        // range header that is malformed or with unit other than bytes is ignored.
+        if let Some(specs) = req
             .headers()
             .get(RANGE)
+            .filter(|_| encoding.is_none())
             .and_then(|h| h.to_str().ok())
-            .and_then(|range| http_range_header::parse_range_header(range).ok())
-            .map(|range| range.validate(size))
+            .and_then(|range| parse_range(range).ok())
         {
-            let (start, end) = range
-                .map_err(|_| ServeError::RangeNotSatisfied(size))?
-                .pop()
-                .expect("http_range_header produced empty range")
-                .into_inner();
+            let ranges = resolve_ranges(&specs, size).map_err(|_| ServeError::RangeNotSatisfied(size))?;
+
+            // multiple ranges would need a multipart/byteranges response which is not supported.
+            // serve the whole file instead.
+            if let [(start, end)] = ranges[..] {
+                file.seek(SeekFrom::Start(start)).await?;
+
+                *res.status_mut() = StatusCode::PARTIAL_CONTENT;
+                let range = ContentRange::Bytes {
+                    first: start,
+                    last: end,
+                    complete: Some(size),
+                };
+                let val = buf_write_header!(0, "{range}");
+                res.headers_mut().insert(CONTENT_RANGE, val);
+
+                size = end - start + 1;
+            }
+        }
 
-            file.seek(SeekFrom::Start(start)).await?;
+        res.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static(ct));
+        res.headers_mut().insert(CONTENT_LENGTH, HeaderValue::from(size));
 
-            *res.status_mut() = StatusCode::PARTIAL_CONTENT;
-            let val = buf_write_header!(0, "bytes {start}-{end}/{size}");
-            res.headers_mut().insert(CONTENT_RANGE, val);
+        match encoding {
+            Some(encoding) => {
+                res.headers_mut().insert(CONTENT_ENCODING, encoding.header_value());
+            }
+            None => {
+                res.headers_mut()
+                    .insert(ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+            }
+        }
 
-            size = end - start + 1;
+        if !self.precompressed.is_empty() {
+            res.headers_mut().insert(VARY, HeaderValue::from_name(ACCEPT_ENCODING));
         }
 
-        res.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static(ct));
-        res.headers_mut().insert(CONTENT_LENGTH, HeaderValue::from(size));
-        res.headers_mut()
-            .insert(ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+        if let Some(etag) = etag {
+            res.headers_mut().insert(ETAG, etag);
+        }
 
         if let Some(modified) = modified {
             let val = date::date_to_header(modified);
@@ -157,6 +214,20 @@ impl<FS: AsyncFs> ServeDir<FS> {
 }
 
 impl<FS: AsyncFs> ServeDir<FS> {
+    // open pre-compressed sibling of path when it's accepted by client and fallback to path itself.
+    async fn open<Ext>(&self, req: &Request<Ext>, path: PathBuf) -> Result<(FS::File, Option<Encoding>), ServeError> {
+        for encoding in self.precompressed.iter().filter(|enc| enc.is_accepted(req)) {
+            // sibling is opened through async file system and it's metadata decides if it's usable.
+            match self.async_fs.open(encoding.sibling(&path)).await {
+                Ok(file) if file.is_file() => return Ok((file, Some(*encoding))),
+                _ => {}
+            }
+        }
+
+        let file = self.async_fs.open(path).await?;
+        Ok((file, None))
+    }
+
     fn path_check(&self, path: &str) -> Result<PathBuf, ServeError> {
         let path = path.trim_start_matches('/').as_bytes();
 
@@ -194,6 +265,7 @@ mod test {
     use core::future::poll_fn;
 
     use futures_core::stream::Stream;
+    use http::header::IF_NONE_MATCH;
 
     use super::*;
 
@@ -354,9 +426,183 @@ mod test {
         test_range(ServeDir::new("sample")).await;
     }
 
+    #[tokio::test]
+    async fn ranged_edge_cases() {
+        let dir = ServeDir::new("sample");
+
+        let serve = |range: &'static str| {
+            let req = Request::builder()
+                .uri("/test.txt")
+                .header(RANGE, range)
+                .body(())
+                .unwrap();
+            let dir = dir.clone();
+            async move { dir.serve(&req).await }
+        };
+
+        let res = serve("bytes=-6").await.unwrap();
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(res.headers().get(CONTENT_RANGE).unwrap(), "bytes 7-12/13");
+        assert_eq!(collect::<runtime::TokioFs>(res).await, b"world!");
+
+        // overlapping ranges are coalesced into one.
+        let res = serve("bytes=0-4, 2-6").await.unwrap();
+        assert_eq!(res.headers().get(CONTENT_RANGE).unwrap(), "bytes 0-6/13");
+        assert_eq!(collect::<runtime::TokioFs>(res).await, b"hello, ");
+
+        // multiple ranges, malformed range and other units are ignored.
+        for range in ["bytes=0-1, 5-6", "bytes=5-4", "bytes=a-", "items=0-1"] {
+            let res = serve(range).await.unwrap();
+            assert_eq!(res.status(), StatusCode::OK, "{range}");
+            assert!(res.headers().get(CONTENT_RANGE).is_none());
+            assert_eq!(collect::<runtime::TokioFs>(res).await, b"hello, world!");
+        }
+
+        for range in ["bytes=13-", "bytes=-0", "bytes=100-200"] {
+            let err = serve(range).await.err().unwrap();
+            assert!(matches!(err, ServeError::RangeNotSatisfied(13)), "{range}");
+            let res = err.into_response();
+            assert_eq!(res.headers().get(CONTENT_RANGE).unwrap(), "bytes */13");
+        }
+    }
+
     #[cfg(all(target_os = "linux", feature = "tokio-uring"))]
     #[test]
     fn ranged_tokio_uring() {
         tokio_uring::start(test_range(ServeDir::new_tokio_uring("sample")))
     }
+
+    async fn collect<FS: AsyncFs>(res: Response<ChunkReader<FS::File>>) -> Vec<u8> {
+        let mut stream = Box::pin(res.into_body());
+        let mut body = Vec::new();
+        while let Some(Ok(bytes)) = poll_fn(|cx| stream.as_mut().poll_next(cx)).await {
+            body.extend_from_slice(bytes.as_ref());
+        }
+        body
+    }
+
+    fn precompressed_req(accept_encoding: &str) -> Request<()> {
+        Request::builder()
+            .uri("/test.txt")
+            .header(ACCEPT_ENCODING, accept_encoding)
+            .header(RANGE, "bytes=2-12")
+            .body(())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn precompressed() {
+        let mut dir = ServeDir::new("sample");
+        dir.precompressed();
+
+        for (accept_encoding, encoding, file) in [
+            ("gzip, br", Some("br"), "sample/test.txt.br"),
+            ("gzip;q=0.5, br;q=0", Some("gzip"), "sample/test.txt.gz"),
+            ("identity", None, "sample/test.txt"),
+        ] {
+            let res = dir.serve(&precompressed_req(accept_encoding)).await.unwrap();
+            assert_eq!(
+                res.headers().get(CONTENT_ENCODING).map(|v| v.to_str().unwrap()),
+                encoding
+            );
+            assert_eq!(
+                res.headers().get(CONTENT_TYPE).unwrap(),
+                HeaderValue::from_static("text/plain")
+            );
+            assert_eq!(res.headers().get(VARY).unwrap(), "accept-encoding");
+
+            if encoding.is_some() {
+                // range is ignored for pre-compressed file.
+                assert_eq!(res.status(), StatusCode::OK);
+                assert!(res.headers().get(ACCEPT_RANGES).is_none());
+                assert!(res.headers().get(CONTENT_RANGE).is_none());
+                assert_eq!(collect::<runtime::TokioFs>(res).await, std::fs::read(file).unwrap());
+            } else {
+                assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+                assert_eq!(collect::<runtime::TokioFs>(res).await, b"llo, world!");
+            }
+        }
+
+        // lookup order is configurable.
+        dir.precompressed_order([Encoding::Gzip, Encoding::Br]);
+        let res = dir.serve(&precompressed_req("br, gzip")).await.unwrap();
+        assert_eq!(res.headers().get(CONTENT_ENCODING).unwrap(), "gzip");
+
+        // missing sibling falls back to uncompressed file.
+        let req = Request::builder()
+            .uri("/test.txt.gz")
+            .header(ACCEPT_ENCODING, "gzip")
+            .body(())
+            .unwrap();
+        let res = dir.serve(&req).await.unwrap();
+        assert!(res.headers().get(CONTENT_ENCODING).is_none());
+
+        // disabled by default.
+        let res = ServeDir::new("sample").serve(&precompressed_req("br")).await.unwrap();
+        assert!(res.headers().get(CONTENT_ENCODING).is_none());
+        assert!(res.headers().get(VARY).is_none());
+    }
+
+    #[tokio::test]
+    async fn precompressed_sibling_dir() {
+        let base = std::env::temp_dir().join("xitca_http_file_sibling_dir");
+        let _ = std::fs::remove_dir_all(&base);
+        std::fs::create_dir_all(base.join("test.txt.gz")).unwrap();
+        std::fs::write(base.join("test.txt"), "hello, world!").unwrap();
+
+        let mut dir = ServeDir::new(&base);
+        dir.precompressed();
+
+        // directory with the name of sibling is not served as pre-compressed file.
+        let req = Request::builder()
+            .uri("/test.txt")
+            .header(ACCEPT_ENCODING, "gzip")
+            .body(())
+            .unwrap();
+        let res = dir.serve(&req).await.unwrap();
+        assert!(res.headers().get(CONTENT_ENCODING).is_none());
+        assert_eq!(collect::<runtime::TokioFs>(res).await, b"hello, world!");
+
+        std::fs::remove_dir_all(&base).unwrap();
+    }
+
+    #[tokio::test]
+    async fn precompressed_etag() {
+        let mut dir = ServeDir::new("sample");
+        dir.precompressed();
+
+        let etag = |accept_encoding| {
+            let req = Request::builder()
+                .uri("/test.txt")
+                .header(ACCEPT_ENCODING, accept_encoding)
+                .body(())
+                .unwrap();
+            let dir = &dir;
+            async move { dir.serve(&req).await.unwrap().headers().get(ETAG).unwrap().clone() }
+        };
+
+        let br = etag("br").await;
+        let gzip = etag("gzip").await;
+        let identity = etag("identity").await;
+        assert_ne!(br, gzip);
+        assert_ne!(br, identity);
+        assert_ne!(gzip, identity);
+
+        let req = |if_none_match: &HeaderValue| {
+            Request::builder()
+                .uri("/test.txt")
+                .header(ACCEPT_ENCODING, "br")
+                .header(IF_NONE_MATCH, if_none_match)
+                .body(())
+                .unwrap()
+        };
+
+        let e = dir.serve(&req(&br)).await.err().unwrap();
+        assert!(matches!(e, ServeError::NotModified));
+
+        // tag of another variant does not match.
+        let res = dir.serve(&req(&gzip)).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get(ETAG).unwrap(), br);
+    }
 }
@@ -27,6 +27,12 @@ pub trait Meta {
     /// the length hint of file.
     fn len(&self) -> u64;
 
+    /// whether the opened path is a regular file. directories are skipped when looking up
+    /// pre-compressed files.
+    fn is_file(&self) -> bool {
+        true
+    }
+
     #[cold]
     #[inline(never)]
     fn is_empty(&self) -> bool {
@@ -83,6 +89,7 @@ mod tokio_impl {
                         file: file.into(),
                         modified_time,
                         len,
+                        is_file: meta.is_file(),
                     })
                 })
                 .await
@@ -95,6 +102,7 @@ mod tokio_impl {
         file: File,
         modified_time: Option<SystemTime>,
         len: u64,
+        is_file: bool,
     }
 
     impl Meta for TokioFile {
@@ -105,6 +113,10 @@ mod tokio_impl {
         fn len(&self) -> u64 {
             self.len
         }
+
+        fn is_file(&self) -> bool {
+            self.is_file
+        }
     }
 
     impl ChunkRead for TokioFile {
@@ -147,12 +159,12 @@ mod tokio_uring_impl {
 
         fn open(&self, path: PathBuf) -> Self::OpenFuture {
             async {
-                let (file, modified_time, len) = tokio::task::spawn_blocking(move || {
+                let (file, modified_time, len, is_file) = tokio::task::spawn_blocking(move || {
                     let file = std::fs::File::open(path)?;
                     let meta = file.metadata()?;
                     let modified_time = meta.modified().ok();
                     let len = meta.len();
-                    Ok::<_, io::Error>((file, modified_time, len))
+                    Ok::<_, io::Error>((file, modified_time, len, meta.is_file()))
                 })
                 .await
                 .unwrap()?;
@@ -162,6 +174,7 @@ mod tokio_uring_impl {
                     pos: 0,
                     modified_time,
                     len,
+                    is_file,
                 })
             }
         }
@@ -172,6 +185,7 @@ mod tokio_uring_impl {
         pos: u64,
         modified_time: Option<SystemTime>,
         len: u64,
+        is_file: bool,
     }
 
     impl Meta for TokioUringFile {
@@ -182,6 +196,10 @@ mod tokio_uring_impl {
         fn len(&self) -> u64 {
             self.len
         }
+
+        fn is_file(&self) -> bool {
+            self.is_file
+        }
     }
 
     impl ChunkRead for TokioUringFile {
@@ -0,0 +1,69 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use tokio::time::Instant;
+use xitca_http::{
+    body::ResponseBody,
+    bytes::{Bytes, BytesMut},
+    date::DateTime,
+    h1::proto::context::Context,
+    http::{
+        header::{HeaderValue, CONTENT_TYPE, SERVER},
+        Response,
+    },
+    util::cached::CachedResponse,
+};
+
+const BODY: &[u8] = b"Hello, World!";
+
+// static date so encoding does not depend on timer task.
+struct Date;
+
+impl DateTime for Date {
+    const DATE_VALUE_LENGTH: usize = 29;
+
+    fn with_date<F, O>(&self, f: F) -> O
+    where
+        F: FnOnce(&[u8]) -> O,
+    {
+        f(b"Sun, 06 Nov 1994 08:49:37 GMT")
+    }
+
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+fn plaintext() -> Response<ResponseBody> {
+    let mut res = Response::new(ResponseBody::from(Bytes::from_static(BODY)));
+    res.headers_mut().insert(SERVER, HeaderValue::from_static("xitca-web"));
+    res.headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+    res
+}
+
+fn encode(ctx: &mut Context<'_, Date, 64>, buf: &mut BytesMut, res: Response<ResponseBody>) {
+    let (parts, body) = res.into_parts();
+    ctx.encode_head(parts, &body, buf).unwrap();
+    buf.clear();
+}
+
+fn cached_response(c: &mut Criterion) {
+    let mut group = c.benchmark_group("plaintext");
+
+    group.bench_function("encode", |b| {
+        let mut ctx = Context::new(&Date);
+        let mut buf = BytesMut::with_capacity(4096);
+        b.iter(|| encode(&mut ctx, &mut buf, plaintext()));
+    });
+
+    group.bench_function("cached", |b| {
+        let cached = CachedResponse::new(plaintext().map(|_| Bytes::from_static(BODY)));
+        let mut ctx = Context::new(&Date);
+        let mut buf = BytesMut::with_capacity(4096);
+        b.iter(|| encode(&mut ctx, &mut buf, cached.response()));
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, cached_response);
+criterion_main!(benches);
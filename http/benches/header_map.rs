@@ -0,0 +1,65 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use tokio::time::Instant;
+use xitca_http::{
+    body::ResponseBody,
+    bytes::{Bytes, BytesMut},
+    date::DateTime,
+    h1::proto::context::Context,
+    http::{
+        header::{HeaderValue, CONTENT_TYPE, SERVER},
+        Response,
+    },
+};
+
+// static date so encoding does not depend on timer task.
+struct Date;
+
+impl DateTime for Date {
+    const DATE_VALUE_LENGTH: usize = 29;
+
+    fn with_date<F, O>(&self, f: F) -> O
+    where
+        F: FnOnce(&[u8]) -> O,
+    {
+        f(b"Sun, 06 Nov 1994 08:49:37 GMT")
+    }
+
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+const REQ_HEADERS: &[u8] = b"GET / HTTP/1.1\r\naccept-encoding: gzip\r\naccept: */*\r\nuser-agent: b\r\n\r\n";
+
+// decode a request and encode response for it like a keep-alive connection does.
+fn round(ctx: &mut Context<'_, Date, 64>, read_buf: &mut BytesMut, write_buf: &mut BytesMut) {
+    let (req, _) = ctx.decode_head::<4096>(read_buf).unwrap().unwrap();
+    drop(req);
+
+    let mut res = Response::<ResponseBody>::new(ResponseBody::from(Bytes::from_static(b"996")));
+    res.headers_mut().insert(SERVER, HeaderValue::from_static("xitca-web"));
+    res.headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+
+    let (parts, body) = res.into_parts();
+    ctx.encode_head(parts, &body, write_buf).unwrap();
+    write_buf.clear();
+}
+
+fn header_map(c: &mut Criterion) {
+    let mut group = c.benchmark_group("header_map");
+
+    group.bench_function("keep_alive", |b| {
+        let mut ctx = Context::new(&Date);
+        let mut write_buf = BytesMut::with_capacity(4096);
+        b.iter(|| {
+            let mut read_buf = BytesMut::from(REQ_HEADERS);
+            round(&mut ctx, &mut read_buf, &mut write_buf)
+        });
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, header_map);
+criterion_main!(benches);
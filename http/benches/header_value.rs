@@ -0,0 +1,72 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use xitca_http::{
+    http::header::{HeaderMap, HeaderName, HeaderValue, AGE, RETRY_AFTER},
+    util::header::{duration_header_value, int_header_value},
+};
+
+// global allocator counting allocations so zero allocation of header construction can be asserted.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+const LIMIT: HeaderName = HeaderName::from_static("x-ratelimit-limit");
+const REMAINING: HeaderName = HeaderName::from_static("x-ratelimit-remaining");
+
+fn to_string(headers: &mut HeaderMap) {
+    headers.insert(LIMIT, HeaderValue::from_str(&black_box(100u64).to_string()).unwrap());
+    headers.insert(REMAINING, HeaderValue::from_str(&black_box(42u64).to_string()).unwrap());
+    headers.insert(
+        RETRY_AFTER,
+        HeaderValue::from_str(&black_box(30u64).to_string()).unwrap(),
+    );
+    headers.insert(AGE, HeaderValue::from_str(&black_box(120u64).to_string()).unwrap());
+    headers.clear();
+}
+
+fn helper(headers: &mut HeaderMap) {
+    headers.insert(LIMIT, int_header_value(black_box(100)));
+    headers.insert(REMAINING, int_header_value(black_box(42)));
+    headers.insert(RETRY_AFTER, duration_header_value(black_box(Duration::from_secs(30))));
+    headers.insert(AGE, int_header_value(black_box(120)));
+    headers.clear();
+}
+
+fn header_value(c: &mut Criterion) {
+    // header map keeps it's capacity after clear.
+    let mut headers = HeaderMap::with_capacity(4);
+    helper(&mut headers);
+
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    helper(&mut headers);
+    assert_eq!(ALLOCATIONS.load(Ordering::Relaxed) - before, 0);
+
+    let mut group = c.benchmark_group("numeric_headers");
+
+    group.bench_function("to_string", |b| b.iter(|| to_string(&mut headers)));
+    group.bench_function("helper", |b| b.iter(|| helper(&mut headers)));
+
+    group.finish();
+}
+
+criterion_group!(benches, header_value);
+criterion_main!(benches);
@@ -0,0 +1,99 @@
+use std::{convert::Infallible, time::Duration};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::UnixStream,
+    runtime::{Builder, Runtime},
+    task::LocalSet,
+};
+use xitca_http::{
+    body::{RequestBody, ResponseBody},
+    bytes::Bytes,
+    config::{FlushPolicy, HttpServiceConfig},
+    http::{Request, RequestExt, Response},
+    HttpServiceBuilder,
+};
+use xitca_io::io::PollIoAdapter;
+use xitca_service::{fn_service, Service};
+
+// requests pipelined in one iteration.
+const REQUESTS: usize = 16;
+
+async fn handler(_: Request<RequestExt<RequestBody>>) -> Result<Response<ResponseBody>, Infallible> {
+    Ok(Response::new(Bytes::from_static(b"Hello, World!").into()))
+}
+
+// connected client and the byte length of responses to one batch of pipelined requests.
+struct Client {
+    io: UnixStream,
+    reqs: Vec<u8>,
+    buf: Vec<u8>,
+    len: usize,
+}
+
+impl Client {
+    async fn connect(rt: &LocalSet, policy: FlushPolicy) -> Self {
+        let config = HttpServiceConfig::new()
+            .flush_policy(policy)
+            .keep_alive_timeout(Duration::from_secs(3600));
+        let service = HttpServiceBuilder::with_config(fn_service(handler), config)
+            .call(())
+            .await
+            .unwrap();
+
+        let (io, server) = UnixStream::pair().unwrap();
+        rt.spawn_local(async move { service.serve_connection(PollIoAdapter::new(server), None).await });
+
+        let mut client = Self {
+            io,
+            reqs: b"GET /plaintext HTTP/1.1\r\nhost: localhost\r\n\r\n".repeat(REQUESTS),
+            buf: vec![0; 64 * 1024],
+            len: 0,
+        };
+
+        // responses have fixed length. count it with the first batch.
+        client.io.write_all(&client.reqs).await.unwrap();
+        let mut res = Vec::new();
+        while res.windows(13).filter(|w| *w == b"Hello, World!").count() < REQUESTS {
+            let n = client.io.read(&mut client.buf).await.unwrap();
+            res.extend_from_slice(&client.buf[..n]);
+        }
+        client.len = res.len();
+
+        client
+    }
+
+    async fn batch(&mut self) {
+        self.io.write_all(&self.reqs).await.unwrap();
+        let mut read = 0;
+        while read < self.len {
+            read += self.io.read(&mut self.buf).await.unwrap();
+        }
+    }
+}
+
+fn runtime() -> Runtime {
+    Builder::new_current_thread().enable_all().build().unwrap()
+}
+
+fn pipeline(c: &mut Criterion) {
+    let rt = runtime();
+
+    let mut group = c.benchmark_group("pipeline_16");
+    for (name, policy) in [
+        ("every_response", FlushPolicy::EveryResponse),
+        ("buffered_until_4k", FlushPolicy::BufferedUntil(4096)),
+        ("end_of_read_batch", FlushPolicy::EndOfReadBatch),
+    ] {
+        let local = LocalSet::new();
+        let mut client = rt.block_on(local.run_until(Client::connect(&local, policy)));
+        group.bench_function(BenchmarkId::from_parameter(name), |b| {
+            b.iter(|| rt.block_on(local.run_until(client.batch())));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, pipeline);
+criterion_main!(benches);
@@ -0,0 +1,120 @@
+// responses are written to a unix socket pair so every flush is a real write syscall without
+// tcp stack in the way. the bench is skipped on other platforms.
+#[cfg(unix)]
+mod bench {
+    use std::{
+        io::{self, Read},
+        os::unix::net::UnixStream,
+        thread,
+    };
+
+    use criterion::{criterion_group, BenchmarkId, Criterion};
+    use tokio::time::Instant;
+    use xitca_http::{
+        body::{BodySize, ResponseBody},
+        bytes::Bytes,
+        config::WriteBufStrategy,
+        date::DateTime,
+        h1::proto::{buf_write::AdaptiveWriteBuf, context::Context},
+        http::{
+            header::{HeaderValue, CONTENT_TYPE},
+            Response,
+        },
+        util::buffered::{BufInterest, BufWrite},
+    };
+
+    const LIMIT: usize = 1024 * 1024;
+
+    // responses written in one iteration.
+    const RESPONSES: usize = 64;
+
+    // static date so encoding does not depend on timer task.
+    struct Date;
+
+    impl DateTime for Date {
+        const DATE_VALUE_LENGTH: usize = 29;
+
+        fn with_date<F, O>(&self, f: F) -> O
+        where
+            F: FnOnce(&[u8]) -> O,
+        {
+            f(b"Sun, 06 Nov 1994 08:49:37 GMT")
+        }
+
+        fn now(&self) -> Instant {
+            Instant::now()
+        }
+    }
+
+    fn json() -> Bytes {
+        let mut json = String::from("{\"message\":\"");
+        json.push_str(&"a".repeat(200 - json.len() - 2));
+        json.push_str("\"}");
+        Bytes::from(json)
+    }
+
+    fn write_responses(
+        ctx: &mut Context<'_, Date, 64>,
+        buf: &mut AdaptiveWriteBuf<LIMIT>,
+        io: &mut UnixStream,
+        body: &Bytes,
+        coalesce: bool,
+    ) -> io::Result<()> {
+        for _ in 0..RESPONSES {
+            let mut res = Response::new(ResponseBody::<ResponseBody>::from(body.clone()));
+            res.headers_mut()
+                .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+            let (parts, res_body) = res.into_parts();
+
+            let mut encoding = if coalesce {
+                let size = BodySize::from_stream(&res_body);
+                ctx.encode_head_with_body(parts, size, body.clone(), buf).unwrap()
+            } else {
+                let mut encoding = ctx.encode_head(parts, &res_body, buf).unwrap();
+                encoding.encode(body.clone(), buf);
+                encoding
+            };
+            encoding.encode_eof(buf).unwrap();
+
+            while buf.want_write_io() {
+                buf.do_io(io)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn small_body(c: &mut Criterion) {
+        let (mut tx, mut rx) = UnixStream::pair().unwrap();
+
+        // drain the other end of socket so writes never block for long.
+        thread::spawn(move || {
+            let mut buf = vec![0; 1024 * 1024];
+            while rx.read(&mut buf).map(|n| n > 0).unwrap_or(false) {}
+        });
+
+        let body = json();
+
+        let mut group = c.benchmark_group("json_200b");
+        for (name, strategy) in [
+            ("flat", WriteBufStrategy::Flat),
+            ("vectored", WriteBufStrategy::Vectored),
+        ] {
+            for (mode, coalesce) in [("separate", false), ("coalesce", true)] {
+                group.bench_function(BenchmarkId::new(name, mode), |b| {
+                    let mut ctx = Context::new(&Date);
+                    let mut buf = AdaptiveWriteBuf::<LIMIT>::new(strategy, true);
+                    b.iter(|| write_responses(&mut ctx, &mut buf, &mut tx, &body, coalesce).unwrap());
+                });
+            }
+        }
+        group.finish();
+    }
+
+    criterion_group!(benches, small_body);
+}
+
+#[cfg(unix)]
+criterion::criterion_main!(bench::benches);
+
+#[cfg(not(unix))]
+fn main() {}
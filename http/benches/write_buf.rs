@@ -0,0 +1,85 @@
+use std::{
+    io::{self, Read},
+    os::unix::net::UnixStream,
+    thread,
+};
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use xitca_http::{
+    bytes::{BufMut, Bytes},
+    config::WriteBufStrategy,
+    h1::proto::buf_write::{AdaptiveWriteBuf, H1BufWrite},
+    util::buffered::{BufInterest, BufWrite},
+};
+
+const LIMIT: usize = 1024 * 1024;
+
+// responses written in one iteration. more than sample window of auto strategy so it has the
+// chance to settle on one buffer type.
+const RESPONSES: usize = 64;
+
+// response pattern with body chunks of given size and count.
+struct Workload {
+    name: &'static str,
+    chunk: Bytes,
+    chunks: usize,
+}
+
+fn write_responses(buf: &mut AdaptiveWriteBuf<LIMIT>, io: &mut UnixStream, workload: &Workload) -> io::Result<()> {
+    for _ in 0..RESPONSES {
+        let _ = buf.write_buf_head(|b| {
+            b.put_slice(b"HTTP/1.1 200 OK\r\ntransfer-encoding: chunked\r\n\r\n");
+            Ok::<_, io::Error>(())
+        });
+        for _ in 0..workload.chunks {
+            buf.write_buf_bytes_chunked(workload.chunk.clone());
+        }
+        buf.write_buf_static(b"0\r\n\r\n");
+        while buf.want_write_io() {
+            buf.do_io(io)?;
+        }
+    }
+    Ok(())
+}
+
+fn write_buf(c: &mut Criterion) {
+    let (mut tx, mut rx) = UnixStream::pair().unwrap();
+
+    // drain the other end of socket so writes never block for long.
+    thread::spawn(move || {
+        let mut buf = vec![0; 1024 * 1024];
+        while rx.read(&mut buf).map(|n| n > 0).unwrap_or(false) {}
+    });
+
+    let workloads = [
+        Workload {
+            name: "small_chunks",
+            chunk: Bytes::from_static(&[b'a'; 16]),
+            chunks: 24,
+        },
+        Workload {
+            name: "large_chunks",
+            chunk: Bytes::from(vec![b'a'; 64 * 1024]),
+            chunks: 2,
+        },
+    ];
+
+    for workload in workloads.iter() {
+        let mut group = c.benchmark_group(workload.name);
+        for (name, strategy) in [
+            ("flat", WriteBufStrategy::Flat),
+            ("vectored", WriteBufStrategy::Vectored),
+            ("auto", WriteBufStrategy::Auto),
+        ] {
+            group.bench_function(BenchmarkId::from_parameter(name), |b| {
+                // buffer lives across iterations like it does for a keep-alive connection.
+                let mut buf = AdaptiveWriteBuf::<LIMIT>::new(strategy, true);
+                b.iter(|| write_responses(&mut buf, &mut tx, workload).unwrap());
+            });
+        }
+        group.finish();
+    }
+}
+
+criterion_group!(benches, write_buf);
+criterion_main!(benches);
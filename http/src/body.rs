@@ -1,10 +1,14 @@
 use std::{
     borrow::Cow,
     convert::Infallible,
-    error,
+    error, fmt,
     marker::PhantomData,
     mem,
     pin::Pin,
+    sync::{
+        atomic::{AtomicU64, AtomicU8, Ordering},
+        Arc,
+    },
     task::{Context, Poll},
 };
 
@@ -40,6 +44,8 @@ pub enum RequestBody {
     H2(super::h2::RequestBody),
     #[cfg(feature = "http3")]
     H3(super::h3::RequestBody),
+    /// Type erased body that is not bound to any http protocol. Mostly used for testing purpose.
+    Unknown(BoxStream),
     #[default]
     None,
 }
@@ -48,19 +54,26 @@ impl Stream for RequestBody {
     type Item = Result<Bytes, BodyError>;
 
     #[inline]
-    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         match self.get_mut() {
             #[cfg(feature = "http1")]
-            Self::H1(body) => Pin::new(body).poll_next(_cx).map_err(Into::into),
+            Self::H1(body) => Pin::new(body).poll_next(cx),
             #[cfg(feature = "http2")]
-            Self::H2(body) => Pin::new(body).poll_next(_cx),
+            Self::H2(body) => Pin::new(body).poll_next(cx),
             #[cfg(feature = "http3")]
-            Self::H3(body) => Pin::new(body).poll_next(_cx),
+            Self::H3(body) => Pin::new(body).poll_next(cx),
+            Self::Unknown(body) => Pin::new(body).poll_next(cx),
             Self::None => Poll::Ready(None),
         }
     }
 }
 
+impl From<BoxStream> for RequestBody {
+    fn from(body: BoxStream) -> Self {
+        Self::Unknown(body)
+    }
+}
+
 /// None body type.
 /// B type is used to infer other types of body's output type used together with NoneBody.
 pub struct NoneBody<B>(PhantomData<B>);
@@ -178,7 +191,7 @@ where
         self.project()
             .body
             .poll_next(cx)
-            .map_err(|e| BodyError::from(Box::new(e) as Box<dyn error::Error + Send + Sync>))
+            .map_err(|e| BodyError::Boxed(Box::new(e)))
     }
 
     #[inline]
@@ -214,6 +227,20 @@ impl ResponseBody {
     {
         Self::stream(BoxStream::new(stream))
     }
+
+    /// Construct a new Stream variant of ResponseBody with known length in bytes.
+    ///
+    /// The length is sent as `Content-Length` header of response. Stream yielding more or less
+    /// bytes than given length is treated as error and the response is aborted by closing the
+    /// connection(HTTP/1) or resetting the stream(HTTP/2 and HTTP/3).
+    #[inline]
+    pub fn sized_stream<B, E>(stream: B, len: usize) -> Self
+    where
+        B: Stream<Item = Result<Bytes, E>> + 'static,
+        E: error::Error + Send + Sync + 'static,
+    {
+        Self::box_stream(SizedStream::new(stream, BodySize::Sized(len)))
+    }
 }
 
 impl<B> ResponseBody<B> {
@@ -340,6 +367,568 @@ impl BodySize {
     }
 }
 
+pin_project! {
+    /// Stream body with explicit [BodySize] that overrides the size hint of inner stream.
+    ///
+    /// Useful for passing through a streaming body with known length. e.g. a body received from
+    /// upstream with `Content-Length` header can be forwarded as is instead of being re-encoded
+    /// with chunked transfer coding.
+    ///
+    /// # Note:
+    /// Inner stream must yield exactly the amount of bytes given by [BodySize::Sized]. A mismatch
+    /// is treated as error and the response would be aborted.
+    pub struct SizedStream<B> {
+        #[pin]
+        stream: B,
+        size: BodySize,
+    }
+}
+
+impl<B> SizedStream<B> {
+    #[inline]
+    pub const fn new(stream: B, size: BodySize) -> Self {
+        Self { stream, size }
+    }
+}
+
+impl<B> Stream for SizedStream<B>
+where
+    B: Stream,
+{
+    type Item = B::Item;
+
+    #[inline]
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.project().stream.poll_next(cx)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.size {
+            BodySize::None => none_body_hint(),
+            BodySize::Sized(size) => exact_body_hint(size),
+            BodySize::Stream => (0, None),
+        }
+    }
+}
+
+/// Callback invoked by dispatcher when response body yields an error after response head is sent.
+///
+/// Insert it to response extensions to observe body error. The callback receives the error and
+/// the number of body bytes already sent to client. After callback is invoked, Http/1 connection
+/// is closed without terminating chunk or with fewer bytes than content-length and Http/2 stream
+/// is reset with `INTERNAL_ERROR` so client can detect the body is incomplete.
+///
+/// # Examples:
+/// ```rust
+/// # use xitca_http::{body::BodyErrorHook, http::Response};
+/// let mut res = Response::new(());
+/// res.extensions_mut().insert(BodyErrorHook::new(|err, sent| {
+///     eprintln!("response body failed after {sent} bytes: {err:?}");
+/// }));
+/// ```
+pub struct BodyErrorHook(Box<HookFn>);
+
+type HookFn = dyn FnOnce(&dyn fmt::Debug, u64) + Send + Sync;
+
+impl BodyErrorHook {
+    pub fn new<F>(f: F) -> Self
+    where
+        F: FnOnce(&dyn fmt::Debug, u64) + Send + Sync + 'static,
+    {
+        Self(Box::new(f))
+    }
+
+    /// Invoke callback with body error and count of sent body bytes.
+    pub fn call(self, err: &dyn fmt::Debug, sent: u64) {
+        (self.0)(err, sent)
+    }
+}
+
+impl fmt::Debug for BodyErrorHook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("BodyErrorHook")
+    }
+}
+
+/// Probe of response body bytes sent by dispatcher.
+///
+/// Insert a clone of it to response extensions and keep the other one to observe the final size
+/// of response body after it's sent. This is useful for streaming response body where the size
+/// is not known before hand. Only payload bytes are counted and framing of chunked encoding and
+/// Http/2 frames is excluded.
+///
+/// # Examples:
+/// ```rust
+/// # use xitca_http::{body::BodySizeProbe, http::Response};
+/// let probe = BodySizeProbe::new();
+/// let mut res = Response::new(());
+/// res.extensions_mut().insert(probe.clone());
+///
+/// // after response is sent by dispatcher.
+/// if probe.is_complete() {
+///     println!("response body sent with {} bytes", probe.sent());
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct BodySizeProbe(Arc<ProbeInner>);
+
+#[derive(Default)]
+struct ProbeInner {
+    sent: AtomicU64,
+    state: AtomicU8,
+}
+
+// default state of zero means body is still being sent.
+const PROBE_COMPLETE: u8 = 1;
+const PROBE_ABORTED: u8 = 2;
+
+impl BodySizeProbe {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Number of body bytes sent so far.
+    pub fn sent(&self) -> u64 {
+        self.0.sent.load(Ordering::Relaxed)
+    }
+
+    /// Check if response body is ended cleanly and all bytes of it are sent.
+    pub fn is_complete(&self) -> bool {
+        self.0.state.load(Ordering::Relaxed) == PROBE_COMPLETE
+    }
+
+    /// Check if response body is aborted by body error or connection going away before it ends.
+    pub fn is_aborted(&self) -> bool {
+        self.0.state.load(Ordering::Relaxed) == PROBE_ABORTED
+    }
+}
+
+impl fmt::Debug for BodySizeProbe {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BodySizeProbe")
+            .field("sent", &self.sent())
+            .field("complete", &self.is_complete())
+            .field("aborted", &self.is_aborted())
+            .finish()
+    }
+}
+
+// dispatcher side of BodySizeProbe. body is treated as aborted when guard is dropped before
+// complete is called. no probe means no operation.
+pub(crate) struct ProbeGuard(Option<BodySizeProbe>);
+
+impl ProbeGuard {
+    pub(crate) fn new(probe: Option<BodySizeProbe>) -> Self {
+        Self(probe)
+    }
+
+    #[inline]
+    pub(crate) fn add(&self, len: usize) {
+        if let Some(ref probe) = self.0 {
+            probe.0.sent.fetch_add(len as u64, Ordering::Relaxed);
+        }
+    }
+
+    pub(crate) fn complete(mut self) {
+        if let Some(probe) = self.0.take() {
+            probe.0.state.store(PROBE_COMPLETE, Ordering::Relaxed);
+        }
+    }
+}
+
+impl Drop for ProbeGuard {
+    fn drop(&mut self) {
+        if let Some(probe) = self.0.take() {
+            probe.0.state.store(PROBE_ABORTED, Ordering::Relaxed);
+        }
+    }
+}
+
+#[cfg(feature = "runtime")]
+pub use self::io_impl::{ReaderStream, StreamReader};
+
+#[cfg(feature = "runtime")]
+mod io_impl {
+    use std::io;
+
+    use tokio::io::{AsyncBufRead, AsyncRead, ReadBuf};
+
+    use super::*;
+
+    impl RequestBody {
+        /// Convert request body to a type implement [AsyncRead] and [AsyncBufRead].
+        ///
+        /// Error from body is converted to [io::Error] where the original [BodyError] can be
+        /// retrieved with [io::Error::get_ref] and downcast.
+        ///
+        /// # Examples:
+        /// ```rust
+        /// # use xitca_http::body::RequestBody;
+        /// use tokio::io::AsyncBufReadExt;
+        ///
+        /// # async fn lines(body: RequestBody) -> std::io::Result<()> {
+        /// let mut lines = body.into_async_read().lines();
+        /// while let Some(line) = lines.next_line().await? {
+        ///     println!("{line}");
+        /// }
+        /// # Ok(())
+        /// # }
+        /// ```
+        #[inline]
+        pub fn into_async_read(self) -> StreamReader<Self> {
+            StreamReader::new(self)
+        }
+    }
+
+    impl ResponseBody {
+        /// Construct a new Stream variant of ResponseBody from a type implement [AsyncRead].
+        /// Reader is read with a buffer of given chunk_size and every read produces a chunk of body.
+        ///
+        /// Body size is unknown and would be sent with chunked transfer coding for http/1.1.
+        pub fn from_async_read<R>(reader: R, chunk_size: usize) -> Self
+        where
+            R: AsyncRead + 'static,
+        {
+            Self::box_stream(ReaderStream::new(reader, chunk_size))
+        }
+    }
+
+    pin_project! {
+        /// Adapter type convert a [Stream] of [Bytes] to [AsyncRead] and [AsyncBufRead].
+        ///
+        /// Partially consumed chunk is kept by reader itself so it's cancel safe to drop any
+        /// pending read and read again.
+        pub struct StreamReader<S> {
+            #[pin]
+            stream: S,
+            chunk: Bytes,
+        }
+    }
+
+    impl<S> StreamReader<S> {
+        #[inline]
+        pub const fn new(stream: S) -> Self {
+            Self {
+                stream,
+                chunk: Bytes::new(),
+            }
+        }
+
+        /// Get a reference to inner stream.
+        #[inline]
+        pub fn get_ref(&self) -> &S {
+            &self.stream
+        }
+
+        /// Consume reader and return inner stream.
+        ///
+        /// # Note:
+        /// Buffered chunk that is not consumed yet would be lost.
+        #[inline]
+        pub fn into_inner(self) -> S {
+            self.stream
+        }
+    }
+
+    impl<S, E> AsyncBufRead for StreamReader<S>
+    where
+        S: Stream<Item = Result<Bytes, E>>,
+        E: Into<io::Error>,
+    {
+        fn poll_fill_buf(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<&[u8]>> {
+            let mut this = self.project();
+            let chunk = this.chunk;
+            // skip empty chunks. empty slice is only returned when stream is finished.
+            while chunk.is_empty() {
+                match this.stream.as_mut().poll_next(cx) {
+                    Poll::Ready(Some(Ok(bytes))) => *chunk = bytes,
+                    Poll::Ready(Some(Err(e))) => return Poll::Ready(Err(e.into())),
+                    Poll::Ready(None) => break,
+                    Poll::Pending => return Poll::Pending,
+                }
+            }
+            Poll::Ready(Ok(chunk))
+        }
+
+        #[inline]
+        fn consume(self: Pin<&mut Self>, amt: usize) {
+            self.project().chunk.advance(amt);
+        }
+    }
+
+    impl<S, E> AsyncRead for StreamReader<S>
+    where
+        S: Stream<Item = Result<Bytes, E>>,
+        E: Into<io::Error>,
+    {
+        fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+            if buf.remaining() == 0 {
+                return Poll::Ready(Ok(()));
+            }
+            let chunk = match self.as_mut().poll_fill_buf(cx) {
+                Poll::Ready(Ok(chunk)) => chunk,
+                Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                Poll::Pending => return Poll::Pending,
+            };
+            let len = chunk.len().min(buf.remaining());
+            buf.put_slice(&chunk[..len]);
+            self.consume(len);
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    pin_project! {
+        /// Adapter type convert a type implement [AsyncRead] to a [Stream] of [Bytes].
+        pub struct ReaderStream<R> {
+            #[pin]
+            reader: Option<R>,
+            buf: BytesMut,
+            chunk_size: usize,
+        }
+    }
+
+    impl<R> ReaderStream<R> {
+        /// Construct a new stream that read at most chunk_size bytes for each item it yields.
+        ///
+        /// # Panics:
+        /// When chunk_size is zero.
+        pub fn new(reader: R, chunk_size: usize) -> Self {
+            assert_ne!(chunk_size, 0, "chunk_size must be none zero");
+            Self {
+                reader: Some(reader),
+                buf: BytesMut::new(),
+                chunk_size,
+            }
+        }
+    }
+
+    impl<R> Stream for ReaderStream<R>
+    where
+        R: AsyncRead,
+    {
+        type Item = io::Result<Bytes>;
+
+        fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            let mut this = self.project();
+
+            let Some(reader) = this.reader.as_mut().as_pin_mut() else {
+                return Poll::Ready(None);
+            };
+
+            this.buf.resize(*this.chunk_size, 0);
+            let mut buf = ReadBuf::new(&mut this.buf[..]);
+
+            let res = match reader.poll_read(cx, &mut buf) {
+                Poll::Ready(res) => res,
+                Poll::Pending => return Poll::Pending,
+            };
+
+            let len = buf.filled().len();
+
+            match res {
+                Ok(_) if len == 0 => {
+                    this.reader.set(None);
+                    Poll::Ready(None)
+                }
+                Ok(_) => {
+                    this.buf.truncate(len);
+                    Poll::Ready(Some(Ok(this.buf.split().freeze())))
+                }
+                Err(e) => {
+                    this.reader.set(None);
+                    Poll::Ready(Some(Err(e)))
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "http-body-compat")]
+pub use self::http_body_impl::{HttpBodyAdaptor, StreamBodyAdaptor};
+
+#[cfg(feature = "http-body-compat")]
+mod http_body_impl {
+    use std::task::ready;
+
+    use http_body::{Body, SizeHint};
+
+    use crate::http::HeaderMap;
+
+    use super::*;
+
+    pin_project! {
+        /// Adapter type convert a [Body] to [Stream] of [Bytes] with [BodyError] as error.
+        ///
+        /// Data frames are yielded as they are received without buffering. Trailers are polled
+        /// after the last data frame and can be accessed with [HttpBodyAdaptor::trailers] when
+        /// stream is finished. Body at the end of stream is never polled.
+        ///
+        /// # Examples:
+        /// ```rust
+        /// # use xitca_http::{body::{BodySize, HttpBodyAdaptor}, bytes::Bytes};
+        /// let body = HttpBodyAdaptor::new(http_body::Full::new(Bytes::from_static(b"996")));
+        /// assert_eq!(BodySize::from_stream(&body), BodySize::Sized(3));
+        /// ```
+        pub struct HttpBodyAdaptor<B> {
+            #[pin]
+            body: B,
+            state: State,
+            trailers: Option<HeaderMap>,
+        }
+    }
+
+    enum State {
+        Data,
+        Trailers,
+        Done,
+    }
+
+    impl<B> HttpBodyAdaptor<B> {
+        pub fn new(body: B) -> Self {
+            Self {
+                body,
+                state: State::Data,
+                trailers: None,
+            }
+        }
+
+        /// Trailers received after the last data frame of body.
+        pub fn trailers(&self) -> Option<&HeaderMap> {
+            self.trailers.as_ref()
+        }
+
+        pub fn into_inner(self) -> B {
+            self.body
+        }
+    }
+
+    impl<B> Stream for HttpBodyAdaptor<B>
+    where
+        B: Body,
+        B::Error: Into<Box<dyn error::Error + Send + Sync>>,
+    {
+        type Item = Result<Bytes, BodyError>;
+
+        fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+            let mut this = self.project();
+            loop {
+                match *this.state {
+                    State::Data if this.body.is_end_stream() => *this.state = State::Done,
+                    State::Data => match ready!(this.body.as_mut().poll_data(cx)) {
+                        Some(Ok(mut data)) => {
+                            // Bytes is split without copying.
+                            let len = data.remaining();
+                            return Poll::Ready(Some(Ok(data.copy_to_bytes(len))));
+                        }
+                        Some(Err(e)) => {
+                            *this.state = State::Done;
+                            return Poll::Ready(Some(Err(BodyError::Boxed(e.into()))));
+                        }
+                        None => *this.state = State::Trailers,
+                    },
+                    State::Trailers => {
+                        let res = ready!(this.body.as_mut().poll_trailers(cx));
+                        *this.state = State::Done;
+                        match res {
+                            Ok(trailers) => *this.trailers = trailers,
+                            Err(e) => return Poll::Ready(Some(Err(BodyError::Boxed(e.into())))),
+                        }
+                    }
+                    State::Done => return Poll::Ready(None),
+                }
+            }
+        }
+
+        fn size_hint(&self) -> (usize, Option<usize>) {
+            if matches!(self.state, State::Done) || self.body.is_end_stream() {
+                return exact_body_hint(0);
+            }
+            let hint = self.body.size_hint();
+            match hint.exact() {
+                Some(size) => exact_body_hint(size as usize),
+                None => (hint.lower() as usize, None),
+            }
+        }
+    }
+
+    pin_project! {
+        /// Adapter type convert a [Stream] of [Buf] to [Body]. e.g. [RequestBody] and
+        /// [ResponseBody].
+        ///
+        /// Size hint of body is derived from [BodySize] of stream. Stream of [BodySize::None] or
+        /// [BodySize::Sized] of zero is at the end of stream and never polled. Stream has no
+        /// trailers.
+        ///
+        /// # Examples:
+        /// ```rust
+        /// # use xitca_http::body::{BoxStream, ResponseBody, StreamBodyAdaptor};
+        /// use http_body::Body;
+        ///
+        /// let body = StreamBodyAdaptor::new(ResponseBody::<BoxStream>::bytes("996"));
+        /// assert_eq!(body.size_hint().exact(), Some(3));
+        /// ```
+        pub struct StreamBodyAdaptor<B> {
+            #[pin]
+            body: B,
+            eof: bool,
+        }
+    }
+
+    impl<B> StreamBodyAdaptor<B> {
+        pub fn new(body: B) -> Self {
+            Self { body, eof: false }
+        }
+
+        pub fn into_inner(self) -> B {
+            self.body
+        }
+    }
+
+    impl<B, T, E> Body for StreamBodyAdaptor<B>
+    where
+        B: Stream<Item = Result<T, E>>,
+        T: Buf,
+    {
+        type Data = T;
+        type Error = E;
+
+        fn poll_data(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+            if self.is_end_stream() {
+                return Poll::Ready(None);
+            }
+            let this = self.project();
+            let res = ready!(this.body.poll_next(cx));
+            *this.eof = res.is_none();
+            Poll::Ready(res)
+        }
+
+        #[inline]
+        fn poll_trailers(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Result<Option<HeaderMap>, Self::Error>> {
+            Poll::Ready(Ok(None))
+        }
+
+        fn is_end_stream(&self) -> bool {
+            self.eof || matches!(BodySize::from_stream(&self.body), BodySize::None | BodySize::Sized(0))
+        }
+
+        fn size_hint(&self) -> SizeHint {
+            if self.eof {
+                return SizeHint::with_exact(0);
+            }
+            match BodySize::from_stream(&self.body) {
+                BodySize::None => SizeHint::with_exact(0),
+                BodySize::Sized(size) => SizeHint::with_exact(size as u64),
+                BodySize::Stream => {
+                    let mut hint = SizeHint::new();
+                    hint.set_lower(self.body.size_hint().0 as u64);
+                    hint
+                }
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -352,4 +941,236 @@ mod test {
         let body = BoxStream::new(NoneBody::<Bytes>::default());
         assert_eq!(BodySize::from_stream(&body), BodySize::None);
     }
+
+    #[test]
+    fn sized_stream_size_hint() {
+        for size in [BodySize::None, BodySize::Sized(996), BodySize::Stream] {
+            let body = BoxStream::new(SizedStream::new(NoneBody::<Bytes>::default(), size));
+            assert_eq!(BodySize::from_stream(&body), size);
+        }
+    }
+
+    #[cfg(feature = "runtime")]
+    mod io {
+        use std::io;
+
+        use tokio::io::{AsyncBufReadExt, AsyncReadExt};
+
+        use super::*;
+
+        struct Chunks(Vec<Result<Bytes, BodyError>>);
+
+        impl Stream for Chunks {
+            type Item = Result<Bytes, BodyError>;
+
+            fn poll_next(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+                let this = self.get_mut();
+                Poll::Ready((!this.0.is_empty()).then(|| this.0.remove(0)))
+            }
+        }
+
+        fn body(chunks: Vec<Result<Bytes, BodyError>>) -> RequestBody {
+            RequestBody::from(BoxStream::new(Chunks(chunks)))
+        }
+
+        #[tokio::test]
+        async fn request_body_read_line() {
+            let chunks = ["hel", "lo\nwor", "", "ld\n\nla", "st"]
+                .into_iter()
+                .map(|c| Ok(Bytes::from_static(c.as_bytes())))
+                .collect();
+
+            let mut reader = body(chunks).into_async_read();
+            let mut line = String::new();
+
+            for expected in ["hello\n", "world\n", "\n", "last", ""] {
+                line.clear();
+                reader.read_line(&mut line).await.unwrap();
+                assert_eq!(line, expected);
+            }
+        }
+
+        #[tokio::test]
+        async fn request_body_read_partial_chunk() {
+            let chunks = vec![Ok(Bytes::from_static(b"hello world"))];
+            let mut reader = body(chunks).into_async_read();
+
+            let mut buf = [0; 4];
+            reader.read_exact(&mut buf).await.unwrap();
+            assert_eq!(&buf, b"hell");
+
+            assert_eq!(reader.fill_buf().await.unwrap(), b"o world");
+            reader.consume(2);
+
+            let mut rest = Vec::new();
+            reader.read_to_end(&mut rest).await.unwrap();
+            assert_eq!(rest, b"world");
+        }
+
+        #[tokio::test]
+        async fn request_body_read_error() {
+            let chunks = vec![Ok(Bytes::from_static(b"foo\n")), Err(BodyError::Overflow { limit: 4 })];
+            let mut reader = StreamReader::new(Chunks(chunks));
+
+            let mut line = String::new();
+            reader.read_line(&mut line).await.unwrap();
+            assert_eq!(line, "foo\n");
+
+            let err = reader.read_line(&mut line).await.err().unwrap();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+            let err = err.get_ref().unwrap().downcast_ref::<BodyError>().unwrap();
+            assert!(err.is_overflow());
+        }
+
+        #[tokio::test]
+        async fn response_body_from_async_read() {
+            let body = ResponseBody::from_async_read(&b"hello world"[..], 4);
+            assert_eq!(BodySize::from_stream(&body), BodySize::Stream);
+
+            let mut reader = StreamReader::new(body);
+            let mut chunks = Vec::new();
+            while !reader.fill_buf().await.unwrap().is_empty() {
+                let chunk = reader.fill_buf().await.unwrap().to_vec();
+                reader.consume(chunk.len());
+                chunks.push(chunk);
+            }
+            assert_eq!(chunks, [&b"hell"[..], b"o wo", b"rld"]);
+        }
+    }
+
+    #[cfg(feature = "http-body-compat")]
+    mod http_body_compat {
+        use core::future::poll_fn;
+
+        use http_body::Body;
+        use xitca_unsafe_collection::futures::NowOrPanic;
+
+        use crate::http::{HeaderMap, HeaderValue};
+
+        use super::*;
+
+        // chunked body with unknown size.
+        struct Chunks(Vec<Result<Bytes, BodyError>>);
+
+        impl Stream for Chunks {
+            type Item = Result<Bytes, BodyError>;
+
+            fn poll_next(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+                let this = self.get_mut();
+                Poll::Ready((!this.0.is_empty()).then(|| this.0.remove(0)))
+            }
+        }
+
+        fn collect<B>(body: &mut B) -> Vec<Result<Bytes, BodyError>>
+        where
+            B: Stream<Item = Result<Bytes, BodyError>> + Unpin,
+        {
+            let mut chunks = Vec::new();
+            while let Some(res) = poll_fn(|cx| Pin::new(&mut *body).poll_next(cx)).now_or_panic() {
+                chunks.push(res);
+            }
+            chunks
+        }
+
+        #[test]
+        fn round_trip_chunked() {
+            let chunks = ["hel", "lo", "", " world"]
+                .into_iter()
+                .map(|c| Ok(Bytes::from_static(c.as_bytes())))
+                .chain([Err(BodyError::Overflow { limit: 11 })])
+                .collect();
+
+            let body = StreamBodyAdaptor::new(Chunks(chunks));
+            assert!(!body.is_end_stream());
+            assert_eq!(Body::size_hint(&body).exact(), None);
+
+            let mut body = HttpBodyAdaptor::new(body);
+            assert_eq!(BodySize::from_stream(&body), BodySize::Stream);
+
+            let mut chunks = collect(&mut body).into_iter();
+            for expected in ["hel", "lo", "", " world"] {
+                assert_eq!(chunks.next().unwrap().unwrap(), expected);
+            }
+
+            // error of stream is passed through both adapters.
+            let BodyError::Boxed(e) = chunks.next().unwrap().unwrap_err() else {
+                panic!("error must be boxed")
+            };
+            assert!(e.downcast_ref::<BodyError>().unwrap().is_overflow());
+            assert!(chunks.next().is_none());
+
+            assert!(body.trailers().is_none());
+            assert_eq!(BodySize::from_stream(&body), BodySize::Sized(0));
+        }
+
+        #[test]
+        fn sized_size_hint() {
+            let body = StreamBodyAdaptor::new(ResponseBody::<BoxStream>::bytes("hello,world"));
+            assert!(!body.is_end_stream());
+            assert_eq!(Body::size_hint(&body).exact(), Some(11));
+
+            let mut body = HttpBodyAdaptor::new(body);
+            assert_eq!(Stream::size_hint(&body), exact_body_hint(11));
+            assert_eq!(BodySize::from_stream(&body), BodySize::Sized(11));
+
+            let chunks = collect(&mut body);
+            assert_eq!(chunks.len(), 1);
+            assert_eq!(chunks[0].as_ref().unwrap(), "hello,world");
+        }
+
+        #[test]
+        fn empty_body() {
+            struct Panic;
+
+            impl Stream for Panic {
+                type Item = Result<Bytes, BodyError>;
+
+                fn poll_next(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+                    panic!("empty body must not be polled")
+                }
+
+                fn size_hint(&self) -> (usize, Option<usize>) {
+                    exact_body_hint(0)
+                }
+            }
+
+            for body in [ResponseBody::None, ResponseBody::stream(Panic)] {
+                let body = StreamBodyAdaptor::new(body);
+                assert!(body.is_end_stream());
+                assert_eq!(Body::size_hint(&body).exact(), Some(0));
+
+                let mut body = HttpBodyAdaptor::new(body);
+                assert_eq!(BodySize::from_stream(&body), BodySize::Sized(0));
+                assert!(collect(&mut body).is_empty());
+            }
+        }
+
+        #[test]
+        fn trailers() {
+            struct Trailers(Option<Bytes>);
+
+            impl Body for Trailers {
+                type Data = Bytes;
+                type Error = BodyError;
+
+                fn poll_data(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<Result<Bytes, BodyError>>> {
+                    Poll::Ready(self.get_mut().0.take().map(Ok))
+                }
+
+                fn poll_trailers(
+                    self: Pin<&mut Self>,
+                    _: &mut Context<'_>,
+                ) -> Poll<Result<Option<HeaderMap>, BodyError>> {
+                    let mut trailers = HeaderMap::new();
+                    trailers.insert("grpc-status", HeaderValue::from_static("0"));
+                    Poll::Ready(Ok(Some(trailers)))
+                }
+            }
+
+            let mut body = HttpBodyAdaptor::new(Trailers(Some(Bytes::from_static(b"996"))));
+            let chunks = collect(&mut body);
+            assert_eq!(chunks.len(), 1);
+            assert_eq!(body.trailers().unwrap().get("grpc-status").unwrap(), "0");
+        }
+    }
 }
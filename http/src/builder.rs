@@ -1,7 +1,7 @@
 use std::{future::Future, marker::PhantomData};
 
 use xitca_io::net;
-use xitca_service::{EnclosedFactory, Service, ServiceExt};
+use xitca_service::{ready::ReadyService, EnclosedFactory, Service, ServiceExt};
 
 use super::{
     body::RequestBody,
@@ -9,7 +9,10 @@ use super::{
     error::BuildError,
     service::HttpService,
     tls,
-    util::middleware::Logger,
+    util::{
+        drain::Drain,
+        middleware::{Logger, Readiness},
+    },
 };
 
 // marker type for separate HttpServerBuilders' ServiceFactory implement with specialized trait
@@ -23,6 +26,8 @@ pub(crate) mod marker {
     pub struct Http1Uring;
     #[cfg(feature = "http2")]
     pub struct Http2;
+    #[cfg(all(feature = "io-uring", feature = "http2"))]
+    pub struct Http2Uring;
 }
 
 /// HttpService Builder type.
@@ -39,6 +44,7 @@ pub struct HttpServiceBuilder<
     pub(crate) factory: F,
     pub(crate) tls_factory: FA,
     pub(crate) config: HttpServiceConfig<HEADER_LIMIT, READ_BUF_LIMIT, WRITE_BUF_LIMIT>,
+    pub(crate) drain: Option<Drain>,
     pub(crate) _body: PhantomData<(V, St)>,
 }
 
@@ -75,6 +81,7 @@ impl<F>
             factory,
             tls_factory: tls::NoOpTlsAcceptorBuilder,
             config,
+            drain: None,
             _body: PhantomData,
         }
     }
@@ -99,6 +106,7 @@ impl<F>
             factory,
             tls_factory: tls::NoOpTlsAcceptorBuilder,
             config: HttpServiceConfig::default(),
+            drain: None,
             _body: PhantomData,
         }
     }
@@ -123,6 +131,7 @@ impl<F>
             factory,
             tls_factory: tls::NoOpTlsAcceptorBuilder,
             config: HttpServiceConfig::default(),
+            drain: None,
             _body: PhantomData,
         }
     }
@@ -144,28 +153,93 @@ impl<V, St, F, FA, const HEADER_LIMIT: usize, const READ_BUF_LIMIT: usize, const
         self,
         config: HttpServiceConfig<HEADER_LIMIT_2, READ_BUF_LIMIT_2, WRITE_BUF_LIMIT_2>,
     ) -> HttpServiceBuilder<V, St, F, FA, HEADER_LIMIT_2, READ_BUF_LIMIT_2, WRITE_BUF_LIMIT_2> {
+        let mut config = config;
+        config.tls = self.config.tls;
         HttpServiceBuilder {
             factory: self.factory,
             tls_factory: self.tls_factory,
             config,
+            drain: self.drain,
             _body: PhantomData,
         }
     }
 
     /// Pass a tls service factory to Builder and use it to handle tls handling with
     /// Http/1 and Http/2.
+    ///
+    /// Http/1 request uri would use `https` scheme afterwards.
     pub fn with_tls<TlsF>(
-        self,
+        mut self,
         tls_factory: TlsF,
     ) -> HttpServiceBuilder<V, St, F, TlsF, HEADER_LIMIT, READ_BUF_LIMIT, WRITE_BUF_LIMIT> {
+        self.config.tls = true;
         HttpServiceBuilder {
             factory: self.factory,
             tls_factory,
             config: self.config,
+            drain: self.drain,
+            _body: PhantomData,
+        }
+    }
+
+    /// Attach an extra readiness check to service built by factory.
+    ///
+    /// Given function is called for every instance of service(one per worker thread of server) and
+    /// it's output's [ReadyService] is combined with the service's. Server stops accepting new
+    /// connections while it's not ready. This is useful for shedding load at the door when a
+    /// resource like database connection pool is exhausted.
+    ///
+    /// # Examples:
+    /// ```rust
+    /// # #![feature(impl_trait_in_assoc_type)]
+    /// # use std::{convert::Infallible, future::Future, sync::Arc};
+    /// # use xitca_http::{http::{Request, RequestExt, Response}, RequestBody, ResponseBody, HttpServiceBuilder};
+    /// # use xitca_service::{fn_service, ready::ReadyService};
+    /// # struct Pool;
+    /// # impl Pool { async fn wait_for_idle(&self) {} }
+    /// // a readiness type resolves when there is idle connection in a shared database pool.
+    /// struct PoolCapacity(Arc<Pool>);
+    ///
+    /// impl ReadyService for PoolCapacity {
+    ///     type Ready = ();
+    ///     type Future<'f> = impl Future<Output = Self::Ready> + 'f where Self: 'f;
+    ///
+    ///     fn ready(&self) -> Self::Future<'_> {
+    ///         self.0.wait_for_idle()
+    ///     }
+    /// }
+    ///
+    /// async fn handler(_: Request<RequestExt<RequestBody>>) -> Result<Response<ResponseBody>, Infallible> {
+    ///     Ok(Response::new(ResponseBody::None))
+    /// }
+    ///
+    /// let pool = Arc::new(Pool);
+    /// let builder = HttpServiceBuilder::new(fn_service(handler)).readiness(move || PoolCapacity(pool.clone()));
+    /// ```
+    pub fn readiness<R, RS>(
+        self,
+        readiness: R,
+    ) -> HttpServiceBuilder<V, St, EnclosedFactory<F, Readiness<R>>, FA, HEADER_LIMIT, READ_BUF_LIMIT, WRITE_BUF_LIMIT>
+    where
+        R: Fn() -> RS,
+        RS: ReadyService,
+    {
+        HttpServiceBuilder {
+            factory: EnclosedFactory::new(self.factory, Readiness::new(readiness)),
+            tls_factory: self.tls_factory,
+            config: self.config,
+            drain: self.drain,
             _body: PhantomData,
         }
     }
 
+    /// Set signal for draining connections served by services built from builder. See [Drain] for
+    /// detail.
+    pub fn drain(mut self, drain: Drain) -> Self {
+        self.drain = Some(drain);
+        self
+    }
+
     /// Finish builder with default logger.
     ///
     /// Would consume input.
@@ -194,6 +268,17 @@ impl<V, St, F, FA, const HEADER_LIMIT: usize, const READ_BUF_LIMIT: usize, const
         self.with_tls(tls::rustls::TlsAcceptorBuilder::new(config))
     }
 
+    /// Use rustls with a config that can be reloaded at runtime. See [tls::ReloadableTlsAcceptor]
+    /// for detail.
+    #[cfg(feature = "rustls")]
+    pub fn rustls_reloadable(
+        self,
+        acceptor: tls::ReloadableTlsAcceptor,
+    ) -> HttpServiceBuilder<V, St, F, tls::rustls::TlsAcceptorBuilder, HEADER_LIMIT, READ_BUF_LIMIT, WRITE_BUF_LIMIT>
+    {
+        self.with_tls(tls::rustls::TlsAcceptorBuilder::from(acceptor))
+    }
+
     #[cfg(feature = "rustls-uring")]
     pub fn rustls_uring(
         self,
@@ -238,7 +323,28 @@ where
         async {
             let tls_acceptor = self.tls_factory.call(()).await.map_err(BuildError::First)?;
             let service = self.factory.call(arg).await.map_err(BuildError::Second)?;
-            Ok(HttpService::new(self.config, service, tls_acceptor))
+            let mut service = HttpService::new(self.config, service, tls_acceptor);
+            service.drain = self.drain.as_ref().map(Drain::watch);
+            Ok(service)
         }
     }
 }
+
+#[cfg(all(test, feature = "http1"))]
+mod test {
+    use crate::http::uri::Scheme;
+
+    use super::*;
+
+    #[test]
+    fn tls_scheme() {
+        let builder = HttpServiceBuilder::h1(());
+        assert_eq!(builder.config.scheme(), Scheme::HTTP);
+
+        // tls flag is kept when config is replaced afterwards.
+        let builder = builder
+            .with_tls(tls::NoOpTlsAcceptorBuilder)
+            .config(HttpServiceConfig::new());
+        assert_eq!(builder.config.scheme(), Scheme::HTTPS);
+    }
+}
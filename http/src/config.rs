@@ -1,4 +1,6 @@
-use std::time::Duration;
+use std::{net::SocketAddr, time::Duration};
+
+use crate::http::TlsConnectionInfo;
 
 /// The default maximum read buffer size. If the head gets this big and
 /// a message is still not complete, a `TooLarge` error is triggered.
@@ -17,17 +19,166 @@ pub const DEFAULT_WRITE_BUF_LIMIT: usize = 8192 + 4096 * 100;
 /// 64 chosen for no particular reason.
 pub const DEFAULT_HEADER_LIMIT: usize = 64;
 
+/// The default maximum length of request target(uri) in bytes as it's received from peer. If the
+/// target of request line gets this big a `414 URI Too Long` response is sent.
+pub const DEFAULT_MAX_URI_LENGTH: usize = 8 * 1024;
+
+/// The default maximum size of http/1 response head in bytes. If the head written by service gets
+/// this big it's replaced by a `500 Internal Server Error` response.
+pub const DEFAULT_MAX_RESPONSE_HEAD_SIZE: usize = 64 * 1024;
+
+/// The default maximum size of http/1 response body in bytes that is written together with
+/// response head.
+pub const DEFAULT_MAX_COALESCE_BODY_SIZE: usize = 4 * 1024;
+
+/// Strategy of http/1 response write buffer.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum WriteBufStrategy {
+    /// Start with vectored buffer and sample responses of a connection. Switch to flat buffer when
+    /// response body chunks are small on average and back when they are large.
+    ///
+    /// Fall back to [WriteBufStrategy::Flat] when IO is not able to perform vectored write.
+    Auto,
+    /// Copy response head and body into one contiguous buffer. Favors many small writes.
+    Flat,
+    /// Queue response body as is and write with vectored io. Favors few large writes.
+    ///
+    /// Fall back to [WriteBufStrategy::Flat] when IO is not able to perform vectored write.
+    Vectored,
+}
+
+/// Policy of flushing http/1 write buffer to io between responses of pipelined requests.
+/// See [HttpServiceConfig::flush_policy].
+///
+/// Regardless of policy write buffer is always flushed when no more request can be decoded from
+/// read buffer, before waiting for more request body from client and when write buffer is full.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum FlushPolicy {
+    /// Flush after every response. Favors latency of each response.
+    EveryResponse,
+    /// Flush after a response when write buffer holds at least given amount of bytes.
+    BufferedUntil(usize),
+    /// Flush only when pipelined requests in read buffer are all served. Favors throughput of
+    /// pipelined requests.
+    EndOfReadBatch,
+}
+
+/// Strategy of refusing http/2 stream exceeding the limit of concurrent requests.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum H2Refusal {
+    /// Respond with `503 Service Unavailable`.
+    ServiceUnavailable,
+    /// Reset stream with `REFUSED_STREAM` error code. Client can safely retry the request as it's
+    /// guaranteed to not be processed.
+    RefusedStream,
+}
+
+/// Policy of handling http/1 request exceeding the rate limit of connection.
+/// See [HttpServiceConfig::max_requests_per_second].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum RequestRatePolicy {
+    /// Stop decoding requests until the current one second window ends. Requests sent by client
+    /// are left in read buffer and socket, which applies backpressure to it.
+    Delay,
+    /// Respond with `429 Too Many Requests` and close the connection.
+    Reject,
+}
+
+/// Strategy of validating and normalizing request target of http/1 request.
+/// See [HttpServiceConfig::uri_normalization].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum UriNormalization {
+    /// Request target is passed to service as it's received.
+    Disable,
+    /// Reject request target containing control bytes, `DEL` or non ASCII bytes and remove dot
+    /// segments (`.` and `..`) of path according to
+    /// [RFC 3986](https://www.rfc-editor.org/rfc/rfc3986#section-5.2.4). Percent-encoded bytes are
+    /// never decoded and a percent-encoded dot segment(`%2e%2e`) is left as is.
+    Standard,
+    /// Same as [UriNormalization::Standard] and percent-encoded dot segments are removed too.
+    Strict,
+}
+
+/// Event of a connection served by [HttpService](crate::HttpService).
+/// See [HttpServiceConfig::connection_observer].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ConnectionEvent {
+    /// Connection is accepted and about to be served.
+    Open,
+    /// Tls handshake of connection failed or timed out. It's followed by [ConnectionEvent::Close].
+    TlsAcceptFailed,
+    /// Tls handshake of connection is finished with given facts. Handshakes of connections can
+    /// be counted with it. (e.g. full and resumed ones)
+    TlsHandshake(TlsConnectionInfo),
+    /// Service call of a request on connection is stuck longer than
+    /// [HttpServiceConfig::stuck_request_threshold]. It can happen more than once per connection.
+    StuckRequest,
+    /// Body of a request on connection or it's response violates a policy enforced by middleware.
+    /// It's not emitted by [HttpService](crate::HttpService) itself.
+    BodyViolation(BodyViolation),
+    /// Connection is finished.
+    Close,
+}
+
+/// Violation of request or response body policy. See [ConnectionEvent::BodyViolation].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum BodyViolation {
+    /// Request body is larger than limit in bytes.
+    RequestTooLarge { limit: u64 },
+    /// Request content type is not allowed.
+    UnsupportedMediaType,
+    /// Response body is larger than limit in bytes. truncated is true when body is cut at the
+    /// limit instead of being rejected.
+    ResponseTooLarge { limit: usize, truncated: bool },
+}
+
+/// Action taken on service call stuck longer than the threshold.
+/// See [HttpServiceConfig::stuck_request_threshold].
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum StuckRequestAction {
+    /// Emit the warning and keep waiting for service call.
+    Warn,
+    /// Emit the warning, drop service call and respond with `503 Service Unavailable`. Http/1
+    /// connection is closed after the response.
+    Abort,
+}
+
 #[derive(Copy, Clone)]
 pub struct HttpServiceConfig<
     const HEADER_LIMIT: usize = DEFAULT_HEADER_LIMIT,
     const READ_BUF_LIMIT: usize = DEFAULT_READ_BUF_LIMIT,
     const WRITE_BUF_LIMIT: usize = DEFAULT_WRITE_BUF_LIMIT,
 > {
-    pub(crate) vectored_write: bool,
+    pub(crate) write_buf_strategy: WriteBufStrategy,
+    pub(crate) flush_policy: FlushPolicy,
     pub(crate) keep_alive_timeout: Duration,
+    pub(crate) keep_alive_granularity: Duration,
     pub(crate) request_head_timeout: Duration,
     pub(crate) tls_accept_timeout: Duration,
-    pub(crate) peek_protocol: bool,
+    pub(crate) raw_request_head: bool,
+    pub(crate) request_scratch: bool,
+    pub(crate) request_arrival: bool,
+    pub(crate) informational_responses: bool,
+    pub(crate) cache_request_head: bool,
+    pub(crate) max_request_body_size: u64,
+    pub(crate) max_uri_length: usize,
+    pub(crate) max_response_head_size: usize,
+    pub(crate) max_coalesce_body_size: usize,
+    pub(crate) preserve_header_case: bool,
+    pub(crate) normalize_request_headers: bool,
+    pub(crate) uri_normalization: UriNormalization,
+    pub(crate) min_write_rate: Option<(u64, Duration)>,
+    pub(crate) h2_max_concurrent_requests: Option<(usize, H2Refusal)>,
+    pub(crate) h2_refused_observer: Option<fn(SocketAddr, usize)>,
+    pub(crate) connection_observer: Option<fn(SocketAddr, ConnectionEvent)>,
+    pub(crate) h2_connection_body_budget: Option<usize>,
+    pub(crate) h2_max_header_list_size: Option<usize>,
+    pub(crate) max_connections: Option<usize>,
+    pub(crate) max_requests_per_second: Option<(u32, RequestRatePolicy)>,
+    pub(crate) stuck_request_threshold: Option<(Duration, StuckRequestAction)>,
+    // set by HttpServiceBuilder when a tls acceptor is used. it decides the scheme of http/1
+    // request uri.
+    pub(crate) tls: bool,
 }
 
 impl Default for HttpServiceConfig {
@@ -39,11 +190,34 @@ impl Default for HttpServiceConfig {
 impl HttpServiceConfig {
     pub const fn new() -> Self {
         Self {
-            vectored_write: true,
+            write_buf_strategy: WriteBufStrategy::Vectored,
+            flush_policy: FlushPolicy::EndOfReadBatch,
             keep_alive_timeout: Duration::from_secs(5),
+            keep_alive_granularity: Duration::from_secs(1),
             request_head_timeout: Duration::from_secs(5),
             tls_accept_timeout: Duration::from_secs(3),
-            peek_protocol: false,
+            raw_request_head: false,
+            request_scratch: false,
+            request_arrival: false,
+            informational_responses: false,
+            cache_request_head: false,
+            max_request_body_size: u64::MAX,
+            max_uri_length: DEFAULT_MAX_URI_LENGTH,
+            max_response_head_size: DEFAULT_MAX_RESPONSE_HEAD_SIZE,
+            max_coalesce_body_size: DEFAULT_MAX_COALESCE_BODY_SIZE,
+            preserve_header_case: false,
+            normalize_request_headers: true,
+            uri_normalization: UriNormalization::Standard,
+            min_write_rate: None,
+            h2_max_concurrent_requests: None,
+            h2_refused_observer: None,
+            connection_observer: None,
+            h2_connection_body_budget: None,
+            h2_max_header_list_size: None,
+            max_connections: None,
+            max_requests_per_second: None,
+            stuck_request_threshold: None,
+            tls: false,
         }
     }
 }
@@ -55,7 +229,24 @@ impl<const HEADER_LIMIT: usize, const READ_BUF_LIMIT: usize, const WRITE_BUF_LIM
     ///
     /// This is beneficial when dealing with small size of response body.
     pub fn disable_vectored_write(mut self) -> Self {
-        self.vectored_write = false;
+        self.write_buf_strategy = WriteBufStrategy::Flat;
+        self
+    }
+
+    /// Define strategy of http/1 response write buffer.
+    ///
+    /// Default to [WriteBufStrategy::Vectored]. See [WriteBufStrategy] for detail.
+    pub fn write_buf_strategy(mut self, strategy: WriteBufStrategy) -> Self {
+        self.write_buf_strategy = strategy;
+        self
+    }
+
+    /// Define policy of flushing http/1 write buffer between responses of pipelined requests.
+    ///
+    /// Default to [FlushPolicy::EndOfReadBatch]. See [FlushPolicy] for detail. io-uring based
+    /// http/1 is not affected by this setting.
+    pub fn flush_policy(mut self, policy: FlushPolicy) -> Self {
+        self.flush_policy = policy;
         self
     }
 
@@ -68,6 +259,19 @@ impl<const HEADER_LIMIT: usize, const READ_BUF_LIMIT: usize, const WRITE_BUF_LIM
         self
     }
 
+    /// Define granularity of connection timers.
+    ///
+    /// Deadlines of keep-alive and request head timeout are rounded up to a multiple of given
+    /// duration. Connections serving requests frequently would skip most of their timer updates
+    /// at the cost of possibly living up to one granularity longer than the configured timeouts.
+    /// Zero duration disables the rounding.
+    ///
+    /// Default to 1 second.
+    pub fn keep_alive_granularity(mut self, dur: Duration) -> Self {
+        self.keep_alive_granularity = dur;
+        self
+    }
+
     /// Define duration of how long a connection must finish it's request head transferring.
     /// starting from first byte(s) of current request(s) received from peer.
     ///
@@ -121,11 +325,305 @@ impl<const HEADER_LIMIT: usize, const READ_BUF_LIMIT: usize, const WRITE_BUF_LIM
     ///
     /// This API is used to bypass alpn setting from tls and enable Http/2 protocol over
     /// plain Tcp connection.
-    pub fn peek_protocol(mut self) -> Self {
-        self.peek_protocol = true;
+    ///
+    /// Peeking is not implemented and this method has no effect. Protocol of connection is
+    /// decided by alpn negotiation and falls back to http/1 for plain connection.
+    #[doc(hidden)]
+    #[deprecated(note = "peeking protocol of connection is not implemented and this method has no effect")]
+    pub fn peek_protocol(self) -> Self {
+        self
+    }
+
+    /// Define max request body size in bytes a connection would accept.
+    ///
+    /// The limit is the default of every request with body and service can override it per request
+    /// through [RequestBodyLimit](crate::http::RequestBodyLimit) in request's extensions.
+    ///
+    /// Request advertising a larger Content-Length is answered with 413 Payload Too Large before
+    /// any of it's body is read and Http/1 client is not invited to upload it with 100 continue.
+    /// For Http/1 the connection is closed afterwards.
+    ///
+    /// Body without Content-Length is counted as it's received. Once it goes over the limit the body
+    /// yields an error and the request is answered with 413 Payload Too Large. Http/1 connection is
+    /// closed afterwards and Http/2 stream is reset.
+    ///
+    /// Default to no limit.
+    pub fn max_request_body_size(mut self, size: u64) -> Self {
+        self.max_request_body_size = size;
+        self
+    }
+
+    /// Define max length of http/1 request target(uri) in bytes.
+    ///
+    /// The length is checked against request line as it's received from peer(percent-encoded)
+    /// before the rest of request head is parsed. Request with longer target is answered with
+    /// 414 URI Too Long and the connection is closed afterwards.
+    ///
+    /// See [DEFAULT_MAX_URI_LENGTH](DEFAULT_MAX_URI_LENGTH) for default value.
+    pub fn max_uri_length(mut self, len: usize) -> Self {
+        self.max_uri_length = len;
+        self
+    }
+
+    /// Define max size of http/1 response head in bytes.
+    ///
+    /// The size is checked before every header line is written so oversized header never grows
+    /// the write buffer. Response with larger head is discarded and an error naming the
+    /// offending header is logged. A 500 Internal Server Error is sent instead and the connection
+    /// is closed afterwards.
+    ///
+    /// See [DEFAULT_MAX_RESPONSE_HEAD_SIZE](DEFAULT_MAX_RESPONSE_HEAD_SIZE) for default value.
+    pub fn max_response_head_size(mut self, size: usize) -> Self {
+        self.max_response_head_size = size;
+        self
+    }
+
+    /// Set max size of http/1 response body in bytes that is copied into the same buffer with
+    /// response head.
+    ///
+    /// Response body with exact size no larger than it is polled once before the head is encoded.
+    /// When it's ready the head and body are written to io together as one piece. Set to 0 to
+    /// disable it.
+    ///
+    /// See [DEFAULT_MAX_COALESCE_BODY_SIZE](DEFAULT_MAX_COALESCE_BODY_SIZE) for default value.
+    pub fn max_coalesce_body_size(mut self, size: usize) -> Self {
+        self.max_coalesce_body_size = size;
+        self
+    }
+
+    /// Keep the raw bytes of http/1 request head as they are received from peer and store them in
+    /// request's extensions as [RawRequestHead](crate::http::RawRequestHead).
+    ///
+    /// The raw head shares the same buffer with parsed header values so no extra copy is made.
+    /// Http/2 and Http/3 requests are not affected by this setting.
+    pub fn keep_raw_request_head(mut self) -> Self {
+        self.raw_request_head = true;
+        self
+    }
+
+    /// Keep a per connection [Scratch](crate::util::scratch::Scratch) arena for http/1 and store
+    /// a handle of it in request's extensions. Extractors can use it for temporary values instead
+    /// of allocating them on heap for every request.
+    ///
+    /// The arena is reset when next request of the connection is decoded. Http/2 and Http/3
+    /// requests are not affected by this setting.
+    pub fn request_scratch(mut self) -> Self {
+        self.request_scratch = true;
+        self
+    }
+
+    /// Record the time when request head is decoded and store it in request's extensions as
+    /// [RequestArrival](crate::http::RequestArrival). The time is read from OS with
+    /// [DateTime::now_precise](crate::date::DateTime::now_precise) for every request.
+    ///
+    /// Http/3 requests are not affected by this setting.
+    pub fn request_arrival_time(mut self) -> Self {
+        self.request_arrival = true;
+        self
+    }
+
+    /// Store [Informational](crate::http::Informational) handle in extensions of http/1.1 request
+    /// so service can send informational(1xx) responses like `103 Early Hints` before the final
+    /// response.
+    ///
+    /// Http/1.0, Http/2 and Http/3 requests are not affected by this setting.
+    pub fn informational_responses(mut self) -> Self {
+        self.informational_responses = true;
+        self
+    }
+
+    /// Cache the decoded parts of the most recent http/1 request head of a connection. A following
+    /// request head byte identical to the cached one is decoded by cloning method, uri and headers
+    /// from the cache instead of parsing it again.
+    ///
+    /// This is for clients sending the same request head repeatedly on a connection. (e.g.
+    /// telemetry agents only differing in request body) Request head is compared with the cached
+    /// one in full so any different byte falls back to parsing. Only one head is cached per
+    /// connection and it's replaced by every parsed head. Http/2 and Http/3 requests are not
+    /// affected by this setting.
+    pub fn cache_request_head(mut self) -> Self {
+        self.cache_request_head = true;
+        self
+    }
+
+    /// Write http/1 response header names with their original casing instead of lowercase.
+    ///
+    /// Spelling is looked up from [HeaderCaseMap](crate::http::HeaderCaseMap) in response's
+    /// extensions. Names not found in it are written in title case. (e.g. `Content-Length`)
+    /// This is for legacy clients that treat header names case-sensitively. Http/2 and Http/3
+    /// responses are not affected by this setting.
+    pub fn preserve_header_case(mut self) -> Self {
+        self.preserve_header_case = true;
+        self
+    }
+
+    /// Disable normalization of Http/1 and Http/2 request headers.
+    ///
+    /// By default hop-by-hop headers (See [remove_hop_by_hop_headers](crate::http::remove_hop_by_hop_headers))
+    /// are removed from request passed to service. `Connection` and `Upgrade` headers of upgrade
+    /// request are kept. Request with duplicate `Content-Type` or `Host` headers or `Content-Length`
+    /// headers with differing values is rejected with `400 Bad Request` response.
+    ///
+    /// [RawRequestHead](crate::http::RawRequestHead) is not affected by normalization.
+    pub fn disable_request_header_normalization(mut self) -> Self {
+        self.normalize_request_headers = false;
         self
     }
 
+    /// Define how request target of Http/1 request is validated and normalized before it's
+    /// parsed into request uri. Request with invalid target is rejected with `400 Bad Request`
+    /// response.
+    ///
+    /// Normalized path prevents `/admin/../public` from being routed differently by service and
+    /// a proxy in front of it. See [UriNormalization] for detail.
+    ///
+    /// Default to [UriNormalization::Standard].
+    pub fn uri_normalization(mut self, normalization: UriNormalization) -> Self {
+        self.uri_normalization = normalization;
+        self
+    }
+
+    /// Define the minimum rate in bytes per second a client must read http/1 response at.
+    ///
+    /// Rate is measured by bytes actually written to socket in every second while response data is
+    /// pending to be written. Measuring starts after `grace_period` elapsed since response head is
+    /// written. Connection of client reading slower than given rate is dropped. This mitigates
+    /// slow read attack where a client pins response body and buffers by reading very slowly.
+    ///
+    /// Disabled by default. Http/2 and Http/3 connections and io-uring based http/1 are not
+    /// affected by this setting.
+    pub fn min_write_rate(mut self, bytes_per_sec: u64, grace_period: Duration) -> Self {
+        self.min_write_rate = Some((bytes_per_sec, grace_period));
+        self
+    }
+
+    /// Set the max number of in-flight requests per http/2 connection. A request is in-flight until
+    /// it's response body is fully sent. New stream exceeding the limit is refused according to
+    /// given [H2Refusal] strategy without calling service.
+    ///
+    /// The limit is enforced by server regardless of the `SETTINGS_MAX_CONCURRENT_STREAMS` value
+    /// advertised to client. Unlimited by default.
+    pub fn h2_max_concurrent_requests(mut self, limit: usize, refusal: H2Refusal) -> Self {
+        self.h2_max_concurrent_requests = Some((limit, refusal));
+        self
+    }
+
+    /// Set observer function called when a http/2 stream is refused by
+    /// [HttpServiceConfig::h2_max_concurrent_requests]. It receives the address of peer and the
+    /// total count of refused streams of the connection.
+    pub fn h2_refused_observer(mut self, observer: fn(SocketAddr, usize)) -> Self {
+        self.h2_refused_observer = Some(observer);
+        self
+    }
+
+    /// Set observer function called on [ConnectionEvent] of connections served by
+    /// [HttpService](crate::HttpService). It receives the address of peer and the event.
+    ///
+    /// Observer is called on the worker thread serving the connection and it should be cheap.
+    pub fn connection_observer(mut self, observer: fn(SocketAddr, ConnectionEvent)) -> Self {
+        self.connection_observer = Some(observer);
+        self
+    }
+
+    /// Set the max bytes of request body data a http/2 connection allows peer to send on top of
+    /// initial flow control windows of it's streams.
+    ///
+    /// The budget is shared by all streams of connection. When it's exhausted flow control
+    /// capacity of body data read by service is not released to peer until other streams read
+    /// their buffered data, which throttles peer from sending more. Unlimited by default.
+    pub fn h2_connection_body_budget(mut self, bytes: usize) -> Self {
+        self.h2_connection_body_budget = Some(bytes);
+        self
+    }
+
+    /// Set the max size of http/2 header list in bytes. It's advertised to client as
+    /// `SETTINGS_MAX_HEADER_LIST_SIZE` and size of header is counted as the length of it's name
+    /// and value plus 32 bytes of overhead. (RFC 9113 section 6.5.2)
+    ///
+    /// Request with larger header block is answered with `431 Request Header Fields Too Large`
+    /// on it's stream without calling service and other streams of the connection are not
+    /// affected. CONTINUATION frames of a header block are buffered up to a number bounded by
+    /// the limit and peer sending more than that is treated as connection error. Response with
+    /// larger header list is discarded and an error naming the offending header is logged. A
+    /// `500 Internal Server Error` is sent on it's stream instead.
+    ///
+    /// Default to 16MB for request and unlimited for response.
+    pub fn h2_max_header_list_size(mut self, size: usize) -> Self {
+        self.h2_max_header_list_size = Some(size);
+        self
+    }
+
+    /// Set the max number of connections served at the same time by one instance of
+    /// [HttpService](crate::HttpService). Server runs an instance on every worker thread.
+    ///
+    /// The limit is part of service's readiness. When it's reached server stops accepting from
+    /// listener and resumes after a connection is finished. Pending connections are left in the
+    /// listen backlog of OS. Unlimited by default. io-uring based services are not affected by
+    /// this setting.
+    ///
+    /// # Panics:
+    ///
+    /// When limit is zero.
+    pub fn max_connections(mut self, limit: usize) -> Self {
+        assert!(limit > 0, "max_connections must be greater than zero");
+        self.max_connections = Some(limit);
+        self
+    }
+
+    /// Set the max number of requests per second a http/1 connection can send. Requests exceeding
+    /// the limit are handled according to given [RequestRatePolicy].
+    ///
+    /// Requests are counted in one second windows measured with the clock of date service. This
+    /// mitigates clients sending large amount of cheap pipelined requests on a few connections.
+    /// Unlimited by default. Http/2 and Http/3 connections and io-uring based http/1 are not
+    /// affected by this setting.
+    ///
+    /// # Panics:
+    ///
+    /// When limit is zero.
+    pub fn max_requests_per_second(mut self, limit: u32, policy: RequestRatePolicy) -> Self {
+        assert!(limit > 0, "max_requests_per_second must be greater than zero");
+        self.max_requests_per_second = Some((limit, policy));
+        self
+    }
+
+    /// Set the threshold of a service call being considered stuck. The call is measured from the
+    /// request being dispatched to service until response head is produced.
+    ///
+    /// A stuck call is reported with a warn level [tracing] event carrying connection id, method
+    /// and path of request, and a [ConnectionEvent::StuckRequest] is sent to
+    /// [HttpServiceConfig::connection_observer]. It's then handled according to given
+    /// [StuckRequestAction].
+    ///
+    /// Threshold is expected to be much larger than other timeouts. (e.g. minutes) The detection is
+    /// coarse and a stuck call can be reported later than the threshold by a fraction of it.
+    /// Disabled by default. Http/3 connections and io-uring based http/1 are not affected by this
+    /// setting.
+    ///
+    /// # Panics:
+    ///
+    /// When threshold is zero.
+    pub fn stuck_request_threshold(mut self, threshold: Duration, action: StuckRequestAction) -> Self {
+        assert!(
+            !threshold.is_zero(),
+            "stuck_request_threshold must be greater than zero"
+        );
+        self.stuck_request_threshold = Some((threshold, action));
+        self
+    }
+
+    // scheme of request uri for connections served with this config.
+    #[cfg(any(feature = "http1", feature = "http2"))]
+    pub(crate) fn scheme(&self) -> crate::http::uri::Scheme {
+        use crate::http::uri::Scheme;
+
+        if self.tls {
+            Scheme::HTTPS
+        } else {
+            Scheme::HTTP
+        }
+    }
+
     #[doc(hidden)]
     /// A shortcut for mutating const generic params.
     pub fn mutate_const_generic<
@@ -136,11 +634,34 @@ impl<const HEADER_LIMIT: usize, const READ_BUF_LIMIT: usize, const WRITE_BUF_LIM
         self,
     ) -> HttpServiceConfig<HEADER_LIMIT2, READ_BUF_LIMIT2, WRITE_BUF_LIMIT2> {
         HttpServiceConfig {
-            vectored_write: self.vectored_write,
+            write_buf_strategy: self.write_buf_strategy,
+            flush_policy: self.flush_policy,
             keep_alive_timeout: self.keep_alive_timeout,
+            keep_alive_granularity: self.keep_alive_granularity,
             request_head_timeout: self.request_head_timeout,
             tls_accept_timeout: self.tls_accept_timeout,
-            peek_protocol: self.peek_protocol,
+            raw_request_head: self.raw_request_head,
+            request_scratch: self.request_scratch,
+            request_arrival: self.request_arrival,
+            informational_responses: self.informational_responses,
+            cache_request_head: self.cache_request_head,
+            max_request_body_size: self.max_request_body_size,
+            max_uri_length: self.max_uri_length,
+            max_response_head_size: self.max_response_head_size,
+            max_coalesce_body_size: self.max_coalesce_body_size,
+            preserve_header_case: self.preserve_header_case,
+            normalize_request_headers: self.normalize_request_headers,
+            uri_normalization: self.uri_normalization,
+            min_write_rate: self.min_write_rate,
+            h2_max_concurrent_requests: self.h2_max_concurrent_requests,
+            h2_refused_observer: self.h2_refused_observer,
+            connection_observer: self.connection_observer,
+            h2_connection_body_budget: self.h2_connection_body_budget,
+            h2_max_header_list_size: self.h2_max_header_list_size,
+            max_connections: self.max_connections,
+            max_requests_per_second: self.max_requests_per_second,
+            stuck_request_threshold: self.stuck_request_threshold,
+            tls: self.tls,
         }
     }
 }
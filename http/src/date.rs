@@ -2,7 +2,7 @@ use std::{
     cell::RefCell,
     fmt::{self, Write},
     ops::Deref,
-    rc::Rc,
+    rc::{Rc, Weak},
     time::{Duration, SystemTime},
 };
 
@@ -12,6 +12,8 @@ use tokio::{
     time::{interval, Instant},
 };
 
+use crate::http::header::HeaderValue;
+
 /// Trait for getting current date/time.
 ///
 /// This is usually used by a low resolution of timer to reduce frequent syscall to OS.
@@ -24,22 +26,51 @@ pub trait DateTime {
     where
         F: FnOnce(&[u8]) -> O;
 
+    /// Current time at the resolution of the timer. It's cheap and suitable for deadlines where
+    /// precision does not matter.
     fn now(&self) -> Instant;
+
+    /// Current time read from OS on every call. It's used for measuring duration where the
+    /// resolution of [DateTime::now] is too coarse.
+    #[inline]
+    fn now_precise(&self) -> Instant {
+        Instant::now()
+    }
+
+    /// [HeaderValue] representation of [HttpDate].
+    ///
+    /// Default implementation constructs a new value from [DateTime::with_date] on every call.
+    fn header_value(&self) -> HeaderValue {
+        self.with_date(HeaderValue::from_bytes)
+            .expect("HttpDate must be valid HeaderValue")
+    }
 }
 
 /// Struct with Date update periodically at 500 milli seconds interval.
+///
+/// Cloning the service shares the same date and timer.
+#[derive(Clone)]
 pub struct DateTimeService {
+    timer: Rc<Timer>,
+}
+
+struct Timer {
     state: Rc<RefCell<DateTimeState>>,
     handle: JoinHandle<()>,
 }
 
-impl Drop for DateTimeService {
+impl Drop for Timer {
     fn drop(&mut self) {
         // stop the timer update async task on drop.
         self.handle.abort();
     }
 }
 
+thread_local! {
+    // date service of current worker thread. see DateTimeService::current for detail.
+    static CURRENT: RefCell<Weak<Timer>> = const { RefCell::new(Weak::new()) };
+}
+
 impl Default for DateTimeService {
     fn default() -> Self {
         Self::new()
@@ -62,12 +93,29 @@ impl DateTimeService {
             }
         });
 
-        Self { state, handle }
+        Self {
+            timer: Rc::new(Timer { state, handle }),
+        }
+    }
+
+    /// Get the date service of current worker thread.
+    ///
+    /// Http services and middlewares running on the same worker share one date and timer through
+    /// this method. A new service is started when there is none alive on current thread.
+    pub fn current() -> Self {
+        CURRENT.with(|current| {
+            if let Some(timer) = current.borrow().upgrade() {
+                return Self { timer };
+            }
+            let this = Self::new();
+            *current.borrow_mut() = Rc::downgrade(&this.timer);
+            this
+        })
     }
 
     #[inline]
     pub fn get(&self) -> &DateTimeHandle {
-        self.state.deref()
+        self.timer.state.deref()
     }
 }
 
@@ -77,10 +125,13 @@ pub(crate) type DateTimeHandle = RefCell<DateTimeState>;
 pub const DATE_VALUE_LENGTH: usize = 29;
 
 /// struct contains byte representation of [HttpDate] and [Instant].
-#[derive(Copy, Clone)]
+#[derive(Clone)]
 pub struct DateTimeState {
     pub date: [u8; DATE_VALUE_LENGTH],
     pub now: Instant,
+    // cached date value shared by all responses until next update. it's constructed once per
+    // update of date and cloning it is a reference count increment.
+    value: HeaderValue,
 }
 
 impl Default for DateTimeState {
@@ -94,8 +145,10 @@ impl DateTimeState {
         let mut date = Self {
             date: [0; DATE_VALUE_LENGTH],
             now: Instant::now(),
+            value: HeaderValue::from_static(""),
         };
         let _ = write!(date, "{}", HttpDate::from(SystemTime::now()));
+        date.value = HeaderValue::from_bytes(&date.date).expect("HttpDate must be valid HeaderValue");
         date
     }
 }
@@ -125,4 +178,31 @@ impl DateTime for DateTimeHandle {
     fn now(&self) -> Instant {
         self.borrow().now
     }
+
+    #[inline]
+    fn header_value(&self) -> HeaderValue {
+        self.borrow().value.clone()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[tokio::test]
+    async fn current_shared() {
+        tokio::task::LocalSet::new()
+            .run_until(async {
+                let date = DateTimeService::current();
+                let date2 = DateTimeService::current();
+                assert!(core::ptr::eq(date.get(), date2.get()));
+
+                // a new date service is started after all handles of current one are dropped.
+                drop((date, date2));
+                let date = DateTimeService::current();
+                assert!(!date.timer.handle.is_finished());
+                assert!(!core::ptr::eq(date.get(), DateTimeService::new().get()));
+            })
+            .await
+    }
 }
@@ -86,27 +86,147 @@ impl<S, B> From<Infallible> for HttpServiceError<S, B> {
     }
 }
 
+/// Error of response body yielding a different amount of bytes than it's exact size hint.
+///
+/// The size is written to response head as `Content-Length` before body is sent. When body
+/// yields more bytes the excess is not sent and when it ends early the connection(or http/2
+/// stream) is closed without completing the body so client can tell the response is malformed.
+#[derive(Debug)]
+pub struct BodySizeMismatch {
+    expected: u64,
+    actual: u64,
+}
+
+impl BodySizeMismatch {
+    pub(crate) const fn new(expected: u64, actual: u64) -> Self {
+        Self { expected, actual }
+    }
+
+    /// Size of body written to response head.
+    pub fn expected(&self) -> u64 {
+        self.expected
+    }
+
+    /// Bytes yielded by body. For body yielding more bytes than expected it's the count when the
+    /// excess is observed.
+    pub fn actual(&self) -> u64 {
+        self.actual
+    }
+}
+
+impl Display for BodySizeMismatch {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.actual > self.expected {
+            write!(f, "response body exceeded it's size of {} bytes", self.expected)
+        } else {
+            write!(
+                f,
+                "response body ended after {} bytes of expected {} bytes",
+                self.actual, self.expected
+            )
+        }
+    }
+}
+
+impl Error for BodySizeMismatch {}
+
 /// Default Request/Response body error.
 #[derive(Debug)]
-pub struct BodyError(Box<dyn Error + Send + Sync>);
+pub enum BodyError {
+    /// Io error from reading body.
+    Io(io::Error),
+    /// Malformed body framing. e.g. invalid chunked transfer encoding.
+    Parse(Box<dyn Error + Send + Sync>),
+    /// Body exceeded size limit in bytes.
+    Overflow { limit: usize },
+    /// Peer disconnected or reset the stream before body is fully received.
+    Disconnected,
+    /// Connection ended before the size declared by body framing is received. `expected` is the
+    /// declared Content-Length and is `None` for chunked body missing it's terminal chunk.
+    IncompleteBody { expected: Option<u64>, received: u64 },
+    /// Catch-all variant for arbitrary error. Mostly from user provided body streams.
+    Boxed(Box<dyn Error + Send + Sync>),
+}
+
+impl BodyError {
+    /// Check if error is caused by peer disconnecting. This is usually an expected outcome of
+    /// client going away and not worth logging as error.
+    pub fn is_disconnect(&self) -> bool {
+        matches!(self, Self::Disconnected)
+    }
+
+    /// Check if error is caused by malformed body framing from peer.
+    pub fn is_parse(&self) -> bool {
+        matches!(self, Self::Parse(_))
+    }
+
+    /// Check if error is caused by body exceeding size limit.
+    pub fn is_overflow(&self) -> bool {
+        matches!(self, Self::Overflow { .. })
+    }
+
+    /// Check if error is caused by body ending before it's declared size. The received part of
+    /// body is a truncated prefix and must not be treated as complete.
+    pub fn is_incomplete(&self) -> bool {
+        matches!(self, Self::IncompleteBody { .. })
+    }
+}
 
 impl Display for BodyError {
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        Display::fmt(&self.0, f)
+        match *self {
+            Self::Io(ref e) => Display::fmt(e, f),
+            Self::Parse(ref e) => Display::fmt(e, f),
+            Self::Overflow { limit } => write!(f, "body exceeded size limit of {limit} bytes"),
+            Self::Disconnected => f.write_str("peer disconnected before body is fully received"),
+            Self::IncompleteBody {
+                expected: Some(expected),
+                received,
+            } => write!(f, "body ended after {received} bytes of expected {expected} bytes"),
+            Self::IncompleteBody {
+                expected: None,
+                received,
+            } => {
+                write!(f, "chunked body ended after {received} bytes without terminal chunk")
+            }
+            Self::Boxed(ref e) => Display::fmt(e, f),
+        }
     }
 }
 
-impl Error for BodyError {}
+impl Error for BodyError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match *self {
+            Self::Io(ref e) => Some(e),
+            Self::Parse(ref e) | Self::Boxed(ref e) => Some(&**e),
+            Self::Overflow { .. } | Self::Disconnected | Self::IncompleteBody { .. } => None,
+        }
+    }
+}
 
 impl From<io::Error> for BodyError {
     fn from(e: io::Error) -> Self {
-        Self(Box::new(e))
+        Self::Io(e)
+    }
+}
+
+// BodyError is always kept as inner error so it can be retrieved with io::Error::get_ref and
+// downcast. io error kind is forwarded when possible.
+impl From<BodyError> for io::Error {
+    fn from(e: BodyError) -> Self {
+        let kind = match e {
+            BodyError::Io(ref e) => e.kind(),
+            BodyError::Parse(_) | BodyError::Overflow { .. } => io::ErrorKind::InvalidData,
+            BodyError::Disconnected | BodyError::IncompleteBody { .. } => io::ErrorKind::UnexpectedEof,
+            BodyError::Boxed(_) => io::ErrorKind::Other,
+        };
+        io::Error::new(kind, e)
     }
 }
 
 impl From<Box<dyn Error + Send + Sync>> for BodyError {
     fn from(e: Box<dyn Error + Send + Sync>) -> Self {
-        Self(e)
+        Self::Boxed(e)
     }
 }
 
@@ -10,7 +10,9 @@ use std::{collections::VecDeque, io, rc::Rc};
 
 use futures_core::stream::Stream;
 
-use crate::bytes::Bytes;
+use crate::{bytes::Bytes, error::BodyError};
+
+use super::proto::codec::TransferCoding;
 
 /// max buffer size 32k
 pub(crate) const MAX_BUFFER_SIZE: usize = 32_768;
@@ -58,9 +60,9 @@ impl RequestBody {
 }
 
 impl Stream for RequestBody {
-    type Item = io::Result<Bytes>;
+    type Item = Result<Bytes, BodyError>;
 
-    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<io::Result<Bytes>>> {
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
         match self.get_mut().0 {
             RequestBodyInner::Some(ref mut inner) => inner.borrow_mut().poll_next_unpin(cx),
             RequestBodyInner::None => Poll::Ready(None),
@@ -76,6 +78,33 @@ impl From<RequestBody> for crate::body::RequestBody {
     }
 }
 
+// classify io error from reading connection. peer closing or resetting connection is treated as
+// disconnect.
+pub(super) fn read_error(e: io::Error) -> BodyError {
+    match e.kind() {
+        io::ErrorKind::ConnectionReset | io::ErrorKind::ConnectionAborted | io::ErrorKind::UnexpectedEof => {
+            BodyError::Disconnected
+        }
+        _ => BodyError::Io(e),
+    }
+}
+
+// error for connection ending before body is fully received. sized and chunked body framing tells
+// the body is truncated. other decoders end with connection so it's a plain disconnect.
+pub(super) fn incomplete_error(decoder: &TransferCoding, received: u64) -> BodyError {
+    match *decoder {
+        TransferCoding::Length(rem) => BodyError::IncompleteBody {
+            expected: Some(received + rem),
+            received,
+        },
+        TransferCoding::DecodeChunked(..) => BodyError::IncompleteBody {
+            expected: None,
+            received,
+        },
+        _ => BodyError::Disconnected,
+    }
+}
+
 /// Sender part of the payload stream
 pub struct RequestBodySender(RequestBodyInner);
 
@@ -83,8 +112,9 @@ pub struct RequestBodySender(RequestBodyInner);
 impl Drop for RequestBodySender {
     fn drop(&mut self) {
         if let Some(mut inner) = self.try_inner() {
-            if !inner.eof {
-                inner.feed_error(io::ErrorKind::UnexpectedEof.into());
+            // keep error fed before dropping as it's more specific.
+            if !inner.eof && inner.err.is_none() {
+                inner.feed_error(BodyError::Disconnected);
             }
         }
     }
@@ -121,7 +151,7 @@ impl RequestBodySender {
         }
     }
 
-    pub(super) fn feed_error(&mut self, e: io::Error) {
+    pub(super) fn feed_error(&mut self, e: BodyError) {
         if let Some(mut inner) = self.try_inner_infallible() {
             inner.feed_error(e);
         }
@@ -177,7 +207,7 @@ impl RequestBodySender {
 struct Inner {
     eof: bool,
     len: usize,
-    err: Option<io::Error>,
+    err: Option<BodyError>,
     items: VecDeque<Bytes>,
     task: Option<Waker>,
     io_task: Option<Waker>,
@@ -219,7 +249,7 @@ impl Inner {
         }
     }
 
-    fn feed_error(&mut self, err: io::Error) {
+    fn feed_error(&mut self, err: BodyError) {
         self.err = Some(err);
         self.wake();
     }
@@ -239,7 +269,7 @@ impl Inner {
         self.len >= MAX_BUFFER_SIZE
     }
 
-    fn poll_next_unpin(&mut self, cx: &mut Context<'_>) -> Poll<Option<io::Result<Bytes>>> {
+    fn poll_next_unpin(&mut self, cx: &mut Context<'_>) -> Poll<Option<Result<Bytes, BodyError>>> {
         if let Some(data) = self.items.pop_front() {
             self.len -= data.len();
             Poll::Ready(Some(Ok(data)))
@@ -254,3 +284,34 @@ impl Inner {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use super::*;
+
+    fn next(body: &mut RequestBody) -> Option<Result<Bytes, BodyError>> {
+        poll_fn(|cx| Pin::new(&mut *body).poll_next(cx)).now_or_panic()
+    }
+
+    #[test]
+    fn sender_drop_disconnect() {
+        let (mut tx, mut body) = RequestBody::channel(false);
+        tx.feed_data(Bytes::from_static(b"996"));
+        drop(tx);
+
+        assert_eq!(next(&mut body).unwrap().unwrap().as_ref(), b"996");
+        assert!(next(&mut body).unwrap().unwrap_err().is_disconnect());
+    }
+
+    #[test]
+    fn read_error_kind() {
+        assert!(read_error(io::ErrorKind::UnexpectedEof.into()).is_disconnect());
+        assert!(read_error(io::ErrorKind::ConnectionReset.into()).is_disconnect());
+        assert!(matches!(
+            read_error(io::ErrorKind::PermissionDenied.into()),
+            BodyError::Io(_)
+        ));
+    }
+}
@@ -5,6 +5,7 @@ use xitca_service::Service;
 use crate::{
     builder::{marker, HttpServiceBuilder},
     error::BuildError,
+    util::drain::Drain,
 };
 
 use super::service::H1Service;
@@ -33,6 +34,7 @@ impl<St, F, FA, const HEADER_LIMIT: usize, const READ_BUF_LIMIT: usize, const WR
             factory: self.factory,
             tls_factory: self.tls_factory,
             config: self.config,
+            drain: self.drain,
             _body: std::marker::PhantomData,
         }
     }
@@ -57,6 +59,7 @@ impl<St, F, FA, const HEADER_LIMIT: usize, const READ_BUF_LIMIT: usize, const WR
             factory: self.factory,
             tls_factory: self.tls_factory,
             config: self.config,
+            drain: self.drain,
             _body: std::marker::PhantomData,
         }
     }
@@ -79,7 +82,9 @@ where
         async {
             let tls_acceptor = self.tls_factory.call(()).await.map_err(BuildError::First)?;
             let service = self.factory.call(arg).await.map_err(BuildError::Second)?;
-            Ok(H1Service::new(self.config, service, tls_acceptor))
+            let mut service = H1Service::new(self.config, service, tls_acceptor);
+            service.drain = self.drain.as_ref().map(Drain::watch);
+            Ok(service)
         }
     }
 }
@@ -103,7 +108,9 @@ where
         async {
             let tls_acceptor = self.tls_factory.call(()).await.map_err(BuildError::First)?;
             let service = self.factory.call(arg).await.map_err(BuildError::Second)?;
-            Ok(super::service::H1UringService::new(self.config, service, tls_acceptor))
+            let mut service = super::service::H1UringService::new(self.config, service, tls_acceptor);
+            service.drain = self.drain.as_ref().map(Drain::watch);
+            Ok(service)
         }
     }
 }
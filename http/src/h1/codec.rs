@@ -0,0 +1,505 @@
+//! Sans-io http/1 server codec.
+//!
+//! [RequestDecoder] and [ResponseEncoder] expose the same parsing and framing logic used by the
+//! http/1 dispatcher without any io, timer or [Service](xitca_service::Service) involved. They
+//! are meant to be embedded in a custom event loop that owns the socket and it's buffers.
+//!
+//! # Examples:
+//! ```rust
+//! use xitca_http::{
+//!     body::BodySize,
+//!     bytes::{Bytes, BytesMut},
+//!     date::DateTimeService,
+//!     h1::codec::{RequestDecoder, RequestEvent, ResponseEncoder},
+//!     http::Response,
+//! };
+//!
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() {
+//! # tokio::task::LocalSet::new().run_until(async {
+//! let date = DateTimeService::new();
+//! let mut decoder = RequestDecoder::<64>::new();
+//! let mut encoder = ResponseEncoder::new(date.get());
+//!
+//! let mut read_buf = BytesMut::from(&b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n"[..]);
+//! let mut write_buf = BytesMut::new();
+//!
+//! while let Some(event) = decoder.decode(&mut read_buf).unwrap() {
+//!     match event {
+//!         RequestEvent::Head(req) => assert_eq!(req.uri().path(), "/"),
+//!         RequestEvent::BodyChunk(_) => {}
+//!         RequestEvent::BodyEnd => {
+//!             let (parts, _) = Response::new(()).into_parts();
+//!             encoder.encode_head(&decoder, parts, BodySize::Sized(5), &mut write_buf).unwrap();
+//!             encoder.encode_body(Bytes::from_static(b"hello"), &mut write_buf);
+//!             encoder.encode_eof(&mut write_buf).unwrap();
+//!         }
+//!     }
+//! }
+//!
+//! assert!(write_buf.starts_with(b"HTTP/1.1 200 OK\r\n"));
+//! assert!(write_buf.ends_with(b"\r\n\r\nhello"));
+//! assert!(encoder.is_keep_alive());
+//! # }).await
+//! # }
+//! ```
+
+use std::{io, net::SocketAddr};
+
+use crate::{
+    body::BodySize,
+    bytes::{Bytes, BytesMut},
+    config::{UriNormalization, DEFAULT_HEADER_LIMIT, DEFAULT_READ_BUF_LIMIT},
+    date::DateTime,
+    http::{response::Parts, uri::Scheme, Request, RequestExt},
+};
+
+use super::proto::{
+    codec::{ChunkResult, TransferCoding},
+    context::Context,
+    encode::{encode_continue, SizeHint},
+    error::ProtoError,
+};
+
+/// Event produced by [RequestDecoder::decode].
+#[derive(Debug)]
+pub enum RequestEvent {
+    /// Head of a new request. It's always followed by zero or more [RequestEvent::BodyChunk] and
+    /// one [RequestEvent::BodyEnd], including request without body.
+    Head(Request<RequestExt<()>>),
+    /// Chunk of request body with transfer coding removed.
+    BodyChunk(Bytes),
+    /// End of request body. Next event would be head of the next request.
+    BodyEnd,
+}
+
+/// Error type of [RequestDecoder::decode].
+#[derive(Debug)]
+pub enum DecodeError {
+    /// Malformed request head.
+    Proto(ProtoError),
+    /// Malformed request body.
+    Body(io::Error),
+}
+
+impl From<ProtoError> for DecodeError {
+    fn from(e: ProtoError) -> Self {
+        Self::Proto(e)
+    }
+}
+
+/// Stateful decoder of http/1 requests on a single connection.
+///
+/// `HEADER_LIMIT` is the max number of headers a request can have. `READ_BUF_LIMIT` is the max
+/// size in bytes of a request head.
+pub struct RequestDecoder<
+    const HEADER_LIMIT: usize = DEFAULT_HEADER_LIMIT,
+    const READ_BUF_LIMIT: usize = DEFAULT_READ_BUF_LIMIT,
+> {
+    ctx: Context<'static, (), HEADER_LIMIT>,
+    body: Option<TransferCoding>,
+    upgrade: bool,
+}
+
+impl<const HEADER_LIMIT: usize, const READ_BUF_LIMIT: usize> Default for RequestDecoder<HEADER_LIMIT, READ_BUF_LIMIT> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const HEADER_LIMIT: usize, const READ_BUF_LIMIT: usize> RequestDecoder<HEADER_LIMIT, READ_BUF_LIMIT> {
+    /// Construct a new decoder.
+    pub fn new() -> Self {
+        Self::with_addr(crate::unspecified_socket_addr())
+    }
+
+    /// Construct a new decoder with [SocketAddr] of remote peer. The address is attached to
+    /// every decoded request.
+    pub fn with_addr(addr: SocketAddr) -> Self {
+        Self {
+            ctx: Context::with_addr(addr, &()),
+            body: None,
+            upgrade: false,
+        }
+    }
+
+    /// Set max length of request target in bytes.
+    ///
+    /// Default to [DEFAULT_MAX_URI_LENGTH](crate::config::DEFAULT_MAX_URI_LENGTH).
+    pub fn max_uri_length(&mut self, len: usize) {
+        self.ctx.max_uri_length(len);
+    }
+
+    /// Set validation and normalization of request target.
+    ///
+    /// Default to [UriNormalization::Disable].
+    pub fn uri_normalization(&mut self, normalization: UriNormalization) {
+        self.ctx.set_uri_normalization(normalization);
+    }
+
+    /// Set scheme used to complete request target in origin-form into an absolute uri.
+    ///
+    /// Default to [Scheme::HTTP].
+    pub fn set_scheme(&mut self, scheme: Scheme) {
+        self.ctx.set_scheme(scheme);
+    }
+
+    /// Enable storing raw request head bytes as [RawRequestHead](crate::http::RawRequestHead) in
+    /// request extensions.
+    pub fn keep_raw_head(&mut self) {
+        self.ctx.keep_raw_head();
+    }
+
+    /// Decode the next event from given buffer. Decoded bytes are split off from the buffer.
+    ///
+    /// `Ok(None)` means more bytes are needed. Buffer must be filled and method called again.
+    pub fn decode(&mut self, buf: &mut BytesMut) -> Result<Option<RequestEvent>, DecodeError> {
+        match self.body {
+            Some(ref mut coding) => match coding.decode(buf) {
+                ChunkResult::Ok(bytes) => Ok(Some(RequestEvent::BodyChunk(bytes))),
+                ChunkResult::InsufficientData => Ok(None),
+                ChunkResult::OnEof | ChunkResult::AlreadyEof => {
+                    self.body = None;
+                    Ok(Some(RequestEvent::BodyEnd))
+                }
+                ChunkResult::Err(e) => {
+                    coding.set_corrupted();
+                    Err(DecodeError::Body(e))
+                }
+                ChunkResult::Corrupted => Err(DecodeError::Body(io::ErrorKind::InvalidInput.into())),
+            },
+            None => match self.ctx.decode_head::<READ_BUF_LIMIT>(buf)? {
+                Some((req, coding)) => {
+                    self.upgrade = coding.is_upgrade();
+                    self.body = Some(coding);
+                    Ok(Some(RequestEvent::Head(req)))
+                }
+                None => Ok(None),
+            },
+        }
+    }
+
+    /// Return true when body of current request is not fully decoded.
+    pub fn is_body_pending(&self) -> bool {
+        self.body.is_some()
+    }
+
+    /// Return true when current request expects a `100 Continue` response before sending it's
+    /// body. See [ResponseEncoder::encode_continue].
+    pub fn is_expect_continue(&self) -> bool {
+        self.ctx.is_expect_header()
+    }
+
+    /// Return true when current request is an upgrade or CONNECT request. Body of such request
+    /// never ends and every following byte on connection is yielded as
+    /// [RequestEvent::BodyChunk].
+    pub fn is_upgrade(&self) -> bool {
+        self.upgrade
+    }
+
+    /// Return true when connection can be kept alive after current request according to it's
+    /// version and `Connection` header.
+    pub fn is_keep_alive(&self) -> bool {
+        !self.ctx.is_connection_closed()
+    }
+}
+
+/// Encoder of http/1 responses on a single connection.
+///
+/// Response must be encoded after head of it's request is decoded by [RequestDecoder] as method
+/// and connection state of request affects response framing.
+pub struct ResponseEncoder<'a, D> {
+    ctx: Context<'a, D, DEFAULT_HEADER_LIMIT>,
+    body: TransferCoding,
+}
+
+impl<'a, D> ResponseEncoder<'a, D>
+where
+    D: DateTime,
+{
+    /// Construct a new encoder with reference of certain type that impl [DateTime] trait. It's
+    /// used to write `Date` header when response does not have one.
+    pub fn new(date: &'a D) -> Self {
+        Self {
+            ctx: Context::new(date),
+            body: TransferCoding::eof(),
+        }
+    }
+
+    /// Enable writing response header names with casing from
+    /// [HeaderCaseMap](crate::http::HeaderCaseMap) in response extensions or title case.
+    pub fn preserve_header_case(&mut self) {
+        self.ctx.preserve_header_case();
+    }
+
+    /// Set max size of encoded response head in bytes.
+    ///
+    /// Default to no limit.
+    pub fn max_response_head_size(&mut self, size: usize) {
+        self.ctx.max_response_head_size(size);
+    }
+
+    /// Write `100 Continue` response.
+    pub fn encode_continue(&mut self, buf: &mut BytesMut) {
+        encode_continue(buf);
+    }
+
+    /// Encode response head for current request of given decoder. `size` decides the framing of
+    /// body that is encoded by following [ResponseEncoder::encode_body] calls.
+    ///
+    /// On error nothing is written to buffer.
+    pub fn encode_head<const HEADER_LIMIT: usize, const READ_BUF_LIMIT: usize>(
+        &mut self,
+        decoder: &RequestDecoder<HEADER_LIMIT, READ_BUF_LIMIT>,
+        parts: Parts,
+        size: BodySize,
+        buf: &mut BytesMut,
+    ) -> Result<(), ProtoError> {
+        let req = &decoder.ctx;
+        self.ctx.reset();
+        if req.is_head_method() {
+            self.ctx.set_head_method();
+        }
+        if req.is_connect_method() {
+            self.ctx.set_connect_method();
+        }
+        if req.is_connection_closed() {
+            self.ctx.set_close();
+        }
+
+        self.body = self.ctx.encode_head(parts, &SizeHint(size), buf)?;
+        Ok(())
+    }
+
+    /// Encode a chunk of response body. Bytes exceeding `Content-Length` of response are dropped.
+    ///
+    /// Body of response to `HEAD` request or with `204`/`304` status code is never written.
+    pub fn encode_body(&mut self, bytes: Bytes, buf: &mut BytesMut) {
+        self.body.encode(bytes, buf);
+    }
+
+    /// Encode the end of response body.
+    ///
+    /// Error when fewer bytes than `Content-Length` of response are encoded. The response can not be
+    /// completed and connection must be closed.
+    pub fn encode_eof(&mut self, buf: &mut BytesMut) -> Result<(), ProtoError> {
+        self.body.encode_eof(buf)?;
+        if !self.body.is_upgrade() {
+            self.body.set_eof();
+        }
+        Ok(())
+    }
+
+    /// Return true when response switches connection to other protocol. Bytes of new protocol are
+    /// passed through [ResponseEncoder::encode_body] as is.
+    pub fn is_upgrade(&self) -> bool {
+        self.body.is_upgrade()
+    }
+
+    /// Return true when connection can be kept alive after last encoded response. It's decided by
+    /// request and `Connection` header of response together.
+    pub fn is_keep_alive(&self) -> bool {
+        !self.ctx.is_connection_closed()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        date::DateTimeService,
+        http::{
+            header::{CONNECTION, UPGRADE},
+            HeaderValue, Method, Response, StatusCode,
+        },
+    };
+
+    use super::*;
+
+    fn decode_all<const H: usize, const R: usize>(
+        decoder: &mut RequestDecoder<H, R>,
+        buf: &mut BytesMut,
+    ) -> Vec<RequestEvent> {
+        let mut events = Vec::new();
+        while let Some(event) = decoder.decode(buf).unwrap() {
+            events.push(event);
+        }
+        events
+    }
+
+    fn parse_response(buf: &[u8]) -> (u16, Vec<(String, String)>, usize) {
+        let mut headers = [httparse::EMPTY_HEADER; 16];
+        let mut res = httparse::Response::new(&mut headers);
+        let len = match res.parse(buf).unwrap() {
+            httparse::Status::Complete(len) => len,
+            httparse::Status::Partial => panic!("partial response head"),
+        };
+        let headers = res
+            .headers
+            .iter()
+            .map(|h| (h.name.to_owned(), String::from_utf8(h.value.to_vec()).unwrap()))
+            .collect();
+        (res.code.unwrap(), headers, len)
+    }
+
+    #[tokio::test]
+    async fn round_trip() {
+        tokio::task::LocalSet::new()
+            .run_until(async {
+                let date = DateTimeService::new();
+                let mut decoder = RequestDecoder::<8>::new();
+                let mut encoder = ResponseEncoder::new(date.get());
+
+                let req = b"POST /echo HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n\
+                            5\r\nhello\r\n6\r\n world\r\n0\r\n\r\n";
+
+                // feed request one byte at a time.
+                let mut buf = BytesMut::new();
+                let mut body = BytesMut::new();
+                let mut ended = false;
+                for b in req {
+                    buf.extend_from_slice(&[*b]);
+                    while let Some(event) = decoder.decode(&mut buf).unwrap() {
+                        match event {
+                            RequestEvent::Head(req) => {
+                                assert_eq!(req.method(), Method::POST);
+                                assert_eq!(req.uri().path(), "/echo");
+                            }
+                            RequestEvent::BodyChunk(chunk) => body.extend_from_slice(&chunk),
+                            RequestEvent::BodyEnd => ended = true,
+                        }
+                    }
+                }
+                assert!(ended);
+                assert!(!decoder.is_body_pending());
+                assert_eq!(body, &b"hello world"[..]);
+
+                // echo body back with chunked encoding and decode it with the same chunked decoder.
+                let mut out = BytesMut::new();
+                let (parts, _) = Response::new(()).into_parts();
+                encoder
+                    .encode_head(&decoder, parts, BodySize::Stream, &mut out)
+                    .unwrap();
+                encoder.encode_body(body.split_to(5).freeze(), &mut out);
+                encoder.encode_body(body.freeze(), &mut out);
+                encoder.encode_eof(&mut out).unwrap();
+                assert!(encoder.is_keep_alive());
+
+                let (status, headers, len) = parse_response(&out);
+                assert_eq!(status, 200);
+                assert!(headers.iter().any(|(n, v)| n == "transfer-encoding" && v == "chunked"));
+
+                let mut res_body = out.split_off(len);
+                let mut coding = TransferCoding::decode_chunked();
+                let mut decoded = BytesMut::new();
+                loop {
+                    match coding.decode(&mut res_body) {
+                        ChunkResult::Ok(chunk) => decoded.extend_from_slice(&chunk),
+                        ChunkResult::OnEof => break,
+                        res => panic!("unexpected decode result: {res}"),
+                    }
+                }
+                assert_eq!(decoded, &b"hello world"[..]);
+                assert!(res_body.is_empty());
+            })
+            .await;
+    }
+
+    #[tokio::test]
+    async fn pipelined() {
+        tokio::task::LocalSet::new()
+            .run_until(async {
+                let date = DateTimeService::new();
+                let mut decoder = RequestDecoder::<8>::new();
+                let mut encoder = ResponseEncoder::new(date.get());
+
+                let mut buf = BytesMut::from(
+                    &b"GET /1 HTTP/1.1\r\n\r\n\
+                       HEAD /2 HTTP/1.1\r\nContent-Length: 3\r\n\r\nabc\
+                       GET /3 HTTP/1.0\r\n\r\n"[..],
+                );
+
+                let events = decode_all(&mut decoder, &mut buf);
+                assert_eq!(events.len(), 7);
+                assert!(matches!(events[0], RequestEvent::Head(ref req) if req.uri().path() == "/1"));
+                assert!(matches!(events[1], RequestEvent::BodyEnd));
+                assert!(matches!(events[2], RequestEvent::Head(ref req) if req.method() == Method::HEAD));
+                assert!(matches!(events[3], RequestEvent::BodyChunk(ref b) if b == "abc"));
+                assert!(matches!(events[4], RequestEvent::BodyEnd));
+                assert!(matches!(events[5], RequestEvent::Head(ref req) if req.uri().path() == "/3"));
+                assert!(matches!(events[6], RequestEvent::BodyEnd));
+                assert!(buf.is_empty());
+
+                // http/1.0 request closes connection.
+                assert!(!decoder.is_keep_alive());
+                let mut out = BytesMut::new();
+                let (parts, _) = Response::new(()).into_parts();
+                encoder.encode_head(&decoder, parts, BodySize::None, &mut out).unwrap();
+                encoder.encode_eof(&mut out).unwrap();
+                assert!(!encoder.is_keep_alive());
+                let (_, headers, _) = parse_response(&out);
+                assert!(headers.iter().any(|(n, v)| n == "connection" && v == "close"));
+            })
+            .await;
+    }
+
+    #[tokio::test]
+    async fn keep_alive_and_upgrade() {
+        tokio::task::LocalSet::new()
+            .run_until(async {
+                let date = DateTimeService::new();
+                let mut decoder = RequestDecoder::<8>::new();
+                let mut encoder = ResponseEncoder::new(date.get());
+
+                let mut buf = BytesMut::from(&b"GET / HTTP/1.1\r\n\r\n"[..]);
+                decode_all(&mut decoder, &mut buf);
+                assert!(decoder.is_keep_alive());
+
+                // response can close connection.
+                let mut out = BytesMut::new();
+                let mut res = Response::new(());
+                res.headers_mut().insert(CONNECTION, HeaderValue::from_static("close"));
+                let (parts, _) = res.into_parts();
+                encoder.encode_head(&decoder, parts, BodySize::None, &mut out).unwrap();
+                assert!(!encoder.is_keep_alive());
+
+                let mut buf =
+                    BytesMut::from(&b"GET / HTTP/1.1\r\nConnection: upgrade\r\nUpgrade: websocket\r\n\r\nframe"[..]);
+                let events = decode_all(&mut decoder, &mut buf);
+                assert!(decoder.is_upgrade());
+                assert!(decoder.is_body_pending());
+                assert!(matches!(events[1], RequestEvent::BodyChunk(ref b) if b == "frame"));
+
+                let mut out = BytesMut::new();
+                let mut res = Response::new(());
+                *res.status_mut() = StatusCode::SWITCHING_PROTOCOLS;
+                res.headers_mut()
+                    .insert(CONNECTION, HeaderValue::from_static("upgrade"));
+                res.headers_mut().insert(UPGRADE, HeaderValue::from_static("websocket"));
+                let (parts, _) = res.into_parts();
+                encoder
+                    .encode_head(&decoder, parts, BodySize::Stream, &mut out)
+                    .unwrap();
+                assert!(encoder.is_upgrade());
+                let len = out.len();
+                encoder.encode_body(Bytes::from_static(b"frame"), &mut out);
+                assert_eq!(&out[len..], b"frame");
+            })
+            .await;
+    }
+
+    #[test]
+    fn malformed() {
+        let mut decoder = RequestDecoder::<8>::new();
+        let mut buf = BytesMut::from(&b"GET / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\nzz\r\n"[..]);
+        assert!(matches!(decoder.decode(&mut buf), Ok(Some(RequestEvent::Head(_)))));
+        assert!(matches!(decoder.decode(&mut buf), Err(DecodeError::Body(_))));
+        assert!(matches!(decoder.decode(&mut buf), Err(DecodeError::Body(_))));
+
+        let mut decoder = RequestDecoder::<8, 32>::new();
+        let mut buf = BytesMut::from(&b"GET / HTTP/1.1\r\nx-long-header: 0123456789abcdef"[..]);
+        assert!(matches!(
+            decoder.decode(&mut buf),
+            Err(DecodeError::Proto(ProtoError::HeaderTooLarge))
+        ));
+    }
+}
@@ -0,0 +1,222 @@
+//! raw bytes protocol fixtures shared by all http/1 dispatchers and the sans-io codec. every one of
+//! them must produce the same responses for them.
+
+use core::{future::poll_fn, pin::pin, time::Duration};
+
+use std::{
+    io::{Read, Write},
+    net::{TcpListener, TcpStream},
+    thread::JoinHandle,
+};
+
+use futures_core::Stream;
+use xitca_service::{fn_service, Service};
+
+use crate::{
+    body::{BodySize, ResponseBody},
+    bytes::BytesMut,
+    date::DateTimeService,
+    error::BodyError,
+    http::{Request, RequestExt, Response},
+    HttpServiceBuilder,
+};
+
+use super::{
+    codec::{RequestDecoder, RequestEvent, ResponseEncoder},
+    RequestBody,
+};
+
+struct Fixture {
+    name: &'static str,
+    request: &'static [u8],
+    // parts of response expected to be found in order.
+    response: &'static [&'static str],
+}
+
+const FIXTURES: &[Fixture] = &[
+    Fixture {
+        name: "get",
+        request: b"GET /get HTTP/1.1\r\nHost: a\r\nConnection: close\r\n\r\n",
+        response: &["HTTP/1.1 200 OK\r\n", "\r\n\r\n/get:"],
+    },
+    Fixture {
+        name: "content_length",
+        request: b"POST /post HTTP/1.1\r\nHost: a\r\nContent-Length: 5\r\nConnection: close\r\n\r\nhello",
+        response: &["HTTP/1.1 200 OK\r\n", "\r\n\r\n/post:hello"],
+    },
+    Fixture {
+        name: "chunked_trailers",
+        request: b"POST /chunked HTTP/1.1\r\nHost: a\r\nTransfer-Encoding: chunked\r\n\r\n\
+                   5\r\nhello\r\n6\r\n world\r\n0\r\nX-Unknown: 1\r\nX-Checksum: abc\r\n\r\n\
+                   GET /next HTTP/1.1\r\nHost: a\r\nConnection: close\r\n\r\n",
+        response: &[
+            "HTTP/1.1 200 OK\r\n",
+            "\r\n\r\n/chunked:hello world",
+            "HTTP/1.1 200 OK\r\n",
+            "\r\n\r\n/next:",
+        ],
+    },
+    Fixture {
+        name: "expect_continue",
+        request: b"POST /expect HTTP/1.1\r\nHost: a\r\nExpect: 100-continue\r\nContent-Length: 5\r\n\
+                   Connection: close\r\n\r\nhello",
+        response: &[
+            "HTTP/1.1 100 Continue\r\n\r\n",
+            "HTTP/1.1 200 OK\r\n",
+            "\r\n\r\n/expect:hello",
+        ],
+    },
+    Fixture {
+        name: "expect_continue_pipelined",
+        request: b"GET /1 HTTP/1.1\r\nHost: a\r\n\r\n\
+                   POST /2 HTTP/1.1\r\nHost: a\r\nExpect: 100-continue\r\nContent-Length: 3\r\n\
+                   Connection: close\r\n\r\nabc",
+        response: &[
+            "HTTP/1.1 200 OK\r\n",
+            "\r\n\r\n/1:",
+            "HTTP/1.1 100 Continue\r\n\r\n",
+            "HTTP/1.1 200 OK\r\n",
+            "\r\n\r\n/2:abc",
+        ],
+    },
+    Fixture {
+        name: "pipelined",
+        request: b"GET /1 HTTP/1.1\r\nHost: a\r\n\r\n\
+                   POST /2 HTTP/1.1\r\nHost: a\r\nContent-Length: 3\r\n\r\nabc\
+                   GET /3 HTTP/1.1\r\nHost: a\r\nConnection: close\r\n\r\n",
+        response: &[
+            "HTTP/1.1 200 OK\r\n",
+            "\r\n\r\n/1:",
+            "HTTP/1.1 200 OK\r\n",
+            "\r\n\r\n/2:abc",
+            "HTTP/1.1 200 OK\r\n",
+            "\r\n\r\n/3:",
+        ],
+    },
+];
+
+// respond with request path and body.
+async fn echo(req: Request<RequestExt<RequestBody>>) -> Result<Response<ResponseBody>, BodyError> {
+    let mut res = BytesMut::from(req.uri().path());
+    res.extend_from_slice(b":");
+
+    let mut body = pin!(req.into_body());
+    while let Some(chunk) = poll_fn(|cx| body.as_mut().poll_next(cx)).await {
+        res.extend_from_slice(&chunk?);
+    }
+
+    Ok(Response::new(res.freeze().into()))
+}
+
+// write fixture request from client thread and collect response until server closes connection.
+fn client(listener: &TcpListener, request: &'static [u8]) -> JoinHandle<String> {
+    let addr = listener.local_addr().unwrap();
+    std::thread::spawn(move || {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.set_read_timeout(Some(Duration::from_secs(3))).unwrap();
+        stream.write_all(request).unwrap();
+        let mut res = Vec::new();
+        // read timeout means dispatcher stuck on fixture. partial response is checked.
+        let _ = stream.read_to_end(&mut res);
+        String::from_utf8(res).unwrap()
+    })
+}
+
+fn assert_fixture(fixture: &Fixture, res: &str) {
+    let mut rest = res;
+    for part in fixture.response {
+        match rest.find(part) {
+            Some(idx) => rest = &rest[idx + part.len()..],
+            None => panic!("fixture {} expect {part:?} in response: {res:?}", fixture.name),
+        }
+    }
+}
+
+fn run(serve: impl Fn(TcpListener)) {
+    for fixture in FIXTURES {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = client(&listener, fixture.request);
+        serve(listener);
+        assert_fixture(fixture, &client.join().unwrap());
+    }
+}
+
+#[test]
+fn dispatcher() {
+    run(|listener| {
+        let rt = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        tokio::task::LocalSet::new().block_on(&rt, async {
+            let service = HttpServiceBuilder::h1(fn_service(echo)).call(()).await.unwrap();
+            let (stream, addr) = listener.accept().unwrap();
+            stream.set_nonblocking(true).unwrap();
+            let stream = xitca_io::net::TcpStream::from_std(stream).unwrap();
+            let _ = service.call((stream, addr)).await;
+        })
+    });
+}
+
+#[tokio::test]
+async fn codec() {
+    tokio::task::LocalSet::new()
+        .run_until(async {
+            let date = DateTimeService::new();
+
+            for fixture in FIXTURES {
+                let mut decoder = RequestDecoder::<64>::new();
+                let mut encoder = ResponseEncoder::new(date.get());
+
+                let mut read_buf = BytesMut::from(fixture.request);
+                let mut write_buf = BytesMut::new();
+                let mut res = BytesMut::new();
+
+                // same as echo service but driven by codec events.
+                while let Some(event) = decoder.decode(&mut read_buf).unwrap() {
+                    match event {
+                        RequestEvent::Head(req) => {
+                            if decoder.is_expect_continue() {
+                                encoder.encode_continue(&mut write_buf);
+                            }
+                            res.extend_from_slice(req.uri().path().as_bytes());
+                            res.extend_from_slice(b":");
+                        }
+                        RequestEvent::BodyChunk(chunk) => res.extend_from_slice(&chunk),
+                        RequestEvent::BodyEnd => {
+                            let body = res.split().freeze();
+                            let (parts, _) = Response::new(()).into_parts();
+                            encoder
+                                .encode_head(&decoder, parts, BodySize::Sized(body.len()), &mut write_buf)
+                                .unwrap();
+                            encoder.encode_body(body, &mut write_buf);
+                            encoder.encode_eof(&mut write_buf).unwrap();
+                            if !encoder.is_keep_alive() {
+                                break;
+                            }
+                        }
+                    }
+                }
+
+                assert_fixture(fixture, core::str::from_utf8(&write_buf).unwrap());
+            }
+        })
+        .await;
+}
+
+#[cfg(feature = "io-uring")]
+#[test]
+fn dispatcher_uring() {
+    run(|listener| {
+        tokio_uring::start(async {
+            let service = HttpServiceBuilder::h1(fn_service(echo))
+                .io_uring()
+                .call(())
+                .await
+                .unwrap();
+            let (stream, addr) = listener.accept().unwrap();
+            let stream = xitca_io::net::io_uring::TcpStream::from_std(stream);
+            let _ = service.call((stream, addr)).await;
+        })
+    });
+}
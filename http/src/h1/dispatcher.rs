@@ -1,49 +1,60 @@
 use core::{
     convert::Infallible,
+    fmt,
     future::{pending, poll_fn, Future},
     marker::PhantomData,
     pin::{pin, Pin},
+    task::Poll,
     time::Duration,
 };
 
 use std::{io, net::SocketAddr};
 
 use futures_core::stream::Stream;
-use tracing::trace;
+use tokio::time::{sleep_until, Instant};
+use tracing::{trace, Instrument};
 use xitca_io::io::{AsyncIo, Interest, Ready};
 use xitca_service::Service;
 use xitca_unsafe_collection::futures::{Select as _, SelectOutput};
 
 use crate::{
-    body::NoneBody,
-    bytes::{Bytes, EitherBuf},
-    config::HttpServiceConfig,
+    body::{BodyErrorHook, BodySize, BodySizeProbe, NoneBody, ProbeGuard, ResponseBody},
+    bytes::Bytes,
+    config::{FlushPolicy, HttpServiceConfig, RequestRatePolicy, StuckRequestAction},
     date::DateTime,
+    error::{BodyError, BodySizeMismatch},
     h1::{
-        body::{RequestBody, RequestBodySender},
+        body::{incomplete_error, read_error, RequestBody, RequestBodySender},
         error::Error,
     },
     http::{
+        header::TRANSFER_ENCODING,
         response::{Parts, Response},
-        StatusCode,
+        HeaderMap, Informational, InformationalReceiver, RequestArrival, RequestBodyLimit, StatusCode, Version,
     },
+    response,
+    tls::TlsExtensions,
     util::{
-        buffered::{BufferedIo, ListWriteBuf, ReadBuf, WriteBuf},
+        buffered::{BufferedIo, ReadBuf},
+        drain::{draining, DrainWatch},
+        span,
         timer::{KeepAlive, Timeout},
+        watchdog::{Watch, Watchdog},
     },
 };
 
 use super::proto::{
-    buf_write::H1BufWrite,
-    codec::{ChunkResult, TransferCoding},
+    buf_write::{AdaptiveWriteBuf, H1BufWrite},
+    codec::{ChunkResult, ChunkedState, TransferCoding},
     context::Context,
-    encode::encode_continue,
+    encode::{encode_continue, encode_informational, is_no_body_status, SizeHint},
     error::ProtoError,
 };
 
 type ExtRequest<B> = crate::http::Request<crate::http::RequestExt<B>>;
 
 /// function to generic over different writer buffer types dispatcher.
+#[allow(clippy::too_many_arguments)]
 pub(crate) async fn run<
     'a,
     St,
@@ -59,25 +70,27 @@ pub(crate) async fn run<
     io: &'a mut St,
     addr: SocketAddr,
     timer: Pin<&'a mut KeepAlive>,
-    config: HttpServiceConfig<HEADER_LIMIT, READ_BUF_LIMIT, WRITE_BUF_LIMIT>,
+    config: &'a HttpServiceConfig<HEADER_LIMIT, READ_BUF_LIMIT, WRITE_BUF_LIMIT>,
     service: &'a S,
     date: &'a D,
+    drain: Option<&'a DrainWatch>,
+    tls: TlsExtensions,
 ) -> Result<(), Error<S::Error, BE>>
 where
     S: Service<ExtRequest<ReqB>, Response = Response<ResB>>,
     ReqB: From<RequestBody>,
     ResB: Stream<Item = Result<Bytes, BE>>,
+    BE: fmt::Debug,
     St: AsyncIo,
     D: DateTime,
 {
-    let write_buf = if config.vectored_write && io.is_vectored_write() {
-        EitherBuf::Left(ListWriteBuf::<_, WRITE_BUF_LIMIT>::default())
-    } else {
-        EitherBuf::Right(WriteBuf::<WRITE_BUF_LIMIT>::default())
-    };
+    let write_buf = AdaptiveWriteBuf::<WRITE_BUF_LIMIT>::new(config.write_buf_strategy, io.is_vectored_write());
+
+    let id = span::next_id();
 
-    Dispatcher::new(io, addr, timer, config, service, date, write_buf)
+    Dispatcher::new(io, addr, id, timer, config, service, date, drain, write_buf, tls)
         .run()
+        .instrument(span::connection("h1", addr, id))
         .await
 }
 
@@ -87,6 +100,16 @@ struct Dispatcher<'a, St, S, ReqB, W, D, const HEADER_LIMIT: usize, const READ_B
     timer: Timer<'a>,
     ctx: Context<'a, D, HEADER_LIMIT>,
     service: &'a S,
+    max_body_size: u64,
+    max_coalesce_body_size: usize,
+    flush_policy: FlushPolicy,
+    write_rate: Option<WriteRate>,
+    request_rate: Option<RequestRate>,
+    drain: Option<&'a DrainWatch>,
+    watchdog: Option<Watchdog>,
+    request_arrival: bool,
+    informational: bool,
+    tls: TlsExtensions,
     _phantom: PhantomData<ReqB>,
 }
 
@@ -129,7 +152,7 @@ impl<'a> Timer<'a> {
     }
 
     // update timer with a given base instant value. the final deadline is calculated base on it.
-    pub(super) fn update(&mut self, now: tokio::time::Instant) {
+    pub(super) fn update(&mut self, now: Instant) {
         let dur = match self.state {
             TimerState::Idle => {
                 self.state = TimerState::Wait;
@@ -144,14 +167,116 @@ impl<'a> Timer<'a> {
         self.timer.as_mut().update(now + dur)
     }
 
+    // set timer to fire at given deadline regardless of it's current state.
+    fn arm(&mut self, deadline: Instant) {
+        self.timer.as_mut().update(deadline);
+        self.timer.as_mut().reset();
+    }
+
+    // map timer expiration to error. partial indicates at least one byte of a new request has been
+    // received. timeout on an idle keep-alive connection is closed silently as a 408 response
+    // there can be mistaken by client as the response to it's next request.
     #[cold]
     #[inline(never)]
-    pub(super) fn map_to_err<SE, BE>(&self) -> Error<SE, BE> {
+    pub(super) fn map_to_err<SE, BE>(&self, partial: bool) -> Error<SE, BE> {
         match self.state {
-            TimerState::Wait => Error::KeepAliveExpire,
-            TimerState::Throttle => Error::RequestTimeout,
             TimerState::Idle => unreachable!(),
+            _ if partial => Error::RequestTimeout,
+            _ => Error::KeepAliveExpire,
+        }
+    }
+}
+
+// interval of measuring write rate.
+const WRITE_RATE_INTERVAL: Duration = Duration::from_secs(1);
+
+// minimum write rate guard of response. see HttpServiceConfig::min_write_rate for detail.
+struct WriteRate {
+    rate: u64,
+    grace: Duration,
+    deadline: Instant,
+    in_grace: bool,
+    written: u64,
+}
+
+impl WriteRate {
+    fn new(rate: u64, grace: Duration, now: Instant) -> Self {
+        Self {
+            rate,
+            grace,
+            deadline: now + grace,
+            in_grace: true,
+            written: 0,
+        }
+    }
+
+    // start measuring for a new response.
+    fn start(&mut self, now: Instant) {
+        self.deadline = now + self.grace;
+        self.in_grace = true;
+        self.written = 0;
+    }
+
+    // write buffer is drained and client is keeping up. only the time data is pending to be
+    // written is measured.
+    fn reset(&mut self, now: Instant) {
+        if !self.in_grace {
+            self.deadline = now + WRITE_RATE_INTERVAL;
+            self.written = 0;
+        }
+    }
+
+    // called when deadline is reached. return false when rate is lower than minimum.
+    fn tick(&mut self, now: Instant) -> bool {
+        if !core::mem::take(&mut self.in_grace) && self.written < self.rate {
+            trace!(target: "h1_dispatcher", "Connection write rate is lower than minimum. Dropping");
+            return false;
+        }
+        self.deadline = now + WRITE_RATE_INTERVAL;
+        self.written = 0;
+        true
+    }
+}
+
+// length of window requests are counted in.
+const REQUEST_RATE_WINDOW: Duration = Duration::from_secs(1);
+
+// request rate guard of connection. see HttpServiceConfig::max_requests_per_second for detail.
+struct RequestRate {
+    max: u32,
+    policy: RequestRatePolicy,
+    count: u32,
+    window_end: Instant,
+}
+
+impl RequestRate {
+    fn new(max: u32, policy: RequestRatePolicy, now: Instant) -> Self {
+        Self {
+            max,
+            policy,
+            count: 0,
+            window_end: now + REQUEST_RATE_WINDOW,
+        }
+    }
+
+    // count a new request. return the end of current window when the limit is exceeded.
+    fn tick(&mut self, now: Instant) -> Option<Instant> {
+        if now >= self.window_end {
+            self.window_end = now + REQUEST_RATE_WINDOW;
+            self.count = 0;
+        }
+        if self.count == self.max {
+            return Some(self.window_end);
         }
+        self.count += 1;
+        None
+    }
+
+    // start a new window with the delayed request counted. the clock of date service can lag
+    // behind so the window is started from the deadline it's delayed to.
+    fn delayed(&mut self, deadline: Instant) {
+        self.window_end = deadline + REQUEST_RATE_WINDOW;
+        self.count = 1;
     }
 }
 
@@ -161,24 +286,68 @@ where
     S: Service<ExtRequest<ReqB>, Response = Response<ResB>>,
     ReqB: From<RequestBody>,
     ResB: Stream<Item = Result<Bytes, BE>>,
+    BE: fmt::Debug,
     St: AsyncIo,
     W: H1BufWrite,
     D: DateTime,
 {
+    #[allow(clippy::too_many_arguments)]
     fn new<const WRITE_BUF_LIMIT: usize>(
         io: &'a mut St,
         addr: SocketAddr,
+        id: u64,
         timer: Pin<&'a mut KeepAlive>,
-        config: HttpServiceConfig<HEADER_LIMIT, READ_BUF_LIMIT, WRITE_BUF_LIMIT>,
+        config: &'a HttpServiceConfig<HEADER_LIMIT, READ_BUF_LIMIT, WRITE_BUF_LIMIT>,
         service: &'a S,
         date: &'a D,
+        drain: Option<&'a DrainWatch>,
         write_buf: W,
+        tls: TlsExtensions,
     ) -> Self {
+        let mut ctx = Context::with_addr(addr, date);
+        if config.raw_request_head {
+            ctx.keep_raw_head();
+        }
+        if config.request_scratch {
+            ctx.enable_scratch();
+        }
+        if config.cache_request_head {
+            ctx.cache_request_head();
+        }
+        if config.preserve_header_case {
+            ctx.preserve_header_case();
+        }
+        if config.normalize_request_headers {
+            ctx.normalize_headers();
+        }
+        ctx.set_uri_normalization(config.uri_normalization);
+        ctx.max_uri_length(config.max_uri_length);
+        ctx.max_response_head_size(config.max_response_head_size);
+        ctx.set_scheme(config.scheme());
+
+        let write_rate = config
+            .min_write_rate
+            .map(|(rate, grace)| WriteRate::new(rate, grace, ctx.date().now()));
+
+        let request_rate = config
+            .max_requests_per_second
+            .map(|(max, policy)| RequestRate::new(max, policy, ctx.date().now()));
+
         Self {
             io: BufferedIo::new(io, write_buf),
             timer: Timer::new(timer, config.keep_alive_timeout, config.request_head_timeout),
-            ctx: Context::with_addr(addr, date),
+            ctx,
             service,
+            max_body_size: config.max_request_body_size,
+            max_coalesce_body_size: config.max_coalesce_body_size,
+            flush_policy: config.flush_policy,
+            write_rate,
+            request_rate,
+            drain,
+            watchdog: Watchdog::new(config.stuck_request_threshold, config.connection_observer, addr, id),
+            request_arrival: config.request_arrival,
+            informational: config.informational_responses,
+            tls,
             _phantom: PhantomData,
         }
     }
@@ -191,16 +360,27 @@ where
                     trace!(target: "h1_dispatcher", "Connection keep-alive expired. Shutting down");
                     return Ok(());
                 }
-                Err(Error::RequestTimeout) => self.request_error(|| status_only(StatusCode::REQUEST_TIMEOUT)),
-                Err(Error::Proto(ProtoError::HeaderTooLarge)) => {
-                    self.request_error(|| status_only(StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE))
+                Err(Error::WriteRateTooLow) => return Ok(()),
+                Err(Error::RequestTimeout) => self.request_error(response::request_timeout),
+                Err(Error::TooManyRequests) => self.request_error(response::too_many_requests),
+                Err(Error::StuckRequest) => self.request_error(|| response::service_unavailable(None)),
+                Err(Error::Proto(ProtoError::UriTooLong)) => self.request_error(response::uri_too_long),
+                Err(Error::Proto(ProtoError::HeaderTooLarge)) => self.request_error(response::header_too_large),
+                Err(Error::Proto(ProtoError::BodyTooLarge)) => self.request_error(response::payload_too_large),
+                // response head is already written and connection is closed without a response.
+                Err(e @ Error::Proto(ProtoError::BodyLengthMismatch)) => return Err(e),
+                Err(Error::Proto(ProtoError::ResponseHeadTooLarge)) => {
+                    self.request_error(response::internal_server_error)
                 }
-                Err(Error::Proto(_)) => self.request_error(|| status_only(StatusCode::BAD_REQUEST)),
+                Err(Error::Proto(_)) => self.request_error(response::bad_request),
                 Err(e) => return Err(e),
             }
 
-            // TODO: add timeout for drain write?
-            self.io.drain_write().await?;
+            match self.drain_write().await {
+                Ok(_) => {}
+                Err(Error::WriteRateTooLow) => return Ok(()),
+                Err(e) => return Err(e),
+            }
 
             if self.ctx.is_connection_closed() {
                 return self.io.shutdown().await.map_err(Into::into);
@@ -210,90 +390,481 @@ where
 
     async fn _run(&mut self) -> Result<(), Error<S::Error, BE>> {
         self.timer.update(self.ctx.date().now());
-        self.io
-            .read()
-            .timeout(self.timer.get())
-            .await
-            .map_err(|_| self.timer.map_to_err())??;
 
-        while let Some((req, decoder)) = self.ctx.decode_head::<READ_BUF_LIMIT>(&mut self.io.read_buf)? {
+        // only idle connection is closed on draining. partial request head is waited for.
+        let drain = self.drain.filter(|_| self.io.read_buf.is_empty());
+
+        match self.io.read().timeout(self.timer.get()).select(draining(drain)).await {
+            SelectOutput::A(res) => match res.map_err(|_| self.timer.map_to_err(!self.io.read_buf.is_empty()))? {
+                Ok(_) => {}
+                // client shut down it's write half after complete requests and can still be
+                // reading responses. they are flushed before connection is closed. see Self::run.
+                Err(e) if e.kind() == io::ErrorKind::UnexpectedEof && self.io.read_buf.is_empty() => {
+                    trace!(target: "h1_dispatcher", "Connection is half closed by client. Shutting down");
+                    self.ctx.set_close();
+                    return Ok(());
+                }
+                // eof in the middle of request head is an abort.
+                Err(e) => return Err(e.into()),
+            },
+            SelectOutput::B(_) => {
+                trace!(target: "h1_dispatcher", "Connection is draining. Shutting down");
+                self.ctx.set_close();
+                return Ok(());
+            }
+        }
+
+        while let Some((mut req, decoder)) = self.ctx.decode_head::<READ_BUF_LIMIT>(&mut self.io.read_buf)? {
             self.timer.reset_state();
 
-            let (mut body_reader, body) = BodyReader::from_coding(decoder);
-            let req = req.map(|ext| ext.map_body(|_| ReqB::from(body)));
+            if self.request_arrival {
+                req.extensions_mut()
+                    .insert(RequestArrival(self.ctx.date().now_precise().into_std()));
+            }
 
-            let (parts, body) = match self
-                .service
-                .call(req)
-                .select(self.request_body_handler(&mut body_reader))
-                .await
-            {
-                SelectOutput::A(Ok(res)) => res.into_parts(),
-                SelectOutput::A(Err(e)) => return Err(Error::Service(e)),
-                SelectOutput::B(Err(e)) => return Err(e),
-                SelectOutput::B(Ok(i)) => match i {},
-            };
+            self.tls.insert_to(req.extensions_mut());
 
-            let encoder = &mut self.encode_head(parts, &body)?;
-            let mut body = pin!(body);
+            let informational = (self.informational && req.version() == Version::HTTP_11).then(|| {
+                let (tx, rx) = Informational::channel();
+                req.extensions_mut().insert(tx);
+                rx
+            });
 
-            loop {
-                match self
-                    .try_poll_body(body.as_mut())
-                    .select(self.io_ready(&mut body_reader))
+            self.pace_request().await?;
+
+            let span = span::request(&req);
+
+            let watch = self
+                .watchdog
+                .as_ref()
+                .map(|watchdog| watchdog.watch(&req, self.ctx.date().now()));
+
+            let (mut body_reader, body) =
+                BodyReader::from_coding(decoder, self.ctx.is_expect_header(), self.max_body_size);
+            if let Some(limit) = body_reader.limit.as_ref() {
+                req.extensions_mut().insert(limit.clone());
+            }
+            let req = req.map(|ext| ext.map_body(|_| ReqB::from(body)));
+
+            async {
+                let (mut parts, body) = match self
+                    .service
+                    .call(req)
+                    .select(self.request_body_handler(&mut body_reader, watch.as_ref(), informational.as_ref()))
                     .await
                 {
-                    SelectOutput::A(Some(Ok(bytes))) => encoder.encode(bytes, &mut self.io.write_buf),
-                    SelectOutput::B(Ok(ready)) => {
-                        if ready.is_readable() {
-                            if let Err(e) = self.io.try_read() {
-                                body_reader.feed_error(e);
+                    SelectOutput::A(Ok(res)) => res.into_parts(),
+                    SelectOutput::A(Err(e)) => return Err(Error::Service(e)),
+                    SelectOutput::B(Err(e)) => return Err(e),
+                    SelectOutput::B(Ok(i)) => match i {},
+                };
+
+                // pull timer back from watchdog deadline. it's always updated before next poll.
+                if watch.is_some() {
+                    self.timer.arm(self.ctx.date().now());
+                }
+
+                // informational responses queued before final response are written ahead of it.
+                // dropping the receiver drops the ones sent after.
+                if let Some(informational) = informational {
+                    while let Some((status, headers)) = informational.try_recv() {
+                        encode_informational(status, &headers, &mut self.io.write_buf);
+                    }
+                }
+
+                // responses of pipelined requests can fill write buffer. make room for response head.
+                if !self.io.write_buf.want_write_buf() {
+                    self.drain_write().await?;
+                }
+
+                let hook = parts.extensions.remove::<BodyErrorHook>();
+                let probe = ProbeGuard::new(parts.extensions.remove::<BodySizeProbe>());
+                let mut sent = 0;
+
+                // connection is draining. make it the last response so client reconnects.
+                if self.drain.is_some_and(DrainWatch::is_draining) {
+                    self.ctx.set_close();
+                }
+
+                let no_body = is_no_body_status(parts.status);
+
+                // body of response with no body status is dropped without polling.
+                if no_body {
+                    self.encode_head(parts, &body, None)?;
+                    probe.complete();
+                    return Ok(());
+                }
+
+                let mut body = pin!(body);
+
+                // small body is polled once so it can be written together with head when it's ready.
+                let size = BodySize::from_stream(body.as_ref().get_ref());
+                let mut peeked = None;
+                if self.is_coalesce_body(size, &parts.headers) {
+                    if let Poll::Ready(res) = poll_fn(|cx| Poll::Ready(body.as_mut().poll_next(cx))).await {
+                        peeked = Some(res);
+                    }
+                }
+
+                // exact size of body. yielding more or less bytes than it is a mismatch error.
+                let expected = match size {
+                    BodySize::Sized(size) => Some(size as u64),
+                    _ => None,
+                };
+
+                let encoder = &mut match peeked.take() {
+                    // oversized chunk is left to body loop for mismatch handling.
+                    Some(Some(Ok(bytes))) if !expected.is_some_and(|size| bytes.len() as u64 > size) => {
+                        sent += bytes.len() as u64;
+                        probe.add(bytes.len());
+                        self.encode_head(parts, &SizeHint(size), Some(bytes))?
+                    }
+                    res => {
+                        peeked = res;
+                        self.encode_head(parts, &SizeHint(size), None)?
+                    }
+                };
+
+                // body is fully written with head.
+                if matches!(encoder, TransferCoding::Length(0)) {
+                    probe.complete();
+                    return Ok(());
+                }
+
+                loop {
+                    let res = match peeked.take() {
+                        Some(res) => SelectOutput::A(res),
+                        None => {
+                            self.try_poll_body(body.as_mut())
+                                .select(self.io_ready(&mut body_reader))
+                                .await
+                        }
+                    };
+
+                    match res {
+                        SelectOutput::A(Some(Ok(bytes))) => {
+                            let yielded = sent + bytes.len() as u64;
+                            if let Some(size) = expected.filter(|size| yielded > *size) {
+                                let e = BodySizeMismatch::new(size, yielded);
+                                self.abort_body(hook, &e, sent).await;
+                                return Err(Error::BodySizeMismatch(e));
                             }
+                            sent = yielded;
+                            probe.add(bytes.len());
+                            encoder.encode(bytes, &mut self.io.write_buf);
                         }
-                        if ready.is_writable() {
-                            self.io.try_write()?;
+                        SelectOutput::B(Ok(ready)) => {
+                            if ready.is_readable() {
+                                if let Err(e) = self.io.try_read() {
+                                    body_reader.feed_error(read_error(e));
+                                }
+                            }
+                            if ready.is_writable() {
+                                self.try_write()?;
+                            }
+                        }
+                        SelectOutput::A(None) => {
+                            if let Some(size) = expected.filter(|size| sent < *size) {
+                                let e = BodySizeMismatch::new(size, sent);
+                                self.abort_body(hook, &e, sent).await;
+                                return Err(Error::BodySizeMismatch(e));
+                            }
+                            // body shorter than Content-Length header of response.
+                            if let Err(e) = encoder.encode_eof(&mut self.io.write_buf) {
+                                self.abort_body(hook, &e, sent).await;
+                                return Err(Error::Proto(e));
+                            }
+                            probe.complete();
+                            break;
+                        }
+                        SelectOutput::B(Err(e)) => return Err(e),
+                        SelectOutput::A(Some(Err(e))) => {
+                            self.abort_body(hook, &e, sent).await;
+                            return Err(Error::Body(e));
                         }
                     }
-                    SelectOutput::A(None) => {
-                        encoder.encode_eof(&mut self.io.write_buf);
-                        break;
-                    }
-                    SelectOutput::B(Err(e)) => return Err(e.into()),
-                    SelectOutput::A(Some(Err(e))) => return Err(Error::Body(e)),
                 }
+
+                Ok::<_, Error<S::Error, BE>>(())
             }
+            .instrument(span)
+            .await?;
 
+            // request body is not fully read. (including the one never invited by 100 continue)
+            // close connection as the rest of body can not be told apart from next request.
             if !body_reader.decoder.is_eof() {
-                self.ctx.set_close();
+                self.force_close();
                 break;
             }
+
+            // pipelined requests after the last response are not served.
+            if self.ctx.is_connection_closed() {
+                break;
+            }
+
+            // responses are flushed when no more request can be decoded. see Self::run.
+            if self.want_flush() {
+                self.drain_write().await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // apply request rate limit to decoded request before it's served.
+    async fn pace_request(&mut self) -> Result<(), Error<S::Error, BE>> {
+        let now = self.ctx.date().now();
+        let Some(rate) = self.request_rate.as_mut() else {
+            return Ok(());
+        };
+        let Some(deadline) = rate.tick(now) else {
+            return Ok(());
+        };
+
+        match rate.policy {
+            RequestRatePolicy::Delay => {
+                trace!(target: "h1_dispatcher", "Connection request rate exceeded. Delaying");
+                rate.delayed(deadline);
+                // responses of served requests are written before waiting.
+                self.drain_write().await?;
+                sleep_until(deadline).await;
+                Ok(())
+            }
+            RequestRatePolicy::Reject => {
+                trace!(target: "h1_dispatcher", "Connection request rate exceeded. Rejecting");
+                Err(Error::TooManyRequests)
+            }
+        }
+    }
+
+    fn encode_head(
+        &mut self,
+        parts: Parts,
+        body: &impl Stream,
+        chunk: Option<Bytes>,
+    ) -> Result<TransferCoding, ProtoError> {
+        let encoding = match chunk {
+            Some(chunk) => {
+                let size = BodySize::from_stream(body);
+                self.ctx
+                    .encode_head_with_body(parts, size, chunk, &mut self.io.write_buf)?
+            }
+            None => self.ctx.encode_head(parts, body, &mut self.io.write_buf)?,
+        };
+        if let Some(ref mut rate) = self.write_rate {
+            rate.start(self.ctx.date().now());
+            self.timer.arm(rate.deadline);
+        }
+        Ok(encoding)
+    }
+
+    // close connection after buffered responses. when the last of them is not written to io yet it's
+    // marked with connection close header so client does not reuse the connection for requests
+    // after it.
+    fn force_close(&mut self) {
+        if !self.ctx.is_connection_closed() {
+            self.io.write_buf.close_last_head();
+        }
+        self.ctx.set_close();
+    }
+
+    // flush the sent part of response and close connection without finishing body framing so
+    // client can tell the body is incomplete.
+    async fn abort_body(&mut self, hook: Option<BodyErrorHook>, err: &dyn fmt::Debug, sent: u64) {
+        if let Some(hook) = hook {
+            hook.call(err, sent);
+        }
+        self.ctx.set_close();
+        let _ = self.drain_write().await;
+    }
+
+    // write to io and measure the write rate when it's enabled.
+    fn try_write(&mut self) -> Result<(), Error<S::Error, BE>> {
+        let Some(ref mut rate) = self.write_rate else {
+            return self.io.try_write().map_err(Into::into);
+        };
+
+        rate.written += self.io.try_write_count()? as u64;
+
+        let now = self.ctx.date().now();
+        if !self.io.write_buf.want_write_io() {
+            rate.reset(now);
+        } else if now >= rate.deadline {
+            if !rate.tick(now) {
+                return Err(Error::WriteRateTooLow);
+            }
+            self.timer.arm(rate.deadline);
         }
 
         Ok(())
     }
 
-    fn encode_head(&mut self, parts: Parts, body: &impl Stream) -> Result<TransferCoding, ProtoError> {
-        self.ctx.encode_head(parts, body, &mut self.io.write_buf)
+    // called when timer fired while waiting for io to be writable.
+    #[cold]
+    #[inline(never)]
+    fn write_rate_timeout(&mut self) -> Result<(), Error<S::Error, BE>> {
+        let rate = self
+            .write_rate
+            .as_mut()
+            .expect("timer must not fire when write rate is disabled");
+
+        // write rate deadline may be moved after timer is armed.
+        let now = Instant::now();
+        if now >= rate.deadline && !rate.tick(now) {
+            return Err(Error::WriteRateTooLow);
+        }
+        self.timer.arm(rate.deadline);
+
+        Ok(())
+    }
+
+    async fn drain_write(&mut self) -> Result<(), Error<S::Error, BE>> {
+        if self.write_rate.is_none() {
+            return self.io.drain_write().await.map_err(Into::into);
+        }
+
+        while self.io.write_buf.want_write_io() {
+            match self.io.io.ready(Interest::WRITABLE).timeout(self.timer.get()).await {
+                Ok(res) => {
+                    res?;
+                    self.try_write()?;
+                }
+                Err(_) => self.write_rate_timeout()?,
+            }
+        }
+
+        Ok(())
     }
 
     // an associated future of self.service that runs until service is resolved or error produced.
-    async fn request_body_handler(&mut self, body_reader: &mut BodyReader) -> Result<Infallible, Error<S::Error, BE>> {
-        if self.ctx.is_expect_header() {
-            // wait for service future to start polling RequestBody.
-            if body_reader.wait_for_poll().await.is_ok() {
+    // when service call is watched timer is armed with the deadline of it.
+    async fn request_body_handler(
+        &mut self,
+        body_reader: &mut BodyReader,
+        watch: Option<&Watch>,
+        informational: Option<&InformationalReceiver>,
+    ) -> Result<Infallible, Error<S::Error, BE>> {
+        let Some(watch) = watch else {
+            return Self::read_request_body(&mut self.io, &mut self.ctx, body_reader, informational).await;
+        };
+
+        self.timer.arm(watch.deadline);
+
+        let Self {
+            io,
+            timer,
+            ctx,
+            watchdog,
+            ..
+        } = self;
+
+        let mut read = pin!(Self::read_request_body(io, ctx, body_reader, informational));
+
+        if let Ok(res) = read.as_mut().timeout(timer.get()).await {
+            return res;
+        }
+
+        match watchdog
+            .as_ref()
+            .expect("watch must be created by watchdog")
+            .report(watch)
+        {
+            StuckRequestAction::Warn => read.await,
+            StuckRequestAction::Abort => Err(Error::StuckRequest),
+        }
+    }
+
+    async fn read_request_body(
+        io: &mut BufferedIo<'a, St, W, READ_BUF_LIMIT>,
+        ctx: &mut Context<'a, D, HEADER_LIMIT>,
+        body_reader: &mut BodyReader,
+        informational: Option<&InformationalReceiver>,
+    ) -> Result<Infallible, Error<S::Error, BE>> {
+        // service is polled once before reading body and it can override the limit of body. body
+        // advertising a larger Content-Length is rejected before continue is sent or any of it is
+        // read.
+        if body_reader.is_overflow() {
+            return Err(Error::Proto(ProtoError::BodyTooLarge));
+        }
+
+        if body_reader.continue_pending {
+            // wait for service future to start polling RequestBody. when service responds without
+            // ever polling it the continue is skipped entirely and client is not invited to upload.
+            let res = loop {
+                match body_reader
+                    .wait_for_poll()
+                    .select(recv_informational(informational))
+                    .await
+                {
+                    SelectOutput::A(res) => break res,
+                    SelectOutput::B((status, headers)) => {
+                        encode_informational(status, &headers, &mut io.write_buf);
+                        io.drain_write().await?;
+                    }
+                }
+            };
+            if res.is_ok() {
                 // encode continue as service future want a body.
-                encode_continue(&mut self.io.write_buf);
-                // use drain write to make sure continue is sent to client.
-                self.io.drain_write().await?;
+                encode_continue(&mut io.write_buf);
+                body_reader.continue_pending = false;
+                // use drain write to make sure continue is sent to client before reading body.
+                io.drain_write().await?;
             }
         }
 
         loop {
-            body_reader.ready(&mut self.io.read_buf).await;
-            self.io.read().await?;
+            // informational responses are written as soon as they are sent by service.
+            if let SelectOutput::B((status, headers)) = body_reader
+                .ready(&mut io.read_buf)
+                .select(recv_informational(informational))
+                .await
+            {
+                encode_informational(status, &headers, &mut io.write_buf);
+                io.drain_write().await?;
+                continue;
+            }
+            // response is not started yet. answer the oversized body with 413 instead of service.
+            if body_reader.is_overflow() {
+                return Err(Error::Proto(ProtoError::BodyTooLarge));
+            }
+            // responses of previous pipelined requests are flushed before waiting for more body.
+            // client can hold back the body until it receives them.
+            if io.write_buf.want_write_io() {
+                io.drain_write().await?;
+            }
+            // body reader only wants read when body is not fully received. feed the error to
+            // request body so service can observe it and reader stays pending afterwards.
+            match io.read().select(recv_informational(informational)).await {
+                SelectOutput::A(Ok(_)) => {}
+                SelectOutput::A(Err(e)) => {
+                    body_reader.feed_error(read_error(e));
+                    ctx.set_close();
+                }
+                SelectOutput::B((status, headers)) => {
+                    encode_informational(status, &headers, &mut io.write_buf);
+                    io.drain_write().await?;
+                }
+            }
+        }
+    }
+
+    // check if write buffer should be flushed after a response according to flush policy.
+    fn want_flush(&self) -> bool {
+        match self.flush_policy {
+            FlushPolicy::EveryResponse => self.io.write_buf.want_write_io(),
+            FlushPolicy::BufferedUntil(size) => self.io.write_buf.buffered_len() >= size,
+            FlushPolicy::EndOfReadBatch => false,
         }
     }
 
+    // check if response body is small enough to be written together with head.
+    // chunked response opts out as it's body is framed on it's own.
+    fn is_coalesce_body(&self, size: BodySize, headers: &HeaderMap) -> bool {
+        !self.ctx.is_head_method()
+            && !headers.contains_key(TRANSFER_ENCODING)
+            && matches!(size, BodySize::Sized(size) if size > 0 && size <= self.max_coalesce_body_size)
+    }
+
     fn try_poll_body<'b>(&self, mut body: Pin<&'b mut ResB>) -> impl Future<Output = Option<Result<Bytes, BE>>> + 'b {
         let want_buf = self.io.write_buf.want_write_buf();
         async move {
@@ -307,40 +878,76 @@ where
 
     // Check readable and writable state of BufferedIo and ready state of request body reader.
     // return error when runtime is shutdown.(See AsyncIo::ready for reason).
-    async fn io_ready(&mut self, body_reader: &mut BodyReader) -> io::Result<Ready> {
+    // when write rate is measured the waiting for writable io is bounded by the deadline of it.
+    async fn io_ready(&mut self, body_reader: &mut BodyReader) -> Result<Ready, Error<S::Error, BE>> {
         if !self.io.write_buf.want_write_io() {
             body_reader.ready(&mut self.io.read_buf).await;
-            self.io.io.ready(Interest::READABLE).await
-        } else {
-            match body_reader
+            return self.io.io.ready(Interest::READABLE).await.map_err(Into::into);
+        }
+
+        loop {
+            let ready = body_reader
                 .ready(&mut self.io.read_buf)
-                .select(self.io.io.ready(Interest::WRITABLE))
-                .await
-            {
+                .select(self.io.io.ready(Interest::WRITABLE));
+
+            let res = match self.write_rate {
+                None => ready.await,
+                Some(_) => match ready.timeout(self.timer.get()).await {
+                    Ok(res) => res,
+                    Err(_) => {
+                        self.write_rate_timeout()?;
+                        continue;
+                    }
+                },
+            };
+
+            let res = match res {
                 SelectOutput::A(_) => self.io.io.ready(Interest::READABLE | Interest::WRITABLE).await,
                 SelectOutput::B(res) => res,
-            }
+            };
+
+            return res.map_err(Into::into);
         }
     }
 
     #[cold]
     #[inline(never)]
-    fn request_error(&mut self, func: impl FnOnce() -> Response<NoneBody<Bytes>>) {
-        self.ctx.set_close();
+    fn request_error(&mut self, func: impl FnOnce() -> Response<ResponseBody<NoneBody<Bytes>>>) {
+        self.force_close();
         let (parts, body) = func().into_parts();
-        self.encode_head(parts, &body).expect("request_error must be correct");
+        // error response is generated by dispatcher and not subject to max response head size.
+        let max = self.ctx.max_response_head_len();
+        self.ctx.max_response_head_size(usize::MAX);
+        let res = self.encode_head(parts, &body, None);
+        self.ctx.max_response_head_size(max);
+        res.expect("request_error must be correct");
     }
 }
 
 pub(super) struct BodyReader {
     pub(super) decoder: TransferCoding,
     tx: RequestBodySender,
+    // request has expect header and 100 continue is not sent yet.
+    continue_pending: bool,
+    // bytes of body fed to RequestBody.
+    received: u64,
+    // max bytes of body shared with service. body exceeding it is fed with overflow error. request
+    // without body has no limit.
+    pub(super) limit: Option<RequestBodyLimit>,
 }
 
 impl BodyReader {
-    pub(super) fn from_coding(decoder: TransferCoding) -> (Self, RequestBody) {
-        let (tx, body) = RequestBody::channel(decoder.is_eof());
-        let body_reader = BodyReader { decoder, tx };
+    // continue is only pending when there is body to read. request without body does not need it.
+    pub(super) fn from_coding(decoder: TransferCoding, expect: bool, limit: u64) -> (Self, RequestBody) {
+        let eof = decoder.is_eof();
+        let (tx, body) = RequestBody::channel(eof);
+        let body_reader = BodyReader {
+            decoder,
+            tx,
+            continue_pending: expect && !eof,
+            received: 0,
+            limit: (!eof).then(|| RequestBodyLimit::new(limit)),
+        };
         (body_reader, body)
     }
 
@@ -349,7 +956,17 @@ impl BodyReader {
     pub(super) async fn ready<const READ_BUF_LIMIT: usize>(&mut self, read_buf: &mut ReadBuf<READ_BUF_LIMIT>) {
         loop {
             match self.decoder.decode(&mut *read_buf) {
-                ChunkResult::Ok(bytes) => self.tx.feed_data(bytes),
+                ChunkResult::Ok(bytes) => {
+                    self.received += bytes.len() as u64;
+                    // body without Content-Length is only known to be oversized when it's received.
+                    if self.is_overflow() {
+                        let limit = self.limit.as_ref().map_or(u64::MAX, RequestBodyLimit::get);
+                        let limit = usize::try_from(limit).unwrap_or(usize::MAX);
+                        self.feed_error(BodyError::Overflow { limit });
+                        return;
+                    }
+                    self.tx.feed_data(bytes);
+                }
                 ChunkResult::InsufficientData => match self.tx.ready().await {
                     Ok(_) => return,
                     // service future drop RequestBody so marker decoder to corrupted.
@@ -357,15 +974,31 @@ impl BodyReader {
                 },
                 ChunkResult::OnEof => self.tx.feed_eof(),
                 ChunkResult::AlreadyEof | ChunkResult::Corrupted => pending().await,
-                ChunkResult::Err(e) => self.feed_error(e),
+                ChunkResult::Err(e) => self.feed_error(BodyError::Parse(e.into())),
             }
         }
     }
 
+    // body with Content-Length is known to be oversized before it's fully received.
+    pub(super) fn is_overflow(&self) -> bool {
+        let Some(ref limit) = self.limit else {
+            return false;
+        };
+        let expected = match self.decoder {
+            TransferCoding::Length(len) => len,
+            _ => 0,
+        };
+        !limit.check(self.received.saturating_add(expected))
+    }
+
     // feed error to body sender and prepare for close connection.
     #[cold]
     #[inline(never)]
-    pub(super) fn feed_error(&mut self, e: io::Error) {
+    pub(super) fn feed_error(&mut self, e: BodyError) {
+        let e = match e {
+            BodyError::Disconnected => incomplete_error(&self.decoder, self.received),
+            e => e,
+        };
         self.tx.feed_error(e);
         self.decoder.set_corrupted();
     }
@@ -380,8 +1013,527 @@ impl BodyReader {
     }
 }
 
-#[cold]
-#[inline(never)]
-pub(super) fn status_only(status: StatusCode) -> Response<NoneBody<Bytes>> {
-    Response::builder().status(status).body(NoneBody::default()).unwrap()
+// dispatcher can be dropped in the middle of reading body. (timeout, io error, shutdown etc)
+// RequestBody possibly moved out of service must not end as if body is complete.
+impl Drop for BodyReader {
+    fn drop(&mut self) {
+        match self.decoder {
+            TransferCoding::Length(0) | TransferCoding::DecodeChunked(ChunkedState::End, _) => {}
+            TransferCoding::Length(_) | TransferCoding::DecodeChunked(..) => {
+                self.feed_error(BodyError::Disconnected);
+            }
+            _ => {}
+        }
+    }
+}
+
+// wait for next informational response sent by service. pending forever when it's not enabled.
+pub(super) async fn recv_informational(informational: Option<&InformationalReceiver>) -> (StatusCode, HeaderMap) {
+    match informational {
+        Some(informational) => poll_fn(|cx| informational.poll_recv(cx)).await,
+        None => pending().await,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use tokio::{
+        io::{duplex, AsyncReadExt, AsyncWriteExt},
+        task::{spawn_local, LocalSet},
+    };
+    use xitca_io::io::PollIoAdapter;
+    use xitca_service::fn_service;
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::{
+        config::ConnectionEvent,
+        http::{header::HeaderValue, Request, RequestExt},
+        HttpServiceBuilder,
+    };
+
+    use super::*;
+
+    // request type of HttpService. not to be confused with RequestBody of dispatcher.
+    type ServiceRequest = Request<RequestExt<crate::body::RequestBody>>;
+
+    async fn handler(req: ServiceRequest) -> Result<Response<ResponseBody>, Infallible> {
+        let body = format!("{} {}", req.uri(), req.body().socket_addr());
+        Ok(Response::new(Bytes::from(body).into()))
+    }
+
+    #[test]
+    fn body_reader_parse_error() {
+        let (mut reader, mut body) = BodyReader::from_coding(TransferCoding::decode_chunked(), false, u64::MAX);
+
+        let mut buf = ReadBuf::<1024>::new();
+        buf.extend_from_slice(b"X\r\n");
+
+        // reader stays pending after error is fed and decoder is corrupted.
+        let res = reader.ready(&mut buf).select(async {}).now_or_panic();
+        assert!(matches!(res, SelectOutput::B(())));
+
+        let err = poll_fn(|cx| Pin::new(&mut body).poll_next(cx))
+            .now_or_panic()
+            .unwrap()
+            .unwrap_err();
+        assert!(err.is_parse());
+    }
+
+    fn next(body: &mut RequestBody) -> Option<Result<Bytes, BodyError>> {
+        poll_fn(|cx| Pin::new(&mut *body).poll_next(cx)).now_or_panic()
+    }
+
+    #[test]
+    fn body_reader_incomplete() {
+        let (mut reader, mut body) = BodyReader::from_coding(TransferCoding::length(10), false, u64::MAX);
+
+        let mut buf = ReadBuf::<1024>::new();
+        buf.extend_from_slice(b"hello");
+        let _ = reader.ready(&mut buf).select(async {}).now_or_panic();
+
+        // connection closed by peer.
+        reader.feed_error(read_error(io::ErrorKind::UnexpectedEof.into()));
+
+        assert_eq!(next(&mut body).unwrap().unwrap().as_ref(), b"hello");
+        match next(&mut body).unwrap().unwrap_err() {
+            BodyError::IncompleteBody { expected, received } => {
+                assert_eq!(expected, Some(10));
+                assert_eq!(received, 5);
+            }
+            e => panic!("unexpected error: {e:?}"),
+        }
+
+        // reader dropped in the middle of chunked body.
+        let (mut reader, mut body) = BodyReader::from_coding(TransferCoding::decode_chunked(), false, u64::MAX);
+        buf.extend_from_slice(b"5\r\nhello\r\n");
+        let _ = reader.ready(&mut buf).select(async {}).now_or_panic();
+        drop(reader);
+
+        assert_eq!(next(&mut body).unwrap().unwrap().as_ref(), b"hello");
+        match next(&mut body).unwrap().unwrap_err() {
+            BodyError::IncompleteBody { expected, received } => {
+                assert_eq!(expected, None);
+                assert_eq!(received, 5);
+            }
+            e => panic!("unexpected error: {e:?}"),
+        }
+
+        // completed body ends as usual.
+        let (mut reader, mut body) = BodyReader::from_coding(TransferCoding::length(5), false, u64::MAX);
+        buf.extend_from_slice(b"hello");
+        let _ = reader.ready(&mut buf).select(async {}).now_or_panic();
+        drop(reader);
+
+        assert_eq!(next(&mut body).unwrap().unwrap().as_ref(), b"hello");
+        assert!(next(&mut body).is_none());
+    }
+
+    #[tokio::test]
+    async fn flush_policy_lockstep() {
+        use tokio::{io::DuplexStream, time::timeout};
+
+        // respond with path and length of request body.
+        async fn echo(req: ServiceRequest) -> Result<Response<ResponseBody>, Infallible> {
+            let path = req.uri().path().to_owned();
+            let mut body = pin!(req.into_body());
+            let mut len = 0;
+            while let Some(chunk) = poll_fn(|cx| body.as_mut().poll_next(cx)).await {
+                len += chunk.unwrap().len();
+            }
+            Ok(Response::new(Bytes::from(format!("{path}:{len};")).into()))
+        }
+
+        async fn read_until(client: &mut DuplexStream, end: &str) -> String {
+            let mut res = Vec::new();
+            while !res.ends_with(end.as_bytes()) {
+                let mut buf = [0; 64];
+                let n = client.read(&mut buf).await.unwrap();
+                assert_ne!(n, 0, "connection closed before {end}");
+                res.extend_from_slice(&buf[..n]);
+            }
+            String::from_utf8(res).unwrap()
+        }
+
+        // client sending next request or rest of body only after it receives previous response.
+        async fn lockstep(policy: FlushPolicy) {
+            let config = HttpServiceConfig::new().flush_policy(policy);
+            let service = HttpServiceBuilder::with_config(fn_service(echo), config)
+                .call(())
+                .await
+                .unwrap();
+
+            let (mut client, server) = duplex(64);
+
+            let handle = spawn_local(async move { service.serve_connection(PollIoAdapter::new(server), None).await });
+
+            for path in ["/a", "/b", "/c"] {
+                let req = format!("GET {path} HTTP/1.1\r\nhost: localhost\r\n\r\n");
+                client.write_all(req.as_bytes()).await.unwrap();
+                read_until(&mut client, &format!("{path}:0;")).await;
+            }
+
+            // response of first request is buffered while body of second request is pending.
+            client
+                .write_all(b"GET /d HTTP/1.1\r\nhost: localhost\r\n\r\nPOST /e HTTP/1.1\r\nhost: localhost\r\ncontent-length: 6\r\n\r\nfoo")
+                .await
+                .unwrap();
+            read_until(&mut client, "/d:0;").await;
+            client.write_all(b"bar").await.unwrap();
+            read_until(&mut client, "/e:6;").await;
+
+            // pipelined requests are all served.
+            client
+                .write_all(b"GET /f HTTP/1.1\r\nhost: localhost\r\n\r\nGET /g HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\n\r\n")
+                .await
+                .unwrap();
+            let mut res = String::new();
+            client.read_to_string(&mut res).await.unwrap();
+            assert!(res.contains("/f:0;"));
+            assert!(res.ends_with("/g:0;"));
+
+            handle.await.unwrap().unwrap();
+        }
+
+        LocalSet::new()
+            .run_until(async {
+                for policy in [
+                    FlushPolicy::EveryResponse,
+                    FlushPolicy::BufferedUntil(4096),
+                    FlushPolicy::EndOfReadBatch,
+                ] {
+                    timeout(Duration::from_secs(5), lockstep(policy))
+                        .await
+                        .unwrap_or_else(|_| panic!("{policy:?} deadlocked"));
+                }
+            })
+            .await
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn stuck_request() {
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc, Mutex,
+        };
+
+        use tokio::time::sleep;
+        use tracing::{
+            field::{Field, Visit},
+            Event, Level, Subscriber,
+        };
+        use tracing_subscriber::{
+            layer::{Context, SubscriberExt},
+            util::SubscriberInitExt,
+            Layer, Registry,
+        };
+
+        static STUCK: AtomicUsize = AtomicUsize::new(0);
+
+        fn observer(_: SocketAddr, event: ConnectionEvent) {
+            if event == ConnectionEvent::StuckRequest {
+                STUCK.fetch_add(1, Ordering::SeqCst);
+            }
+        }
+
+        // path field of warn events.
+        #[derive(Clone, Default)]
+        struct Capture(Arc<Mutex<Vec<String>>>);
+
+        struct Path(Option<String>);
+
+        impl Visit for Path {
+            fn record_str(&mut self, field: &Field, value: &str) {
+                if field.name() == "path" {
+                    self.0 = Some(value.to_owned());
+                }
+            }
+
+            fn record_debug(&mut self, _: &Field, _: &dyn fmt::Debug) {}
+        }
+
+        impl<S: Subscriber> Layer<S> for Capture {
+            fn on_event(&self, event: &Event<'_>, _: Context<'_, S>) {
+                if *event.metadata().level() == Level::WARN {
+                    let mut path = Path(None);
+                    event.record(&mut path);
+                    self.0.lock().unwrap().extend(path.0);
+                }
+            }
+        }
+
+        async fn stuck_handler(req: ServiceRequest) -> Result<Response<ResponseBody>, Infallible> {
+            match req.uri().path() {
+                "/stuck" => pending().await,
+                _ => {
+                    sleep(Duration::from_secs(600)).await;
+                    handler(req).await
+                }
+            }
+        }
+
+        async fn serve(action: StuckRequestAction, req: &[u8]) -> String {
+            let config = HttpServiceConfig::new()
+                .stuck_request_threshold(Duration::from_secs(300), action)
+                .connection_observer(observer);
+            let service = HttpServiceBuilder::with_config(fn_service(stuck_handler), config)
+                .call(())
+                .await
+                .unwrap();
+
+            let (mut client, server) = duplex(64);
+
+            let handle = spawn_local(async move { service.serve_connection(PollIoAdapter::new(server), None).await });
+
+            client.write_all(req).await.unwrap();
+
+            let mut res = String::new();
+            client.read_to_string(&mut res).await.unwrap();
+            handle.await.unwrap().unwrap();
+            res
+        }
+
+        let capture = Capture::default();
+        let _guard = Registry::default().with(capture.clone()).set_default();
+
+        LocalSet::new()
+            .run_until(async {
+                // never resolving service call is dropped and connection is closed after response.
+                let res = serve(
+                    StuckRequestAction::Abort,
+                    b"GET /stuck HTTP/1.1\r\nhost: localhost\r\n\r\nGET /foo HTTP/1.1\r\nhost: localhost\r\n\r\n",
+                )
+                .await;
+                assert!(res.starts_with("HTTP/1.1 503 Service Unavailable\r\n"));
+                assert_eq!(res.matches("HTTP/1.1").count(), 1);
+
+                // slow service call is reported and it's response is still sent.
+                let res = serve(
+                    StuckRequestAction::Warn,
+                    b"GET /slow HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\n\r\n",
+                )
+                .await;
+                assert!(res.starts_with("HTTP/1.1 200 OK\r\n"));
+                assert!(res.ends_with("/slow 0.0.0.0:0"));
+            })
+            .await;
+
+        assert_eq!(STUCK.load(Ordering::SeqCst), 2);
+        assert_eq!(*capture.0.lock().unwrap(), ["/stuck", "/slow"]);
+    }
+
+    #[tokio::test]
+    async fn request_error_small_head_size() {
+        async fn header(req: ServiceRequest) -> Result<Response<ResponseBody>, Infallible> {
+            let mut res = handler(req).await?;
+            res.headers_mut()
+                .insert("x-header", HeaderValue::from_static("header-value"));
+            Ok(res)
+        }
+
+        LocalSet::new()
+            .run_until(async {
+                for (req, status) in [
+                    // response of service goes over limit and is replaced by 500.
+                    (&b"GET / HTTP/1.1\r\nhost: localhost\r\n\r\n"[..], "HTTP/1.1 500"),
+                    // malformed request is answered with 400.
+                    (&b"GET / HTTP/1.1\r\nhost localhost\r\n\r\n"[..], "HTTP/1.1 400"),
+                ] {
+                    let config = HttpServiceConfig::new().max_response_head_size(8);
+                    let service = HttpServiceBuilder::with_config(fn_service(header), config)
+                        .call(())
+                        .await
+                        .unwrap();
+                    let (mut client, server) = duplex(1024);
+                    let handle =
+                        spawn_local(async move { service.serve_connection(PollIoAdapter::new(server), None).await });
+
+                    client.write_all(req).await.unwrap();
+
+                    let mut res = String::new();
+                    client.read_to_string(&mut res).await.unwrap();
+                    assert!(res.starts_with(status));
+                    handle.await.unwrap().unwrap();
+                }
+            })
+            .await
+    }
+
+    #[tokio::test]
+    async fn body_shorter_than_content_length() {
+        use core::task::Context;
+
+        // streaming body without size hint.
+        struct Short(bool);
+
+        impl Stream for Short {
+            type Item = Result<Bytes, Infallible>;
+
+            fn poll_next(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+                let this = self.get_mut();
+                let done = core::mem::replace(&mut this.0, true);
+                Poll::Ready((!done).then(|| Ok(Bytes::from_static(b"foo"))))
+            }
+        }
+
+        async fn short(_: ServiceRequest) -> Result<Response<ResponseBody>, Infallible> {
+            let mut res = Response::new(ResponseBody::box_stream(Short(false)));
+            res.headers_mut()
+                .insert(crate::http::header::CONTENT_LENGTH, HeaderValue::from_static("10"));
+            Ok(res)
+        }
+
+        LocalSet::new()
+            .run_until(async {
+                let service = HttpServiceBuilder::new(fn_service(short)).call(()).await.unwrap();
+                let (mut client, server) = duplex(1024);
+                let handle =
+                    spawn_local(async move { service.serve_connection(PollIoAdapter::new(server), None).await });
+
+                client
+                    .write_all(b"GET / HTTP/1.1\r\nhost: localhost\r\n\r\n")
+                    .await
+                    .unwrap();
+
+                // connection is closed after the partial body.
+                let mut res = String::new();
+                client.read_to_string(&mut res).await.unwrap();
+                assert!(res.starts_with("HTTP/1.1 200 OK\r\n"));
+                assert!(res.contains("content-length: 10\r\n"));
+                assert!(res.ends_with("\r\n\r\nfoo"));
+                assert!(handle.await.unwrap().is_err());
+            })
+            .await
+    }
+
+    #[tokio::test]
+    async fn informational() {
+        async fn hints(req: ServiceRequest) -> Result<Response<ResponseBody>, Infallible> {
+            let Some(informational) = req.extensions().get::<Informational>().cloned() else {
+                return Ok(Response::new(Bytes::from_static(b"none").into()));
+            };
+            let mut headers = HeaderMap::new();
+            headers.insert("link", HeaderValue::from_static("</style.css>; rel=preload; as=style"));
+            assert!(informational.send(StatusCode::from_u16(103).unwrap(), headers.clone()));
+            // informational response is written before service resolves.
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            assert!(informational.send(StatusCode::from_u16(103).unwrap(), headers.clone()));
+            let res = handler(req).await;
+            tokio::task::spawn_local(async move {
+                assert!(!informational.send(StatusCode::from_u16(103).unwrap(), headers));
+            });
+            res
+        }
+
+        async fn serve(req: &[u8]) -> String {
+            let config = HttpServiceConfig::new().informational_responses();
+            let service = HttpServiceBuilder::with_config(fn_service(hints), config)
+                .call(())
+                .await
+                .unwrap();
+            let (mut client, server) = duplex(1024);
+            let handle = spawn_local(async move { service.serve_connection(PollIoAdapter::new(server), None).await });
+
+            client.write_all(req).await.unwrap();
+
+            let mut res = Vec::new();
+            let mut buf = [0; 1024];
+            loop {
+                let n = client.read(&mut buf).await.unwrap();
+                res.extend_from_slice(&buf[..n]);
+                if n == 0 || res.ends_with(b"0.0.0.0:0") || res.ends_with(b"none") {
+                    break;
+                }
+            }
+            drop(client);
+            let _ = handle.await.unwrap();
+            String::from_utf8(res).unwrap()
+        }
+
+        const HINT: &str = "HTTP/1.1 103 Early Hints\r\nlink: </style.css>; rel=preload; as=style\r\n\r\n";
+
+        LocalSet::new()
+            .run_until(async {
+                let res = serve(b"GET / HTTP/1.1\r\nhost: localhost\r\n\r\n").await;
+                let final_res = res.strip_prefix(HINT).and_then(|res| res.strip_prefix(HINT)).unwrap();
+                assert!(final_res.starts_with("HTTP/1.1 200 OK\r\n"));
+
+                // informational response is sent while request body is pending.
+                let res = serve(b"POST / HTTP/1.1\r\nhost: localhost\r\ncontent-length: 4\r\n\r\nab").await;
+                assert!(res.starts_with(HINT));
+
+                // http/1.0 client does not get informational response.
+                let res = serve(b"GET / HTTP/1.0\r\nhost: localhost\r\n\r\n").await;
+                assert!(res.starts_with("HTTP/1.1 200 OK\r\n"));
+                assert!(res.ends_with("none"));
+            })
+            .await
+    }
+
+    #[tokio::test]
+    async fn half_close() {
+        use core::task::{ready, Context};
+
+        use tokio::time::{sleep, Instant, Sleep};
+
+        // body yielding a chunk after each delay so client's FIN arrives before response is
+        // finished.
+        struct Slow(u8, Pin<Box<Sleep>>);
+
+        impl Stream for Slow {
+            type Item = Result<Bytes, Infallible>;
+
+            fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+                let this = self.get_mut();
+                if this.0 == 3 {
+                    return Poll::Ready(None);
+                }
+                ready!(this.1.as_mut().poll(cx));
+                this.1.as_mut().reset(Instant::now() + Duration::from_millis(10));
+                this.0 += 1;
+                Poll::Ready(Some(Ok(Bytes::from((this.0 - 1).to_string()))))
+            }
+        }
+
+        async fn slow(_: ServiceRequest) -> Result<Response<ResponseBody>, Infallible> {
+            sleep(Duration::from_millis(10)).await;
+            let body = Slow(0, Box::pin(sleep(Duration::from_millis(10))));
+            Ok(Response::new(ResponseBody::box_stream(body)))
+        }
+
+        LocalSet::new()
+            .run_until(async {
+                // complete request followed by shutdown of client write is served.
+                let service = HttpServiceBuilder::new(fn_service(slow)).call(()).await.unwrap();
+                let (mut client, server) = duplex(64);
+                let handle =
+                    spawn_local(async move { service.serve_connection(PollIoAdapter::new(server), None).await });
+
+                client
+                    .write_all(b"GET / HTTP/1.1\r\nhost: localhost\r\n\r\n")
+                    .await
+                    .unwrap();
+                client.shutdown().await.unwrap();
+
+                let mut res = String::new();
+                client.read_to_string(&mut res).await.unwrap();
+                assert!(res.starts_with("HTTP/1.1 200 OK\r\n"));
+                assert!(res.ends_with("\r\n\r\n1\r\n0\r\n1\r\n1\r\n1\r\n2\r\n0\r\n\r\n"));
+                handle.await.unwrap().unwrap();
+
+                // shutdown in the middle of request head is an abort.
+                let service = HttpServiceBuilder::new(fn_service(slow)).call(()).await.unwrap();
+                let (mut client, server) = duplex(64);
+                let handle =
+                    spawn_local(async move { service.serve_connection(PollIoAdapter::new(server), None).await });
+
+                client.write_all(b"GET / HTTP/1.1\r\nhost: ").await.unwrap();
+                client.shutdown().await.unwrap();
+
+                let mut res = String::new();
+                client.read_to_string(&mut res).await.unwrap();
+                assert!(res.is_empty());
+                assert!(handle.await.unwrap().is_err());
+            })
+            .await
+    }
 }
@@ -16,33 +16,41 @@ use std::{
 
 use futures_core::stream::Stream;
 use pin_project_lite::pin_project;
-use tracing::trace;
+use tracing::{trace, Instrument};
 use xitca_io::{
     bytes::BytesMut,
     io_uring::{AsyncBufRead, AsyncBufWrite, IoBuf},
 };
 use xitca_service::Service;
-use xitca_unsafe_collection::futures::SelectOutput;
+use xitca_unsafe_collection::futures::{Select as _, SelectOutput};
 
 use crate::{
-    body::NoneBody,
+    body::{BodyErrorHook, BodySize, BodySizeProbe, NoneBody, ProbeGuard, ResponseBody},
     bytes::Bytes,
     config::HttpServiceConfig,
     date::DateTime,
-    h1::{body::RequestBody, error::Error},
-    http::{response::Response, StatusCode},
+    error::{BodyError, BodySizeMismatch},
+    h1::{
+        body::{incomplete_error, read_error, RequestBody},
+        error::Error,
+    },
+    http::{response::Response, Informational, RequestArrival, RequestBodyLimit, Version},
+    response,
+    tls::TlsExtensions,
     util::{
         buffered::ReadBuf,
+        drain::{draining, DrainWatch},
+        span,
         timer::{KeepAlive, Timeout},
     },
 };
 
 use super::{
-    dispatcher::{status_only, Timer},
+    dispatcher::{recv_informational, Timer},
     proto::{
         codec::{ChunkResult, TransferCoding},
         context::Context,
-        encode::encode_continue,
+        encode::{encode_continue, encode_informational, is_no_body_status},
         error::ProtoError,
     },
 };
@@ -55,9 +63,14 @@ pub(super) struct Dispatcher<'a, Io, S, ReqB, D, const H_LIMIT: usize, const R_L
     timer: Timer<'a>,
     ctx: Context<'a, D, H_LIMIT>,
     service: &'a S,
+    max_body_size: u64,
     read_buf: ReadBuf<R_LIMIT>,
     write_buf: WriteBuf<W_LIMIT>,
     notify: Notify<ReadBufErased>,
+    drain: Option<&'a DrainWatch>,
+    request_arrival: bool,
+    informational: bool,
+    tls: TlsExtensions,
     _phantom: PhantomData<ReqB>,
 }
 
@@ -120,24 +133,54 @@ where
     S: Service<ExtRequest<ReqB>, Response = Response<ResB>>,
     ReqB: From<RequestBody>,
     ResB: Stream<Item = Result<Bytes, BE>>,
+    BE: fmt::Debug,
     D: DateTime,
 {
+    #[allow(clippy::too_many_arguments)]
     pub(super) fn new(
         io: Io,
         addr: SocketAddr,
         timer: Pin<&'a mut KeepAlive>,
-        config: HttpServiceConfig<H_LIMIT, R_LIMIT, W_LIMIT>,
+        config: &'a HttpServiceConfig<H_LIMIT, R_LIMIT, W_LIMIT>,
         service: &'a S,
         date: &'a D,
+        drain: Option<&'a DrainWatch>,
+        tls: TlsExtensions,
     ) -> Self {
+        let mut ctx = Context::<_, H_LIMIT>::with_addr(addr, date);
+        if config.raw_request_head {
+            ctx.keep_raw_head();
+        }
+        if config.request_scratch {
+            ctx.enable_scratch();
+        }
+        if config.cache_request_head {
+            ctx.cache_request_head();
+        }
+        if config.preserve_header_case {
+            ctx.preserve_header_case();
+        }
+        if config.normalize_request_headers {
+            ctx.normalize_headers();
+        }
+        ctx.set_uri_normalization(config.uri_normalization);
+        ctx.max_uri_length(config.max_uri_length);
+        ctx.max_response_head_size(config.max_response_head_size);
+        ctx.set_scheme(config.scheme());
+
         Self {
             io: Rc::new(io),
             timer: Timer::new(timer, config.keep_alive_timeout, config.request_head_timeout),
-            ctx: Context::<_, H_LIMIT>::with_addr(addr, date),
+            ctx,
             service,
+            max_body_size: config.max_request_body_size,
             read_buf: ReadBuf::<R_LIMIT>::new(),
             write_buf: WriteBuf::<W_LIMIT>::new(),
             notify: Notify::new(),
+            drain,
+            request_arrival: config.request_arrival,
+            informational: config.informational_responses,
+            tls,
             _phantom: PhantomData,
         }
     }
@@ -150,11 +193,16 @@ where
                     trace!(target: "h1_dispatcher", "Connection keep-alive expired. Shutting down");
                     return Ok(());
                 }
-                Err(Error::RequestTimeout) => self.request_error(|| status_only(StatusCode::REQUEST_TIMEOUT)),
-                Err(Error::Proto(ProtoError::HeaderTooLarge)) => {
-                    self.request_error(|| status_only(StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE))
+                Err(Error::RequestTimeout) => self.request_error(response::request_timeout),
+                Err(Error::Proto(ProtoError::UriTooLong)) => self.request_error(response::uri_too_long),
+                Err(Error::Proto(ProtoError::HeaderTooLarge)) => self.request_error(response::header_too_large),
+                Err(Error::Proto(ProtoError::BodyTooLarge)) => self.request_error(response::payload_too_large),
+                // response head is already written and connection is closed without a response.
+                Err(e @ Error::Proto(ProtoError::BodyLengthMismatch)) => return Err(e),
+                Err(Error::Proto(ProtoError::ResponseHeadTooLarge)) => {
+                    self.request_error(response::internal_server_error)
                 }
-                Err(Error::Proto(_)) => self.request_error(|| status_only(StatusCode::BAD_REQUEST)),
+                Err(Error::Proto(_)) => self.request_error(response::bad_request),
                 Err(e) => return Err(e),
             }
 
@@ -169,79 +217,210 @@ where
     async fn _run(&mut self) -> Result<(), Error<S::Error, BE>> {
         self.timer.update(self.ctx.date().now());
 
-        let read = self
+        // only idle connection is closed on draining. partial request head is waited for.
+        let drain = self.drain.filter(|_| self.read_buf.is_empty());
+
+        let read = match self
             .read_buf
             .read_io(&*self.io)
             .timeout(self.timer.get())
+            .select(draining(drain))
             .await
-            .map_err(|_| self.timer.map_to_err())??;
+        {
+            SelectOutput::A(res) => res.map_err(|_| self.timer.map_to_err(!self.read_buf.is_empty()))??,
+            SelectOutput::B(_) => {
+                trace!(target: "h1_dispatcher", "Connection is draining. Shutting down");
+                self.ctx.set_close();
+                return Ok(());
+            }
+        };
 
+        // client shut down it's write half. eof in the middle of request head is an abort.
         if read == 0 {
+            if !self.read_buf.is_empty() {
+                return Err(Error::Closed);
+            }
             self.ctx.set_close();
             return Ok(());
         }
 
-        while let Some((req, decoder)) = self.ctx.decode_head::<R_LIMIT>(&mut self.read_buf)? {
+        while let Some((mut req, decoder)) = self.ctx.decode_head::<R_LIMIT>(&mut self.read_buf)? {
             self.timer.reset_state();
 
-            let (waiter, body) = if decoder.is_eof() {
-                (None, RequestBody::default())
+            if self.request_arrival {
+                req.extensions_mut()
+                    .insert(RequestArrival(self.ctx.date().now_precise().into_std()));
+            }
+
+            self.tls.insert_to(req.extensions_mut());
+
+            let informational = (self.informational && req.version() == Version::HTTP_11).then(|| {
+                let (tx, rx) = Informational::channel();
+                req.extensions_mut().insert(tx);
+                rx
+            });
+
+            let span = span::request(&req);
+
+            let mut limit = None;
+
+            let (wait, body) = if decoder.is_eof() {
+                (false, RequestBody::default())
             } else {
+                // continue is written to io directly by request body. flush responses of
+                // pipelined requests before it so they are not written out of order.
+                if self.ctx.is_expect_header() && !self.write_buf.is_empty() {
+                    self.write_buf.write_io(&*self.io).await?;
+                }
+
+                // limit is shared with service so it can be overridden before body is read.
+                let max_size = RequestBodyLimit::new(self.max_body_size);
+                req.extensions_mut().insert(max_size.clone());
+                limit = Some(max_size.clone());
+
                 let body = Body::new(
                     self.io.clone(),
                     self.ctx.is_expect_header(),
                     R_LIMIT,
+                    max_size,
                     decoder,
                     mem::take(&mut self.read_buf).limit(),
                     self.notify.notifier(),
                 );
 
-                (Some(&mut self.notify), RequestBody::io_uring(body))
+                (true, RequestBody::io_uring(body))
             };
 
             let req = req.map(|ext| ext.map_body(|_| ReqB::from(body)));
 
-            let (parts, body) = self.service.call(req).await.map_err(Error::Service)?.into_parts();
+            async {
+                let mut call = pin!(self.service.call(req));
 
-            let mut encoder = self.ctx.encode_head(parts, &body, &mut *self.write_buf)?;
+                // informational responses are written as soon as they are sent by service.
+                let res = loop {
+                    match call.as_mut().select(recv_informational(informational.as_ref())).await {
+                        SelectOutput::A(res) => break res,
+                        SelectOutput::B((status, headers)) => {
+                            encode_informational(status, &headers, &mut *self.write_buf);
+                            self.write_buf.write_io(&*self.io).await?;
+                        }
+                    }
+                };
 
-            // this block is necessary. ResB has to be dropped asap as it may hold ownership of
-            // Body type which if not dropped before Notifier::notify is called would prevent
-            // Notifier from waking up Notify.
-            {
-                let mut body = pin!(body);
+                // informational responses queued before final response are written ahead of it.
+                // dropping the receiver drops the ones sent after.
+                if let Some(informational) = informational {
+                    while let Some((status, headers)) = informational.try_recv() {
+                        encode_informational(status, &headers, &mut *self.write_buf);
+                    }
+                }
 
-                loop {
-                    let buf = &mut *self.write_buf;
+                // request body exceeded size limit. response of service is replaced.
+                if limit.as_ref().is_some_and(RequestBodyLimit::is_exceeded) {
+                    return Err(Error::Proto(ProtoError::BodyTooLarge));
+                }
 
-                    if buf.len() < W_LIMIT {
-                        let res = poll_fn(|cx| match body.as_mut().poll_next(cx) {
-                            Poll::Ready(res) => Poll::Ready(SelectOutput::A(res)),
-                            Poll::Pending if buf.is_empty() => Poll::Pending,
-                            Poll::Pending => Poll::Ready(SelectOutput::B(())),
-                        })
-                        .await;
+                let (mut parts, body) = res.map_err(Error::Service)?.into_parts();
 
-                        match res {
-                            SelectOutput::A(Some(res)) => {
-                                let bytes = res.map_err(Error::Body)?;
-                                encoder.encode(bytes, buf);
-                                continue;
-                            }
-                            SelectOutput::A(None) => {
-                                encoder.encode_eof(buf);
-                                break;
+                let hook = parts.extensions.remove::<BodyErrorHook>();
+                let probe = ProbeGuard::new(parts.extensions.remove::<BodySizeProbe>());
+                let mut sent = 0;
+
+                // connection is draining. make it the last response so client reconnects.
+                if self.drain.is_some_and(DrainWatch::is_draining) {
+                    self.ctx.set_close();
+                }
+
+                let no_body = is_no_body_status(parts.status);
+                let mut encoder = self.ctx.encode_head(parts, &body, &mut *self.write_buf)?;
+
+                // exact size of body. yielding more or less bytes than it is a mismatch error.
+                let expected = match BodySize::from_stream(&body) {
+                    BodySize::Sized(size) => Some(size as u64),
+                    _ => None,
+                };
+
+                // body of response with no body status is dropped without polling.
+                if no_body {
+                    probe.complete();
+                    return Ok(());
+                }
+
+                // this block is necessary. ResB has to be dropped asap as it may hold ownership of
+                // Body type which if not dropped before Notifier::notify is called would prevent
+                // Notifier from waking up Notify.
+                {
+                    let mut body = pin!(body);
+
+                    loop {
+                        let buf = &mut *self.write_buf;
+
+                        if buf.len() < W_LIMIT {
+                            let res = poll_fn(|cx| match body.as_mut().poll_next(cx) {
+                                Poll::Ready(res) => Poll::Ready(SelectOutput::A(res)),
+                                Poll::Pending if buf.is_empty() => Poll::Pending,
+                                Poll::Pending => Poll::Ready(SelectOutput::B(())),
+                            })
+                            .await;
+
+                            match res {
+                                SelectOutput::A(Some(Ok(bytes))) => {
+                                    let yielded = sent + bytes.len() as u64;
+                                    if let Some(size) = expected.filter(|size| yielded > *size) {
+                                        let e = BodySizeMismatch::new(size, yielded);
+                                        if let Some(hook) = hook {
+                                            hook.call(&e, sent);
+                                        }
+                                        self.ctx.set_close();
+                                        return Err(Error::BodySizeMismatch(e));
+                                    }
+                                    sent = yielded;
+                                    probe.add(bytes.len());
+                                    encoder.encode(bytes, buf);
+                                    continue;
+                                }
+                                SelectOutput::A(Some(Err(e))) => {
+                                    if let Some(hook) = hook {
+                                        hook.call(&e, sent);
+                                    }
+                                    self.ctx.set_close();
+                                    return Err(Error::Body(e));
+                                }
+                                SelectOutput::A(None) => {
+                                    if let Some(size) = expected.filter(|size| sent < *size) {
+                                        let e = BodySizeMismatch::new(size, sent);
+                                        if let Some(hook) = hook {
+                                            hook.call(&e, sent);
+                                        }
+                                        self.ctx.set_close();
+                                        return Err(Error::BodySizeMismatch(e));
+                                    }
+                                    // body shorter than Content-Length header of response.
+                                    if let Err(e) = encoder.encode_eof(buf) {
+                                        if let Some(hook) = hook {
+                                            hook.call(&e, sent);
+                                        }
+                                        self.ctx.set_close();
+                                        return Err(Error::Proto(e));
+                                    }
+                                    probe.complete();
+                                    break;
+                                }
+                                SelectOutput::B(_) => {}
                             }
-                            SelectOutput::B(_) => {}
                         }
-                    }
 
-                    self.write_buf.write_io(&*self.io).await?;
+                        self.write_buf.write_io(&*self.io).await?;
+                    }
                 }
+
+                Ok::<_, Error<S::Error, BE>>(())
             }
+            .instrument(span)
+            .await?;
 
-            if let Some(waiter) = waiter {
-                match waiter.wait().await {
+            if wait {
+                match self.notify.wait().await {
                     Some(read_buf) => self.read_buf = read_buf.limit(),
                     None => {
                         self.ctx.set_close();
@@ -249,6 +428,11 @@ where
                     }
                 }
             }
+
+            // pipelined requests after the last response are not served.
+            if self.ctx.is_connection_closed() {
+                break;
+            }
         }
 
         Ok(())
@@ -256,22 +440,26 @@ where
 
     #[cold]
     #[inline(never)]
-    fn request_error(&mut self, func: impl FnOnce() -> Response<NoneBody<Bytes>>) {
+    fn request_error(&mut self, func: impl FnOnce() -> Response<ResponseBody<NoneBody<Bytes>>>) {
         self.ctx.set_close();
         let (parts, body) = func().into_parts();
-        self.ctx
-            .encode_head(parts, &body, &mut *self.write_buf)
-            .expect("request_error must be correct");
+        // error response is generated by dispatcher and not subject to max response head size.
+        let max = self.ctx.max_response_head_len();
+        self.ctx.max_response_head_size(usize::MAX);
+        let res = self.ctx.encode_head(parts, &body, &mut *self.write_buf);
+        self.ctx.max_response_head_size(max);
+        res.expect("request_error must be correct");
     }
 }
 
-pub(super) struct Body(Pin<Box<dyn Stream<Item = io::Result<Bytes>>>>);
+pub(super) struct Body(Pin<Box<dyn Stream<Item = Result<Bytes, BodyError>>>>);
 
 impl Body {
     fn new<Io>(
         io: Rc<Io>,
         is_expect: bool,
         limit: usize,
+        max_size: RequestBodyLimit,
         decoder: TransferCoding,
         read_buf: ReadBufErased,
         notify: Notifier<ReadBufErased>,
@@ -282,6 +470,8 @@ impl Body {
         let body = BodyInner {
             io,
             limit,
+            max_size,
+            received: 0,
             decoder: Decoder {
                 decoder,
                 read_buf,
@@ -292,10 +482,14 @@ impl Body {
         let state = if is_expect {
             State::ExpectWrite {
                 fut: async {
+                    // client is not invited to upload body larger than limit.
+                    if let Some(e) = body.overflow() {
+                        return Err(e);
+                    }
                     let mut bytes = BytesMut::new();
                     encode_continue(&mut bytes);
                     let (res, _) = write_all(&*body.io, bytes).await;
-                    res.map(|_| body)
+                    res.map(|_| body).map_err(read_error)
                 },
             }
         } else {
@@ -322,7 +516,7 @@ impl Clone for Body {
 }
 
 impl Stream for Body {
-    type Item = io::Result<Bytes>;
+    type Item = Result<Bytes, BodyError>;
 
     #[inline]
     fn poll_next(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Option<Self::Item>> {
@@ -360,6 +554,10 @@ pin_project! {
 struct BodyInner<Io> {
     io: Rc<Io>,
     limit: usize,
+    // max bytes of body shared with service. body exceeding it ends with overflow error.
+    max_size: RequestBodyLimit,
+    // bytes of body yielded from decoder.
+    received: u64,
     decoder: Decoder,
 }
 
@@ -367,12 +565,28 @@ impl<Io> BodyInner<Io>
 where
     Io: AsyncBufRead,
 {
-    async fn chunk_read(mut self) -> io::Result<Self> {
-        let read = self.decoder.read_buf.read_io(&*self.io).await?;
-        if read == 0 {
-            return Err(io::ErrorKind::UnexpectedEof.into());
+    // body with Content-Length is known to be oversized before it's fully received.
+    fn overflow(&self) -> Option<BodyError> {
+        let expected = match self.decoder.decoder {
+            TransferCoding::Length(len) => len,
+            _ => 0,
+        };
+        if self.max_size.check(self.received.saturating_add(expected)) {
+            return None;
+        }
+        let limit = usize::try_from(self.max_size.get()).unwrap_or(usize::MAX);
+        Some(BodyError::Overflow { limit })
+    }
+
+    async fn chunk_read(mut self) -> Result<Self, BodyError> {
+        match self.decoder.read_buf.read_io(&*self.io).await {
+            Ok(0) => Err(incomplete_error(&self.decoder.decoder, self.received)),
+            Ok(_) => Ok(self),
+            Err(e) => match read_error(e) {
+                BodyError::Disconnected => Err(incomplete_error(&self.decoder.decoder, self.received)),
+                e => Err(e),
+            },
         }
-        Ok(self)
     }
 }
 
@@ -380,10 +594,10 @@ impl<Io, F, FutC, FutE> Stream for BodyReader<Io, F, FutC, FutE>
 where
     Io: AsyncBufRead,
     F: Fn(BodyInner<Io>) -> FutC,
-    FutC: Future<Output = io::Result<BodyInner<Io>>>,
-    FutE: Future<Output = io::Result<BodyInner<Io>>>,
+    FutC: Future<Output = Result<BodyInner<Io>, BodyError>>,
+    FutE: Future<Output = Result<BodyInner<Io>, BodyError>>,
 {
-    type Item = io::Result<Bytes>;
+    type Item = Result<Bytes, BodyError>;
 
     #[inline]
     fn poll_next(self: Pin<&mut Self>, cx: &mut task::Context<'_>) -> Poll<Option<Self::Item>> {
@@ -391,23 +605,32 @@ where
         loop {
             match this.state.as_mut().project() {
                 StateProj::Body { body } => {
+                    if let Some(e) = body.overflow() {
+                        body.decoder.decoder.set_corrupted();
+                        return Poll::Ready(Some(Err(e)));
+                    }
                     match body.decoder.decoder.decode(&mut body.decoder.read_buf) {
-                        ChunkResult::Ok(bytes) => return Poll::Ready(Some(Ok(bytes))),
-                        ChunkResult::Err(e) => return Poll::Ready(Some(Err(e))),
+                        ChunkResult::Ok(bytes) => {
+                            body.received += bytes.len() as u64;
+                            // body without Content-Length is only known to be oversized when it's received.
+                            if let Some(e) = body.overflow() {
+                                body.decoder.decoder.set_corrupted();
+                                return Poll::Ready(Some(Err(e)));
+                            }
+                            return Poll::Ready(Some(Ok(bytes)));
+                        }
+                        ChunkResult::Err(e) => return Poll::Ready(Some(Err(BodyError::Parse(e.into())))),
                         ChunkResult::InsufficientData => {}
                         _ => return Poll::Ready(None),
                     }
 
                     if body.decoder.read_buf.len() >= body.limit {
-                        let msg = format!(
-                            "READ_BUF_LIMIT reached: {{ limit: {}, length: {} }}",
-                            body.limit,
-                            body.decoder.read_buf.len()
-                        );
-                        return Poll::Ready(Some(Err(io::Error::new(io::ErrorKind::Other, msg))));
+                        return Poll::Ready(Some(Err(BodyError::Overflow { limit: body.limit })));
                     }
 
-                    let StateProjReplace::Body { body } = this.state.as_mut().project_replace(State::None) else { unreachable!() };
+                    let StateProjReplace::Body { body } = this.state.as_mut().project_replace(State::None) else {
+                        unreachable!()
+                    };
                     this.state.as_mut().project_replace(State::ChunkRead {
                         fut: (this.chunk_read)(body),
                     });
@@ -418,9 +641,9 @@ where
                 }
                 StateProj::ExpectWrite { fut } => {
                     let body = ready!(fut.poll(cx))?;
-                    this.state.as_mut().project_replace(State::ChunkRead {
-                        fut: (this.chunk_read)(body),
-                    });
+                    // client can send body without waiting for continue. decode what's already
+                    // in read buffer before reading from io.
+                    this.state.as_mut().project_replace(State::Body { body });
                 }
                 StateProj::None => unreachable!(
                     "None variant is only used internally and must not be observable from stream consumer."
@@ -2,7 +2,7 @@ use core::fmt;
 
 use std::io;
 
-use crate::error::HttpServiceError;
+use crate::error::{BodySizeMismatch, HttpServiceError};
 
 use super::proto::error::ProtoError;
 
@@ -11,11 +11,24 @@ pub enum Error<S, B> {
     KeepAliveExpire,
     /// socket fail to receive a complete request head in given time window.
     RequestTimeout,
+    /// socket fail to write response at the rate of
+    /// [HttpServiceConfig::min_write_rate](crate::config::HttpServiceConfig::min_write_rate).
+    WriteRateTooLow,
+    /// socket sent requests at a rate higher than
+    /// [HttpServiceConfig::max_requests_per_second](crate::config::HttpServiceConfig::max_requests_per_second).
+    TooManyRequests,
+    /// service call is stuck longer than
+    /// [HttpServiceConfig::stuck_request_threshold](crate::config::HttpServiceConfig::stuck_request_threshold)
+    /// and aborted.
+    StuckRequest,
     Closed,
     /// service error. terminate connection right away.
     Service(S),
     /// service response body error. terminate connection right away.
     Body(B),
+    /// service response body yielded different amount of bytes than it's size. terminate
+    /// connection right away.
+    BodySizeMismatch(BodySizeMismatch),
     /// socket and/or runtime error. terminate connection right away.
     Io(io::Error),
     /// http/1 protocol error. transform into http response and send to client.
@@ -32,9 +45,13 @@ where
         match *self {
             Self::KeepAliveExpire => f.write_str("Keep-Alive time expired"),
             Self::RequestTimeout => f.write_str("request head time out"),
+            Self::WriteRateTooLow => f.write_str("response write rate too low"),
+            Self::TooManyRequests => f.write_str("request rate too high"),
+            Self::StuckRequest => f.write_str("service call stuck"),
             Self::Closed => f.write_str("closed"),
             Self::Service(ref e) => fmt::Debug::fmt(e, f),
             Self::Body(ref e) => fmt::Debug::fmt(e, f),
+            Self::BodySizeMismatch(ref e) => fmt::Debug::fmt(e, f),
             Self::Io(ref e) => fmt::Debug::fmt(e, f),
             Self::Proto(ref e) => fmt::Debug::fmt(e, f),
         }
@@ -1,3 +1,4 @@
+pub mod codec;
 pub mod proto;
 
 pub(crate) mod dispatcher;
@@ -13,3 +14,6 @@ pub use self::service::H1Service;
 
 #[cfg(feature = "io-uring")]
 mod dispatcher_uring;
+
+#[cfg(test)]
+mod conformance;
@@ -1,14 +1,18 @@
 use core::convert::Infallible;
 
-use std::io::Write;
+use std::io::{self, Write};
 
 use crate::{
     bytes::{buf::Chain, Buf, BufMut, BufMutWriter, Bytes, BytesMut, EitherBuf},
-    util::buffered::{BufWrite, ListWriteBuf, WriteBuf},
+    config::WriteBufStrategy,
+    util::buffered::{BufInterest, BufWrite, ListWriteBuf, WriteBuf},
 };
 
 /// trait for add http/1 data to buffer that implement [BufWrite] trait.
 pub trait H1BufWrite: BufWrite {
+    /// length of bytes buffered and not written to io yet.
+    fn buffered_len(&self) -> usize;
+
     /// write http response head(status code and reason line, header lines) to buffer with fallible
     /// closure. on error path the buffer is reverted back to state before method was called.
     #[inline]
@@ -45,11 +49,30 @@ pub trait H1BufWrite: BufWrite {
             Ok::<_, Infallible>(())
         });
     }
+
+    /// add `connection: close` header to the last response head in buffer when it's not written
+    /// to io yet. return true when the header is added.
+    ///
+    /// default implementation does not track response head and always return false.
+    #[inline]
+    fn close_last_head(&mut self) -> bool {
+        false
+    }
 }
 
-impl H1BufWrite for BytesMut {}
+impl H1BufWrite for BytesMut {
+    #[inline]
+    fn buffered_len(&self) -> usize {
+        self.len()
+    }
+}
 
-impl<const BUF_LIMIT: usize> H1BufWrite for WriteBuf<BUF_LIMIT> {}
+impl<const BUF_LIMIT: usize> H1BufWrite for WriteBuf<BUF_LIMIT> {
+    #[inline]
+    fn buffered_len(&self) -> usize {
+        self.len()
+    }
+}
 
 // as special type for eof chunk when using transfer-encoding: chunked
 type Eof = Chain<Chain<Bytes, Bytes>, &'static [u8]>;
@@ -57,6 +80,11 @@ type Eof = Chain<Chain<Bytes, Bytes>, &'static [u8]>;
 type EncodedBuf<B, B2> = EitherBuf<B, EitherBuf<B2, &'static [u8]>>;
 
 impl<const BUF_LIMIT: usize> H1BufWrite for ListWriteBuf<EncodedBuf<Bytes, Eof>, BUF_LIMIT> {
+    #[inline]
+    fn buffered_len(&self) -> usize {
+        self.len()
+    }
+
     fn write_buf_head<F, T, E>(&mut self, func: F) -> Result<T, E>
     where
         F: FnOnce(&mut BytesMut) -> Result<T, E>,
@@ -94,6 +122,14 @@ where
     L: H1BufWrite,
     R: H1BufWrite,
 {
+    #[inline]
+    fn buffered_len(&self) -> usize {
+        match *self {
+            Self::Left(ref l) => l.buffered_len(),
+            Self::Right(ref r) => r.buffered_len(),
+        }
+    }
+
     #[inline]
     fn write_buf_head<F, T, E>(&mut self, func: F) -> Result<T, E>
     where
@@ -128,4 +164,328 @@ where
             Self::Right(ref mut r) => r.write_buf_bytes_chunked(bytes),
         }
     }
+
+    #[inline]
+    fn close_last_head(&mut self) -> bool {
+        match *self {
+            Self::Left(ref mut l) => l.close_last_head(),
+            Self::Right(ref mut r) => r.close_last_head(),
+        }
+    }
+}
+
+const CLOSE: &[u8] = b"\r\nconnection: close";
+
+// find position in response head where connection close header line should be inserted.
+// return None when head is not complete, already carries the header or is a protocol upgrade.
+fn close_position(head: &[u8]) -> Option<usize> {
+    if head.get(9..12) == Some(b"101") {
+        return None;
+    }
+
+    let end = head.windows(4).position(|w| w == b"\r\n\r\n")?;
+
+    let closed = head[..end].split(|b| *b == b'\n').skip(1).any(|line| {
+        let Some(idx) = line.iter().position(|b| *b == b':') else {
+            return false;
+        };
+        let (name, value) = line.split_at(idx);
+        name.trim_ascii().eq_ignore_ascii_case(b"connection")
+            && value[1..]
+                .split(|b| *b == b',')
+                .any(|v| v.trim_ascii().eq_ignore_ascii_case(b"close"))
+    });
+
+    (!closed).then_some(end)
+}
+
+// number of responses in one sample window of AdaptiveWriteBuf. strategy is decided at the end of
+// every window.
+const SAMPLE_RESPONSES: usize = 16;
+
+// average size of response body chunk below which flat buffer is used. copying small chunks into
+// one buffer is cheaper than writing them with vectored io.
+const FLAT_CHUNK_SIZE: usize = 1024;
+
+// average size of response body chunk above which vectored buffer is used. copying large chunks is
+// more expensive than writing them as is.
+const VECTORED_CHUNK_SIZE: usize = 16 * 1024;
+
+/// Write buffer that switches between vectored [ListWriteBuf] and flat [WriteBuf] according to
+/// [WriteBufStrategy].
+///
+/// With [WriteBufStrategy::Auto] responses of a connection are sampled in windows and buffer type is
+/// switched at the start of a new response when the other type clearly fits response pattern better.
+/// Switch only happens when all previous responses are written to io so no pending bytes have to be
+/// carried over between buffers.
+pub struct AdaptiveWriteBuf<const LIMIT: usize> {
+    buf: EitherBuf<ListWriteBuf<EncodedBuf<Bytes, Eof>, LIMIT>, WriteBuf<LIMIT>>,
+    sample: Option<Sample>,
+    // offset of the last response head counting from the first byte not written to io yet.
+    // None when the head is (partially) written or there is no head in buffer.
+    head: Option<usize>,
+}
+
+#[derive(Default)]
+struct Sample {
+    responses: usize,
+    chunks: usize,
+    bytes: usize,
+}
+
+impl<const LIMIT: usize> AdaptiveWriteBuf<LIMIT> {
+    /// Construct a new buffer with given strategy. vectored indicates if io is able to perform
+    /// vectored write. When it's false flat buffer is always used.
+    pub fn new(strategy: WriteBufStrategy, vectored: bool) -> Self {
+        let (buf, sample) = match strategy {
+            WriteBufStrategy::Auto if vectored => (EitherBuf::Left(Default::default()), Some(Sample::default())),
+            WriteBufStrategy::Vectored if vectored => (EitherBuf::Left(Default::default()), None),
+            _ => (EitherBuf::Right(WriteBuf::new()), None),
+        };
+        Self {
+            buf,
+            sample,
+            head: None,
+        }
+    }
+
+    /// Check if buffer is currently using vectored write.
+    pub fn is_vectored(&self) -> bool {
+        matches!(self.buf, EitherBuf::Left(_))
+    }
+
+    fn sample_chunk(&mut self, len: usize) {
+        if let Some(ref mut sample) = self.sample {
+            sample.chunks += 1;
+            sample.bytes += len;
+        }
+    }
+
+    fn sample_response(&mut self) {
+        let Some(ref mut sample) = self.sample else { return };
+
+        if sample.responses < SAMPLE_RESPONSES {
+            sample.responses += 1;
+            return;
+        }
+
+        // previous responses are not fully written. defer to the start of next response.
+        if self.buf.want_write_io() {
+            return;
+        }
+
+        let avg = sample.bytes.checked_div(sample.chunks).unwrap_or(0);
+        *sample = Sample::default();
+
+        match self.buf {
+            EitherBuf::Left(_) if avg < FLAT_CHUNK_SIZE => self.buf = EitherBuf::Right(WriteBuf::new()),
+            EitherBuf::Right(_) if avg > VECTORED_CHUNK_SIZE => self.buf = EitherBuf::Left(Default::default()),
+            _ => {}
+        }
+    }
+}
+
+impl<const LIMIT: usize> BufInterest for AdaptiveWriteBuf<LIMIT> {
+    #[inline]
+    fn want_write_buf(&self) -> bool {
+        self.buf.want_write_buf()
+    }
+
+    #[inline]
+    fn want_write_io(&self) -> bool {
+        self.buf.want_write_io()
+    }
+}
+
+impl<const LIMIT: usize> BufWrite for AdaptiveWriteBuf<LIMIT> {
+    #[inline]
+    fn write_buf<F, T, E>(&mut self, func: F) -> Result<T, E>
+    where
+        F: FnOnce(&mut BytesMut) -> Result<T, E>,
+    {
+        self.buf.write_buf(func)
+    }
+
+    fn do_io<Io: io::Write>(&mut self, io: &mut Io) -> io::Result<()> {
+        let len = self.buf.buffered_len();
+        let res = self.buf.do_io(io);
+        if let Some(head) = self.head {
+            let written = len - self.buf.buffered_len();
+            self.head = head.checked_sub(written);
+        }
+        res
+    }
+}
+
+impl<const LIMIT: usize> H1BufWrite for AdaptiveWriteBuf<LIMIT> {
+    #[inline]
+    fn buffered_len(&self) -> usize {
+        self.buf.buffered_len()
+    }
+
+    #[inline]
+    fn write_buf_head<F, T, E>(&mut self, func: F) -> Result<T, E>
+    where
+        F: FnOnce(&mut BytesMut) -> Result<T, E>,
+    {
+        self.sample_response();
+        let offset = self.buf.buffered_len();
+        self.buf.write_buf_head(func).map(|t| {
+            self.head = Some(offset);
+            t
+        })
+    }
+
+    #[inline]
+    fn write_buf_static(&mut self, bytes: &'static [u8]) {
+        self.buf.write_buf_static(bytes)
+    }
+
+    #[inline]
+    fn write_buf_bytes(&mut self, bytes: Bytes) {
+        self.sample_chunk(bytes.len());
+        self.buf.write_buf_bytes(bytes)
+    }
+
+    #[inline]
+    fn write_buf_bytes_chunked(&mut self, bytes: Bytes) {
+        self.sample_chunk(bytes.len());
+        self.buf.write_buf_bytes_chunked(bytes)
+    }
+
+    fn close_last_head(&mut self) -> bool {
+        let Some(offset) = self.head else { return false };
+
+        match self.buf {
+            EitherBuf::Left(ref mut list) => list.replace_at(offset, |item| {
+                let EitherBuf::Left(ref head) = *item else { return None };
+                let pos = close_position(head)?;
+                let mut buf = BytesMut::with_capacity(head.len() + CLOSE.len());
+                buf.put_slice(&head[..pos]);
+                buf.put_slice(CLOSE);
+                buf.put_slice(&head[pos..]);
+                Some(EitherBuf::Left(buf.freeze()))
+            }),
+            EitherBuf::Right(ref mut flat) => flat
+                .write_buf(|buf| {
+                    let Some(pos) = close_position(&buf[offset..]) else {
+                        return Ok::<_, Infallible>(false);
+                    };
+                    let tail = buf.split_off(offset + pos);
+                    buf.put_slice(CLOSE);
+                    buf.unsplit(tail);
+                    Ok(true)
+                })
+                .unwrap(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    type Buf = AdaptiveWriteBuf<{ 1024 * 1024 }>;
+
+    fn drain(buf: &mut Buf, io: &mut Vec<u8>) {
+        while buf.want_write_io() {
+            buf.do_io(io).unwrap();
+        }
+    }
+
+    #[test]
+    fn adaptive_write_buf() {
+        assert!(!Buf::new(WriteBufStrategy::Auto, false).is_vectored());
+        assert!(!Buf::new(WriteBufStrategy::Vectored, false).is_vectored());
+        assert!(!Buf::new(WriteBufStrategy::Flat, true).is_vectored());
+
+        let mut buf = Buf::new(WriteBufStrategy::Auto, true);
+        assert!(buf.is_vectored());
+
+        let mut io = Vec::new();
+        let mut expected = Vec::new();
+
+        let mut write = |buf: &mut Buf, chunk: &[u8]| {
+            buf.write_buf_head(|b| {
+                b.put_slice(b"HEAD\r\n");
+                Ok::<_, Infallible>(())
+            })
+            .unwrap();
+            buf.write_buf_bytes(Bytes::copy_from_slice(chunk));
+            buf.write_buf_bytes_chunked(Bytes::copy_from_slice(chunk));
+            buf.write_buf_static(b"0\r\n\r\n");
+
+            expected.extend_from_slice(b"HEAD\r\n");
+            expected.extend_from_slice(chunk);
+            expected.extend_from_slice(format!("{:X}\r\n", chunk.len()).as_bytes());
+            expected.extend_from_slice(chunk);
+            expected.extend_from_slice(b"\r\n0\r\n\r\n");
+        };
+
+        // small chunks switch to flat buffer at start of a new response.
+        for _ in 0..=SAMPLE_RESPONSES {
+            write(&mut buf, b"small");
+            drain(&mut buf, &mut io);
+        }
+        assert!(!buf.is_vectored());
+
+        // large chunks switch back to vectored buffer. pending bytes delay switching until they are
+        // written to io.
+        for _ in 0..SAMPLE_RESPONSES {
+            drain(&mut buf, &mut io);
+            write(&mut buf, &[b'a'; VECTORED_CHUNK_SIZE * 2]);
+        }
+        write(&mut buf, b"pending");
+        assert!(!buf.is_vectored());
+        drain(&mut buf, &mut io);
+
+        write(&mut buf, b"small");
+        assert!(buf.is_vectored());
+        drain(&mut buf, &mut io);
+
+        assert_eq!(io, expected);
+    }
+
+    #[test]
+    fn close_last_head() {
+        fn head(buf: &mut Buf, head: &'static [u8]) {
+            buf.write_buf_head(|b| {
+                b.put_slice(head);
+                Ok::<_, Infallible>(())
+            })
+            .unwrap();
+            buf.write_buf_bytes(Bytes::from_static(b"body"));
+        }
+
+        for strategy in [WriteBufStrategy::Vectored, WriteBufStrategy::Flat] {
+            let mut buf = Buf::new(strategy, true);
+            let mut io = Vec::new();
+
+            assert!(!buf.close_last_head());
+
+            head(&mut buf, b"HTTP/1.1 200 OK\r\ncontent-length: 4\r\n\r\n");
+            head(&mut buf, b"HTTP/1.1 200 OK\r\ncontent-length: 4\r\n\r\n");
+            assert!(buf.close_last_head());
+            // header is only added once.
+            assert!(!buf.close_last_head());
+            drain(&mut buf, &mut io);
+
+            assert_eq!(
+                io,
+                b"HTTP/1.1 200 OK\r\ncontent-length: 4\r\n\r\nbody\
+                HTTP/1.1 200 OK\r\ncontent-length: 4\r\nconnection: close\r\n\r\nbody"
+            );
+
+            // written head can not be changed.
+            assert!(!buf.close_last_head());
+
+            head(
+                &mut buf,
+                b"HTTP/1.1 101 Switching Protocols\r\nupgrade: websocket\r\n\r\n",
+            );
+            assert!(!buf.close_last_head());
+            head(&mut buf, b"HTTP/1.1 200 OK\r\nConnection: keep-alive, Close\r\n\r\n");
+            assert!(!buf.close_last_head());
+        }
+    }
 }
@@ -303,17 +303,18 @@ impl TransferCoding {
         }
     }
 
-    /// Encode eof. Return `EOF` state of encoder
-    pub fn encode_eof<W>(&mut self, buf: &mut W)
+    /// Encode eof. Error when body is shorter than it's length and connection must be closed.
+    pub fn encode_eof<W>(&mut self, buf: &mut W) -> Result<(), ProtoError>
     where
         W: H1BufWrite,
     {
         match *self {
             Self::Eof | Self::Upgrade | Self::Length(0) => {}
             Self::EncodeChunked => buf.write_buf_static(b"0\r\n\r\n"),
-            Self::Length(n) => unreachable!("UnexpectedEof for Length Body with {} remaining", n),
+            Self::Length(_) => return Err(ProtoError::BodyLengthMismatch),
             _ => unreachable!(),
         }
+        Ok(())
     }
 
     /// decode body. See [ChunkResult] for detailed outcome.
@@ -562,7 +563,7 @@ mod test {
 
         assert_eq!(dst.buf(), b"7\r\nfoo bar\r\nD\r\nbaz quux herp\r\n");
 
-        encoder.encode_eof(dst);
+        encoder.encode_eof(dst).unwrap();
 
         assert_eq!(dst.buf(), b"7\r\nfoo bar\r\nD\r\nbaz quux herp\r\n0\r\n\r\n");
     }
@@ -587,8 +588,20 @@ mod test {
             assert_eq!(dst.buf(), b"foo barb");
         }
 
-        encoder.encode_eof(dst);
+        encoder.encode_eof(dst).unwrap();
         assert_eq!(dst.buf().len(), max_len);
         assert_eq!(dst.buf(), b"foo barb");
     }
+
+    #[test]
+    fn encode_length_short() {
+        let mut encoder = TransferCoding::length(8);
+
+        let dst = &mut WriteBuf::<1024>::default();
+
+        encoder.encode(Bytes::from("foo"), dst);
+
+        assert!(matches!(encoder.encode_eof(dst), Err(ProtoError::BodyLengthMismatch)));
+        assert_eq!(dst.buf(), b"foo");
+    }
 }
@@ -2,22 +2,48 @@ use core::mem;
 
 use std::net::SocketAddr;
 
-use crate::http::{header::HeaderMap, Extensions};
+use crate::{
+    config::{UriNormalization, DEFAULT_MAX_URI_LENGTH},
+    http::{header::HeaderMap, uri::Scheme, Extensions},
+    util::scratch::Scratch,
+};
+
+use super::decode::HeadCache;
 
 /// Context is connection specific struct contain states for processing.
 pub struct Context<'a, D, const HEADER_LIMIT: usize> {
     addr: SocketAddr,
     state: ContextState,
-    // header map reused by next request.
-    header: Option<HeaderMap>,
+    // pool of header maps reused by following requests.
+    headers: Vec<HeaderMap>,
     // http extensions reused by next request.
     exts: Extensions,
+    // keep raw request head bytes in request extensions.
+    raw_head: bool,
+    // scratch arena shared with requests through extensions.
+    scratch: Option<Scratch>,
+    // reuse decoded parts of the most recent request head for byte identical head.
+    head_cache: bool,
+    cached_head: Option<HeadCache>,
+    // max length of request target in request line.
+    max_uri_len: usize,
+    // max size of encoded response head.
+    max_head_size: usize,
+    // write response header names with their original casing.
+    header_case: bool,
+    // strip hop-by-hop headers and check singleton headers of request.
+    normalize_headers: bool,
+    // validation and normalization of request target.
+    uri_normalization: UriNormalization,
+    // scheme used for request uri in origin-form.
+    scheme: Scheme,
     date: &'a D,
 }
 
 // A set of state for current request that are used after request's ownership is passed
 // to service call.
-struct ContextState(u8);
+#[derive(Clone, Copy)]
+pub(super) struct ContextState(u8);
 
 impl ContextState {
     // Enable when current request has 100-continue header.
@@ -47,6 +73,13 @@ impl ContextState {
 }
 
 impl<'a, D, const HEADER_LIMIT: usize> Context<'a, D, HEADER_LIMIT> {
+    /// Max capacity of HeaderMap cached by Context for reuse. It's enough for a request with
+    /// `HEADER_LIMIT` headers and no less than 128.
+    pub const MAX_CACHED_HEADER_CAPACITY: usize = if HEADER_LIMIT > 64 { HEADER_LIMIT * 2 } else { 128 };
+
+    /// Max number of HeaderMap pooled by Context for reuse.
+    pub const MAX_POOLED_HEADERS: usize = 4;
+
     /// Context is constructed with reference of certain type that impl [DateTime] trait.
     #[inline]
     pub fn new(date: &'a D) -> Self {
@@ -59,24 +92,180 @@ impl<'a, D, const HEADER_LIMIT: usize> Context<'a, D, HEADER_LIMIT> {
         Self {
             addr,
             state: ContextState::new(),
-            header: None,
+            headers: Vec::new(),
             exts: Extensions::new(),
+            raw_head: false,
+            scratch: None,
+            head_cache: false,
+            cached_head: None,
+            max_uri_len: DEFAULT_MAX_URI_LENGTH,
+            max_head_size: usize::MAX,
+            header_case: false,
+            normalize_headers: false,
+            uri_normalization: UriNormalization::Disable,
+            scheme: Scheme::HTTP,
             date,
         }
     }
 
+    /// Enable storing raw request head bytes as [RawRequestHead](crate::http::RawRequestHead) in
+    /// request extensions for all following requests.
+    #[inline]
+    pub fn keep_raw_head(&mut self) {
+        self.raw_head = true;
+    }
+
+    /// Return true if raw request head bytes would be stored in request extensions.
+    #[inline]
+    pub const fn is_keep_raw_head(&self) -> bool {
+        self.raw_head
+    }
+
+    /// Enable storing a handle of connection's [Scratch] arena in request extensions for all
+    /// following requests.
+    #[inline]
+    pub fn enable_scratch(&mut self) {
+        self.scratch.get_or_insert_with(Scratch::new);
+    }
+
+    /// Return connection's [Scratch] arena if it's enabled.
+    #[inline]
+    pub fn scratch(&self) -> Option<&Scratch> {
+        self.scratch.as_ref()
+    }
+
+    /// Reset scratch arena for next request. When the arena is still borrowed by previous request
+    /// it's replaced with a new one.
+    pub(super) fn reset_scratch(&mut self) -> Option<&Scratch> {
+        let scratch = self.scratch.as_mut()?;
+        if !scratch.try_reset() {
+            *scratch = Scratch::new();
+        }
+        Some(scratch)
+    }
+
+    /// Enable caching decoded parts of the most recent request head for all following requests.
+    /// A request head byte identical to the cached one is decoded by cloning the cached parts
+    /// instead of parsing it again.
+    #[inline]
+    pub fn cache_request_head(&mut self) {
+        self.head_cache = true;
+    }
+
+    /// Return true if decoded request head is cached.
+    #[inline]
+    pub const fn is_cache_request_head(&self) -> bool {
+        self.head_cache
+    }
+
+    pub(super) fn cached_head(&self) -> Option<&HeadCache> {
+        self.cached_head.as_ref()
+    }
+
+    pub(super) fn set_cached_head(&mut self, head: HeadCache) {
+        self.cached_head = Some(head);
+    }
+
+    /// Enable writing response header names with casing from [HeaderCaseMap](crate::http::HeaderCaseMap)
+    /// in response extensions or title case for all following responses.
+    #[inline]
+    pub fn preserve_header_case(&mut self) {
+        self.header_case = true;
+    }
+
+    /// Return true if response header names are written with preserved casing.
+    #[inline]
+    pub const fn is_preserve_header_case(&self) -> bool {
+        self.header_case
+    }
+
+    /// Enable normalization of request headers for all following requests. See
+    /// [HttpServiceConfig::disable_request_header_normalization](crate::config::HttpServiceConfig::disable_request_header_normalization)
+    /// for detail.
+    #[inline]
+    pub fn normalize_headers(&mut self) {
+        self.normalize_headers = true;
+    }
+
+    /// Return true if request headers are normalized.
+    #[inline]
+    pub const fn is_normalize_headers(&self) -> bool {
+        self.normalize_headers
+    }
+
+    /// Set validation and normalization of request target for all following requests.
+    ///
+    /// Default to [UriNormalization::Disable].
+    #[inline]
+    pub fn set_uri_normalization(&mut self, normalization: UriNormalization) {
+        self.uri_normalization = normalization;
+    }
+
+    /// Return validation and normalization of request target.
+    #[inline]
+    pub const fn uri_normalization(&self) -> UriNormalization {
+        self.uri_normalization
+    }
+
+    /// Set max length of request target in bytes for all following requests.
+    ///
+    /// Default to [DEFAULT_MAX_URI_LENGTH].
+    #[inline]
+    pub fn max_uri_length(&mut self, len: usize) {
+        self.max_uri_len = len;
+    }
+
+    /// Return max length of request target in bytes.
+    #[inline]
+    pub const fn max_uri_len(&self) -> usize {
+        self.max_uri_len
+    }
+
+    /// Set max size of encoded head in bytes for all following responses.
+    ///
+    /// Default to no limit.
+    #[inline]
+    pub fn max_response_head_size(&mut self, size: usize) {
+        self.max_head_size = size;
+    }
+
+    /// Return max size of encoded response head in bytes.
+    #[inline]
+    pub const fn max_response_head_len(&self) -> usize {
+        self.max_head_size
+    }
+
+    /// Set scheme of request uri for all following requests. It's used together with `Host` header
+    /// to complete request target in origin-form(`/path?query`) into an absolute uri.
+    ///
+    /// Default to [Scheme::HTTP].
+    #[inline]
+    pub fn set_scheme(&mut self, scheme: Scheme) {
+        self.scheme = scheme;
+    }
+
+    /// Return scheme of request uri.
+    #[inline]
+    pub const fn scheme(&self) -> &Scheme {
+        &self.scheme
+    }
+
     /// Get Date type from Context.
     #[inline]
     pub fn date(&self) -> &D {
         self.date
     }
 
-    /// Take ownership of HeaderMap stored in Context.
+    /// Take ownership of a HeaderMap pooled by Context.
+    ///
+    /// When the pool is empty a new HeaderMap is constructed.
     ///
-    /// When Context does not have one a new HeaderMap is constructed.
+    /// Request headers are decoded into the map and moved to service with request. Maps are put
+    /// back to the pool from response after it's headers are encoded and from request that failed
+    /// to be decoded.
     #[inline]
     pub fn take_headers(&mut self) -> HeaderMap {
-        self.header.take().unwrap_or_else(HeaderMap::new)
+        self.headers.pop().unwrap_or_default()
     }
 
     /// Take ownership of Extensions stored in Context.
@@ -85,11 +274,18 @@ impl<'a, D, const HEADER_LIMIT: usize> Context<'a, D, HEADER_LIMIT> {
         mem::take(&mut self.exts)
     }
 
-    /// Replace a new HeaderMap in current Context.
+    /// Put a HeaderMap back to the pool of current Context.
+    ///
+    /// HeaderMap with capacity larger than [Context::MAX_CACHED_HEADER_CAPACITY] is dropped so a
+    /// single response with large amount of headers does not grow memory held by connection. Map
+    /// without allocated capacity or exceeding [Context::MAX_POOLED_HEADERS] is dropped as well.
     #[inline]
     pub fn replace_headers(&mut self, headers: HeaderMap) {
         debug_assert!(headers.is_empty());
-        self.header = Some(headers);
+        let cap = headers.capacity();
+        if cap != 0 && cap <= Self::MAX_CACHED_HEADER_CAPACITY && self.headers.len() < Self::MAX_POOLED_HEADERS {
+            self.headers.push(headers);
+        }
     }
 
     /// Replace a new Extensions in current Context.
@@ -105,6 +301,14 @@ impl<'a, D, const HEADER_LIMIT: usize> Context<'a, D, HEADER_LIMIT> {
         self.state = ContextState::new();
     }
 
+    pub(super) const fn state(&self) -> ContextState {
+        self.state
+    }
+
+    pub(super) fn set_state(&mut self, state: ContextState) {
+        self.state = state;
+    }
+
     /// Set Context's state to EXPECT header received.
     #[inline]
     pub fn set_expect_header(&mut self) {
@@ -165,3 +369,68 @@ impl<'a, D, const HEADER_LIMIT: usize> Context<'a, D, HEADER_LIMIT> {
         &self.addr
     }
 }
+
+#[cfg(test)]
+mod test {
+    use crate::{
+        bytes::BytesMut,
+        http::header::{HeaderName, HeaderValue},
+    };
+
+    use super::*;
+
+    #[test]
+    fn cached_header_capacity() {
+        let mut ctx = Context::<_, 64>::new(&());
+
+        let mut headers = ctx.take_headers();
+        headers.insert("x-a", HeaderValue::from_static("996"));
+        headers.clear();
+        let cap = headers.capacity();
+        ctx.replace_headers(headers);
+
+        // small map is reused with it's capacity.
+        assert_eq!(ctx.take_headers().capacity(), cap);
+
+        let mut headers = HeaderMap::new();
+        for i in 0..200 {
+            let name = HeaderName::from_bytes(format!("x-{i}").as_bytes()).unwrap();
+            headers.insert(name, HeaderValue::from_static("996"));
+        }
+        headers.clear();
+        assert!(headers.capacity() > Context::<(), 64>::MAX_CACHED_HEADER_CAPACITY);
+        ctx.replace_headers(headers);
+
+        // large map is dropped and a fresh one is constructed.
+        assert_eq!(ctx.take_headers().capacity(), 0);
+    }
+
+    #[test]
+    fn pooled_headers() {
+        let mut ctx = Context::<_, 64>::new(&());
+
+        for _ in 0..Context::<(), 64>::MAX_POOLED_HEADERS + 1 {
+            ctx.replace_headers(HeaderMap::with_capacity(8));
+        }
+
+        // map without capacity is not pooled.
+        ctx.replace_headers(HeaderMap::new());
+
+        for _ in 0..Context::<(), 64>::MAX_POOLED_HEADERS {
+            assert!(ctx.take_headers().capacity() > 0);
+        }
+        assert_eq!(ctx.take_headers().capacity(), 0);
+    }
+
+    #[test]
+    fn reclaim_headers_from_bad_request() {
+        let mut ctx = Context::<_, 64>::new(&());
+        ctx.replace_headers(HeaderMap::with_capacity(8));
+
+        let mut buf = BytesMut::from(&b"GET / HTTP/1.1\r\ncontent-length: 1\r\ncontent-length: 2\r\n\r\n"[..]);
+        assert!(ctx.decode_head::<4096>(&mut buf).is_err());
+
+        // header map of failed request is put back to the pool.
+        assert!(ctx.take_headers().capacity() > 0);
+    }
+}
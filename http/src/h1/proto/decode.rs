@@ -1,24 +1,50 @@
+use std::borrow::Cow;
+
 use httparse::Status;
 use xitca_unsafe_collection::uninit;
 
 use crate::{
     bytes::{Buf, Bytes, BytesMut},
+    config::UriNormalization,
     http::{
+        complete_uri,
         header::{HeaderMap, HeaderName, HeaderValue, CONNECTION, CONTENT_LENGTH, EXPECT, TRANSFER_ENCODING, UPGRADE},
-        Extension, Method, Request, RequestExt, Uri, Version,
+        normalize_request_headers, Extension, Method, RawRequestHead, Request, RequestExt, Uri, Version,
     },
 };
 
-use super::{codec::TransferCoding, context::Context, error::ProtoError, header::HeaderIndex};
+use super::{
+    codec::TransferCoding,
+    context::{Context, ContextState},
+    error::ProtoError,
+    header::HeaderIndex,
+};
 
 type Decoded = (Request<RequestExt<()>>, TransferCoding);
 
+// decoded parts of the most recent request head. the raw bytes of head are kept as they are and
+// a following head is only matched when it's byte identical to them. any difference of bytes
+// falls back to parsing.
+pub(super) struct HeadCache {
+    raw: Bytes,
+    method: Method,
+    version: Version,
+    uri: Uri,
+    headers: HeaderMap,
+    decoder: TransferCoding,
+    state: ContextState,
+}
+
 impl<D, const MAX_HEADERS: usize> Context<'_, D, MAX_HEADERS> {
     // decode head and generate request and body decoder.
     pub fn decode_head<const READ_BUF_LIMIT: usize>(
         &mut self,
         buf: &mut BytesMut,
     ) -> Result<Option<Decoded>, ProtoError> {
+        if let Some(decoded) = self.decode_cached_head(buf) {
+            return Ok(Some(decoded));
+        }
+
         let mut req = httparse::Request::new(&mut []);
         let mut headers = uninit::uninit_array::<_, MAX_HEADERS>();
 
@@ -29,7 +55,15 @@ impl<D, const MAX_HEADERS: usize> Context<'_, D, MAX_HEADERS> {
 
                 let method = Method::from_bytes(req.method.unwrap().as_bytes())?;
 
-                let uri = req.path.unwrap().parse::<Uri>()?;
+                let path = req.path.unwrap();
+                if path.len() > self.max_uri_len() {
+                    return Err(ProtoError::UriTooLong);
+                }
+
+                let uri = match normalize_target(path, self.uri_normalization()).ok_or(ProtoError::Uri)? {
+                    Cow::Borrowed(path) => path.parse::<Uri>()?,
+                    Cow::Owned(path) => Uri::try_from(path)?,
+                };
 
                 // Set connection type when doing version match.
                 let version = if req.version.unwrap() == 1 {
@@ -57,9 +91,23 @@ impl<D, const MAX_HEADERS: usize> Context<'_, D, MAX_HEADERS> {
                 headers.reserve(headers_len);
 
                 // write headers to headermap and update request states.
-                header_idx_slice
+                let res = header_idx_slice
                     .iter()
-                    .try_for_each(|idx| self.try_write_header(&mut headers, &mut decoder, idx, &slice, version))?;
+                    .try_for_each(|idx| self.try_write_header(&mut headers, &mut decoder, idx, &slice, version))
+                    .and_then(|_| {
+                        if self.is_normalize_headers() && !normalize_request_headers(&mut headers) {
+                            Err(ProtoError::HeaderName)
+                        } else {
+                            Ok(())
+                        }
+                    });
+
+                if let Err(e) = res {
+                    // put header map back to pool for next request.
+                    headers.clear();
+                    self.replace_headers(headers);
+                    return Err(e);
+                }
 
                 // set method to context so it can pass method to response.
                 match method {
@@ -82,11 +130,30 @@ impl<D, const MAX_HEADERS: usize> Context<'_, D, MAX_HEADERS> {
                 *req.headers_mut() = headers;
                 *req.extensions_mut() = extensions;
 
+                complete_uri(&mut req, self.scheme());
+
+                if self.is_cache_request_head() {
+                    self.set_cached_head(HeadCache {
+                        raw: slice.clone(),
+                        method: req.method().clone(),
+                        version,
+                        uri: req.uri().clone(),
+                        headers: req.headers().clone(),
+                        decoder: decoder.clone(),
+                        state: self.state(),
+                    });
+                }
+
+                self.finish_request(&mut req, slice);
+
                 Ok(Some((req, decoder)))
             }
 
             Status::Partial => {
-                if buf.remaining() >= READ_BUF_LIMIT {
+                // check request target before the whole request line is buffered.
+                if partial_uri_len(buf) > self.max_uri_len() {
+                    Err(ProtoError::UriTooLong)
+                } else if buf.remaining() >= READ_BUF_LIMIT {
                     Err(ProtoError::HeaderTooLarge)
                 } else {
                     Ok(None)
@@ -95,6 +162,51 @@ impl<D, const MAX_HEADERS: usize> Context<'_, D, MAX_HEADERS> {
         }
     }
 
+    // decode request head byte identical to the cached one by cloning it's decoded parts.
+    fn decode_cached_head(&mut self, buf: &mut BytesMut) -> Option<Decoded> {
+        let head = self.cached_head()?;
+        let len = head.raw.len();
+
+        if buf.get(..len)? != head.raw {
+            return None;
+        }
+
+        let ext = Extension::new(*self.socket_addr());
+        let mut req = Request::new(RequestExt::from_parts((), ext));
+
+        *req.method_mut() = head.method.clone();
+        *req.version_mut() = head.version;
+        *req.uri_mut() = head.uri.clone();
+
+        let decoder = head.decoder.clone();
+        let state = head.state;
+
+        // clone headers into cached headermap.
+        let mut headers = self.take_headers();
+        let head = self.cached_head().unwrap();
+        headers.extend(head.headers.iter().map(|(name, value)| (name.clone(), value.clone())));
+        *req.headers_mut() = headers;
+
+        self.set_state(state);
+        *req.extensions_mut() = self.take_extensions();
+
+        let slice = buf.split_to(len).freeze();
+        self.finish_request(&mut req, slice);
+
+        Some((req, decoder))
+    }
+
+    // insert connection specific extensions to decoded request.
+    fn finish_request(&mut self, req: &mut Request<RequestExt<()>>, slice: Bytes) {
+        if self.is_keep_raw_head() {
+            req.extensions_mut().insert(RawRequestHead(slice));
+        }
+
+        if let Some(scratch) = self.reset_scratch() {
+            req.extensions_mut().insert(scratch.clone());
+        }
+    }
+
     pub fn try_write_header(
         &mut self,
         headers: &mut HeaderMap,
@@ -131,6 +243,12 @@ impl<D, const MAX_HEADERS: usize> Context<'_, D, MAX_HEADERS> {
                     .and_then(|v| v.parse::<u64>().ok())
                     .ok_or(ProtoError::HeaderValue)?;
 
+                // duplicate with the same value is collapsed by header normalization.
+                if self.is_normalize_headers() && matches!(*decoder, TransferCoding::Length(l) if l == len) {
+                    headers.append(name, value);
+                    return Ok(());
+                }
+
                 decoder.try_set(TransferCoding::length(len))?;
             }
             CONNECTION => self.try_set_close_from_header(&value)?,
@@ -167,8 +285,104 @@ impl<D, const MAX_HEADERS: usize> Context<'_, D, MAX_HEADERS> {
     }
 }
 
+// length of request target received so far from a partial request line.
+fn partial_uri_len(buf: &[u8]) -> usize {
+    let line = match buf.iter().position(|b| *b == b'\n') {
+        Some(idx) => &buf[..idx],
+        None => buf,
+    };
+
+    match line.iter().position(|b| *b == b' ') {
+        Some(idx) => {
+            let target = &line[idx + 1..];
+            target
+                .iter()
+                .position(|b| *b == b' ' || *b == b'\r')
+                .unwrap_or(target.len())
+        }
+        None => 0,
+    }
+}
+
+// validate request target and remove dot segments from it's path. None is returned when target
+// contains byte that must be percent-encoded.
+fn normalize_target(target: &str, normalization: UriNormalization) -> Option<Cow<'_, str>> {
+    if normalization == UriNormalization::Disable {
+        return Some(Cow::Borrowed(target));
+    }
+
+    if target.bytes().any(|b| !(0x20..0x7f).contains(&b)) {
+        return None;
+    }
+
+    // path of origin-form starts from the beginning and path of absolute-form starts after
+    // authority. authority-form and asterisk-form have no path.
+    let start = if target.starts_with('/') {
+        0
+    } else {
+        let Some(idx) = target.find("://").map(|idx| idx + 3) else {
+            return Some(Cow::Borrowed(target));
+        };
+        match target[idx..].find(['/', '?']).map(|i| idx + i) {
+            Some(start) if target[start..].starts_with('/') => start,
+            _ => return Some(Cow::Borrowed(target)),
+        }
+    };
+
+    let end = target[start..].find('?').map_or(target.len(), |i| start + i);
+
+    match remove_dot_segments(&target[start..end], normalization == UriNormalization::Strict) {
+        Cow::Borrowed(_) => Some(Cow::Borrowed(target)),
+        Cow::Owned(path) => Some(Cow::Owned([&target[..start], &path, &target[end..]].concat())),
+    }
+}
+
+// remove_dot_segments of RFC 3986 on path starting with '/'. percent-encoded bytes are not
+// decoded except dots in strict mode.
+fn remove_dot_segments(path: &str, strict: bool) -> Cow<'_, str> {
+    let is_dot = |seg: &str| seg == "." || (strict && seg.eq_ignore_ascii_case("%2e"));
+    let is_dot_dot = |seg: &str| {
+        seg == ".."
+            || (strict
+                && ["%2e%2e", ".%2e", "%2e."]
+                    .iter()
+                    .any(|dots| seg.eq_ignore_ascii_case(dots)))
+    };
+
+    if !path.split('/').any(|seg| is_dot(seg) || is_dot_dot(seg)) {
+        return Cow::Borrowed(path);
+    }
+
+    let mut out = Vec::new();
+
+    let mut segs = path.split('/').skip(1).peekable();
+    while let Some(seg) = segs.next() {
+        if is_dot_dot(seg) {
+            out.pop();
+        } else if !is_dot(seg) {
+            out.push(seg);
+            continue;
+        }
+
+        // path ending with dot segment keeps it's trailing slash.
+        if segs.peek().is_none() {
+            out.push("");
+        }
+    }
+
+    let mut res = String::with_capacity(path.len());
+    for seg in out {
+        res.push('/');
+        res.push_str(seg);
+    }
+
+    Cow::Owned(res)
+}
+
 #[cfg(test)]
 mod test {
+    use crate::http::{uri::Scheme, Scratch};
+
     use super::*;
 
     #[test]
@@ -209,6 +423,267 @@ mod test {
         assert!(!ctx.is_connection_closed());
     }
 
+    #[test]
+    fn raw_head() {
+        let mut ctx = Context::<_, 4>::new(&());
+
+        let head = b"\
+                POST /path?q=1 HTTP/1.1\r\n\
+                x-LOWER-upper: Value\r\n\
+                content-Length: 4\r\n\
+                HOST: localhost\r\n\
+                \r\n\
+                ";
+        let mut buf = BytesMut::from(&head[..]);
+        buf.extend_from_slice(b"body");
+
+        let (req, _) = ctx.decode_head::<128>(&mut buf).unwrap().unwrap();
+        assert!(req.extensions().get::<RawRequestHead>().is_none());
+
+        ctx.keep_raw_head();
+
+        let mut buf = BytesMut::from(&head[..]);
+        buf.extend_from_slice(b"body");
+
+        let (req, _) = ctx.decode_head::<128>(&mut buf).unwrap().unwrap();
+        let raw = req.extensions().get::<RawRequestHead>().unwrap();
+        assert_eq!(raw.0, &head[..]);
+        assert_eq!(buf, &b"body"[..]);
+    }
+
+    #[test]
+    fn scratch() {
+        let mut ctx = Context::<_, 4>::new(&());
+
+        let decode = |ctx: &mut Context<'_, _, 4>| {
+            let mut buf = BytesMut::from(&b"GET / HTTP/1.1\r\n\r\n"[..]);
+            let (req, _) = ctx.decode_head::<128>(&mut buf).unwrap().unwrap();
+            req
+        };
+
+        assert!(decode(&mut ctx).extensions().get::<Scratch>().is_none());
+
+        ctx.enable_scratch();
+
+        let req = decode(&mut ctx);
+        let scratch = req.extensions().get::<Scratch>().unwrap();
+        let handle = scratch.arena().unwrap().alloc_str("996");
+        drop(req);
+
+        // arena is reset for next request.
+        let req = decode(&mut ctx);
+        let scratch = req.extensions().get::<Scratch>().unwrap();
+        let res = std::panic::catch_unwind(|| scratch.arena().unwrap().str(handle).len());
+        assert!(res.is_err());
+
+        // arena still borrowed by previous request is replaced.
+        let arena = scratch.arena();
+        let req = decode(&mut ctx);
+        assert!(req.extensions().get::<Scratch>().unwrap().arena().is_some());
+        drop(arena);
+    }
+
+    #[test]
+    fn head_cache() {
+        const HEAD: &[u8] = b"\
+                POST /path?q=1 HTTP/1.0\r\n\
+                x-custom: 996\r\n\
+                content-length: 4\r\n\
+                expect: 100-continue\r\n\
+                \r\n\
+                ";
+
+        let decode = |ctx: &mut Context<'_, _, 4>, head: &[u8]| {
+            ctx.reset();
+            let mut buf = BytesMut::from(head);
+            buf.extend_from_slice(b"body");
+            let (req, decoder) = ctx.decode_head::<256>(&mut buf).unwrap().unwrap();
+            assert_eq!(buf, &b"body"[..]);
+            (req, decoder)
+        };
+
+        let head = |from: &str, to: &str| std::str::from_utf8(HEAD).unwrap().replace(from, to);
+
+        // header value shared with cached head.
+        let ptr = |req: &Request<RequestExt<()>>| req.headers().get("x-custom").unwrap().as_bytes().as_ptr();
+
+        let mut ctx = Context::<_, 4>::new(&());
+
+        let (req, _) = decode(&mut ctx, HEAD);
+        let (req2, _) = decode(&mut ctx, HEAD);
+        assert_ne!(ptr(&req), ptr(&req2));
+
+        ctx.cache_request_head();
+        ctx.keep_raw_head();
+
+        let (req, decoder) = decode(&mut ctx, HEAD);
+        let (req2, decoder2) = decode(&mut ctx, HEAD);
+        assert_eq!(ptr(&req), ptr(&req2));
+        assert_eq!(req.method(), req2.method());
+        assert_eq!(req.version(), req2.version());
+        assert_eq!(req.uri(), req2.uri());
+        assert_eq!(req.headers(), req2.headers());
+        assert_eq!(decoder, decoder2);
+        assert_eq!(req2.extensions().get::<RawRequestHead>().unwrap().0, HEAD);
+        assert!(ctx.is_connection_closed());
+        assert!(ctx.is_expect_header());
+
+        // one different byte anywhere in head falls back to parsing.
+        let (path, _) = decode(&mut ctx, head("/path", "/patH").as_bytes());
+        assert_ne!(ptr(&req), ptr(&path));
+        assert_eq!(path.uri().path(), "/patH");
+
+        let (value, _) = decode(&mut ctx, head("996", "997").as_bytes());
+        assert_ne!(ptr(&path), ptr(&value));
+        assert_eq!(value.headers().get("x-custom").unwrap(), "997");
+
+        let (req, decoder) = decode(&mut ctx, head("1.0", "1.1").replace(": 4", ": 5").as_bytes());
+        assert_ne!(ptr(&value), ptr(&req));
+        assert_eq!(decoder, TransferCoding::length(5));
+        assert!(!ctx.is_connection_closed());
+
+        // the most recent parsed head replaces cached one.
+        let (req2, _) = decode(&mut ctx, head("1.0", "1.1").replace(": 4", ": 5").as_bytes());
+        assert_eq!(ptr(&req), ptr(&req2));
+        let (req3, _) = decode(&mut ctx, HEAD);
+        assert_ne!(ptr(&req), ptr(&req3));
+    }
+
+    #[test]
+    fn uri_scheme_authority() {
+        let mut ctx = Context::<_, 4>::new(&());
+
+        let mut decode = |head: &[u8]| {
+            let mut buf = BytesMut::from(head);
+            let (req, _) = ctx.decode_head::<128>(&mut buf).unwrap().unwrap();
+            req.uri().clone()
+        };
+
+        let uri = decode(b"GET /path?q=1 HTTP/1.1\r\nHost: localhost:8080\r\n\r\n");
+        assert_eq!(uri.scheme_str(), Some("http"));
+        assert_eq!(uri.authority().unwrap(), "localhost:8080");
+        assert_eq!(uri, "http://localhost:8080/path?q=1");
+
+        // absolute-form is not affected by Host header.
+        let uri = decode(b"GET https://example.com/ HTTP/1.1\r\nHost: localhost\r\n\r\n");
+        assert_eq!(uri, "https://example.com/");
+
+        // no Host header and invalid Host header.
+        let uri = decode(b"GET /path HTTP/1.0\r\n\r\n");
+        assert_eq!(uri, "/path");
+        let uri = decode(b"GET /path HTTP/1.1\r\nHost: a b\r\n\r\n");
+        assert_eq!(uri, "/path");
+
+        // authority-form and asterisk-form.
+        let uri = decode(b"CONNECT localhost:443 HTTP/1.1\r\nHost: localhost:443\r\n\r\n");
+        assert_eq!(uri.scheme(), None);
+        assert_eq!(uri.authority().unwrap(), "localhost:443");
+        let uri = decode(b"OPTIONS * HTTP/1.1\r\nHost: localhost\r\n\r\n");
+        assert_eq!(uri, "*");
+
+        ctx.set_scheme(Scheme::HTTPS);
+        let mut buf = BytesMut::from(&b"GET / HTTP/1.1\r\nHost: localhost\r\n\r\n"[..]);
+        let (req, _) = ctx.decode_head::<128>(&mut buf).unwrap().unwrap();
+        assert_eq!(req.uri(), "https://localhost/");
+        assert_eq!(req.version(), Version::HTTP_11);
+    }
+
+    #[test]
+    fn uri_too_long() {
+        let mut ctx = Context::<_, 4>::new(&());
+        ctx.max_uri_length(16);
+
+        let mut buf = BytesMut::from(&b"GET /0123456789abcde HTTP/1.1\r\n\r\n"[..]);
+        assert!(ctx.decode_head::<128>(&mut buf).unwrap().is_some());
+
+        let mut buf = BytesMut::from(&b"GET /0123456789abcdef HTTP/1.1\r\n\r\n"[..]);
+        assert!(matches!(ctx.decode_head::<128>(&mut buf), Err(ProtoError::UriTooLong)));
+
+        // partial request line is rejected as soon as it's target exceeds limit.
+        let mut buf = BytesMut::from(&b"GET /0123456789abcd"[..]);
+        assert!(ctx.decode_head::<128>(&mut buf).unwrap().is_none());
+        buf.extend_from_slice(b"ef");
+        assert!(matches!(ctx.decode_head::<128>(&mut buf), Err(ProtoError::UriTooLong)));
+
+        // percent-encoded length is used.
+        let mut buf = BytesMut::from(&b"GET /%20%20%20%20%20%20 HTTP/1.1\r\n\r\n"[..]);
+        assert!(matches!(ctx.decode_head::<128>(&mut buf), Err(ProtoError::UriTooLong)));
+    }
+
+    #[test]
+    fn normalize_request_target() {
+        let cases = [
+            ("/", Some("/")),
+            ("/a/b/c", Some("/a/b/c")),
+            ("/a/../b", Some("/b")),
+            ("/admin/../secret", Some("/secret")),
+            ("/a/./b", Some("/a/b")),
+            ("/a/b/..", Some("/a/")),
+            ("/a/b/.", Some("/a/b/")),
+            ("/..", Some("/")),
+            ("/../../a", Some("/a")),
+            ("/a//../b", Some("/a/b")),
+            ("//a/../b", Some("//b")),
+            ("/a/.../b", Some("/a/.../b")),
+            ("/a/..b/c", Some("/a/..b/c")),
+            ("/a/../b?c=/../d", Some("/b?c=/../d")),
+            ("/a?/../b", Some("/a?/../b")),
+            // percent-encoded slash is never decoded.
+            ("/a/..%2f../b", Some("/a/..%2f../b")),
+            ("/a/%2F../b", Some("/a/%2F../b")),
+            // percent-encoded dots are left as is.
+            ("/a/%2e%2e/b", Some("/a/%2e%2e/b")),
+            ("/a/.%2E/b", Some("/a/.%2E/b")),
+            ("http://localhost/a/../b?c", Some("http://localhost/b?c")),
+            ("http://localhost?/../b", Some("http://localhost?/../b")),
+            ("localhost:443", Some("localhost:443")),
+            ("*", Some("*")),
+            // bytes must be percent-encoded.
+            ("/a\0b", None),
+            ("/a\tb", None),
+            ("/a\x7fb", None),
+            ("/你好", None),
+        ];
+
+        for (target, res) in cases {
+            let normalized = normalize_target(target, UriNormalization::Standard);
+            assert_eq!(normalized.as_deref(), res, "{target}");
+        }
+
+        let cases = [
+            ("/a/%2e%2e/b", "/b"),
+            ("/a/.%2E/b", "/b"),
+            ("/a/%2E./b", "/b"),
+            ("/a/%2e/b", "/a/b"),
+            ("/a/b/%2e", "/a/b/"),
+            ("/a/%2e%2e%2e/b", "/a/%2e%2e%2e/b"),
+            ("/a/..%2f../b", "/a/..%2f../b"),
+        ];
+
+        for (target, res) in cases {
+            let normalized = normalize_target(target, UriNormalization::Strict);
+            assert_eq!(normalized.as_deref(), Some(res), "{target}");
+        }
+
+        for target in ["/a/../b", "/你好", "/a\x7fb"] {
+            let normalized = normalize_target(target, UriNormalization::Disable);
+            assert_eq!(normalized.as_deref(), Some(target));
+        }
+    }
+
+    #[test]
+    fn uri_normalization() {
+        let mut ctx = Context::<_, 4>::new(&());
+        ctx.set_uri_normalization(UriNormalization::Standard);
+
+        let mut buf = BytesMut::from(&b"GET /admin/../secret?a=b HTTP/1.1\r\nHost: localhost\r\n\r\n"[..]);
+        let (req, _) = ctx.decode_head::<128>(&mut buf).unwrap().unwrap();
+        assert_eq!(req.uri(), "http://localhost/secret?a=b");
+
+        let mut buf = BytesMut::from("GET /你好 HTTP/1.1\r\n\r\n".as_bytes());
+        assert!(matches!(ctx.decode_head::<128>(&mut buf), Err(ProtoError::Uri)));
+    }
+
     #[test]
     fn transfer_encoding() {
         let mut ctx = Context::<_, 4>::new(&());
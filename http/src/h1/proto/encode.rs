@@ -1,15 +1,25 @@
+use core::{
+    convert::Infallible,
+    mem,
+    pin::Pin,
+    task::{Context as TaskContext, Poll},
+};
+
+use std::io;
+
 use futures_core::stream::Stream;
-use tracing::{debug, warn};
+use tracing::{debug, error, warn};
 
 use crate::{
-    body::BodySize,
-    bytes::BytesMut,
+    body::{exact_body_hint, none_body_hint, BodySize},
+    bytes::{Bytes, BytesMut},
     date::DateTime,
     http::{
-        header::{HeaderMap, CONNECTION, CONTENT_LENGTH, DATE, TE, TRANSFER_ENCODING, UPGRADE},
+        header::{HeaderMap, HeaderName, CONNECTION, CONTENT_LENGTH, DATE, TE, TRANSFER_ENCODING, UPGRADE},
         response::Parts,
-        Extensions, StatusCode, Version,
+        Extensions, HeaderCaseMap, StatusCode, Version,
     },
+    util::cached::CachedResponse,
 };
 
 use super::{buf_write::H1BufWrite, codec::TransferCoding, context::Context, error::ProtoError};
@@ -19,6 +29,30 @@ pub fn encode_continue(buf: &mut impl H1BufWrite) {
     buf.write_buf_static(b"HTTP/1.1 100 Continue\r\n\r\n");
 }
 
+/// encode informational(1xx) response head. it has no body and does not affect state of connection.
+pub fn encode_informational(status: StatusCode, headers: &HeaderMap, buf: &mut impl H1BufWrite) {
+    // reason of 103 is not known by http crate.
+    let reason = match status.as_u16() {
+        103 => "Early Hints",
+        _ => status.canonical_reason().unwrap_or("<none>"),
+    };
+    let _ = buf.write_buf_head(|buf| {
+        buf.extend_from_slice(b"HTTP/1.1 ");
+        buf.extend_from_slice(status.as_str().as_bytes());
+        buf.extend_from_slice(b" ");
+        buf.extend_from_slice(reason.as_bytes());
+        buf.extend_from_slice(b"\r\n");
+        for (name, value) in headers {
+            buf.extend_from_slice(name.as_str().as_bytes());
+            buf.extend_from_slice(b": ");
+            buf.extend_from_slice(value.as_bytes());
+            buf.extend_from_slice(b"\r\n");
+        }
+        buf.extend_from_slice(b"\r\n");
+        Ok::<_, Infallible>(())
+    });
+}
+
 impl<D, const MAX_HEADERS: usize> Context<'_, D, MAX_HEADERS>
 where
     D: DateTime,
@@ -31,16 +65,73 @@ where
         buf.write_buf_head(|buf| self.encode_head_inner(parts, body, buf))
     }
 
-    fn encode_head_inner<B>(&mut self, parts: Parts, body: &B, buf: &mut BytesMut) -> Result<TransferCoding, ProtoError>
+    /// Encode response head and the first chunk of it's body into the same buffer. It's for small
+    /// body so head and body can be written to io together.
+    ///
+    /// `size` is the size of whole body before the chunk is taken from it. Chunked body opts out
+    /// and it's chunk is encoded after head as a separate piece.
+    pub fn encode_head_with_body<W>(
+        &mut self,
+        parts: Parts,
+        size: BodySize,
+        chunk: Bytes,
+        buf: &mut W,
+    ) -> Result<TransferCoding, ProtoError>
+    where
+        W: H1BufWrite,
+    {
+        let mut chunk = Some(chunk);
+
+        let mut encoding = buf.write_buf_head(|buf| {
+            let mut encoding = self.encode_head_inner(parts, &SizeHint(size), buf)?;
+            if !matches!(encoding, TransferCoding::EncodeChunked) {
+                encoding.encode(chunk.take().unwrap(), buf);
+            }
+            Ok::<_, ProtoError>(encoding)
+        })?;
+
+        if let Some(chunk) = chunk {
+            encoding.encode(chunk, buf);
+        }
+
+        Ok(encoding)
+    }
+
+    fn encode_head_inner<B>(
+        &mut self,
+        mut parts: Parts,
+        body: &B,
+        buf: &mut BytesMut,
+    ) -> Result<TransferCoding, ProtoError>
     where
         B: Stream,
     {
+        if let Some(cached) = parts.extensions.remove::<CachedResponse>() {
+            if let Some(encoding) = self.try_encode_cached(&cached, &mut parts, body, buf) {
+                return Ok(encoding);
+            }
+            cached.restore_headers(&mut parts.headers);
+        }
+
         let version = parts.version;
         let status = parts.status;
 
+        let no_body = is_no_body_status(status);
+        if no_body {
+            if !matches!(BodySize::from_stream(body), BodySize::None | BodySize::Sized(0)) {
+                debug!(target: "h1_encode", "response with {status} status code can not have body. body is dropped");
+            }
+            // 204 response can not have content-length or transfer-encoding header. 304 response
+            // keeps them as they describe the body of 200 response to the same request.
+            if status == StatusCode::NO_CONTENT {
+                parts.headers.remove(CONTENT_LENGTH);
+                parts.headers.remove(TRANSFER_ENCODING);
+            }
+        }
+
         // decide if content-length or transfer-encoding header would be skipped.
         let skip_len = match (status, version) {
-            (StatusCode::SWITCHING_PROTOCOLS, _) => true,
+            (StatusCode::SWITCHING_PROTOCOLS | StatusCode::NO_CONTENT, _) => true,
             // Sending content-length or transfer-encoding header on 2xx response
             // to CONNECT is forbidden in RFC 7231.
             (s, _) if self.is_connect_method() && s.is_success() => true,
@@ -54,12 +145,77 @@ where
         // In some error cases, we don't know about the invalid message until already
         // pushing some bytes onto the `buf`. In those cases, we don't want to send
         // the half-pushed message, so rewind to before.
-        // let orig_len = buf.len();
+        let orig_len = buf.len();
 
         // encode version, status code and reason
         encode_version_status_reason(buf, version, status);
 
-        self.encode_headers(parts.headers, parts.extensions, body, buf, skip_len)
+        let encoding = self
+            .encode_headers(parts.headers, parts.extensions, body, buf, skip_len)
+            .map_err(|e| {
+                buf.truncate(orig_len);
+                e
+            })?;
+
+        // body of 204 and 304 response is never written. See RFC 9110 section 6.4.1.
+        Ok(if no_body { TransferCoding::eof() } else { encoding })
+    }
+}
+
+impl<D, const MAX_HEADERS: usize> Context<'_, D, MAX_HEADERS>
+where
+    D: DateTime,
+{
+    // copy pre-built head of cached response into buf. return None when it can not be used.
+    fn try_encode_cached<B>(
+        &mut self,
+        cached: &CachedResponse,
+        parts: &mut Parts,
+        body: &B,
+        buf: &mut BytesMut,
+    ) -> Option<TransferCoding>
+    where
+        B: Stream,
+    {
+        // connection header differs on close and head/connect responses have different body framing.
+        if self.is_connection_closed()
+            || self.is_head_method()
+            || self.is_connect_method()
+            || self.is_preserve_header_case()
+            || is_no_body_status(parts.status)
+        {
+            return None;
+        }
+
+        let len = match BodySize::from_stream(body) {
+            BodySize::Sized(len) => Some(len),
+            _ => None,
+        };
+
+        let head = cached.head(parts.status, parts.version, &parts.headers, len)?;
+
+        let date_len = head.date_offset.map(|_| D::DATE_VALUE_LENGTH).unwrap_or(0);
+        if head.bytes.len() + date_len > self.max_response_head_len() {
+            return None;
+        }
+
+        buf.reserve(head.bytes.len() + date_len);
+        match head.date_offset {
+            Some(offset) => {
+                buf.extend_from_slice(&head.bytes[..offset]);
+                self.date().with_date(|slice| buf.extend_from_slice(slice));
+                buf.extend_from_slice(&head.bytes[offset..]);
+            }
+            None => buf.extend_from_slice(&head.bytes),
+        }
+
+        // put header map and extensions back to cache.
+        self.replace_headers(mem::take(&mut parts.headers));
+        let mut extensions = mem::take(&mut parts.extensions);
+        extensions.clear();
+        self.replace_extensions(extensions);
+
+        Some(TransferCoding::length(cached.body().len() as u64))
     }
 }
 
@@ -115,6 +271,14 @@ where
 
         let mut encoding = TransferCoding::eof();
 
+        // Some when header names are written with preserved casing.
+        let case = self
+            .is_preserve_header_case()
+            .then(|| extensions.get::<HeaderCaseMap>());
+
+        // header lines exceeding this length would not be written.
+        let max_len = buf.len().saturating_add(self.max_response_head_len());
+
         // use the shortest header name as default
         let mut name = TE;
 
@@ -158,10 +322,28 @@ where
 
             let value = value.as_bytes();
 
+            let len = if is_continue {
+                value.len() + 1
+            } else {
+                name.as_str().len() + value.len() + 4
+            };
+
+            if buf.len() + len > max_len {
+                error!(
+                    target: "h1_encode",
+                    "response header {name} exceeds max response head size of {} bytes",
+                    self.max_response_head_len()
+                );
+                return Err(ProtoError::ResponseHeadTooLarge);
+            }
+
             if is_continue {
                 buf.reserve(value.len() + 1);
                 buf.extend_from_slice(b",");
                 buf.extend_from_slice(value);
+            } else if let Some(map) = case {
+                encode_name_case(buf, &name, map);
+                buf.extend_from_slice(value);
             } else {
                 let name = name.as_str().as_bytes();
 
@@ -174,7 +356,13 @@ where
         }
 
         if self.is_connection_closed() {
-            buf.extend_from_slice(b"\r\nconnection: close");
+            match case {
+                Some(map) => {
+                    encode_name_case(buf, &CONNECTION, map);
+                    buf.extend_from_slice(b"close");
+                }
+                None => buf.extend_from_slice(b"\r\nconnection: close"),
+            }
         }
 
         // encode transfer-encoding or content-length
@@ -184,15 +372,26 @@ where
                     encoding = TransferCoding::eof();
                 }
                 BodySize::Stream => {
-                    buf.extend_from_slice(b"\r\ntransfer-encoding: chunked");
+                    match case {
+                        Some(map) => {
+                            encode_name_case(buf, &TRANSFER_ENCODING, map);
+                            buf.extend_from_slice(b"chunked");
+                        }
+                        None => buf.extend_from_slice(b"\r\ntransfer-encoding: chunked"),
+                    }
                     encoding = TransferCoding::encode_chunked();
                 }
                 BodySize::Sized(size) => {
                     let mut buffer = itoa::Buffer::new();
                     let buffer = buffer.format(size).as_bytes();
 
-                    buf.reserve(buffer.len() + 18);
-                    buf.extend_from_slice(b"\r\ncontent-length: ");
+                    match case {
+                        Some(map) => encode_name_case(buf, &CONTENT_LENGTH, map),
+                        None => {
+                            buf.reserve(buffer.len() + 18);
+                            buf.extend_from_slice(b"\r\ncontent-length: ");
+                        }
+                    }
                     buf.extend_from_slice(buffer);
 
                     encoding = TransferCoding::length(size as u64);
@@ -212,7 +411,10 @@ where
         // set date header if there is not any.
         if !skip_date {
             buf.reserve(D::DATE_VALUE_LENGTH + 12);
-            buf.extend_from_slice(b"\r\ndate: ");
+            match case {
+                Some(map) => encode_name_case(buf, &DATE, map),
+                None => buf.extend_from_slice(b"\r\ndate: "),
+            }
             self.date().with_date(|slice| buf.extend_from_slice(slice));
         }
 
@@ -229,13 +431,58 @@ where
     }
 }
 
+// stream type only used for passing body size to response head encoder.
+pub(crate) struct SizeHint(pub(crate) BodySize);
+
+impl Stream for SizeHint {
+    type Item = Result<Bytes, io::Error>;
+
+    fn poll_next(self: Pin<&mut Self>, _: &mut TaskContext<'_>) -> Poll<Option<Self::Item>> {
+        unreachable!("SizeHint must not be polled")
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.0 {
+            BodySize::None => none_body_hint(),
+            BodySize::Sized(size) => exact_body_hint(size),
+            BodySize::Stream => (0, None),
+        }
+    }
+}
+
+/// Return true when response with given status code must not have body. The body of such response
+/// is not written by encoder and dispatcher drops it without polling.
+#[inline]
+pub fn is_no_body_status(status: StatusCode) -> bool {
+    matches!(status, StatusCode::NO_CONTENT | StatusCode::NOT_MODIFIED)
+}
+
+// write header line prefix with name spelled by given case map or in title case.
+#[cold]
+#[inline(never)]
+fn encode_name_case(buf: &mut BytesMut, name: &HeaderName, map: Option<&HeaderCaseMap>) {
+    buf.extend_from_slice(b"\r\n");
+    match map.and_then(|map| map.get(name)) {
+        Some(spelling) => buf.extend_from_slice(spelling.as_bytes()),
+        None => {
+            let mut upper = true;
+            buf.extend(name.as_str().bytes().map(|b| {
+                let b = if upper { b.to_ascii_uppercase() } else { b };
+                upper = b == b'-';
+                b
+            }));
+        }
+    }
+    buf.extend_from_slice(b": ");
+}
+
 #[cfg(test)]
 mod test {
     use crate::{
-        body::{BoxStream, Once},
+        body::{BoxStream, Once, ResponseBody},
         bytes::Bytes,
         date::DateTimeService,
-        http::{HeaderValue, Response},
+        http::{header::CONTENT_TYPE, HeaderValue, Response},
     };
 
     use super::*;
@@ -273,4 +520,287 @@ mod test {
             })
             .await
     }
+
+    #[tokio::test]
+    async fn preserve_header_case() {
+        tokio::task::LocalSet::new()
+            .run_until(async {
+                let date = DateTimeService::new();
+
+                let encode = |preserve: bool, map: Option<HeaderCaseMap>| {
+                    let mut ctx = Context::<_, 64>::new(date.get());
+                    if preserve {
+                        ctx.preserve_header_case();
+                    }
+
+                    let mut res = Response::new(BoxStream::new(Once::new(Bytes::from_static(b"hello"))));
+                    res.headers_mut().insert("x-api-key", HeaderValue::from_static("996"));
+                    res.headers_mut().insert("x-request-id", HeaderValue::from_static("1"));
+                    // static date value for exact bytes assertion.
+                    res.headers_mut().insert(DATE, HeaderValue::from_static("now"));
+                    if let Some(map) = map {
+                        res.extensions_mut().insert(map);
+                    }
+
+                    let (parts, body) = res.into_parts();
+                    let mut buf = BytesMut::new();
+                    ctx.encode_head(parts, &body, &mut buf).unwrap();
+                    buf
+                };
+
+                let map = || {
+                    let mut map = HeaderCaseMap::new();
+                    map.insert("X-API-Key");
+                    map.insert("content-LENGTH");
+                    map
+                };
+
+                assert_eq!(
+                    encode(true, Some(map())).as_ref(),
+                    b"HTTP/1.1 200 OK\r\nX-API-Key: 996\r\nX-Request-Id: 1\r\nDate: now\r\ncontent-LENGTH: 5\r\n\r\n"
+                );
+
+                assert_eq!(
+                    encode(true, None).as_ref(),
+                    b"HTTP/1.1 200 OK\r\nX-Api-Key: 996\r\nX-Request-Id: 1\r\nDate: now\r\nContent-Length: 5\r\n\r\n"
+                );
+
+                // case map is ignored when not enabled.
+                assert_eq!(
+                    encode(false, Some(map())).as_ref(),
+                    b"HTTP/1.1 200 OK\r\nx-api-key: 996\r\nx-request-id: 1\r\ndate: now\r\ncontent-length: 5\r\n\r\n"
+                );
+            })
+            .await
+    }
+
+    #[tokio::test]
+    async fn coalesce_body() {
+        use std::io;
+
+        use crate::{
+            config::WriteBufStrategy,
+            h1::proto::buf_write::AdaptiveWriteBuf,
+            util::buffered::{BufInterest, BufWrite},
+        };
+
+        // io counting write calls and the buffers passed to them.
+        #[derive(Default)]
+        struct CountIo {
+            writes: usize,
+            bufs: usize,
+            bytes: Vec<u8>,
+        }
+
+        impl io::Write for CountIo {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.write_vectored(&[io::IoSlice::new(buf)])
+            }
+
+            fn write_vectored(&mut self, bufs: &[io::IoSlice<'_>]) -> io::Result<usize> {
+                self.writes += 1;
+                self.bufs += bufs.iter().filter(|buf| !buf.is_empty()).count();
+                let len = self.bytes.len();
+                bufs.iter().for_each(|buf| self.bytes.extend_from_slice(buf));
+                Ok(self.bytes.len() - len)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        tokio::task::LocalSet::new()
+            .run_until(async {
+                let date = DateTimeService::new();
+
+                let write = |strategy, coalesce: bool| {
+                    let mut ctx = Context::<_, 64>::new(date.get());
+                    let mut buf = AdaptiveWriteBuf::<{ 1024 * 1024 }>::new(strategy, true);
+
+                    let chunk = Bytes::from_static(&[b'a'; 200]);
+                    let mut res = Response::new(ResponseBody::<BoxStream>::from(chunk.clone()));
+                    res.headers_mut().insert(DATE, HeaderValue::from_static("now"));
+                    let (parts, body) = res.into_parts();
+
+                    let mut encoding = if coalesce {
+                        let size = BodySize::from_stream(&body);
+                        ctx.encode_head_with_body(parts, size, chunk, &mut buf).unwrap()
+                    } else {
+                        let mut encoding = ctx.encode_head(parts, &body, &mut buf).unwrap();
+                        encoding.encode(chunk, &mut buf);
+                        encoding
+                    };
+                    encoding.encode_eof(&mut buf).unwrap();
+                    assert_eq!(encoding, TransferCoding::length(0));
+
+                    let mut io = CountIo::default();
+                    while buf.want_write_io() {
+                        buf.do_io(&mut io).unwrap();
+                    }
+                    io
+                };
+
+                let io = write(WriteBufStrategy::Vectored, false);
+                assert_eq!((io.writes, io.bufs), (1, 2));
+
+                // head and body are one piece of buffer.
+                let io2 = write(WriteBufStrategy::Vectored, true);
+                assert_eq!((io2.writes, io2.bufs), (1, 1));
+                assert_eq!(io.bytes, io2.bytes);
+
+                let io3 = write(WriteBufStrategy::Flat, true);
+                assert_eq!((io3.writes, io3.bufs), (1, 1));
+                assert_eq!(io.bytes, io3.bytes);
+
+                // chunked body opts out and is written as a separate piece.
+                let mut ctx = Context::<_, 64>::new(date.get());
+                let mut buf = AdaptiveWriteBuf::<{ 1024 * 1024 }>::new(WriteBufStrategy::Vectored, true);
+
+                let chunk = Bytes::from_static(&[b'a'; 200]);
+                let mut res = Response::new(ResponseBody::<BoxStream>::from(chunk.clone()));
+                res.headers_mut().insert(DATE, HeaderValue::from_static("now"));
+                res.headers_mut()
+                    .insert(TRANSFER_ENCODING, HeaderValue::from_static("chunked"));
+                let (parts, body) = res.into_parts();
+
+                let size = BodySize::from_stream(&body);
+                let mut encoding = ctx.encode_head_with_body(parts, size, chunk, &mut buf).unwrap();
+                assert_eq!(encoding, TransferCoding::encode_chunked());
+                encoding.encode_eof(&mut buf).unwrap();
+
+                let mut io = CountIo::default();
+                while buf.want_write_io() {
+                    buf.do_io(&mut io).unwrap();
+                }
+                assert_eq!(io.writes, 1);
+                assert!(io.bufs > 1);
+                assert_eq!(
+                    io.bytes,
+                    [
+                        &b"HTTP/1.1 200 OK\r\ndate: now\r\ntransfer-encoding: chunked\r\n\r\nC8\r\n"[..],
+                        &[b'a'; 200],
+                        b"\r\n0\r\n\r\n"
+                    ]
+                    .concat()
+                );
+            })
+            .await
+    }
+
+    #[tokio::test]
+    async fn no_body_status() {
+        tokio::task::LocalSet::new()
+            .run_until(async {
+                let date = DateTimeService::new();
+                let mut ctx = Context::<_, 64>::new(date.get());
+
+                let mut encode = |status| {
+                    let mut res = Response::new(BoxStream::new(Once::new(Bytes::from_static(b"hello"))));
+                    *res.status_mut() = status;
+                    res.headers_mut().insert(CONTENT_LENGTH, HeaderValue::from_static("5"));
+                    let (parts, body) = res.into_parts();
+                    let mut buf = BytesMut::new();
+                    let encoding = ctx.encode_head(parts, &body, &mut buf).unwrap();
+                    (encoding, String::from_utf8(buf.to_vec()).unwrap())
+                };
+
+                let (encoding, head) = encode(StatusCode::NO_CONTENT);
+                assert!(encoding.is_eof());
+                assert!(!head.contains("content-length"));
+
+                let (encoding, head) = encode(StatusCode::NOT_MODIFIED);
+                assert!(encoding.is_eof());
+                assert!(head.contains("\r\ncontent-length: 5\r\n"));
+            })
+            .await
+    }
+
+    #[tokio::test]
+    async fn response_head_too_large() {
+        tokio::task::LocalSet::new()
+            .run_until(async {
+                let date = DateTimeService::new();
+                let mut ctx = Context::<_, 64>::new(date.get());
+                ctx.max_response_head_size(64 * 1024);
+
+                let mut res = Response::new(BoxStream::new(Once::new(Bytes::new())));
+                res.headers_mut().insert("x-small", HeaderValue::from_static("996"));
+                let value = HeaderValue::from_bytes(&vec![b'a'; 10 * 1024 * 1024]).unwrap();
+                res.headers_mut().insert("x-large", value);
+
+                let (parts, body) = res.into_parts();
+
+                let mut buf = BytesMut::from(&b"pending"[..]);
+                let err = ctx.encode_head(parts, &body, &mut buf).err().unwrap();
+                assert!(matches!(err, ProtoError::ResponseHeadTooLarge));
+
+                // head is rewound and the large header is never copied into buffer.
+                assert_eq!(buf.as_ref(), b"pending");
+                assert!(buf.capacity() < 64 * 1024);
+            })
+            .await
+    }
+
+    #[tokio::test]
+    async fn cached_response() {
+        tokio::task::LocalSet::new()
+            .run_until(async {
+                let date = DateTimeService::new();
+
+                let mut res = Response::new(Bytes::from_static(b"Hello, World!"));
+                res.headers_mut()
+                    .insert(CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+                let cached = CachedResponse::new(res);
+
+                let encode = |ctx: &mut Context<'_, _, 64>, res: Response<ResponseBody>| {
+                    let (parts, body) = res.into_parts();
+                    let mut buf = BytesMut::new();
+                    let encoding = ctx.encode_head(parts, &body, &mut buf).unwrap();
+                    (buf, encoding)
+                };
+
+                let expected = date.get().with_date(|date| {
+                    let mut buf =
+                        b"HTTP/1.1 200 OK\r\ncontent-type: text/plain\r\ncontent-length: 13\r\ndate: ".to_vec();
+                    buf.extend_from_slice(date);
+                    buf.extend_from_slice(b"\r\n\r\n");
+                    buf
+                });
+
+                let mut ctx = Context::<_, 64>::new(date.get());
+
+                // pre-built head with patched date.
+                let (buf, encoding) = encode(&mut ctx, cached.response());
+                assert_eq!(buf.as_ref(), expected);
+                assert_eq!(encoding, TransferCoding::length(13));
+
+                // identical to normal encoding of the same response.
+                let mut res = Response::new(ResponseBody::from(Bytes::from_static(b"Hello, World!")));
+                res.headers_mut()
+                    .insert(CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+                assert_eq!(encode(&mut ctx, res).0.as_ref(), expected);
+
+                // modified response falls back to normal encoding with cached headers restored.
+                let mut res = cached.response::<ResponseBody>();
+                res.headers_mut().insert("x-api-key", HeaderValue::from_static("996"));
+                let (buf, _) = encode(&mut ctx, res);
+                assert!(buf.starts_with(b"HTTP/1.1 200 OK\r\nx-api-key: 996\r\ncontent-type: text/plain\r\n"));
+
+                // connection header differs on force close.
+                ctx.set_close();
+                let (buf, _) = encode(&mut ctx, cached.response());
+                assert!(buf.starts_with(b"HTTP/1.1 200 OK\r\ncontent-type: text/plain\r\nconnection: close\r\n"));
+
+                // response to HEAD request has no body.
+                let mut ctx = Context::<_, 64>::new(date.get());
+                ctx.set_head_method();
+                let mut res = cached.response::<ResponseBody>();
+                *res.body_mut() = ResponseBody::None;
+                let (buf, encoding) = encode(&mut ctx, res);
+                assert!(buf.starts_with(b"HTTP/1.1 200 OK\r\ncontent-type: text/plain\r\ndate: "));
+                assert!(encoding.is_eof());
+            })
+            .await
+    }
 }
@@ -5,8 +5,13 @@ pub enum ProtoError {
     HeaderName,
     HeaderValue,
     HeaderTooLarge,
+    BodyTooLarge,
+    ResponseHeadTooLarge,
+    // response body ended before Content-Length of it's head is written.
+    BodyLengthMismatch,
     Method,
     Uri,
+    UriTooLong,
     NewLine,
     Status,
     Token,
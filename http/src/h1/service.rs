@@ -1,4 +1,4 @@
-use core::{future::Future, pin::pin};
+use core::{fmt, future::Future, pin::pin};
 
 use std::net::SocketAddr;
 
@@ -11,6 +11,7 @@ use crate::{
     error::{HttpServiceError, TimeoutError},
     http::{Request, RequestExt, Response},
     service::HttpService,
+    tls::TlsExtensions,
     util::timer::Timeout,
 };
 
@@ -19,6 +20,54 @@ use super::body::RequestBody;
 pub type H1Service<St, S, A, const HEADER_LIMIT: usize, const READ_BUF_LIMIT: usize, const WRITE_BUF_LIMIT: usize> =
     HttpService<St, S, RequestBody, A, HEADER_LIMIT, READ_BUF_LIMIT, WRITE_BUF_LIMIT>;
 
+impl<St, S, A, const HEADER_LIMIT: usize, const READ_BUF_LIMIT: usize, const WRITE_BUF_LIMIT: usize>
+    H1Service<St, S, A, HEADER_LIMIT, READ_BUF_LIMIT, WRITE_BUF_LIMIT>
+{
+    /// Serve a connection provided by caller as http/1 and return when the connection is finished.
+    ///
+    /// See [HttpService::serve_connection] for detail.
+    pub async fn serve_connection<Io, B, BE>(
+        &self,
+        io: Io,
+        addr: Option<SocketAddr>,
+    ) -> Result<(), HttpServiceError<S::Error, BE>>
+    where
+        S: Service<Request<RequestExt<RequestBody>>, Response = Response<B>>,
+        A: Service<Io>,
+        A::Response: AsyncIo,
+        B: Stream<Item = Result<Bytes, BE>>,
+        BE: fmt::Debug,
+        HttpServiceError<S::Error, BE>: From<A::Error>,
+    {
+        let addr = addr.unwrap_or_else(crate::unspecified_socket_addr);
+
+        // at this stage keep-alive timer is used to tracks tls accept timeout.
+        let mut timer = pin!(self.keep_alive());
+
+        let mut io = self
+            .tls_acceptor
+            .call(io)
+            .timeout(timer.as_mut())
+            .await
+            .map_err(|_| HttpServiceError::Timeout(TimeoutError::TlsAccept))??;
+
+        let tls = TlsExtensions::from_stream(&io);
+
+        super::dispatcher::run(
+            &mut io,
+            addr,
+            timer,
+            &self.config,
+            &self.service,
+            self.date.get(),
+            self.drain.as_ref(),
+            tls,
+        )
+        .await
+        .map_err(Into::into)
+    }
+}
+
 impl<St, S, B, BE, A, const HEADER_LIMIT: usize, const READ_BUF_LIMIT: usize, const WRITE_BUF_LIMIT: usize>
     Service<(St, SocketAddr)> for H1Service<St, S, A, HEADER_LIMIT, READ_BUF_LIMIT, WRITE_BUF_LIMIT>
 where
@@ -27,6 +76,7 @@ where
     St: AsyncIo,
     A::Response: AsyncIo,
     B: Stream<Item = Result<Bytes, BE>>,
+    BE: fmt::Debug,
     HttpServiceError<S::Error, BE>: From<A::Error>,
 {
     type Response = ();
@@ -37,26 +87,13 @@ where
     where
         St: 's,
     {
-        async move {
-            // at this stage keep-alive timer is used to tracks tls accept timeout.
-            let mut timer = pin!(self.keep_alive());
-
-            let mut io = self
-                .tls_acceptor
-                .call(io)
-                .timeout(timer.as_mut())
-                .await
-                .map_err(|_| HttpServiceError::Timeout(TimeoutError::TlsAccept))??;
-
-            super::dispatcher::run(&mut io, addr, timer, self.config, &self.service, self.date.get())
-                .await
-                .map_err(Into::into)
-        }
+        async move { self.serve_connection(io, Some(addr)).await }
     }
 }
 
 #[cfg(feature = "io-uring")]
 use {
+    tracing::Instrument,
     xitca_io::{
         io_uring::{AsyncBufRead, AsyncBufWrite},
         net::io_uring::TcpStream,
@@ -68,13 +105,14 @@ use {
 use crate::{
     config::HttpServiceConfig,
     date::{DateTime, DateTimeService},
-    util::timer::KeepAlive,
+    util::{drain::DrainWatch, span, timer::KeepAlive},
 };
 
 #[cfg(feature = "io-uring")]
 pub struct H1UringService<S, A, const HEADER_LIMIT: usize, const READ_BUF_LIMIT: usize, const WRITE_BUF_LIMIT: usize> {
     pub(crate) config: HttpServiceConfig<HEADER_LIMIT, READ_BUF_LIMIT, WRITE_BUF_LIMIT>,
     pub(crate) date: DateTimeService,
+    pub(crate) drain: Option<DrainWatch>,
     pub(crate) service: S,
     pub(crate) tls_acceptor: A,
 }
@@ -90,7 +128,8 @@ impl<S, A, const HEADER_LIMIT: usize, const READ_BUF_LIMIT: usize, const WRITE_B
     ) -> Self {
         Self {
             config,
-            date: DateTimeService::new(),
+            date: DateTimeService::current(),
+            drain: None,
             service,
             tls_acceptor,
         }
@@ -105,6 +144,7 @@ where
     A: Service<TcpStream>,
     A::Response: AsyncBufRead + AsyncBufWrite + 'static,
     B: Stream<Item = Result<Bytes, BE>>,
+    BE: fmt::Debug,
     HttpServiceError<S::Error, BE>: From<A::Error>,
 {
     type Response = ();
@@ -118,7 +158,7 @@ where
         async move {
             let accept_dur = self.config.tls_accept_timeout;
             let deadline = self.date.get().now() + accept_dur;
-            let mut timer = pin!(KeepAlive::new(deadline));
+            let mut timer = pin!(KeepAlive::new(deadline, self.config.keep_alive_granularity));
 
             let io = self
                 .tls_acceptor
@@ -127,10 +167,22 @@ where
                 .await
                 .map_err(|_| HttpServiceError::Timeout(TimeoutError::TlsAccept))??;
 
-            super::dispatcher_uring::Dispatcher::new(io, addr, timer, self.config, &self.service, self.date.get())
-                .run()
-                .await
-                .map_err(Into::into)
+            let tls = TlsExtensions::from_stream(&io);
+
+            super::dispatcher_uring::Dispatcher::new(
+                io,
+                addr,
+                timer,
+                &self.config,
+                &self.service,
+                self.date.get(),
+                self.drain.as_ref(),
+                tls,
+            )
+            .run()
+            .instrument(span::connection("h1", addr, span::next_id()))
+            .await
+            .map_err(Into::into)
         }
     }
 }
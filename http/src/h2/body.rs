@@ -1,32 +1,192 @@
 use core::{
+    cell::{Cell, RefCell},
     pin::Pin,
-    task::{Context, Poll},
+    task::{ready, Context, Poll, Waker},
 };
 
+use std::rc::Rc;
+
 use futures_core::stream::Stream;
 use h2::RecvStream;
 use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
 
-use crate::{bytes::Bytes, error::BodyError};
+use crate::{bytes::Bytes, error::BodyError, http::RequestBodyLimit};
 
 /// Request body type for Http/2 specifically.
-pub struct RequestBody(RecvStream);
+pub struct RequestBody {
+    stream: RecvStream,
+    budget: Option<Rc<BodyBudget>>,
+    // flow control capacity released to peer and not received yet.
+    granted: usize,
+    // flow control capacity of received data waiting for budget to be released to peer.
+    pending: usize,
+    // body of CONNECT request is one half of a tunnel.
+    tunnel: bool,
+    limit: Option<BodyLimit>,
+}
+
+// max bytes of body shared with service and dispatcher. dispatcher tells body exceeded it from
+// the shared state.
+struct BodyLimit {
+    max: RequestBodyLimit,
+    received: u64,
+    // advertised Content-Length of body.
+    length: u64,
+}
+
+impl BodyLimit {
+    fn is_exceeded(&self) -> bool {
+        self.max.is_exceeded() || !self.max.check(self.received.max(self.length))
+    }
+}
+
+impl RequestBody {
+    pub(super) fn new(stream: RecvStream, budget: Option<Rc<BodyBudget>>) -> Self {
+        Self {
+            stream,
+            budget,
+            granted: 0,
+            pending: 0,
+            tunnel: false,
+            limit: None,
+        }
+    }
+
+    // body exceeding max bytes ends with overflow error and the limit is marked as exceeded.
+    pub(super) fn set_limit(&mut self, max: RequestBodyLimit, length: Option<u64>) {
+        self.limit = Some(BodyLimit {
+            max,
+            received: 0,
+            length: length.unwrap_or(0),
+        });
+    }
+
+    fn overflow(max: &RequestBodyLimit) -> BodyError {
+        BodyError::Overflow {
+            limit: usize::try_from(max.get()).unwrap_or(usize::MAX),
+        }
+    }
+
+    // stream reset by peer ends tunnel as a clean closure instead of an error.
+    pub(super) fn set_tunnel(&mut self) {
+        self.tunnel = true;
+    }
+
+    fn poll_release(&mut self, cx: &mut Context<'_>) -> Result<(), h2::Error> {
+        if self.pending == 0 {
+            return Ok(());
+        }
+
+        if let Some(ref budget) = self.budget {
+            if !budget.try_acquire(self.pending) {
+                // capacity is released when other streams of connection free the budget.
+                budget.register(cx.waker());
+                return Ok(());
+            }
+            self.granted += self.pending;
+        }
+
+        let pending = core::mem::take(&mut self.pending);
+        self.stream.flow_control().release_capacity(pending)
+    }
+}
 
 impl Stream for RequestBody {
     type Item = Result<Bytes, BodyError>;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        let stream = &mut self.get_mut().0;
+        let this = self.get_mut();
 
-        stream.poll_data(cx).map(|opt| {
-            opt.map(|res| {
-                let bytes = res?;
-                stream.flow_control().release_capacity(bytes.len())?;
+        // capacity of oversized body is not released and peer is stopped by flow control. body with
+        // Content-Length is known to be oversized before any of it is received.
+        if let Some(ref limit) = this.limit {
+            if limit.is_exceeded() {
+                return Poll::Ready(Some(Err(Self::overflow(&limit.max))));
+            }
+        }
 
-                Ok(bytes)
-            })
+        this.poll_release(cx)?;
+
+        match ready!(this.stream.poll_data(cx)) {
+            Some(Ok(bytes)) => {
+                if let Some(ref budget) = this.budget {
+                    let received = core::cmp::min(bytes.len(), this.granted);
+                    this.granted -= received;
+                    budget.release(received);
+                }
+                if let Some(ref mut limit) = this.limit {
+                    limit.received += bytes.len() as u64;
+                    if limit.is_exceeded() {
+                        return Poll::Ready(Some(Err(Self::overflow(&limit.max))));
+                    }
+                }
+                this.pending += bytes.len();
+                this.poll_release(cx)?;
+                Poll::Ready(Some(Ok(bytes)))
+            }
+            Some(Err(e)) if this.tunnel && e.is_reset() => Poll::Ready(None),
+            Some(Err(e)) => Poll::Ready(Some(Err(e.into()))),
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+impl Drop for RequestBody {
+    fn drop(&mut self) {
+        if let Some(ref budget) = self.budget {
+            budget.release(self.granted);
+        }
+    }
+}
+
+/// Memory budget of request bodies shared by all streams of one http/2 connection.
+///
+/// It tracks flow control capacity released to peer which is not received by request body yet.
+/// This is the amount of body data peer can send on top of initial windows of streams. When
+/// budget is exhausted a stream delays releasing capacity of it's received data until other
+/// streams free the budget, which stops peer from sending more data to it.
+pub(crate) struct BodyBudget {
+    limit: usize,
+    used: Cell<usize>,
+    waiters: RefCell<Vec<Waker>>,
+}
+
+impl BodyBudget {
+    pub(crate) fn new(limit: usize) -> Rc<Self> {
+        Rc::new(Self {
+            limit,
+            used: Cell::new(0),
+            waiters: RefCell::new(Vec::new()),
         })
     }
+
+    // acquire always succeeds when budget is not in use so data larger than limit can make progress.
+    fn try_acquire(&self, n: usize) -> bool {
+        let used = self.used.get();
+        if used == 0 || used + n <= self.limit {
+            self.used.set(used + n);
+            true
+        } else {
+            false
+        }
+    }
+
+    fn release(&self, n: usize) {
+        if n == 0 {
+            return;
+        }
+        self.used.set(self.used.get() - n);
+        for waker in self.waiters.borrow_mut().drain(..) {
+            waker.wake();
+        }
+    }
+
+    fn register(&self, waker: &Waker) {
+        let mut waiters = self.waiters.borrow_mut();
+        if !waiters.iter().any(|w| w.will_wake(waker)) {
+            waiters.push(waker.clone());
+        }
+    }
 }
 
 impl From<RequestBody> for crate::body::RequestBody {
@@ -37,14 +197,14 @@ impl From<RequestBody> for crate::body::RequestBody {
 
 impl From<RecvStream> for RequestBody {
     fn from(stream: RecvStream) -> Self {
-        RequestBody(stream)
+        RequestBody::new(stream, None)
     }
 }
 
 // Skip h2::body::RequestBody type and convert to crate level RequestBody directly
 impl From<RecvStream> for crate::body::RequestBody {
     fn from(stream: RecvStream) -> Self {
-        Self::H2(RequestBody(stream))
+        Self::H2(RequestBody::from(stream))
     }
 }
 
@@ -65,3 +225,122 @@ impl Stream for RequestBodyV2 {
         self.get_mut().0.poll_recv(cx)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use core::future::poll_fn;
+
+    use crate::http::Request;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn reset_disconnect() {
+        tokio::task::LocalSet::new().run_until(reset_disconnect_local()).await
+    }
+
+    async fn reset_disconnect_local() {
+        let (client, server) = tokio::io::duplex(4096);
+        let (tx, rx) = tokio::sync::oneshot::channel();
+
+        let server = tokio::task::spawn_local(async move {
+            let mut conn = h2::server::handshake(server).await.unwrap();
+            let (req, _res) = conn.accept().await.unwrap().unwrap();
+            tokio::spawn(async move { while conn.accept().await.is_some() {} });
+
+            let mut body = RequestBody::from(req.into_body());
+            let chunk = poll_fn(|cx| Pin::new(&mut body).poll_next(cx)).await.unwrap().unwrap();
+            assert_eq!(chunk.as_ref(), b"996");
+            tx.send(()).unwrap();
+            poll_fn(|cx| Pin::new(&mut body).poll_next(cx)).await
+        });
+
+        let (mut client, conn) = h2::client::handshake(client).await.unwrap();
+        tokio::spawn(conn);
+
+        let req = Request::builder().uri("http://localhost/").body(()).unwrap();
+        let (_res, mut stream) = client.send_request(req, false).unwrap();
+        stream.send_data(Bytes::from_static(b"996"), false).unwrap();
+
+        rx.await.unwrap();
+        stream.send_reset(h2::Reason::CANCEL);
+
+        let err = server.await.unwrap().unwrap().unwrap_err();
+        assert!(err.is_disconnect());
+    }
+
+    #[tokio::test]
+    async fn connection_budget() {
+        const STREAMS: usize = 16;
+        const BODY: usize = 16 * 1024;
+        const BUDGET: usize = 4096;
+
+        let (client, server) = tokio::io::duplex(64 * 1024);
+        let (tx, rx) = tokio::sync::oneshot::channel();
+
+        let server = async move {
+            let mut conn = h2::server::Builder::new()
+                .initial_window_size(1024)
+                .initial_connection_window_size(1024 * 1024)
+                .handshake::<_, Bytes>(server)
+                .await
+                .unwrap();
+
+            let budget = BodyBudget::new(BUDGET);
+            let peak = Rc::new(Cell::new(0));
+            let mut handles = Vec::new();
+
+            for _ in 0..STREAMS {
+                let (req, _res) = conn.accept().await.unwrap().unwrap();
+                let mut body = RequestBody::new(req.into_body(), Some(budget.clone()));
+                let (budget, peak) = (budget.clone(), peak.clone());
+                handles.push(tokio::task::spawn_local(async move {
+                    let mut len = 0;
+                    while let Some(chunk) = poll_fn(|cx| Pin::new(&mut body).poll_next(cx)).await {
+                        len += chunk.unwrap().len();
+                        peak.set(core::cmp::max(peak.get(), budget.used.get()));
+                        // slow handler.
+                        tokio::time::sleep(core::time::Duration::from_millis(1)).await;
+                    }
+                    len
+                }));
+            }
+
+            let conn = tokio::task::spawn_local(async move { while conn.accept().await.is_some() {} });
+
+            for handle in handles {
+                assert_eq!(handle.await.unwrap(), BODY);
+            }
+            conn.abort();
+            tx.send(()).unwrap();
+
+            assert!(peak.get() > 0);
+            assert!(peak.get() <= BUDGET);
+            assert_eq!(budget.used.get(), 0);
+        };
+
+        let client = async move {
+            let (mut client, conn) = h2::client::handshake(client).await.unwrap();
+            tokio::task::spawn_local(conn);
+
+            let mut streams = Vec::new();
+            for _ in 0..STREAMS {
+                let req = Request::builder().uri("http://localhost/").body(()).unwrap();
+                let (res, mut stream) = client.send_request(req, false).unwrap();
+                stream.send_data(Bytes::from(vec![0; BODY]), true).unwrap();
+                streams.push((res, stream));
+            }
+
+            // keep streams alive until server finished reading.
+            rx.await.unwrap();
+        };
+
+        tokio::task::LocalSet::new()
+            .run_until(async {
+                let client = tokio::task::spawn_local(client);
+                server.await;
+                client.await.unwrap();
+            })
+            .await;
+    }
+}
@@ -5,10 +5,40 @@ use xitca_service::Service;
 use crate::{
     builder::{marker, HttpServiceBuilder},
     error::BuildError,
+    util::drain::Drain,
 };
 
 use super::service::H2Service;
 
+impl<St, F, FA, const HEADER_LIMIT: usize, const READ_BUF_LIMIT: usize, const WRITE_BUF_LIMIT: usize>
+    HttpServiceBuilder<marker::Http2, St, F, FA, HEADER_LIMIT, READ_BUF_LIMIT, WRITE_BUF_LIMIT>
+{
+    #[cfg(feature = "io-uring")]
+    /// Transform Self to a Http2 service builder that able to take in [xitca_io::net::io_uring::TcpStream]
+    pub fn io_uring(
+        self,
+    ) -> HttpServiceBuilder<
+        marker::Http2Uring,
+        xitca_io::net::io_uring::TcpStream,
+        F,
+        FA,
+        HEADER_LIMIT,
+        READ_BUF_LIMIT,
+        WRITE_BUF_LIMIT,
+    >
+    where
+        FA: Service,
+    {
+        HttpServiceBuilder {
+            factory: self.factory,
+            tls_factory: self.tls_factory,
+            config: self.config,
+            drain: self.drain,
+            _body: std::marker::PhantomData,
+        }
+    }
+}
+
 impl<St, F, Arg, FA, const HEADER_LIMIT: usize, const READ_BUF_LIMIT: usize, const WRITE_BUF_LIMIT: usize> Service<Arg>
     for HttpServiceBuilder<marker::Http2, St, F, FA, HEADER_LIMIT, READ_BUF_LIMIT, WRITE_BUF_LIMIT>
 where
@@ -26,7 +56,35 @@ where
         async {
             let tls_acceptor = self.tls_factory.call(()).await.map_err(BuildError::First)?;
             let service = self.factory.call(arg).await.map_err(BuildError::Second)?;
-            Ok(H2Service::new(self.config, service, tls_acceptor))
+            let mut service = H2Service::new(self.config, service, tls_acceptor);
+            service.drain = self.drain.as_ref().map(Drain::watch);
+            Ok(service)
+        }
+    }
+}
+
+#[cfg(feature = "io-uring")]
+impl<St, F, Arg, FA, const HEADER_LIMIT: usize, const READ_BUF_LIMIT: usize, const WRITE_BUF_LIMIT: usize> Service<Arg>
+    for HttpServiceBuilder<marker::Http2Uring, St, F, FA, HEADER_LIMIT, READ_BUF_LIMIT, WRITE_BUF_LIMIT>
+where
+    F: Service<Arg>,
+    FA: Service,
+{
+    type Response =
+        super::service::H2UringService<F::Response, FA::Response, HEADER_LIMIT, READ_BUF_LIMIT, WRITE_BUF_LIMIT>;
+    type Error = BuildError<FA::Error, F::Error>;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where Self: 'f, Arg: 'f;
+
+    fn call<'s>(&'s self, arg: Arg) -> Self::Future<'s>
+    where
+        Arg: 's,
+    {
+        async {
+            let tls_acceptor = self.tls_factory.call(()).await.map_err(BuildError::First)?;
+            let service = self.factory.call(arg).await.map_err(BuildError::Second)?;
+            let mut service = super::service::H2UringService::new(self.config, service, tls_acceptor);
+            service.drain = self.drain.as_ref().map(Drain::watch);
+            Ok(service)
         }
     }
 }
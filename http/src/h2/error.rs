@@ -1,9 +1,11 @@
-use crate::error::{BodyError, HttpServiceError};
+use crate::error::{BodyError, BodySizeMismatch, HttpServiceError};
 
 #[derive(Debug)]
 pub enum Error<S, B> {
     Service(S),
     Body(B),
+    // response body yielded different amount of bytes than it's size.
+    BodySizeMismatch(BodySizeMismatch),
     // error from h2 crate.
     H2(::h2::Error),
 }
@@ -31,6 +33,13 @@ impl<S, B> From<::h2::Error> for HttpServiceError<S, B> {
 
 impl From<::h2::Error> for BodyError {
     fn from(e: ::h2::Error) -> Self {
-        BodyError::from(Box::new(e) as Box<dyn std::error::Error + Send + Sync>)
+        // stream reset or connection going away initiated by peer.
+        if e.is_remote() && (e.is_reset() || e.is_go_away()) {
+            BodyError::Disconnected
+        } else if e.is_io() {
+            BodyError::Io(e.into_io().unwrap())
+        } else {
+            BodyError::Boxed(Box::new(e))
+        }
     }
 }
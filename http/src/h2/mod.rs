@@ -13,3 +13,6 @@ pub use self::service::H2Service;
 
 #[doc(hidden)]
 pub use self::proto::run;
+
+#[cfg(feature = "io-uring")]
+mod uring;
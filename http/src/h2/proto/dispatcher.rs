@@ -1,4 +1,5 @@
 use core::{
+    cell::Cell,
     cmp, fmt,
     future::{poll_fn, Future},
     marker::PhantomData,
@@ -11,25 +12,44 @@ use std::net::SocketAddr;
 
 use ::h2::{
     server::{Connection, SendResponse},
-    Ping, PingPong,
+    Ping, PingPong, Reason, RecvStream, SendStream,
 };
 use futures_core::stream::Stream;
-use tracing::trace;
+use tokio::time::{interval_at, Instant, Interval, MissedTickBehavior};
+use tracing::{error, trace, Instrument};
 use xitca_io::io::{AsyncRead, AsyncWrite};
 use xitca_service::Service;
 use xitca_unsafe_collection::futures::{Select as _, SelectOutput};
 
 use crate::{
-    body::BodySize,
+    body::{BodyErrorHook, BodySize, BodySizeProbe, ProbeGuard},
     bytes::Bytes,
+    config::{ConnectionEvent, H2Refusal, HttpServiceConfig, StuckRequestAction},
     date::{DateTime, DateTimeHandle},
-    error::HttpServiceError,
-    h2::{body::RequestBody, error::Error},
+    error::{BodySizeMismatch, HttpServiceError},
+    h2::{
+        body::{BodyBudget, RequestBody},
+        error::Error,
+    },
     http::{
-        header::{HeaderMap, HeaderName, HeaderValue, CONNECTION, CONTENT_LENGTH, DATE, TRAILER},
-        Extension, Request, RequestExt, Response, Version,
+        complete_uri,
+        header::{Entry, HeaderMap, HeaderName, CONNECTION, CONTENT_LENGTH, DATE, TRAILER},
+        normalize_request_headers,
+        uri::Scheme,
+        Extension, Method, Protocol, Request, RequestArrival, RequestBodyLimit, RequestExt, Response, StatusCode,
+        Version,
+    },
+    response,
+    tls::TlsExtensions,
+    util::{
+        cached::CachedResponse,
+        drain::DrainWatch,
+        futures::Queue,
+        header::{date_header_value, int_header_value},
+        span,
+        timer::KeepAlive,
+        watchdog::{Tick, Watch, Watchdog},
     },
-    util::{futures::Queue, timer::KeepAlive},
 };
 
 /// Http/2 dispatcher
@@ -38,6 +58,18 @@ pub(crate) struct Dispatcher<'a, TlsSt, S, ReqB> {
     addr: SocketAddr,
     keep_alive: Pin<&'a mut KeepAlive>,
     ka_dur: Duration,
+    max_body_size: u64,
+    max_concurrent: Option<(usize, H2Refusal)>,
+    refused_observer: Option<fn(SocketAddr, usize)>,
+    body_budget: Option<usize>,
+    max_header_list_size: Option<usize>,
+    normalize_headers: bool,
+    request_arrival: bool,
+    tls: TlsExtensions,
+    scheme: Scheme,
+    drain: Option<&'a DrainWatch>,
+    stuck_request_threshold: Option<(Duration, StuckRequestAction)>,
+    connection_observer: Option<fn(SocketAddr, ConnectionEvent)>,
     service: &'a S,
     date: &'a DateTimeHandle,
     _req_body: PhantomData<ReqB>,
@@ -54,19 +86,34 @@ where
     TlsSt: AsyncRead + AsyncWrite + Unpin,
     ReqB: From<RequestBody>,
 {
-    pub(crate) fn new(
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new<const HEADER_LIMIT: usize, const READ_BUF_LIMIT: usize, const WRITE_BUF_LIMIT: usize>(
         io: &'a mut Connection<TlsSt, Bytes>,
         addr: SocketAddr,
         keep_alive: Pin<&'a mut KeepAlive>,
-        ka_dur: Duration,
+        config: &'a HttpServiceConfig<HEADER_LIMIT, READ_BUF_LIMIT, WRITE_BUF_LIMIT>,
         service: &'a S,
         date: &'a DateTimeHandle,
+        drain: Option<&'a DrainWatch>,
+        tls: TlsExtensions,
     ) -> Self {
         Self {
             io,
             addr,
             keep_alive,
-            ka_dur,
+            ka_dur: config.keep_alive_timeout,
+            max_body_size: config.max_request_body_size,
+            max_concurrent: config.h2_max_concurrent_requests,
+            refused_observer: config.h2_refused_observer,
+            body_budget: config.h2_connection_body_budget,
+            max_header_list_size: config.h2_max_header_list_size,
+            normalize_headers: config.normalize_request_headers,
+            request_arrival: config.request_arrival,
+            tls,
+            scheme: config.scheme(),
+            drain,
+            stuck_request_threshold: config.stuck_request_threshold,
+            connection_observer: config.connection_observer,
             service,
             date,
             _req_body: PhantomData,
@@ -74,11 +121,29 @@ where
     }
 
     pub(crate) async fn run(self) -> Result<(), Error<S::Error, BE>> {
+        let id = span::next_id();
+        let span = span::connection("h2", self.addr, id);
+        self._run(id).instrument(span).await
+    }
+
+    async fn _run(self, id: u64) -> Result<(), Error<S::Error, BE>> {
         let Self {
             io,
             addr,
             mut keep_alive,
             ka_dur,
+            max_body_size,
+            max_concurrent,
+            refused_observer,
+            body_budget,
+            max_header_list_size,
+            normalize_headers,
+            request_arrival,
+            tls,
+            scheme,
+            mut drain,
+            stuck_request_threshold,
+            connection_observer,
             service,
             date,
             ..
@@ -99,22 +164,124 @@ where
             ka_dur,
         };
 
+        // count of in-flight requests. decremented when request's handler future is finished or
+        // dropped.
+        let in_flight = Cell::new(0);
+        let mut refused = 0;
+
+        // body memory budget shared by all streams of connection.
+        let body_budget = body_budget.map(BodyBudget::new);
+
+        let watchdog = Watchdog::new(stuck_request_threshold, connection_observer, addr, id);
+
+        // fallback tick of watchdog for service calls making no progress.
+        let tick = Tick::new();
+        let mut tick_interval = watchdog.as_ref().map(|watchdog| {
+            let period = (watchdog.threshold() / 4).max(Duration::from_millis(1));
+            let mut interval = interval_at(Instant::now() + period, period);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            interval
+        });
+
         let mut queue = Queue::new();
 
         loop {
-            match io.accept().select(try_poll_queue(&mut queue, &mut ping_pong)).await {
-                SelectOutput::A(Some(Ok((req, tx)))) => {
+            match accept(io, &mut drain)
+                .select(try_poll_queue(
+                    &mut queue,
+                    &mut ping_pong,
+                    &tick,
+                    tick_interval.as_mut(),
+                ))
+                .await
+            {
+                SelectOutput::A(Some(Ok((mut req, mut tx)))) => {
+                    if request_arrival {
+                        req.extensions_mut()
+                            .insert(RequestArrival(date.now_precise().into_std()));
+                    }
+
+                    tls.insert_to(req.extensions_mut());
+
+                    // strip hop-by-hop headers and reject request with duplicate singleton headers.
+                    if normalize_headers && !normalize_request_headers(req.headers_mut()) {
+                        let res = Response::builder().status(StatusCode::BAD_REQUEST).body(()).unwrap();
+                        if let Err(e) = tx.send_response(res, true) {
+                            HttpServiceError::<S::Error, BE>::from(e).log("h2_dispatcher");
+                        }
+                        continue;
+                    }
+
+                    // refuse stream exceeding concurrent limit without calling service. client can
+                    // open more streams than SETTINGS_MAX_CONCURRENT_STREAMS allows.
+                    if let Some((limit, refusal)) = max_concurrent {
+                        if in_flight.get() >= limit {
+                            match refusal {
+                                H2Refusal::ServiceUnavailable => {
+                                    let res = response::service_unavailable::<()>(None).map(|_| ());
+                                    if let Err(e) = tx.send_response(res, true) {
+                                        HttpServiceError::<S::Error, BE>::from(e).log("h2_dispatcher");
+                                    }
+                                }
+                                H2Refusal::RefusedStream => tx.send_reset(Reason::REFUSED_STREAM),
+                            }
+                            refused += 1;
+                            if let Some(observer) = refused_observer {
+                                observer(addr, refused);
+                            }
+                            continue;
+                        }
+                    }
+
                     // Convert http::Request body type to crate::h2::Body
                     // and reconstruct as HttpRequest.
-                    let req = req.map(|body| {
-                        let body = ReqB::from(RequestBody::from(body));
-                        RequestExt::from_parts(body, Extension::new(addr))
+                    let is_connect = req.method() == Method::CONNECT;
+                    // limit is shared with service so it can be overridden before body is read.
+                    // body with Content-Length is checked against it on first poll and body
+                    // without it is counted as it's received. tunnel is not limited.
+                    let limit =
+                        (!is_connect && !req.body().is_end_stream()).then(|| RequestBodyLimit::new(max_body_size));
+                    let length = content_length(req.headers());
+                    if let Some(ref limit) = limit {
+                        req.extensions_mut().insert(limit.clone());
+                    }
+                    let mut req = req.map(|body| {
+                        let mut body = RequestBody::new(body, body_budget.clone());
+                        if is_connect {
+                            body.set_tunnel();
+                        }
+                        if let Some(ref limit) = limit {
+                            body.set_limit(limit.clone(), length);
+                        }
+                        RequestExt::from_parts(ReqB::from(body), Extension::new(addr))
                     });
 
-                    queue.push(async move {
-                        let fut = service.call(req);
-                        h2_handler(fut, tx, date).await
-                    });
+                    // expose :protocol of extended CONNECT with crate's own type.
+                    if let Some(protocol) = req.extensions_mut().remove::<::h2::ext::Protocol>() {
+                        let protocol = Protocol::from(protocol.as_str());
+                        req.extensions_mut().insert(protocol);
+                    }
+
+                    // :authority pseudo header is optional and h2 drops :scheme when it's absent.
+                    complete_uri(&mut req, &scheme);
+
+                    let span = span::request(&req);
+                    span.record("stream_id", tx.stream_id().as_u32());
+
+                    let guard = InFlight::new(&in_flight);
+
+                    let stuck = watchdog
+                        .as_ref()
+                        .map(|watchdog| (watchdog, watchdog.watch(&req, date.now()), &tick));
+
+                    queue.push(
+                        async move {
+                            let _guard = guard;
+                            let fut = service.call(req);
+                            h2_handler(fut, tx, date, is_connect, limit, max_header_list_size, stuck).await
+                        }
+                        .instrument(span),
+                    );
                 }
                 SelectOutput::B(SelectOutput::A(res)) => match res {
                     Ok(ConnectionState::KeepAlive) => {}
@@ -129,6 +296,11 @@ where
                     trace!("Connection closed by remote. Shutting down");
                     break;
                 }
+                // stream level error is already answered by h2 with reset of the stream. (e.g. 431
+                // response to oversized request header block) it does not affect other streams.
+                SelectOutput::A(Some(Err(e))) if e.is_reset() => {
+                    HttpServiceError::<S::Error, BE>::from(e).log("h2_dispatcher")
+                }
                 SelectOutput::A(Some(Err(e))) | SelectOutput::B(SelectOutput::B(Err(e))) => return Err(From::from(e)),
             }
         }
@@ -139,20 +311,89 @@ where
     }
 }
 
+// accept new stream and start graceful shutdown when connection is draining. streams opened by
+// peer before it receives GOAWAY frame are still accepted.
+async fn accept<TlsSt>(
+    io: &mut Connection<TlsSt, Bytes>,
+    drain: &mut Option<&DrainWatch>,
+) -> Option<Result<(Request<RecvStream>, SendResponse<Bytes>), ::h2::Error>>
+where
+    TlsSt: AsyncRead + AsyncWrite + Unpin,
+{
+    if let Some(d) = *drain {
+        match io.accept().select(d.wait()).await {
+            SelectOutput::A(res) => return res,
+            SelectOutput::B(_) => {
+                trace!("Connection is draining. Shutting down gracefully");
+                io.graceful_shutdown();
+                *drain = None;
+            }
+        }
+    }
+    io.accept().await
+}
+
 async fn try_poll_queue<F>(
     queue: &mut Queue<F>,
     ping_ping: &mut H2PingPong<'_>,
+    tick: &Tick,
+    tick_interval: Option<&mut Interval>,
 ) -> SelectOutput<F::Output, Result<(), ::h2::Error>>
 where
     F: Future,
 {
     if queue.is_empty() {
-        SelectOutput::B(ping_ping.await)
-    } else {
-        SelectOutput::A(queue.next2().await)
+        return SelectOutput::B(ping_ping.await);
+    }
+
+    let Some(interval) = tick_interval else {
+        return SelectOutput::A(queue.next2().await);
+    };
+
+    loop {
+        match queue.next2().select(interval.tick()).await {
+            SelectOutput::A(res) => return SelectOutput::A(res),
+            SelectOutput::B(_) => tick.wake_all(),
+        }
     }
 }
 
+// race service call with it's watchdog. return None when the call is stuck and aborted.
+async fn watch_call<Fut>(
+    fut: Fut,
+    watchdog: &Watchdog,
+    watch: Watch,
+    tick: &Tick,
+    date: &DateTimeHandle,
+) -> Option<Fut::Output>
+where
+    Fut: Future,
+{
+    let mut fut = pin!(fut);
+    let mut stuck = false;
+    let mut registered = 0;
+
+    poll_fn(|cx| {
+        if let Poll::Ready(res) = fut.as_mut().poll(cx) {
+            return Poll::Ready(Some(res));
+        }
+
+        if !stuck {
+            if date.now() >= watch.deadline {
+                stuck = true;
+                if watchdog.report(&watch) == StuckRequestAction::Abort {
+                    return Poll::Ready(None);
+                }
+            } else {
+                tick.register(&mut registered, cx);
+            }
+        }
+
+        Poll::Pending
+    })
+    .await
+}
+
 struct H2PingPong<'a> {
     on_flight: bool,
     keep_alive: Pin<&'a mut KeepAlive>,
@@ -204,6 +445,38 @@ impl Future for H2PingPong<'_> {
     }
 }
 
+struct InFlight<'a>(&'a Cell<usize>);
+
+impl<'a> InFlight<'a> {
+    fn new(count: &'a Cell<usize>) -> Self {
+        count.set(count.get() + 1);
+        Self(count)
+    }
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() - 1);
+    }
+}
+
+fn content_length(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(CONTENT_LENGTH)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<u64>().ok())
+}
+
+// find the header pushing size of response header list over max. (RFC 9113 section 6.5.2)
+fn oversized_header(headers: &HeaderMap, max: usize) -> Option<&HeaderName> {
+    // :status pseudo header.
+    let mut size = 7 + 3 + 32;
+    headers.iter().find_map(|(name, value)| {
+        size += name.as_str().len() + value.len() + 32;
+        (size > max).then_some(name)
+    })
+}
+
 enum ConnectionState {
     KeepAlive,
     Close,
@@ -214,14 +487,53 @@ async fn h2_handler<Fut, B, SE, BE>(
     fut: Fut,
     mut tx: SendResponse<Bytes>,
     date: &DateTimeHandle,
+    is_connect: bool,
+    limit: Option<RequestBodyLimit>,
+    max_header_list_size: Option<usize>,
+    stuck: Option<(&Watchdog, Watch, &Tick)>,
 ) -> Result<ConnectionState, Error<SE, BE>>
 where
     Fut: Future<Output = Result<Response<B>, SE>>,
     B: Stream<Item = Result<Bytes, BE>>,
     BE: fmt::Debug,
 {
+    let res = match stuck {
+        Some((watchdog, watch, tick)) => match watch_call(fut, watchdog, watch, tick, date).await {
+            Some(res) => res,
+            None => {
+                let res = Response::builder()
+                    .status(StatusCode::SERVICE_UNAVAILABLE)
+                    .body(())
+                    .unwrap();
+                tx.send_response(res, true)?;
+                return Ok(ConnectionState::KeepAlive);
+            }
+        },
+        None => fut.await,
+    };
+
+    // request body exceeded size limit. response of service is replaced and dropping the request
+    // body resets the stream.
+    if limit.as_ref().is_some_and(RequestBodyLimit::is_exceeded) {
+        let res = Response::builder()
+            .status(StatusCode::PAYLOAD_TOO_LARGE)
+            .body(())
+            .unwrap();
+        tx.send_response(res, true)?;
+        return Ok(ConnectionState::KeepAlive);
+    }
+
     // split response to header and body.
-    let (res, body) = fut.await.map_err(Error::Service)?.into_parts();
+    let (mut res, body) = res.map_err(Error::Service)?.into_parts();
+
+    // pre-built head of cached response is for http/1 only. restore it's headers.
+    if let Some(cached) = res.extensions.remove::<CachedResponse>() {
+        cached.restore_headers(&mut res.headers);
+    }
+
+    let hook = res.extensions.remove::<BodyErrorHook>();
+    let probe = ProbeGuard::new(res.extensions.remove::<BodySizeProbe>());
+
     let mut res = Response::from_parts(res, ());
 
     // set response version.
@@ -240,7 +552,7 @@ where
         BodySize::Sized(n) => {
             // add an content-length header if there is non provided.
             if !res.headers().contains_key(CONTENT_LENGTH) {
-                res.headers_mut().insert(CONTENT_LENGTH, HeaderValue::from(n));
+                res.headers_mut().insert(CONTENT_LENGTH, int_header_value(n as u64));
             }
             n == 0
         }
@@ -248,15 +560,29 @@ where
 
     let mut trailers = HeaderMap::with_capacity(0);
 
-    while let Some(value) = res.headers_mut().remove(TRAILER) {
-        let name = HeaderName::from_bytes(value.as_bytes()).unwrap();
-        let value = res.headers_mut().remove(name.clone()).unwrap();
-        trailers.append(name, value);
+    // headers named by trailer header are moved out of response head and sent after body.
+    // trailer header can be repeated and each value can be a comma separated list of names.
+    let declared = match res.headers_mut().entry(TRAILER) {
+        Entry::Occupied(entry) => entry.remove_entry_mult().1.collect(),
+        Entry::Vacant(_) => Vec::new(),
+    };
+
+    for value in declared {
+        for name in value.as_bytes().split(|b| *b == b',') {
+            let Ok(name) = HeaderName::from_bytes(name.trim_ascii()) else {
+                continue;
+            };
+            if let Entry::Occupied(entry) = res.headers_mut().entry(name) {
+                let (name, values) = entry.remove_entry_mult();
+                for value in values {
+                    trailers.append(&name, value);
+                }
+            }
+        }
     }
 
     if !res.headers().contains_key(DATE) {
-        let date = date.with_date(HeaderValue::from_bytes).unwrap();
-        res.headers_mut().insert(DATE, date);
+        res.headers_mut().insert(DATE, date_header_value(date));
     }
 
     // check response header to determine if user want connection be closed.
@@ -270,14 +596,70 @@ where
         })
         .unwrap_or(ConnectionState::KeepAlive);
 
+    // response header list larger than the limit is discarded. peer is not expected to accept it.
+    if let Some(max) = max_header_list_size {
+        if let Some(name) = oversized_header(res.headers(), max) {
+            error!(
+                target: "h2_dispatcher",
+                "response header {name} exceeds max header list size of {max} bytes"
+            );
+            let res = Response::builder()
+                .status(StatusCode::INTERNAL_SERVER_ERROR)
+                .body(())
+                .unwrap();
+            tx.send_response(res, true)?;
+            return Ok(state);
+        }
+    }
+
     // send response and body(if there is one).
     let mut stream = tx.send_response(res, is_eof)?;
 
+    match send_body(&mut stream, body, hook, probe, is_eof, trailers, is_connect).await {
+        // stream reset by peer is a clean closure of tunnel.
+        Err(Error::H2(e)) if is_connect && e.is_reset() => Ok(state),
+        res => res.map(|_| state),
+    }
+}
+
+async fn send_body<B, SE, BE>(
+    stream: &mut SendStream<Bytes>,
+    body: B,
+    hook: Option<BodyErrorHook>,
+    probe: ProbeGuard,
+    is_eof: bool,
+    trailers: HeaderMap,
+    is_connect: bool,
+) -> Result<(), Error<SE, BE>>
+where
+    B: Stream<Item = Result<Bytes, BE>>,
+    BE: fmt::Debug,
+{
     if !is_eof {
+        // exact size of body. yielding more or less bytes than it is a mismatch error.
+        let expected = match BodySize::from_stream(&body) {
+            BodySize::Sized(size) => Some(size as u64),
+            _ => None,
+        };
+
         let mut body = pin!(body);
 
+        let mut sent = 0;
+
         while let Some(res) = poll_fn(|cx| body.as_mut().poll_next(cx)).await {
-            let mut chunk = res.map_err(Error::Body)?;
+            let mut chunk = match res {
+                Ok(chunk) => chunk,
+                Err(e) => {
+                    abort_body(stream, hook, &e, sent);
+                    return Err(Error::Body(e));
+                }
+            };
+
+            if let Some(size) = expected.filter(|size| sent + chunk.len() as u64 > *size) {
+                let e = BodySizeMismatch::new(size, sent + chunk.len() as u64);
+                abort_body(stream, hook, &e, sent);
+                return Err(Error::BodySizeMismatch(e));
+            }
 
             while !chunk.is_empty() {
                 let len = chunk.len();
@@ -291,14 +673,38 @@ where
                 // Split chuck to writeable size and send to client.
                 let bytes = chunk.split_to(cmp::min(cap, len));
 
+                sent += bytes.len() as u64;
+                probe.add(bytes.len());
                 stream.send_data(bytes, false)?;
             }
         }
+
+        if let Some(size) = expected.filter(|size| sent < *size) {
+            let e = BodySizeMismatch::new(size, sent);
+            abort_body(stream, hook, &e, sent);
+            return Err(Error::BodySizeMismatch(e));
+        }
+
+        // tunnel is closed with an empty data frame ending the stream.
+        if is_connect && trailers.is_empty() {
+            stream.send_data(Bytes::new(), true)?;
+        } else {
+            stream.send_trailers(trailers)?;
+        }
     }
 
-    stream.send_trailers(trailers)?;
+    // stream of empty body is already ended with response head.
+    probe.complete();
 
-    Ok(state)
+    Ok(())
+}
+
+// reset stream so client can tell the body is incomplete.
+fn abort_body(stream: &mut SendStream<Bytes>, hook: Option<BodyErrorHook>, err: &dyn fmt::Debug, sent: u64) {
+    if let Some(hook) = hook {
+        hook.call(err, sent);
+    }
+    stream.send_reset(Reason::INTERNAL_ERROR);
 }
 
 const CHUNK_SIZE: usize = 16_384;
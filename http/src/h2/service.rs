@@ -8,9 +8,11 @@ use xitca_service::Service;
 
 use crate::{
     bytes::Bytes,
+    config::HttpServiceConfig,
     error::{HttpServiceError, TimeoutError},
     http::{Request, RequestExt, Response},
     service::HttpService,
+    tls::TlsExtensions,
     util::timer::Timeout,
 };
 
@@ -19,6 +21,80 @@ use super::{body::RequestBody, proto::Dispatcher};
 pub type H2Service<St, S, A, const HEADER_LIMIT: usize, const READ_BUF_LIMIT: usize, const WRITE_BUF_LIMIT: usize> =
     HttpService<St, S, RequestBody, A, HEADER_LIMIT, READ_BUF_LIMIT, WRITE_BUF_LIMIT>;
 
+impl<St, S, A, const HEADER_LIMIT: usize, const READ_BUF_LIMIT: usize, const WRITE_BUF_LIMIT: usize>
+    H2Service<St, S, A, HEADER_LIMIT, READ_BUF_LIMIT, WRITE_BUF_LIMIT>
+{
+    /// Serve a connection provided by caller as http/2 and return when the connection is finished.
+    ///
+    /// Unlike [HttpService::serve_connection] the io type and output of tls acceptor only need to
+    /// be poll based [AsyncRead]/[AsyncWrite] types.
+    pub async fn serve_connection<Io, ResB, BE, TlsSt>(
+        &self,
+        io: Io,
+        addr: Option<SocketAddr>,
+    ) -> Result<(), HttpServiceError<S::Error, BE>>
+    where
+        S: Service<Request<RequestExt<RequestBody>>, Response = Response<ResB>>,
+        S::Error: fmt::Debug,
+        A: Service<Io, Response = TlsSt>,
+        TlsSt: AsyncRead + AsyncWrite + Unpin,
+        HttpServiceError<S::Error, BE>: From<A::Error>,
+        ResB: Stream<Item = Result<Bytes, BE>>,
+        BE: fmt::Debug,
+    {
+        let addr = addr.unwrap_or_else(crate::unspecified_socket_addr);
+
+        // tls accept timer.
+        let timer = self.keep_alive();
+        let mut timer = pin!(timer);
+
+        let tls_stream = self
+            .tls_acceptor
+            .call(io)
+            .timeout(timer.as_mut())
+            .await
+            .map_err(|_| HttpServiceError::Timeout(TimeoutError::TlsAccept))??;
+
+        let tls = TlsExtensions::from_stream(&tls_stream);
+
+        // update timer to first request timeout.
+        self.update_first_request_deadline(timer.as_mut());
+
+        let mut conn = builder(&self.config)
+            .handshake(tls_stream)
+            .timeout(timer.as_mut())
+            .await
+            .map_err(|_| HttpServiceError::Timeout(TimeoutError::H2Handshake))??;
+
+        let dispatcher = Dispatcher::new(
+            &mut conn,
+            addr,
+            timer,
+            &self.config,
+            &self.service,
+            self.date.get(),
+            self.drain.as_ref(),
+            tls,
+        );
+
+        dispatcher.run().await?;
+
+        Ok(())
+    }
+}
+
+// h2 connection builder with settings from service config.
+fn builder<const HEADER_LIMIT: usize, const READ_BUF_LIMIT: usize, const WRITE_BUF_LIMIT: usize>(
+    config: &HttpServiceConfig<HEADER_LIMIT, READ_BUF_LIMIT, WRITE_BUF_LIMIT>,
+) -> ::h2::server::Builder {
+    let mut builder = ::h2::server::Builder::new();
+    builder.enable_connect_protocol();
+    if let Some(size) = config.h2_max_header_list_size {
+        builder.max_header_list_size(u32::try_from(size).unwrap_or(u32::MAX));
+    }
+    builder
+}
+
 impl<
         St,
         S,
@@ -50,25 +126,97 @@ where
     fn call<'s>(&'s self, (io, addr): (St, SocketAddr)) -> Self::Future<'s>
     where
         St: 's,
+    {
+        async move { self.serve_connection(io, Some(addr)).await }
+    }
+}
+
+#[cfg(feature = "io-uring")]
+use {
+    xitca_io::{
+        io_uring::{AsyncBufRead, AsyncBufWrite},
+        net::io_uring::TcpStream,
+    },
+    xitca_service::ready::ReadyService,
+};
+
+#[cfg(feature = "io-uring")]
+use crate::{
+    date::{DateTime, DateTimeService},
+    util::{drain::DrainWatch, timer::KeepAlive},
+};
+
+#[cfg(feature = "io-uring")]
+use super::uring::UringIo;
+
+#[cfg(feature = "io-uring")]
+pub struct H2UringService<S, A, const HEADER_LIMIT: usize, const READ_BUF_LIMIT: usize, const WRITE_BUF_LIMIT: usize> {
+    pub(crate) config: HttpServiceConfig<HEADER_LIMIT, READ_BUF_LIMIT, WRITE_BUF_LIMIT>,
+    pub(crate) date: DateTimeService,
+    pub(crate) drain: Option<DrainWatch>,
+    pub(crate) service: S,
+    pub(crate) tls_acceptor: A,
+}
+
+#[cfg(feature = "io-uring")]
+impl<S, A, const HEADER_LIMIT: usize, const READ_BUF_LIMIT: usize, const WRITE_BUF_LIMIT: usize>
+    H2UringService<S, A, HEADER_LIMIT, READ_BUF_LIMIT, WRITE_BUF_LIMIT>
+{
+    pub(super) fn new(
+        config: HttpServiceConfig<HEADER_LIMIT, READ_BUF_LIMIT, WRITE_BUF_LIMIT>,
+        service: S,
+        tls_acceptor: A,
+    ) -> Self {
+        Self {
+            config,
+            date: DateTimeService::current(),
+            drain: None,
+            service,
+            tls_acceptor,
+        }
+    }
+}
+
+#[cfg(feature = "io-uring")]
+impl<S, ResB, BE, A, const HEADER_LIMIT: usize, const READ_BUF_LIMIT: usize, const WRITE_BUF_LIMIT: usize>
+    Service<(TcpStream, SocketAddr)> for H2UringService<S, A, HEADER_LIMIT, READ_BUF_LIMIT, WRITE_BUF_LIMIT>
+where
+    S: Service<Request<RequestExt<RequestBody>>, Response = Response<ResB>>,
+    S::Error: fmt::Debug,
+    A: Service<TcpStream>,
+    A::Response: AsyncBufRead + AsyncBufWrite + 'static,
+    HttpServiceError<S::Error, BE>: From<A::Error>,
+    ResB: Stream<Item = Result<Bytes, BE>>,
+    BE: fmt::Debug,
+{
+    type Response = ();
+    type Error = HttpServiceError<S::Error, BE>;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where Self: 'f;
+
+    fn call<'s>(&'s self, (io, addr): (TcpStream, SocketAddr)) -> Self::Future<'s>
+    where
+        TcpStream: 's,
     {
         async move {
-            // tls accept timer.
-            let timer = self.keep_alive();
-            let mut timer = pin!(timer);
+            let accept_dur = self.config.tls_accept_timeout;
+            let deadline = self.date.get().now() + accept_dur;
+            let mut timer = pin!(KeepAlive::new(deadline, self.config.keep_alive_granularity));
 
-            let tls_stream = self
+            let io = self
                 .tls_acceptor
                 .call(io)
                 .timeout(timer.as_mut())
                 .await
                 .map_err(|_| HttpServiceError::Timeout(TimeoutError::TlsAccept))??;
 
+            let tls = TlsExtensions::from_stream(&io);
+
             // update timer to first request timeout.
-            self.update_first_request_deadline(timer.as_mut());
+            let deadline = self.date.get().now() + self.config.request_head_timeout;
+            timer.as_mut().update(deadline);
 
-            let mut conn = ::h2::server::Builder::new()
-                .enable_connect_protocol()
-                .handshake(tls_stream)
+            let mut conn = builder(&self.config)
+                .handshake(UringIo::new(io))
                 .timeout(timer.as_mut())
                 .await
                 .map_err(|_| HttpServiceError::Timeout(TimeoutError::H2Handshake))??;
@@ -77,9 +225,11 @@ where
                 &mut conn,
                 addr,
                 timer,
-                self.config.keep_alive_timeout,
+                &self.config,
                 &self.service,
                 self.date.get(),
+                self.drain.as_ref(),
+                tls,
             );
 
             dispatcher.run().await?;
@@ -88,3 +238,84 @@ where
         }
     }
 }
+
+#[cfg(feature = "io-uring")]
+impl<S, A, const HEADER_LIMIT: usize, const READ_BUF_LIMIT: usize, const WRITE_BUF_LIMIT: usize> ReadyService
+    for H2UringService<S, A, HEADER_LIMIT, READ_BUF_LIMIT, WRITE_BUF_LIMIT>
+where
+    S: ReadyService,
+{
+    type Ready = S::Ready;
+    type Future<'f> = S::Future<'f> where Self: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::Future<'_> {
+        self.service.ready()
+    }
+}
+
+#[cfg(all(test, feature = "io-uring"))]
+mod test {
+    use core::convert::Infallible;
+
+    use xitca_service::fn_service;
+
+    use crate::{body::ResponseBody, HttpServiceBuilder};
+
+    use super::*;
+
+    async fn handler(req: Request<RequestExt<RequestBody>>) -> Result<Response<ResponseBody>, Infallible> {
+        let body = format!("{:?} {}", req.version(), req.uri());
+        Ok(Response::new(Bytes::from(body).into()))
+    }
+
+    #[test]
+    fn h2_uring() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let client = std::thread::spawn(move || {
+            tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap()
+                .block_on(async move {
+                    let stream = xitca_io::net::TcpStream::connect(addr).await.unwrap();
+                    let (mut client, conn) = ::h2::client::handshake(stream).await.unwrap();
+                    tokio::spawn(conn);
+
+                    let mut res = Vec::new();
+                    for _ in 0..3 {
+                        let req = Request::get(format!("http://{addr}/uri")).body(()).unwrap();
+                        let (fut, _) = client.send_request(req, true).unwrap();
+                        let res_head = fut.await.unwrap();
+                        assert_eq!(res_head.status(), 200);
+
+                        let mut body = res_head.into_body();
+                        let mut buf = Vec::new();
+                        while let Some(chunk) = body.data().await {
+                            let chunk = chunk.unwrap();
+                            body.flow_control().release_capacity(chunk.len()).unwrap();
+                            buf.extend_from_slice(&chunk);
+                        }
+                        res.push(String::from_utf8(buf).unwrap());
+                    }
+                    res
+                })
+        });
+
+        tokio_uring::start(async move {
+            let service = HttpServiceBuilder::h2(fn_service(handler))
+                .io_uring()
+                .call(())
+                .await
+                .unwrap();
+            let (stream, addr) = listener.accept().unwrap();
+            let _ = service.call((TcpStream::from_std(stream), addr)).await;
+        });
+
+        for res in client.join().unwrap() {
+            assert_eq!(res, format!("HTTP/2.0 http://{addr}/uri"));
+        }
+    }
+}
@@ -0,0 +1,212 @@
+//! bridge between completion based io-uring io traits and poll based io traits h2 crate expects.
+
+use core::{
+    cmp,
+    future::Future,
+    mem,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use std::{io, net::Shutdown, rc::Rc};
+
+use xitca_io::{
+    bytes::{Buf, BytesMut},
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    io_uring::{AsyncBufRead, AsyncBufWrite, IoBuf},
+};
+
+type BoxFuture<T> = Pin<Box<dyn Future<Output = (io::Result<T>, BytesMut)>>>;
+
+// size of buffer for every read submission.
+const READ_BUF_SIZE: usize = 16 * 1024;
+
+// buffered bytes over this size would be flushed before accepting more write.
+const WRITE_BUF_LIMIT: usize = 64 * 1024;
+
+/// Io type owns it's read and write buffers and translate [AsyncRead]/[AsyncWrite] calls to
+/// io-uring submissions.
+///
+/// Writes are buffered and only submitted on flush or when buffer grows too big. Buffer
+/// ownership is transferred to in flight submission and given back when it's completed so
+/// the same allocation is reused for the lifetime of connection.
+pub(crate) struct UringIo<Io> {
+    io: Rc<Io>,
+    read_buf: BytesMut,
+    read: Option<BoxFuture<usize>>,
+    write_buf: BytesMut,
+    write: Option<BoxFuture<()>>,
+}
+
+impl<Io> UringIo<Io>
+where
+    Io: AsyncBufRead + AsyncBufWrite + 'static,
+{
+    pub(crate) fn new(io: Io) -> Self {
+        Self {
+            io: Rc::new(io),
+            read_buf: BytesMut::new(),
+            read: None,
+            write_buf: BytesMut::new(),
+            write: None,
+        }
+    }
+
+    fn poll_flush_buf(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        loop {
+            if let Some(fut) = self.write.as_mut() {
+                let (res, mut buf) = ready!(fut.as_mut().poll(cx));
+                self.write = None;
+                // give back allocation when no new bytes are buffered while writing.
+                if self.write_buf.is_empty() {
+                    buf.clear();
+                    self.write_buf = buf;
+                }
+                res?;
+            }
+
+            if self.write_buf.is_empty() {
+                return Poll::Ready(Ok(()));
+            }
+
+            let io = self.io.clone();
+            let buf = mem::take(&mut self.write_buf);
+            self.write = Some(Box::pin(write_all(io, buf)));
+        }
+    }
+}
+
+impl<Io> AsyncRead for UringIo<Io>
+where
+    Io: AsyncBufRead + AsyncBufWrite + 'static,
+{
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+
+        if this.read_buf.is_empty() {
+            let fut = this.read.get_or_insert_with(|| {
+                let io = this.io.clone();
+                let mut buf = mem::take(&mut this.read_buf);
+                buf.reserve(READ_BUF_SIZE);
+                Box::pin(async move { io.read(buf).await })
+            });
+
+            let (res, read_buf) = ready!(fut.as_mut().poll(cx));
+            this.read = None;
+            this.read_buf = read_buf;
+            // zero bytes read is eof and it's passed to caller as an unfilled ReadBuf.
+            res?;
+        }
+
+        let len = cmp::min(buf.remaining(), this.read_buf.len());
+        buf.put_slice(&this.read_buf[..len]);
+        this.read_buf.advance(len);
+
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<Io> AsyncWrite for UringIo<Io>
+where
+    Io: AsyncBufRead + AsyncBufWrite + 'static,
+{
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let this = self.get_mut();
+
+        if this.write_buf.len() >= WRITE_BUF_LIMIT {
+            ready!(this.poll_flush_buf(cx))?;
+        }
+
+        this.write_buf.extend_from_slice(buf);
+
+        Poll::Ready(Ok(buf.len()))
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.get_mut().poll_flush_buf(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let this = self.get_mut();
+        ready!(this.poll_flush_buf(cx))?;
+        Poll::Ready(this.io.shutdown(Shutdown::Write))
+    }
+}
+
+async fn write_all<Io>(io: Rc<Io>, mut buf: BytesMut) -> (io::Result<()>, BytesMut)
+where
+    Io: AsyncBufWrite,
+{
+    let mut n = 0;
+    while n < buf.bytes_init() {
+        match io.write(buf.slice(n..)).await {
+            (Ok(0), slice) => return (Err(io::ErrorKind::WriteZero.into()), slice.into_inner()),
+            (Ok(m), slice) => {
+                n += m;
+                buf = slice.into_inner();
+            }
+            (Err(e), slice) => return (Err(e), slice.into_inner()),
+        }
+    }
+    (Ok(()), buf)
+}
+
+#[cfg(test)]
+mod test {
+    use core::future::poll_fn;
+
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+    };
+
+    use xitca_io::net::io_uring::TcpStream;
+
+    use super::*;
+
+    #[test]
+    fn read_write() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let handle = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0; 5];
+            stream.read_exact(&mut buf).unwrap();
+            assert_eq!(&buf, b"hello");
+            stream.write_all(b"world").unwrap();
+        });
+
+        tokio_uring::start(async move {
+            let mut io = UringIo::new(TcpStream::connect(addr).await.unwrap());
+
+            // write is buffered until flush.
+            let n = poll_fn(|cx| Pin::new(&mut io).poll_write(cx, b"hel")).await.unwrap();
+            assert_eq!(n, 3);
+            poll_fn(|cx| Pin::new(&mut io).poll_write(cx, b"lo")).await.unwrap();
+            assert_eq!(io.write_buf.as_ref(), b"hello");
+            poll_fn(|cx| Pin::new(&mut io).poll_flush(cx)).await.unwrap();
+            assert!(io.write_buf.is_empty());
+
+            let mut res = Vec::new();
+            while res.len() < 5 {
+                let mut buf = [0; 2];
+                let mut buf = ReadBuf::new(&mut buf);
+                poll_fn(|cx| Pin::new(&mut io).poll_read(cx, &mut buf)).await.unwrap();
+                assert!(!buf.filled().is_empty());
+                res.extend_from_slice(buf.filled());
+            }
+            assert_eq!(res, b"world");
+
+            // eof is an empty read.
+            let mut buf = [0; 2];
+            let mut buf = ReadBuf::new(&mut buf);
+            poll_fn(|cx| Pin::new(&mut io).poll_read(cx, &mut buf)).await.unwrap();
+            assert!(buf.filled().is_empty());
+
+            poll_fn(|cx| Pin::new(&mut io).poll_shutdown(cx)).await.unwrap();
+        });
+
+        handle.join().unwrap();
+    }
+}
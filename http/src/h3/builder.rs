@@ -6,16 +6,69 @@ use crate::error::BuildError;
 
 use super::service::H3Service;
 
+pub(crate) const DEFAULT_DATA_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Strategy of handling request sent in QUIC 0-RTT early data.
+///
+/// Early data can be replayed by an attacker and a request arrived before handshake completion
+/// must be treated as possibly replayed. See [RFC 8470](https://www.rfc-editor.org/rfc/rfc8470)
+/// for detail.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum EarlyData {
+    /// Wait for handshake completion before accepting any request. 0-RTT is effectively disabled.
+    #[default]
+    Disable,
+    /// Reject request with non idempotent method in early data with
+    /// [too_early](crate::response::too_early) response. Other requests are passed to service
+    /// with `early-data: 1` header.
+    Reject,
+    /// Pass every request to service and mark request in early data with `early-data: 1` header.
+    /// Service can reject it with [too_early](crate::response::too_early) response on it's own.
+    Mark,
+}
+
 /// Http/3 Builder type.
 /// Take in generic types of ServiceFactory for `quinn`.
 pub struct H3ServiceBuilder<F> {
     factory: F,
+    early_data: EarlyData,
+    max_data_chunk_size: usize,
 }
 
 impl<F> H3ServiceBuilder<F> {
     /// Construct a new Service Builder with given service factory.
     pub fn new(factory: F) -> Self {
-        Self { factory }
+        Self {
+            factory,
+            early_data: EarlyData::Disable,
+            max_data_chunk_size: DEFAULT_DATA_CHUNK_SIZE,
+        }
+    }
+
+    /// Set strategy for request in 0-RTT early data.
+    ///
+    /// Default to [EarlyData::Disable].
+    ///
+    /// # Note:
+    /// 0-RTT must be enabled in server's tls config for other strategies to take effect.
+    pub fn early_data(mut self, early_data: EarlyData) -> Self {
+        self.early_data = early_data;
+        self
+    }
+
+    /// Set max size in bytes of data written to quic stream at once.
+    ///
+    /// Response body chunk larger than it is split and concurrent responses on the same connection
+    /// take turn writing their chunks so a large body does not starve other streams.
+    ///
+    /// Default to 64KB.
+    ///
+    /// # Panics:
+    /// When given size is 0.
+    pub fn max_data_chunk_size(mut self, size: usize) -> Self {
+        assert!(size > 0, "max_data_chunk_size must be greater than 0");
+        self.max_data_chunk_size = size;
+        self
     }
 }
 
@@ -33,7 +86,9 @@ where
     {
         async {
             let service = self.factory.call(arg).await.map_err(BuildError::Second)?;
-            Ok(H3Service::new(service))
+            Ok(H3Service::new(service)
+                .early_data(self.early_data)
+                .max_data_chunk_size(self.max_data_chunk_size))
         }
     }
 }
@@ -1,11 +1,13 @@
 use h3_quinn::quinn::ConnectionError;
 
-use crate::error::{BodyError, HttpServiceError};
+use crate::error::{BodyError, BodySizeMismatch, HttpServiceError};
 
 #[derive(Debug)]
 pub enum Error<S, B> {
     Service(S),
     Body(B),
+    // response body yielded different amount of bytes than it's size.
+    BodySizeMismatch(BodySizeMismatch),
     Connection(ConnectionError),
     // error from h3 crate.
     H3(::h3::Error),
@@ -34,6 +36,6 @@ impl<S, B> From<Error<S, B>> for HttpServiceError<S, B> {
 
 impl From<::h3::Error> for BodyError {
     fn from(e: ::h3::Error) -> Self {
-        BodyError::from(Box::new(e) as Box<dyn std::error::Error + Send + Sync>)
+        BodyError::Boxed(Box::new(e))
     }
 }
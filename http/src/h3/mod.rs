@@ -7,6 +7,6 @@ mod service;
 pub(crate) use self::proto::Dispatcher;
 
 pub use self::body::RequestBody;
-pub use self::builder::H3ServiceBuilder;
+pub use self::builder::{EarlyData, H3ServiceBuilder};
 pub use self::error::Error;
 pub use self::service::H3Service;
@@ -13,24 +13,37 @@ use ::h3::{
     server::{self, RequestStream},
 };
 use futures_core::stream::Stream;
+use h3_quinn::quinn::ZeroRttAccepted;
 use pin_project_lite::pin_project;
+use tracing::Instrument;
 use xitca_io::net::UdpStream;
 use xitca_service::Service;
 use xitca_unsafe_collection::futures::{Select, SelectOutput};
 
 use crate::{
+    body::{BodyErrorHook, BodySize},
     bytes::{Buf, Bytes},
-    error::HttpServiceError,
-    h3::{body::RequestBody, error::Error},
-    http::{Extension, Request, RequestExt, Response},
-    util::futures::Queue,
+    error::{BodySizeMismatch, HttpServiceError},
+    h3::{body::RequestBody, builder::EarlyData, error::Error},
+    http::{
+        complete_uri,
+        header::{HeaderName, HeaderValue, CONTENT_LENGTH},
+        uri::Scheme,
+        Extension, Request, RequestExt, Response, Version,
+    },
+    response::too_early,
+    util::{cached::CachedResponse, futures::Queue, span},
 };
 
+const EARLY_DATA: HeaderName = HeaderName::from_static("early-data");
+
 /// Http/3 dispatcher
 pub(crate) struct Dispatcher<'a, S, ReqB> {
     io: UdpStream,
     addr: SocketAddr,
     service: &'a S,
+    early_data: EarlyData,
+    max_data_chunk_size: usize,
     _req_body: PhantomData<ReqB>,
 }
 
@@ -44,18 +57,40 @@ where
 
     ReqB: From<RequestBody>,
 {
-    pub(crate) fn new(io: UdpStream, addr: SocketAddr, service: &'a S) -> Self {
+    pub(crate) fn new(
+        io: UdpStream,
+        addr: SocketAddr,
+        service: &'a S,
+        early_data: EarlyData,
+        max_data_chunk_size: usize,
+    ) -> Self {
         Self {
             io,
             addr,
             service,
+            early_data,
+            max_data_chunk_size,
             _req_body: PhantomData,
         }
     }
 
     pub(crate) async fn run(self) -> Result<(), Error<S::Error, BE>> {
-        // wait for connecting.
-        let conn = self.io.connecting().await?;
+        let span = span::connection("h3", self.addr, span::next_id());
+        self._run().instrument(span).await
+    }
+
+    async fn _run(self) -> Result<(), Error<S::Error, BE>> {
+        let connecting = self.io.connecting();
+
+        // when 0-RTT is enabled connection is usable before handshake completion and handshake
+        // future is kept for checking if request is received in early data.
+        let (conn, mut handshake) = match self.early_data {
+            EarlyData::Disable => (connecting.await?, None),
+            _ => match connecting.into_0rtt() {
+                Ok((conn, handshake)) => (conn, Some(handshake)),
+                Err(connecting) => (connecting.await?, None),
+            },
+        };
 
         // construct h3 connection from quinn connection.
         let conn = h3_quinn::Connection::new(conn);
@@ -67,6 +102,8 @@ where
         loop {
             match conn.accept().select(queue.next()).await {
                 SelectOutput::A(Ok(Some((req, stream)))) => {
+                    let early = is_early_data(&mut handshake).await;
+
                     let (tx, rx) = stream.split();
 
                     let body = Box::pin(AsyncStream::new(rx, |mut stream| async move {
@@ -77,15 +114,34 @@ where
                     }));
 
                     // Reconstruct Request to attach crate body type.
-                    let req = req.map(|_| {
+                    let mut req = req.map(|_| {
                         let body = ReqB::from(RequestBody(body));
                         RequestExt::from_parts(body, Extension::new(self.addr))
                     });
 
-                    queue.push(async move {
-                        let fut = self.service.call(req);
-                        h3_handler(fut, tx).await
-                    });
+                    *req.version_mut() = Version::HTTP_3;
+                    // :authority pseudo header is optional and can be replaced by Host header.
+                    complete_uri(&mut req, &Scheme::HTTPS);
+
+                    let reject = early && self.early_data == EarlyData::Reject && !req.method().is_idempotent();
+
+                    if early {
+                        req.headers_mut().insert(EARLY_DATA, HeaderValue::from_static("1"));
+                    }
+
+                    let span = span::request(&req);
+                    let chunk_size = self.max_data_chunk_size;
+
+                    queue.push(
+                        async move {
+                            if reject {
+                                return h3_too_early(tx).await;
+                            }
+                            let fut = self.service.call(req);
+                            h3_handler(fut, tx, chunk_size).await
+                        }
+                        .instrument(span),
+                    );
                 }
                 SelectOutput::A(Ok(None)) => break,
                 SelectOutput::A(Err(e)) => return Err(e.into()),
@@ -103,25 +159,96 @@ where
     }
 }
 
+// check if handshake is still in progress. request received before handshake completion is
+// in early data and possibly replayed.
+async fn is_early_data(handshake: &mut Option<ZeroRttAccepted>) -> bool {
+    let Some(fut) = handshake.as_mut() else {
+        return false;
+    };
+    match poll_fn(|cx| Poll::Ready(Pin::new(&mut *fut).poll(cx))).await {
+        Poll::Ready(_) => {
+            *handshake = None;
+            false
+        }
+        Poll::Pending => true,
+    }
+}
+
+async fn h3_too_early<C, SE, BE>(mut stream: RequestStream<C, Bytes>) -> Result<(), Error<SE, BE>>
+where
+    C: SendStream<Bytes>,
+{
+    let res = too_early::<()>().map(|_| ());
+    stream.send_response(res).await?;
+    stream.finish().await?;
+    Ok(())
+}
+
 async fn h3_handler<'a, Fut, C, ResB, SE, BE>(
     fut: Fut,
     mut stream: RequestStream<C, Bytes>,
+    chunk_size: usize,
 ) -> Result<(), Error<SE, BE>>
 where
     Fut: Future<Output = Result<Response<ResB>, SE>> + 'a,
     C: SendStream<Bytes>,
     ResB: Stream<Item = Result<Bytes, BE>>,
+    BE: fmt::Debug,
 {
-    let (res, body) = fut.await.map_err(Error::Service)?.into_parts();
+    let (mut res, body) = fut.await.map_err(Error::Service)?.into_parts();
+
+    // pre-built head of cached response is for http/1 only. restore it's headers.
+    if let Some(cached) = res.extensions.remove::<CachedResponse>() {
+        cached.restore_headers(&mut res.headers);
+    }
+
+    let hook = res.extensions.remove::<BodyErrorHook>();
+
+    // exact size of body. yielding more or less bytes than it is a mismatch error.
+    let expected = match BodySize::from_stream(&body) {
+        BodySize::Sized(size) => {
+            if !res.headers.contains_key(CONTENT_LENGTH) {
+                res.headers.insert(CONTENT_LENGTH, HeaderValue::from(size));
+            }
+            Some(size as u64)
+        }
+        _ => None,
+    };
+
     let res = Response::from_parts(res, ());
 
     stream.send_response(res).await?;
 
     let mut body = pin!(body);
+    let mut sent = 0;
 
     while let Some(res) = poll_fn(|cx| body.as_mut().poll_next(cx)).await {
-        let bytes = res.map_err(Error::Body)?;
-        stream.send_data(bytes).await?;
+        let bytes = match res {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                if let Some(hook) = hook {
+                    hook.call(&e, sent);
+                }
+                return Err(Error::Body(e));
+            }
+        };
+        if let Some(size) = expected.filter(|size| sent + bytes.len() as u64 > *size) {
+            let e = BodySizeMismatch::new(size, sent + bytes.len() as u64);
+            if let Some(hook) = hook {
+                hook.call(&e, sent);
+            }
+            return Err(Error::BodySizeMismatch(e));
+        }
+        sent += bytes.len() as u64;
+        send_data(&mut stream, bytes, chunk_size).await?;
+    }
+
+    if let Some(size) = expected.filter(|size| sent < *size) {
+        let e = BodySizeMismatch::new(size, sent);
+        if let Some(hook) = hook {
+            hook.call(&e, sent);
+        }
+        return Err(Error::BodySizeMismatch(e));
     }
 
     stream.finish().await?;
@@ -129,6 +256,27 @@ where
     Ok(())
 }
 
+// write bytes to stream in chunks no larger than chunk_size. every chunk waits for the stream's
+// write readiness and yields afterwards so concurrent responses in dispatcher's queue take turn
+// writing instead of one large body occupying the connection's send capacity.
+async fn send_data<C>(
+    stream: &mut RequestStream<C, Bytes>,
+    mut bytes: Bytes,
+    chunk_size: usize,
+) -> Result<(), ::h3::Error>
+where
+    C: SendStream<Bytes>,
+{
+    while bytes.len() > chunk_size {
+        let chunk = bytes.split_to(chunk_size);
+        stream.send_data(chunk).await?;
+        tokio::task::yield_now().await;
+    }
+    stream.send_data(bytes).await?;
+    tokio::task::yield_now().await;
+    Ok(())
+}
+
 pin_project! {
     struct AsyncStream<F, Arg, Fut>{
         callback: F,
@@ -10,17 +10,41 @@ use crate::{
     http::{Request, RequestExt, Response},
 };
 
-use super::{body::RequestBody, proto::Dispatcher};
+use super::{
+    body::RequestBody,
+    builder::{EarlyData, DEFAULT_DATA_CHUNK_SIZE},
+    proto::Dispatcher,
+};
 
 pub struct H3Service<S> {
     service: S,
+    early_data: EarlyData,
+    max_data_chunk_size: usize,
 }
 
 impl<S> H3Service<S> {
     /// Construct new Http3Service.
     /// No upgrade/expect services allowed in Http/3.
     pub fn new(service: S) -> Self {
-        Self { service }
+        Self {
+            service,
+            early_data: EarlyData::Disable,
+            max_data_chunk_size: DEFAULT_DATA_CHUNK_SIZE,
+        }
+    }
+
+    /// Set strategy for request in 0-RTT early data. See [EarlyData] for detail.
+    pub fn early_data(mut self, early_data: EarlyData) -> Self {
+        self.early_data = early_data;
+        self
+    }
+
+    /// Set max size in bytes of data written to quic stream at once.
+    /// See [H3ServiceBuilder::max_data_chunk_size](super::H3ServiceBuilder::max_data_chunk_size) for detail.
+    pub fn max_data_chunk_size(mut self, size: usize) -> Self {
+        assert!(size > 0, "max_data_chunk_size must be greater than 0");
+        self.max_data_chunk_size = size;
+        self
     }
 }
 
@@ -41,7 +65,7 @@ where
         UdpStream: 's,
     {
         async move {
-            let dispatcher = Dispatcher::new(stream, addr, &self.service);
+            let dispatcher = Dispatcher::new(stream, addr, &self.service, self.early_data, self.max_data_chunk_size);
 
             dispatcher.run().await?;
 
@@ -1,17 +1,47 @@
+//! Http types re-exported from `http` crate with extended types from xitca-http.
+//!
+//! # Request uri
+//! [Request::uri] is completed to an absolute form regardless of the protocol request is
+//! received from so the scheme and authority of it can be accessed through [Uri::scheme] and
+//! [Uri::authority] consistently:
+//!
+//! - http/1: request target in origin-form(`/path?query`) is combined with `http` or `https`
+//!   scheme depending on if the connection is served with tls and the value of `Host` header.
+//!   request target in absolute-form is kept as is.
+//! - http/2 and http/3: `:scheme` and `:authority` pseudo headers are used. When `:authority` is
+//!   absent the `Host` header is used instead.
+//!
+//! Request uri is left in it's original form when there is no authority available (e.g. http/1.0
+//! request without `Host` header) and for CONNECT(authority-form) and OPTIONS(asterisk-form)
+//! request targets.
+//!
+//! [Request::version] is always the protocol version request is received from.
+
 // re-export everything from http crate.
 pub use ::http::*;
 
 use core::{
     borrow::{Borrow, BorrowMut},
     pin::Pin,
-    task::{Context, Poll},
+    task::{Context, Poll, Waker},
+    time::Duration,
 };
 
-use std::net::SocketAddr;
+use std::{
+    collections::VecDeque,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Instant,
+};
 
 use futures_core::stream::Stream;
 use pin_project_lite::pin_project;
 
+use crate::bytes::Bytes;
+
 /// Some often used header value.
 #[allow(clippy::declare_interior_mutable_const)]
 pub mod const_header_value {
@@ -29,6 +59,7 @@ pub mod const_header_value {
         (TEXT, "text/plain"),
         (TEXT_UTF8, "text/plain; charset=utf-8"),
         (JSON, "application/json"),
+        (NDJSON, "application/x-ndjson"),
         (TEXT_HTML_UTF8, "text/html; charset=utf-8"),
         (GRPC, "application/grpc"),
         (WEBSOCKET, "websocket")
@@ -48,7 +79,12 @@ pub mod const_header_name {
             }
         }
 
-    const_name!((PROTOCOL, "protocol"));
+    const_name!(
+        (PROTOCOL, "protocol"),
+        (GRPC_STATUS, "grpc-status"),
+        (GRPC_MESSAGE, "grpc-message"),
+        (GRPC_ENCODING, "grpc-encoding")
+    );
 }
 
 /// Helper trait for convert a [Request] to [Response].
@@ -87,12 +123,473 @@ where
     }
 }
 
+/// Raw bytes of http/1 request head exactly as received from peer. Including request line, header
+/// lines and the ending empty line.
+///
+/// Only present in request's [Extensions] when enabled by
+/// [HttpServiceConfig::keep_raw_request_head](crate::config::HttpServiceConfig::keep_raw_request_head).
+/// Http/2 and Http/3 requests never carry it as their headers are not transferred in plain text.
+#[derive(Clone, Debug)]
+pub struct RawRequestHead(pub Bytes);
+
+pub use crate::util::scratch::Scratch;
+
+/// DER encoded certificate chain presented by peer in tls handshake and verified by tls acceptor.
+/// The first certificate is the end entity one.
+///
+/// Present in request's [Extensions] when the connection is accepted by rustls or openssl
+/// acceptor of [HttpService](crate::HttpService) and peer presents certificate. Whether a
+/// certificate is demanded is decided by the client verifier of tls config. Clone of it is
+/// cheap and shared by all requests of the connection.
+#[derive(Clone, Debug)]
+pub struct PeerCertificates(Arc<[Bytes]>);
+
+impl PeerCertificates {
+    /// Construct from DER encoded certificates ordered from end entity to root.
+    ///
+    /// # Panics:
+    ///
+    /// When given chain is empty.
+    pub fn new(chain: Vec<Bytes>) -> Self {
+        assert!(!chain.is_empty(), "certificate chain must not be empty");
+        Self(chain.into())
+    }
+
+    /// DER encoded end entity certificate of peer.
+    #[inline]
+    pub fn leaf(&self) -> &Bytes {
+        &self.0[0]
+    }
+
+    /// DER encoded certificate chain.
+    #[inline]
+    pub fn chain(&self) -> &[Bytes] {
+        &self.0
+    }
+}
+
+/// Facts of tls connection negotiated in handshake.
+///
+/// Present in request's [Extensions] when the connection is accepted by rustls or openssl
+/// acceptor of [HttpService](crate::HttpService). It's shared by all requests of the connection.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct TlsConnectionInfo {
+    protocol_version: &'static str,
+    cipher_suite: &'static str,
+    resumed: Option<bool>,
+    early_data: bool,
+}
+
+impl TlsConnectionInfo {
+    /// Construct from negotiated protocol version and cipher suite names, if the handshake resumed
+    /// a previous session and if early data is accepted.
+    pub const fn new(
+        protocol_version: &'static str,
+        cipher_suite: &'static str,
+        resumed: Option<bool>,
+        early_data: bool,
+    ) -> Self {
+        Self {
+            protocol_version,
+            cipher_suite,
+            resumed,
+            early_data,
+        }
+    }
+
+    /// Negotiated protocol version. (e.g. `TLSv1.3`)
+    #[inline]
+    pub fn protocol_version(&self) -> &'static str {
+        self.protocol_version
+    }
+
+    /// Negotiated cipher suite named as the tls library reports it.
+    #[inline]
+    pub fn cipher_suite(&self) -> &'static str {
+        self.cipher_suite
+    }
+
+    /// If the handshake resumed a previous session instead of performing a full one. None when
+    /// tls library can not tell it. (e.g. TLSv1.2 connection accepted by rustls)
+    #[inline]
+    pub fn resumed(&self) -> Option<bool> {
+        self.resumed
+    }
+
+    /// If 0-RTT early data sent by client is accepted. Requests of such connection can be
+    /// replayed by attacker and service should only act on idempotent ones or respond with
+    /// [too_early](crate::response::too_early).
+    #[inline]
+    pub fn early_data(&self) -> bool {
+        self.early_data
+    }
+}
+
+/// Precise time when request head is decoded by server.
+///
+/// Only present in request's [Extensions] when enabled by
+/// [HttpServiceConfig::request_arrival_time](crate::config::HttpServiceConfig::request_arrival_time).
+/// It can be used to tell how long a request is waiting before reaching a service.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub struct RequestArrival(pub Instant);
+
+impl RequestArrival {
+    /// Duration passed since request arrival.
+    #[inline]
+    pub fn elapsed(&self) -> Duration {
+        self.0.elapsed()
+    }
+}
+
+/// Handle for sending informational(1xx) responses ahead of the final response of request.
+///
+/// Only present in request's [Extensions] when enabled by
+/// [HttpServiceConfig::informational_responses](crate::config::HttpServiceConfig::informational_responses)
+/// and the request is received from Http/1.1. Http/1.0 client does not understand 1xx responses
+/// and Http/2 dispatcher can not send them.
+///
+/// Responses sent before service produces the final response are written to client in order and
+/// ahead of it. Clone of it is cheap and shares the same queue.
+///
+/// # Examples:
+/// ```rust
+/// use xitca_http::http::{header::{HeaderMap, HeaderValue, LINK}, Informational, Request, StatusCode};
+///
+/// fn early_hints<B>(req: &Request<B>) {
+///     if let Some(informational) = req.extensions().get::<Informational>() {
+///         let mut headers = HeaderMap::new();
+///         headers.insert(LINK, HeaderValue::from_static("</style.css>; rel=preload; as=style"));
+///         informational.send(StatusCode::from_u16(103).unwrap(), headers);
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Informational(Arc<Mutex<InformationalQueue>>);
+
+#[derive(Debug, Default)]
+struct InformationalQueue {
+    queue: VecDeque<(StatusCode, HeaderMap)>,
+    waker: Option<Waker>,
+    closed: bool,
+}
+
+impl Informational {
+    #[cfg(feature = "http1")]
+    pub(crate) fn channel() -> (Self, InformationalReceiver) {
+        let queue = Arc::new(Mutex::new(InformationalQueue::default()));
+        (Self(queue.clone()), InformationalReceiver(queue))
+    }
+
+    /// Send an informational response with given status code and headers. Return false when the
+    /// final response is already produced and the informational one is dropped.
+    ///
+    /// # Panics
+    /// When status code is not 1xx or it's `100 Continue` or `101 Switching Protocols` which are
+    /// managed by server.
+    pub fn send(&self, status: StatusCode, headers: HeaderMap) -> bool {
+        assert!(
+            status.is_informational() && status != StatusCode::CONTINUE && status != StatusCode::SWITCHING_PROTOCOLS,
+            "{status} can not be sent as informational response"
+        );
+
+        let mut inner = self.0.lock().unwrap();
+        if inner.closed {
+            return false;
+        }
+        inner.queue.push_back((status, headers));
+        if let Some(waker) = inner.waker.take() {
+            waker.wake();
+        }
+        true
+    }
+}
+
+// dispatcher side of Informational. dropping it closes the queue.
+#[cfg(feature = "http1")]
+pub(crate) struct InformationalReceiver(Arc<Mutex<InformationalQueue>>);
+
+#[cfg(feature = "http1")]
+impl InformationalReceiver {
+    pub(crate) fn try_recv(&self) -> Option<(StatusCode, HeaderMap)> {
+        self.0.lock().unwrap().queue.pop_front()
+    }
+
+    pub(crate) fn poll_recv(&self, cx: &mut Context<'_>) -> Poll<(StatusCode, HeaderMap)> {
+        let mut inner = self.0.lock().unwrap();
+        match inner.queue.pop_front() {
+            Some(res) => Poll::Ready(res),
+            None => {
+                inner.waker = Some(cx.waker().clone());
+                Poll::Pending
+            }
+        }
+    }
+}
+
+#[cfg(feature = "http1")]
+impl Drop for InformationalReceiver {
+    fn drop(&mut self) {
+        let mut inner = self.0.lock().unwrap();
+        inner.closed = true;
+        inner.queue.clear();
+    }
+}
+
+/// Max size in bytes of request body shared with the dispatcher decoding it.
+///
+/// Present in request's [Extensions] when request has a body. It starts with the value of
+/// [HttpServiceConfig::max_request_body_size](crate::config::HttpServiceConfig::max_request_body_size)
+/// and a service can override it with a smaller or larger value for the request. The override
+/// must happen before the first time service yields or polls request body so a body advertising
+/// `Content-Length` larger than the limit is checked against the overridden value.
+///
+/// # Examples:
+/// ```rust
+/// use xitca_http::http::{Request, RequestBodyLimit};
+///
+/// fn allow_upload<B>(req: &Request<B>) {
+///     if let Some(limit) = req.extensions().get::<RequestBodyLimit>() {
+///         limit.set(64 * 1024 * 1024);
+///     }
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct RequestBodyLimit(Arc<BodyLimitState>);
+
+#[derive(Debug)]
+struct BodyLimitState {
+    max: AtomicU64,
+    exceeded: AtomicBool,
+}
+
+impl RequestBodyLimit {
+    #[cfg(any(feature = "http1", feature = "http2"))]
+    pub(crate) fn new(max: u64) -> Self {
+        Self(Arc::new(BodyLimitState {
+            max: AtomicU64::new(max),
+            exceeded: AtomicBool::new(false),
+        }))
+    }
+
+    /// Current max size in bytes.
+    #[inline]
+    pub fn get(&self) -> u64 {
+        self.0.max.load(Ordering::Relaxed)
+    }
+
+    /// Override max size in bytes.
+    #[inline]
+    pub fn set(&self, max: u64) {
+        self.0.max.store(max, Ordering::Relaxed);
+    }
+
+    /// Return true when request body is aborted by dispatcher for going over the limit.
+    #[inline]
+    pub fn is_exceeded(&self) -> bool {
+        self.0.exceeded.load(Ordering::Relaxed)
+    }
+
+    // size of body already received plus the size still expected is checked against limit.
+    #[cfg(any(feature = "http1", feature = "http2"))]
+    pub(crate) fn check(&self, size: u64) -> bool {
+        let exceeded = size > self.get();
+        if exceeded {
+            self.0.exceeded.store(true, Ordering::Relaxed);
+        }
+        !exceeded
+    }
+}
+
+/// Map of header names to their on-wire spelling for http/1 response.
+///
+/// When inserted into response's [Extensions] and
+/// [HttpServiceConfig::preserve_header_case](crate::config::HttpServiceConfig::preserve_header_case)
+/// is enabled the mapped spelling is written instead of lowercase header name. Http/2 and Http/3
+/// ignore it as their header names must be lowercase.
+///
+/// # Examples:
+/// ```rust
+/// use xitca_http::http::{HeaderCaseMap, Response};
+///
+/// let mut map = HeaderCaseMap::new();
+/// map.insert("X-API-Key");
+///
+/// let mut res = Response::new(());
+/// res.headers_mut().insert("x-api-key", "996".parse().unwrap());
+/// res.extensions_mut().insert(map);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct HeaderCaseMap(std::collections::HashMap<HeaderName, Box<str>>);
+
+impl HeaderCaseMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Insert on-wire spelling of a header name. Lowercase form of spelling is used as key.
+    ///
+    /// # Panics:
+    ///
+    /// When given spelling is not a valid header name.
+    pub fn insert(&mut self, spelling: &str) {
+        let name = HeaderName::from_bytes(spelling.as_bytes()).expect("invalid header name");
+        self.0.insert(name, spelling.into());
+    }
+
+    /// Get on-wire spelling of given header name.
+    pub fn get(&self, name: &HeaderName) -> Option<&str> {
+        self.0.get(name).map(|s| &**s)
+    }
+}
+
+/// Value of `:protocol` pseudo header of an extended CONNECT request. See
+/// [RFC 8441](https://www.rfc-editor.org/rfc/rfc8441#section-4).
+///
+/// Present in request's [Extensions] when a http/2 CONNECT request carries it. Request and
+/// response bodies of a CONNECT request form a bidirectional tunnel the same way they do for a
+/// http/1 upgrade request: after responding `200 OK` with a streaming body the request body reads
+/// from peer and the response body writes to it. So handler like websocket can serve both without
+/// knowing the protocol version.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Protocol(Box<str>);
+
+impl Protocol {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for Protocol {
+    fn from(value: &str) -> Self {
+        Self(value.into())
+    }
+}
+
+/// Remove hop-by-hop headers that are meaningful only for a single transport-level connection and
+/// must not be forwarded by proxies. See [RFC 9110](https://www.rfc-editor.org/rfc/rfc9110#section-7.6.1).
+///
+/// Besides the well known ones (`Connection`, `Keep-Alive`, `Proxy-Connection`, `TE`, `Trailer`,
+/// `Transfer-Encoding` and `Upgrade`) headers listed in `Connection` header value are removed too.
+///
+/// # Examples:
+/// ```rust
+/// use xitca_http::http::{remove_hop_by_hop_headers, HeaderMap, HeaderValue};
+///
+/// let mut headers = HeaderMap::new();
+/// headers.insert("connection", HeaderValue::from_static("keep-alive, x-hop"));
+/// headers.insert("x-hop", HeaderValue::from_static("1"));
+/// headers.insert("x-end", HeaderValue::from_static("2"));
+///
+/// remove_hop_by_hop_headers(&mut headers);
+///
+/// assert_eq!(headers.len(), 1);
+/// assert!(headers.contains_key("x-end"));
+/// ```
+pub fn remove_hop_by_hop_headers(headers: &mut HeaderMap) {
+    let names = headers
+        .get_all(header::CONNECTION)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|name| HeaderName::from_bytes(name.trim().as_bytes()).ok())
+        .collect::<Vec<_>>();
+
+    for name in names {
+        headers.remove(name);
+    }
+
+    for name in [
+        header::CONNECTION,
+        header::TE,
+        header::TRAILER,
+        header::TRANSFER_ENCODING,
+        header::UPGRADE,
+    ] {
+        headers.remove(name);
+    }
+
+    headers.remove("keep-alive");
+    headers.remove("proxy-connection");
+}
+
+// strip hop-by-hop headers from request and check singleton headers. return false when request
+// has duplicate Content-Type or Host headers or Content-Length headers with differing values.
+// duplicate Content-Length headers with the same value are collapsed into one.
+//
+// Connection and Upgrade headers of upgrade request are kept for service to handle the upgrade.
+#[cfg(any(feature = "http1", feature = "http2"))]
+pub(crate) fn normalize_request_headers(headers: &mut HeaderMap) -> bool {
+    use header::{CONNECTION, CONTENT_LENGTH, CONTENT_TYPE, HOST, UPGRADE};
+
+    if headers.get_all(CONTENT_TYPE).iter().nth(1).is_some() || headers.get_all(HOST).iter().nth(1).is_some() {
+        return false;
+    }
+
+    let mut lens = headers.get_all(CONTENT_LENGTH).iter();
+    if let Some(len) = lens.next() {
+        if lens.any(|l| l != len) {
+            return false;
+        }
+        let len = len.clone();
+        headers.insert(CONTENT_LENGTH, len);
+    }
+
+    let is_upgrade = headers.contains_key(UPGRADE)
+        && headers
+            .get_all(CONNECTION)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .any(|v| v.trim().eq_ignore_ascii_case("upgrade"));
+
+    if is_upgrade {
+        let connection = headers.get_all(CONNECTION).iter().cloned().collect::<Vec<_>>();
+        let upgrade = headers.get_all(UPGRADE).iter().cloned().collect::<Vec<_>>();
+        remove_hop_by_hop_headers(headers);
+        connection.into_iter().for_each(|v| {
+            headers.append(CONNECTION, v);
+        });
+        upgrade.into_iter().for_each(|v| {
+            headers.append(UPGRADE, v);
+        });
+    } else {
+        remove_hop_by_hop_headers(headers);
+    }
+
+    true
+}
+
+// complete request uri with given scheme and Host header when it's in origin-form and lacks
+// authority.
+#[cfg(any(feature = "http1", feature = "http2", feature = "http3"))]
+pub(crate) fn complete_uri<B>(req: &mut Request<B>, scheme: &uri::Scheme) {
+    if req.uri().authority().is_some() || !req.uri().path().starts_with('/') {
+        return;
+    }
+
+    let Some(authority) = req
+        .headers()
+        .get(header::HOST)
+        .and_then(|host| uri::Authority::try_from(host.as_bytes()).ok())
+    else {
+        return;
+    };
+
+    let mut parts = req.uri().clone().into_parts();
+    parts.scheme = Some(scheme.clone());
+    parts.authority = Some(authority);
+
+    if let Ok(uri) = Uri::from_parts(parts) {
+        *req.uri_mut() = uri;
+    }
+}
+
 #[cfg(feature = "util-service")]
 use super::util::service::router::Params;
 
 pin_project! {
     /// typed http extension
-    #[derive(Debug)]
+    #[derive(Clone, Debug)]
     pub struct RequestExt<B> {
         #[pin]
         body: B,
@@ -102,7 +599,7 @@ pin_project! {
 
 // a separate extension type contain information can not be carried by http::Request. the goal is
 // to keep extended info strongly typed and not depend on runtime type map of http::Extensions.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub(crate) struct Extension(Box<_Extension>);
 
 impl Extension {
@@ -115,7 +612,7 @@ impl Extension {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 struct _Extension {
     addr: SocketAddr,
     #[cfg(feature = "util-service")]
@@ -244,6 +741,20 @@ impl<Ext> BorrowReq<Method> for Request<Ext> {
     }
 }
 
+impl<Ext> BorrowReq<HeaderMap> for Request<Ext> {
+    #[inline]
+    fn borrow(&self) -> &HeaderMap {
+        self.headers()
+    }
+}
+
+impl<Ext> BorrowReq<Extensions> for Request<Ext> {
+    #[inline]
+    fn borrow(&self) -> &Extensions {
+        self.extensions()
+    }
+}
+
 impl<Ext> BorrowReqMut<Extensions> for Request<Ext> {
     #[inline]
     fn borrow_mut(&mut self) -> &mut Extensions {
@@ -6,6 +6,7 @@
 
 #![forbid(unsafe_code)]
 #![feature(impl_trait_in_assoc_type)]
+#![feature(min_specialization)]
 
 #[cfg(feature = "runtime")]
 mod builder;
@@ -17,6 +18,7 @@ mod version;
 pub mod body;
 pub mod error;
 pub mod http;
+pub mod response;
 
 #[cfg(feature = "runtime")]
 pub mod date;
@@ -36,6 +38,8 @@ pub use xitca_io::bytes;
 pub use self::body::{RequestBody, ResponseBody};
 pub use self::error::{BodyError, HttpServiceError};
 pub use self::http::{Request, Response};
+#[cfg(feature = "rustls")]
+pub use self::tls::{ReloadableTlsAcceptor, RustlsAcceptorBuilder, TlsStats};
 #[cfg(feature = "runtime")]
 pub use self::{builder::HttpServiceBuilder, service::HttpService};
 
@@ -0,0 +1,181 @@
+//! Canned http responses for common error conditions.
+//!
+//! These responses are used by dispatchers when a request can not be passed to service. They are
+//! also available for middleware and services that want to produce the same output.
+//!
+//! All responses come with `cache-control: no-store` header and empty body.
+
+use core::time::Duration;
+
+use crate::{
+    body::ResponseBody,
+    http::{
+        header::{HeaderValue, CACHE_CONTROL, CONNECTION, RETRY_AFTER},
+        Response, StatusCode,
+    },
+    util::header::int_header_value,
+};
+
+/// 400 Bad Request.
+pub fn bad_request<B>() -> Response<ResponseBody<B>> {
+    status_only(StatusCode::BAD_REQUEST)
+}
+
+/// 408 Request Timeout with `connection: close` header.
+pub fn request_timeout<B>() -> Response<ResponseBody<B>> {
+    let mut res = status_only(StatusCode::REQUEST_TIMEOUT);
+    res.headers_mut().insert(CONNECTION, HeaderValue::from_static("close"));
+    res
+}
+
+/// 413 Payload Too Large.
+pub fn payload_too_large<B>() -> Response<ResponseBody<B>> {
+    status_only(StatusCode::PAYLOAD_TOO_LARGE)
+}
+
+/// 414 URI Too Long with `connection: close` header.
+pub fn uri_too_long<B>() -> Response<ResponseBody<B>> {
+    let mut res = status_only(StatusCode::URI_TOO_LONG);
+    res.headers_mut().insert(CONNECTION, HeaderValue::from_static("close"));
+    res
+}
+
+/// 425 Too Early. Used for rejecting request sent in replayable early data. See
+/// [RFC 8470](https://www.rfc-editor.org/rfc/rfc8470#section-5.2) for detail.
+pub fn too_early<B>() -> Response<ResponseBody<B>> {
+    status_only(StatusCode::from_u16(425).unwrap())
+}
+
+/// 429 Too Many Requests with `connection: close` header.
+pub fn too_many_requests<B>() -> Response<ResponseBody<B>> {
+    let mut res = status_only(StatusCode::TOO_MANY_REQUESTS);
+    res.headers_mut().insert(CONNECTION, HeaderValue::from_static("close"));
+    res
+}
+
+/// 431 Request Header Fields Too Large.
+pub fn header_too_large<B>() -> Response<ResponseBody<B>> {
+    status_only(StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE)
+}
+
+/// 500 Internal Server Error with `connection: close` header.
+pub fn internal_server_error<B>() -> Response<ResponseBody<B>> {
+    let mut res = status_only(StatusCode::INTERNAL_SERVER_ERROR);
+    res.headers_mut().insert(CONNECTION, HeaderValue::from_static("close"));
+    res
+}
+
+/// 503 Service Unavailable. When retry_after is given it's written as `Retry-After` header in
+/// delta-seconds format.
+pub fn service_unavailable<B>(retry_after: Option<Duration>) -> Response<ResponseBody<B>> {
+    let mut res = status_only(StatusCode::SERVICE_UNAVAILABLE);
+    if let Some(dur) = retry_after {
+        res.headers_mut().insert(RETRY_AFTER, int_header_value(dur.as_secs()));
+    }
+    res
+}
+
+/// 503 Service Unavailable with `Retry-After` header in HTTP-date format. The date is calculated
+/// from current date of given [DateTime](crate::date::DateTime) plus retry_after duration.
+#[cfg(feature = "runtime")]
+pub fn service_unavailable_date<B, D>(retry_after: Duration, date: &D) -> Response<ResponseBody<B>>
+where
+    D: crate::date::DateTime,
+{
+    let mut res = status_only(StatusCode::SERVICE_UNAVAILABLE);
+
+    let value = date
+        .with_date(|date| core::str::from_utf8(date).ok().map(httpdate::parse_http_date))
+        .and_then(Result::ok)
+        .map(|now| httpdate::fmt_http_date(now + retry_after))
+        .and_then(|date| HeaderValue::try_from(date).ok());
+
+    if let Some(value) = value {
+        res.headers_mut().insert(RETRY_AFTER, value);
+    }
+
+    res
+}
+
+fn status_only<B>(status: StatusCode) -> Response<ResponseBody<B>> {
+    let mut res = Response::new(ResponseBody::None);
+    *res.status_mut() = status;
+    res.headers_mut()
+        .insert(CACHE_CONTROL, HeaderValue::from_static("no-store"));
+    res
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{body::BoxStream, http::header::HeaderMap};
+
+    use super::*;
+
+    fn assert_res(res: Response<ResponseBody<BoxStream>>, status: StatusCode, headers: &[(&'static str, &str)]) {
+        assert_eq!(res.status(), status);
+        let mut expected = HeaderMap::new();
+        expected.insert(CACHE_CONTROL, HeaderValue::from_static("no-store"));
+        for (name, value) in headers {
+            expected.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        assert_eq!(res.headers(), &expected);
+        assert!(matches!(res.body(), ResponseBody::None));
+    }
+
+    #[test]
+    fn canned() {
+        assert_res(bad_request(), StatusCode::BAD_REQUEST, &[]);
+        assert_res(
+            request_timeout(),
+            StatusCode::REQUEST_TIMEOUT,
+            &[("connection", "close")],
+        );
+        assert_res(payload_too_large(), StatusCode::PAYLOAD_TOO_LARGE, &[]);
+        assert_res(uri_too_long(), StatusCode::URI_TOO_LONG, &[("connection", "close")]);
+        assert_res(too_early(), StatusCode::from_u16(425).unwrap(), &[]);
+        assert_res(
+            too_many_requests(),
+            StatusCode::TOO_MANY_REQUESTS,
+            &[("connection", "close")],
+        );
+        assert_res(header_too_large(), StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE, &[]);
+        assert_res(
+            internal_server_error(),
+            StatusCode::INTERNAL_SERVER_ERROR,
+            &[("connection", "close")],
+        );
+        assert_res(service_unavailable(None), StatusCode::SERVICE_UNAVAILABLE, &[]);
+        assert_res(
+            service_unavailable(Some(Duration::from_millis(120_500))),
+            StatusCode::SERVICE_UNAVAILABLE,
+            &[("retry-after", "120")],
+        );
+    }
+
+    #[cfg(feature = "runtime")]
+    #[test]
+    fn retry_after_date() {
+        struct FixedDate;
+
+        impl crate::date::DateTime for FixedDate {
+            const DATE_VALUE_LENGTH: usize = 29;
+
+            fn with_date<F, O>(&self, f: F) -> O
+            where
+                F: FnOnce(&[u8]) -> O,
+            {
+                f(b"Sun, 06 Nov 1994 08:49:37 GMT")
+            }
+
+            fn now(&self) -> tokio::time::Instant {
+                tokio::time::Instant::now()
+            }
+        }
+
+        assert_res(
+            service_unavailable_date(Duration::from_secs(90), &FixedDate),
+            StatusCode::SERVICE_UNAVAILABLE,
+            &[("retry-after", "Sun, 06 Nov 1994 08:51:07 GMT")],
+        );
+    }
+}
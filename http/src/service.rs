@@ -1,5 +1,7 @@
 use core::{fmt, future::Future, marker::PhantomData, pin::pin};
 
+use std::net::SocketAddr;
+
 use futures_core::Stream;
 use xitca_io::{
     io::{AsyncIo, AsyncRead, AsyncWrite},
@@ -11,11 +13,16 @@ use xitca_service::{ready::ReadyService, Service};
 use super::{
     body::RequestBody,
     bytes::Bytes,
-    config::HttpServiceConfig,
+    config::{ConnectionEvent, HttpServiceConfig},
     date::{DateTime, DateTimeService},
     error::{HttpServiceError, TimeoutError},
     http::{Request, RequestExt, Response},
-    util::timer::{KeepAlive, Timeout},
+    tls::TlsExtensions,
+    util::{
+        drain::DrainWatch,
+        limit::{ConnectionLimit, ConnectionPermit},
+        timer::{KeepAlive, Timeout},
+    },
     version::AsVersion,
 };
 
@@ -31,8 +38,10 @@ pub struct HttpService<
 > {
     pub(crate) config: HttpServiceConfig<HEADER_LIMIT, READ_BUF_LIMIT, WRITE_BUF_LIMIT>,
     pub(crate) date: DateTimeService,
+    pub(crate) drain: Option<DrainWatch>,
     pub(crate) service: S,
     pub(crate) tls_acceptor: A,
+    limit: Option<ConnectionLimit>,
     _body: PhantomData<(St, ReqB)>,
 }
 
@@ -46,8 +55,10 @@ impl<St, S, ReqB, A, const HEADER_LIMIT: usize, const READ_BUF_LIMIT: usize, con
         tls_acceptor: A,
     ) -> Self {
         Self {
+            limit: config.max_connections.map(ConnectionLimit::new),
             config,
-            date: DateTimeService::new(),
+            date: DateTimeService::current(),
+            drain: None,
             service,
             tls_acceptor,
             _body: PhantomData,
@@ -69,7 +80,107 @@ impl<St, S, ReqB, A, const HEADER_LIMIT: usize, const READ_BUF_LIMIT: usize, con
     pub(crate) fn keep_alive(&self) -> KeepAlive {
         let accept_dur = self.config.tls_accept_timeout;
         let deadline = self.date.get().now() + accept_dur;
-        KeepAlive::new(deadline)
+        KeepAlive::new(deadline, self.config.keep_alive_granularity)
+    }
+}
+
+impl<St, S, A, const HEADER_LIMIT: usize, const READ_BUF_LIMIT: usize, const WRITE_BUF_LIMIT: usize>
+    HttpService<St, S, RequestBody, A, HEADER_LIMIT, READ_BUF_LIMIT, WRITE_BUF_LIMIT>
+{
+    /// Serve a connection provided by caller and return when the connection is finished.
+    ///
+    /// Connection goes through the same pipeline as the ones accepted from listener including
+    /// tls acceptor, config and timers of service. Tls is skipped when service is built without
+    /// tls acceptor. http version is decided by alpn protocol of tls stream and falls back to
+    /// http/1 for plain connection.
+    ///
+    /// `addr` is the peer address exposed to request. Unspecified address is used when it's None.
+    ///
+    /// Poll based io types can be served with [PollIoAdapter](xitca_io::io::PollIoAdapter).
+    pub async fn serve_connection<Io, ResB, BE>(
+        &self,
+        io: Io,
+        addr: Option<SocketAddr>,
+    ) -> Result<(), HttpServiceError<S::Error, BE>>
+    where
+        S: Service<Request<RequestExt<RequestBody>>, Response = Response<ResB>>,
+        A: Service<Io>,
+        A::Response: AsyncIo + AsVersion + AsyncRead + AsyncWrite + Unpin,
+        HttpServiceError<S::Error, BE>: From<A::Error>,
+        S::Error: fmt::Debug,
+        ResB: Stream<Item = Result<Bytes, BE>>,
+        BE: fmt::Debug,
+    {
+        let _addr = addr.unwrap_or_else(crate::unspecified_socket_addr);
+
+        let observer = Observer::open(self.config.connection_observer, _addr);
+
+        // tls accept timer.
+        let timer = self.keep_alive();
+        let mut timer = pin!(timer);
+
+        let mut _tls_stream = match self.tls_acceptor.call(io).timeout(timer.as_mut()).await {
+            Ok(Ok(stream)) => stream,
+            Ok(Err(e)) => {
+                observer.event(ConnectionEvent::TlsAcceptFailed);
+                return Err(e.into());
+            }
+            Err(_) => {
+                observer.event(ConnectionEvent::TlsAcceptFailed);
+                return Err(HttpServiceError::Timeout(TimeoutError::TlsAccept));
+            }
+        };
+
+        let version = _tls_stream.as_version();
+
+        let _tls = TlsExtensions::from_stream(&_tls_stream);
+
+        if let Some(info) = _tls.info() {
+            observer.event(ConnectionEvent::TlsHandshake(info));
+        }
+
+        match version {
+            #[cfg(feature = "http1")]
+            super::http::Version::HTTP_11 | super::http::Version::HTTP_10 => super::h1::dispatcher::run(
+                &mut _tls_stream,
+                _addr,
+                timer.as_mut(),
+                &self.config,
+                &self.service,
+                self.date.get(),
+                self.drain.as_ref(),
+                _tls,
+            )
+            .await
+            .map_err(From::from),
+            #[cfg(feature = "http2")]
+            super::http::Version::HTTP_2 => {
+                // update timer to first request timeout.
+                self.update_first_request_deadline(timer.as_mut());
+
+                let mut conn = ::h2::server::Builder::new()
+                    .enable_connect_protocol()
+                    .handshake(_tls_stream)
+                    .timeout(timer.as_mut())
+                    .await
+                    .map_err(|_| HttpServiceError::Timeout(TimeoutError::H2Handshake))??;
+
+                super::h2::Dispatcher::new(
+                    &mut conn,
+                    _addr,
+                    timer.as_mut(),
+                    &self.config,
+                    &self.service,
+                    self.date.get(),
+                    self.drain.as_ref(),
+                    _tls,
+                )
+                .run()
+                .await
+                .map_err(Into::into)
+            }
+            version => Err(HttpServiceError::UnSupportedVersion(version)),
+        }
     }
 }
 
@@ -94,71 +205,16 @@ where
         ServerStream: 's,
     {
         async {
-            // tls accept timer.
-            let timer = self.keep_alive();
-            let mut timer = pin!(timer);
-
             match io {
                 #[cfg(feature = "http3")]
-                ServerStream::Udp(io, addr) => super::h3::Dispatcher::new(io, addr, &self.service)
-                    .run()
-                    .await
-                    .map_err(From::from),
-                ServerStream::Tcp(io, _addr) => {
-                    let mut _tls_stream = self
-                        .tls_acceptor
-                        .call(io)
-                        .timeout(timer.as_mut())
+                ServerStream::Udp(io, addr) => {
+                    let _observer = Observer::open(self.config.connection_observer, addr);
+                    super::h3::Dispatcher::new(io, addr, &self.service)
+                        .run()
                         .await
-                        .map_err(|_| HttpServiceError::Timeout(TimeoutError::TlsAccept))??;
-
-                    let version = if self.config.peek_protocol {
-                        // peek version from connection to figure out the real protocol used
-                        // regardless of AsVersion's outcome.
-                        todo!("peek version is not implemented yet!")
-                    } else {
-                        _tls_stream.as_version()
-                    };
-
-                    match version {
-                        #[cfg(feature = "http1")]
-                        super::http::Version::HTTP_11 | super::http::Version::HTTP_10 => super::h1::dispatcher::run(
-                            &mut _tls_stream,
-                            _addr,
-                            timer.as_mut(),
-                            self.config,
-                            &self.service,
-                            self.date.get(),
-                        )
-                        .await
-                        .map_err(From::from),
-                        #[cfg(feature = "http2")]
-                        super::http::Version::HTTP_2 => {
-                            // update timer to first request timeout.
-                            self.update_first_request_deadline(timer.as_mut());
-
-                            let mut conn = ::h2::server::Builder::new()
-                                .enable_connect_protocol()
-                                .handshake(_tls_stream)
-                                .timeout(timer.as_mut())
-                                .await
-                                .map_err(|_| HttpServiceError::Timeout(TimeoutError::H2Handshake))??;
-
-                            super::h2::Dispatcher::new(
-                                &mut conn,
-                                _addr,
-                                timer.as_mut(),
-                                self.config.keep_alive_timeout,
-                                &self.service,
-                                self.date.get(),
-                            )
-                            .run()
-                            .await
-                            .map_err(Into::into)
-                        }
-                        version => Err(HttpServiceError::UnSupportedVersion(version)),
-                    }
+                        .map_err(From::from)
                 }
+                ServerStream::Tcp(io, addr) => self.serve_connection(io, Some(addr)).await,
                 #[cfg(unix)]
                 ServerStream::Unix(mut _io, _) => {
                     #[cfg(not(feature = "http1"))]
@@ -168,13 +224,25 @@ where
 
                     #[cfg(feature = "http1")]
                     {
+                        let _observer =
+                            Observer::open(self.config.connection_observer, crate::unspecified_socket_addr());
+
+                        // unix socket does not go through tls acceptor.
+                        let mut config = self.config;
+                        config.tls = false;
+
+                        let timer = self.keep_alive();
+                        let mut timer = pin!(timer);
+
                         super::h1::dispatcher::run(
                             &mut _io,
                             crate::unspecified_socket_addr(),
                             timer.as_mut(),
-                            self.config,
+                            &config,
                             &self.service,
                             self.date.get(),
+                            self.drain.as_ref(),
+                            TlsExtensions::default(),
                         )
                         .await
                         .map_err(From::from)
@@ -185,16 +253,179 @@ where
     }
 }
 
+// notify connection observer with open event on construction and close event on drop.
+struct Observer {
+    observer: Option<fn(SocketAddr, ConnectionEvent)>,
+    addr: SocketAddr,
+}
+
+impl Observer {
+    fn open(observer: Option<fn(SocketAddr, ConnectionEvent)>, addr: SocketAddr) -> Self {
+        let this = Self { observer, addr };
+        this.event(ConnectionEvent::Open);
+        this
+    }
+
+    fn event(&self, event: ConnectionEvent) {
+        if let Some(observer) = self.observer {
+            observer(self.addr, event);
+        }
+    }
+}
+
+impl Drop for Observer {
+    fn drop(&mut self) {
+        self.event(ConnectionEvent::Close);
+    }
+}
+
+// service is ready when connection count is below limit and both tls acceptor and service are ready.
+// the outputs are held by server until the connection accepted afterwards is finished.
 impl<St, S, ReqB, A, const HEADER_LIMIT: usize, const READ_BUF_LIMIT: usize, const WRITE_BUF_LIMIT: usize> ReadyService
     for HttpService<St, S, ReqB, A, HEADER_LIMIT, READ_BUF_LIMIT, WRITE_BUF_LIMIT>
 where
     S: ReadyService,
+    A: ReadyService,
 {
-    type Ready = S::Ready;
-    type Future<'f> = S::Future<'f> where Self: 'f;
+    type Ready = (Option<ConnectionPermit>, A::Ready, S::Ready);
+    type Future<'f> = impl Future<Output = Self::Ready> where Self: 'f;
 
     #[inline]
     fn ready(&self) -> Self::Future<'_> {
-        self.service.ready()
+        async {
+            let permit = match self.limit {
+                Some(ref limit) => Some(limit.acquire().await),
+                None => None,
+            };
+            let tls = self.tls_acceptor.ready().await;
+            let service = self.service.ready().await;
+            (permit, tls, service)
+        }
+    }
+}
+
+#[cfg(all(test, feature = "http1"))]
+mod test {
+    use core::convert::Infallible;
+
+    use tokio::{
+        io::{duplex, AsyncReadExt, AsyncWriteExt},
+        task::{spawn_local, LocalSet},
+    };
+    use xitca_io::io::PollIoAdapter;
+    use xitca_service::fn_service;
+
+    use crate::{body::ResponseBody, HttpServiceBuilder};
+
+    use super::*;
+
+    async fn handler(req: Request<RequestExt<RequestBody>>) -> Result<Response<ResponseBody>, Infallible> {
+        let body = format!("{} {}", req.uri(), req.body().socket_addr());
+        Ok(Response::new(Bytes::from(body).into()))
+    }
+
+    #[tokio::test]
+    async fn serve_connection() {
+        LocalSet::new()
+            .run_until(async {
+                let service = HttpServiceBuilder::new(fn_service(handler)).call(()).await.unwrap();
+
+                let (mut client, server) = duplex(64);
+                let addr = "127.0.0.1:8080".parse().unwrap();
+
+                let handle =
+                    spawn_local(async move { service.serve_connection(PollIoAdapter::new(server), Some(addr)).await });
+
+                client
+                    .write_all(b"GET /foo HTTP/1.1\r\nhost: localhost\r\n\r\nGET /bar HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\n\r\n")
+                    .await
+                    .unwrap();
+
+                let mut res = String::new();
+                client.read_to_string(&mut res).await.unwrap();
+
+                assert_eq!(res.matches("HTTP/1.1 200 OK\r\n").count(), 2);
+                assert!(res.contains("/foo 127.0.0.1:8080"));
+                assert!(res.ends_with("/bar 127.0.0.1:8080"));
+
+                handle.await.unwrap().unwrap();
+            })
+            .await
+    }
+
+    #[tokio::test]
+    async fn connection_observer() {
+        use std::sync::Mutex;
+
+        use crate::config::HttpServiceConfig;
+
+        static EVENTS: Mutex<Vec<(SocketAddr, ConnectionEvent)>> = Mutex::new(Vec::new());
+
+        fn observer(addr: SocketAddr, event: ConnectionEvent) {
+            EVENTS.lock().unwrap().push((addr, event));
+        }
+
+        LocalSet::new()
+            .run_until(async {
+                let config = HttpServiceConfig::new().connection_observer(observer);
+                let service = HttpServiceBuilder::with_config(fn_service(handler), config)
+                    .call(())
+                    .await
+                    .unwrap();
+
+                let (mut client, server) = duplex(64);
+                let addr = "127.0.0.1:8080".parse().unwrap();
+
+                let handle =
+                    spawn_local(async move { service.serve_connection(PollIoAdapter::new(server), Some(addr)).await });
+
+                client
+                    .write_all(b"GET / HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\n\r\n")
+                    .await
+                    .unwrap();
+
+                let mut res = String::new();
+                client.read_to_string(&mut res).await.unwrap();
+                handle.await.unwrap().unwrap();
+
+                assert_eq!(
+                    *EVENTS.lock().unwrap(),
+                    [(addr, ConnectionEvent::Open), (addr, ConnectionEvent::Close)]
+                );
+            })
+            .await
+    }
+
+    #[cfg(feature = "util-service")]
+    #[tokio::test]
+    async fn router_normalized_path() {
+        use crate::util::service::Router;
+
+        LocalSet::new()
+            .run_until(async {
+                let router = Router::new()
+                    .insert("/secret", fn_service(handler))
+                    .insert("/admin/secret", fn_service(handler));
+                let service = HttpServiceBuilder::new(router).call(()).await.unwrap();
+
+                let (mut client, server) = duplex(64);
+
+                let handle =
+                    spawn_local(async move { service.serve_connection(PollIoAdapter::new(server), None).await });
+
+                client
+                    .write_all(b"GET /admin/../secret HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\n\r\n")
+                    .await
+                    .unwrap();
+
+                let mut res = String::new();
+                client.read_to_string(&mut res).await.unwrap();
+                handle.await.unwrap().unwrap();
+
+                // dot segments are removed before request is routed.
+                assert!(res.starts_with("HTTP/1.1 200 OK\r\n"));
+                assert!(res.contains("\r\n\r\nhttp://localhost/secret "));
+            })
+            .await
     }
 }
@@ -17,9 +17,66 @@ mod error;
 
 pub use error::TlsError;
 
+#[cfg(feature = "rustls")]
+pub use self::rustls::{ReloadableTlsAcceptor, TlsAcceptorBuilder as RustlsAcceptorBuilder, TlsStats};
+
 use std::future::Future;
 
-use xitca_service::Service;
+use xitca_service::{ready::ReadyService, Service};
+
+use crate::http::{Extensions, PeerCertificates, TlsConnectionInfo};
+
+/// A helper trait for getting certificates presented by peer and other facts of tls connection.
+///
+/// It's implemented for all types and always return None by default which is for connection
+/// without tls. Tls stream types specialize it with facts of their connections so custom tls
+/// acceptor does not have to implement it.
+pub trait AsTlsInfo {
+    fn peer_certificates(&self) -> Option<PeerCertificates>;
+
+    fn tls_connection_info(&self) -> Option<TlsConnectionInfo>;
+}
+
+impl<T> AsTlsInfo for T {
+    #[inline]
+    default fn peer_certificates(&self) -> Option<PeerCertificates> {
+        None
+    }
+
+    #[inline]
+    default fn tls_connection_info(&self) -> Option<TlsConnectionInfo> {
+        None
+    }
+}
+
+// facts of tls connection inserted into extensions of every request served on it.
+#[derive(Default)]
+pub(crate) struct TlsExtensions {
+    peer_certs: Option<PeerCertificates>,
+    info: Option<TlsConnectionInfo>,
+}
+
+impl TlsExtensions {
+    pub(crate) fn from_stream<St>(stream: &St) -> Self {
+        Self {
+            peer_certs: stream.peer_certificates(),
+            info: stream.tls_connection_info(),
+        }
+    }
+
+    pub(crate) fn info(&self) -> Option<TlsConnectionInfo> {
+        self.info
+    }
+
+    pub(crate) fn insert_to(&self, ext: &mut Extensions) {
+        if let Some(ref certs) = self.peer_certs {
+            ext.insert(certs.clone());
+        }
+        if let Some(info) = self.info {
+            ext.insert(info);
+        }
+    }
+}
 
 /// A NoOp Tls Acceptor pass through input Stream type.
 #[derive(Copy, Clone)]
@@ -47,3 +104,13 @@ impl<St> Service<St> for NoOpTlsAcceptorService {
         async { Ok(io) }
     }
 }
+
+impl ReadyService for NoOpTlsAcceptorService {
+    type Ready = ();
+    type Future<'f> = impl Future<Output = Self::Ready> where Self: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::Future<'_> {
+        async {}
+    }
+}
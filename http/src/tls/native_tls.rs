@@ -11,7 +11,7 @@ use std::{
 
 use native_tls::{Error, HandshakeError};
 use xitca_io::io::{AsyncIo, AsyncRead, AsyncWrite, Interest, ReadBuf, Ready};
-use xitca_service::Service;
+use xitca_service::{ready::ReadyService, Service};
 
 use crate::{http::Version, version::AsVersion};
 
@@ -99,6 +99,16 @@ impl<St: AsyncIo> Service<St> for TlsAcceptorService {
     }
 }
 
+impl ReadyService for TlsAcceptorService {
+    type Ready = ();
+    type Future<'f> = impl Future<Output = Self::Ready> where Self: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::Future<'_> {
+        async {}
+    }
+}
+
 impl<S: AsyncIo> AsyncIo for TlsStream<S> {
     type Future<'f> = impl Future<Output = io::Result<Ready>> + 'f where Self: 'f;
 
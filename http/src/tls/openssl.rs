@@ -14,11 +14,15 @@ use openssl::{
     ssl::{Error, ErrorCode, ShutdownResult, Ssl, SslStream},
 };
 use xitca_io::io::{AsyncIo, AsyncRead, AsyncWrite, Interest, ReadBuf, Ready};
-use xitca_service::Service;
+use xitca_service::{ready::ReadyService, Service};
 
-use crate::{http::Version, version::AsVersion};
+use crate::{
+    bytes::Bytes,
+    http::{PeerCertificates, TlsConnectionInfo, Version},
+    version::AsVersion,
+};
 
-use super::error::TlsError;
+use super::{error::TlsError, AsTlsInfo};
 
 /// A wrapper type for [SslStream].
 ///
@@ -37,6 +41,31 @@ impl<Io> AsVersion for TlsStream<Io> {
     }
 }
 
+impl<Io> AsTlsInfo for TlsStream<Io> {
+    fn peer_certificates(&self) -> Option<PeerCertificates> {
+        let ssl = self.io.ssl();
+        let mut chain = vec![Bytes::from(ssl.peer_certificate()?.to_der().ok()?)];
+        // chain of server side connection does not include peer's end entity certificate.
+        if let Some(certs) = ssl.peer_cert_chain() {
+            chain.extend(certs.iter().filter_map(|cert| cert.to_der().ok()).map(Bytes::from));
+        }
+        Some(PeerCertificates::new(chain))
+    }
+
+    // openssl acceptor does not accept early data.
+    fn tls_connection_info(&self) -> Option<TlsConnectionInfo> {
+        let ssl = self.io.ssl();
+        let cipher = ssl.current_cipher()?;
+        let cipher = cipher.standard_name().unwrap_or_else(|| cipher.name());
+        Some(TlsConnectionInfo::new(
+            ssl.version_str(),
+            cipher,
+            Some(ssl.session_reused()),
+            false,
+        ))
+    }
+}
+
 #[derive(Clone)]
 pub struct TlsAcceptorBuilder {
     acceptor: TlsAcceptor,
@@ -102,6 +131,16 @@ impl<Io: AsyncIo> Service<Io> for TlsAcceptorService {
     }
 }
 
+impl ReadyService for TlsAcceptorService {
+    type Ready = ();
+    type Future<'f> = impl Future<Output = Self::Ready> where Self: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::Future<'_> {
+        async {}
+    }
+}
+
 impl<Io: AsyncIo> AsyncIo for TlsStream<Io> {
     type Future<'f> = impl Future<Output = io::Result<Ready>> + 'f where Self: 'f;
 
@@ -1,21 +1,33 @@
 use std::{
+    cell::RefCell,
     convert::Infallible,
-    error, fmt,
-    future::Future,
+    error, fmt, fs,
+    future::{poll_fn, Future},
     io,
+    path::PathBuf,
     pin::Pin,
-    sync::Arc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, RwLock, Weak,
+    },
     task::{Context, Poll},
+    thread,
+    time::Duration,
 };
 
-use rustls::{Error, ServerConfig, ServerConnection};
+use rustls::{server::ProducesTickets, CommonState, Error, ProtocolVersion, ServerConfig, ServerConnection, Ticketer};
+use tracing::{error, info};
 use xitca_io::io::{AsyncIo, AsyncRead, AsyncWrite, Interest, ReadBuf, Ready};
-use xitca_service::Service;
+use xitca_service::{ready::ReadyService, Service};
 use xitca_tls::rustls::TlsStream as _TlsStream;
 
-use crate::{http::Version, version::AsVersion};
+use crate::{
+    bytes::{Buf, Bytes},
+    http::{PeerCertificates, TlsConnectionInfo, Version},
+    version::AsVersion,
+};
 
-use super::error::TlsError;
+use super::{error::TlsError, AsTlsInfo};
 
 pub(crate) type RustlsConfig = Arc<ServerConfig>;
 
@@ -25,6 +37,9 @@ where
     Io: AsyncIo,
 {
     inner: _TlsStream<ServerConnection, Io>,
+    info: TlsConnectionInfo,
+    // early data received in handshake. it's read before data from inner stream.
+    early_data: Bytes,
 }
 
 impl<Io> AsVersion for TlsStream<Io>
@@ -40,14 +55,332 @@ where
     }
 }
 
+impl<Io> AsTlsInfo for TlsStream<Io>
+where
+    Io: AsyncIo,
+{
+    fn peer_certificates(&self) -> Option<PeerCertificates> {
+        peer_certificates(self.inner.session())
+    }
+
+    fn tls_connection_info(&self) -> Option<TlsConnectionInfo> {
+        Some(self.info)
+    }
+}
+
+/// Builder of rustls acceptor with tuning of session resumption and early data. It's passed to
+/// [HttpServiceBuilder::with_tls](crate::HttpServiceBuilder::with_tls). Cloned builders share the
+/// same [TlsStats].
+///
+/// # Examples:
+/// ```rust
+/// # use std::sync::Arc;
+/// # use xitca_http::RustlsAcceptorBuilder;
+/// # fn builder(config: Arc<rustls::ServerConfig>) {
+/// let acceptor = RustlsAcceptorBuilder::new(config)
+///     .session_tickets(true)
+///     .tls13_tickets(2);
+///
+/// // counters are shared with acceptor and can be read while server is running.
+/// let stats = acceptor.stats();
+/// assert_eq!(stats.resumed_handshakes(), 0);
+/// # }
+/// ```
 #[derive(Clone)]
 pub struct TlsAcceptorBuilder {
-    acceptor: Arc<ServerConfig>,
+    acceptor: Acceptor,
+    tuning: Tuning,
+    stats: TlsStats,
 }
 
 impl TlsAcceptorBuilder {
     pub fn new(acceptor: Arc<ServerConfig>) -> Self {
-        Self { acceptor }
+        Self::with_acceptor(Acceptor::Static(acceptor))
+    }
+
+    fn with_acceptor(acceptor: Acceptor) -> Self {
+        Self {
+            acceptor,
+            tuning: Tuning::default(),
+            stats: TlsStats::default(),
+        }
+    }
+
+    /// Enable or disable stateless session tickets. Enabled tickets are encrypted with keys
+    /// rotated every 6 hours and shared by all connections accepted by this builder.
+    ///
+    /// When disabled the session state is kept in [ServerConfig::session_storage] and only
+    /// resumption with the session id or ticket pointing to it is possible.
+    ///
+    /// Default to the ticketer of given [ServerConfig].
+    ///
+    /// # Panics:
+    /// When enabling tickets and the random generator of system fails.
+    pub fn session_tickets(mut self, enable: bool) -> Self {
+        self.tuning.ticketer = Some(if enable {
+            Ticketer::new().expect("failed to generate session ticket key")
+        } else {
+            Arc::new(NoTickets)
+        });
+        self
+    }
+
+    /// Set the number of session tickets sent to client after TLSv1.3 handshake. A ticket can
+    /// only be used for one resumption. 0 disables resumption of TLSv1.3 connections.
+    ///
+    /// Default to [ServerConfig::send_tls13_tickets] of given config.
+    pub fn tls13_tickets(mut self, count: usize) -> Self {
+        self.tuning.tls13_tickets = Some(count);
+        self
+    }
+
+    /// Set the max size in bytes of 0-RTT early data accepted from client resuming a TLSv1.3
+    /// session. 0 disables early data.
+    ///
+    /// Rustls only accepts early data when stateless session tickets are disabled. See
+    /// [TlsAcceptorBuilder::session_tickets]. A stateful session can be resumed only once which
+    /// makes replaying early data to the same server harder but it's not prevented across
+    /// servers. Early data is passed to http service as the beginning of connection and requests
+    /// in it can be replayed by attacker. Whether it's accepted is reported by
+    /// [TlsConnectionInfo::early_data] and service should only act on idempotent requests of
+    /// such connection or respond with [too_early](crate::response::too_early).
+    ///
+    /// This setting is for connections over tcp. Http/3 has it's own strategy of early data
+    /// and quic demands it to be either 0 or [u32::MAX] so a config tuned by this method should
+    /// not be shared with http/3 listener. See `H3ServiceBuilder::early_data` for http/3.
+    ///
+    /// Default to [ServerConfig::max_early_data_size] of given config.
+    pub fn max_early_data_size(mut self, size: u32) -> Self {
+        self.tuning.max_early_data_size = Some(size);
+        self
+    }
+
+    /// Get the counters of tls handshakes performed by acceptor services built from this
+    /// builder.
+    pub fn stats(&self) -> TlsStats {
+        self.stats.clone()
+    }
+}
+
+impl From<ReloadableTlsAcceptor> for TlsAcceptorBuilder {
+    fn from(acceptor: ReloadableTlsAcceptor) -> Self {
+        Self::with_acceptor(Acceptor::Reloadable(acceptor))
+    }
+}
+
+// overrides of server config set by TlsAcceptorBuilder.
+#[derive(Clone, Default)]
+struct Tuning {
+    ticketer: Option<Arc<dyn ProducesTickets>>,
+    tls13_tickets: Option<usize>,
+    max_early_data_size: Option<u32>,
+}
+
+impl Tuning {
+    fn is_empty(&self) -> bool {
+        self.ticketer.is_none() && self.tls13_tickets.is_none() && self.max_early_data_size.is_none()
+    }
+
+    fn apply(&self, config: &ServerConfig) -> ServerConfig {
+        let mut config = config.clone();
+        if let Some(ref ticketer) = self.ticketer {
+            config.ticketer = ticketer.clone();
+        }
+        if let Some(count) = self.tls13_tickets {
+            config.send_tls13_tickets = count;
+        }
+        if let Some(size) = self.max_early_data_size {
+            config.max_early_data_size = size;
+        }
+        config
+    }
+}
+
+struct NoTickets;
+
+impl ProducesTickets for NoTickets {
+    fn enabled(&self) -> bool {
+        false
+    }
+
+    fn lifetime(&self) -> u32 {
+        0
+    }
+
+    fn encrypt(&self, _: &[u8]) -> Option<Vec<u8>> {
+        None
+    }
+
+    fn decrypt(&self, _: &[u8]) -> Option<Vec<u8>> {
+        None
+    }
+}
+
+/// Counters of tls handshakes performed by rustls acceptor. Cloned handles share the same
+/// counters.
+///
+/// Rustls can not tell if a TLSv1.2 handshake is resumed and it's counted by neither
+/// [TlsStats::full_handshakes] nor [TlsStats::resumed_handshakes].
+#[derive(Clone, Default)]
+pub struct TlsStats(Arc<Counters>);
+
+#[derive(Default)]
+struct Counters {
+    full: AtomicUsize,
+    resumed: AtomicUsize,
+    early_data: AtomicUsize,
+}
+
+impl TlsStats {
+    /// Count of full handshakes.
+    pub fn full_handshakes(&self) -> usize {
+        self.0.full.load(Ordering::Relaxed)
+    }
+
+    /// Count of handshakes resumed a previous session.
+    pub fn resumed_handshakes(&self) -> usize {
+        self.0.resumed.load(Ordering::Relaxed)
+    }
+
+    /// Count of handshakes with 0-RTT early data accepted.
+    pub fn early_data_accepted(&self) -> usize {
+        self.0.early_data.load(Ordering::Relaxed)
+    }
+
+    fn record(&self, info: &TlsConnectionInfo) {
+        match info.resumed() {
+            Some(true) => self.0.resumed.fetch_add(1, Ordering::Relaxed),
+            Some(false) => self.0.full.fetch_add(1, Ordering::Relaxed),
+            None => 0,
+        };
+        if info.early_data() {
+            self.0.early_data.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+impl fmt::Debug for TlsStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TlsStats")
+            .field("full_handshakes", &self.full_handshakes())
+            .field("resumed_handshakes", &self.resumed_handshakes())
+            .field("early_data_accepted", &self.early_data_accepted())
+            .finish()
+    }
+}
+
+#[derive(Clone)]
+enum Acceptor {
+    Static(RustlsConfig),
+    Reloadable(ReloadableTlsAcceptor),
+}
+
+impl Acceptor {
+    fn config(&self) -> RustlsConfig {
+        match *self {
+            Self::Static(ref config) => config.clone(),
+            Self::Reloadable(ref acceptor) => acceptor.config(),
+        }
+    }
+}
+
+/// Rustls acceptor with hot reloadable [ServerConfig]. Cloned acceptors share the same config.
+///
+/// A reloaded config only applies to new tls handshakes. Established connections keep using the
+/// config they are accepted with.
+///
+/// # Examples:
+/// ```rust
+/// # use std::{sync::Arc, time::Duration};
+/// # use xitca_http::ReloadableTlsAcceptor;
+/// # fn load() -> Result<rustls::ServerConfig, std::io::Error> { todo!() }
+/// # fn acceptor(config: rustls::ServerConfig) {
+/// let acceptor = ReloadableTlsAcceptor::new(Arc::new(config));
+///
+/// // replace config programmatically.
+/// acceptor.reload(Arc::new(load().unwrap()));
+///
+/// // or reload config when certificate and key files are changed.
+/// acceptor.watch_files(["cert.pem", "key.pem"], Duration::from_secs(10), load);
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct ReloadableTlsAcceptor {
+    config: Arc<RwLock<RustlsConfig>>,
+}
+
+impl ReloadableTlsAcceptor {
+    pub fn new(config: RustlsConfig) -> Self {
+        Self {
+            config: Arc::new(RwLock::new(config)),
+        }
+    }
+
+    /// Get current config.
+    pub fn config(&self) -> RustlsConfig {
+        self.config.read().unwrap().clone()
+    }
+
+    /// Replace current config with given one.
+    pub fn reload(&self, config: RustlsConfig) {
+        *self.config.write().unwrap() = config;
+    }
+
+    /// Spawn a thread checking the modified time of given files with given interval. When any
+    /// file is changed and stays unchanged for another interval the loader is called and the
+    /// config it returns replaces current one.
+    ///
+    /// Error from loader is logged and current config is kept serving.
+    ///
+    /// The thread exits when all clones of acceptor are dropped.
+    pub fn watch_files<I, P, F, E>(&self, files: I, interval: Duration, loader: F) -> thread::JoinHandle<()>
+    where
+        I: IntoIterator<Item = P>,
+        P: Into<PathBuf>,
+        F: Fn() -> Result<ServerConfig, E> + Send + 'static,
+        E: fmt::Display,
+    {
+        let config = Arc::downgrade(&self.config);
+        let files = files.into_iter().map(Into::into).collect::<Vec<_>>();
+
+        thread::spawn(move || {
+            let modified = || {
+                files
+                    .iter()
+                    .map(|file| fs::metadata(file).and_then(|m| m.modified()).ok())
+                    .collect::<Vec<_>>()
+            };
+
+            let mut current = modified();
+            let mut pending = None;
+
+            loop {
+                thread::sleep(interval);
+
+                let Some(config) = Weak::upgrade(&config) else {
+                    return;
+                };
+
+                let next = modified();
+
+                // debounce for files that are still being written.
+                if pending.as_ref() == Some(&next) {
+                    pending = None;
+                    current = next;
+                    match loader() {
+                        Ok(cfg) => {
+                            *config.write().unwrap() = Arc::new(cfg);
+                            info!("tls config reloaded");
+                        }
+                        Err(e) => error!("tls config reload failed, keep serving with current config: {e}"),
+                    }
+                } else if next != current {
+                    pending = Some(next);
+                } else {
+                    pending = None;
+                }
+            }
+        })
     }
 }
 
@@ -59,6 +392,9 @@ impl Service for TlsAcceptorBuilder {
     fn call<'s>(&self, _: ()) -> Self::Future<'s> {
         let service = TlsAcceptorService {
             acceptor: self.acceptor.clone(),
+            tuning: self.tuning.clone(),
+            tuned: RefCell::new(None),
+            stats: self.stats.clone(),
         };
         async { Ok(service) }
     }
@@ -66,7 +402,32 @@ impl Service for TlsAcceptorBuilder {
 
 /// Rustls Acceptor. Used to accept a unsecure Stream and upgrade it to a TlsStream.
 pub struct TlsAcceptorService {
-    acceptor: Arc<ServerConfig>,
+    acceptor: Acceptor,
+    tuning: Tuning,
+    // config from acceptor and the tuned clone of it. tuning is only applied again when acceptor
+    // is reloaded with a new config.
+    tuned: RefCell<Option<(RustlsConfig, RustlsConfig)>>,
+    stats: TlsStats,
+}
+
+impl TlsAcceptorService {
+    fn config(&self) -> RustlsConfig {
+        let config = self.acceptor.config();
+
+        if self.tuning.is_empty() {
+            return config;
+        }
+
+        let mut tuned = self.tuned.borrow_mut();
+        match *tuned {
+            Some((ref origin, ref config_tuned)) if Arc::ptr_eq(origin, &config) => config_tuned.clone(),
+            _ => {
+                let config_tuned = Arc::new(self.tuning.apply(&config));
+                *tuned = Some((config, config_tuned.clone()));
+                config_tuned
+            }
+        }
+    }
 }
 
 impl<Io: AsyncIo> Service<Io> for TlsAcceptorService {
@@ -79,26 +440,87 @@ impl<Io: AsyncIo> Service<Io> for TlsAcceptorService {
         Io: 's,
     {
         async move {
-            let conn = ServerConnection::new(self.acceptor.clone())?;
-            let inner = _TlsStream::handshake(io, conn).await?;
-            Ok(TlsStream { inner })
+            let conn = ServerConnection::new(self.config())?;
+            let mut inner = _TlsStream::handshake(io, conn).await?;
+
+            let session = inner.session_mut();
+
+            let mut early_data = Vec::new();
+            let early_data_accepted = match session.early_data() {
+                Some(mut data) => {
+                    io::Read::read_to_end(&mut data, &mut early_data)?;
+                    true
+                }
+                None => false,
+            };
+
+            let info = connection_info(session, early_data_accepted);
+            self.stats.record(&info);
+
+            Ok(TlsStream {
+                inner,
+                info,
+                early_data: Bytes::from(early_data),
+            })
         }
     }
 }
 
+// certificate chain presented by client in tls handshake.
+pub(super) fn peer_certificates(session: &ServerConnection) -> Option<PeerCertificates> {
+    // AsTlsInfo is implemented for all types and shadows the method of session.
+    let certs = CommonState::peer_certificates(session)?;
+    let chain = certs
+        .iter()
+        .map(|cert| Bytes::copy_from_slice(&cert.0))
+        .collect::<Vec<_>>();
+    (!chain.is_empty()).then(|| PeerCertificates::new(chain))
+}
+
+pub(super) fn connection_info(session: &ServerConnection, early_data_accepted: bool) -> TlsConnectionInfo {
+    let version = session.protocol_version();
+    TlsConnectionInfo::new(
+        match version {
+            Some(ProtocolVersion::TLSv1_3) => "TLSv1.3",
+            Some(ProtocolVersion::TLSv1_2) => "TLSv1.2",
+            _ => "unknown",
+        },
+        session
+            .negotiated_cipher_suite()
+            .and_then(|suite| suite.suite().as_str())
+            .unwrap_or("unknown"),
+        // rustls only exposes resumption of TLSv1.3 connection.
+        (version == Some(ProtocolVersion::TLSv1_3)).then(|| session.received_resumption_data().is_some()),
+        early_data_accepted,
+    )
+}
+
+impl ReadyService for TlsAcceptorService {
+    type Ready = ();
+    type Future<'f> = impl Future<Output = Self::Ready> where Self: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::Future<'_> {
+        async {}
+    }
+}
+
 impl<Io> AsyncIo for TlsStream<Io>
 where
     Io: AsyncIo,
 {
-    type Future<'f> = Io::Future<'f> where Self: 'f;
+    type Future<'f> = impl Future<Output = io::Result<Ready>> + 'f where Self: 'f;
 
     #[inline]
     fn ready(&self, interest: Interest) -> Self::Future<'_> {
-        self.inner.ready(interest)
+        poll_fn(move |cx| self.poll_ready(interest, cx))
     }
 
     #[inline]
     fn poll_ready(&self, interest: Interest, cx: &mut Context<'_>) -> Poll<io::Result<Ready>> {
+        if interest.is_readable() && !self.early_data.is_empty() {
+            return Poll::Ready(Ok(Ready::READABLE));
+        }
         self.inner.poll_ready(interest, cx)
     }
 
@@ -114,6 +536,12 @@ where
 impl<Io: AsyncIo> io::Read for TlsStream<Io> {
     #[inline]
     fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if !self.early_data.is_empty() {
+            let len = buf.len().min(self.early_data.len());
+            buf[..len].copy_from_slice(&self.early_data[..len]);
+            self.early_data.advance(len);
+            return Ok(len);
+        }
         io::Read::read(&mut self.inner, buf)
     }
 }
@@ -141,7 +569,14 @@ where
 {
     #[inline]
     fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
-        Pin::new(&mut self.get_mut().inner).poll_read(cx, buf)
+        let this = self.get_mut();
+        if !this.early_data.is_empty() {
+            let len = buf.remaining().min(this.early_data.len());
+            buf.put_slice(&this.early_data[..len]);
+            this.early_data.advance(len);
+            return Poll::Ready(Ok(()));
+        }
+        Pin::new(&mut this.inner).poll_read(cx, buf)
     }
 }
 
@@ -222,3 +657,276 @@ impl From<RustlsError> for TlsError {
         Self::Rustls(e)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::net::{TcpListener, TcpStream};
+
+    use rustls::{
+        server::AllowAnyAnonymousOrAuthenticatedClient, Certificate, ClientConfig, ClientConnection, PrivateKey,
+        RootCertStore,
+    };
+
+    use super::*;
+
+    fn key_pair() -> (Certificate, PrivateKey) {
+        let cert = rcgen::generate_simple_self_signed(vec!["localhost".to_string()]).unwrap();
+        let key = PrivateKey(cert.serialize_private_key_der());
+        (Certificate(cert.serialize_der().unwrap()), key)
+    }
+
+    fn cert() -> (Certificate, RustlsConfig) {
+        let (cert, key) = key_pair();
+
+        let config = ServerConfig::builder()
+            .with_safe_defaults()
+            .with_no_client_auth()
+            .with_single_cert(vec![cert.clone()], key)
+            .unwrap();
+
+        (cert, Arc::new(config))
+    }
+
+    // connect to server and return the accepted stream and the leaf certificate server presents.
+    async fn handshake(
+        service: &TlsAcceptorService,
+        client: Arc<ClientConfig>,
+    ) -> (TlsStream<xitca_io::net::TcpStream>, Certificate) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let handle = thread::spawn(move || {
+            let mut conn = ClientConnection::new(client, "localhost".try_into().unwrap()).unwrap();
+            let mut stream = TcpStream::connect(addr).unwrap();
+            while conn.is_handshaking() {
+                conn.complete_io(&mut stream).unwrap();
+            }
+            CommonState::peer_certificates(&conn).unwrap()[0].clone()
+        });
+
+        let (stream, _) = listener.accept().unwrap();
+        stream.set_nonblocking(true).unwrap();
+        let stream = xitca_io::net::TcpStream::from_std(stream).unwrap();
+        let stream = service.call(stream).await.unwrap();
+
+        let cert = tokio::task::spawn_blocking(move || handle.join().unwrap())
+            .await
+            .unwrap();
+
+        (stream, cert)
+    }
+
+    // connect to server with early data when client is resuming a session. return the accepted stream
+    // and if early data is accepted by server.
+    async fn connect(
+        service: &TlsAcceptorService,
+        client: Arc<ClientConfig>,
+        early_data: &'static [u8],
+    ) -> (TlsStream<xitca_io::net::TcpStream>, bool) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        let handle = thread::spawn(move || {
+            let mut conn = ClientConnection::new(client, "localhost".try_into().unwrap()).unwrap();
+            if let Some(mut early) = conn.early_data() {
+                io::Write::write_all(&mut early, early_data).unwrap();
+            }
+            let mut stream = TcpStream::connect(addr).unwrap();
+            // session tickets are sent before the byte server writes after handshake.
+            let mut buf = [0];
+            io::Read::read_exact(&mut rustls::Stream::new(&mut conn, &mut stream), &mut buf).unwrap();
+            conn.is_early_data_accepted()
+        });
+
+        let (stream, _) = listener.accept().unwrap();
+        stream.set_nonblocking(true).unwrap();
+        let stream = xitca_io::net::TcpStream::from_std(stream).unwrap();
+        let mut stream = service.call(stream).await.unwrap();
+
+        io::Write::write_all(&mut stream, b"1").unwrap();
+        io::Write::flush(&mut stream).unwrap();
+
+        let accepted = tokio::task::spawn_blocking(move || handle.join().unwrap())
+            .await
+            .unwrap();
+
+        (stream, accepted)
+    }
+
+    #[tokio::test]
+    async fn reload() {
+        let (cert1, config1) = cert();
+        let (cert2, config2) = cert();
+
+        let mut roots = RootCertStore::empty();
+        roots.add(&cert1).unwrap();
+        roots.add(&cert2).unwrap();
+        let client = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        let client = Arc::new(client);
+
+        let acceptor = ReloadableTlsAcceptor::new(config1);
+        let service = TlsAcceptorBuilder::from(acceptor.clone()).call(()).await.unwrap();
+
+        assert_eq!(handshake(&service, client.clone()).await.1, cert1);
+
+        acceptor.reload(config2);
+
+        assert_eq!(handshake(&service, client).await.1, cert2);
+    }
+
+    #[tokio::test]
+    async fn peer_certificates() {
+        let (server_cert, server_key) = key_pair();
+        let (client_cert, client_key) = key_pair();
+
+        // client certificate is optional so connections without it are accepted.
+        let mut roots = RootCertStore::empty();
+        roots.add(&client_cert).unwrap();
+        let config = ServerConfig::builder()
+            .with_safe_defaults()
+            .with_client_cert_verifier(AllowAnyAnonymousOrAuthenticatedClient::new(roots).boxed())
+            .with_single_cert(vec![server_cert.clone()], server_key)
+            .unwrap();
+        let service = TlsAcceptorBuilder::new(Arc::new(config)).call(()).await.unwrap();
+
+        let mut roots = RootCertStore::empty();
+        roots.add(&server_cert).unwrap();
+        let client = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots);
+
+        let anonymous = Arc::new(client.clone().with_no_client_auth());
+        let (stream, _) = handshake(&service, anonymous).await;
+        assert!(stream.peer_certificates().is_none());
+
+        let authenticated = Arc::new(
+            client
+                .with_client_auth_cert(vec![client_cert.clone()], client_key)
+                .unwrap(),
+        );
+        let (stream, _) = handshake(&service, authenticated).await;
+        let certs = stream.peer_certificates().unwrap();
+        assert_eq!(certs.leaf().as_ref(), client_cert.0);
+        assert_eq!(certs.chain().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn resumption() {
+        let (cert, config) = cert();
+
+        let mut roots = RootCertStore::empty();
+        roots.add(&cert).unwrap();
+        let mut client = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        client.enable_early_data = true;
+        let client = Arc::new(client);
+
+        let builder = TlsAcceptorBuilder::new(config)
+            .session_tickets(false)
+            .max_early_data_size(1024);
+        let stats = builder.stats();
+        let service = builder.call(()).await.unwrap();
+
+        let req = b"GET / HTTP/1.1\r\n\r\n";
+
+        let (stream, accepted) = connect(&service, client.clone(), req).await;
+        let info = stream.tls_connection_info().unwrap();
+        assert_eq!(info.protocol_version(), "TLSv1.3");
+        assert!(info.cipher_suite().starts_with("TLS13_"));
+        assert_eq!(info.resumed(), Some(false));
+        assert!(!info.early_data());
+        assert!(!accepted);
+
+        let (mut stream, accepted) = connect(&service, client, req).await;
+        let info = stream.tls_connection_info().unwrap();
+        assert_eq!(info.resumed(), Some(true));
+        assert!(info.early_data());
+        assert!(accepted);
+
+        // early data is read before anything else from connection.
+        assert!(stream.ready(Interest::READABLE).await.unwrap().is_readable());
+        let mut buf = [0; 64];
+        let n = io::Read::read(&mut stream, &mut buf).unwrap();
+        assert_eq!(&buf[..n], req);
+
+        assert_eq!(stats.full_handshakes(), 1);
+        assert_eq!(stats.resumed_handshakes(), 1);
+        assert_eq!(stats.early_data_accepted(), 1);
+    }
+
+    #[tokio::test]
+    async fn no_resumption() {
+        let (cert, config) = cert();
+
+        let mut roots = RootCertStore::empty();
+        roots.add(&cert).unwrap();
+        let client = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots)
+            .with_no_client_auth();
+        let client = Arc::new(client);
+
+        let builder = TlsAcceptorBuilder::new(config).tls13_tickets(0);
+        let stats = builder.stats();
+        let service = builder.call(()).await.unwrap();
+
+        for _ in 0..2 {
+            let (stream, _) = connect(&service, client.clone(), b"").await;
+            assert_eq!(stream.tls_connection_info().unwrap().resumed(), Some(false));
+        }
+
+        assert_eq!(stats.full_handshakes(), 2);
+        assert_eq!(stats.resumed_handshakes(), 0);
+    }
+
+    #[test]
+    fn watch_files() {
+        let (_, config1) = cert();
+        let (_, config2) = cert();
+
+        let path = std::env::temp_dir().join(format!("xitca-http-tls-reload-{}", std::process::id()));
+        fs::write(&path, "1").unwrap();
+
+        let acceptor = ReloadableTlsAcceptor::new(config1.clone());
+
+        let file = path.clone();
+        let cfg = config2;
+        acceptor.watch_files([&path], Duration::from_millis(10), move || {
+            match fs::read_to_string(&file)?.as_str() {
+                "2" => {
+                    let mut cfg = (*cfg).clone();
+                    cfg.alpn_protocols = vec![b"reloaded".to_vec()];
+                    Ok(cfg)
+                }
+                _ => Err(io::Error::new(io::ErrorKind::InvalidData, "malformed certificate")),
+            }
+        });
+
+        let wait_modified = |content: &str| {
+            let modified = fs::metadata(&path).unwrap().modified().unwrap();
+            fs::write(&path, content).unwrap();
+            // make sure modified time is changed on file system with coarse time granularity.
+            fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(modified + Duration::from_secs(1))
+                .unwrap();
+            thread::sleep(Duration::from_millis(200));
+        };
+
+        // failed reload keeps current config.
+        wait_modified("bad");
+        assert!(Arc::ptr_eq(&acceptor.config(), &config1));
+
+        wait_modified("2");
+        assert_eq!(acceptor.config().alpn_protocols, [b"reloaded"]);
+
+        fs::remove_file(&path).unwrap();
+    }
+}
@@ -4,12 +4,18 @@ use std::{io, net::Shutdown, sync::Arc};
 
 use rustls::{ServerConfig, ServerConnection};
 use xitca_io::io_uring::{AsyncBufRead, AsyncBufWrite, IoBuf, IoBufMut};
-use xitca_service::Service;
+use xitca_service::{ready::ReadyService, Service};
 use xitca_tls::rustls_uring::TlsStream as _TlsStream;
 
-use crate::{http::Version, version::AsVersion};
+use crate::{
+    http::{PeerCertificates, TlsConnectionInfo, Version},
+    version::AsVersion,
+};
 
-use super::rustls::RustlsError;
+use super::{
+    rustls::{connection_info, peer_certificates, RustlsError},
+    AsTlsInfo,
+};
 
 /// A stream managed by rustls for tls read/write.
 pub struct TlsStream<Io> {
@@ -22,6 +28,17 @@ impl<Io> AsVersion for TlsStream<Io> {
     }
 }
 
+impl<Io> AsTlsInfo for TlsStream<Io> {
+    fn peer_certificates(&self) -> Option<PeerCertificates> {
+        peer_certificates(&self.inner.session())
+    }
+
+    // io-uring acceptor does not accept early data.
+    fn tls_connection_info(&self) -> Option<TlsConnectionInfo> {
+        Some(connection_info(&self.inner.session(), false))
+    }
+}
+
 #[derive(Clone)]
 pub struct TlsAcceptorBuilder {
     acceptor: Arc<ServerConfig>,
@@ -71,6 +88,16 @@ where
     }
 }
 
+impl ReadyService for TlsAcceptorService {
+    type Ready = ();
+    type Future<'f> = impl Future<Output = Self::Ready> where Self: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::Future<'_> {
+        async {}
+    }
+}
+
 impl<Io> AsyncBufRead for TlsStream<Io>
 where
     Io: AsyncBufRead,
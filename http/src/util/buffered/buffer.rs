@@ -118,6 +118,17 @@ impl<const LIMIT: usize> WriteBuf<LIMIT> {
         Self(xitca_io::bytes::WriteBuf::new())
     }
 
+    /// length of bytes buffered and not written to io yet.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
     #[cfg(test)]
     pub fn buf(&self) -> &[u8] {
         self.0.buf()
@@ -179,6 +190,17 @@ impl<B: Buf, const LIMIT: usize> ListWriteBuf<B, LIMIT> {
         self.buf.split()
     }
 
+    /// length of bytes buffered in list and not written to io yet.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.list.remaining()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
     /// add new buf to list.
     ///
     /// # Panics
@@ -188,6 +210,29 @@ impl<B: Buf, const LIMIT: usize> ListWriteBuf<B, LIMIT> {
         // cross reference with <Self as BufWrite>::buf_write method.
         self.want_flush = false;
     }
+
+    /// replace buffered item starting at given offset(counting from the first byte not written to io
+    /// yet) with the output of given function. return false when no item starts at the offset or the
+    /// function returns None.
+    pub fn replace_at<F>(&mut self, offset: usize, func: F) -> bool
+    where
+        F: FnOnce(&B) -> Option<B>,
+    {
+        let mut start = 0;
+        let Some(idx) = self.list.iter().position(|item| {
+            let found = start == offset;
+            start += item.remaining();
+            found
+        }) else {
+            return false;
+        };
+
+        let Some(buf) = self.list.iter().nth(idx).and_then(func) else {
+            return false;
+        };
+        self.list.replace(idx, buf);
+        true
+    }
 }
 
 impl<B: Buf, const LIMIT: usize> fmt::Debug for ListWriteBuf<B, LIMIT> {
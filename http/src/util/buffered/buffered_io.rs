@@ -45,6 +45,17 @@ where
         BufWrite::do_io(&mut self.write_buf, self.io)
     }
 
+    /// write until write buffer is emptied or io blocked and return the number of bytes written to
+    /// io.
+    pub fn try_write_count(&mut self) -> io::Result<usize> {
+        let mut io = WriteCount {
+            io: &mut *self.io,
+            count: 0,
+        };
+        BufWrite::do_io(&mut self.write_buf, &mut io)?;
+        Ok(io.count)
+    }
+
     /// check for io read readiness in async and do [Self::try_read].
     pub async fn read(&mut self) -> io::Result<()> {
         self.io.ready(Interest::READABLE).await?;
@@ -65,3 +76,31 @@ where
         poll_fn(|cx| Pin::new(&mut *self.io).poll_shutdown(cx))
     }
 }
+
+// io writer counting the bytes written through it.
+struct WriteCount<'a, Io> {
+    io: &'a mut Io,
+    count: usize,
+}
+
+impl<Io> io::Write for WriteCount<'_, Io>
+where
+    Io: io::Write,
+{
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let n = self.io.write(buf)?;
+        self.count += n;
+        Ok(n)
+    }
+
+    fn write_vectored(&mut self, bufs: &[io::IoSlice<'_>]) -> io::Result<usize> {
+        let n = self.io.write_vectored(bufs)?;
+        self.count += n;
+        Ok(n)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        self.io.flush()
+    }
+}
@@ -0,0 +1,273 @@
+//! Pre-serialized response for endpoints always responding with identical bytes.
+
+use std::sync::Arc;
+
+use crate::{
+    bytes::Bytes,
+    http::{
+        header::{HeaderMap, CONTENT_LENGTH},
+        Response, StatusCode,
+    },
+};
+
+#[cfg(feature = "http1")]
+use crate::{
+    bytes::BytesMut,
+    http::{
+        header::{CONNECTION, DATE, TRANSFER_ENCODING, UPGRADE},
+        Version,
+    },
+};
+
+/// A response with it's http/1 wire form serialized once on construction.
+///
+/// Response produced by [CachedResponse::response] carries the cache as extension and Http/1
+/// dispatcher copies the pre-built head straight into write buffer instead of encoding it. The
+/// `date` header is patched in from date cache of dispatcher.
+///
+/// Pre-built head is bypassed and response is encoded normally when:
+/// - connection is going to be closed after response.
+/// - request method is HEAD or CONNECT.
+/// - header case preserving is enabled.
+/// - response status, version, headers or body are modified after it's produced.
+/// - response status is informational, 204 No Content or 304 Not Modified. These responses carry
+///   no body and their framing headers are left to normal encoding.
+///
+/// Headers are kept in the cache and not visible to middlewares from produced response. They can
+/// be inspected with [CachedResponse::headers] and are restored when response is encoded normally
+/// or by Http/2 and Http/3 dispatchers.
+///
+/// # Examples:
+/// ```rust
+/// # use xitca_http::{bytes::Bytes, http::{header::{HeaderValue, CONTENT_TYPE}, Response}, util::cached::CachedResponse, ResponseBody};
+/// let mut res = Response::new(Bytes::from_static(b"Hello, World!"));
+/// res.headers_mut().insert(CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+/// let cached = CachedResponse::new(res);
+///
+/// // construct response in service call.
+/// let res = cached.response::<ResponseBody>();
+/// ```
+#[derive(Clone)]
+pub struct CachedResponse {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Bytes,
+    #[cfg(feature = "http1")]
+    head: Option<Head>,
+}
+
+// serialized response head without date value.
+#[cfg(feature = "http1")]
+pub(crate) struct Head {
+    pub(crate) bytes: Bytes,
+    // offset of date value in bytes. None when user provided date header is serialized.
+    pub(crate) date_offset: Option<usize>,
+}
+
+impl CachedResponse {
+    /// Construct from response and serialize it's head.
+    ///
+    /// `content-length` header is always derived from body and user provided one is ignored.
+    pub fn new(res: Response<Bytes>) -> Self {
+        let (parts, body) = res.into_parts();
+        let mut headers = parts.headers;
+        headers.remove(CONTENT_LENGTH);
+
+        Self {
+            inner: Arc::new(Inner {
+                status: parts.status,
+                #[cfg(feature = "http1")]
+                head: serialize(parts.status, &headers, body.len()),
+                headers,
+                body,
+            }),
+        }
+    }
+
+    /// Produce a response with cached status and body.
+    pub fn response<B>(&self) -> Response<B>
+    where
+        B: From<Bytes>,
+    {
+        let mut res = Response::new(B::from(self.inner.body.clone()));
+        *res.status_mut() = self.inner.status;
+        res.extensions_mut().insert(self.clone());
+        res
+    }
+
+    /// Get cached status code.
+    pub fn status(&self) -> StatusCode {
+        self.inner.status
+    }
+
+    /// Get reference of cached headers. `content-length` header is not included.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.inner.headers
+    }
+
+    /// Get reference of cached body.
+    pub fn body(&self) -> &Bytes {
+        &self.inner.body
+    }
+
+    // return serialized head when response is not modified after it's produced.
+    #[cfg(feature = "http1")]
+    pub(crate) fn head(
+        &self,
+        status: StatusCode,
+        version: Version,
+        headers: &HeaderMap,
+        body_len: Option<usize>,
+    ) -> Option<&Head> {
+        let inner = &*self.inner;
+        (status == inner.status
+            && version == Version::HTTP_11
+            && headers.is_empty()
+            && body_len == Some(inner.body.len()))
+        .then_some(inner.head.as_ref())
+        .flatten()
+    }
+
+    /// Restore cached headers into given header map. Headers already present are not overwritten.
+    pub(crate) fn restore_headers(&self, headers: &mut HeaderMap) {
+        for name in self.inner.headers.keys() {
+            if !headers.contains_key(name) {
+                for value in self.inner.headers.get_all(name) {
+                    headers.append(name.clone(), value.clone());
+                }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "http1")]
+fn serialize(status: StatusCode, headers: &HeaderMap, body_len: usize) -> Option<Head> {
+    // bodiless status and headers affecting connection state or body framing are left to normal
+    // encoding.
+    if status.is_informational()
+        || status == StatusCode::NO_CONTENT
+        || status == StatusCode::NOT_MODIFIED
+        || [CONNECTION, UPGRADE, TRANSFER_ENCODING]
+            .iter()
+            .any(|name| headers.contains_key(name))
+    {
+        return None;
+    }
+
+    let mut buf = BytesMut::new();
+
+    buf.extend_from_slice(b"HTTP/1.1 ");
+    buf.extend_from_slice(status.as_str().as_bytes());
+    buf.extend_from_slice(b" ");
+    buf.extend_from_slice(status.canonical_reason().unwrap_or("<none>").as_bytes());
+
+    for (name, value) in headers {
+        buf.extend_from_slice(b"\r\n");
+        buf.extend_from_slice(name.as_str().as_bytes());
+        buf.extend_from_slice(b": ");
+        buf.extend_from_slice(value.as_bytes());
+    }
+
+    buf.extend_from_slice(b"\r\ncontent-length: ");
+    buf.extend_from_slice(body_len.to_string().as_bytes());
+
+    let date_offset = (!headers.contains_key(DATE)).then(|| {
+        buf.extend_from_slice(b"\r\ndate: ");
+        buf.len()
+    });
+
+    buf.extend_from_slice(b"\r\n\r\n");
+
+    Some(Head {
+        bytes: buf.freeze(),
+        date_offset,
+    })
+}
+
+#[cfg(all(test, feature = "http1"))]
+mod test {
+    use crate::http::header::{HeaderValue, CONTENT_TYPE};
+
+    use super::*;
+
+    fn cached() -> CachedResponse {
+        let mut res = Response::new(Bytes::from_static(b"Hello, World!"));
+        res.headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+        res.headers_mut()
+            .insert(CONTENT_LENGTH, HeaderValue::from_static("996"));
+        CachedResponse::new(res)
+    }
+
+    #[test]
+    fn serialize_head() {
+        let cached = cached();
+        let head = cached
+            .head(StatusCode::OK, Version::HTTP_11, &HeaderMap::new(), Some(13))
+            .unwrap();
+        assert_eq!(
+            head.bytes.as_ref(),
+            b"HTTP/1.1 200 OK\r\ncontent-type: text/plain\r\ncontent-length: 13\r\ndate: \r\n\r\n"
+        );
+        assert_eq!(head.date_offset, Some(head.bytes.len() - 4));
+    }
+
+    #[test]
+    fn modified() {
+        let cached = cached();
+        let headers = HeaderMap::new();
+        assert!(cached
+            .head(StatusCode::CREATED, Version::HTTP_11, &headers, Some(13))
+            .is_none());
+        assert!(cached
+            .head(StatusCode::OK, Version::HTTP_10, &headers, Some(13))
+            .is_none());
+        assert!(cached
+            .head(StatusCode::OK, Version::HTTP_11, &headers, Some(3))
+            .is_none());
+        assert!(cached.head(StatusCode::OK, Version::HTTP_11, &headers, None).is_none());
+
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_static("text/html"));
+        assert!(cached
+            .head(StatusCode::OK, Version::HTTP_11, &headers, Some(13))
+            .is_none());
+
+        // restoring does not overwrite modified header.
+        cached.restore_headers(&mut headers);
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers.get(CONTENT_TYPE).unwrap(), "text/html");
+
+        let mut headers = HeaderMap::new();
+        cached.restore_headers(&mut headers);
+        assert_eq!(headers.get(CONTENT_TYPE).unwrap(), "text/plain");
+        assert!(!headers.contains_key(CONTENT_LENGTH));
+    }
+
+    #[test]
+    fn not_cacheable() {
+        let mut res = Response::new(Bytes::new());
+        res.headers_mut().insert(CONNECTION, HeaderValue::from_static("close"));
+        let cached = CachedResponse::new(res);
+        assert!(cached
+            .head(StatusCode::OK, Version::HTTP_11, &HeaderMap::new(), Some(0))
+            .is_none());
+
+        for status in [StatusCode::NO_CONTENT, StatusCode::NOT_MODIFIED] {
+            let mut res = Response::new(Bytes::new());
+            *res.status_mut() = status;
+            res.headers_mut()
+                .insert(CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+            let cached = CachedResponse::new(res);
+            assert!(cached
+                .head(status, Version::HTTP_11, &HeaderMap::new(), Some(0))
+                .is_none());
+            assert_eq!(cached.status(), status);
+            assert_eq!(cached.headers().get(CONTENT_TYPE).unwrap(), "text/plain");
+        }
+    }
+}
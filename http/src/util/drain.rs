@@ -0,0 +1,218 @@
+//! Connection draining signal shared by http dispatchers.
+
+use core::{
+    future::Future,
+    mem,
+    pin::Pin,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    task::{Context, Poll, Waker},
+};
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, Weak},
+};
+
+/// Signal for draining connections served by [HttpService](crate::HttpService).
+///
+/// Signal is passed to [HttpServiceBuilder::drain](crate::HttpServiceBuilder::drain). After
+/// [Drain::drain] is called:
+/// - Http/1 connections are closed when they are idle and waiting for next request. In-flight
+///   request is served and it's response is sent with `connection: close` header.
+/// - Http/2 connections are shut down gracefully with `GOAWAY` frame.
+///
+/// Draining does not stop server from accepting new connections and the ones accepted afterwards
+/// are drained the same way. It's meant to be paired with a graceful shutdown of server.
+///
+/// # Examples:
+/// ```rust
+/// # use std::convert::Infallible;
+/// # use xitca_http::{http::{Request, RequestExt, Response}, util::drain::Drain, HttpServiceBuilder, RequestBody, ResponseBody};
+/// # use xitca_service::fn_service;
+/// # async fn handler(_: Request<RequestExt<RequestBody>>) -> Result<Response<ResponseBody>, Infallible> {
+/// #   Ok(Response::new(ResponseBody::None))
+/// # }
+/// let drain = Drain::new();
+/// let builder = HttpServiceBuilder::new(fn_service(handler)).drain(drain.clone());
+///
+/// // start draining connections served by services built from builder.
+/// drain.drain();
+/// assert!(drain.is_draining());
+/// ```
+#[derive(Clone, Default)]
+pub struct Drain {
+    inner: Arc<Inner>,
+}
+
+#[derive(Default)]
+struct Inner {
+    draining: AtomicBool,
+    // registries of services built with the signal. each one is only touched by connections of
+    // the service it belongs to.
+    registries: Mutex<Vec<Weak<Registry>>>,
+}
+
+impl Drain {
+    /// Construct a new signal in non draining state.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start draining. Calling it more than once has no extra effect.
+    pub fn drain(&self) {
+        if !self.inner.draining.swap(true, Ordering::AcqRel) {
+            let registries = mem::take(&mut *self.inner.registries.lock().unwrap());
+            for registry in registries.iter().filter_map(Weak::upgrade) {
+                let wakers = mem::take(&mut *registry.wakers.lock().unwrap());
+                wakers.into_values().for_each(Waker::wake);
+            }
+        }
+    }
+
+    /// Check if draining is started.
+    #[inline]
+    pub fn is_draining(&self) -> bool {
+        self.inner.draining.load(Ordering::Acquire)
+    }
+
+    // construct a handle with it's own waker registry. it's called when service is built so
+    // wakers of connections served by different services are not registered to the same place.
+    pub(crate) fn watch(&self) -> DrainWatch {
+        let registry = Arc::new(Registry {
+            drain: self.clone(),
+            next_key: AtomicUsize::new(0),
+            wakers: Mutex::new(HashMap::new()),
+        });
+
+        let mut registries = self.inner.registries.lock().unwrap();
+        registries.retain(|r| r.strong_count() > 0);
+        registries.push(Arc::downgrade(&registry));
+
+        DrainWatch(registry)
+    }
+}
+
+/// Handle of [Drain] owned by service and shared by connections it serves.
+pub(crate) struct DrainWatch(Arc<Registry>);
+
+struct Registry {
+    drain: Drain,
+    next_key: AtomicUsize,
+    wakers: Mutex<HashMap<usize, Waker>>,
+}
+
+impl DrainWatch {
+    #[cfg(feature = "http1")]
+    #[inline]
+    pub(crate) fn is_draining(&self) -> bool {
+        self.0.drain.is_draining()
+    }
+
+    // a future resolves when draining is started.
+    pub(crate) fn wait(&self) -> Draining<'_> {
+        Draining { watch: self, key: None }
+    }
+}
+
+pub(crate) struct Draining<'a> {
+    watch: &'a DrainWatch,
+    key: Option<usize>,
+}
+
+impl Future for Draining<'_> {
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        let registry = &*this.watch.0;
+
+        if registry.drain.is_draining() {
+            return Poll::Ready(());
+        }
+
+        let key = *this
+            .key
+            .get_or_insert_with(|| registry.next_key.fetch_add(1, Ordering::Relaxed));
+
+        let mut wakers = registry.wakers.lock().unwrap();
+
+        // check again with lock held. Drain::drain could take wakers before insertion.
+        if registry.drain.is_draining() {
+            return Poll::Ready(());
+        }
+
+        match wakers.get_mut(&key) {
+            Some(waker) if waker.will_wake(cx.waker()) => {}
+            Some(waker) => *waker = cx.waker().clone(),
+            None => {
+                wakers.insert(key, cx.waker().clone());
+            }
+        }
+
+        Poll::Pending
+    }
+}
+
+impl Drop for Draining<'_> {
+    fn drop(&mut self) {
+        if let Some(key) = self.key {
+            self.watch.0.wakers.lock().unwrap().remove(&key);
+        }
+    }
+}
+
+// resolve when optional drain signal is started. pending forever when there is no signal.
+#[cfg(feature = "http1")]
+pub(crate) async fn draining(drain: Option<&DrainWatch>) {
+    match drain {
+        Some(drain) => drain.wait().await,
+        None => core::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use super::*;
+
+    #[test]
+    fn wake() {
+        let drain = Drain::new();
+        let watch = drain.watch();
+        let watch2 = drain.watch();
+
+        let mut fut = Box::pin(watch.wait());
+        assert!(fut.as_mut().poll(&mut Context::from_waker(Waker::noop())).is_pending());
+        assert_eq!(watch.0.wakers.lock().unwrap().len(), 1);
+
+        // registries are separate for each watch.
+        assert!(watch2.0.wakers.lock().unwrap().is_empty());
+
+        drain.clone().drain();
+        assert!(drain.is_draining());
+        assert!(watch.0.wakers.lock().unwrap().is_empty());
+        assert!(fut.as_mut().poll(&mut Context::from_waker(Waker::noop())).is_ready());
+
+        // signal is sticky.
+        watch2.wait().now_or_panic();
+        drain.watch().wait().now_or_panic();
+    }
+
+    #[test]
+    fn drop_unregister() {
+        let drain = Drain::new();
+        let watch = drain.watch();
+
+        let mut fut = Box::pin(watch.wait());
+        assert!(fut.as_mut().poll(&mut Context::from_waker(Waker::noop())).is_pending());
+        drop(fut);
+
+        assert!(watch.0.wakers.lock().unwrap().is_empty());
+
+        // registry of dropped watch is removed.
+        drop(watch);
+        drain.watch();
+        assert_eq!(drain.inner.registries.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,663 @@
+//! Utilities for serving gRPC calls over http/2.
+//!
+//! Only the transport side of gRPC is handled here:
+//! - [MessageStream] decodes length-prefixed messages from request body.
+//! - [MessageBody] and [response] encode messages to length-prefixed response body with
+//!   `grpc-status` and `grpc-message` sent as trailers after it.
+//! - [is_grpc] guard(with `util-service` feature) and [split_path] for routing gRPC calls.
+//!
+//! Serialization of messages(protobuf or any other format) is left to user.
+//!
+//! # Examples:
+//! ```rust
+//! # use std::convert::Infallible;
+//! # use futures_util::StreamExt;
+//! use xitca_http::{
+//!     body::{Once, ResponseBody},
+//!     bytes::Bytes,
+//!     h2::RequestBody,
+//!     http::{Request, RequestExt, Response},
+//!     util::grpc::{self, MessageBody, MessageStream, Status},
+//! };
+//!
+//! // unary call echoing request message back to client.
+//! async fn echo(
+//!     req: Request<RequestExt<RequestBody>>,
+//! ) -> Result<Response<ResponseBody<MessageBody<Once<Bytes>>>>, Infallible> {
+//!     let mut messages = MessageStream::new(req.into_body());
+//!
+//!     let res = match messages.next().await {
+//!         // decode message with protobuf and encode the reply.
+//!         Some(Ok(msg)) => grpc::response(Once::new(msg.data), Status::ok()),
+//!         Some(Err(e)) => grpc::error_response(e.into()),
+//!         None => grpc::error_response(Status::new(grpc::Code::InvalidArgument, "missing request message")),
+//!     };
+//!
+//!     Ok(res)
+//! }
+//! ```
+
+use core::{
+    convert::Infallible,
+    fmt,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use std::{borrow::Cow, error};
+
+use futures_core::stream::Stream;
+use pin_project_lite::pin_project;
+
+use crate::{
+    body::ResponseBody,
+    bytes::{Buf, BufMut, Bytes, BytesMut},
+    http::{
+        const_header_name::{GRPC_MESSAGE, GRPC_STATUS},
+        const_header_value::GRPC,
+        header::{HeaderMap, HeaderValue, CONTENT_TYPE, TRAILER},
+        Response,
+    },
+};
+
+/// Length of the prefix in front of every message. 1 byte compressed flag followed by 4 bytes
+/// big-endian length of message.
+pub const PREFIX_LEN: usize = 5;
+
+/// Default max size of a single request message. Same as the default of other gRPC
+/// implementations.
+pub const DEFAULT_MAX_MESSAGE_SIZE: usize = 4 * 1024 * 1024;
+
+/// A length-prefixed gRPC message.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Message {
+    /// Message is compressed with the algorithm from `grpc-encoding` header of request.
+    pub compressed: bool,
+    /// Message payload without prefix.
+    pub data: Bytes,
+}
+
+pin_project! {
+    /// Stream of [Message] decoded from length-prefixed request body.
+    ///
+    /// A message can be split across multiple chunks(DATA frames) of body and it's buffered until
+    /// complete. Any error is terminal and the stream ends after yielding it.
+    pub struct MessageStream<B> {
+        #[pin]
+        body: B,
+        buf: BytesMut,
+        max_size: usize,
+        accept_compressed: bool,
+        eof: bool,
+    }
+}
+
+impl<B> MessageStream<B> {
+    pub fn new(body: B) -> Self {
+        Self {
+            body,
+            buf: BytesMut::new(),
+            max_size: DEFAULT_MAX_MESSAGE_SIZE,
+            accept_compressed: false,
+            eof: false,
+        }
+    }
+
+    /// Change max size of a single message. Larger message is rejected with
+    /// [MessageError::TooLarge].
+    ///
+    /// Default to [DEFAULT_MAX_MESSAGE_SIZE].
+    pub fn max_message_size(mut self, size: usize) -> Self {
+        self.max_size = size;
+        self
+    }
+
+    /// Accept message with compressed flag. It's yielded as is and user must decompress it.
+    ///
+    /// By default compressed message is rejected with [MessageError::Compressed].
+    pub fn accept_compressed(mut self) -> Self {
+        self.accept_compressed = true;
+        self
+    }
+}
+
+impl<B, E> Stream for MessageStream<B>
+where
+    B: Stream<Item = Result<Bytes, E>>,
+{
+    type Item = Result<Message, MessageError<E>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+
+        loop {
+            match decode(this.buf, *this.max_size, *this.accept_compressed) {
+                Ok(Some(msg)) => return Poll::Ready(Some(Ok(msg))),
+                Ok(None) => {}
+                Err(e) => {
+                    this.buf.clear();
+                    *this.eof = true;
+                    return Poll::Ready(Some(Err(e)));
+                }
+            }
+
+            if *this.eof {
+                if this.buf.is_empty() {
+                    return Poll::Ready(None);
+                }
+                this.buf.clear();
+                return Poll::Ready(Some(Err(MessageError::Incomplete)));
+            }
+
+            match ready!(this.body.as_mut().poll_next(cx)) {
+                Some(Ok(bytes)) => this.buf.extend_from_slice(&bytes),
+                Some(Err(e)) => {
+                    this.buf.clear();
+                    *this.eof = true;
+                    return Poll::Ready(Some(Err(MessageError::Body(e))));
+                }
+                None => *this.eof = true,
+            }
+        }
+    }
+}
+
+fn decode<E>(buf: &mut BytesMut, max_size: usize, accept_compressed: bool) -> Result<Option<Message>, MessageError<E>> {
+    if buf.len() < PREFIX_LEN {
+        return Ok(None);
+    }
+
+    let compressed = match buf[0] {
+        0 => false,
+        1 if accept_compressed => true,
+        1 => return Err(MessageError::Compressed),
+        flag => return Err(MessageError::Flag(flag)),
+    };
+
+    let len = u32::from_be_bytes([buf[1], buf[2], buf[3], buf[4]]) as usize;
+
+    if len > max_size {
+        return Err(MessageError::TooLarge {
+            size: len,
+            max: max_size,
+        });
+    }
+
+    if buf.len() < PREFIX_LEN + len {
+        buf.reserve(PREFIX_LEN + len - buf.len());
+        return Ok(None);
+    }
+
+    buf.advance(PREFIX_LEN);
+
+    Ok(Some(Message {
+        compressed,
+        data: buf.split_to(len).freeze(),
+    }))
+}
+
+/// Error type of [MessageStream].
+#[derive(Debug)]
+pub enum MessageError<E> {
+    /// Error from request body.
+    Body(E),
+    /// Message is compressed while [MessageStream::accept_compressed] is not enabled.
+    Compressed,
+    /// Compressed flag is neither 0 nor 1.
+    Flag(u8),
+    /// Message is larger than [MessageStream::max_message_size] when decoding or larger than
+    /// [u32::MAX] when encoding.
+    TooLarge { size: usize, max: usize },
+    /// Request body ended in the middle of a message.
+    Incomplete,
+}
+
+impl<E> fmt::Display for MessageError<E>
+where
+    E: fmt::Display,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Body(e) => fmt::Display::fmt(e, f),
+            Self::Compressed => f.write_str("compressed message is not supported"),
+            Self::Flag(flag) => write!(f, "invalid compressed flag {flag}"),
+            Self::TooLarge { size, max } => write!(f, "message size {size} exceeds max message size {max}"),
+            Self::Incomplete => f.write_str("request body ended in the middle of message"),
+        }
+    }
+}
+
+impl<E> error::Error for MessageError<E> where E: fmt::Debug + fmt::Display {}
+
+impl<E> From<MessageError<E>> for Status
+where
+    E: fmt::Display,
+{
+    fn from(e: MessageError<E>) -> Self {
+        let code = match e {
+            MessageError::Body(_) => Code::Unavailable,
+            MessageError::Compressed => Code::Unimplemented,
+            MessageError::TooLarge { .. } => Code::ResourceExhausted,
+            MessageError::Flag(_) | MessageError::Incomplete => Code::Internal,
+        };
+        Status::new(code, e.to_string())
+    }
+}
+
+/// Encode given message with length prefix.
+///
+/// # Errors:
+///
+/// [MessageError::TooLarge] when message is longer than [u32::MAX].
+pub fn encode(message: &[u8]) -> Result<Bytes, MessageError<Infallible>> {
+    encode_buf(message)
+}
+
+fn encode_buf<E>(message: impl Buf) -> Result<Bytes, MessageError<E>> {
+    let size = message.remaining();
+    let len = u32::try_from(size).map_err(|_| MessageError::TooLarge {
+        size,
+        max: u32::MAX as usize,
+    })?;
+    let mut buf = BytesMut::with_capacity(PREFIX_LEN + size);
+    buf.put_u8(0);
+    buf.put_u32(len);
+    buf.put(message);
+    Ok(buf.freeze())
+}
+
+pin_project! {
+    /// Response body encoding each item of inner stream as one length-prefixed message.
+    ///
+    /// Error from inner stream is yielded as [MessageError::Body] and message longer than
+    /// [u32::MAX] is yielded as [MessageError::TooLarge].
+    pub struct MessageBody<S> {
+        #[pin]
+        stream: S,
+    }
+}
+
+impl<S> MessageBody<S> {
+    pub const fn new(stream: S) -> Self {
+        Self { stream }
+    }
+}
+
+impl<S, B, E> Stream for MessageBody<S>
+where
+    S: Stream<Item = Result<B, E>>,
+    B: Buf,
+{
+    type Item = Result<Bytes, MessageError<E>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let res = ready!(self.project().stream.poll_next(cx));
+        Poll::Ready(res.map(|res| res.map_err(MessageError::Body).and_then(encode_buf)))
+    }
+
+    // size hint is left as unknown. sized body with zero length is treated as eof by dispatcher
+    // and it would skip sending trailers.
+}
+
+/// Construct a gRPC response streaming given messages. Each item of the stream is encoded as one
+/// length-prefixed message and status is sent as trailers after the last message.
+///
+/// Status has to be known before the first message is sent. Error in the middle of message stream
+/// resets http/2 stream and client would observe it as `CANCELLED` or `INTERNAL` status.
+pub fn response<S>(messages: S, status: Status) -> Response<ResponseBody<MessageBody<S>>> {
+    let mut res = Response::new(ResponseBody::stream(MessageBody::new(messages)));
+    res.headers_mut().insert(CONTENT_TYPE, GRPC);
+    status.extend_headers(res.headers_mut());
+    for name in [GRPC_STATUS, GRPC_MESSAGE] {
+        if res.headers().contains_key(&name) {
+            res.headers_mut().append(TRAILER, HeaderValue::from_name(name));
+        }
+    }
+    res
+}
+
+/// Construct a trailers-only gRPC response without message. Status is sent in response head.
+///
+/// It's what gRPC server would respond with when call fails before any message is produced.
+pub fn error_response<B>(status: Status) -> Response<ResponseBody<B>> {
+    let mut res = Response::new(ResponseBody::None);
+    res.headers_mut().insert(CONTENT_TYPE, GRPC);
+    status.extend_headers(res.headers_mut());
+    res
+}
+
+/// Status code of gRPC call.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Code {
+    Ok = 0,
+    Cancelled = 1,
+    Unknown = 2,
+    InvalidArgument = 3,
+    DeadlineExceeded = 4,
+    NotFound = 5,
+    AlreadyExists = 6,
+    PermissionDenied = 7,
+    ResourceExhausted = 8,
+    FailedPrecondition = 9,
+    Aborted = 10,
+    OutOfRange = 11,
+    Unimplemented = 12,
+    Internal = 13,
+    Unavailable = 14,
+    DataLoss = 15,
+    Unauthenticated = 16,
+}
+
+impl Code {
+    /// Value of `grpc-status` header.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            Self::Ok => "0",
+            Self::Cancelled => "1",
+            Self::Unknown => "2",
+            Self::InvalidArgument => "3",
+            Self::DeadlineExceeded => "4",
+            Self::NotFound => "5",
+            Self::AlreadyExists => "6",
+            Self::PermissionDenied => "7",
+            Self::ResourceExhausted => "8",
+            Self::FailedPrecondition => "9",
+            Self::Aborted => "10",
+            Self::OutOfRange => "11",
+            Self::Unimplemented => "12",
+            Self::Internal => "13",
+            Self::Unavailable => "14",
+            Self::DataLoss => "15",
+            Self::Unauthenticated => "16",
+        }
+    }
+}
+
+/// Status of gRPC call sent as `grpc-status` and optional `grpc-message` header.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Status {
+    code: Code,
+    message: Cow<'static, str>,
+}
+
+impl Status {
+    /// Status of successful call.
+    pub const fn ok() -> Self {
+        Self {
+            code: Code::Ok,
+            message: Cow::Borrowed(""),
+        }
+    }
+
+    pub fn new(code: Code, message: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+
+    #[inline]
+    pub fn code(&self) -> Code {
+        self.code
+    }
+
+    #[inline]
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Insert `grpc-status` and `grpc-message`(when message is not empty) headers to given
+    /// header map. Message is percent-encoded as required by gRPC.
+    pub fn extend_headers(&self, headers: &mut HeaderMap) {
+        headers.insert(GRPC_STATUS, HeaderValue::from_static(self.code.as_str()));
+        if !self.message.is_empty() {
+            let value = percent_encode(self.message.as_bytes());
+            headers.insert(GRPC_MESSAGE, HeaderValue::from_maybe_shared(value).unwrap());
+        }
+    }
+}
+
+// bytes outside of printable ascii and '%' itself are percent-encoded in grpc-message.
+fn percent_encode(input: &[u8]) -> Bytes {
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+    let mut buf = BytesMut::with_capacity(input.len());
+    for &b in input {
+        if (0x20..=0x7e).contains(&b) && b != b'%' {
+            buf.put_u8(b);
+        } else {
+            buf.put_slice(&[b'%', HEX[(b >> 4) as usize], HEX[(b & 0xf) as usize]]);
+        }
+    }
+    buf.freeze()
+}
+
+/// Split path of gRPC call to fully qualified service name and method name.
+/// `/package.Service/Method` is split to `("package.Service", "Method")`.
+pub fn split_path(path: &str) -> Option<(&str, &str)> {
+    let (service, method) = path.strip_prefix('/')?.split_once('/')?;
+    (!service.is_empty() && !method.is_empty() && !method.contains('/')).then_some((service, method))
+}
+
+/// Guard passes when request is a gRPC call. A `POST` request with `application/grpc` or
+/// `application/grpc+{format}` content type and `/package.Service/Method` path.
+///
+/// # Examples:
+/// ```rust
+/// # use std::convert::Infallible;
+/// # use xitca_service::fn_service;
+/// # use xitca_http::{
+/// #   http::{Request, Response},
+/// #   util::{grpc, service::{route::post, Router}}
+/// # };
+/// # async fn say_hello(_: Request<()>) -> Result<Response<()>, Infallible> { todo!() }
+/// let router = Router::new().insert("/helloworld.Greeter/SayHello", post(fn_service(say_hello)).guard(grpc::is_grpc));
+/// ```
+#[cfg(feature = "util-service")]
+pub fn is_grpc(head: &super::service::guard::Head<'_>) -> bool {
+    head.method() == crate::http::Method::POST
+        && head.headers().get(CONTENT_TYPE).is_some_and(|v| {
+            let v = v.as_bytes();
+            v.strip_prefix(GRPC.as_bytes())
+                .is_some_and(|rest| rest.is_empty() || rest[0] == b'+' || rest[0] == b';')
+        })
+        && split_path(head.uri().path()).is_some()
+}
+
+#[cfg(test)]
+mod test {
+    use core::{future::poll_fn, pin::pin};
+
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::body::Once;
+
+    use super::*;
+
+    fn collect<B, E>(body: MessageStream<B>) -> Vec<Result<Message, MessageError<E>>>
+    where
+        B: Stream<Item = Result<Bytes, E>>,
+    {
+        let mut body = pin!(body);
+        let mut res = Vec::new();
+        while let Some(item) = poll_fn(|cx| body.as_mut().poll_next(cx)).now_or_panic() {
+            res.push(item);
+        }
+        res
+    }
+
+    fn chunks(chunks: Vec<Vec<u8>>) -> impl Stream<Item = Result<Bytes, Infallible>> {
+        futures_util::stream::iter(chunks.into_iter().map(|c| Ok(Bytes::from(c))))
+    }
+
+    fn msg(data: &'static [u8]) -> Message {
+        Message {
+            compressed: false,
+            data: Bytes::from_static(data),
+        }
+    }
+
+    #[test]
+    fn decode_split() {
+        let mut body = encode(b"hello").unwrap().to_vec();
+        body.extend_from_slice(&encode(b"").unwrap());
+        body.extend_from_slice(&encode(b"world").unwrap());
+
+        // split body to every possible chunk size.
+        for size in 1..=body.len() {
+            let res = collect(MessageStream::new(chunks(body.chunks(size).map(Vec::from).collect())));
+            let res = res.into_iter().map(Result::unwrap).collect::<Vec<_>>();
+            assert_eq!(res, [msg(b"hello"), msg(b""), msg(b"world")], "chunk size {size}");
+        }
+    }
+
+    #[test]
+    fn decode_error() {
+        let mut compressed = encode(b"hello").unwrap().to_vec();
+        compressed[0] = 1;
+
+        let res = collect(MessageStream::new(chunks(vec![compressed.clone()])));
+        assert!(matches!(res[..], [Err(MessageError::Compressed)]));
+
+        let res = collect(MessageStream::new(chunks(vec![compressed])).accept_compressed());
+        assert_eq!(res[0].as_ref().unwrap().data, "hello");
+        assert!(res[0].as_ref().unwrap().compressed);
+
+        let res = collect(MessageStream::new(chunks(vec![vec![2, 0, 0, 0, 0]])));
+        assert!(matches!(res[..], [Err(MessageError::Flag(2))]));
+
+        let res = collect(MessageStream::new(chunks(vec![encode(b"hello").unwrap().to_vec()])).max_message_size(4));
+        assert!(matches!(res[..], [Err(MessageError::TooLarge { size: 5, max: 4 })]));
+
+        // message after error is not yielded.
+        let mut body = encode(b"hello").unwrap()[..4].to_vec();
+        let res = collect(MessageStream::new(chunks(vec![body.clone()])));
+        assert!(matches!(res[..], [Err(MessageError::Incomplete)]));
+
+        body.extend_from_slice(&encode(b"hello").unwrap()[4..]);
+        body.extend_from_slice(&[3, 0, 0, 0, 0]);
+        body.extend_from_slice(&encode(b"world").unwrap());
+        let res = collect(MessageStream::new(chunks(vec![body])));
+        assert_eq!(res.len(), 2);
+        assert_eq!(res[0].as_ref().unwrap(), &msg(b"hello"));
+        assert!(matches!(res[1], Err(MessageError::Flag(3))));
+    }
+
+    #[test]
+    fn status() {
+        let status = Status::from(MessageError::<Infallible>::TooLarge { size: 5, max: 4 });
+        assert_eq!(status.code(), Code::ResourceExhausted);
+
+        let mut headers = HeaderMap::new();
+        Status::new(Code::Internal, "100% 失败\n").extend_headers(&mut headers);
+        assert_eq!(headers.get(GRPC_STATUS).unwrap(), "13");
+        assert_eq!(headers.get(GRPC_MESSAGE).unwrap(), "100%25 %E5%A4%B1%E8%B4%A5%0A");
+
+        let mut headers = HeaderMap::new();
+        Status::ok().extend_headers(&mut headers);
+        assert_eq!(headers.get(GRPC_STATUS).unwrap(), "0");
+        assert!(!headers.contains_key(GRPC_MESSAGE));
+    }
+
+    #[test]
+    fn response_trailers() {
+        let res = response(
+            Once::new(Bytes::from_static(b"hello")),
+            Status::new(Code::Aborted, "abort"),
+        );
+        assert_eq!(res.headers().get(CONTENT_TYPE).unwrap(), GRPC);
+        assert_eq!(res.headers().get(GRPC_STATUS).unwrap(), "10");
+        let trailers = res.headers().get_all(TRAILER).iter().collect::<Vec<_>>();
+        assert_eq!(trailers, ["grpc-status", "grpc-message"]);
+
+        let mut body = pin!(res.into_body());
+        let msg = poll_fn(|cx| body.as_mut().poll_next(cx))
+            .now_or_panic()
+            .unwrap()
+            .unwrap();
+        assert_eq!(msg, encode(b"hello").unwrap());
+        assert_eq!(&msg[..PREFIX_LEN], [0, 0, 0, 0, 5]);
+        assert!(poll_fn(|cx| body.as_mut().poll_next(cx)).now_or_panic().is_none());
+
+        let res = error_response::<Once<Bytes>>(Status::new(Code::Unimplemented, "unknown method"));
+        assert!(!res.headers().contains_key(TRAILER));
+        assert_eq!(res.headers().get(GRPC_STATUS).unwrap(), "12");
+        assert!(matches!(res.body(), ResponseBody::None));
+    }
+
+    #[test]
+    fn encode_too_large() {
+        // buffer claiming a length over u32::MAX without allocating it. encoding must fail before
+        // reading any of it.
+        struct Giant;
+
+        impl Buf for Giant {
+            fn remaining(&self) -> usize {
+                u32::MAX as usize + 1
+            }
+
+            fn chunk(&self) -> &[u8] {
+                unreachable!()
+            }
+
+            fn advance(&mut self, _: usize) {
+                unreachable!()
+            }
+        }
+
+        let body = MessageBody::new(futures_util::stream::iter([Ok::<_, Infallible>(Giant)]));
+        let mut body = pin!(body);
+        let res = poll_fn(|cx| body.as_mut().poll_next(cx)).now_or_panic().unwrap();
+        assert!(matches!(
+            res,
+            Err(MessageError::TooLarge { size, max }) if size == u32::MAX as usize + 1 && max == u32::MAX as usize
+        ));
+    }
+
+    #[test]
+    fn path() {
+        assert_eq!(
+            split_path("/helloworld.Greeter/SayHello"),
+            Some(("helloworld.Greeter", "SayHello"))
+        );
+        assert_eq!(split_path("/Greeter/SayHello"), Some(("Greeter", "SayHello")));
+        for path in [
+            "/",
+            "//SayHello",
+            "/helloworld.Greeter/",
+            "/a/b/c",
+            "helloworld.Greeter/SayHello",
+        ] {
+            assert_eq!(split_path(path), None, "{path}");
+        }
+    }
+
+    #[cfg(feature = "util-service")]
+    #[test]
+    fn guard() {
+        use crate::http::{Method, Request};
+
+        use super::super::service::guard::Head;
+
+        let req = |method, content_type| {
+            Request::builder()
+                .method(method)
+                .uri("/helloworld.Greeter/SayHello")
+                .header(CONTENT_TYPE, content_type)
+                .body(())
+                .unwrap()
+        };
+
+        for ct in [
+            "application/grpc",
+            "application/grpc+proto",
+            "application/grpc; charset=utf-8",
+        ] {
+            assert!(is_grpc(&Head::new(&req(Method::POST, ct))), "{ct}");
+        }
+
+        assert!(!is_grpc(&Head::new(&req(Method::GET, "application/grpc"))));
+        assert!(!is_grpc(&Head::new(&req(Method::POST, "application/grpc-web"))));
+        assert!(!is_grpc(&Head::new(&req(Method::POST, "application/json"))));
+    }
+}
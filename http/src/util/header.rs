@@ -0,0 +1,97 @@
+//! [HeaderValue] construction for integer, duration and date values without going through
+//! intermediate [String] formatting.
+
+use core::time::Duration;
+
+use crate::http::header::HeaderValue;
+
+/// Integers lower than this value are served from a static table without allocation.
+pub const STATIC_INT_LIMIT: u64 = 1000;
+
+// decimal representation of 0..STATIC_INT_LIMIT. digits are left aligned.
+static INTS: [[u8; 3]; STATIC_INT_LIMIT as usize] = {
+    let mut table = [[0; 3]; STATIC_INT_LIMIT as usize];
+    let mut n = 0;
+    while n < STATIC_INT_LIMIT as usize {
+        table[n] = match n {
+            0..=9 => [b'0' + n as u8, 0, 0],
+            10..=99 => [b'0' + (n / 10) as u8, b'0' + (n % 10) as u8, 0],
+            _ => [
+                b'0' + (n / 100) as u8,
+                b'0' + (n / 10 % 10) as u8,
+                b'0' + (n % 10) as u8,
+            ],
+        };
+        n += 1;
+    }
+    table
+};
+
+/// Construct [HeaderValue] from integer in decimal format.
+///
+/// Value lower than [STATIC_INT_LIMIT] references a static table and does not allocate. Larger
+/// value is formatted on stack with [itoa] and copied into [HeaderValue] with one allocation.
+///
+/// # Examples:
+/// ```rust
+/// # use xitca_http::util::header::int_header_value;
+/// let value = int_header_value(120);
+/// assert_eq!(value, "120");
+/// ```
+pub fn int_header_value(n: u64) -> HeaderValue {
+    if n < STATIC_INT_LIMIT {
+        let len = match n {
+            0..=9 => 1,
+            10..=99 => 2,
+            _ => 3,
+        };
+        // table only contains ascii digits.
+        let digits = core::str::from_utf8(&INTS[n as usize][..len]).unwrap();
+        HeaderValue::from_static(digits)
+    } else {
+        let mut buf = itoa::Buffer::new();
+        // itoa only produces ascii digits.
+        HeaderValue::from_str(buf.format(n)).unwrap()
+    }
+}
+
+/// Construct [HeaderValue] from [Duration] in delta-seconds format. e.g. `Retry-After` and `Age`
+/// headers.
+///
+/// Sub-second part of duration is rounded up so peer never acts earlier than expected.
+pub fn duration_header_value(dur: Duration) -> HeaderValue {
+    int_header_value(dur.as_secs() + u64::from(dur.subsec_nanos() > 0))
+}
+
+/// Construct [HeaderValue] from current date of given [DateTime](crate::date::DateTime) in HTTP-date
+/// format.
+///
+/// [DateTimeService](crate::date::DateTimeService) caches the value for every update of date so
+/// the construction is a reference count increment. Other [DateTime](crate::date::DateTime) types
+/// construct a new value from their date bytes on every call.
+#[cfg(feature = "runtime")]
+pub fn date_header_value<D>(date: &D) -> HeaderValue
+where
+    D: crate::date::DateTime,
+{
+    date.header_value()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn int() {
+        for n in [0, 7, 10, 42, 99, 100, 999, 1000, 12345, u64::MAX] {
+            assert_eq!(int_header_value(n), n.to_string().as_str());
+        }
+    }
+
+    #[test]
+    fn duration() {
+        assert_eq!(duration_header_value(Duration::ZERO), "0");
+        assert_eq!(duration_header_value(Duration::from_secs(3)), "3");
+        assert_eq!(duration_header_value(Duration::from_millis(3001)), "4");
+    }
+}
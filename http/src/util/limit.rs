@@ -0,0 +1,96 @@
+//! Connection count limit of [HttpService](crate::HttpService).
+
+use core::{
+    cell::{Cell, RefCell},
+    future::poll_fn,
+    task::{Poll, Waker},
+};
+
+use std::rc::Rc;
+
+// limit is shared by all listeners of one service instance which lives on a single worker thread.
+pub(crate) struct ConnectionLimit {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    max: usize,
+    count: Cell<usize>,
+    wakers: RefCell<Vec<Waker>>,
+}
+
+impl ConnectionLimit {
+    pub(crate) fn new(max: usize) -> Self {
+        Self {
+            inner: Rc::new(Inner {
+                max,
+                count: Cell::new(0),
+                wakers: RefCell::new(Vec::new()),
+            }),
+        }
+    }
+
+    // resolve when connection count is below limit.
+    pub(crate) async fn acquire(&self) -> ConnectionPermit {
+        poll_fn(|cx| {
+            let inner = &self.inner;
+            let count = inner.count.get();
+            if count < inner.max {
+                inner.count.set(count + 1);
+                return Poll::Ready(ConnectionPermit { inner: inner.clone() });
+            }
+
+            let mut wakers = inner.wakers.borrow_mut();
+            if !wakers.iter().any(|w| w.will_wake(cx.waker())) {
+                wakers.push(cx.waker().clone());
+            }
+            Poll::Pending
+        })
+        .await
+    }
+}
+
+/// Permit of connection counted by [HttpServiceConfig::max_connections](crate::config::HttpServiceConfig::max_connections).
+///
+/// It's held until the connection is finished and dropping it makes room for a new connection.
+pub struct ConnectionPermit {
+    inner: Rc<Inner>,
+}
+
+impl Drop for ConnectionPermit {
+    fn drop(&mut self) {
+        self.inner.count.set(self.inner.count.get() - 1);
+        let wakers = core::mem::take(&mut *self.inner.wakers.borrow_mut());
+        wakers.into_iter().for_each(Waker::wake);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::{future::Future, pin::pin, task::Context};
+
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use super::*;
+
+    #[test]
+    fn acquire() {
+        let limit = ConnectionLimit::new(1);
+
+        let permit = limit.acquire().now_or_panic();
+
+        let mut fut = pin!(limit.acquire());
+        assert!(fut.as_mut().poll(&mut Context::from_waker(Waker::noop())).is_pending());
+        assert_eq!(limit.inner.wakers.borrow().len(), 1);
+
+        drop(permit);
+        assert!(limit.inner.wakers.borrow().is_empty());
+        let Poll::Ready(permit) = fut.as_mut().poll(&mut Context::from_waker(Waker::noop())) else {
+            panic!("permit must be acquired after release");
+        };
+        assert_eq!(limit.inner.count.get(), 1);
+
+        drop(permit);
+        assert_eq!(limit.inner.count.get(), 0);
+    }
+}
@@ -4,10 +4,11 @@ use std::{
     future::Future,
     pin::Pin,
     task::{Context, Poll},
+    time::Instant,
 };
 
 use pin_project_lite::pin_project;
-use tracing::{error, span, Level, Span};
+use tracing::{error, span, trace, Level, Span};
 use xitca_service::{ready::ReadyService, Service};
 
 /// A factory for logger service.
@@ -47,7 +48,8 @@ impl<S> Service<S> for Logger {
 }
 
 /// Logger service uses a tracking span called `xitca_http_logger` and would collect
-/// log from all levels(from trace to info)
+/// log from all levels(from trace to info). Duration of service call is logged with `elapsed` field
+/// and it's measured with precise time read from OS.
 pub struct LoggerService<S> {
     service: S,
     span: Span,
@@ -68,10 +70,14 @@ where
     {
         Instrumented {
             task: async {
-                self.service.call(req).await.map_err(|e| {
-                    error!("{:?}", e);
-                    e
-                })
+                let start = Instant::now();
+                let res = self.service.call(req).await;
+                let elapsed = start.elapsed();
+                match res {
+                    Ok(_) => trace!(?elapsed, "service call finished"),
+                    Err(ref e) => error!(?elapsed, "{:?}", e),
+                }
+                res
             },
             span: &self.span,
         }
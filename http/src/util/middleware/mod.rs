@@ -1,5 +1,6 @@
 mod extension;
 mod logger;
+mod readiness;
 
 #[cfg(not(target_family = "wasm"))]
 #[cfg(feature = "runtime")]
@@ -7,6 +8,7 @@ mod socket_config;
 
 pub use extension::Extension;
 pub use logger::Logger;
+pub use readiness::Readiness;
 
 #[cfg(not(target_family = "wasm"))]
 #[cfg(feature = "runtime")]
@@ -0,0 +1,142 @@
+use core::{convert::Infallible, future::Future};
+
+use xitca_service::{ready::ReadyService, Service};
+
+/// A middleware attaching an extra readiness check to service.
+///
+/// The readiness type is constructed by given function for every instance of service(one per
+/// worker thread of server) and it's [ReadyService::ready] is awaited before the one of service.
+/// When service is served by [HttpService](crate::HttpService) accepting new connection is paused
+/// until both of them are ready.
+///
+/// See [HttpServiceBuilder::readiness](crate::HttpServiceBuilder::readiness) for example.
+#[derive(Clone)]
+pub struct Readiness<F> {
+    factory: F,
+}
+
+impl<F> Readiness<F> {
+    pub fn new<R>(factory: F) -> Self
+    where
+        F: Fn() -> R,
+        R: ReadyService,
+    {
+        Self { factory }
+    }
+}
+
+impl<S, F, R> Service<S> for Readiness<F>
+where
+    F: Fn() -> R,
+{
+    type Response = ReadinessService<S, R>;
+    type Error = Infallible;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where Self: 'f, S: 'f;
+
+    fn call<'s>(&'s self, service: S) -> Self::Future<'s>
+    where
+        S: 's,
+    {
+        async {
+            Ok(ReadinessService {
+                service,
+                readiness: (self.factory)(),
+            })
+        }
+    }
+}
+
+pub struct ReadinessService<S, R> {
+    service: S,
+    readiness: R,
+}
+
+impl<S, R, Req> Service<Req> for ReadinessService<S, R>
+where
+    S: Service<Req>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future<'f> = S::Future<'f> where Self: 'f, Req: 'f;
+
+    #[inline]
+    fn call<'s>(&'s self, req: Req) -> Self::Future<'s>
+    where
+        Req: 's,
+    {
+        self.service.call(req)
+    }
+}
+
+impl<S, R> ReadyService for ReadinessService<S, R>
+where
+    S: ReadyService,
+    R: ReadyService,
+{
+    type Ready = (R::Ready, S::Ready);
+    type Future<'f> = impl Future<Output = Self::Ready> where Self: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::Future<'_> {
+        async {
+            let readiness = self.readiness.ready().await;
+            let ready = self.service.ready().await;
+            (readiness, ready)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::{cell::Cell, pin::pin, task::Context, task::Waker};
+
+    use std::rc::Rc;
+
+    use xitca_service::{fn_service, ServiceExt};
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use super::*;
+
+    struct Flag(Rc<Cell<bool>>);
+
+    impl ReadyService for Flag {
+        type Ready = ();
+        type Future<'f> = impl Future<Output = Self::Ready> where Self: 'f;
+
+        fn ready(&self) -> Self::Future<'_> {
+            core::future::poll_fn(|cx| {
+                if self.0.get() {
+                    core::task::Poll::Ready(())
+                } else {
+                    cx.waker().wake_by_ref();
+                    core::task::Poll::Pending
+                }
+            })
+        }
+    }
+
+    #[test]
+    fn readiness() {
+        let flag = Rc::new(Cell::new(false));
+
+        let service = {
+            let flag = flag.clone();
+            fn_service(|_: ()| async { Ok::<_, Infallible>("996") })
+                .enclosed(Readiness::new(move || Flag(flag.clone())))
+                .call(())
+                .now_or_panic()
+                .unwrap()
+        };
+
+        let mut ready = pin!(service.ready());
+        assert!(ready
+            .as_mut()
+            .poll(&mut Context::from_waker(Waker::noop()))
+            .is_pending());
+
+        flag.set(true);
+        assert!(ready.as_mut().poll(&mut Context::from_waker(Waker::noop())).is_ready());
+
+        assert_eq!(service.call(()).now_or_panic().unwrap(), "996");
+    }
+}
@@ -1,4 +1,12 @@
+pub mod cached;
+pub mod drain;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod header;
 pub mod middleware;
+pub mod percent;
+pub mod range;
+pub mod scratch;
 
 #[cfg(feature = "util-service")]
 pub mod service;
@@ -6,5 +14,11 @@ pub mod service;
 #[cfg(any(feature = "http1", feature = "http2"))]
 pub mod buffered;
 pub(crate) mod futures;
+#[cfg(any(feature = "http1", feature = "http2", feature = "http3"))]
+pub(crate) mod span;
+#[cfg(feature = "runtime")]
+pub(crate) mod limit;
 #[cfg(feature = "runtime")]
 pub(crate) mod timer;
+#[cfg(any(feature = "http1", feature = "http2"))]
+pub(crate) mod watchdog;
@@ -0,0 +1,215 @@
+//! Percent-decoding utilities for request path and query.
+//!
+//! All decoding functions return [Cow::Borrowed] when input does not contain any escape sequence
+//! and no allocation would happen in that case.
+//!
+//! Malformed escape sequence(a lone `%` or `%` followed by non hex digits) is not treated as error
+//! and it's kept as is in output.
+
+use std::{borrow::Cow, str::Utf8Error};
+
+/// Percent-decode given bytes.
+pub fn decode(input: &[u8]) -> Cow<'_, [u8]> {
+    decode_inner(input, false)
+}
+
+/// Percent-decode given string and validate the output as utf-8.
+pub fn decode_utf8(input: &str) -> Result<Cow<'_, str>, Utf8Error> {
+    match decode(input.as_bytes()) {
+        Cow::Borrowed(_) => Ok(Cow::Borrowed(input)),
+        Cow::Owned(bytes) => String::from_utf8(bytes).map(Cow::Owned).map_err(|e| e.utf8_error()),
+    }
+}
+
+/// Percent-decode given string and replace invalid utf-8 sequence in output with
+/// [U+FFFD REPLACEMENT CHARACTER](char::REPLACEMENT_CHARACTER).
+pub fn decode_utf8_lossy(input: &str) -> Cow<'_, str> {
+    lossy(input, decode(input.as_bytes()))
+}
+
+/// Iterate over `key=value` pairs of given query string.
+///
+/// - pairs are separated by `&` and empty pairs are skipped.
+/// - `+` is decoded as space.
+/// - pair without `=` would yield an empty value.
+/// - key and value are decoded with [decode_utf8_lossy].
+///
+/// # Examples:
+/// ```rust
+/// use xitca_http::util::percent::query_pairs;
+///
+/// let mut pairs = query_pairs("name=foo+bar&&id=%31&flag");
+/// assert_eq!(pairs.next(), Some(("name".into(), "foo bar".into())));
+/// assert_eq!(pairs.next(), Some(("id".into(), "1".into())));
+/// assert_eq!(pairs.next(), Some(("flag".into(), "".into())));
+/// assert_eq!(pairs.next(), None);
+/// ```
+pub fn query_pairs(query: &str) -> QueryPairs<'_> {
+    QueryPairs { query }
+}
+
+/// Iterator returned by [query_pairs].
+#[derive(Clone, Debug)]
+pub struct QueryPairs<'a> {
+    query: &'a str,
+}
+
+impl<'a> Iterator for QueryPairs<'a> {
+    type Item = (Cow<'a, str>, Cow<'a, str>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.query.is_empty() {
+                return None;
+            }
+
+            let pair = match self.query.split_once('&') {
+                Some((pair, rest)) => {
+                    self.query = rest;
+                    pair
+                }
+                None => core::mem::take(&mut self.query),
+            };
+
+            if pair.is_empty() {
+                continue;
+            }
+
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+
+            return Some((decode_form(key), decode_form(value)));
+        }
+    }
+}
+
+fn decode_form(input: &str) -> Cow<'_, str> {
+    lossy(input, decode_inner(input.as_bytes(), true))
+}
+
+fn lossy<'a>(input: &'a str, decoded: Cow<'a, [u8]>) -> Cow<'a, str> {
+    match decoded {
+        Cow::Borrowed(_) => Cow::Borrowed(input),
+        Cow::Owned(bytes) => match String::from_utf8(bytes) {
+            Ok(string) => Cow::Owned(string),
+            Err(e) => Cow::Owned(String::from_utf8_lossy(e.as_bytes()).into_owned()),
+        },
+    }
+}
+
+fn decode_inner(input: &[u8], plus_as_space: bool) -> Cow<'_, [u8]> {
+    let needs_decode = |idx: usize| match input[idx] {
+        b'%' => hex_pair(input, idx).is_some(),
+        b'+' => plus_as_space,
+        _ => false,
+    };
+
+    let Some(start) = (0..input.len()).find(|idx| needs_decode(*idx)) else {
+        return Cow::Borrowed(input);
+    };
+
+    let mut output = Vec::with_capacity(input.len());
+    output.extend_from_slice(&input[..start]);
+    decode_extend(&input[start..], plus_as_space, &mut output);
+
+    Cow::Owned(output)
+}
+
+// append percent-decoded input to output.
+pub(super) fn decode_extend(input: &[u8], plus_as_space: bool, output: &mut Vec<u8>) {
+    let mut idx = 0;
+    while idx < input.len() {
+        match input[idx] {
+            b'%' => match hex_pair(input, idx) {
+                Some(byte) => {
+                    output.push(byte);
+                    idx += 3;
+                    continue;
+                }
+                None => output.push(b'%'),
+            },
+            b'+' if plus_as_space => output.push(b' '),
+            byte => output.push(byte),
+        }
+        idx += 1;
+    }
+}
+
+// try to decode two hex digits following the % at given index.
+fn hex_pair(input: &[u8], idx: usize) -> Option<u8> {
+    let hi = hex(*input.get(idx + 1)?)?;
+    let lo = hex(*input.get(idx + 2)?)?;
+    Some(hi << 4 | lo)
+}
+
+const fn hex(byte: u8) -> Option<u8> {
+    match byte {
+        b'0'..=b'9' => Some(byte - b'0'),
+        b'a'..=b'f' => Some(byte - b'a' + 10),
+        b'A'..=b'F' => Some(byte - b'A' + 10),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn no_escape_borrowed() {
+        for input in ["", "/", "/users/996", "a+b", "%", "100%", "%Z1"] {
+            assert!(matches!(decode(input.as_bytes()), Cow::Borrowed(_)), "{input}");
+            assert!(matches!(decode_utf8(input).unwrap(), Cow::Borrowed(_)), "{input}");
+            assert!(matches!(decode_utf8_lossy(input), Cow::Borrowed(_)), "{input}");
+        }
+
+        let (key, value) = query_pairs("key=value").next().unwrap();
+        assert!(matches!(key, Cow::Borrowed(_)));
+        assert!(matches!(value, Cow::Borrowed(_)));
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(decode_utf8("/a%20b/%2F").unwrap(), "/a b//");
+        assert_eq!(decode_utf8("%e4%BD%a0").unwrap(), "你");
+        // + is only decoded in query.
+        assert_eq!(decode_utf8("a+b%2B").unwrap(), "a+b+");
+        // lone % and invalid hex digits are kept.
+        assert_eq!(decode_utf8("%%41%").unwrap(), "%A%");
+        assert_eq!(decode_utf8("%ZZ%4").unwrap(), "%ZZ%4");
+        assert_eq!(decode_utf8("%4%41").unwrap(), "%4A");
+        // embedded nul.
+        assert_eq!(decode(b"a%00b").as_ref(), b"a\0b");
+        assert_eq!(decode_utf8("a%00b").unwrap(), "a\0b");
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        // overlong encoding of '/'.
+        assert!(decode_utf8("%C0%AF").is_err());
+        assert_eq!(decode_utf8_lossy("%C0%AF"), "\u{FFFD}\u{FFFD}");
+        // truncated multi bytes sequence.
+        assert!(decode_utf8("%E4%BD").is_err());
+        assert_eq!(decode_utf8_lossy("a%E4%BDb"), "a\u{FFFD}b");
+        assert_eq!(decode(b"%C0%AF").as_ref(), &[0xC0, 0xAF]);
+    }
+
+    #[test]
+    fn query() {
+        let pairs = query_pairs("a=1&b=foo+bar%21&&=empty&c&d=&e=x=y&%ZZ=%").collect::<Vec<_>>();
+        assert_eq!(
+            pairs,
+            [
+                ("a".into(), "1".into()),
+                ("b".into(), "foo bar!".into()),
+                ("".into(), "empty".into()),
+                ("c".into(), "".into()),
+                ("d".into(), "".into()),
+                ("e".into(), "x=y".into()),
+                ("%ZZ".into(), "%".into()),
+            ]
+        );
+
+        assert_eq!(query_pairs("").count(), 0);
+        assert_eq!(query_pairs("&&").count(), 0);
+    }
+}
@@ -0,0 +1,407 @@
+//! Parsing and formatting of `Range` and `Content-Range` header values. (RFC 9110 section 14)
+//!
+//! Only `bytes` range unit is supported. Header value with other units is reported with
+//! [RangeError::Unit] and it's expected to be ignored by caller.
+//!
+//! # Examples:
+//! ```rust
+//! use xitca_http::util::range::{parse_range, resolve_ranges, ContentRange, RangeSpec};
+//!
+//! let specs = parse_range("bytes=0-99, 200-, -50").unwrap();
+//! assert_eq!(specs, [RangeSpec::FromTo(0, 99), RangeSpec::From(200), RangeSpec::Suffix(50)]);
+//!
+//! // resolve against a 1000 bytes representation. suffix range overlaps with 200- and coalesced.
+//! assert_eq!(resolve_ranges(&specs, 1000).unwrap(), [(0, 99), (200, 999)]);
+//!
+//! let range = ContentRange::parse("bytes 0-99/1000").unwrap();
+//! assert_eq!(range.len(), Some(100));
+//! assert_eq!(range.to_string(), "bytes 0-99/1000");
+//! ```
+
+use core::fmt;
+
+use std::error;
+
+/// A single range of `Range` header. Positions are inclusive.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RangeSpec {
+    /// `first-last`
+    FromTo(u64, u64),
+    /// `first-` till the end of representation.
+    From(u64),
+    /// `-length` as the last length of bytes of representation.
+    Suffix(u64),
+}
+
+impl RangeSpec {
+    /// Resolve range against length of representation to inclusive `(first, last)` positions.
+    ///
+    /// Return None when the range is not satisfiable.
+    pub fn resolve(&self, len: u64) -> Option<(u64, u64)> {
+        let end = len.checked_sub(1)?;
+        match *self {
+            Self::FromTo(first, last) => (first <= end).then_some((first, last.min(end))),
+            Self::From(first) => (first <= end).then_some((first, end)),
+            Self::Suffix(0) => None,
+            Self::Suffix(suffix) => Some((len.saturating_sub(suffix), end)),
+        }
+    }
+}
+
+impl fmt::Display for RangeSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::FromTo(first, last) => write!(f, "{first}-{last}"),
+            Self::From(first) => write!(f, "{first}-"),
+            Self::Suffix(suffix) => write!(f, "-{suffix}"),
+        }
+    }
+}
+
+/// Error of parsing and resolving range header values.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RangeError {
+    /// Range unit is not `bytes`.
+    Unit,
+    /// Header value is not well formed. (e.g. last position is smaller than first position, number
+    /// overflows u64)
+    Malformed,
+    /// None of the ranges is satisfiable.
+    Unsatisfiable,
+}
+
+impl fmt::Display for RangeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Unit => f.write_str("range unit is not supported"),
+            Self::Malformed => f.write_str("range is malformed"),
+            Self::Unsatisfiable => f.write_str("range is not satisfiable"),
+        }
+    }
+}
+
+impl error::Error for RangeError {}
+
+/// Parse `Range` header value. (e.g. `bytes=0-99,200-,-50`)
+///
+/// Ranges are kept in their order from header value. Empty list elements and optional white
+/// spaces around them are allowed.
+pub fn parse_range(value: &str) -> Result<Vec<RangeSpec>, RangeError> {
+    let (unit, set) = value.trim().split_once('=').ok_or(RangeError::Malformed)?;
+
+    if !is_bytes_unit(unit) {
+        return Err(RangeError::Unit);
+    }
+
+    let specs = set
+        .split(',')
+        .map(|spec| spec.trim_matches(is_ows))
+        .filter(|spec| !spec.is_empty())
+        .map(|spec| {
+            let (first, last) = spec.split_once('-').ok_or(RangeError::Malformed)?;
+            match (first, last) {
+                ("", suffix) => parse_u64(suffix).map(RangeSpec::Suffix),
+                (first, "") => parse_u64(first).map(RangeSpec::From),
+                (first, last) => {
+                    let (first, last) = (parse_u64(first)?, parse_u64(last)?);
+                    if last < first {
+                        return Err(RangeError::Malformed);
+                    }
+                    Ok(RangeSpec::FromTo(first, last))
+                }
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    if specs.is_empty() {
+        return Err(RangeError::Malformed);
+    }
+
+    Ok(specs)
+}
+
+/// Resolve ranges against length of representation to inclusive `(first, last)` positions.
+///
+/// Unsatisfiable ranges are skipped. The output is sorted and overlapping or adjacent ranges are
+/// coalesced into one. (RFC 9110 section 14.2)
+pub fn resolve_ranges(specs: &[RangeSpec], len: u64) -> Result<Vec<(u64, u64)>, RangeError> {
+    let mut ranges = specs.iter().filter_map(|spec| spec.resolve(len)).collect::<Vec<_>>();
+
+    if ranges.is_empty() {
+        return Err(RangeError::Unsatisfiable);
+    }
+
+    ranges.sort_unstable();
+
+    let mut coalesced = Vec::<(u64, u64)>::with_capacity(ranges.len());
+    for (first, last) in ranges {
+        match coalesced.last_mut() {
+            // last is always smaller than len so the addition can not overflow.
+            Some(prev) if first <= prev.1 + 1 => prev.1 = prev.1.max(last),
+            _ => coalesced.push((first, last)),
+        }
+    }
+
+    Ok(coalesced)
+}
+
+/// Value of `Content-Range` header.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ContentRange {
+    /// `bytes first-last/complete` where complete length is None when it's unknown. (`*`)
+    Bytes {
+        first: u64,
+        last: u64,
+        complete: Option<u64>,
+    },
+    /// `bytes */complete` used by `416 Range Not Satisfiable` response.
+    Unsatisfied(u64),
+}
+
+impl ContentRange {
+    /// Parse `Content-Range` header value.
+    ///
+    /// Range with last position smaller than first position or complete length not larger than
+    /// last position is [RangeError::Malformed].
+    pub fn parse(value: &str) -> Result<Self, RangeError> {
+        let (unit, resp) = value.trim().split_once(' ').ok_or(RangeError::Malformed)?;
+
+        if !is_bytes_unit(unit) {
+            return Err(RangeError::Unit);
+        }
+
+        let (range, complete) = resp.split_once('/').ok_or(RangeError::Malformed)?;
+
+        if range == "*" {
+            return parse_u64(complete).map(Self::Unsatisfied);
+        }
+
+        let (first, last) = range.split_once('-').ok_or(RangeError::Malformed)?;
+        let (first, last) = (parse_u64(first)?, parse_u64(last)?);
+
+        let complete = match complete {
+            "*" => None,
+            complete => Some(parse_u64(complete)?),
+        };
+
+        if last < first || complete.is_some_and(|complete| complete <= last) {
+            return Err(RangeError::Malformed);
+        }
+
+        Ok(Self::Bytes { first, last, complete })
+    }
+
+    /// Length of bytes in range. None for [ContentRange::Unsatisfied] or when the length can not
+    /// be represented by u64. (`bytes 0-18446744073709551615/*`)
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> Option<u64> {
+        match *self {
+            Self::Bytes { first, last, .. } => (last - first).checked_add(1),
+            Self::Unsatisfied(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for ContentRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Bytes {
+                first,
+                last,
+                complete: Some(complete),
+            } => write!(f, "bytes {first}-{last}/{complete}"),
+            Self::Bytes { first, last, .. } => write!(f, "bytes {first}-{last}/*"),
+            Self::Unsatisfied(complete) => write!(f, "bytes */{complete}"),
+        }
+    }
+}
+
+fn is_bytes_unit(unit: &str) -> bool {
+    unit.eq_ignore_ascii_case("bytes")
+}
+
+const fn is_ows(c: char) -> bool {
+    matches!(c, ' ' | '\t')
+}
+
+// 1*DIGIT without sign and white space. overflow is treated as malformed.
+fn parse_u64(value: &str) -> Result<u64, RangeError> {
+    if value.is_empty() {
+        return Err(RangeError::Malformed);
+    }
+
+    value.bytes().try_fold(0u64, |num, b| {
+        if !b.is_ascii_digit() {
+            return Err(RangeError::Malformed);
+        }
+        num.checked_mul(10)
+            .and_then(|num| num.checked_add(u64::from(b - b'0')))
+            .ok_or(RangeError::Malformed)
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use RangeSpec::*;
+
+    #[test]
+    fn range() {
+        let cases: &[(&str, Result<&[RangeSpec], RangeError>)] = &[
+            ("bytes=0-499", Ok(&[FromTo(0, 499)])),
+            ("bytes=500-999", Ok(&[FromTo(500, 999)])),
+            ("bytes=-500", Ok(&[Suffix(500)])),
+            ("bytes=9500-", Ok(&[From(9500)])),
+            ("bytes=0-0,-1", Ok(&[FromTo(0, 0), Suffix(1)])),
+            ("bytes=0-0", Ok(&[FromTo(0, 0)])),
+            ("bytes=-0", Ok(&[Suffix(0)])),
+            ("bytes=500-600,601-999", Ok(&[FromTo(500, 600), FromTo(601, 999)])),
+            ("bytes=500-700,601-999", Ok(&[FromTo(500, 700), FromTo(601, 999)])),
+            ("bytes=0-1, 5-", Ok(&[FromTo(0, 1), From(5)])),
+            ("bytes= 0-1 ,\t-2 , ", Ok(&[FromTo(0, 1), Suffix(2)])),
+            ("bytes=,,0-1,,", Ok(&[FromTo(0, 1)])),
+            ("  bytes=0-1  ", Ok(&[FromTo(0, 1)])),
+            ("BYTES=0-1", Ok(&[FromTo(0, 1)])),
+            ("bytes=007-010", Ok(&[FromTo(7, 10)])),
+            ("bytes=0-18446744073709551615", Ok(&[FromTo(0, u64::MAX)])),
+            ("bytes=18446744073709551615-", Ok(&[From(u64::MAX)])),
+            ("bytes=0-18446744073709551616", Err(RangeError::Malformed)),
+            ("bytes=-18446744073709551616", Err(RangeError::Malformed)),
+            ("bytes=99999999999999999999999-", Err(RangeError::Malformed)),
+            ("bytes=5-4", Err(RangeError::Malformed)),
+            ("bytes=0-1,5-4", Err(RangeError::Malformed)),
+            ("bytes=-", Err(RangeError::Malformed)),
+            ("bytes=", Err(RangeError::Malformed)),
+            ("bytes=,", Err(RangeError::Malformed)),
+            ("bytes=1", Err(RangeError::Malformed)),
+            ("bytes=a-b", Err(RangeError::Malformed)),
+            ("bytes=+1-2", Err(RangeError::Malformed)),
+            ("bytes=1-+2", Err(RangeError::Malformed)),
+            ("bytes=1 - 2", Err(RangeError::Malformed)),
+            ("bytes=1-2-3", Err(RangeError::Malformed)),
+            ("bytes=--1", Err(RangeError::Malformed)),
+            ("bytes 0-1", Err(RangeError::Malformed)),
+            ("", Err(RangeError::Malformed)),
+            ("0-1", Err(RangeError::Malformed)),
+            ("items=0-1", Err(RangeError::Unit)),
+            ("bytes2=0-1", Err(RangeError::Unit)),
+            ("=0-1", Err(RangeError::Unit)),
+            ("items=a-b", Err(RangeError::Unit)),
+        ];
+
+        for (value, expected) in cases {
+            assert_eq!(parse_range(value).as_deref().map_err(|e| *e), *expected, "{value:?}");
+        }
+    }
+
+    #[test]
+    #[allow(clippy::type_complexity)]
+    fn range_resolve() {
+        let cases: &[(&[RangeSpec], u64, Result<&[(u64, u64)], RangeError>)] = &[
+            (&[FromTo(0, 499)], 1000, Ok(&[(0, 499)])),
+            // last position is clamped to the end.
+            (&[FromTo(500, 5000)], 1000, Ok(&[(500, 999)])),
+            (&[FromTo(0, u64::MAX)], 1000, Ok(&[(0, 999)])),
+            (&[From(999)], 1000, Ok(&[(999, 999)])),
+            (&[Suffix(100)], 1000, Ok(&[(900, 999)])),
+            // suffix longer than representation selects all of it.
+            (&[Suffix(5000)], 1000, Ok(&[(0, 999)])),
+            (&[Suffix(u64::MAX)], 1000, Ok(&[(0, 999)])),
+            (&[Suffix(u64::MAX)], u64::MAX, Ok(&[(0, u64::MAX - 1)])),
+            (&[From(u64::MAX - 1)], u64::MAX, Ok(&[(u64::MAX - 1, u64::MAX - 1)])),
+            // unsatisfiable ranges.
+            (&[From(1000)], 1000, Err(RangeError::Unsatisfiable)),
+            (&[FromTo(1000, 2000)], 1000, Err(RangeError::Unsatisfiable)),
+            (&[Suffix(0)], 1000, Err(RangeError::Unsatisfiable)),
+            (&[From(0)], 0, Err(RangeError::Unsatisfiable)),
+            (&[Suffix(1)], 0, Err(RangeError::Unsatisfiable)),
+            (&[From(u64::MAX)], u64::MAX, Err(RangeError::Unsatisfiable)),
+            // unsatisfiable ranges are skipped.
+            (&[From(2000), FromTo(0, 9)], 1000, Ok(&[(0, 9)])),
+            // sorted, overlapping and adjacent ranges are coalesced.
+            (&[FromTo(500, 599), FromTo(0, 99)], 1000, Ok(&[(0, 99), (500, 599)])),
+            (&[FromTo(0, 500), FromTo(400, 999)], 1000, Ok(&[(0, 999)])),
+            (&[FromTo(0, 499), FromTo(500, 999)], 1000, Ok(&[(0, 999)])),
+            (&[FromTo(0, 498), FromTo(500, 999)], 1000, Ok(&[(0, 498), (500, 999)])),
+            (&[FromTo(0, 999), FromTo(10, 20)], 1000, Ok(&[(0, 999)])),
+            (&[Suffix(10), From(995), FromTo(0, 0)], 1000, Ok(&[(0, 0), (990, 999)])),
+            (&[FromTo(0, 0), FromTo(0, 0)], 1, Ok(&[(0, 0)])),
+        ];
+
+        for (specs, len, expected) in cases {
+            assert_eq!(resolve_ranges(specs, *len).as_deref().map_err(|e| *e), *expected, "{specs:?} {len}");
+        }
+    }
+
+    #[test]
+    fn range_display() {
+        for value in ["0-499", "500-", "-500", "0-18446744073709551615"] {
+            let spec = parse_range(&format!("bytes={value}")).unwrap();
+            assert_eq!(spec[0].to_string(), value);
+        }
+    }
+
+    #[test]
+    fn content_range() {
+        let bytes = |first, last, complete| ContentRange::Bytes { first, last, complete };
+
+        let cases: &[(&str, Result<ContentRange, RangeError>)] = &[
+            ("bytes 0-499/1234", Ok(bytes(0, 499, Some(1234)))),
+            ("bytes 500-1233/1234", Ok(bytes(500, 1233, Some(1234)))),
+            ("bytes 0-0/1", Ok(bytes(0, 0, Some(1)))),
+            ("bytes 42-1233/*", Ok(bytes(42, 1233, None))),
+            ("bytes */1234", Ok(ContentRange::Unsatisfied(1234))),
+            ("bytes */0", Ok(ContentRange::Unsatisfied(0))),
+            ("BYTES 0-1/2", Ok(bytes(0, 1, Some(2)))),
+            ("  bytes 0-1/2  ", Ok(bytes(0, 1, Some(2)))),
+            (
+                "bytes 0-18446744073709551614/18446744073709551615",
+                Ok(bytes(0, u64::MAX - 1, Some(u64::MAX))),
+            ),
+            ("bytes 0-18446744073709551615/*", Ok(bytes(0, u64::MAX, None))),
+            // complete length must be larger than last position.
+            (
+                "bytes 0-18446744073709551615/18446744073709551615",
+                Err(RangeError::Malformed),
+            ),
+            ("bytes 0-499/499", Err(RangeError::Malformed)),
+            ("bytes 0-499/0", Err(RangeError::Malformed)),
+            ("bytes 500-499/1234", Err(RangeError::Malformed)),
+            ("bytes 0-18446744073709551616/*", Err(RangeError::Malformed)),
+            ("bytes 0-1/18446744073709551616", Err(RangeError::Malformed)),
+            ("bytes */*", Err(RangeError::Malformed)),
+            ("bytes 0-/10", Err(RangeError::Malformed)),
+            ("bytes -1/10", Err(RangeError::Malformed)),
+            ("bytes 0-1", Err(RangeError::Malformed)),
+            ("bytes 0-1/", Err(RangeError::Malformed)),
+            ("bytes 0-1/2/3", Err(RangeError::Malformed)),
+            ("bytes 0 - 1/2", Err(RangeError::Malformed)),
+            ("bytes=0-1/2", Err(RangeError::Malformed)),
+            ("bytes  0-1/2", Err(RangeError::Malformed)),
+            ("bytes", Err(RangeError::Malformed)),
+            ("", Err(RangeError::Malformed)),
+            ("items 0-1/2", Err(RangeError::Unit)),
+            ("items garbage", Err(RangeError::Unit)),
+        ];
+
+        for (value, expected) in cases {
+            assert_eq!(ContentRange::parse(value), *expected, "{value:?}");
+        }
+    }
+
+    #[test]
+    fn content_range_len_display() {
+        let range = ContentRange::parse("bytes 10-19/100").unwrap();
+        assert_eq!(range.len(), Some(10));
+        assert_eq!(range.to_string(), "bytes 10-19/100");
+
+        let range = ContentRange::parse("bytes 0-18446744073709551615/*").unwrap();
+        assert_eq!(range.len(), None);
+        assert_eq!(range.to_string(), "bytes 0-18446744073709551615/*");
+
+        let range = ContentRange::Unsatisfied(100);
+        assert_eq!(range.len(), None);
+        assert_eq!(range.to_string(), "bytes */100");
+    }
+}
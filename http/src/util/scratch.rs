@@ -0,0 +1,307 @@
+//! Per request scratch arena for transient allocations.
+//!
+//! See [Scratch] for detail.
+
+use core::{fmt, mem, str::Utf8Error};
+
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use crate::bytes::BytesMut;
+
+use super::percent;
+
+/// Arena memory over this size is released when [Scratch] is reset instead of being kept for the
+/// next request.
+pub const MAX_RETAINED_CAPACITY: usize = 64 * 1024;
+
+/// Reusable scratch memory for temporary values produced while handling a request. (e.g.
+/// percent-decoded path params, collected request body before it's deserialized)
+///
+/// Http/1 connection keeps one Scratch and inserts a handle of it into request's
+/// [Extensions](crate::http::Extensions) when enabled by
+/// [HttpServiceConfig::request_scratch](crate::config::HttpServiceConfig::request_scratch). The
+/// arena is reset when next request of the connection is decoded so it's memory is reused across
+/// requests instead of allocated and freed for every one of them.
+///
+/// Values allocated in arena are accessed through [ScratchStr] and [ScratchBuf] handles. A handle
+/// is valid until the Scratch is reset and it must not be used after that.
+///
+/// # Examples:
+/// ```rust
+/// use xitca_http::util::scratch::Scratch;
+///
+/// let scratch = Scratch::new();
+///
+/// let mut arena = scratch.arena().unwrap();
+/// let name = arena.decode_utf8("foo%20bar").unwrap();
+/// let buf = arena.alloc_buf(3);
+/// arena.buf_mut(buf).copy_from_slice(b"996");
+///
+/// assert_eq!(arena.str(name), "foo bar");
+/// assert_eq!(arena.buf(buf), b"996");
+/// ```
+#[derive(Clone, Default)]
+pub struct Scratch(Arc<Mutex<Arena>>);
+
+#[derive(Default)]
+struct Arena {
+    strs: String,
+    bufs: Vec<u8>,
+    bytes: BytesMut,
+    generation: usize,
+}
+
+impl fmt::Debug for Scratch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Scratch").finish_non_exhaustive()
+    }
+}
+
+impl Scratch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Borrow the arena for allocating and accessing values.
+    ///
+    /// Return None when the arena is already borrowed. Caller is expected to fall back to heap
+    /// allocation in that case.
+    pub fn arena(&self) -> Option<ScratchArena<'_>> {
+        self.0.try_lock().ok().map(ScratchArena)
+    }
+
+    /// Take the pooled [BytesMut] out of Scratch. It's empty and keeps capacity from previous use.
+    ///
+    /// A new BytesMut is returned when the arena is borrowed or the pooled one is already taken.
+    /// Use [Scratch::put_bytes] to return it to the pool.
+    pub fn take_bytes(&self) -> BytesMut {
+        self.0
+            .try_lock()
+            .map(|mut arena| mem::take(&mut arena.bytes))
+            .unwrap_or_default()
+    }
+
+    /// Return [BytesMut] taken from [Scratch::take_bytes] to the pool for reuse.
+    pub fn put_bytes(&self, mut bytes: BytesMut) {
+        if let Ok(mut arena) = self.0.try_lock() {
+            if bytes.capacity() > arena.bytes.capacity() {
+                bytes.clear();
+                arena.bytes = bytes;
+            }
+        }
+    }
+
+    /// Reset arena and invalidate all handles allocated from it.
+    ///
+    /// Return false when the arena is borrowed and can not be reset.
+    pub fn try_reset(&self) -> bool {
+        let Ok(mut arena) = self.0.try_lock() else {
+            return false;
+        };
+
+        arena.generation = arena.generation.wrapping_add(1);
+
+        if arena.strs.capacity() > MAX_RETAINED_CAPACITY {
+            arena.strs = String::new();
+        }
+        arena.strs.clear();
+
+        if arena.bufs.capacity() > MAX_RETAINED_CAPACITY {
+            arena.bufs = Vec::new();
+        }
+        arena.bufs.clear();
+
+        if arena.bytes.capacity() > MAX_RETAINED_CAPACITY {
+            arena.bytes = BytesMut::new();
+        }
+        arena.bytes.clear();
+
+        true
+    }
+}
+
+/// Borrowed arena of [Scratch]. Other users of the same Scratch would fall back to heap
+/// allocation while it's alive so it should be dropped as soon as possible.
+pub struct ScratchArena<'a>(MutexGuard<'a, Arena>);
+
+/// Handle of string allocated in [ScratchArena].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ScratchStr {
+    start: usize,
+    end: usize,
+    generation: usize,
+}
+
+/// Handle of bytes buffer allocated in [ScratchArena].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ScratchBuf {
+    start: usize,
+    end: usize,
+    generation: usize,
+}
+
+impl ScratchArena<'_> {
+    /// Copy given string into arena.
+    pub fn alloc_str(&mut self, s: &str) -> ScratchStr {
+        let start = self.0.strs.len();
+        self.0.strs.push_str(s);
+        self.str_handle(start)
+    }
+
+    /// Allocate a zeroed bytes buffer with given length in arena.
+    pub fn alloc_buf(&mut self, len: usize) -> ScratchBuf {
+        let start = self.0.bufs.len();
+        self.0.bufs.resize(start + len, 0);
+        ScratchBuf {
+            start,
+            end: self.0.bufs.len(),
+            generation: self.0.generation,
+        }
+    }
+
+    /// Percent-decode given string into arena and validate the output as utf-8.
+    ///
+    /// See [percent::decode_utf8] for detail.
+    pub fn decode_utf8(&mut self, input: &str) -> Result<ScratchStr, Utf8Error> {
+        self.decode(input, false)
+    }
+
+    /// Percent-decode given part of urlencoded form or query string into arena. `+` is decoded as
+    /// space and invalid utf-8 sequence is replaced.
+    ///
+    /// See [percent::query_pairs] for detail.
+    pub fn decode_form(&mut self, input: &str) -> ScratchStr {
+        match self.decode(input, true) {
+            Ok(s) => s,
+            Err(_) => {
+                let Arena { strs, bufs, .. } = &mut *self.0;
+                let len = bufs.len();
+                percent::decode_extend(input.as_bytes(), true, bufs);
+                let start = strs.len();
+                strs.push_str(&String::from_utf8_lossy(&bufs[len..]));
+                bufs.truncate(len);
+                self.str_handle(start)
+            }
+        }
+    }
+
+    /// Access string allocated in arena.
+    ///
+    /// # Panics:
+    ///
+    /// When the arena is reset after the handle's allocation. Handle must be allocated from the
+    /// same [Scratch]. Handle from another one is not detected and it can point to unrelated content
+    /// or out of range of arena.
+    pub fn str(&self, handle: ScratchStr) -> &str {
+        self.check(handle.generation);
+        &self.0.strs[handle.start..handle.end]
+    }
+
+    /// Access bytes buffer allocated in arena.
+    ///
+    /// # Panics:
+    ///
+    /// See [ScratchArena::str].
+    pub fn buf(&self, handle: ScratchBuf) -> &[u8] {
+        self.check(handle.generation);
+        &self.0.bufs[handle.start..handle.end]
+    }
+
+    /// Mutably access bytes buffer allocated in arena.
+    ///
+    /// # Panics:
+    ///
+    /// See [ScratchArena::str].
+    pub fn buf_mut(&mut self, handle: ScratchBuf) -> &mut [u8] {
+        self.check(handle.generation);
+        &mut self.0.bufs[handle.start..handle.end]
+    }
+
+    fn decode(&mut self, input: &str, plus_as_space: bool) -> Result<ScratchStr, Utf8Error> {
+        let Arena { strs, bufs, .. } = &mut *self.0;
+
+        // decode to the tail of bufs as temporary storage and move the validated string to strs.
+        let len = bufs.len();
+        percent::decode_extend(input.as_bytes(), plus_as_space, bufs);
+        let start = strs.len();
+        let res = core::str::from_utf8(&bufs[len..]).map(|s| strs.push_str(s));
+        bufs.truncate(len);
+
+        res.map(|_| self.str_handle(start))
+    }
+
+    fn str_handle(&self, start: usize) -> ScratchStr {
+        ScratchStr {
+            start,
+            end: self.0.strs.len(),
+            generation: self.0.generation,
+        }
+    }
+
+    fn check(&self, generation: usize) {
+        assert_eq!(
+            generation, self.0.generation,
+            "scratch handle used after arena is reset"
+        );
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn alloc() {
+        let scratch = Scratch::new();
+        let mut arena = scratch.arena().unwrap();
+
+        let a = arena.alloc_str("996");
+        let b = arena.decode_utf8("/%E4%BD%A0/%2F").unwrap();
+        let c = arena.decode_form("foo+bar%21");
+        let d = arena.decode_form("%C0%AF");
+        let buf = arena.alloc_buf(4);
+        arena.buf_mut(buf)[..2].copy_from_slice(b"25");
+
+        assert!(arena.decode_utf8("%C0%AF").is_err());
+
+        assert_eq!(arena.str(a), "996");
+        assert_eq!(arena.str(b), "/你//");
+        assert_eq!(arena.str(c), "foo bar!");
+        assert_eq!(arena.str(d), "\u{FFFD}\u{FFFD}");
+        assert_eq!(arena.buf(buf), b"25\0\0");
+
+        // arena can only be borrowed once at a time.
+        assert!(scratch.arena().is_none());
+        assert!(!scratch.try_reset());
+    }
+
+    #[test]
+    fn reuse() {
+        let scratch = Scratch::new();
+
+        let mut bytes = scratch.take_bytes();
+        bytes.extend_from_slice(&[0; 1024]);
+        let ptr = bytes.as_ptr();
+        scratch.put_bytes(bytes);
+
+        assert!(scratch.try_reset());
+
+        let bytes = scratch.take_bytes();
+        assert!(bytes.is_empty());
+        assert_eq!(bytes.as_ptr(), ptr);
+
+        // oversized arena memory is released on reset.
+        scratch.arena().unwrap().alloc_buf(MAX_RETAINED_CAPACITY + 1);
+        assert!(scratch.try_reset());
+        assert_eq!(scratch.arena().unwrap().0.bufs.capacity(), 0);
+    }
+
+    #[test]
+    #[should_panic]
+    fn handle_after_reset() {
+        let scratch = Scratch::new();
+        let a = scratch.arena().unwrap().alloc_str("996");
+        assert!(scratch.try_reset());
+        scratch.arena().unwrap().str(a);
+    }
+}
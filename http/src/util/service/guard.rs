@@ -0,0 +1,221 @@
+//! Predicate based request filtering for [Route](super::route::Route).
+//!
+//! Guards are evaluated after request path and method are matched. A request rejected by guard
+//! would fall through to the next registration of the same path.
+//!
+//! # Examples:
+//! ```rust
+//! # use std::convert::Infallible;
+//! # use xitca_service::fn_service;
+//! # use xitca_http::{
+//! #   http::{Request, Response},
+//! #   util::service::{guard::{header_eq, not, Head}, route::get}
+//! # };
+//! # async fn handler(_: Request<()>) -> Result<Response<()>, Infallible> { todo!() }
+//! // route matching GET request with "x-api-version: 2" header.
+//! let route = get(fn_service(handler)).guard(header_eq("x-api-version", "2"));
+//!
+//! // closure can be used as guard.
+//! let route = get(fn_service(handler)).guard(not(|head: &Head<'_>| head.uri().query().is_some()));
+//! ```
+
+use crate::http::{
+    header::{HeaderName, HeaderValue},
+    BorrowReq, Extensions, HeaderMap, Method, Uri,
+};
+
+/// Trait for checking if a request can be handled by guarded route.
+pub trait Guard {
+    fn check(&self, head: &Head<'_>) -> bool;
+}
+
+// default guard of route that accept all requests.
+impl Guard for () {
+    #[inline]
+    fn check(&self, _: &Head<'_>) -> bool {
+        true
+    }
+}
+
+impl<F> Guard for F
+where
+    F: Fn(&Head<'_>) -> bool,
+{
+    #[inline]
+    fn check(&self, head: &Head<'_>) -> bool {
+        (self)(head)
+    }
+}
+
+/// Read only view of request head passed to [Guard].
+pub struct Head<'a> {
+    method: &'a Method,
+    uri: &'a Uri,
+    headers: &'a HeaderMap,
+    extensions: &'a Extensions,
+}
+
+impl<'a> Head<'a> {
+    /// Construct a view of given request's head.
+    pub fn new<Req>(req: &'a Req) -> Self
+    where
+        Req: BorrowReq<Method> + BorrowReq<Uri> + BorrowReq<HeaderMap> + BorrowReq<Extensions>,
+    {
+        Self {
+            method: req.borrow(),
+            uri: req.borrow(),
+            headers: req.borrow(),
+            extensions: req.borrow(),
+        }
+    }
+
+    #[inline]
+    pub fn method(&self) -> &'a Method {
+        self.method
+    }
+
+    #[inline]
+    pub fn uri(&self) -> &'a Uri {
+        self.uri
+    }
+
+    #[inline]
+    pub fn headers(&self) -> &'a HeaderMap {
+        self.headers
+    }
+
+    #[inline]
+    pub fn extensions(&self) -> &'a Extensions {
+        self.extensions
+    }
+}
+
+/// Guard passes when request contains a header with given name and value.
+///
+/// # Panics:
+///
+/// When given name or value is not valid for http header.
+pub fn header_eq<N, V>(name: N, value: V) -> HeaderEq
+where
+    N: TryInto<HeaderName>,
+    N::Error: core::fmt::Debug,
+    V: TryInto<HeaderValue>,
+    V::Error: core::fmt::Debug,
+{
+    HeaderEq {
+        name: name.try_into().expect("invalid header name"),
+        value: value.try_into().expect("invalid header value"),
+    }
+}
+
+#[derive(Clone)]
+pub struct HeaderEq {
+    name: HeaderName,
+    value: HeaderValue,
+}
+
+impl Guard for HeaderEq {
+    fn check(&self, head: &Head<'_>) -> bool {
+        head.headers().get_all(&self.name).iter().any(|v| v == self.value)
+    }
+}
+
+/// Guard passes when both given guards pass.
+pub fn and<A, B>(a: A, b: B) -> And<A, B>
+where
+    A: Guard,
+    B: Guard,
+{
+    And(a, b)
+}
+
+#[derive(Clone)]
+pub struct And<A, B>(A, B);
+
+impl<A, B> Guard for And<A, B>
+where
+    A: Guard,
+    B: Guard,
+{
+    #[inline]
+    fn check(&self, head: &Head<'_>) -> bool {
+        self.0.check(head) && self.1.check(head)
+    }
+}
+
+/// Guard passes when any of given guards passes.
+pub fn or<A, B>(a: A, b: B) -> Or<A, B>
+where
+    A: Guard,
+    B: Guard,
+{
+    Or(a, b)
+}
+
+#[derive(Clone)]
+pub struct Or<A, B>(A, B);
+
+impl<A, B> Guard for Or<A, B>
+where
+    A: Guard,
+    B: Guard,
+{
+    #[inline]
+    fn check(&self, head: &Head<'_>) -> bool {
+        self.0.check(head) || self.1.check(head)
+    }
+}
+
+/// Guard passes when given guard rejects.
+pub fn not<G>(guard: G) -> Not<G>
+where
+    G: Guard,
+{
+    Not(guard)
+}
+
+#[derive(Clone)]
+pub struct Not<G>(G);
+
+impl<G> Guard for Not<G>
+where
+    G: Guard,
+{
+    #[inline]
+    fn check(&self, head: &Head<'_>) -> bool {
+        !self.0.check(head)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::http::{header::CONTENT_TYPE, Request};
+
+    use super::*;
+
+    #[test]
+    fn combinator() {
+        let req = Request::builder()
+            .header("x-api-version", "1")
+            .header("x-api-version", "2")
+            .header(CONTENT_TYPE, "application/json")
+            .body(())
+            .unwrap();
+        let head = Head::new(&req);
+
+        let v2 = || header_eq("x-api-version", "2");
+        let v3 = || header_eq("x-api-version", "3");
+        let json = || header_eq(CONTENT_TYPE, "application/json");
+        let get = |head: &Head<'_>| head.method() == Method::GET;
+
+        assert!(().check(&head));
+        assert!(v2().check(&head));
+        assert!(!v3().check(&head));
+        assert!(get.check(&head));
+        assert!(and(v2(), json()).check(&head));
+        assert!(!and(v3(), json()).check(&head));
+        assert!(or(v3(), json()).check(&head));
+        assert!(!or(v3(), not(get)).check(&head));
+        assert!(not(v3()).check(&head));
+    }
+}
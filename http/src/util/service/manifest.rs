@@ -0,0 +1,194 @@
+use core::{any::type_name, fmt};
+
+use std::borrow::Cow;
+
+/// Layers of service pipeline ordered from the outermost one receiving request first to the
+/// innermost one. See [Describe].
+///
+/// It's collected from service factories when they are composed and has no effect on services
+/// built from them. Display of it prints layers as an indented tree.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Manifest {
+    layers: Vec<Layer>,
+}
+
+impl Manifest {
+    pub const fn new() -> Self {
+        Self { layers: Vec::new() }
+    }
+
+    /// Append layer as the inner one of existing layers.
+    pub fn push(&mut self, layer: Layer) {
+        self.layers.push(layer);
+    }
+
+    pub fn layers(&self) -> &[Layer] {
+        &self.layers
+    }
+
+    // prefix path of routes in all nested routers.
+    pub(super) fn prefix(&mut self, prefix: &str) {
+        for layer in self.layers.iter_mut() {
+            let is_router = layer.kind == LayerKind::Router;
+            for (path, manifest) in layer.routes.iter_mut() {
+                if is_router && path != super::router::MatchedRoute::NOT_FOUND {
+                    *path = Cow::Owned(format!("{prefix}{path}"));
+                }
+                manifest.prefix(prefix);
+            }
+        }
+    }
+
+    fn fmt_indent(&self, f: &mut fmt::Formatter<'_>, indent: usize) -> fmt::Result {
+        for layer in self.layers.iter() {
+            writeln!(f, "{:indent$}{} ({})", "", layer.name, layer.kind)?;
+            for (path, manifest) in layer.routes.iter() {
+                writeln!(f, "{:indent$}  {path}", "")?;
+                manifest.fmt_indent(f, indent + 4)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Manifest {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_indent(f, 0)
+    }
+}
+
+/// Trait for describing layers of service factory into [Manifest].
+///
+/// Service factories registered to router must implement it. The default implementation describes
+/// self as a single [LayerKind::Service] layer named after type of self and it's enough for
+/// factories that do not compose other factories.
+///
+/// # Examples:
+/// ```rust
+/// # use xitca_http::util::service::router::{Describe, Layer, LayerKind, Manifest};
+/// struct Index;
+///
+/// // described as "Index (service)".
+/// impl Describe for Index {}
+///
+/// struct Enclosed<F>(F);
+///
+/// // factory wrapping another one describes itself as outer layer and then the inner one.
+/// impl<F: Describe> Describe for Enclosed<F> {
+///     fn describe(&self, manifest: &mut Manifest) {
+///         manifest.push(Layer::new("Enclosed", LayerKind::Middleware));
+///         self.0.describe(manifest);
+///     }
+/// }
+/// ```
+pub trait Describe {
+    /// push layers of self to [Manifest] from the outermost to the innermost.
+    fn describe(&self, manifest: &mut Manifest) {
+        manifest.push(Layer::of::<Self>(LayerKind::Service));
+    }
+}
+
+/// A layer of [Manifest].
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Layer {
+    name: Cow<'static, str>,
+    kind: LayerKind,
+    routes: Vec<(Cow<'static, str>, Manifest)>,
+}
+
+impl Layer {
+    pub fn new(name: impl Into<Cow<'static, str>>, kind: LayerKind) -> Self {
+        Self {
+            name: name.into(),
+            kind,
+            routes: Vec::new(),
+        }
+    }
+
+    /// Construct layer named after given type without it's module path and generic parameters.
+    /// e.g. `Logger` for `xitca_web::middleware::Logger`
+    pub fn of<T: ?Sized>(kind: LayerKind) -> Self {
+        let name = type_name::<T>();
+        let name = name.split('<').next().unwrap_or(name);
+        let name = name.rsplit("::").next().unwrap_or(name);
+        Self::new(name, kind)
+    }
+
+    pub(super) fn router(routes: Vec<(Cow<'static, str>, Manifest)>) -> Self {
+        Self {
+            name: Cow::Borrowed("Router"),
+            kind: LayerKind::Router,
+            routes,
+        }
+    }
+
+    // route layer named after all of it's methods.
+    pub(super) fn route(routes: Vec<(Cow<'static, str>, Manifest)>) -> Self {
+        let name = routes.iter().map(|(methods, _)| methods.as_ref()).collect::<Vec<_>>();
+        Self {
+            name: Cow::Owned(name.join("|")),
+            kind: LayerKind::Route,
+            routes,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn kind(&self) -> LayerKind {
+        self.kind
+    }
+
+    /// Path patterns and manifests of routes when layer is a router. Routes are in the order of
+    /// registration and a router with default service has it at last with
+    /// [MatchedRoute::NOT_FOUND](super::router::MatchedRoute::NOT_FOUND) as path.
+    ///
+    /// Methods joined with `|` and manifests of services handling them when layer is a route.
+    pub fn routes(&self) -> &[(Cow<'static, str>, Manifest)] {
+        &self.routes
+    }
+}
+
+/// Role of [Layer] in service pipeline.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum LayerKind {
+    /// Adapter between http protocol layer and service pipeline.
+    Protocol,
+    /// Middleware type enclosing inner layers.
+    Middleware,
+    /// Async function enclosing inner layers.
+    FnMiddleware,
+    /// Router dispatching request to one of it's routes by path.
+    Router,
+    /// Route dispatching request to it's services by method. Named after it's methods.
+    Route,
+    /// Service handling request.
+    Service,
+}
+
+impl fmt::Display for LayerKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match *self {
+            Self::Protocol => "protocol",
+            Self::Middleware => "middleware",
+            Self::FnMiddleware => "fn middleware",
+            Self::Router => "router",
+            Self::Route => "route",
+            Self::Service => "service",
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    struct Generic<T>(T);
+
+    #[test]
+    fn type_name() {
+        assert_eq!(Layer::of::<Manifest>(LayerKind::Service).name(), "Manifest");
+        assert_eq!(Layer::of::<Generic<Manifest>>(LayerKind::Service).name(), "Generic");
+    }
+}
@@ -1,7 +1,9 @@
+pub mod guard;
 pub mod handler;
 pub mod route;
 
 mod context_priv;
+mod manifest;
 mod router_priv;
 
 pub mod context {
@@ -9,7 +11,10 @@ pub mod context {
 }
 
 pub mod router {
-    pub use super::router_priv::{GenericRouter, MatchError, Params, PathGen, Router, RouterError};
+    pub use super::manifest::{Describe, Layer, LayerKind, Manifest};
+    pub use super::router_priv::{
+        GenericRouter, MatchError, MatchedRoute, Params, PathGen, RouteConflict, Router, RouterError,
+    };
 }
 
 pub use router_priv::{GenericRouter, Router, RouterError};
@@ -1,14 +1,41 @@
 use core::{fmt, future::Future};
 
-use std::error;
+use std::{borrow::Cow, error};
 
-use xitca_service::{pipeline::PipelineE, ready::ReadyService, Service};
+use xitca_service::{pipeline::PipelineE, ready::ReadyService, AsyncClosure, Service};
 
-use crate::http::{BorrowReq, Method};
+use crate::http::{BorrowReq, Extensions, HeaderMap, Method, Uri};
+
+use super::{
+    guard::{Guard, Head},
+    manifest::{Describe, Layer, LayerKind, Manifest},
+};
 
 mod next {
+    use std::borrow::Cow;
+
+    use super::super::manifest::Manifest;
+
     pub struct Exist<S>(pub S);
     pub struct Empty;
+
+    // collect methods and manifests of route chain for it's description.
+    pub trait Routes {
+        fn routes(&self, routes: &mut Vec<(Cow<'static, str>, Manifest)>);
+    }
+
+    impl Routes for Empty {
+        fn routes(&self, _: &mut Vec<(Cow<'static, str>, Manifest)>) {}
+    }
+
+    impl<S> Routes for Exist<S>
+    where
+        S: Routes,
+    {
+        fn routes(&self, routes: &mut Vec<(Cow<'static, str>, Manifest)>) {
+            self.0.routes(routes)
+        }
+    }
 }
 
 macro_rules! method {
@@ -29,12 +56,41 @@ method!(connect, CONNECT);
 method!(patch, PATCH);
 method!(trace, TRACE);
 
-pub struct Route<R, N, const M: usize> {
+pub struct Route<R, N, const M: usize, G = ()> {
     methods: [Method; M],
     route: R,
+    guard: G,
+    guarded: bool,
+    pub(super) name: Option<&'static str>,
     next: N,
 }
 
+impl<R, N, G, const M: usize> next::Routes for Route<R, N, M, G>
+where
+    R: Describe,
+    N: next::Routes,
+{
+    fn routes(&self, routes: &mut Vec<(Cow<'static, str>, Manifest)>) {
+        let methods = self.methods.iter().map(Method::as_str).collect::<Vec<_>>();
+        let mut manifest = Manifest::new();
+        self.route.describe(&mut manifest);
+        routes.push((Cow::Owned(methods.join("|")), manifest));
+        self.next.routes(routes)
+    }
+}
+
+impl<R, N, G, const M: usize> Describe for Route<R, N, M, G>
+where
+    R: Describe,
+    N: next::Routes,
+{
+    fn describe(&self, manifest: &mut Manifest) {
+        let mut routes = Vec::new();
+        next::Routes::routes(self, &mut routes);
+        manifest.push(Layer::route(routes));
+    }
+}
+
 impl<const N: usize> Route<(), next::Empty, N> {
     pub const fn new(methods: [Method; N]) -> Self {
         assert!(N > 0, "Route method can not be empty");
@@ -45,6 +101,9 @@ impl<const N: usize> Route<(), next::Empty, N> {
         Route {
             methods: self.methods,
             route,
+            guard: self.guard,
+            guarded: self.guarded,
+            name: self.name,
             next: self.next,
         }
     }
@@ -53,37 +112,143 @@ impl<const N: usize> Route<(), next::Empty, N> {
         Route {
             methods,
             route,
+            guard: (),
+            guarded: false,
+            name: None,
             next: next::Empty,
         }
     }
 }
 
+impl<R, const M: usize> Route<R, next::Empty, M> {
+    /// Add a [Guard] to route. Guard is checked after request method is matched and a rejected
+    /// request would fall through to the routes added after this one with [Route::next] or
+    /// the method shortcuts.
+    ///
+    /// A guarded route can overlap methods with the routes added after it.
+    ///
+    /// # Examples:
+    /// ```rust
+    /// # use std::convert::Infallible;
+    /// # use xitca_service::fn_service;
+    /// # use xitca_http::{
+    /// #   http::{Request, Response},
+    /// #   util::service::{guard::header_eq, route::get}
+    /// # };
+    /// # async fn v1(_: Request<()>) -> Result<Response<()>, Infallible> { todo!() }
+    /// # async fn v2(_: Request<()>) -> Result<Response<()>, Infallible> { todo!() }
+    /// // GET request with "x-api-version: 2" header goes to v2 and all the others go to v1.
+    /// let route = get(fn_service(v2))
+    ///     .guard(header_eq("x-api-version", "2"))
+    ///     .next(get(fn_service(v1)));
+    /// ```
+    pub fn guard<G>(self, guard: G) -> Route<R, next::Empty, M, G>
+    where
+        G: Guard,
+    {
+        Route {
+            methods: self.methods,
+            route: self.route,
+            guard,
+            guarded: true,
+            name: self.name,
+            next: self.next,
+        }
+    }
+}
+
+impl<R, const M: usize, G> Route<R, next::Empty, M, G> {
+    /// Transform request before it's passed to route's service. It enables routes of the same
+    /// router to receive different request types while the router itself accepts a uniform one.
+    /// (e.g. a route receiving request body collected into bytes)
+    ///
+    /// Transform is an async function receiving request and returning the transformed one.
+    /// It's error is converted to the error type of route's service and returned as
+    /// [RouteError::Second] like the errors of service.
+    ///
+    /// Transform only applies to this route and not the routes added after it with [Route::next]
+    /// or the method shortcuts.
+    ///
+    /// # Examples:
+    /// ```rust
+    /// # use std::convert::Infallible;
+    /// # use xitca_service::fn_service;
+    /// # use xitca_http::{
+    /// #   bytes::Bytes,
+    /// #   error::BodyError,
+    /// #   http::{Request, RequestExt, Response},
+    /// #   util::service::route::post,
+    /// #   RequestBody,
+    /// # };
+    /// # async fn webhook(_: Request<RequestExt<Bytes>>) -> Result<Response<()>, BodyError> { todo!() }
+    /// // collect request body into bytes before it's passed to webhook.
+    /// async fn collect(req: Request<RequestExt<RequestBody>>) -> Result<Request<RequestExt<Bytes>>, BodyError> {
+    ///     // collecting body with a size limit.
+    ///     # todo!()
+    /// }
+    ///
+    /// let route = post(fn_service(webhook)).map_body(collect);
+    /// ```
+    pub fn map_body<T>(self, transform: T) -> Route<MapBody<R, T>, next::Empty, M, G> {
+        Route {
+            methods: self.methods,
+            route: MapBody {
+                route: self.route,
+                transform,
+            },
+            guard: self.guard,
+            guarded: self.guarded,
+            name: self.name,
+            next: self.next,
+        }
+    }
+}
+
 macro_rules! route_method {
     ($method_fn: ident, $method: ident) => {
-        pub fn $method_fn<R1>(self, $method_fn: R1) -> Route<R, next::Exist<Route<R1, N, 1>>, M> {
+        pub fn $method_fn<R1>(self, $method_fn: R1) -> Route<R, next::Exist<Route<R1, N, 1>>, M, G> {
             self.next(Route::new([Method::$method]).route($method_fn))
         }
     };
 }
 
-impl<R, N, const M: usize> Route<R, N, M> {
+impl<R, N, const M: usize, G> Route<R, N, M, G> {
+    /// Assign a name to route. The name is exposed together with matched route pattern by
+    /// [MatchedRoute](super::router::MatchedRoute) after request is routed.
+    ///
+    /// Name must be set on the first route of a chain. Names of routes added by [Route::next]
+    /// or the method shortcuts are ignored.
+    pub fn name(mut self, name: &'static str) -> Self {
+        self.name = Some(name);
+        self
+    }
+
     // TODO is this really the intended behavior? insert `next` between `self` and `self.next`?
-    pub fn next<R1, const M1: usize>(
+    pub fn next<R1, const M1: usize, G1>(
         self,
-        next: Route<R1, next::Empty, M1>,
-    ) -> Route<R, next::Exist<Route<R1, N, M1>>, M> {
-        for m in next.methods.iter() {
-            if self.methods.contains(m) {
-                panic!("{m} method already exists. Route can not contain overlapping methods.");
+        next: Route<R1, next::Empty, M1, G1>,
+    ) -> Route<R, next::Exist<Route<R1, N, M1, G1>>, M, G> {
+        // guarded route can fall through to next route with the same method.
+        if !self.guarded {
+            for m in next.methods.iter() {
+                if self.methods.contains(m) {
+                    panic!("{m} method already exists. Route can not contain overlapping methods.");
+                }
             }
         }
 
         Route {
             methods: self.methods,
             route: self.route,
+            guard: self.guard,
+            guarded: self.guarded,
+            name: self.name,
             next: next::Exist(Route {
                 methods: next.methods,
                 route: next.route,
+                guard: next.guard,
+                guarded: next.guarded,
+                name: None,
                 next: self.next,
             }),
         }
@@ -100,13 +265,14 @@ impl<R, N, const M: usize> Route<R, N, M> {
     route_method!(trace, TRACE);
 }
 
-impl<Arg, R, N, const M: usize> Service<Arg> for Route<R, next::Exist<N>, M>
+impl<Arg, R, N, G, const M: usize> Service<Arg> for Route<R, next::Exist<N>, M, G>
 where
     R: Service<Arg>,
     N: Service<Arg, Error = R::Error>,
+    G: Clone,
     Arg: Clone,
 {
-    type Response = RouteService<R::Response, next::Exist<N::Response>, M>;
+    type Response = RouteService<R::Response, next::Exist<N::Response>, M, G>;
     type Error = R::Error;
     type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where Self: 'f, Arg: 'f;
 
@@ -120,17 +286,19 @@ where
             Ok(RouteService {
                 methods: self.methods.clone(),
                 route,
+                guard: self.guard.clone(),
                 next: next::Exist(next),
             })
         }
     }
 }
 
-impl<Arg, R, const M: usize> Service<Arg> for Route<R, next::Empty, M>
+impl<Arg, R, G, const M: usize> Service<Arg> for Route<R, next::Empty, M, G>
 where
     R: Service<Arg>,
+    G: Clone,
 {
-    type Response = RouteService<R::Response, next::Empty, M>;
+    type Response = RouteService<R::Response, next::Empty, M, G>;
     type Error = R::Error;
     type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where Self: 'f, Arg: 'f;
 
@@ -143,23 +311,26 @@ where
             Ok(RouteService {
                 methods: self.methods.clone(),
                 route,
+                guard: self.guard.clone(),
                 next: next::Empty,
             })
         }
     }
 }
 
-pub struct RouteService<R, N, const M: usize> {
+pub struct RouteService<R, N, const M: usize, G = ()> {
     methods: [Method; M],
     route: R,
+    guard: G,
     next: N,
 }
 
-impl<R, N, Req, E, const M: usize> Service<Req> for RouteService<R, next::Exist<N>, M>
+impl<R, N, G, Req, E, const M: usize> Service<Req> for RouteService<R, next::Exist<N>, M, G>
 where
     R: Service<Req, Error = E>,
     N: Service<Req, Response = R::Response, Error = RouteError<E>>,
-    Req: BorrowReq<Method>,
+    G: Guard,
+    Req: BorrowReq<Method> + BorrowReq<Uri> + BorrowReq<HeaderMap> + BorrowReq<Extensions>,
 {
     type Response = R::Response;
     type Error = RouteError<E>;
@@ -171,14 +342,16 @@ where
         Req: 's,
     {
         async {
-            if self.methods.contains(req.borrow()) {
-                self.route.call(req).await.map_err(RouteError::Second)
-            } else {
+            if !self.methods.contains(req.borrow()) {
                 self.next
                     .0
                     .call(req)
                     .await
                     .map_err(|e| try_append_allowed(e, &self.methods))
+            } else if self.guard.check(&Head::new(&req)) {
+                self.route.call(req).await.map_err(RouteError::Second)
+            } else {
+                self.next.0.call(req).await.map_err(guard_rejected)
             }
         }
     }
@@ -187,16 +360,28 @@ where
 #[cold]
 #[inline(never)]
 fn try_append_allowed<E>(mut e: RouteError<E>, methods: &[Method]) -> RouteError<E> {
-    if let RouteError::First(ref mut e) = e {
+    if let RouteError::First(RouteMatchError::MethodNotAllowed(ref mut e)) = e {
         e.0.extend_from_slice(methods);
     }
     e
 }
 
-impl<R, Req, const M: usize> Service<Req> for RouteService<R, next::Empty, M>
+// request method is matched by a guarded route. method not allowed error from following routes
+// is replaced by guard rejection.
+#[cold]
+#[inline(never)]
+fn guard_rejected<E>(e: RouteError<E>) -> RouteError<E> {
+    match e {
+        RouteError::First(_) => RouteError::First(RouteMatchError::GuardRejected),
+        e => e,
+    }
+}
+
+impl<R, G, Req, const M: usize> Service<Req> for RouteService<R, next::Empty, M, G>
 where
     R: Service<Req>,
-    Req: BorrowReq<Method>,
+    G: Guard,
+    Req: BorrowReq<Method> + BorrowReq<Uri> + BorrowReq<HeaderMap> + BorrowReq<Extensions>,
 {
     type Response = R::Response;
     type Error = RouteError<R::Error>;
@@ -208,18 +393,20 @@ where
         Req: 's,
     {
         async {
-            if self.methods.contains(req.borrow()) {
+            if !self.methods.contains(req.borrow()) {
+                Err(RouteError::First(RouteMatchError::MethodNotAllowed(MethodNotAllowed(
+                    self.methods.iter().cloned().collect(),
+                ))))
+            } else if self.guard.check(&Head::new(&req)) {
                 self.route.call(req).await.map_err(RouteError::Second)
             } else {
-                Err(RouteError::First(MethodNotAllowed(
-                    self.methods.iter().cloned().collect(),
-                )))
+                Err(RouteError::First(RouteMatchError::GuardRejected))
             }
         }
     }
 }
 
-impl<R, N, const M: usize> ReadyService for RouteService<R, N, M> {
+impl<R, N, G, const M: usize> ReadyService for RouteService<R, N, M, G> {
     type Ready = ();
     type Future<'f> = impl Future<Output = Self::Ready> where Self: 'f;
 
@@ -229,10 +416,106 @@ impl<R, N, const M: usize> ReadyService for RouteService<R, N, M> {
     }
 }
 
+/// Service factory transforming request before it's passed to the service produced by inner
+/// factory. See [Route::map_body] for detail.
+pub struct MapBody<R, T> {
+    route: R,
+    transform: T,
+}
+
+// body transform is described as protocol layer wrapping the route service.
+impl<R, T> Describe for MapBody<R, T>
+where
+    R: Describe,
+{
+    fn describe(&self, manifest: &mut Manifest) {
+        manifest.push(Layer::new("map_body", LayerKind::Protocol));
+        self.route.describe(manifest);
+    }
+}
+
+impl<Arg, R, T> Service<Arg> for MapBody<R, T>
+where
+    R: Service<Arg>,
+    T: Clone,
+{
+    type Response = MapBodyService<R::Response, T>;
+    type Error = R::Error;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where Self: 'f, Arg: 'f;
+
+    fn call<'s>(&'s self, arg: Arg) -> Self::Future<'s>
+    where
+        Arg: 's,
+    {
+        async {
+            let service = self.route.call(arg).await?;
+            Ok(MapBodyService {
+                service,
+                transform: self.transform.clone(),
+            })
+        }
+    }
+}
+
+pub struct MapBodyService<S, T> {
+    service: S,
+    transform: T,
+}
+
+impl<S, T, Req, Req1, E> Service<Req> for MapBodyService<S, T>
+where
+    T: AsyncClosure<(Req,), Output = Result<Req1, E>>,
+    S: Service<Req1>,
+    S::Error: From<E>,
+{
+    type Response = S::Response;
+    type Error = S::Error;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where Self: 'f, Req: 'f;
+
+    #[inline]
+    fn call<'s>(&'s self, req: Req) -> Self::Future<'s>
+    where
+        Req: 's,
+    {
+        async {
+            let req = self.transform.call((req,)).await?;
+            self.service.call(req).await
+        }
+    }
+}
+
 /// Error type of Route service.
-/// `First` variant contains [MethodNotAllowed] error.
+/// `First` variant contains [RouteMatchError] error.
 /// `Second` variant contains error returned by the service passed to Route.
-pub type RouteError<E> = PipelineE<MethodNotAllowed, E>;
+pub type RouteError<E> = PipelineE<RouteMatchError, E>;
+
+/// Error type of request not matching any of route.
+pub enum RouteMatchError {
+    /// Request method is not allowed by any route.
+    MethodNotAllowed(MethodNotAllowed),
+    /// Request method is allowed but rejected by [Guard] of all routes with the method.
+    GuardRejected,
+}
+
+impl fmt::Debug for RouteMatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MethodNotAllowed(e) => fmt::Debug::fmt(e, f),
+            Self::GuardRejected => f.write_str("GuardRejected"),
+        }
+    }
+}
+
+impl fmt::Display for RouteMatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MethodNotAllowed(e) => fmt::Display::fmt(e, f),
+            Self::GuardRejected => f.write_str("Request is rejected by route guard"),
+        }
+    }
+}
+
+impl error::Error for RouteMatchError {}
 
 /// Error type of Method not allow for route.
 pub struct MethodNotAllowed(Vec<Method>);
@@ -268,6 +551,7 @@ mod test {
     use crate::{
         body::{RequestBody, ResponseBody},
         http::{Request, Response},
+        util::service::guard::header_eq,
     };
 
     use super::*;
@@ -303,7 +587,45 @@ mod test {
         let mut req = Request::new(RequestBody::None);
         *req.method_mut() = Method::PUT;
         let err = service.call(req).now_or_panic().err().unwrap();
-        assert!(matches!(err, RouteError::First(MethodNotAllowed(_))));
+        assert!(matches!(
+            err,
+            RouteError::First(RouteMatchError::MethodNotAllowed(MethodNotAllowed(_)))
+        ));
+    }
+
+    #[test]
+    fn route_describe() {
+        async fn enclosed<S, Req>(service: &S, req: Req) -> Result<S::Response, S::Error>
+        where
+            S: Service<Req>,
+        {
+            service.call(req).await
+        }
+
+        let route = get(fn_service(index).enclosed_fn(enclosed)).post(fn_service(index));
+
+        let mut manifest = Manifest::new();
+        route.describe(&mut manifest);
+
+        let layers = manifest.layers();
+        assert_eq!(layers.len(), 1);
+        assert_eq!(layers[0].name(), "GET|POST");
+
+        let routes = layers[0]
+            .routes()
+            .iter()
+            .map(|(method, m)| {
+                let layers = m.layers().iter().map(|l| l.kind()).collect::<Vec<_>>();
+                (method.as_ref(), layers)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            routes,
+            [
+                ("GET", vec![LayerKind::FnMiddleware, LayerKind::Service]),
+                ("POST", vec![LayerKind::Service])
+            ]
+        );
     }
 
     #[test]
@@ -323,7 +645,10 @@ mod test {
         let mut req = Request::new(RequestBody::None);
         *req.method_mut() = Method::DELETE;
         let err = service.call(req).now_or_panic().err().unwrap();
-        assert!(matches!(err, RouteError::First(MethodNotAllowed(_))));
+        assert!(matches!(
+            err,
+            RouteError::First(RouteMatchError::MethodNotAllowed(MethodNotAllowed(_)))
+        ));
 
         let mut req = Request::new(RequestBody::None);
         *req.method_mut() = Method::PUT;
@@ -366,7 +691,8 @@ mod test {
         let mut req = Request::new(RequestBody::None);
         *req.method_mut() = Method::DELETE;
 
-        let RouteError::First(e) = service.call(req).now_or_panic().err().unwrap() else {
+        let RouteError::First(RouteMatchError::MethodNotAllowed(e)) = service.call(req).now_or_panic().err().unwrap()
+        else {
             panic!("route does not return error on unallowed method request");
         };
 
@@ -392,6 +718,160 @@ mod test {
         assert_eq!(res.status().as_u16(), 200);
     }
 
+    #[test]
+    fn route_guard() {
+        async fn v2(_: Request<RequestBody>) -> Result<Response<ResponseBody>, Infallible> {
+            Ok(Response::new(ResponseBody::bytes("v2")))
+        }
+
+        let service = get(fn_service(v2))
+            .guard(header_eq("x-api-version", "2"))
+            .next(get(fn_service(index)))
+            .next(post(fn_service(index)).guard(header_eq("x-api-version", "2")))
+            .call(())
+            .now_or_panic()
+            .ok()
+            .unwrap();
+
+        let req = Request::builder()
+            .header("x-api-version", "2")
+            .body(RequestBody::None)
+            .unwrap();
+        let res = service.call(req).now_or_panic().ok().unwrap();
+        assert!(matches!(res.body(), ResponseBody::Bytes { .. }));
+
+        let req = Request::new(RequestBody::None);
+        let res = service.call(req).now_or_panic().ok().unwrap();
+        assert!(matches!(res.body(), ResponseBody::None));
+
+        let mut req = Request::builder()
+            .header("x-api-version", "2")
+            .body(RequestBody::None)
+            .unwrap();
+        *req.method_mut() = Method::POST;
+        service.call(req).now_or_panic().ok().unwrap();
+
+        // guard rejection of all routes with the method is not method not allowed.
+        let mut req = Request::new(RequestBody::None);
+        *req.method_mut() = Method::POST;
+        let err = service.call(req).now_or_panic().err().unwrap();
+        assert!(matches!(err, RouteError::First(RouteMatchError::GuardRejected)));
+
+        let mut req = Request::new(RequestBody::None);
+        *req.method_mut() = Method::PUT;
+        let err = service.call(req).now_or_panic().err().unwrap();
+        assert!(matches!(
+            err,
+            RouteError::First(RouteMatchError::MethodNotAllowed(MethodNotAllowed(_)))
+        ));
+    }
+
+    #[test]
+    fn route_map_body() {
+        use core::{
+            future::poll_fn,
+            pin::{pin, Pin},
+            task::{Context, Poll},
+        };
+
+        use futures_core::Stream;
+
+        use crate::{
+            body::BoxStream,
+            bytes::{Bytes, BytesMut},
+            error::BodyError,
+            http::RequestExt,
+            util::service::{Router, RouterError},
+        };
+
+        const LIMIT: usize = 8;
+
+        // chunks of body yielded one at a time.
+        struct Chunks(Vec<&'static str>);
+
+        impl Stream for Chunks {
+            type Item = Result<Bytes, Infallible>;
+
+            fn poll_next(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+                let this = self.get_mut();
+                Poll::Ready((!this.0.is_empty()).then(|| Ok(Bytes::from_static(this.0.remove(0).as_bytes()))))
+            }
+        }
+
+        async fn collect(req: Request<RequestExt<RequestBody>>) -> Result<Request<RequestExt<Bytes>>, BodyError> {
+            let (parts, ext) = req.into_parts();
+            let (ext, body) = ext.replace_body(());
+            let mut body = pin!(body);
+            let mut buf = BytesMut::new();
+            while let Some(chunk) = poll_fn(|cx| body.as_mut().poll_next(cx)).await {
+                buf.extend_from_slice(&chunk?);
+                if buf.len() > LIMIT {
+                    return Err(BodyError::Overflow { limit: LIMIT });
+                }
+            }
+            Ok(Request::from_parts(parts, ext.map_body(|_| buf.freeze())))
+        }
+
+        async fn collected(req: Request<RequestExt<Bytes>>) -> Result<Response<ResponseBody>, BodyError> {
+            let (_, body) = req.into_body().replace_body(());
+            Ok(Response::new(ResponseBody::bytes(body)))
+        }
+
+        // streaming route receives body chunk by chunk and respond with the number of them.
+        async fn streaming(req: Request<RequestExt<RequestBody>>) -> Result<Response<ResponseBody>, BodyError> {
+            let mut body = pin!(req.into_body());
+            let mut chunks = 0;
+            while let Some(chunk) = poll_fn(|cx| body.as_mut().poll_next(cx)).await {
+                chunk?;
+                chunks += 1;
+            }
+            Ok(Response::new(ResponseBody::bytes(chunks.to_string())))
+        }
+
+        let service = Router::new()
+            .insert("/collected", post(fn_service(collected)).map_body(collect))
+            .insert("/streaming", post(fn_service(streaming)))
+            .call(())
+            .now_or_panic()
+            .unwrap();
+
+        let req = |path: &str, chunks: Vec<&'static str>| {
+            let body = RequestBody::from(BoxStream::new(Chunks(chunks)));
+            let mut req = Request::new(RequestExt::default().map_body(|_: ()| body));
+            *req.method_mut() = Method::POST;
+            *req.uri_mut() = path.parse().unwrap();
+            req
+        };
+
+        let body = |res: Response<ResponseBody>| match res.into_body() {
+            ResponseBody::Bytes { bytes, .. } => bytes,
+            _ => panic!("response body must be bytes"),
+        };
+
+        let res = service
+            .call(req("/collected", vec!["foo", "bar"]))
+            .now_or_panic()
+            .unwrap();
+        assert_eq!(body(res), "foobar");
+
+        let res = service
+            .call(req("/streaming", vec!["foo", "bar"]))
+            .now_or_panic()
+            .unwrap();
+        assert_eq!(body(res), "2");
+
+        // transform error is returned as error of route's service.
+        let err = service
+            .call(req("/collected", vec!["foo", "bar", "baz"]))
+            .now_or_panic()
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            RouterError::Second(RouteError::Second(BodyError::Overflow { limit: LIMIT }))
+        ));
+    }
+
     #[test]
     fn route_accept_crate_request() {
         get(fn_service(|_: Request<()>| async {
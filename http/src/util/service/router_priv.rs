@@ -1,19 +1,23 @@
 pub use xitca_router::{params::Params, MatchError};
 
-use core::{future::Future, marker::PhantomData};
+use core::{fmt, future::Future, marker::PhantomData, panic::Location};
 
-use std::{borrow::Cow, collections::HashMap};
+use std::{borrow::Cow, error, sync::Arc};
 
+use tracing::Span;
 use xitca_service::{
     object::{DefaultObjectConstructor, ObjectConstructor, StaticObject},
-    pipeline::PipelineE,
+    pipeline::{MapErrFactory, MapFactory, PipelineE},
     ready::ReadyService,
     EnclosedFactory, EnclosedFnFactory, FnService, Service,
 };
 
-use crate::http::{BorrowReq, BorrowReqMut, Uri};
+use crate::http::{BorrowReq, BorrowReqMut, Extensions, Uri};
 
-use super::route::Route;
+use super::{
+    manifest::{Describe, Layer, LayerKind, Manifest},
+    route::Route,
+};
 
 /// A [GenericRouter] specialized with [DefaultObjectConstructor]
 pub type Router<Req, Arg, BErr, Res, Err> =
@@ -24,10 +28,22 @@ pub type Router<Req, Arg, BErr, Res, Err> =
 /// An [ObjectConstructor] must be specified as a type parameter
 /// in order to determine how the router type-erases node services.
 pub struct GenericRouter<ObjCons, SF> {
-    routes: HashMap<Cow<'static, str>, SF>,
+    routes: Vec<Registration<SF>>,
+    // index of registration by path. conflicts detected by router are caught on insertion with it.
+    tree: xitca_router::Router<usize>,
+    default: Option<SF>,
+    allow_shadowing: bool,
     _req_body: PhantomData<ObjCons>,
 }
 
+struct Registration<SF> {
+    path: Cow<'static, str>,
+    service: SF,
+    name: Option<&'static str>,
+    manifest: Manifest,
+    location: &'static Location<'static>,
+}
+
 /// Error type of Router service.
 /// `First` variant contains [MatchError] error.
 /// `Second` variant contains error returned by the services passed to Router.
@@ -54,7 +70,10 @@ impl<SF> GenericRouter<(), SF> {
 impl<ObjCons, SF> GenericRouter<ObjCons, SF> {
     pub fn new() -> Self {
         Self {
-            routes: HashMap::new(),
+            routes: Vec::new(),
+            tree: xitca_router::Router::new(),
+            default: None,
+            allow_shadowing: false,
             _req_body: PhantomData,
         }
     }
@@ -63,14 +82,88 @@ impl<ObjCons, SF> GenericRouter<ObjCons, SF> {
     ///
     /// # Panic:
     ///
-    /// When multiple services inserted with the same path.
-    pub fn insert<F>(mut self, path: &'static str, mut factory: F) -> Self
+    /// When path is conflicting with path already inserted. See [GenericRouter::try_insert] for
+    /// conflicting paths.
+    #[track_caller]
+    pub fn insert<F>(self, path: &'static str, factory: F) -> Self
     where
-        F: PathGen,
+        F: PathGen + Describe,
+        ObjCons: ObjectConstructor<F, Object = SF>,
+    {
+        self.try_insert(path, factory).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Insert a new service factory to given path.
+    ///
+    /// Return [RouteConflict] with both conflicting paths and their source locations of insertion
+    /// when path is conflicting with path already inserted. Paths are conflicting when:
+    /// - the same path is inserted multiple times. Multiple methods of the same path should be
+    ///   handled by one [Route].
+    /// - paths can match the same request path with different parameter names. e.g. `/users/:id`
+    ///   and `/users/:name`
+    /// - paths overlap on static and parameter segment or on parameters followed by different
+    ///   segments. e.g. `/users/new` and `/users/:id`, `/users/:id/posts` and `/users/:id/:tab`.
+    ///   See [GenericRouter::allow_shadowing].
+    /// - a catch-all path matches every request path of a path with repeated parameter. e.g.
+    ///   `/files/*path` and `/files/:dirs+/raw`. See [GenericRouter::allow_shadowing].
+    #[track_caller]
+    pub fn try_insert<F>(mut self, path: &'static str, mut factory: F) -> Result<Self, RouteConflict>
+    where
+        F: PathGen + Describe,
         ObjCons: ObjectConstructor<F, Object = SF>,
     {
         let path = factory.gen(path);
-        assert!(self.routes.insert(path, ObjCons::into_object(factory)).is_none());
+        let location = Location::caller();
+
+        check_conflict(&self.routes, &path, location, self.allow_shadowing)?;
+
+        if let Err(e) = self.tree.insert(path.to_string(), self.routes.len()) {
+            let with = match e {
+                xitca_router::InsertError::Conflict { ref with } => {
+                    self.routes.iter().find(|r| r.path == with.as_str())
+                }
+                _ => None,
+            };
+            let kind = match with {
+                Some(_) => ConflictKind::Structure,
+                None => ConflictKind::Invalid(e),
+            };
+            return Err(RouteConflict::new(kind, &path, location, with));
+        }
+
+        let name = factory.name();
+        let mut manifest = Manifest::new();
+        factory.describe(&mut manifest);
+        self.routes.push(Registration {
+            path,
+            service: ObjCons::into_object(factory),
+            name,
+            manifest,
+            location,
+        });
+        Ok(self)
+    }
+
+    /// Allow overlapping paths and catch-all path shadowing path with repeated parameter. Request
+    /// path matched by multiple paths goes to the one with the highest priority. Static segment has
+    /// higher priority than parameter and parameter has higher priority than catch-all.
+    ///
+    /// It must be called before inserting paths. Other conflicts are still rejected.
+    pub fn allow_shadowing(mut self) -> Self {
+        self.allow_shadowing = true;
+        self
+    }
+
+    /// Set a service factory to handle requests not matching any path of router.
+    /// Without it router would reject these requests with [MatchError].
+    ///
+    /// When used as nested router the default service shadows the one from outer router for
+    /// request paths matched by the nested router's prefix.
+    pub fn default_service<F>(mut self, factory: F) -> Self
+    where
+        ObjCons: ObjectConstructor<F, Object = SF>,
+    {
+        self.default = Some(ObjCons::into_object(factory));
         self
     }
 }
@@ -81,6 +174,11 @@ pub trait PathGen {
     fn gen(&mut self, prefix: &'static str) -> Cow<'static, str> {
         Cow::Borrowed(prefix)
     }
+
+    /// name of route exposed by [MatchedRoute]. default to no name.
+    fn name(&self) -> Option<&'static str> {
+        None
+    }
 }
 
 // nest router needs special handling for path generation.
@@ -91,15 +189,12 @@ impl<ObjCons, SF> PathGen for GenericRouter<ObjCons, SF> {
             path.pop();
         }
 
-        self.routes = self
-            .routes
-            .drain()
-            .map(|(k, v)| {
-                let mut path = path.clone();
-                path.push_str(k.as_ref());
-                (Cow::Owned(path), v)
-            })
-            .collect();
+        for route in self.routes.iter_mut() {
+            let mut p = path.clone();
+            p.push_str(route.path.as_ref());
+            route.path = Cow::Owned(p);
+            route.manifest.prefix(&path);
+        }
 
         path.push_str("/:r");
 
@@ -107,10 +202,34 @@ impl<ObjCons, SF> PathGen for GenericRouter<ObjCons, SF> {
     }
 }
 
-impl<R, N, const M: usize> PathGen for Route<R, N, M> {}
+impl<ObjCons, SF> Describe for GenericRouter<ObjCons, SF> {
+    fn describe(&self, manifest: &mut Manifest) {
+        let mut routes = self
+            .routes
+            .iter()
+            .map(|route| (route.path.clone(), route.manifest.clone()))
+            .collect::<Vec<_>>();
+
+        if self.default.is_some() {
+            let mut default = Manifest::new();
+            default.push(Layer::new("default_service", LayerKind::Service));
+            routes.push((Cow::Borrowed(MatchedRoute::NOT_FOUND), default));
+        }
+
+        manifest.push(Layer::router(routes));
+    }
+}
+
+impl<R, N, G, const M: usize> PathGen for Route<R, N, M, G> {
+    fn name(&self) -> Option<&'static str> {
+        self.name
+    }
+}
 
 impl<F> PathGen for FnService<F> {}
 
+impl<F> Describe for FnService<F> {}
+
 impl<F, S> PathGen for EnclosedFactory<F, S>
 where
     F: PathGen,
@@ -118,6 +237,20 @@ where
     fn gen(&mut self, prefix: &'static str) -> Cow<'static, str> {
         self.first.gen(prefix)
     }
+
+    fn name(&self) -> Option<&'static str> {
+        self.first.name()
+    }
+}
+
+impl<F, S> Describe for EnclosedFactory<F, S>
+where
+    F: Describe,
+{
+    fn describe(&self, manifest: &mut Manifest) {
+        manifest.push(Layer::of::<S>(LayerKind::Middleware));
+        self.first.describe(manifest);
+    }
 }
 
 impl<F, S> PathGen for EnclosedFnFactory<F, S>
@@ -127,6 +260,39 @@ where
     fn gen(&mut self, prefix: &'static str) -> Cow<'static, str> {
         self.first.gen(prefix)
     }
+
+    fn name(&self) -> Option<&'static str> {
+        self.first.name()
+    }
+}
+
+impl<F, S> Describe for EnclosedFnFactory<F, S>
+where
+    F: Describe,
+{
+    fn describe(&self, manifest: &mut Manifest) {
+        manifest.push(Layer::of::<S>(LayerKind::FnMiddleware));
+        self.first.describe(manifest);
+    }
+}
+
+// mapping response or error does not add layer to pipeline.
+impl<F, S> Describe for MapFactory<F, S>
+where
+    F: Describe,
+{
+    fn describe(&self, manifest: &mut Manifest) {
+        self.first.describe(manifest);
+    }
+}
+
+impl<F, S> Describe for MapErrFactory<F, S>
+where
+    F: Describe,
+{
+    fn describe(&self, manifest: &mut Manifest) {
+        self.first.describe(manifest);
+    }
 }
 
 impl<ObjCons, SF, Arg> Service<Arg> for GenericRouter<ObjCons, SF>
@@ -145,24 +311,262 @@ where
         async move {
             let mut routes = xitca_router::Router::new();
 
-            for (path, service) in self.routes.iter() {
-                let service = service.call(arg.clone()).await?;
-                routes.insert(path.to_string(), service).unwrap();
+            for route in self.routes.iter() {
+                let service = route.service.call(arg.clone()).await?;
+                let matched = MatchedRoute {
+                    pattern: Arc::from(route.path.as_ref()),
+                    name: route.name,
+                };
+                // conflicts are rejected on insertion.
+                routes.insert(route.path.to_string(), (service, matched)).unwrap();
             }
 
-            Ok(RouterService { routes })
+            let default = match self.default {
+                Some(ref default) => Some(default.call(arg).await?),
+                None => None,
+            };
+
+            Ok(RouterService { routes, default })
+        }
+    }
+}
+
+/// Error of inserting path conflicting with path already inserted to [GenericRouter].
+#[derive(Debug)]
+pub struct RouteConflict {
+    kind: ConflictKind,
+    path: String,
+    location: &'static Location<'static>,
+    // conflicting path already inserted and it's location. None when path itself is invalid.
+    with: Option<(String, &'static Location<'static>)>,
+}
+
+#[derive(Debug)]
+enum ConflictKind {
+    Duplicate,
+    Structure,
+    Overlap,
+    // inserted path is shadowed by the other one.
+    Shadowed,
+    // inserted path shadows the other one.
+    Shadowing,
+    Invalid(xitca_router::InsertError),
+}
+
+impl RouteConflict {
+    fn new<SF>(
+        kind: ConflictKind,
+        path: &str,
+        location: &'static Location<'static>,
+        with: Option<&Registration<SF>>,
+    ) -> Self {
+        Self {
+            kind,
+            path: path.into(),
+            location,
+            with: with.map(|r| (r.path.to_string(), r.location)),
+        }
+    }
+}
+
+impl fmt::Display for RouteConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (path, location) = (&self.path, self.location);
+        let (with, with_location) = match self.with {
+            Some((ref with, with_location)) => (with.as_str(), with_location),
+            None => match self.kind {
+                ConflictKind::Invalid(ref e) => {
+                    return write!(f, "route {path:?} registered at {location} is invalid: {e}")
+                }
+                _ => unreachable!("conflict must have conflicting route"),
+            },
+        };
+        match self.kind {
+            ConflictKind::Duplicate => write!(
+                f,
+                "route {path:?} registered at {location} is already registered at {with_location}"
+            ),
+            ConflictKind::Structure | ConflictKind::Invalid(_) => write!(
+                f,
+                "route {path:?} registered at {location} conflicts with route {with:?} registered at {with_location}"
+            ),
+            ConflictKind::Overlap => write!(
+                f,
+                "route {path:?} registered at {location} overlaps with route {with:?} registered at {with_location}. \
+                use allow_shadowing if it's intended"
+            ),
+            ConflictKind::Shadowed => write!(
+                f,
+                "route {path:?} registered at {location} is shadowed by route {with:?} registered at {with_location}. \
+                use allow_shadowing if it's intended"
+            ),
+            ConflictKind::Shadowing => write!(
+                f,
+                "route {with:?} registered at {with_location} is shadowed by route {path:?} registered at {location}. \
+                use allow_shadowing if it's intended"
+            ),
+        }
+    }
+}
+
+impl error::Error for RouteConflict {}
+
+// check path to be inserted against paths already inserted.
+fn check_conflict<SF>(
+    routes: &[Registration<SF>],
+    path: &str,
+    location: &'static Location<'static>,
+    allow_shadowing: bool,
+) -> Result<(), RouteConflict> {
+    for route in routes {
+        let kind = if *path == *route.path {
+            ConflictKind::Duplicate
+        } else if shape(path) == shape(&route.path) {
+            ConflictKind::Structure
+        } else if allow_shadowing {
+            continue;
+        } else if overlap(path, &route.path) {
+            ConflictKind::Overlap
+        } else if shadowed(path, &route.path) {
+            ConflictKind::Shadowed
+        } else if shadowed(&route.path, path) {
+            ConflictKind::Shadowing
+        } else {
+            continue;
+        };
+        return Err(RouteConflict::new(kind, path, location, Some(route)));
+    }
+    Ok(())
+}
+
+// path with parameter names removed. e.g. `/users/:id/*rest` becomes `/users/:/*`
+fn shape(path: &str) -> String {
+    let mut shape = String::with_capacity(path.len());
+    for (idx, segment) in path.split('/').enumerate() {
+        if idx > 0 {
+            shape.push('/');
+        }
+        match segment.find([':', '*']) {
+            Some(pos) => {
+                let (lit, param) = segment.split_at(pos);
+                shape.push_str(lit);
+                shape.push_str(&param[..1]);
+                if is_repeat(segment) {
+                    shape.push_str(&segment[segment.len() - 1..]);
+                }
+            }
+            None => shape.push_str(segment),
+        }
+    }
+    shape
+}
+
+// check if a request path can be matched by both paths on static and single parameter segments.
+// paths with catch-all or repeated parameter are checked by shadowed.
+fn overlap(a: &str, b: &str) -> bool {
+    let (mut a, mut b) = (a.split('/'), b.split('/'));
+    loop {
+        match (a.next(), b.next()) {
+            (Some(a), Some(b)) => {
+                if [a, b].iter().any(|s| s.starts_with('*') || is_repeat(s)) {
+                    return false;
+                }
+                match (a.contains(':'), b.contains(':')) {
+                    // parameter never matches empty segment.
+                    (true, false) if b.is_empty() => return false,
+                    (false, true) if a.is_empty() => return false,
+                    (false, false) if a != b => return false,
+                    _ => {}
+                }
+            }
+            (None, None) => return true,
+            _ => return false,
         }
     }
 }
 
+fn is_repeat(segment: &str) -> bool {
+    segment.len() > 2 && segment.starts_with(':') && (segment.ends_with('+') || segment.ends_with('*'))
+}
+
+// check if every request path matched by path with repeated parameter is matched by catch-all path.
+// catch-all path has higher priority in router and the repeated one would never be matched.
+fn shadowed(repeat: &str, catch_all: &str) -> bool {
+    let Some((pre, name)) = catch_all.rsplit_once("/*") else {
+        return false;
+    };
+    if name.contains('/') {
+        return false;
+    }
+
+    let segments = repeat.split('/').collect::<Vec<_>>();
+    let Some(pos) = segments.iter().position(|s| is_repeat(s)) else {
+        return false;
+    };
+
+    let pre = pre.split('/').collect::<Vec<_>>();
+
+    // catch-all must cover all segments before repeated one and there must be non empty segment
+    // after them.
+    if pre.len() > pos || (pre.len() == segments.len() - 1 && segments[pos].ends_with('*')) {
+        return false;
+    }
+
+    pre.iter()
+        .zip(segments.iter())
+        .all(|(c, r)| match (c.strip_prefix(':'), r.strip_prefix(':')) {
+            (Some(_), Some(_)) => true,
+            (Some(_), None) => !r.is_empty(),
+            (None, None) => c == r,
+            (None, Some(_)) => false,
+        })
+}
+
 pub struct RouterService<S> {
-    routes: xitca_router::Router<S>,
+    routes: xitca_router::Router<(S, MatchedRoute)>,
+    default: Option<S>,
+}
+
+/// Route pattern and name matched by router.
+///
+/// Router inserts it into request's extensions after matching and before calling the matched
+/// service. Middlewares enclosing the route service or nested router and the route service itself
+/// can observe it. Middlewares enclosing the router run before routing and can not.
+///
+/// When request is handled by default service of router the pattern is [MatchedRoute::NOT_FOUND].
+/// For nested router the pattern is the full path pattern of the route matched inside it.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MatchedRoute {
+    pattern: Arc<str>,
+    name: Option<&'static str>,
+}
+
+impl MatchedRoute {
+    /// Sentinel pattern for request handled by default service.
+    pub const NOT_FOUND: &'static str = "<not_found>";
+
+    /// The route pattern registered to router. e.g. `/users/:id`
+    pub fn pattern(&self) -> &str {
+        &self.pattern
+    }
+
+    /// The name assigned to route with [Route::name].
+    pub fn name(&self) -> Option<&'static str> {
+        self.name
+    }
+
+    fn not_found() -> Self {
+        Self {
+            pattern: Arc::from(Self::NOT_FOUND),
+            name: None,
+        }
+    }
 }
 
 impl<S, Req> Service<Req> for RouterService<S>
 where
     S: Service<Req>,
-    Req: BorrowReq<Uri> + BorrowReqMut<Params>,
+    Req: BorrowReq<Uri> + BorrowReqMut<Params> + BorrowReqMut<Extensions>,
 {
     type Response = S::Response;
     type Error = RouterError<S::Error>;
@@ -174,12 +578,26 @@ where
         Req: 's,
     {
         async {
-            let xitca_router::Match { value, params } =
-                self.routes.at(req.borrow().path()).map_err(RouterError::First)?;
-
-            *req.borrow_mut() = params;
-
-            value.call(req).await.map_err(RouterError::Second)
+            let (service, matched) = match self.routes.at(req.borrow().path()) {
+                Ok(xitca_router::Match {
+                    value: (service, matched),
+                    params,
+                }) => {
+                    *req.borrow_mut() = params;
+                    (service, matched.clone())
+                }
+                Err(e) => (
+                    self.default.as_ref().ok_or(RouterError::First(e))?,
+                    MatchedRoute::not_found(),
+                ),
+            };
+
+            // matched route is recorded to request span of dispatcher.
+            Span::current().record("route", matched.pattern());
+
+            BorrowReqMut::<Extensions>::borrow_mut(&mut req).insert(matched);
+
+            service.call(req).await.map_err(RouterError::Second)
         }
     }
 }
@@ -201,7 +619,10 @@ mod test {
     use xitca_service::{fn_service, middleware::UncheckedReady, Service, ServiceExt};
     use xitca_unsafe_collection::futures::NowOrPanic;
 
-    use crate::http::{Request, RequestExt, Response};
+    use crate::{
+        http::{Request, RequestExt, Response},
+        util::service::route::{get, RouteError},
+    };
 
     use super::*;
 
@@ -318,4 +739,242 @@ mod test {
             .now_or_panic()
             .unwrap();
     }
+
+    #[test]
+    fn router_manifest() {
+        async fn handler<E>(_: Request<RequestExt<()>>) -> Result<Response<()>, E> {
+            Ok(Response::new(()))
+        }
+
+        let inner = Router::new().insert("/c", get(fn_service(handler::<Infallible>)).post(fn_service(handler)));
+        let mid = Router::new().insert("/b", inner).enclosed_fn(enclosed);
+        let outer = Router::new()
+            .insert("/", fn_service(handler))
+            .insert("/a", mid)
+            .default_service(fn_service(handler));
+
+        let mut manifest = Manifest::new();
+        outer.describe(&mut manifest);
+
+        let [router] = manifest.layers() else {
+            panic!("{manifest}")
+        };
+        assert_eq!(router.kind(), LayerKind::Router);
+        let [(root, leaf), (a, mid), (not_found, _)] = router.routes() else {
+            panic!("{manifest}")
+        };
+        assert_eq!(root, "/");
+        assert_eq!(leaf.layers()[0].kind(), LayerKind::Service);
+        assert_eq!(a, "/a/:r");
+        assert_eq!(not_found, MatchedRoute::NOT_FOUND);
+
+        let names = mid.layers().iter().map(|l| (l.name(), l.kind())).collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [("enclosed", LayerKind::FnMiddleware), ("Router", LayerKind::Router)]
+        );
+
+        let [(b, inner)] = mid.layers()[1].routes() else {
+            panic!("{manifest}")
+        };
+        assert_eq!(b, "/a/b/:r");
+        let [(c, route)] = inner.layers()[0].routes() else {
+            panic!("{manifest}")
+        };
+        assert_eq!(c, "/a/b/c");
+        assert_eq!(route.layers()[0].name(), "GET|POST");
+        assert_eq!(route.layers()[0].kind(), LayerKind::Route);
+    }
+
+    #[test]
+    fn router_default_service() {
+        fn status(status: u16) -> Response<()> {
+            let mut res = Response::new(());
+            *res.status_mut() = crate::http::StatusCode::from_u16(status).unwrap();
+            res
+        }
+
+        let nest = Router::new()
+            .insert(
+                "/nest",
+                fn_service(|_: Request<RequestExt<()>>| async { Ok::<_, Infallible>(status(200)) }),
+            )
+            .default_service(fn_service(|_: Request<RequestExt<()>>| async {
+                Ok::<_, Infallible>(status(405))
+            }));
+
+        let service = Router::new()
+            .insert("/scope", nest)
+            .default_service(fn_service(|_: Request<RequestExt<()>>| async {
+                Ok::<_, RouterError<Infallible>>(status(404))
+            }))
+            .call(())
+            .now_or_panic()
+            .unwrap();
+
+        let call = |uri: &'static str| {
+            let req = Request::builder().uri(uri).body(Default::default()).unwrap();
+            service.call(req).now_or_panic().unwrap().status().as_u16()
+        };
+
+        assert_eq!(call("/foo"), 404);
+        assert_eq!(call("/scope/nest"), 200);
+        assert_eq!(call("/scope/foo"), 405);
+    }
+
+    #[test]
+    fn router_matched_route() {
+        async fn handler<E>(req: Request<RequestExt<()>>) -> Result<Response<String>, E> {
+            let matched = req.extensions().get::<MatchedRoute>().unwrap();
+            let body = format!("{} {}", matched.pattern(), matched.name().unwrap_or_default());
+            Ok(Response::new(body))
+        }
+
+        type Error = RouterError<RouteError<Infallible>>;
+
+        let nest = Router::new().insert("/:id", get(fn_service(handler::<Infallible>)).name("get_user"));
+
+        let service = Router::new()
+            .insert("/users", nest)
+            .insert("/files/*path", fn_service(handler::<Error>))
+            .default_service(fn_service(handler::<Error>))
+            .call(())
+            .now_or_panic()
+            .unwrap();
+
+        let call = |uri: &'static str| {
+            let req = Request::builder().uri(uri).body(Default::default()).unwrap();
+            service.call(req).now_or_panic().unwrap().into_body()
+        };
+
+        assert_eq!(call("/users/12345"), "/users/:id get_user");
+        assert_eq!(call("/files/foo/bar.txt"), "/files/*path ");
+        assert_eq!(call("/foo"), "<not_found> ");
+    }
+
+    fn conflict<T>(res: Result<T, RouteConflict>) -> String {
+        res.err().expect("route must be conflicting").to_string()
+    }
+
+    fn location(line: u32) -> String {
+        format!("{}:{line}:", file!())
+    }
+
+    #[test]
+    fn router_conflict() {
+        let handler = || fn_service(|_: Request<RequestExt<()>>| async { Ok::<_, Infallible>(Response::new(())) });
+
+        let line = line!();
+        let router = Router::new().insert("/users", handler());
+        let msg = conflict(router.try_insert("/users", handler()));
+        assert!(msg.contains("\"/users\""));
+        assert!(msg.contains("already registered"));
+        assert!(msg.contains(&location(line + 1)));
+        assert!(msg.contains(&location(line + 2)));
+
+        let line = line!();
+        let router = Router::new().insert("/users/:id", handler());
+        let msg = conflict(router.try_insert("/users/:name", handler()));
+        assert!(msg.contains("\"/users/:name\""));
+        assert!(msg.contains("\"/users/:id\""));
+        assert!(msg.contains(&location(line + 1)));
+        assert!(msg.contains(&location(line + 2)));
+
+        // conflict detected by router.
+        let line = line!();
+        let router = Router::new().insert("/a/:id/x", handler());
+        let msg = conflict(router.try_insert("/a/:name/y", handler()));
+        assert!(msg.contains("conflicts with"));
+        assert!(msg.contains(&location(line + 1)));
+        assert!(msg.contains(&location(line + 2)));
+
+        // insert panics on conflict.
+        let e = std::panic::catch_unwind(|| drop(Router::new().insert("/a", handler()).insert("/a", handler())));
+        let msg = e.unwrap_err().downcast::<String>().unwrap();
+        assert!(msg.contains("already registered"));
+    }
+
+    #[test]
+    fn router_overlap() {
+        let handler = || fn_service(|_: Request<RequestExt<()>>| async { Ok::<_, Infallible>(Response::new(())) });
+
+        // static and parameter segment.
+        let line = line!();
+        let router = Router::new().insert("/users/:id", handler());
+        let msg = conflict(router.try_insert("/users/new", handler()));
+        assert!(msg.contains("route \"/users/new\""));
+        assert!(msg.contains("overlaps with route \"/users/:id\""));
+        assert!(msg.contains(&location(line + 1)));
+        assert!(msg.contains(&location(line + 2)));
+
+        // parameters followed by different segments.
+        let router = Router::new().insert("/users/:id/posts", handler());
+        let msg = conflict(router.try_insert("/users/:id/:tab", handler()));
+        assert!(msg.contains("overlaps with route \"/users/:id/posts\""));
+
+        // paths not overlapping.
+        Router::new()
+            .insert("/users/:id", handler())
+            .insert("/users/:id/posts", handler())
+            .insert("/users", handler())
+            .insert("/posts/:id", handler())
+            .call(())
+            .now_or_panic()
+            .unwrap();
+
+        let service = Router::new()
+            .allow_shadowing()
+            .insert("/users/:id", handler())
+            .insert("/users/new", handler())
+            .call(())
+            .now_or_panic()
+            .unwrap();
+        let req = Request::builder().uri("/users/new").body(Default::default()).unwrap();
+        service.call(req).now_or_panic().unwrap();
+    }
+
+    #[test]
+    fn router_shadowing() {
+        let handler = || fn_service(|_: Request<RequestExt<()>>| async { Ok::<_, Infallible>(Response::new(())) });
+
+        let line = line!();
+        let router = Router::new().insert("/files/*path", handler());
+        let msg = conflict(router.try_insert("/files/:dirs+/raw", handler()));
+        assert!(msg.contains("\"/files/:dirs+/raw\""));
+        assert!(msg.contains("shadowed by route \"/files/*path\""));
+        assert!(msg.contains(&location(line + 1)));
+        assert!(msg.contains(&location(line + 2)));
+
+        // shadowing path inserted after the shadowed one.
+        let router = Router::new().insert("/files/:dirs+/raw", handler());
+        let msg = conflict(router.try_insert("/files/*path", handler()));
+        assert!(msg.contains("route \"/files/:dirs+/raw\""));
+        assert!(msg.contains("shadowed by route \"/files/*path\""));
+
+        // partial shadowing is not conflict.
+        Router::new()
+            .insert("/files/raw/*path", handler())
+            .insert("/files/:dirs+/raw", handler())
+            .call(())
+            .now_or_panic()
+            .unwrap();
+
+        let service = Router::new()
+            .allow_shadowing()
+            .insert("/files/*path", handler())
+            .insert("/files/:dirs+/raw", handler())
+            .call(())
+            .now_or_panic()
+            .unwrap();
+        let req = Request::builder().uri("/files/a/raw").body(Default::default()).unwrap();
+        service.call(req).now_or_panic().unwrap();
+
+        // escape hatch does not suppress other conflicts.
+        let line = line!();
+        let router = Router::new().allow_shadowing().insert("/files/*path", handler());
+        let msg = conflict(router.try_insert("/files/*path", handler()));
+        assert!(msg.contains("already registered"));
+        assert!(msg.contains(&location(line + 1)));
+        assert!(msg.contains(&location(line + 2)));
+    }
 }
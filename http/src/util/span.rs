@@ -0,0 +1,48 @@
+//! [tracing] spans of connection and request.
+//!
+//! Spans are created with [Level::DEBUG] and their fields are only evaluated when the span is
+//! enabled by subscriber.
+
+use core::cell::Cell;
+
+use std::net::SocketAddr;
+
+use tracing::{field::Empty, span, Level, Span};
+
+use crate::http::Request;
+
+thread_local! {
+    // connection id is unique per worker thread.
+    static NEXT_ID: Cell<u64> = const { Cell::new(0) };
+}
+
+/// Generate id of a new connection.
+pub(crate) fn next_id() -> u64 {
+    NEXT_ID.with(|id| {
+        let next = id.get();
+        id.set(next.wrapping_add(1));
+        next
+    })
+}
+
+/// Span entered for the lifetime of a connection with id generated by [next_id].
+pub(crate) fn connection(protocol: &'static str, addr: SocketAddr, id: u64) -> Span {
+    span!(target: "xitca_http", Level::DEBUG, "connection", protocol, peer_addr = %addr, id)
+}
+
+/// Span entered for service call and response write of a request. It's a child of the connection
+/// span it's created in.
+///
+/// `stream_id` field is left empty and recorded by multiplexed protocols. `route` field is left
+/// empty and recorded by `Router` with the matched route pattern.
+pub(crate) fn request<B>(req: &Request<B>) -> Span {
+    span!(
+        target: "xitca_http",
+        Level::DEBUG,
+        "request",
+        method = %req.method(),
+        path = req.uri().path(),
+        stream_id = Empty,
+        route = Empty
+    )
+}
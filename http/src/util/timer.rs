@@ -2,6 +2,7 @@ use std::{
     future::Future,
     pin::Pin,
     task::{ready, Context, Poll},
+    time::Duration,
 };
 
 use pin_project_lite::pin_project;
@@ -46,26 +47,37 @@ pin_project! {
     /// This timer would optimistically assume deadline is not likely to be reached often.
     /// It has little cost inserting a new deadline and additional cost when previous
     /// deadline is met and the lazy reset happen with new deadline.
+    ///
+    /// Updated deadlines are rounded up to a multiple of granularity(counting from the initial
+    /// deadline) so frequent updates in a short period of time would result in the same deadline
+    /// and at most one timer reset for every granularity unit.
     pub struct KeepAlive {
         #[pin]
         timer: Sleep,
         deadline: Instant,
+        origin: Instant,
+        granularity: Duration,
     }
 }
 
 impl KeepAlive {
+    /// Construct a timer with given deadline and rounding granularity of following updates.
+    /// Zero granularity disables the rounding.
     #[inline]
-    pub fn new(deadline: Instant) -> Self {
+    pub fn new(deadline: Instant, granularity: Duration) -> Self {
         Self {
             timer: sleep_until(deadline),
             deadline,
+            origin: deadline,
+            granularity,
         }
     }
 
     #[cfg(any(feature = "http1", feature = "http2"))]
     #[inline]
     pub fn update(self: Pin<&mut Self>, deadline: Instant) {
-        *self.project().deadline = deadline;
+        let this = self.project();
+        *this.deadline = round_up(*this.origin, deadline, *this.granularity);
     }
 
     #[inline]
@@ -94,3 +106,78 @@ impl Future for KeepAlive {
         }
     }
 }
+
+#[cfg(any(feature = "http1", feature = "http2"))]
+fn round_up(origin: Instant, deadline: Instant, granularity: Duration) -> Instant {
+    let granularity = granularity.as_nanos();
+    if granularity == 0 || deadline <= origin {
+        return deadline;
+    }
+
+    let dur = (deadline - origin).as_nanos();
+    let units = dur.div_ceil(granularity);
+
+    // a deadline this far in the future is not meaningful to round.
+    match u64::try_from(units * granularity) {
+        Ok(nanos) => origin + Duration::from_nanos(nanos),
+        Err(_) => deadline,
+    }
+}
+
+#[cfg(all(test, any(feature = "http1", feature = "http2")))]
+mod test {
+    use core::pin::pin;
+
+    use super::*;
+
+    const GRANULARITY: Duration = Duration::from_secs(1);
+
+    #[test]
+    fn round() {
+        let origin = Instant::now();
+        let ms = Duration::from_millis;
+
+        assert_eq!(round_up(origin, origin, GRANULARITY), origin);
+        assert_eq!(round_up(origin, origin + ms(1), GRANULARITY), origin + ms(1000));
+        assert_eq!(round_up(origin, origin + ms(1000), GRANULARITY), origin + ms(1000));
+        assert_eq!(round_up(origin, origin + ms(1001), GRANULARITY), origin + ms(2000));
+        assert_eq!(round_up(origin, origin + ms(1001), Duration::ZERO), origin + ms(1001));
+
+        // deadline before origin is not rounded.
+        let past = origin - ms(10);
+        assert_eq!(round_up(origin, past, GRANULARITY), past);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn update_coalesce() {
+        let start = Instant::now();
+        let mut timer = pin!(KeepAlive::new(start, GRANULARITY));
+
+        timer.as_mut().update(start + Duration::from_millis(100));
+        let deadline = timer.deadline;
+        assert_eq!(deadline, start + GRANULARITY);
+
+        // updates within the same granularity unit are collapsed to the same deadline.
+        for ms in [200, 500, 999, 1000] {
+            timer.as_mut().update(start + Duration::from_millis(ms));
+            assert_eq!(timer.deadline, deadline);
+        }
+
+        timer.as_mut().update(start + Duration::from_millis(1001));
+        assert_eq!(timer.deadline, start + GRANULARITY * 2);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn fire_within_granularity() {
+        let start = Instant::now();
+        let mut timer = pin!(KeepAlive::new(start + Duration::from_millis(10), GRANULARITY));
+
+        let ka_dur = Duration::from_millis(2500);
+        timer.as_mut().update(Instant::now() + ka_dur);
+        timer.as_mut().await;
+
+        let elapsed = start.elapsed();
+        assert!(elapsed >= ka_dur, "timer fired early: {elapsed:?}");
+        assert!(elapsed <= ka_dur + GRANULARITY, "timer fired late: {elapsed:?}");
+    }
+}
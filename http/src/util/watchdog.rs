@@ -0,0 +1,126 @@
+//! Detection of service call stuck longer than
+//! [HttpServiceConfig::stuck_request_threshold](crate::config::HttpServiceConfig::stuck_request_threshold).
+
+use core::time::Duration;
+
+use std::net::SocketAddr;
+
+use tokio::time::Instant;
+use tracing::warn;
+
+use crate::{
+    config::{ConnectionEvent, StuckRequestAction},
+    http::{Method, Request, Uri},
+};
+
+/// Watchdog of a connection. It's shared by all requests served on the connection.
+pub(crate) struct Watchdog {
+    threshold: Duration,
+    action: StuckRequestAction,
+    observer: Option<fn(SocketAddr, ConnectionEvent)>,
+    addr: SocketAddr,
+    id: u64,
+}
+
+impl Watchdog {
+    pub(crate) fn new(
+        stuck: Option<(Duration, StuckRequestAction)>,
+        observer: Option<fn(SocketAddr, ConnectionEvent)>,
+        addr: SocketAddr,
+        id: u64,
+    ) -> Option<Self> {
+        stuck.map(|(threshold, action)| Self {
+            threshold,
+            action,
+            observer,
+            addr,
+            id,
+        })
+    }
+
+    #[cfg(feature = "http2")]
+    pub(crate) fn threshold(&self) -> Duration {
+        self.threshold
+    }
+
+    /// Start watching service call of request dispatched at given instant.
+    pub(crate) fn watch<B>(&self, req: &Request<B>, now: Instant) -> Watch {
+        Watch {
+            deadline: now + self.threshold,
+            method: req.method().clone(),
+            uri: req.uri().clone(),
+        }
+    }
+
+    /// Report a stuck service call and return the action to take on it.
+    ///
+    /// The event is emitted inside request span where the matched route is recorded by
+    /// `Router`.
+    #[cold]
+    #[inline(never)]
+    pub(crate) fn report(&self, watch: &Watch) -> StuckRequestAction {
+        warn!(
+            target: "xitca_http",
+            connection_id = self.id,
+            method = %watch.method,
+            path = watch.uri.path(),
+            threshold = ?self.threshold,
+            "service call is stuck"
+        );
+        if let Some(observer) = self.observer {
+            observer(self.addr, ConnectionEvent::StuckRequest);
+        }
+        self.action
+    }
+}
+
+/// A service call being watched.
+pub(crate) struct Watch {
+    pub(crate) deadline: Instant,
+    method: Method,
+    uri: Uri,
+}
+
+#[cfg(feature = "http2")]
+pub(crate) use tick::Tick;
+
+#[cfg(feature = "http2")]
+mod tick {
+    use core::{
+        cell::{Cell, RefCell},
+        task::{Context, Waker},
+    };
+
+    /// Coarse tick shared by multiplexed service calls of a connection. Calls check their deadline
+    /// when they are woken up by their own progress and a single connection level timer waking
+    /// them with [Tick::wake_all] bounds the delay of detection.
+    pub(crate) struct Tick {
+        round: Cell<u64>,
+        wakers: RefCell<Vec<Waker>>,
+    }
+
+    impl Tick {
+        pub(crate) fn new() -> Self {
+            Self {
+                round: Cell::new(1),
+                wakers: RefCell::new(Vec::new()),
+            }
+        }
+
+        /// Register waker of pending call to be woken by next tick. `registered` is the round the
+        /// call is registered in and a call is only registered once per round.
+        pub(crate) fn register(&self, registered: &mut u64, cx: &Context<'_>) {
+            let round = self.round.get();
+            if *registered != round {
+                *registered = round;
+                self.wakers.borrow_mut().push(cx.waker().clone());
+            }
+        }
+
+        pub(crate) fn wake_all(&self) {
+            self.round.set(self.round.get() + 1);
+            let wakers = core::mem::take(&mut *self.wakers.borrow_mut());
+            wakers.into_iter().for_each(Waker::wake);
+        }
+    }
+}
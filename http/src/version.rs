@@ -36,4 +36,11 @@ mod io_impl {
             Version::HTTP_11
         }
     }
+
+    impl<Io> AsVersion for xitca_io::io::PollIoAdapter<Io> {
+        #[inline]
+        fn as_version(&self) -> Version {
+            Version::HTTP_11
+        }
+    }
 }
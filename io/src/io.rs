@@ -5,13 +5,16 @@
 pub use tokio::io::{AsyncRead, AsyncWrite, Interest, ReadBuf, Ready};
 
 use core::{
-    future::Future,
+    cell::RefCell,
+    future::{poll_fn, Future},
     pin::Pin,
-    task::{Context, Poll},
+    task::{ready, Context, Poll},
 };
 
 use std::io;
 
+use crate::bytes::{Buf, BytesMut};
+
 /// A wrapper trait for an [AsyncRead]/[AsyncWrite] tokio type with additional methods.
 pub trait AsyncIo: io::Read + io::Write + Unpin {
     type Future<'f>: Future<Output = io::Result<Ready>>
@@ -49,3 +52,233 @@ pub trait AsyncIo: io::Read + io::Write + Unpin {
     /// tokio's network Stream types do not expose other api for shutdown besides [AsyncWrite::poll_shutdown].
     fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>>;
 }
+
+// size of buffer for every read from inner io.
+const READ_BUF_SIZE: usize = 8 * 1024;
+
+// buffered bytes over this size would be written to inner io before accepting more write.
+const WRITE_BUF_LIMIT: usize = 64 * 1024;
+
+/// Adapter for using a poll based [AsyncRead]/[AsyncWrite] type as [AsyncIo].
+///
+/// Any duplex stream (in process pipe, vsock, ssh channel etc) can be wrapped and served where
+/// [AsyncIo] is expected.
+///
+/// Adapter owns it's read and write buffers. Waiting for read readiness reads from inner io into
+/// read buffer and waiting for write readiness writes buffered bytes into inner io and flush it.
+/// [std::io::Read]/[std::io::Write] methods only operate on buffers and return
+/// [io::ErrorKind::WouldBlock] when buffer is empty or full.
+pub struct PollIoAdapter<Io> {
+    inner: RefCell<Inner<Io>>,
+}
+
+struct Inner<Io> {
+    io: Io,
+    read_buf: BytesMut,
+    // eof or error from last read of inner io. eof is sticky.
+    read_state: Option<io::Result<()>>,
+    write_buf: BytesMut,
+    // error from last write of inner io. it's returned on next write.
+    write_err: Option<io::Error>,
+}
+
+impl<Io> PollIoAdapter<Io> {
+    pub fn new(io: Io) -> Self {
+        Self {
+            inner: RefCell::new(Inner {
+                io,
+                read_buf: BytesMut::new(),
+                read_state: None,
+                write_buf: BytesMut::new(),
+                write_err: None,
+            }),
+        }
+    }
+
+    /// Consume self and return the inner io. Buffered bytes are dropped.
+    pub fn into_inner(self) -> Io {
+        self.inner.into_inner().io
+    }
+}
+
+impl<Io> Inner<Io>
+where
+    Io: AsyncRead + AsyncWrite + Unpin,
+{
+    fn poll_read_buf(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        if !self.read_buf.is_empty() || self.read_state.is_some() {
+            return Poll::Ready(());
+        }
+
+        self.read_buf.resize(READ_BUF_SIZE, 0);
+        let mut buf = ReadBuf::new(&mut self.read_buf);
+        let res = Pin::new(&mut self.io).poll_read(cx, &mut buf);
+        let len = buf.filled().len();
+        self.read_buf.truncate(len);
+
+        match ready!(res) {
+            Ok(_) if len == 0 => self.read_state = Some(Ok(())),
+            Ok(_) => {}
+            Err(e) => self.read_state = Some(Err(e)),
+        }
+
+        Poll::Ready(())
+    }
+
+    fn poll_drain(&mut self, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        while !self.write_buf.is_empty() {
+            match ready!(Pin::new(&mut self.io).poll_write(cx, &self.write_buf))? {
+                0 => return Poll::Ready(Err(io::ErrorKind::WriteZero.into())),
+                n => self.write_buf.advance(n),
+            }
+        }
+        Pin::new(&mut self.io).poll_flush(cx)
+    }
+}
+
+impl<Io> AsyncIo for PollIoAdapter<Io>
+where
+    Io: AsyncRead + AsyncWrite + Unpin,
+{
+    type Future<'f> = impl Future<Output = io::Result<Ready>> + 'f where Self: 'f;
+
+    #[inline]
+    fn ready(&self, interest: Interest) -> Self::Future<'_> {
+        poll_fn(move |cx| self.poll_ready(interest, cx))
+    }
+
+    fn poll_ready(&self, interest: Interest, cx: &mut Context<'_>) -> Poll<io::Result<Ready>> {
+        let mut inner = self.inner.borrow_mut();
+        let mut ready = Ready::EMPTY;
+
+        if interest.is_readable() && inner.poll_read_buf(cx).is_ready() {
+            ready |= Ready::READABLE;
+        }
+
+        if interest.is_writable() {
+            if let Poll::Ready(res) = inner.poll_drain(cx) {
+                if let Err(e) = res {
+                    inner.write_buf.clear();
+                    inner.write_err = Some(e);
+                }
+                ready |= Ready::WRITABLE;
+            }
+        }
+
+        if ready.is_empty() {
+            Poll::Pending
+        } else {
+            Poll::Ready(Ok(ready))
+        }
+    }
+
+    fn is_vectored_write(&self) -> bool {
+        false
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let inner = self.get_mut().inner.get_mut();
+        ready!(inner.poll_drain(cx))?;
+        Pin::new(&mut inner.io).poll_shutdown(cx)
+    }
+}
+
+impl<Io> io::Read for PollIoAdapter<Io> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let inner = self.inner.get_mut();
+
+        if !inner.read_buf.is_empty() {
+            let len = core::cmp::min(buf.len(), inner.read_buf.len());
+            buf[..len].copy_from_slice(&inner.read_buf[..len]);
+            inner.read_buf.advance(len);
+            return Ok(len);
+        }
+
+        match inner.read_state.take() {
+            Some(Ok(_)) => {
+                inner.read_state = Some(Ok(()));
+                Ok(0)
+            }
+            Some(Err(e)) => Err(e),
+            None => Err(io::ErrorKind::WouldBlock.into()),
+        }
+    }
+}
+
+impl<Io> io::Write for PollIoAdapter<Io> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let inner = self.inner.get_mut();
+
+        if let Some(e) = inner.write_err.take() {
+            return Err(e);
+        }
+
+        if inner.write_buf.len() >= WRITE_BUF_LIMIT {
+            return Err(io::ErrorKind::WouldBlock.into());
+        }
+
+        inner.write_buf.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let inner = self.inner.get_mut();
+
+        if let Some(e) = inner.write_err.take() {
+            return Err(e);
+        }
+
+        if inner.write_buf.is_empty() {
+            Ok(())
+        } else {
+            Err(io::ErrorKind::WouldBlock.into())
+        }
+    }
+}
+
+impl<Io> AsyncRead for PollIoAdapter<Io>
+where
+    Io: AsyncRead + Unpin,
+{
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let inner = self.get_mut().inner.get_mut();
+
+        if !inner.read_buf.is_empty() {
+            let len = core::cmp::min(buf.remaining(), inner.read_buf.len());
+            buf.put_slice(&inner.read_buf[..len]);
+            inner.read_buf.advance(len);
+            return Poll::Ready(Ok(()));
+        }
+
+        match inner.read_state.take() {
+            Some(Err(e)) => Poll::Ready(Err(e)),
+            _ => Pin::new(&mut inner.io).poll_read(cx, buf),
+        }
+    }
+}
+
+impl<Io> AsyncWrite for PollIoAdapter<Io>
+where
+    Io: AsyncRead + AsyncWrite + Unpin,
+{
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        let inner = self.get_mut().inner.get_mut();
+        if let Some(e) = inner.write_err.take() {
+            return Poll::Ready(Err(e));
+        }
+        ready!(inner.poll_drain(cx))?;
+        Pin::new(&mut inner.io).poll_write(cx, buf)
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let inner = self.get_mut().inner.get_mut();
+        if let Some(e) = inner.write_err.take() {
+            return Poll::Ready(Err(e));
+        }
+        inner.poll_drain(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        AsyncIo::poll_shutdown(self, cx)
+    }
+}
@@ -1,6 +1,9 @@
-use std::{io, net::SocketAddr};
+use std::{io, net::SocketAddr, sync::Arc, time::Duration};
 
-use quinn::{Connecting, Endpoint, ServerConfig};
+use quinn::{
+    congestion::{BbrConfig, CubicConfig, NewRenoConfig},
+    Connecting, Endpoint, IdleTimeout, ServerConfig, TransportConfig, VarInt,
+};
 
 use super::Stream;
 
@@ -32,10 +35,130 @@ impl UdpListener {
     }
 }
 
+/// Congestion control algorithm used by QUIC connection.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum CongestionController {
+    #[default]
+    Cubic,
+    NewReno,
+    Bbr,
+}
+
+/// Typed QUIC transport tuning for Http/3 listener.
+///
+/// Settings left unset fall back to default values of [TransportConfig].
+///
+/// # Examples:
+/// ```rust
+/// # use std::time::Duration;
+/// # use xitca_io::net::{CongestionController, H3TransportConfig};
+/// let transport = H3TransportConfig::new()
+///     .max_idle_timeout(Duration::from_secs(10))
+///     .max_data(16 * 1024 * 1024)
+///     .max_stream_data(1024 * 1024)
+///     .max_concurrent_bidi_streams(256)
+///     .keep_alive_interval(Duration::from_secs(3))
+///     .congestion_controller(CongestionController::Bbr);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct H3TransportConfig {
+    max_idle_timeout: Option<Duration>,
+    max_data: Option<u32>,
+    max_stream_data: Option<u32>,
+    max_concurrent_bidi_streams: Option<u32>,
+    keep_alive_interval: Option<Duration>,
+    congestion_controller: Option<CongestionController>,
+}
+
+impl H3TransportConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Close connection when it has been idle for given duration.
+    pub fn max_idle_timeout(mut self, dur: Duration) -> Self {
+        self.max_idle_timeout = Some(dur);
+        self
+    }
+
+    /// Max bytes peer can send on connection before it's acknowledged. (`initial_max_data`)
+    pub fn max_data(mut self, bytes: u32) -> Self {
+        self.max_data = Some(bytes);
+        self
+    }
+
+    /// Max bytes peer can send on a stream before it's acknowledged.
+    /// (`initial_max_stream_data_bidi_remote`)
+    pub fn max_stream_data(mut self, bytes: u32) -> Self {
+        self.max_stream_data = Some(bytes);
+        self
+    }
+
+    /// Max number of bidirectional streams(requests) peer can open concurrently.
+    pub fn max_concurrent_bidi_streams(mut self, num: u32) -> Self {
+        self.max_concurrent_bidi_streams = Some(num);
+        self
+    }
+
+    /// Interval of sending keep alive packet to peer to prevent idle timeout.
+    pub fn keep_alive_interval(mut self, dur: Duration) -> Self {
+        self.keep_alive_interval = Some(dur);
+        self
+    }
+
+    /// Congestion control algorithm. Default to [CongestionController::Cubic].
+    pub fn congestion_controller(mut self, controller: CongestionController) -> Self {
+        self.congestion_controller = Some(controller);
+        self
+    }
+
+    fn apply(&self, config: &mut ServerConfig) -> io::Result<()> {
+        let mut transport = TransportConfig::default();
+
+        if let Some(dur) = self.max_idle_timeout {
+            let timeout = IdleTimeout::try_from(dur)
+                .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "max idle timeout is too large"))?;
+            transport.max_idle_timeout(Some(timeout));
+        }
+
+        if let Some(bytes) = self.max_data {
+            transport.receive_window(VarInt::from_u32(bytes));
+        }
+
+        if let Some(bytes) = self.max_stream_data {
+            transport.stream_receive_window(VarInt::from_u32(bytes));
+        }
+
+        if let Some(num) = self.max_concurrent_bidi_streams {
+            transport.max_concurrent_bidi_streams(VarInt::from_u32(num));
+        }
+
+        transport.keep_alive_interval(self.keep_alive_interval);
+
+        match self.congestion_controller {
+            Some(CongestionController::Cubic) => {
+                transport.congestion_controller_factory(Arc::new(CubicConfig::default()));
+            }
+            Some(CongestionController::NewReno) => {
+                transport.congestion_controller_factory(Arc::new(NewRenoConfig::default()));
+            }
+            Some(CongestionController::Bbr) => {
+                transport.congestion_controller_factory(Arc::new(BbrConfig::default()));
+            }
+            None => {}
+        }
+
+        config.transport_config(Arc::new(transport));
+
+        Ok(())
+    }
+}
+
 /// Builder type for UdpListener.
 pub struct UdpListenerBuilder {
     addr: SocketAddr,
     config: ServerConfig,
+    transport: Option<H3TransportConfig>,
     /// An artificial backlog capacity reinforced by bounded channel.
     /// The channel is tasked with distribute [UdpStream] and can cache stream up most to
     /// the number equal to backlog.
@@ -47,17 +170,34 @@ impl UdpListenerBuilder {
         Self {
             addr,
             config,
+            transport: None,
             backlog: 2048,
         }
     }
 
+    /// Apply typed transport tuning to server config before endpoint is created.
+    ///
+    /// Transport config set by [ServerConfig::transport_config] would be replaced.
+    pub fn transport(mut self, transport: H3TransportConfig) -> Self {
+        self.transport = Some(transport);
+        self
+    }
+
     pub fn backlog(mut self, backlog: u32) -> Self {
         self.backlog = backlog;
         self
     }
 
     pub fn build(self) -> io::Result<UdpListener> {
-        let Self { config, addr, .. } = self;
+        let Self {
+            mut config,
+            addr,
+            transport,
+            ..
+        } = self;
+        if let Some(transport) = transport {
+            transport.apply(&mut config)?;
+        }
         Endpoint::server(config, addr).map(|endpoint| UdpListener { endpoint })
     }
 }
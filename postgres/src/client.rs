@@ -7,6 +7,7 @@ use xitca_unsafe_collection::no_hash::NoHashBuilder;
 use super::{
     driver::{ClientTx, Response},
     error::Error,
+    event::{EventSubscriber, Events},
     statement::Statement,
     util::lock::Lock,
 };
@@ -15,6 +16,7 @@ pub struct Client {
     pub(crate) tx: ClientTx,
     pub(crate) buf: Lock<BytesMut>,
     cached_typeinfo: Lock<CachedTypeInfo>,
+    events: EventSubscriber,
 }
 
 /// A cache of type info and prepared statements for fetching type info
@@ -36,9 +38,10 @@ struct CachedTypeInfo {
 }
 
 impl Client {
-    pub(crate) fn new(tx: ClientTx) -> Self {
+    pub(crate) fn new(tx: ClientTx, events: EventSubscriber) -> Self {
         Self {
             tx,
+            events,
             buf: Lock::new(BytesMut::new()),
             cached_typeinfo: Lock::new(CachedTypeInfo {
                 typeinfo: None,
@@ -53,6 +56,31 @@ impl Client {
         self.tx.is_closed()
     }
 
+    /// Subscribe to [ConnectionEvent](crate::event::ConnectionEvent) of the connection.
+    ///
+    /// Only events happen after subscribing can be observed and subscriber lagging too far behind
+    /// would miss events. Events are not constructed when there is no subscriber so it costs
+    /// nothing when not used.
+    ///
+    /// # Examples:
+    /// ```rust
+    /// use xitca_postgres::{event::ConnectionEvent, AsyncIterator, Client};
+    ///
+    /// async fn log_events(cli: &Client) {
+    ///     let mut events = cli.events();
+    ///     tokio::spawn(async move {
+    ///         while let Some(event) = events.next().await {
+    ///             if let ConnectionEvent::Notice { fields } = event {
+    ///                 println!("{:?}: {:?}", fields.severity(), fields.message());
+    ///             }
+    ///         }
+    ///     });
+    /// }
+    /// ```
+    pub fn events(&self) -> Events {
+        self.events.subscribe()
+    }
+
     pub(crate) async fn send(&self, msg: BytesMut) -> Result<Response, Error> {
         self.tx.send(msg).await
     }
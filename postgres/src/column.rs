@@ -2,29 +2,41 @@ use core::fmt;
 
 use alloc::boxed::Box;
 
+use postgres_types::Oid;
+
 use super::Type;
 
 /// Information about a column of a query.
 #[derive(Clone)]
 pub struct Column {
     name: Box<str>,
+    oid: Oid,
     r#type: Type,
 }
 
 impl Column {
-    pub(crate) fn new(name: &str, r#type: Type) -> Column {
+    pub(crate) fn new(name: &str, oid: Oid, r#type: Type) -> Column {
         Column {
             name: Box::from(name),
+            oid,
             r#type,
         }
     }
 
-    /// Returns the name of the column.
+    /// Returns the name of the column. Alias is returned when the column is renamed with `AS`.
     pub fn name(&self) -> &str {
         &self.name
     }
 
+    /// Returns the oid of column's type as described by database.
+    pub fn type_oid(&self) -> Oid {
+        self.oid
+    }
+
     /// Returns the type of the column.
+    ///
+    /// Column of simple query always has [Type::TEXT] as it's values are transferred in text
+    /// format. Use [Column::type_oid] for the type described by database.
     pub fn r#type(&self) -> &Type {
         &self.r#type
     }
@@ -34,6 +46,7 @@ impl fmt::Debug for Column {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         fmt.debug_struct("Column")
             .field("name", &self.name)
+            .field("oid", &self.oid)
             .field("type", &self.r#type)
             .finish()
     }
@@ -0,0 +1,107 @@
+use core::fmt;
+
+use alloc::boxed::Box;
+
+/// Kind of command completed by database.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CommandKind {
+    Insert,
+    Delete,
+    Update,
+    Merge,
+    Select,
+    Move,
+    Fetch,
+    Copy,
+    /// Command does not report number of rows. (e.g. `CREATE TABLE`, `BEGIN`)
+    Other,
+}
+
+/// Tag of CommandComplete message identifying the completed command and number of rows it
+/// processed. (e.g. `UPDATE 42`, `INSERT 0 1`)
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct CommandTag {
+    tag: Box<str>,
+    kind: CommandKind,
+    rows: u64,
+}
+
+impl CommandTag {
+    pub(crate) fn parse(tag: &str) -> Self {
+        let mut words = tag.split(' ');
+
+        let kind = match words.next().unwrap_or_default() {
+            "INSERT" => CommandKind::Insert,
+            "DELETE" => CommandKind::Delete,
+            "UPDATE" => CommandKind::Update,
+            "MERGE" => CommandKind::Merge,
+            "SELECT" => CommandKind::Select,
+            "MOVE" => CommandKind::Move,
+            "FETCH" => CommandKind::Fetch,
+            "COPY" => CommandKind::Copy,
+            _ => CommandKind::Other,
+        };
+
+        // row count is always the last word. (INSERT tag carries an oid before it)
+        let rows = match kind {
+            CommandKind::Other => 0,
+            _ => words.next_back().and_then(|rows| rows.parse().ok()).unwrap_or(0),
+        };
+
+        Self {
+            tag: Box::from(tag),
+            kind,
+            rows,
+        }
+    }
+
+    // tag of empty query string.
+    pub(crate) fn empty() -> Self {
+        Self::parse("")
+    }
+
+    pub fn kind(&self) -> CommandKind {
+        self.kind
+    }
+
+    /// Number of rows processed by command. 0 is returned for command not reporting it.
+    pub fn rows(&self) -> u64 {
+        self.rows
+    }
+
+    /// Raw tag as sent by database.
+    pub fn as_str(&self) -> &str {
+        &self.tag
+    }
+}
+
+impl fmt::Display for CommandTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.tag)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let tag = CommandTag::parse("INSERT 0 3");
+        assert_eq!(tag.kind(), CommandKind::Insert);
+        assert_eq!(tag.rows(), 3);
+        assert_eq!(tag.as_str(), "INSERT 0 3");
+
+        let tag = CommandTag::parse("UPDATE 42");
+        assert_eq!(tag.kind(), CommandKind::Update);
+        assert_eq!(tag.rows(), 42);
+
+        let tag = CommandTag::parse("CREATE TABLE");
+        assert_eq!(tag.kind(), CommandKind::Other);
+        assert_eq!(tag.rows(), 0);
+
+        let tag = CommandTag::empty();
+        assert_eq!(tag.kind(), CommandKind::Other);
+        assert_eq!(tag.rows(), 0);
+    }
+}
@@ -1,6 +1,8 @@
 pub(crate) mod codec;
 pub(crate) mod generic;
 
+mod reconnect;
+
 #[cfg(feature = "tls")]
 mod tls;
 
@@ -23,7 +25,7 @@ use core::{
     pin::Pin,
 };
 
-use std::net::SocketAddr;
+use std::{io, net::SocketAddr};
 
 use postgres_protocol::message::backend;
 use xitca_io::bytes::BytesMut;
@@ -41,11 +43,20 @@ use xitca_tls::rustls::{ClientConnection, TlsStream};
 #[cfg(unix)]
 use xitca_io::net::UnixStream;
 
-pub(super) async fn connect(mut cfg: Config) -> Result<(Client, Driver), Error> {
+pub(super) async fn connect(mut cfg: Config, backlog: usize) -> Result<(Client, Driver), Error> {
     let mut err = None;
     let hosts = cfg.get_hosts().to_vec();
-    for host in hosts {
-        match _connect(host, &mut cfg).await {
+    let timeout = cfg.get_connect_timeout().copied();
+    // hosts are tried in order for failover.
+    for (idx, host) in hosts.into_iter().enumerate() {
+        let port = cfg.host_port(idx);
+        let res = match timeout {
+            Some(dur) => tokio::time::timeout(dur, _connect(host, port, &mut cfg, backlog))
+                .await
+                .unwrap_or_else(|_| Err(Error::from(io::Error::from(io::ErrorKind::TimedOut)))),
+            None => _connect(host, port, &mut cfg, backlog).await,
+        };
+        match res {
             Ok(t) => return Ok(t),
             Err(e) => err = Some(e),
         }
@@ -187,16 +198,8 @@ impl IntoFuture for Driver {
     }
 }
 
-async fn resolve(host: &str, ports: &[u16]) -> Result<Vec<SocketAddr>, Error> {
-    let addrs = tokio::net::lookup_host((host, 0))
-        .await?
-        .flat_map(|mut addr| {
-            ports.iter().map(move |port| {
-                addr.set_port(*port);
-                addr
-            })
-        })
-        .collect::<Vec<_>>();
+async fn resolve(host: &str, port: u16) -> Result<Vec<SocketAddr>, Error> {
+    let addrs = tokio::net::lookup_host((host, port)).await?.collect::<Vec<_>>();
     Ok(addrs)
 }
 
@@ -216,6 +219,8 @@ impl Driver {
                     drv.write_buf.into_inner(),
                     drv.read_buf.into_inner(),
                     drv.res,
+                    drv.events,
+                    drv.observed,
                 )
             }
             _ => todo!(),
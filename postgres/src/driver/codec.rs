@@ -1,8 +1,10 @@
+use alloc::collections::VecDeque;
+
 use postgres_protocol::message::backend;
 use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
 use xitca_io::bytes::BytesMut;
 
-use crate::error::Error;
+use crate::{error::Error, event::EventSender, observer::Observed};
 
 pub(super) type ResponseSender = UnboundedSender<BytesMut>;
 
@@ -25,6 +27,8 @@ impl Request {
 
 pub enum ResponseMessage {
     Normal { buf: BytesMut, complete: bool },
+    // notice is kept as raw bytes as it can belong to a query's execution.
+    Notice(BytesMut),
     Async(backend::Message),
 }
 
@@ -43,7 +47,14 @@ impl ResponseMessage {
             }
 
             match header.tag() {
-                backend::NOTICE_RESPONSE_TAG | backend::NOTIFICATION_RESPONSE_TAG | backend::PARAMETER_STATUS_TAG => {
+                backend::NOTICE_RESPONSE_TAG => {
+                    if idx == 0 {
+                        return Ok(Some(ResponseMessage::Notice(buf.split_to(len))));
+                    }
+
+                    break;
+                }
+                backend::NOTIFICATION_RESPONSE_TAG | backend::PARAMETER_STATUS_TAG => {
                     if idx == 0 {
                         // TODO:
                         // PagedBytesMut should never expose underlying BytesMut type as reference.
@@ -74,3 +85,46 @@ impl ResponseMessage {
         }
     }
 }
+
+// dispatch decoded messages to their response receivers and events subscribers. return the first
+// message that does not belong to any query.
+pub(super) fn decode(
+    buf: &mut BytesMut,
+    res: &mut VecDeque<ResponseSender>,
+    events: &mut EventSender,
+    observed: &mut Option<Observed>,
+) -> Result<Option<backend::Message>, Error> {
+    while let Some(msg) = ResponseMessage::try_from_buf(buf)? {
+        match msg {
+            ResponseMessage::Normal { buf, complete } => match res.front_mut() {
+                Some(tx) => {
+                    if let Some(observed) = observed {
+                        observed.response(&buf, complete);
+                    }
+                    let _ = tx.send(buf);
+                    if complete {
+                        let _ = res.pop_front();
+                    }
+                }
+                None => events.unsolicited(buf),
+            },
+            ResponseMessage::Notice(mut buf) => {
+                events.notice(&buf);
+                match res.front_mut() {
+                    Some(tx) => {
+                        if let Some(observed) = observed {
+                            observed.notice(&buf);
+                        }
+                        let _ = tx.send(buf);
+                    }
+                    None => return Ok(backend::Message::parse(&mut buf)?),
+                }
+            }
+            ResponseMessage::Async(msg) => {
+                events.message(&msg);
+                return Ok(Some(msg));
+            }
+        }
+    }
+    Ok(None)
+}
@@ -8,19 +8,20 @@ use alloc::collections::VecDeque;
 
 use std::io;
 
-use postgres_protocol::message::backend;
+use postgres_protocol::message::{backend, frontend};
 use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
-use tracing::error;
+use tracing::{error, warn};
 use xitca_io::{
     bytes::{BufInterest, BufRead, BufWrite, BytesMut, WriteBuf},
     io::{AsyncIo, Interest},
 };
 use xitca_unsafe_collection::futures::{Select as _, SelectOutput};
 
-use crate::{error::Error, iter::AsyncIterator};
+use crate::{error::Error, event::EventSender, iter::AsyncIterator, observer::Observed, session::prepare_session};
 
 use super::{
-    codec::{Request, ResponseMessage, ResponseSender},
+    codec::{self, Request, ResponseSender},
+    reconnect::Reconnect,
     Drive,
 };
 
@@ -35,6 +36,9 @@ pub(crate) struct GenericDriver<Io> {
     pub(crate) read_buf: PagedBytesMut,
     pub(crate) rx: Option<GenericDriverRx>,
     pub(crate) res: VecDeque<ResponseSender>,
+    pub(crate) events: EventSender,
+    pub(crate) observed: Option<Observed>,
+    pub(crate) reconnect: Option<Reconnect<Io>>,
 }
 
 impl<Io> GenericDriver<Io>
@@ -50,12 +54,15 @@ where
                 read_buf: PagedBytesMut::new(),
                 rx: Some(rx),
                 res: VecDeque::new(),
+                events: EventSender::new(),
+                observed: None,
+                reconnect: None,
             },
             tx,
         )
     }
 
-    pub(crate) async fn try_next(&mut self) -> Result<Option<backend::Message>, Error> {
+    async fn _try_next(&mut self) -> Result<Option<backend::Message>, Error> {
         loop {
             if let Some(msg) = self.try_decode()? {
                 return Ok(Some(msg));
@@ -89,7 +96,16 @@ where
             match select {
                 // batch message and keep polling.
                 SelectOutput::A(Some(req)) => {
+                    if let Some(ref mut reconnect) = self.reconnect {
+                        let write_buf = &mut self.write_buf;
+                        reconnect
+                            .session
+                            .observe(&req.msg, |msg| write_buf_extend(write_buf, msg));
+                    }
                     self.write_buf_extend(req.msg.as_ref());
+                    if let Some(ref mut observed) = self.observed {
+                        observed.start(&req.msg);
+                    }
                     self.res.push_back(req.tx);
                 }
                 SelectOutput::B(ready) => {
@@ -97,10 +113,16 @@ where
                     if ready.is_readable() {
                         self.try_read()?;
                     }
-                    if ready.is_writable() && self.try_write().is_err() {
-                        // write failed as server stopped reading.
-                        // drop channel so all pending request in it can be notified.
-                        self.rx = None;
+                    if ready.is_writable() {
+                        if let Err(e) = self.try_write() {
+                            // connection is lost and it's left for reconnect.
+                            if self.reconnect.is_some() && self.rx.is_some() {
+                                return Err(e.into());
+                            }
+                            // write failed as server stopped reading.
+                            // drop channel so all pending request in it can be notified.
+                            self.rx = None;
+                        }
                     }
                 }
                 SelectOutput::A(None) => self.rx = None,
@@ -108,13 +130,6 @@ where
         }
     }
 
-    // TODO: remove this feature gate.
-    #[cfg(not(feature = "quic"))]
-    pub(crate) async fn run(mut self) -> Result<(), Error> {
-        while self.try_next().await?.is_some() {}
-        Ok(())
-    }
-
     pub(crate) async fn send(&mut self, msg: BytesMut) -> Result<(), Error> {
         self.write_buf_extend(&msg);
         loop {
@@ -142,10 +157,7 @@ where
     }
 
     fn write_buf_extend(&mut self, buf: &[u8]) {
-        let _ = self.write_buf.write_buf(|w| {
-            w.extend_from_slice(buf);
-            Ok::<_, Infallible>(())
-        });
+        write_buf_extend(&mut self.write_buf, buf)
     }
 
     fn try_read(&mut self) -> Result<(), Error> {
@@ -164,19 +176,122 @@ where
     }
 
     fn try_decode(&mut self) -> Result<Option<backend::Message>, Error> {
-        while let Some(res) = ResponseMessage::try_from_buf(self.read_buf.get_mut())? {
-            match res {
-                ResponseMessage::Normal { buf, complete } => {
-                    let _ = self.res.front_mut().expect("out of bound must not happen").send(buf);
-                    if complete {
-                        let _ = self.res.pop_front();
+        codec::decode(
+            self.read_buf.get_mut(),
+            &mut self.res,
+            &mut self.events,
+            &mut self.observed,
+        )
+    }
+}
+
+impl<Io> GenericDriver<Io>
+where
+    Io: AsyncIo + Send,
+    for<'f> Io::Future<'f>: Send,
+{
+    pub(crate) async fn try_next(&mut self) -> Result<Option<backend::Message>, Error> {
+        let res = loop {
+            match self._try_next().await {
+                // reconnect when connection is lost and client is still alive.
+                Err(Error::Io(e)) if self.reconnect.is_some() && self.rx.is_some() => {
+                    warn!("connection lost: {e}. reconnecting");
+                    if let Err(e) = self.reconnect().await {
+                        break Err(e);
                     }
                 }
-                ResponseMessage::Async(msg) => return Ok(Some(msg)),
+                res => break res,
             }
+        };
+        self.events.on_next(&res);
+        res
+    }
+
+    // TODO: remove this feature gate.
+    #[cfg(not(feature = "quic"))]
+    pub(crate) async fn run(mut self) -> Result<(), Error> {
+        while self.try_next().await?.is_some() {}
+        Ok(())
+    }
+
+    #[cold]
+    #[inline(never)]
+    async fn reconnect(&mut self) -> Result<(), Error> {
+        // requests written to server can not be retried as they may have been executed. an empty
+        // message fails them with Error::ConnectionLost.
+        for tx in self.res.drain(..) {
+            let _ = tx.send(BytesMut::new());
+        }
+        if let Some(ref mut observed) = self.observed {
+            observed.lost();
+        }
+        self.write_buf.clear();
+        self.read_buf.get_mut().clear();
+
+        let mut reconnect = self.reconnect.take().unwrap();
+        reconnect.backlog.set_reconnecting(true);
+        let res = self._reconnect(&mut reconnect).await;
+        reconnect.backlog.set_reconnecting(false);
+        self.reconnect = Some(reconnect);
+
+        if res.is_ok() {
+            self.events.reconnected();
         }
-        Ok(None)
+
+        res
     }
+
+    async fn _reconnect(&mut self, reconnect: &mut Reconnect<Io>) -> Result<(), Error> {
+        let mut err = None;
+        for attempt in 0..reconnect.policy.max_attempts {
+            if attempt > 0 {
+                tokio::time::sleep(reconnect.policy.backoff).await;
+            }
+            match self.try_reconnect(reconnect).await {
+                Ok(_) => return Ok(()),
+                Err(e) => {
+                    warn!("reconnect attempt {} failed: {e}", attempt + 1);
+                    err = Some(e);
+                }
+            }
+        }
+        Err(err.unwrap_or(Error::ConnectionLost))
+    }
+
+    async fn try_reconnect(&mut self, reconnect: &mut Reconnect<Io>) -> Result<(), Error> {
+        self.io = match reconnect.cfg.get_connect_timeout().copied() {
+            Some(dur) => tokio::time::timeout(dur, reconnect.connect())
+                .await
+                .map_err(|_| io::Error::from(io::ErrorKind::TimedOut))??,
+            None => reconnect.connect().await?,
+        };
+        self.write_buf.clear();
+        self.read_buf.get_mut().clear();
+
+        prepare_session(self, &mut reconnect.cfg).await?;
+
+        if let Some(query) = reconnect.session.reset() {
+            let mut buf = BytesMut::new();
+            frontend::query(&query, &mut buf)?;
+            self.send(buf).await?;
+            loop {
+                match Drive::recv(self).await? {
+                    backend::Message::ReadyForQuery(_) => break,
+                    backend::Message::ErrorResponse(_) => error!("failed to replay LISTEN on new connection"),
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn write_buf_extend(write_buf: &mut WriteBuf, buf: &[u8]) {
+    let _ = write_buf.write_buf(|w| {
+        w.extend_from_slice(buf);
+        Ok::<_, Infallible>(())
+    });
 }
 
 impl<Io> AsyncIterator for GenericDriver<Io>
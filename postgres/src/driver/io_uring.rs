@@ -16,10 +16,10 @@ use xitca_io::{
 };
 use xitca_unsafe_collection::futures::{ReusableLocalBoxFuture, Select, SelectOutput};
 
-use crate::{error::Error, iter::AsyncIterator};
+use crate::{error::Error, event::EventSender, iter::AsyncIterator, observer::Observed};
 
 use super::{
-    codec::{ResponseMessage, ResponseSender},
+    codec::{self, ResponseSender},
     generic::GenericDriverRx,
 };
 
@@ -70,6 +70,8 @@ pub struct IoUringDriver<Io> {
     read_buf: BytesMut,
     rx: GenericDriverRx,
     res: VecDeque<ResponseSender>,
+    events: EventSender,
+    observed: Option<Observed>,
 }
 
 impl<Io> IoUringDriver<Io>
@@ -83,6 +85,8 @@ where
         write_buf: BytesMut,
         read_buf: BytesMut,
         res: VecDeque<ResponseSender>,
+        events: EventSender,
+        observed: Option<Observed>,
     ) -> Self {
         Self {
             io: Rc::new(io),
@@ -92,22 +96,24 @@ where
             read_buf,
             rx,
             res,
+            events,
+            observed,
         }
     }
 
     pub(crate) async fn try_next(&mut self) -> Result<Option<backend::Message>, Error> {
+        let res = self._try_next().await;
+        self.events.on_next(&res);
+        res
+    }
+
+    async fn _try_next(&mut self) -> Result<Option<backend::Message>, Error> {
         loop {
             if self.read_task.idle() {
-                while let Some(res) = ResponseMessage::try_from_buf(&mut self.read_buf)? {
-                    match res {
-                        ResponseMessage::Normal { buf, complete } => {
-                            let _ = self.res.front_mut().expect("out of bound must not happen").send(buf);
-                            if complete {
-                                let _ = self.res.pop_front();
-                            }
-                        }
-                        ResponseMessage::Async(msg) => return Ok(Some(msg)),
-                    }
+                if let Some(msg) =
+                    codec::decode(&mut self.read_buf, &mut self.res, &mut self.events, &mut self.observed)?
+                {
+                    return Ok(Some(msg));
                 }
 
                 let mut buf = mem::take(&mut self.read_buf);
@@ -139,6 +145,9 @@ where
                     match res {
                         SelectOutput::A(Some(req)) => {
                             self.write_buf.extend_from_slice(req.msg.as_ref());
+                            if let Some(ref mut observed) = self.observed {
+                                observed.start(&req.msg);
+                            }
                             self.res.push_back(req.tx);
                         }
                         SelectOutput::A(None) => return (Ok(0), mem::take(&mut self.write_buf)),
@@ -20,7 +20,9 @@ use crate::{
     client::Client,
     config::{Config, Host},
     error::{unexpected_eof_err, Error},
+    event::EventSender,
     iter::AsyncIterator,
+    session::prepare_session,
 };
 
 use super::{tls::dangerous_config, Drive, Driver};
@@ -82,14 +84,14 @@ impl ClientTx {
 
 #[cold]
 #[inline(never)]
-pub(super) async fn _connect(host: Host, cfg: &mut Config) -> Result<(Client, Driver), Error> {
+pub(super) async fn _connect(host: Host, port: u16, cfg: &mut Config, _: usize) -> Result<(Client, Driver), Error> {
     match host {
         Host::Udp(ref host) => {
-            let tx = connect_quic(host, cfg.get_ports()).await?;
+            let tx = connect_quic(host, port).await?;
             let streams = tx.inner.open_bi().await.unwrap();
             let mut drv = QuicDriver::new(streams);
-            let mut cli = Client::new(tx);
-            cli.prepare_session(&mut drv, cfg).await?;
+            prepare_session(&mut drv, cfg).await?;
+            let cli = Client::new(tx, drv.events.subscriber());
             drv.close_tx().await;
             Ok((cli, Driver::quic(drv)))
         }
@@ -99,8 +101,8 @@ pub(super) async fn _connect(host: Host, cfg: &mut Config) -> Result<(Client, Dr
 
 #[cold]
 #[inline(never)]
-async fn connect_quic(host: &str, ports: &[u16]) -> Result<ClientTx, Error> {
-    let addrs = super::resolve(host, ports).await?;
+async fn connect_quic(host: &str, port: u16) -> Result<ClientTx, Error> {
+    let addrs = super::resolve(host, port).await?;
     let mut endpoint = Endpoint::client("0.0.0.0:0".parse().unwrap())?;
 
     let cfg = dangerous_config(vec![QUIC_ALPN.to_vec()]);
@@ -127,6 +129,7 @@ pub(crate) struct QuicDriver {
     pub(crate) tx: SendStream,
     pub(crate) rx: RecvStream,
     buf: BytesMut,
+    events: EventSender,
 }
 
 impl QuicDriver {
@@ -135,6 +138,7 @@ impl QuicDriver {
             tx,
             rx,
             buf: BytesMut::new(),
+            events: EventSender::new(),
         }
     }
 
@@ -155,6 +159,15 @@ impl QuicDriver {
     }
 
     async fn try_next(&mut self) -> Result<Option<backend::Message>, Error> {
+        let res = self._try_next().await;
+        if let Ok(Some(ref msg)) = res {
+            self.events.message(msg);
+        }
+        self.events.on_next(&res);
+        res
+    }
+
+    async fn _try_next(&mut self) -> Result<Option<backend::Message>, Error> {
         loop {
             if let Some(msg) = backend::Message::parse(&mut self.buf)? {
                 return Ok(Some(msg));
@@ -3,11 +3,15 @@ use postgres_protocol::message::backend;
 use quinn::RecvStream;
 use xitca_io::bytes::BytesMut;
 
-use crate::error::{unexpected_eof_err, Error};
+use crate::{
+    error::{unexpected_eof_err, Error},
+    event::NoticeFields,
+};
 
 pub struct Response {
     rx: RecvStream,
     buf: BytesMut,
+    notices: Vec<NoticeFields>,
 }
 
 impl Response {
@@ -15,14 +19,29 @@ impl Response {
         Self {
             rx,
             buf: BytesMut::new(),
+            notices: Vec::new(),
         }
     }
 
+    pub(crate) fn notices(&self) -> &[NoticeFields] {
+        &self.notices
+    }
+
     pub(crate) async fn recv(&mut self) -> Result<backend::Message, Error> {
+        match self.recv_with_error().await? {
+            // TODO: error response.
+            backend::Message::ErrorResponse(_body) => Err(Error::ToDo),
+            msg => Ok(msg),
+        }
+    }
+
+    // receive message where ErrorResponse is passed through to caller.
+    pub(crate) async fn recv_with_error(&mut self) -> Result<backend::Message, Error> {
         loop {
             match backend::Message::parse(&mut self.buf)? {
-                // TODO: error response.
-                Some(backend::Message::ErrorResponse(_body)) => return Err(Error::ToDo),
+                Some(backend::Message::NoticeResponse(body)) => {
+                    self.notices.push(NoticeFields::try_from_fields(body.fields())?);
+                }
                 Some(msg) => return Ok(msg),
                 None => {
                     let chunk = self
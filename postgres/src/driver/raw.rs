@@ -6,6 +6,8 @@ mod tls;
 
 pub use self::response::Response;
 
+use alloc::sync::Arc;
+
 use std::io;
 
 use postgres_protocol::message::frontend;
@@ -20,16 +22,19 @@ use crate::{
     client::Client,
     config::{Config, Host, SslMode},
     error::{unexpected_eof_err, write_zero_err, Error},
+    observer::Observed,
+    session::prepare_session,
 };
 
 use super::{
     codec::Request,
     generic::{GenericDriver, GenericDriverTx},
+    reconnect::{Backlog, Reconnect},
     Driver,
 };
 
 #[derive(Debug)]
-pub(crate) struct ClientTx(GenericDriverTx);
+pub(crate) struct ClientTx(GenericDriverTx, Option<Arc<Backlog>>);
 
 impl ClientTx {
     pub(crate) fn is_closed(&self) -> bool {
@@ -37,6 +42,9 @@ impl ClientTx {
     }
 
     pub(crate) async fn send(&self, msg: BytesMut) -> Result<Response, Error> {
+        if let Some(ref backlog) = self.1 {
+            backlog.try_queue()?;
+        }
         let (tx, rx) = unbounded_channel();
         self.0.send(Request::new(tx, msg))?;
         Ok(Response::new(rx))
@@ -50,19 +58,32 @@ impl ClientTx {
 
 #[cold]
 #[inline(never)]
-pub(super) async fn _connect(host: Host, cfg: &mut Config) -> Result<(Client, Driver), Error> {
+pub(super) async fn _connect(
+    host: Host,
+    port: u16,
+    cfg: &mut Config,
+    backlog: usize,
+) -> Result<(Client, Driver), Error> {
     // this block have repeated code due to HRTB limitation.
     // namely for <'_> AsyncIo::Future<'_>: Send bound can not be expressed correctly.
     match host {
-        Host::Tcp(ref host) => {
-            let mut io = connect_tcp(host, cfg.get_ports()).await?;
+        Host::Tcp(host) => {
+            let mut io = connect_tcp(&host, port).await?;
             if should_connect_tls(&mut io, cfg).await? {
                 #[cfg(feature = "tls")]
                 {
-                    let io = tls::connect(io, host, cfg).await?;
-                    let (mut drv, tx) = GenericDriver::new(io);
-                    let mut cli = Client::new(ClientTx(tx));
-                    cli.prepare_session(&mut drv, cfg).await?;
+                    let io = tls::connect(io, &host, cfg).await?;
+                    let reconnect = Reconnect::new(cfg, backlog, move |cfg| {
+                        let host = host.clone();
+                        Box::pin(async move {
+                            let mut io = connect_tcp(&host, port).await?;
+                            if !_should_connect_tls(&mut io).await? {
+                                return Err(Error::TlsRefused);
+                            }
+                            tls::connect(io, &host, cfg).await
+                        })
+                    });
+                    let (drv, cli) = prepare(io, cfg, reconnect).await?;
                     Ok((cli, Driver::tls(drv)))
                 }
                 #[cfg(not(feature = "tls"))]
@@ -70,23 +91,32 @@ pub(super) async fn _connect(host: Host, cfg: &mut Config) -> Result<(Client, Dr
                     Err(crate::error::FeatureError::Tls.into())
                 }
             } else {
-                let (mut drv, tx) = GenericDriver::new(io);
-                let mut cli = Client::new(ClientTx(tx));
-                cli.prepare_session(&mut drv, cfg).await?;
+                let reconnect = Reconnect::new(cfg, backlog, move |_| {
+                    let host = host.clone();
+                    Box::pin(async move { connect_tcp(&host, port).await })
+                });
+                let (drv, cli) = prepare(io, cfg, reconnect).await?;
                 Ok((cli, Driver::tcp(drv)))
             }
         }
         #[cfg(unix)]
-        Host::Unix(ref host) => {
-            let mut io = xitca_io::net::UnixStream::connect(host).await?;
+        Host::Unix(host) => {
+            let mut io = xitca_io::net::UnixStream::connect(&host).await?;
             if should_connect_tls(&mut io, cfg).await? {
                 #[cfg(feature = "tls")]
                 {
-                    let host = host.to_string_lossy();
-                    let io = tls::connect(io, host.as_ref(), cfg).await?;
-                    let (mut drv, tx) = GenericDriver::new(io);
-                    let mut cli = Client::new(ClientTx(tx));
-                    cli.prepare_session(&mut drv, cfg).await?;
+                    let io = tls::connect(io, host.to_string_lossy().as_ref(), cfg).await?;
+                    let reconnect = Reconnect::new(cfg, backlog, move |cfg| {
+                        let host = host.clone();
+                        Box::pin(async move {
+                            let mut io = xitca_io::net::UnixStream::connect(&host).await?;
+                            if !_should_connect_tls(&mut io).await? {
+                                return Err(Error::TlsRefused);
+                            }
+                            tls::connect(io, host.to_string_lossy().as_ref(), cfg).await
+                        })
+                    });
+                    let (drv, cli) = prepare(io, cfg, reconnect).await?;
                     Ok((cli, Driver::unix_tls(drv)))
                 }
                 #[cfg(not(feature = "tls"))]
@@ -94,9 +124,11 @@ pub(super) async fn _connect(host: Host, cfg: &mut Config) -> Result<(Client, Dr
                     Err(crate::error::FeatureError::Tls.into())
                 }
             } else {
-                let (mut drv, tx) = GenericDriver::new(io);
-                let mut cli = Client::new(ClientTx(tx));
-                cli.prepare_session(&mut drv, cfg).await?;
+                let reconnect = Reconnect::new(cfg, backlog, move |_| {
+                    let host = host.clone();
+                    Box::pin(async move { Ok(xitca_io::net::UnixStream::connect(&host).await?) })
+                });
+                let (drv, cli) = prepare(io, cfg, reconnect).await?;
                 Ok((cli, Driver::unix(drv)))
             }
         }
@@ -104,8 +136,26 @@ pub(super) async fn _connect(host: Host, cfg: &mut Config) -> Result<(Client, Dr
     }
 }
 
-async fn connect_tcp(host: &str, ports: &[u16]) -> Result<TcpStream, Error> {
-    let addrs = super::resolve(host, ports).await?;
+async fn prepare<Io>(
+    io: Io,
+    cfg: &mut Config,
+    reconnect: Option<Reconnect<Io>>,
+) -> Result<(GenericDriver<Io>, Client), Error>
+where
+    Io: AsyncIo + Send,
+    for<'f> Io::Future<'f>: Send,
+{
+    let (mut drv, tx) = GenericDriver::new(io);
+    prepare_session(&mut drv, cfg).await?;
+    drv.observed = cfg.get_query_observer().cloned().map(Observed::new);
+    let backlog = reconnect.as_ref().map(|r| r.backlog.clone());
+    drv.reconnect = reconnect;
+    let cli = Client::new(ClientTx(tx, backlog), drv.events.subscriber());
+    Ok((drv, cli))
+}
+
+async fn connect_tcp(host: &str, port: u16) -> Result<TcpStream, Error> {
+    let addrs = super::resolve(host, port).await?;
 
     let mut err = None;
 
@@ -129,7 +179,7 @@ where
     match cfg.get_ssl_mode() {
         SslMode::Disable => Ok(false),
         mode => match (_should_connect_tls(io).await?, mode) {
-            (false, SslMode::Require) => Err(Error::ToDo),
+            (false, SslMode::Require) => Err(Error::TlsRefused),
             (bool, _) => Ok(bool),
         },
     }
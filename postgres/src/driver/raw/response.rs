@@ -9,11 +9,13 @@ use xitca_io::bytes::BytesMut;
 use crate::{
     driver::codec::ResponseReceiver,
     error::{unexpected_eof_err, Error},
+    event::NoticeFields,
 };
 
 pub struct Response {
     rx: ResponseReceiver,
     buf: BytesMut,
+    notices: Vec<NoticeFields>,
 }
 
 impl Response {
@@ -21,23 +23,43 @@ impl Response {
         Self {
             rx,
             buf: BytesMut::new(),
+            notices: Vec::new(),
         }
     }
 
-    pub(crate) fn recv(&mut self) -> impl Future<Output = Result<backend::Message, Error>> + '_ {
-        poll_fn(|cx| {
+    pub(crate) async fn recv(&mut self) -> Result<backend::Message, Error> {
+        match self.recv_with_error().await? {
+            // TODO: error response.
+            backend::Message::ErrorResponse(_body) => Err(Error::ToDo),
+            msg => Ok(msg),
+        }
+    }
+
+    // receive message where ErrorResponse is passed through to caller.
+    pub(crate) fn recv_with_error(&mut self) -> impl Future<Output = Result<backend::Message, Error>> + '_ {
+        poll_fn(|cx| loop {
             if self.buf.is_empty() {
                 self.buf = ready!(self.rx.poll_recv(cx)).ok_or_else(unexpected_eof_err)?;
+                // driver sends empty message when connection is lost and it's reconnecting.
+                if self.buf.is_empty() {
+                    return Poll::Ready(Err(Error::ConnectionLost));
+                }
             }
 
             let res = match backend::Message::parse(&mut self.buf)?.expect("must not parse message from empty buffer.")
             {
-                // TODO: error response.
-                backend::Message::ErrorResponse(_body) => Err(Error::ToDo),
+                backend::Message::NoticeResponse(body) => {
+                    self.notices.push(NoticeFields::try_from_fields(body.fields())?);
+                    continue;
+                }
                 msg => Ok(msg),
             };
 
-            Poll::Ready(res)
+            return Poll::Ready(res);
         })
     }
+
+    pub(crate) fn notices(&self) -> &[NoticeFields] {
+        &self.notices
+    }
 }
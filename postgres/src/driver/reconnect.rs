@@ -0,0 +1,335 @@
+//! reconnect driver to server on connection loss.
+
+// quic driver does not support reconnect.
+#![cfg_attr(feature = "quic", allow(dead_code))]
+
+use core::{
+    future::Future,
+    pin::Pin,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
+use alloc::sync::Arc;
+
+use std::collections::{HashMap, HashSet};
+
+use xitca_io::bytes::Bytes;
+
+use crate::{
+    config::{Config, RetryPolicy},
+    error::{backlog_full_err, Error},
+};
+
+pub(crate) type ConnectFuture<'a, Io> = Pin<Box<dyn Future<Output = Result<Io, Error>> + Send + 'a>>;
+
+type ConnectFn<Io> = Box<dyn for<'a> Fn(&'a mut Config) -> ConnectFuture<'a, Io> + Send>;
+
+pub(crate) struct Reconnect<Io> {
+    pub(crate) cfg: Config,
+    pub(crate) policy: RetryPolicy,
+    pub(crate) backlog: Arc<Backlog>,
+    pub(crate) session: SessionState,
+    connect: ConnectFn<Io>,
+}
+
+impl<Io> Reconnect<Io> {
+    // construct with a function connecting to the same host with the same transport as the lost
+    // connection. authentication and session setup are done by driver after it.
+    pub(crate) fn new<F>(cfg: &Config, backlog: usize, connect: F) -> Option<Self>
+    where
+        F: for<'a> Fn(&'a mut Config) -> ConnectFuture<'a, Io> + Send + 'static,
+    {
+        cfg.get_reconnect().map(|policy| Self {
+            cfg: cfg.clone(),
+            policy: *policy,
+            backlog: Arc::new(Backlog::new(backlog)),
+            session: SessionState::default(),
+            connect: Box::new(connect),
+        })
+    }
+
+    pub(crate) fn connect(&mut self) -> ConnectFuture<'_, Io> {
+        (self.connect)(&mut self.cfg)
+    }
+}
+
+// queries sent by client while driver is reconnecting.
+#[derive(Debug)]
+pub(crate) struct Backlog {
+    reconnecting: AtomicBool,
+    queued: AtomicUsize,
+    cap: usize,
+}
+
+impl Backlog {
+    fn new(cap: usize) -> Self {
+        Self {
+            reconnecting: AtomicBool::new(false),
+            queued: AtomicUsize::new(0),
+            cap,
+        }
+    }
+
+    // called by client before sending a query.
+    pub(crate) fn try_queue(&self) -> Result<(), Error> {
+        if self.reconnecting.load(Ordering::Acquire) && self.queued.fetch_add(1, Ordering::AcqRel) >= self.cap {
+            self.queued.fetch_sub(1, Ordering::AcqRel);
+            return Err(backlog_full_err().into());
+        }
+        Ok(())
+    }
+
+    pub(crate) fn set_reconnecting(&self, reconnecting: bool) {
+        self.queued.store(0, Ordering::Release);
+        self.reconnecting.store(reconnecting, Ordering::Release);
+    }
+}
+
+// state of session collected from frontend messages written to server. it's replayed on new
+// connection after reconnect.
+#[derive(Default)]
+pub(crate) struct SessionState {
+    // parse messages of named statements.
+    statements: HashMap<Box<str>, Bytes>,
+    // named statements prepared on current connection.
+    prepared: HashSet<Box<str>>,
+    // channels registered with LISTEN.
+    listens: Vec<String>,
+}
+
+impl SessionState {
+    // observe messages of a request before it's written to server. parse message of statement
+    // that is not prepared on current connection is passed to inject function and it must be
+    // written before the request.
+    pub(crate) fn observe(&mut self, mut msg: &[u8], mut inject: impl FnMut(&[u8])) {
+        while msg.len() >= 5 {
+            let len = u32::from_be_bytes([msg[1], msg[2], msg[3], msg[4]]) as usize + 1;
+            if msg.len() < len {
+                return;
+            }
+            let (this, rest) = msg.split_at(len);
+            msg = rest;
+
+            let mut body = &this[5..];
+            match this[0] {
+                b'P' => {
+                    let name = cstr(&mut body);
+                    if !name.is_empty() {
+                        self.statements.insert(name.into(), Bytes::copy_from_slice(this));
+                        self.prepared.insert(name.into());
+                    }
+                }
+                b'C' if body.first() == Some(&b'S') => {
+                    body = &body[1..];
+                    let name = cstr(&mut body);
+                    self.statements.remove(name);
+                    self.prepared.remove(name);
+                }
+                b'B' => {
+                    let _portal = cstr(&mut body);
+                    let name = cstr(&mut body);
+                    if !self.prepared.contains(name) {
+                        if let Some(parse) = self.statements.get(name) {
+                            inject(parse);
+                            self.prepared.insert(name.into());
+                        }
+                    }
+                }
+                b'Q' => self.observe_query(cstr(&mut body)),
+                _ => {}
+            }
+        }
+    }
+
+    fn observe_query(&mut self, query: &str) {
+        for stmt in query.split(';') {
+            let mut words = stmt.split_whitespace();
+            let (Some(cmd), Some(channel)) = (words.next(), words.next()) else {
+                continue;
+            };
+            let channel = normalize_ident(channel);
+            if cmd.eq_ignore_ascii_case("listen") {
+                if !self.listens.contains(&channel) {
+                    self.listens.push(channel);
+                }
+            } else if cmd.eq_ignore_ascii_case("unlisten") {
+                if channel == "*" {
+                    self.listens.clear();
+                } else {
+                    self.listens.retain(|c| *c != channel);
+                }
+            }
+        }
+    }
+
+    // reset state on new connection. return query replaying LISTEN registrations.
+    pub(crate) fn reset(&mut self) -> Option<String> {
+        self.prepared.clear();
+        (!self.listens.is_empty()).then(|| self.listens.iter().map(|c| format!("LISTEN {c};")).collect())
+    }
+}
+
+// unquoted identifier is case insensitive.
+fn normalize_ident(ident: &str) -> String {
+    if ident.starts_with('"') {
+        ident.to_string()
+    } else {
+        ident.to_ascii_lowercase()
+    }
+}
+
+// read a null terminated string and advance the slice.
+fn cstr<'a>(buf: &mut &'a [u8]) -> &'a str {
+    let end = buf.iter().position(|b| *b == 0).unwrap_or(buf.len());
+    let s = core::str::from_utf8(&buf[..end]).unwrap_or_default();
+    *buf = buf.get(end + 1..).unwrap_or_default();
+    s
+}
+
+#[cfg(test)]
+mod test {
+    use core::{future::IntoFuture, time::Duration};
+
+    use postgres_protocol::message::frontend;
+    use tokio::{
+        io::copy_bidirectional,
+        net::{TcpListener, TcpStream},
+        sync::broadcast,
+    };
+    use xitca_io::bytes::BytesMut;
+
+    use crate::{event::ConnectionEvent, iter::AsyncIterator, Postgres};
+
+    use super::*;
+
+    #[test]
+    fn session_state() {
+        let mut state = SessionState::default();
+        let mut buf = BytesMut::new();
+
+        frontend::parse("s1", "SELECT 1", [], &mut buf).unwrap();
+        frontend::sync(&mut buf);
+        let parse = buf.split();
+        state.observe(&parse, |_| panic!("statement is prepared"));
+
+        frontend::query("LISTEN foo; listen \"Bar\"; LISTEN FOO; UNLISTEN baz", &mut buf).unwrap();
+        state.observe(&buf.split(), |_| {});
+        assert_eq!(state.reset().unwrap(), "LISTEN foo;LISTEN \"Bar\";");
+
+        // statement is injected only once on new connection.
+        let res = frontend::bind(
+            "",
+            "s1",
+            [],
+            [] as [i32; 0],
+            |_: i32, _| unreachable!(),
+            Some(1),
+            &mut buf,
+        );
+        assert!(res.is_ok());
+        let bind = buf.split();
+        let mut injected = Vec::new();
+        state.observe(&bind, |msg| injected.extend_from_slice(msg));
+        state.observe(&bind, |msg| injected.extend_from_slice(msg));
+        assert_eq!(injected, &parse[..parse.len() - 5]);
+
+        // closed statement is forgotten.
+        frontend::close(b'S', "s1", &mut buf).unwrap();
+        state.observe(&buf.split(), |_| {});
+        frontend::query("UNLISTEN *", &mut buf).unwrap();
+        state.observe(&buf.split(), |_| {});
+        assert!(state.reset().is_none());
+        state.observe(&bind, |_| panic!("statement is closed"));
+    }
+
+    #[test]
+    fn backlog() {
+        let backlog = Backlog::new(1);
+        assert!(backlog.try_queue().is_ok());
+        assert!(backlog.try_queue().is_ok());
+
+        backlog.set_reconnecting(true);
+        assert!(backlog.try_queue().is_ok());
+        assert!(backlog.try_queue().is_err());
+
+        backlog.set_reconnecting(false);
+        assert!(backlog.try_queue().is_ok());
+    }
+
+    // tcp proxy to database. proxied connections are dropped when kill signal is sent.
+    async fn proxy() -> (String, broadcast::Sender<()>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (kill, _) = broadcast::channel(1);
+        let tx = kill.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                let mut kill = tx.subscribe();
+                tokio::spawn(async move {
+                    let mut upstream = TcpStream::connect("localhost:5432").await.unwrap();
+                    tokio::select! {
+                        _ = copy_bidirectional(&mut stream, &mut upstream) => {}
+                        _ = kill.recv() => {}
+                    }
+                });
+            }
+        });
+        (format!("postgres://postgres:postgres@{addr}/postgres"), kill)
+    }
+
+    #[tokio::test]
+    async fn reconnect() {
+        let (url, kill) = proxy().await;
+
+        let mut cfg = Config::try_from(url).unwrap();
+        cfg.reconnect(RetryPolicy {
+            max_attempts: 3,
+            backoff: Duration::from_millis(100),
+        });
+        let (cli, drv) = Postgres::new(cfg).connect().await.unwrap();
+        tokio::spawn(drv.into_future());
+        let mut events = cli.events();
+
+        let stmt = cli.prepare("SELECT $1::INT4 + 1").await.unwrap();
+        cli.execute_simple("LISTEN reconnect_chan").await.unwrap();
+
+        // query in flight at the time of connection loss fails and it's not retried.
+        let (res, _) = tokio::join!(cli.execute_simple("SELECT pg_sleep(1)"), async {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            kill.send(()).unwrap();
+        });
+        assert!(matches!(res, Err(Error::ConnectionLost)));
+
+        // next query waits for reconnect and statement is re-prepared on new connection.
+        let mut stream = cli.query(stmt.as_ref(), &[&1i32]).await.unwrap();
+        assert_eq!(stream.next().await.unwrap().unwrap().get::<i32>(0), 2);
+        drop(stream);
+
+        assert!(matches!(events.next().await.unwrap(), ConnectionEvent::Reconnected));
+
+        // LISTEN registration is replayed.
+        let mut stream = cli.query_simple("SELECT pg_listening_channels()").await.unwrap();
+        assert_eq!(stream.next().await.unwrap().unwrap().get(0), Some("reconnect_chan"));
+        drop(stream);
+
+        // statement keeps working on new connection.
+        let mut stream = cli.query(stmt.as_ref(), &[&2i32]).await.unwrap();
+        assert_eq!(stream.next().await.unwrap().unwrap().get::<i32>(0), 3);
+    }
+
+    #[tokio::test]
+    async fn no_reconnect() {
+        let (url, kill) = proxy().await;
+
+        let (cli, drv) = Postgres::new(url).connect().await.unwrap();
+        tokio::spawn(drv.into_future());
+
+        // without reconnect policy query in flight fails with unexpected eof io error.
+        let (res, _) = tokio::join!(cli.execute_simple("SELECT pg_sleep(1)"), async {
+            tokio::time::sleep(Duration::from_millis(200)).await;
+            kill.send(()).unwrap();
+        });
+        assert!(matches!(res, Err(Error::Io(ref e)) if e.kind() == std::io::ErrorKind::UnexpectedEof));
+    }
+}
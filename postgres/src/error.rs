@@ -1,12 +1,12 @@
 use core::{convert::Infallible, fmt};
 
-use alloc::string::String;
+use alloc::{boxed::Box, string::String};
 
 use std::{error, io};
 
 use tokio::sync::mpsc::error::SendError;
 
-use super::from_sql::FromSqlError;
+use super::{event::NoticeFields, from_sql::FromSqlError, Type};
 
 #[non_exhaustive]
 #[derive(Debug)]
@@ -15,8 +15,17 @@ pub enum Error {
     Authentication(AuthenticationError),
     UnexpectedMessage,
     Io(io::Error),
+    /// Connection is lost before query is finished and client is reconnecting with
+    /// [RetryPolicy](crate::RetryPolicy). The query may or may not have been executed by server and
+    /// it's not retried. Without the policy the loss is reported as [Error::Io].
+    ConnectionLost,
+    /// Server refused to upgrade connection to tls while it's required.
+    TlsRefused,
     FromSql(FromSqlError),
     InvalidColumnIndex(String),
+    Batch(BatchError),
+    Config(ConfigError),
+    Bind(BindError),
     ToDo,
 }
 
@@ -27,8 +36,13 @@ impl fmt::Display for Error {
             Self::Authentication(ref e) => fmt::Display::fmt(e, f),
             Self::UnexpectedMessage => f.write_str("unexpected message from server"),
             Self::Io(ref e) => fmt::Display::fmt(e, f),
+            Self::ConnectionLost => f.write_str("connection lost before query is finished"),
+            Self::TlsRefused => f.write_str("server refused tls connection"),
             Self::FromSql(ref e) => fmt::Display::fmt(e, f),
             Self::InvalidColumnIndex(ref name) => write!(f, "invalid column {name}"),
+            Self::Batch(ref e) => fmt::Display::fmt(e, f),
+            Self::Config(ref e) => fmt::Display::fmt(e, f),
+            Self::Bind(ref e) => fmt::Display::fmt(e, f),
             Self::ToDo => f.write_str("error informant is yet implemented"),
         }
     }
@@ -60,6 +74,121 @@ impl<T> From<SendError<T>> for Error {
     }
 }
 
+/// Error of a failed statement in script executed by [Client::batch_execute](crate::Client::batch_execute).
+#[derive(Debug)]
+pub struct BatchError {
+    pub(crate) index: usize,
+    pub(crate) tags: Vec<String>,
+    pub(crate) fields: NoticeFields,
+}
+
+impl BatchError {
+    /// Zero based index of the failed statement in script.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Command tags of statements completed before the failed one. e.g. `CREATE TABLE`, `INSERT 0 1`.
+    pub fn completed_tags(&self) -> &[String] {
+        &self.tags
+    }
+
+    /// Error fields sent by server.
+    pub fn fields(&self) -> &NoticeFields {
+        &self.fields
+    }
+}
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "statement {} of batch failed", self.index)?;
+        if let Some(msg) = self.fields.message() {
+            write!(f, ": {msg}")?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for BatchError {}
+
+impl From<BatchError> for Error {
+    fn from(e: BatchError) -> Self {
+        Self::Batch(e)
+    }
+}
+
+/// Error of parsing connection string into [Config](crate::Config).
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum ConfigError {
+    /// Parameter key is not recognized.
+    UnknownOption(String),
+    /// Parameter value is invalid for given key.
+    InvalidValue(&'static str),
+    /// Connection string is malformed.
+    Syntax(String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::UnknownOption(ref key) => write!(f, "unknown option `{key}`"),
+            Self::InvalidValue(key) => write!(f, "invalid value for option `{key}`"),
+            Self::Syntax(ref msg) => write!(f, "invalid connection string: {msg}"),
+        }
+    }
+}
+
+impl error::Error for ConfigError {}
+
+impl From<ConfigError> for Error {
+    fn from(e: ConfigError) -> Self {
+        Self::Config(e)
+    }
+}
+
+/// Error of binding parameters to a prepared [Statement](crate::statement::Statement).
+#[non_exhaustive]
+#[derive(Debug)]
+pub enum BindError {
+    /// Number of given parameters does not match the statement.
+    ParamCount { expected: usize, actual: usize },
+    /// Parameter can not be encoded as the type described by database. `index` is zero based.
+    ParamType {
+        index: usize,
+        expected: Type,
+        source: Box<dyn error::Error + Send + Sync>,
+    },
+}
+
+impl fmt::Display for BindError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::ParamCount { expected, actual } => write!(f, "expected {expected} parameters but got {actual}"),
+            Self::ParamType {
+                index,
+                ref expected,
+                ref source,
+            } => write!(f, "parameter ${} expected type {expected}: {source}", index + 1),
+        }
+    }
+}
+
+impl error::Error for BindError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match *self {
+            Self::ParamType { ref source, .. } => Some(&**source),
+            Self::ParamCount { .. } => None,
+        }
+    }
+}
+
+impl From<BindError> for Error {
+    fn from(e: BindError) -> Self {
+        Self::Bind(e)
+    }
+}
+
 #[derive(Debug)]
 pub enum AuthenticationError {
     MissingUserName,
@@ -117,6 +246,15 @@ pub(crate) fn unexpected_eof_err() -> io::Error {
     )
 }
 
+#[cold]
+#[inline(never)]
+pub(crate) fn backlog_full_err() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotConnected,
+        "client is reconnecting and backlog of queued queries is full",
+    )
+}
+
 #[cold]
 #[inline(never)]
 pub(crate) fn write_zero_err() -> io::Error {
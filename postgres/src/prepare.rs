@@ -28,7 +28,20 @@ type BoxedFuture<'a> = Pin<Box<dyn Future<Output = Result<Type, Error>> + 'a>>;
 type BoxedFuture<'a> = Pin<Box<dyn Future<Output = Result<Type, Error>> + Send + 'a>>;
 
 impl Client {
-    pub async fn prepare(&self, query: &str, types: &[Type]) -> Result<StatementGuarded<'_>, Error> {
+    /// Creates a new prepared statement.
+    ///
+    /// Types of parameters are inferred by database and [Statement::params] is used as the source
+    /// of truth when encoding parameters for the statement.
+    #[inline]
+    pub async fn prepare(&self, query: &str) -> Result<StatementGuarded<'_>, Error> {
+        self.prepare_typed(query, &[]).await
+    }
+
+    /// Like [Client::prepare], but allows the types of query parameters to be explicitly specified.
+    ///
+    /// The list of types may be smaller than the number of parameters - the types of the remaining
+    /// parameters will be inferred by database.
+    pub async fn prepare_typed(&self, query: &str, types: &[Type]) -> Result<StatementGuarded<'_>, Error> {
         self._prepare(query, types).await.map(|stmt| stmt.into_guarded(self))
     }
 }
@@ -69,7 +82,7 @@ impl Client {
             let mut it = row_description.fields();
             while let Some(field) = it.next().map_err(|_| Error::ToDo)? {
                 let type_ = self.get_type(field.type_oid()).await?;
-                let column = Column::new(field.name(), type_);
+                let column = Column::new(field.name(), field.type_oid(), type_);
                 columns.push(column);
             }
         }
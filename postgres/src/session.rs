@@ -5,9 +5,9 @@ use postgres_protocol::{
     authentication::{self, sasl},
     message::{backend, frontend},
 };
+use xitca_io::bytes::BytesMut;
 
 use super::{
-    client::Client,
     config::Config,
     driver::Drive,
     error::{AuthenticationError, Error},
@@ -23,10 +23,23 @@ pub enum TargetSessionAttrs {
     ReadWrite,
 }
 
-impl Client {
-    #[cold]
-    #[inline(never)]
-    pub(super) async fn prepare_session<D>(&mut self, drv: &mut D, cfg: &mut Config) -> Result<(), Error>
+// session is prepared on driver before client is constructed and again when driver reconnects.
+#[cold]
+#[inline(never)]
+pub(super) async fn prepare_session<D>(drv: &mut D, cfg: &mut Config) -> Result<(), Error>
+where
+    D: Drive,
+{
+    Session::default().prepare(drv, cfg).await
+}
+
+#[derive(Default)]
+struct Session {
+    buf: BytesMut,
+}
+
+impl Session {
+    async fn prepare<D>(&mut self, drv: &mut D, cfg: &mut Config) -> Result<(), Error>
     where
         D: Drive,
     {
@@ -167,11 +180,24 @@ impl Client {
         }
     }
 
-    async fn send_pass<D>(&self, drv: &mut D, pass: impl AsRef<[u8]>) -> Result<(), Error>
+    async fn send_pass<D>(&mut self, drv: &mut D, pass: impl AsRef<[u8]>) -> Result<(), Error>
     where
         D: Drive,
     {
         let msg = self.try_encode_with(|buf| frontend::password_message(pass.as_ref(), buf))?;
         drv.send(msg).await
     }
+
+    fn try_encode_with<F, E>(&mut self, f: F) -> Result<BytesMut, E>
+    where
+        F: FnOnce(&mut BytesMut) -> Result<(), E>,
+    {
+        match f(&mut self.buf) {
+            Ok(_) => Ok(self.buf.split()),
+            Err(e) => {
+                self.buf.clear();
+                Err(e)
+            }
+        }
+    }
 }
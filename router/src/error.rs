@@ -19,6 +19,9 @@ pub enum InsertError {
     UnnamedParam,
     /// Catch-all parameters are only allowed at the end of a path.
     InvalidCatchAll,
+    /// Only one repeated parameter is allowed per route and route with it can only contain static
+    /// and named parameter segments.
+    InvalidRepeat,
 }
 
 impl fmt::Display for InsertError {
@@ -34,6 +37,9 @@ impl fmt::Display for InsertError {
             Self::TooManyParams => f.write_str("only one parameter is allowed per path segment"),
             Self::UnnamedParam => f.write_str("parameters must be registered with a name"),
             Self::InvalidCatchAll => f.write_str("catch-all parameters are only allowed at the end of a route"),
+            Self::InvalidRepeat => {
+                f.write_str("only one repeated parameter is allowed per route and it must be a whole segment")
+            }
         }
     }
 }
@@ -57,9 +57,30 @@
 //! # }
 //! ```
 //!
+//! ### Repeated Parameters
+//!
+//! Repeated parameters like `/:revs+` match one or more whole segments and `/:revs*` match zero or
+//! more. They can be followed by other segments and only one is allowed per route. Matched segments
+//! are stored as one parameter joined by `/`:
+//!
+//! ```rust
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let mut m = xitca_router::Router::new();
+//! m.insert("/compare/:base/:revs+/summary", true)?;
+//!
+//! let matched = m.at("/compare/main/a/b/summary")?;
+//! assert_eq!(matched.params.get("base"), Some("main"));
+//! assert_eq!(matched.params.get("revs"), Some("a/b"));
+//! assert!(m.at("/compare/main/summary").is_err());
+//!
+//! # Ok(())
+//! # }
+//! ```
+//!
 //! ## Routing Priority
 //!
-//! Static and dynamic route segments are allowed to overlap. If they do, static segments will be given higher priority:
+//! Static and dynamic route segments are allowed to overlap. If they do, static segments will be given higher priority.
+//! Routes with repeated parameter are only matched when no other route matches:
 //!
 //! ```rust
 //! # fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -67,6 +88,7 @@
 //! m.insert("/", "Welcome!").unwrap()    ;  // priority: 1
 //! m.insert("/about", "About Me").unwrap(); // priority: 1
 //! m.insert("/*filepath", "...").unwrap();  // priority: 2
+//! m.insert("/tags/:tags+", "...").unwrap(); // priority: 3
 //!
 //! # Ok(())
 //! # }
@@ -77,6 +99,7 @@
 extern crate alloc;
 
 mod error;
+mod repeat;
 mod router;
 mod tree;
 
@@ -0,0 +1,193 @@
+use alloc::{string::String, vec::Vec};
+
+use xitca_unsafe_collection::bytes::BytesStr;
+
+use super::{params::Params, InsertError};
+
+/// Route with a repeated parameter. ex: `/compare/:base/:revs+/summary`
+///
+/// Routes are matched segment by segment and only when the radix tree has no match. This gives
+/// them lower priority than static and single parameter routes.
+#[derive(Clone)]
+#[cfg_attr(test, derive(Debug))]
+pub(crate) struct Repeated<T> {
+    route: String,
+    prefix: Vec<Segment>,
+    repeat: BytesStr,
+    min: usize,
+    suffix: Vec<Segment>,
+    value: T,
+}
+
+#[derive(Clone, PartialEq, Eq)]
+#[cfg_attr(test, derive(Debug))]
+enum Segment {
+    Static(String),
+    Param(BytesStr),
+}
+
+impl Segment {
+    // match path segment and store it's value when segment is a parameter.
+    fn capture(&self, segment: &str, params: &mut Params) -> Option<()> {
+        match *self {
+            Self::Static(ref s) => (s == segment).then_some(()),
+            Self::Param(ref name) => {
+                if segment.is_empty() {
+                    return None;
+                }
+                params.push(name.clone(), segment);
+                Some(())
+            }
+        }
+    }
+
+    fn as_static(&self) -> Option<&str> {
+        match *self {
+            Self::Static(ref s) => Some(s),
+            Self::Param(_) => None,
+        }
+    }
+}
+
+impl<T> Repeated<T> {
+    /// Check if route contains a repeated parameter. ex: `:name+` or `:name*`
+    pub(crate) fn is_repeated(route: &str) -> bool {
+        route.split('/').any(is_repeat)
+    }
+
+    pub(crate) fn new(route: &str, value: T) -> Result<Self, InsertError> {
+        let mut prefix = Vec::new();
+        let mut suffix = Vec::new();
+        let mut repeat = None;
+
+        for segment in route.split('/') {
+            let seg = if is_repeat(segment) {
+                if repeat.is_some() {
+                    return Err(InsertError::InvalidRepeat);
+                }
+                let name = &segment[1..segment.len() - 1];
+                if name.contains([':', '*']) {
+                    return Err(InsertError::InvalidRepeat);
+                }
+                let min = usize::from(segment.ends_with('+'));
+                repeat = Some((BytesStr::try_from(name.as_bytes())?, min));
+                continue;
+            } else if let Some(name) = segment.strip_prefix(':') {
+                if name.is_empty() {
+                    return Err(InsertError::UnnamedParam);
+                }
+                if name.contains([':', '*']) {
+                    return Err(InsertError::InvalidRepeat);
+                }
+                Segment::Param(BytesStr::try_from(name.as_bytes())?)
+            } else if segment.contains([':', '*']) {
+                // catch-all and parameter in the middle of segment can't be used along with
+                // repeated parameter.
+                return Err(InsertError::InvalidRepeat);
+            } else {
+                Segment::Static(String::from(segment))
+            };
+
+            match repeat {
+                Some(_) => suffix.push(seg),
+                None => prefix.push(seg),
+            }
+        }
+
+        let (repeat, min) = repeat.unwrap();
+
+        Ok(Self {
+            route: String::from(route),
+            prefix,
+            repeat,
+            min,
+            suffix,
+            value,
+        })
+    }
+
+    /// Check if there is a path can be matched by both routes.
+    pub(crate) fn conflict(&self, other: &Self) -> Result<(), InsertError> {
+        let lo = core::cmp::max(self.min_len(), other.min_len());
+        // segments in the middle are all repeated ones after this length and checking longer
+        // path does not give any different result.
+        let hi = lo + self.prefix.len() + self.suffix.len() + other.prefix.len() + other.suffix.len();
+
+        for len in lo..=hi {
+            let overlap = (0..len).all(|idx| match (self.segment_at(idx, len), other.segment_at(idx, len)) {
+                (Some(a), Some(b)) => a == b,
+                // parameter only matches non empty segment.
+                (Some(s), None) | (None, Some(s)) => !s.is_empty(),
+                (None, None) => true,
+            });
+
+            if overlap {
+                return Err(InsertError::Conflict {
+                    with: other.route.clone(),
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    pub(crate) fn at(&self, path: &str) -> Option<(&T, Params)> {
+        let count = path.split('/').count();
+
+        if count < self.min_len() {
+            return None;
+        }
+
+        // segments with their offset in path.
+        let mut segments = path.split('/').scan(0, |offset, segment| {
+            let start = *offset;
+            *offset += segment.len() + 1;
+            Some((start, segment))
+        });
+        let mut params = Params::new();
+
+        for seg in self.prefix.iter() {
+            seg.capture(segments.next()?.1, &mut params)?;
+        }
+
+        // repeated segments are stored as one parameter with it's raw value joined by `/`.
+        let n = count - self.prefix.len() - self.suffix.len();
+        let mut range = None;
+        for _ in 0..n {
+            let (start, segment) = segments.next()?;
+            if segment.is_empty() {
+                return None;
+            }
+            let first = range.map_or(start, |(first, _)| first);
+            range = Some((first, start + segment.len()));
+        }
+        let value = range.map(|(start, end)| &path[start..end]).unwrap_or("");
+        params.push(self.repeat.clone(), value);
+
+        for seg in self.suffix.iter() {
+            seg.capture(segments.next()?.1, &mut params)?;
+        }
+
+        Some((&self.value, params))
+    }
+
+    fn min_len(&self) -> usize {
+        self.prefix.len() + self.min + self.suffix.len()
+    }
+
+    // static segment at given index of path with given number of segments. None for parameter and
+    // repeated parameter.
+    fn segment_at(&self, idx: usize, len: usize) -> Option<&str> {
+        if idx < self.prefix.len() {
+            self.prefix[idx].as_static()
+        } else if idx >= len - self.suffix.len() {
+            self.suffix[idx - (len - self.suffix.len())].as_static()
+        } else {
+            None
+        }
+    }
+}
+
+fn is_repeat(segment: &str) -> bool {
+    segment.len() > 2 && segment.starts_with(':') && (segment.ends_with('+') || segment.ends_with('*'))
+}
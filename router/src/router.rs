@@ -1,4 +1,4 @@
-use super::{params::Params, tree::Node, InsertError, MatchError};
+use super::{params::Params, repeat::Repeated, tree::Node, InsertError, MatchError};
 
 /// A URL router.
 ///
@@ -7,12 +7,16 @@ use super::{params::Params, tree::Node, InsertError, MatchError};
 #[cfg_attr(test, derive(Debug))]
 pub struct Router<T> {
     root: Node<T>,
+    repeated: Vec<Repeated<T>>,
 }
 
 impl<T> Router<T> {
     /// Construct a new router.
     pub const fn new() -> Self {
-        Self { root: Node::new() }
+        Self {
+            root: Node::new(),
+            repeated: Vec::new(),
+        }
     }
 
     /// Insert a route.
@@ -29,7 +33,17 @@ impl<T> Router<T> {
     /// # }
     /// ```
     pub fn insert(&mut self, route: impl Into<String>, value: T) -> Result<(), InsertError> {
-        self.root.insert(route, value)
+        let route = route.into();
+
+        if !Repeated::<T>::is_repeated(&route) {
+            return self.root.insert(route, value);
+        }
+
+        let repeated = Repeated::new(&route, value)?;
+        self.repeated.iter().try_for_each(|other| repeated.conflict(other))?;
+        self.repeated.push(repeated);
+
+        Ok(())
     }
 
     /// Tries to find a value in the router matching the given path.
@@ -49,7 +63,15 @@ impl<T> Router<T> {
     /// ```
     #[inline]
     pub fn at(&self, path: &str) -> Result<Match<&T>, MatchError> {
-        self.root.at(path).map(|(value, params)| Match { value, params })
+        match self.root.at(path) {
+            Ok((value, params)) => Ok(Match { value, params }),
+            Err(e) => self
+                .repeated
+                .iter()
+                .find_map(|route| route.at(path))
+                .map(|(value, params)| Match { value, params })
+                .ok_or(e),
+        }
     }
 
     #[cfg(feature = "test_helpers")]
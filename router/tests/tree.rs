@@ -556,6 +556,46 @@ tsr_tests! {
     },
 }
 
+match_tests! {
+    repeated {
+        routes = [
+            "/compare/:base/:revs+/summary",
+            "/compare/:base/:rev/summary",
+            "/tags/popular",
+            "/tags/:tags+",
+            "/files/:dir/:path*",
+        ],
+        "/compare/main/a/summary"     :: "/compare/:base/:rev/summary"   => { "base" => "main", "rev" => "a" },
+        "/compare/main/a/b/c/summary" :: "/compare/:base/:revs+/summary" => { "base" => "main", "revs" => "a/b/c" },
+        "/compare/main/summary"       :: ""                              => None,
+        "/compare/main/a//summary"    :: ""                              => None,
+        "/compare/main/a/b/summary/"  :: ""                              => None,
+        "/tags/popular"               :: "/tags/popular"                 => {},
+        "/tags/popular/rust"          :: "/tags/:tags+"                  => { "tags" => "popular/rust" },
+        "/tags/a"                     :: "/tags/:tags+"                  => { "tags" => "a" },
+        "/tags"                       :: ""                              => None,
+        "/tags/"                      :: ""                              => None,
+        "/files/home"                 :: "/files/:dir/:path*"            => { "dir" => "home", "path" => "" },
+        "/files/home/a/b"             :: "/files/:dir/:path*"            => { "dir" => "home", "path" => "a/b" },
+    },
+}
+
+insert_tests! {
+    repeated_conflict {
+        "/a/:x+/b"       => Ok(()),
+        "/a/:y+/c"       => Ok(()),
+        "/a/:y+/:z"      => Err(InsertError::Conflict { with: "/a/:x+/b".into() }),
+        "/a/b/:y*"       => Err(InsertError::Conflict { with: "/a/:x+/b".into() }),
+        "/a/:x+/b/c"     => Err(InsertError::Conflict { with: "/a/:y+/c".into() }),
+        "/a/:x*/"        => Ok(()),
+        "/a/:x+"         => Err(InsertError::Conflict { with: "/a/:x+/b".into() }),
+        "/a/:x+/:y+"     => Err(InsertError::InvalidRepeat),
+        "/a/:x+/*rest"   => Err(InsertError::InvalidRepeat),
+        "/a/v:x/:y+"     => Err(InsertError::InvalidRepeat),
+        "/a/:/:y+"       => Err(InsertError::UnnamedParam),
+    },
+}
+
 macro_rules! match_tests {
     ($($name:ident {
         routes = $routes:expr,
@@ -20,6 +20,8 @@ pub struct Builder {
     pub(crate) shutdown_timeout: Duration,
     pub(crate) on_worker_start: Box<dyn Fn() -> Pin<Box<dyn Future<Output = ()> + Send>> + Send + Sync>,
     backlog: u32,
+    #[cfg(feature = "http3")]
+    h3_transport: Option<xitca_io::net::H3TransportConfig>,
 }
 
 impl Default for Builder {
@@ -41,6 +43,8 @@ impl Builder {
             shutdown_timeout: Duration::from_secs(30),
             on_worker_start: Box::new(|| Box::pin(async {})),
             backlog: 2048,
+            #[cfg(feature = "http3")]
+            h3_transport: None,
         }
     }
 
@@ -235,6 +239,15 @@ impl Builder {
 
 #[cfg(feature = "http3")]
 impl Builder {
+    /// Set QUIC transport tuning for Http/3 listeners.
+    ///
+    /// Only applies to listeners bound with [Builder::bind_all] and [Builder::bind_h3] after this
+    /// method is called.
+    pub fn h3_transport(mut self, transport: xitca_io::net::H3TransportConfig) -> Self {
+        self.h3_transport = Some(transport);
+        self
+    }
+
     /// Bind to both Tcp and Udp of the same address to enable http/1/2/3 handling
     /// with single service.
     pub fn bind_all<N, A, F>(
@@ -256,7 +269,7 @@ impl Builder {
 
         self = self._bind(name.as_ref(), addr, factory)?;
 
-        let builder = xitca_io::net::UdpListenerBuilder::new(addr, config).backlog(self.backlog);
+        let builder = self.udp_builder(addr, config);
 
         self.listeners
             .get_mut(name.as_ref())
@@ -284,8 +297,20 @@ impl Builder {
             .next()
             .ok_or_else(|| io::Error::new(io::ErrorKind::AddrNotAvailable, "Can not parse SocketAddr"))?;
 
-        let builder = xitca_io::net::UdpListenerBuilder::new(addr, config).backlog(self.backlog);
+        let builder = self.udp_builder(addr, config);
 
         Ok(self._listen(name, Some(builder), factory))
     }
+
+    fn udp_builder(
+        &self,
+        addr: net::SocketAddr,
+        config: xitca_io::net::H3ServerConfig,
+    ) -> xitca_io::net::UdpListenerBuilder {
+        let builder = xitca_io::net::UdpListenerBuilder::new(addr, config).backlog(self.backlog);
+        match self.h3_transport {
+            Some(ref transport) => builder.transport(transport.clone()),
+            None => builder,
+        }
+    }
 }
@@ -16,3 +16,9 @@ pub type EnclosedFactory<F, S> = PipelineT<F, S, marker::BuildEnclosed>;
 
 /// Type alias for specialized [PipelineT] type with [marker::MapErr].
 pub type MapErrorServiceFactory<F, S> = PipelineT<F, S, marker::MapErr>;
+
+/// Type alias for specialized [PipelineT] type with [marker::BuildMap].
+pub type MapFactory<F, S> = PipelineT<F, S, marker::BuildMap>;
+
+/// Type alias for specialized [PipelineT] type with [marker::BuildMapErr].
+pub type MapErrFactory<F, S> = PipelineT<F, S, marker::BuildMapErr>;
@@ -19,7 +19,7 @@ use xitca_http::{
 };
 use xitca_io::{
     bytes::Bytes,
-    net::{Stream as NetStream, TcpStream},
+    net::{H3TransportConfig, Stream as NetStream, TcpStream},
 };
 use xitca_server::{Builder, ServerFuture, ServerHandle};
 use xitca_service::{ready::ReadyService, Service};
@@ -91,6 +91,57 @@ where
 
 /// A specialized http/3 server
 pub fn test_h3_server<F, I, B, E>(factory: F) -> Result<TestServerHandle, Error>
+where
+    F: Fn() -> I + Send + Sync + 'static,
+    I: Service + 'static,
+    I::Response: ReadyService + Service<Request<RequestExt<h3::RequestBody>>, Response = HResponse<B>> + 'static,
+    <I::Response as Service<Request<RequestExt<h3::RequestBody>>>>::Error: fmt::Debug,
+    I::Error: error::Error + 'static,
+    B: Stream<Item = Result<Bytes, E>> + 'static,
+    E: fmt::Debug + 'static,
+{
+    test_h3_server_with_early_data(h3::EarlyData::Disable, factory)
+}
+
+/// A specialized http/3 server with given strategy for 0-RTT early data.
+pub fn test_h3_server_with_early_data<F, I, B, E>(
+    early_data: h3::EarlyData,
+    factory: F,
+) -> Result<TestServerHandle, Error>
+where
+    F: Fn() -> I + Send + Sync + 'static,
+    I: Service + 'static,
+    I::Response: ReadyService + Service<Request<RequestExt<h3::RequestBody>>, Response = HResponse<B>> + 'static,
+    <I::Response as Service<Request<RequestExt<h3::RequestBody>>>>::Error: fmt::Debug,
+    I::Error: error::Error + 'static,
+    B: Stream<Item = Result<Bytes, E>> + 'static,
+    E: fmt::Debug + 'static,
+{
+    h3_server(early_data, None, factory)
+}
+
+/// A specialized http/3 server with given QUIC transport tuning.
+pub fn test_h3_server_with_transport<F, I, B, E>(
+    transport: H3TransportConfig,
+    factory: F,
+) -> Result<TestServerHandle, Error>
+where
+    F: Fn() -> I + Send + Sync + 'static,
+    I: Service + 'static,
+    I::Response: ReadyService + Service<Request<RequestExt<h3::RequestBody>>, Response = HResponse<B>> + 'static,
+    <I::Response as Service<Request<RequestExt<h3::RequestBody>>>>::Error: fmt::Debug,
+    I::Error: error::Error + 'static,
+    B: Stream<Item = Result<Bytes, E>> + 'static,
+    E: fmt::Debug + 'static,
+{
+    h3_server(h3::EarlyData::Disable, Some(transport), factory)
+}
+
+fn h3_server<F, I, B, E>(
+    early_data: h3::EarlyData,
+    transport: Option<H3TransportConfig>,
+    factory: F,
+) -> Result<TestServerHandle, Error>
 where
     F: Fn() -> I + Send + Sync + 'static,
     I: Service + 'static,
@@ -119,16 +170,21 @@ where
         .with_single_cert(cert, key)?;
 
     config.alpn_protocols = vec![b"h3".to_vec(), b"h3-29".to_vec(), b"h3-28".to_vec(), b"h3-27".to_vec()];
+    // quic requires max early data size to be u32::MAX for enabling 0-RTT.
+    config.max_early_data_size = u32::MAX;
 
     let config = h3_quinn::quinn::ServerConfig::with_crypto(std::sync::Arc::new(config));
 
-    let handle = Builder::new()
-        .worker_threads(1)
-        .server_threads(1)
-        .disable_signal()
+    let mut builder = Builder::new().worker_threads(1).server_threads(1).disable_signal();
+
+    if let Some(transport) = transport {
+        builder = builder.h3_transport(transport);
+    }
+
+    let handle = builder
         .bind_h3("test_server", addr, config, move || {
             let f = factory();
-            HttpServiceBuilder::h3(f)
+            HttpServiceBuilder::h3(f).early_data(early_data)
         })?
         .build();
 
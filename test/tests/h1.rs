@@ -1,22 +1,34 @@
 use futures_util::StreamExt;
 use std::{
-    io::{Read, Write},
-    net::TcpStream,
-    time::Duration,
+    convert::Infallible,
+    future::Future,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpStream},
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
 };
 
+use tokio::io::AsyncBufReadExt;
 use xitca_client::Client;
 use xitca_http::{
-    body::{BoxStream, ResponseBody},
+    body::{BodyErrorHook, BodySizeProbe, BoxStream, RequestBody, ResponseBody},
     bytes::{Bytes, BytesMut},
+    config::{HttpServiceConfig, RequestRatePolicy, WriteBufStrategy},
     h1,
     http::{
         header::{self, HeaderValue, CONNECTION},
-        Method, Request, RequestExt, Response,
+        HeaderCaseMap, Method, RawRequestHead, Request, RequestArrival, RequestBodyLimit, RequestExt, Response,
+        StatusCode,
     },
+    util::drain::Drain,
+    HttpServiceBuilder,
 };
-use xitca_service::fn_service;
-use xitca_test::{test_h1_server, Error};
+use xitca_service::{fn_service, ready::ReadyService};
+use xitca_test::{test_h1_server, test_server, Error};
 
 #[tokio::test]
 async fn h1_get() -> Result<(), Error> {
@@ -220,14 +232,1444 @@ async fn h1_keepalive() -> Result<(), Error> {
     Ok(())
 }
 
-async fn handle(req: Request<RequestExt<h1::RequestBody>>) -> Result<Response<ResponseBody>, Error> {
-    // Some yield for testing h1 dispatcher's concurrent future handling.
-    tokio::task::yield_now().await;
-    tokio::task::yield_now().await;
-    tokio::task::yield_now().await;
+#[tokio::test]
+async fn h1_pipeline_error_close() -> Result<(), Error> {
+    let mut handle = test_h1_server(|| fn_service(handle))?;
 
-    match (req.method(), req.uri().path()) {
-        (&Method::GET, "/") => Ok(Response::new(Bytes::from("GET Response").into())),
+    let mut stream = TcpStream::connect(handle.addr())?;
+
+    // second request has malformed header line.
+    stream.write_all(b"GET / HTTP/1.1\r\nhost: localhost\r\n\r\nGET / HTTP/1.1\r\nhost localhost\r\n\r\n")?;
+
+    let res = read_until_close(&mut stream)?;
+    let res = String::from_utf8(res)?;
+
+    // the response before the error is marked as the last one of connection.
+    let (first, second) = res.split_once("GET Response").unwrap();
+    assert!(first.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(first.contains("\r\nconnection: close\r\n"));
+    assert!(second.starts_with("HTTP/1.1 400 Bad Request\r\n"));
+    assert!(second.contains("\r\nconnection: close\r\n"));
+
+    handle.try_handle()?.stop(true);
+
+    handle.await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn h1_raw_request_head() -> Result<(), Error> {
+    let mut handle = test_server::<_, _, (xitca_io::net::TcpStream, SocketAddr)>(|| {
+        let config = HttpServiceConfig::new().keep_raw_request_head();
+        HttpServiceBuilder::h1(fn_service(raw_head_handle)).config(config)
+    })?;
+
+    let mut stream = TcpStream::connect(handle.addr())?;
+
+    const REQ: &[u8] =
+        b"GET /raw?a=b HTTP/1.1\r\nx-Custom-HEADER: Foo\r\nhost: localhost\r\nACCEPT:*/*\r\ncontent-length: 0\r\n\r\n";
+
+    stream.write_all(REQ)?;
+
+    // response body is the raw request head echoed by server.
+    let mut buf = Vec::new();
+    let mut chunk = [0; 256];
+    while !buf.ends_with(REQ) {
+        let n = stream.read(&mut chunk)?;
+        assert_ne!(n, 0, "connection closed before raw request head is received");
+        buf.extend_from_slice(&chunk[..n]);
+    }
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+async fn raw_head_handle(req: Request<RequestExt<h1::RequestBody>>) -> Result<Response<ResponseBody>, Error> {
+    let RawRequestHead(head) = req.extensions().get::<RawRequestHead>().cloned().unwrap();
+    Ok(Response::new(head.into()))
+}
+
+#[tokio::test]
+async fn h1_normalize_request_headers() -> Result<(), Error> {
+    let mut handle = test_server::<_, _, (xitca_io::net::TcpStream, SocketAddr)>(|| {
+        let config = HttpServiceConfig::new().keep_raw_request_head();
+        HttpServiceBuilder::h1(fn_service(header_names_handle)).config(config)
+    })?;
+
+    let send = |req: &[u8]| -> Result<String, Error> {
+        let mut stream = TcpStream::connect(handle.addr())?;
+        stream.write_all(req)?;
+        Ok(String::from_utf8(read_until_close(&mut stream)?)?)
+    };
+
+    // hop-by-hop headers and the ones listed in connection header are removed.
+    const HOP: &str = "GET / HTTP/1.1\r\nconnection: close, x-hop\r\nx-hop: 1\r\nkeep-alive: timeout=5\r\n\
+                       proxy-connection: keep-alive\r\nte: trailers\r\ntrailer: x-sum\r\nx-end: 2\r\n\r\n";
+    let res = send(HOP.as_bytes())?;
+    assert!(res.starts_with("HTTP/1.1 200"));
+    let (_, body) = res.split_once("\r\n\r\n").unwrap();
+    let (names, raw) = body.split_once('\n').unwrap();
+    assert_eq!(names, "x-end");
+    // raw request head is not affected.
+    assert_eq!(raw, HOP);
+
+    // transfer-encoding is removed after it's used for decoding body.
+    let res =
+        send(b"POST / HTTP/1.1\r\nconnection: close\r\ntransfer-encoding: chunked\r\n\r\n5\r\nhello\r\n0\r\n\r\n")?;
+    assert!(res.starts_with("HTTP/1.1 200"));
+    assert!(res.contains("\r\n\r\nhello|\n"));
+
+    // same value content-length duplicates are collapsed.
+    let res = send(b"POST / HTTP/1.1\r\nconnection: close\r\ncontent-length: 5\r\ncontent-length: 5\r\n\r\nhello")?;
+    assert!(res.starts_with("HTTP/1.1 200"));
+    assert!(res.contains("\r\n\r\nhello|content-length\n"));
+
+    // connection and upgrade headers of upgrade request are kept.
+    let res = send(b"GET / HTTP/1.1\r\nconnection: upgrade, x-hop\r\nupgrade: foo\r\nx-hop: 1\r\n\r\n")?;
+    assert!(res.starts_with("HTTP/1.1 200"));
+    assert!(res.contains("\r\n\r\nconnection,upgrade\n"));
+
+    // duplicate singleton headers are rejected.
+    for req in [
+        &b"GET / HTTP/1.1\r\ncontent-type: text/plain\r\ncontent-type: application/json\r\n\r\n"[..],
+        b"GET / HTTP/1.1\r\nhost: a\r\nhost: b\r\n\r\n",
+        b"POST / HTTP/1.1\r\ncontent-length: 5\r\ncontent-length: 6\r\n\r\nhello",
+    ] {
+        assert!(send(req)?.starts_with("HTTP/1.1 400"));
+    }
+
+    handle.try_handle()?.stop(false);
+    handle.await?;
+
+    // normalization can be disabled.
+    let mut handle = test_server::<_, _, (xitca_io::net::TcpStream, SocketAddr)>(|| {
+        let config = HttpServiceConfig::new().disable_request_header_normalization();
+        HttpServiceBuilder::h1(fn_service(header_names_handle)).config(config)
+    })?;
+
+    let mut stream = TcpStream::connect(handle.addr())?;
+    stream.write_all(b"GET / HTTP/1.1\r\nconnection: close, x-hop\r\nx-hop: 1\r\nhost: a\r\nhost: b\r\n\r\n")?;
+    let res = String::from_utf8(read_until_close(&mut stream)?)?;
+    assert!(res.starts_with("HTTP/1.1 200"));
+    assert!(res.ends_with("\r\n\r\nconnection,host,x-hop\n"));
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+// respond with request body and sorted header names of request followed by raw request head.
+async fn header_names_handle(req: Request<RequestExt<h1::RequestBody>>) -> Result<Response<ResponseBody>, Error> {
+    let mut names = req
+        .headers()
+        .keys()
+        .map(|name| name.as_str().to_owned())
+        .collect::<Vec<_>>();
+    names.sort();
+    names.dedup();
+
+    let raw = req
+        .extensions()
+        .get::<RawRequestHead>()
+        .map(|RawRequestHead(head)| String::from_utf8_lossy(head).into_owned())
+        .unwrap_or_default();
+
+    let mut body = String::new();
+    // upgrade request body is not read as it does not end until connection is closed.
+    if req.method() == Method::POST {
+        let mut stream = req.into_body();
+        while let Some(chunk) = stream.next().await {
+            body.push_str(std::str::from_utf8(&chunk?)?);
+        }
+        body.push('|');
+    }
+
+    let body = format!("{body}{}\n{raw}", names.join(","));
+    Ok(Response::new(Bytes::from(body).into()))
+}
+
+#[tokio::test]
+async fn h1_preserve_header_case() -> Result<(), Error> {
+    let mut handle = test_server::<_, _, (xitca_io::net::TcpStream, SocketAddr)>(|| {
+        let config = HttpServiceConfig::new().preserve_header_case();
+        HttpServiceBuilder::h1(fn_service(header_case_handle)).config(config)
+    })?;
+
+    let mut stream = TcpStream::connect(handle.addr())?;
+    stream.write_all(b"GET / HTTP/1.1\r\nconnection: close\r\n\r\n")?;
+    let res = read_until_close(&mut stream)?;
+
+    // mapped name uses it's spelling and the others are title cased.
+    let head =
+        b"HTTP/1.1 200 OK\r\nX-API-Key: 996\r\nX-Request-Id: 1\r\nConnection: close\r\nContent-Length: 5\r\nDate: ";
+    assert!(res.starts_with(head), "{}", String::from_utf8_lossy(&res));
+    assert!(res.ends_with(b"\r\n\r\nhello"));
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+async fn header_case_handle(_: Request<RequestExt<h1::RequestBody>>) -> Result<Response<ResponseBody>, Error> {
+    let mut map = HeaderCaseMap::new();
+    map.insert("X-API-Key");
+
+    let mut res = Response::new(Bytes::from_static(b"hello").into());
+    res.headers_mut().insert("x-api-key", HeaderValue::from_static("996"));
+    res.headers_mut().insert("x-request-id", HeaderValue::from_static("1"));
+    res.extensions_mut().insert(map);
+    Ok(res)
+}
+
+#[tokio::test]
+async fn h1_no_body_status() -> Result<(), Error> {
+    let mut handle = test_h1_server(|| fn_service(no_body_handle))?;
+
+    let mut stream = TcpStream::connect(handle.addr())?;
+    stream.write_all(
+        b"GET /204 HTTP/1.1\r\n\r\nGET /304 HTTP/1.1\r\n\r\nGET /200 HTTP/1.1\r\nconnection: close\r\n\r\n",
+    )?;
+    let res = String::from_utf8(read_until_close(&mut stream)?)?;
+
+    // body bytes are never written and pipelined request is parsed from where the head ends.
+    let res = res.split("HTTP/1.1 ").skip(1).collect::<Vec<_>>();
+    assert_eq!(res.len(), 3, "{res:?}");
+
+    assert!(res[0].starts_with("204 No Content\r\n"));
+    assert!(!res[0].contains("content-length"));
+    assert!(!res[0].contains("transfer-encoding"));
+    assert!(res[0].ends_with("\r\n\r\n"));
+
+    // content-length set by handler describes the body of 200 response and is kept.
+    assert!(res[1].starts_with("304 Not Modified\r\n"));
+    assert!(res[1].contains("\r\ncontent-length: 5\r\n"));
+    assert!(res[1].ends_with("\r\n\r\n"));
+
+    assert!(res[2].starts_with("200 OK\r\n"));
+    assert!(res[2].ends_with("\r\n\r\n/200"));
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+async fn no_body_handle(req: Request<RequestExt<h1::RequestBody>>) -> Result<Response<ResponseBody>, Error> {
+    let path = req.uri().path();
+    let (status, body) = match path {
+        "/204" => (StatusCode::NO_CONTENT, ResponseBody::from(Bytes::from_static(b"hello"))),
+        "/304" => (
+            StatusCode::NOT_MODIFIED,
+            ResponseBody::from(Bytes::from_static(b"hello")),
+        ),
+        _ => (
+            StatusCode::OK,
+            ResponseBody::from(Bytes::copy_from_slice(path.as_bytes())),
+        ),
+    };
+    let mut res = Response::new(body);
+    *res.status_mut() = status;
+    if status == StatusCode::NO_CONTENT {
+        res.headers_mut()
+            .insert(header::TRANSFER_ENCODING, HeaderValue::from_static("chunked"));
+    } else if status == StatusCode::NOT_MODIFIED {
+        res.headers_mut()
+            .insert(header::CONTENT_LENGTH, HeaderValue::from_static("5"));
+    }
+    Ok(res)
+}
+
+#[tokio::test]
+async fn h1_coalesce_body() -> Result<(), Error> {
+    let mut handle = test_h1_server(|| fn_service(coalesce_handle))?;
+
+    let mut stream = TcpStream::connect(handle.addr())?;
+    stream.write_all(
+        b"GET /small HTTP/1.1\r\n\r\nHEAD /small HTTP/1.1\r\n\r\nGET /chunked HTTP/1.1\r\n\r\n\
+          GET /large HTTP/1.1\r\nconnection: close\r\n\r\n",
+    )?;
+    let res = String::from_utf8(read_until_close(&mut stream)?)?;
+
+    let res = res.split("HTTP/1.1 200 OK\r\n").skip(1).collect::<Vec<_>>();
+    assert_eq!(res.len(), 4, "{res:?}");
+
+    let small = "a".repeat(200);
+    assert!(res[0].contains("content-length: 200\r\n"));
+    assert!(res[0].ends_with(&format!("\r\n\r\n{small}")));
+
+    // head response has content-length of would be body and no body bytes.
+    assert!(res[1].contains("content-length: 200\r\n"));
+    assert!(res[1].ends_with("\r\n\r\n"));
+
+    // chunked encoding set by handler opts out of coalescing and body is framed as usual.
+    assert!(res[2].contains("transfer-encoding: chunked\r\n"));
+    assert!(res[2].ends_with(&format!("\r\n\r\nC8\r\n{small}\r\n0\r\n\r\n")));
+
+    let large = "a".repeat(8 * 1024);
+    assert!(res[3].contains("content-length: 8192\r\n"));
+    assert!(res[3].ends_with(&format!("\r\n\r\n{large}")));
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+async fn coalesce_handle(req: Request<RequestExt<h1::RequestBody>>) -> Result<Response<ResponseBody>, Error> {
+    let mut res = match req.uri().path() {
+        "/large" => Response::new(Bytes::from(vec![b'a'; 8 * 1024]).into()),
+        _ if req.method() == Method::HEAD => {
+            let mut res = Response::new(ResponseBody::None);
+            res.headers_mut()
+                .insert(header::CONTENT_LENGTH, HeaderValue::from_static("200"));
+            res
+        }
+        _ => Response::new(Bytes::from(vec![b'a'; 200]).into()),
+    };
+    if req.uri().path() == "/chunked" {
+        res.headers_mut()
+            .insert(header::TRANSFER_ENCODING, HeaderValue::from_static("chunked"));
+    }
+    Ok(res)
+}
+
+#[tokio::test]
+async fn h1_response_head_too_large() -> Result<(), Error> {
+    let mut handle = test_h1_server(|| fn_service(large_header_handle))?;
+
+    let mut stream = TcpStream::connect(handle.addr())?;
+    stream.write_all(b"GET / HTTP/1.1\r\n\r\n")?;
+    let res = read_until_close(&mut stream)?;
+
+    // a clean canned response is sent and none of the oversized head is on the wire.
+    assert!(res.starts_with(b"HTTP/1.1 500"));
+    assert!(res.ends_with(b"\r\n\r\n"));
+    assert!(res.len() < 1024);
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+async fn collect_handle(req: Request<RequestExt<h1::RequestBody>>) -> Result<Response<ResponseBody>, Error> {
+    let mut body = req.into_body();
+    let mut buf = BytesMut::new();
+    while let Some(bytes) = body.next().await {
+        buf.extend_from_slice(&bytes?);
+    }
+    Ok(Response::new(buf.freeze().into()))
+}
+
+async fn large_header_handle(_: Request<RequestExt<h1::RequestBody>>) -> Result<Response<ResponseBody>, Error> {
+    let mut res = Response::new(Bytes::from_static(b"hello").into());
+    let value = HeaderValue::from_bytes(&vec![b'a'; 10 * 1024 * 1024])?;
+    res.headers_mut().insert(header::SET_COOKIE, value);
+    Ok(res)
+}
+
+#[tokio::test]
+async fn h1_body_too_large() -> Result<(), Error> {
+    let mut handle = test_server::<_, _, (xitca_io::net::TcpStream, SocketAddr)>(|| {
+        let config = HttpServiceConfig::new().max_request_body_size(16);
+        HttpServiceBuilder::h1(fn_service(|req: Request<RequestExt<h1::RequestBody>>| async move {
+            match req.uri().path() {
+                "/collect" => collect_handle(req).await,
+                "/raise" => {
+                    req.extensions().get::<RequestBodyLimit>().unwrap().set(1024);
+                    collect_handle(req).await
+                }
+                _ => handle(req).await,
+            }
+        }))
+        .config(config)
+    })?;
+
+    // body within limit is handled as usual.
+    let mut stream = TcpStream::connect(handle.addr())?;
+    stream.write_all(b"POST / HTTP/1.1\r\ncontent-length: 12\r\ncontent-type: text/plain\r\n\r\nHello,World!")?;
+    let mut buf = Vec::new();
+    let mut chunk = [0; 256];
+    while !buf.ends_with(b"Hello,World!") {
+        let n = stream.read(&mut chunk)?;
+        assert_ne!(n, 0, "connection closed before response is received");
+        buf.extend_from_slice(&chunk[..n]);
+    }
+    assert!(buf.starts_with(b"HTTP/1.1 200"));
+
+    // oversized body with expect header gets 413 and never a 100 continue.
+    let mut stream = TcpStream::connect(handle.addr())?;
+    stream.write_all(b"POST / HTTP/1.1\r\ncontent-length: 1024\r\nexpect: 100-continue\r\n\r\n")?;
+    let res = read_until_close(&mut stream)?;
+    assert!(res.starts_with(b"HTTP/1.1 413"));
+    assert!(!res.windows(12).any(|w| w == b"100 Continue"));
+
+    // oversized body without expect header gets 413 and connection is closed without reading body.
+    let mut stream = TcpStream::connect(handle.addr())?;
+    stream.write_all(b"POST / HTTP/1.1\r\ncontent-length: 1024\r\ncontent-type: text/plain\r\n\r\npartial")?;
+    let res = read_until_close(&mut stream)?;
+    assert!(res.starts_with(b"HTTP/1.1 413"));
+
+    // chunked body is counted as it's decoded and gets 413 once it goes over limit.
+    let mut stream = TcpStream::connect(handle.addr())?;
+    stream.write_all(b"POST /collect HTTP/1.1\r\ntransfer-encoding: chunked\r\n\r\n8\r\nHello,Wo\r\n")?;
+    stream.write_all(b"c\r\nrld!Hello,Wo\r\n0\r\n\r\n")?;
+    let res = read_until_close(&mut stream)?;
+    assert!(res.starts_with(b"HTTP/1.1 413"));
+
+    // chunked body within limit is handled as usual.
+    let mut stream = TcpStream::connect(handle.addr())?;
+    stream.write_all(b"POST /collect HTTP/1.1\r\ntransfer-encoding: chunked\r\nconnection: close\r\n\r\n")?;
+    stream.write_all(b"8\r\nHello,Wo\r\n4\r\nrld!\r\n0\r\n\r\n")?;
+    let res = read_until_close(&mut stream)?;
+    assert!(res.starts_with(b"HTTP/1.1 200"));
+    assert!(res.ends_with(b"Hello,World!"));
+
+    // service raising the limit before reading body accepts body larger than the default one.
+    let mut stream = TcpStream::connect(handle.addr())?;
+    stream.write_all(b"POST /raise HTTP/1.1\r\ncontent-length: 24\r\nconnection: close\r\n\r\n")?;
+    stream.write_all(b"Hello,World!Hello,World!")?;
+    let res = read_until_close(&mut stream)?;
+    assert!(res.starts_with(b"HTTP/1.1 200"));
+    assert!(res.ends_with(b"Hello,World!Hello,World!"));
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn h1_expect_continue() -> Result<(), Error> {
+    const CONTINUE: &[u8] = b"HTTP/1.1 100 Continue\r\n\r\n";
+
+    let mut handle = test_h1_server(|| fn_service(expect_handle))?;
+
+    // rejecting handler never touches body and client is not invited to upload.
+    let mut stream = TcpStream::connect(handle.addr())?;
+    stream.write_all(b"POST /reject HTTP/1.1\r\ncontent-length: 5\r\nexpect: 100-continue\r\n\r\n")?;
+    let res = read_until_close(&mut stream)?;
+    assert!(res.starts_with(b"HTTP/1.1 401"));
+    assert!(!res.windows(12).any(|w| w == b"100 Continue"));
+
+    // accepting handler gets continue sent before client uploads body.
+    let mut stream = TcpStream::connect(handle.addr())?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    stream.write_all(b"POST /accept HTTP/1.1\r\ncontent-length: 5\r\nexpect: 100-continue\r\n\r\n")?;
+    let mut buf = [0; CONTINUE.len()];
+    stream.read_exact(&mut buf)?;
+    assert_eq!(buf, CONTINUE);
+    stream.write_all(b"hello")?;
+
+    let mut reader = BufReader::new(&mut stream);
+    assert_eq!(read_response(&mut reader)?, b"hello");
+
+    // request without body needs no continue and connection is kept alive.
+    reader
+        .get_mut()
+        .write_all(b"GET /accept HTTP/1.1\r\nexpect: 100-continue\r\n\r\n")?;
+    assert_eq!(read_response(&mut reader)?, b"");
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+async fn expect_handle(req: Request<RequestExt<h1::RequestBody>>) -> Result<Response<ResponseBody>, Error> {
+    if req.uri().path() == "/reject" {
+        let mut res = Response::new(Bytes::new().into());
+        *res.status_mut() = StatusCode::UNAUTHORIZED;
+        return Ok(res);
+    }
+
+    let (_, mut body) = req.into_body().replace_body(());
+    let mut buf = BytesMut::new();
+    while let Some(chunk) = body.next().await {
+        buf.extend_from_slice(&chunk?);
+    }
+    Ok(Response::new(buf.freeze().into()))
+}
+
+#[tokio::test]
+async fn h1_uri_too_long() -> Result<(), Error> {
+    let mut handle = test_h1_server(|| fn_service(handle))?;
+
+    let line = format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(100 * 1024));
+
+    // only part of request line is sent and server must respond without waiting for the rest of it.
+    let mut stream = TcpStream::connect(handle.addr())?;
+    stream.write_all(&line.as_bytes()[..16 * 1024])?;
+    let res = read_until_close(&mut stream)?;
+    assert!(res.starts_with(b"HTTP/1.1 414"));
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn h1_too_many_headers() -> Result<(), Error> {
+    let mut handle = test_h1_server(|| fn_service(handle))?;
+
+    let mut req = String::from("GET / HTTP/1.1\r\n");
+    for i in 0..128 {
+        req.push_str(&format!("x-header-{i}: foo\r\n"));
+    }
+    req.push_str("\r\n");
+
+    let mut stream = TcpStream::connect(handle.addr())?;
+    stream.write_all(req.as_bytes())?;
+    let res = read_until_close(&mut stream)?;
+    assert!(res.starts_with(b"HTTP/1.1 431"));
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn h1_request_head_timeout() -> Result<(), Error> {
+    let mut handle = test_server::<_, _, (xitca_io::net::TcpStream, SocketAddr)>(|| {
+        let config = HttpServiceConfig::new()
+            .keep_alive_timeout(Duration::from_secs(1))
+            .request_head_timeout(Duration::from_secs(1));
+        HttpServiceBuilder::h1(fn_service(handle)).config(config)
+    })?;
+
+    // partial request head times out with 408 response.
+    let mut stream = TcpStream::connect(handle.addr())?;
+    stream.write_all(b"GET / HTTP/1.1\r\nhost: ")?;
+    let res = read_until_close(&mut stream)?;
+    assert!(res.starts_with(b"HTTP/1.1 408"));
+    assert!(res.windows(17).any(|w| w.eq_ignore_ascii_case(b"connection: close")));
+
+    // partial request head pipelined after a complete request times out with 408 response.
+    let mut stream = TcpStream::connect(handle.addr())?;
+    stream.write_all(&[SIMPLE_GET_REQ, b"GET / HTT"].concat())?;
+    let res = read_until_close(&mut stream)?;
+    assert!(res.starts_with(b"HTTP/1.1 200"));
+    assert_eq!(res.windows(8).filter(|w| w.starts_with(b"HTTP/1.1")).count(), 2);
+    assert!(res.windows(12).any(|w| w == b"HTTP/1.1 408"));
+
+    // idle keep-alive connection is closed silently.
+    let mut stream = TcpStream::connect(handle.addr())?;
+    stream.write_all(SIMPLE_GET_REQ)?;
+    let res = read_until_close(&mut stream)?;
+    assert!(res.starts_with(b"HTTP/1.1 200"));
+    assert!(res.ends_with(b"GET Response"));
+
+    let mut stream = TcpStream::connect(handle.addr())?;
+    assert!(read_until_close(&mut stream)?.is_empty());
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn h1_write_buf_strategy_switch() -> Result<(), Error> {
+    let mut handle = test_server::<_, _, (xitca_io::net::TcpStream, SocketAddr)>(|| {
+        let config = HttpServiceConfig::new().write_buf_strategy(WriteBufStrategy::Auto);
+        HttpServiceBuilder::h1(fn_service(write_buf_handle)).config(config)
+    })?;
+
+    let stream = TcpStream::connect(handle.addr())?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+
+    let req = |path: &str| format!("GET {path} HTTP/1.1\r\nhost: localhost\r\n\r\n");
+
+    // alternate response pattern on one connection so the write buffer switches back and forth.
+    for path in ["/small", "/large", "/small", "/large"] {
+        for _ in 0..40 {
+            writer.write_all(req(path).as_bytes())?;
+            assert_eq!(read_response(&mut reader)?, expected_body(path));
+        }
+    }
+
+    // pipelined requests with mixed response pattern.
+    let pipelined = (0..64)
+        .map(|i| if i % 8 < 4 { "/large" } else { "/small" })
+        .collect::<Vec<_>>();
+    let reqs = pipelined.iter().map(|path| req(path)).collect::<String>();
+    writer.write_all(reqs.as_bytes())?;
+    for path in pipelined {
+        assert_eq!(read_response(&mut reader)?, expected_body(path));
+    }
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn h1_request_arrival() -> Result<(), Error> {
+    let mut handle = test_server::<_, _, (xitca_io::net::TcpStream, SocketAddr)>(|| {
+        let config = HttpServiceConfig::new().request_arrival_time();
+        HttpServiceBuilder::h1(fn_service(arrival_handle)).config(config)
+    })?;
+
+    let server_url = format!("http://{}/", handle.ip_port_string());
+
+    let c = Client::new();
+
+    for _ in 0..3 {
+        let mut res = c.get(&server_url)?.send().await?;
+        assert_eq!(res.status().as_u16(), 200);
+        let body = res.string().await?;
+        let (queue, handled) = body.split_once(' ').unwrap();
+        let (queue, handled) = (queue.parse::<u64>()?, handled.parse::<u64>()?);
+
+        // durations are in micro seconds.
+        assert!(queue < 5_000, "queue duration: {queue}");
+        assert!((50_000..60_000).contains(&handled), "handle duration: {handled}");
+    }
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn h1_request_head_cache() -> Result<(), Error> {
+    let mut handle = test_server::<_, _, (xitca_io::net::TcpStream, SocketAddr)>(|| {
+        let config = HttpServiceConfig::new().cache_request_head();
+        HttpServiceBuilder::h1(fn_service(head_cache_handle)).config(config)
+    })?;
+
+    let stream = TcpStream::connect(handle.addr())?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+
+    let req = |path: &str, agent: &str, body: &str| {
+        format!(
+            "POST {path} HTTP/1.1\r\nhost: localhost\r\nx-agent: {agent}\r\ncontent-length: {}\r\n\r\n{body}",
+            body.len()
+        )
+    };
+
+    // identical heads with different bodies and heads differing in one byte.
+    let reqs = [
+        ("/ingest", "1", "aaaa"),
+        ("/ingest", "1", "bbbb"),
+        ("/ingest", "2", "cccc"),
+        ("/ingesT", "2", "dddd"),
+        ("/ingesT", "2", "ee"),
+        ("/ingest", "1", "ffff"),
+    ];
+
+    for (path, agent, body) in reqs {
+        writer.write_all(req(path, agent, body).as_bytes())?;
+        assert_eq!(read_response(&mut reader)?, format!("{path} {agent} {body}").as_bytes());
+    }
+
+    // pipelined.
+    let pipelined = reqs.iter().map(|(p, a, b)| req(p, a, b)).collect::<String>();
+    writer.write_all(pipelined.as_bytes())?;
+    for (path, agent, body) in reqs {
+        assert_eq!(read_response(&mut reader)?, format!("{path} {agent} {body}").as_bytes());
+    }
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+async fn head_cache_handle(req: Request<RequestExt<h1::RequestBody>>) -> Result<Response<ResponseBody>, Error> {
+    let head = format!(
+        "{} {} ",
+        req.uri().path(),
+        req.headers().get("x-agent").unwrap().to_str().unwrap()
+    );
+    let (_, mut body) = req.into_body().replace_body(());
+    let mut buf = BytesMut::from(head.as_bytes());
+    while let Some(chunk) = body.next().await {
+        buf.extend_from_slice(&chunk?);
+    }
+    Ok(Response::new(buf.freeze().into()))
+}
+
+async fn arrival_handle(req: Request<RequestExt<h1::RequestBody>>) -> Result<Response<ResponseBody>, Error> {
+    let arrival = *req.extensions().get::<RequestArrival>().unwrap();
+    let queue = arrival.elapsed();
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let handled = arrival.elapsed();
+    let body = format!("{} {}", queue.as_micros(), handled.as_micros());
+    Ok(Response::new(Bytes::from(body).into()))
+}
+
+fn expected_body(path: &str) -> Vec<u8> {
+    match path {
+        "/small" => (0..64u8).flat_map(|i| [b'a' + i % 26; 16]).collect(),
+        _ => vec![b'L'; 64 * 1024],
+    }
+}
+
+async fn write_buf_handle(req: Request<RequestExt<h1::RequestBody>>) -> Result<Response<ResponseBody>, Error> {
+    let body = expected_body(req.uri().path());
+    let res = match req.uri().path() {
+        // many small chunks with chunked transfer encoding.
+        "/small" => {
+            let chunks = body.chunks(16).map(Bytes::copy_from_slice).collect::<Vec<_>>();
+            let stream = futures_util::stream::iter(chunks.into_iter().map(Ok::<_, Infallible>));
+            // unknown size hint to force chunked encoding.
+            let stream = futures_util::stream::unfold(stream, |mut stream| async move {
+                stream.next().await.map(|chunk| (chunk, stream))
+            });
+            Response::new(ResponseBody::box_stream(stream))
+        }
+        // one large chunk with content-length.
+        _ => Response::new(Bytes::from(body).into()),
+    };
+    Ok(res)
+}
+
+// read one response with either content-length or chunked body and return the body.
+fn read_response(reader: &mut impl BufRead) -> Result<Vec<u8>, Error> {
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    assert!(line.starts_with("HTTP/1.1 200"), "malformed status line: {line:?}");
+
+    let mut len = None;
+    let mut chunked = false;
+    loop {
+        line.clear();
+        reader.read_line(&mut line)?;
+        let l = line.trim_end().to_ascii_lowercase();
+        if l.is_empty() {
+            break;
+        }
+        if let Some(v) = l.strip_prefix("content-length: ") {
+            len = Some(v.parse::<usize>().unwrap());
+        }
+        if l == "transfer-encoding: chunked" {
+            chunked = true;
+        }
+    }
+
+    let mut body = Vec::new();
+    if chunked {
+        loop {
+            line.clear();
+            reader.read_line(&mut line)?;
+            let size = usize::from_str_radix(line.trim_end(), 16).unwrap();
+            let mut chunk = vec![0; size + 2];
+            reader.read_exact(&mut chunk)?;
+            assert!(chunk.ends_with(b"\r\n"));
+            if size == 0 {
+                return Ok(body);
+            }
+            body.extend_from_slice(&chunk[..size]);
+        }
+    }
+
+    body.resize(len.expect("response must have content-length or chunked body"), 0);
+    reader.read_exact(&mut body)?;
+    Ok(body)
+}
+
+#[tokio::test]
+async fn h1_min_write_rate() -> Result<(), Error> {
+    let mut handle = test_server::<_, _, (xitca_io::net::TcpStream, SocketAddr)>(|| {
+        let config = HttpServiceConfig::new().min_write_rate(1024, Duration::from_secs(1));
+        HttpServiceBuilder::h1(fn_service(slow_read_handle)).config(config)
+    })?;
+
+    // small receive buffer so the response can not be buffered by client's socket.
+    let socket = tokio::net::TcpSocket::new_v4()?;
+    socket.set_recv_buffer_size(4096)?;
+    let mut stream = socket.connect(handle.addr()).await?.into_std()?;
+    stream.set_nonblocking(false)?;
+    stream.write_all(b"GET / HTTP/1.1\r\n\r\n")?;
+
+    // read 100 bytes per second.
+    let start = Instant::now();
+    let mut buf = [0; 100];
+    while !SLOW_READ_BODY_DROPPED.load(Ordering::SeqCst) {
+        assert!(
+            start.elapsed() < Duration::from_secs(6),
+            "slow read connection is not dropped"
+        );
+        let _ = stream.read(&mut buf);
+        std::thread::sleep(Duration::from_secs(1));
+    }
+
+    // body is not fully produced.
+    assert!(SLOW_READ_BODY_CHUNKS.load(Ordering::SeqCst) < SLOW_READ_BODY_CHUNKS_MAX);
+
+    handle.try_handle()?.stop(false);
+    handle.await?;
+
+    Ok(())
+}
+
+static SLOW_READ_BODY_DROPPED: AtomicBool = AtomicBool::new(false);
+static SLOW_READ_BODY_CHUNKS: AtomicUsize = AtomicUsize::new(0);
+
+// 16MB body. server side socket buffer of loopback can absorb a couple of MB on it's own.
+const SLOW_READ_BODY_CHUNKS_MAX: usize = 16 * 1024;
+
+struct SlowReadGuard;
+
+impl Drop for SlowReadGuard {
+    fn drop(&mut self) {
+        SLOW_READ_BODY_DROPPED.store(true, Ordering::SeqCst);
+    }
+}
+
+// response body in 1KB chunks that notify when it's dropped.
+async fn slow_read_handle(_: Request<RequestExt<h1::RequestBody>>) -> Result<Response<ResponseBody>, Error> {
+    // unfold stream has no size hint and the body is sent with chunked encoding.
+    let body = futures_util::stream::unfold(SlowReadGuard, |guard| async {
+        let n = SLOW_READ_BODY_CHUNKS.fetch_add(1, Ordering::SeqCst);
+        (n < SLOW_READ_BODY_CHUNKS_MAX).then(|| (Ok::<_, Infallible>(Bytes::from(vec![b'a'; 1024])), guard))
+    });
+    Ok(Response::new(ResponseBody::box_stream(body)))
+}
+
+// read from stream until it's closed by server.
+fn read_until_close(stream: &mut TcpStream) -> Result<Vec<u8>, Error> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+
+    let mut buf = Vec::new();
+    let mut chunk = [0; 256];
+    loop {
+        match stream.read(&mut chunk) {
+            Ok(0) => return Ok(buf),
+            Ok(n) => buf.extend_from_slice(&chunk[..n]),
+            // server can reset the connection when closing with unread data.
+            Err(e) if e.kind() == std::io::ErrorKind::ConnectionReset => return Ok(buf),
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+#[tokio::test]
+async fn h1_body_error_hook() -> Result<(), Error> {
+    let mut handle = test_h1_server(|| fn_service(body_error_handle))?;
+
+    let mut stream = TcpStream::connect(handle.addr())?;
+    stream.write_all(b"GET / HTTP/1.1\r\n\r\n")?;
+
+    let res = read_until_close(&mut stream)?;
+    let res = String::from_utf8(res)?;
+
+    // sent chunks are flushed and terminal chunk is omitted.
+    assert!(res.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(res.ends_with("\r\n\r\n5\r\nhello\r\n6\r\nworld!\r\n"));
+    assert_eq!(BODY_ERROR_SENT.load(Ordering::SeqCst), 11);
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+static BODY_ERROR_SENT: AtomicUsize = AtomicUsize::new(0);
+
+async fn body_error_handle(_: Request<RequestExt<h1::RequestBody>>) -> Result<Response<ResponseBody>, Error> {
+    // unfold stream has no size hint and the body is sent with chunked encoding.
+    let body = futures_util::stream::unfold(0, |n| async move {
+        let item = match n {
+            0 => Ok(Bytes::from_static(b"hello")),
+            1 => Ok(Bytes::from_static(b"world!")),
+            2 => Err(std::io::Error::new(std::io::ErrorKind::Other, "upstream failed")),
+            _ => return None,
+        };
+        Some((item, n + 1))
+    });
+    let mut res = Response::new(ResponseBody::box_stream(body));
+    res.extensions_mut().insert(BodyErrorHook::new(|err, sent| {
+        assert!(format!("{err:?}").contains("upstream failed"));
+        BODY_ERROR_SENT.store(sent as usize, Ordering::SeqCst);
+    }));
+    Ok(res)
+}
+
+#[tokio::test]
+async fn h1_sized_stream() -> Result<(), Error> {
+    let mut handle = test_h1_server(|| fn_service(sized_stream_handle))?;
+
+    let mut stream = TcpStream::connect(handle.addr())?;
+    stream.write_all(b"GET /exact HTTP/1.1\r\n\r\nGET /exact HTTP/1.1\r\nconnection: close\r\n\r\n")?;
+    let res = String::from_utf8(read_until_close(&mut stream)?)?;
+
+    // exact sized body is sent with content-length and connection is kept alive.
+    let res = res.split("HTTP/1.1 200 OK\r\n").skip(1).collect::<Vec<_>>();
+    assert_eq!(res.len(), 2, "{res:?}");
+    for res in res {
+        assert!(res.contains("content-length: 11\r\n"));
+        assert!(!res.contains("transfer-encoding"));
+        assert!(res.ends_with("\r\n\r\nhelloworld!"));
+    }
+
+    // over producing body is cut at the last chunk fitting the size and connection is closed.
+    let mut stream = TcpStream::connect(handle.addr())?;
+    stream.write_all(b"GET /over HTTP/1.1\r\n\r\nGET /exact HTTP/1.1\r\n\r\n")?;
+    let res = String::from_utf8(read_until_close(&mut stream)?)?;
+    assert!(res.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(res.contains("content-length: 8\r\n"));
+    assert!(res.ends_with("\r\n\r\nhello"));
+    assert_eq!(SIZE_MISMATCH_SENT.load(Ordering::SeqCst), 5);
+
+    // under producing body is followed by connection close.
+    let mut stream = TcpStream::connect(handle.addr())?;
+    stream.write_all(b"GET /under HTTP/1.1\r\n\r\nGET /exact HTTP/1.1\r\n\r\n")?;
+    let res = String::from_utf8(read_until_close(&mut stream)?)?;
+    assert!(res.starts_with("HTTP/1.1 200 OK\r\n"));
+    assert!(res.contains("content-length: 16\r\n"));
+    assert!(res.ends_with("\r\n\r\nhelloworld!"));
+    assert_eq!(SIZE_MISMATCH_SENT.load(Ordering::SeqCst), 11);
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+static SIZE_MISMATCH_SENT: AtomicUsize = AtomicUsize::new(0);
+
+async fn sized_stream_handle(req: Request<RequestExt<h1::RequestBody>>) -> Result<Response<ResponseBody>, Error> {
+    let len = match req.uri().path() {
+        "/over" => 8,
+        "/under" => 16,
+        _ => 11,
+    };
+    let body = futures_util::stream::iter([Bytes::from_static(b"hello"), Bytes::from_static(b"world!")])
+        .map(Ok::<_, Infallible>);
+    let mut res = Response::new(ResponseBody::sized_stream(body, len));
+    res.extensions_mut().insert(BodyErrorHook::new(|_, sent| {
+        SIZE_MISMATCH_SENT.store(sent as usize, Ordering::SeqCst);
+    }));
+    Ok(res)
+}
+
+#[tokio::test]
+async fn h1_body_size_probe() -> Result<(), Error> {
+    let mut handle = test_h1_server(|| fn_service(body_size_probe_handle))?;
+
+    let mut stream = TcpStream::connect(handle.addr())?;
+    stream.write_all(b"GET / HTTP/1.1\r\nconnection: close\r\n\r\n")?;
+    let res = String::from_utf8(read_until_close(&mut stream)?)?;
+    assert!(res.ends_with("\r\n\r\n5\r\nhello\r\n6\r\nworld!\r\n0\r\n\r\n"));
+
+    // chunk framing is not counted.
+    let probe = PROBE.lock().unwrap().take().unwrap();
+    assert_eq!(probe.sent(), 11);
+    assert!(probe.is_complete());
+    assert!(!probe.is_aborted());
+
+    let mut stream = TcpStream::connect(handle.addr())?;
+    stream.write_all(b"GET /abort HTTP/1.1\r\n\r\n")?;
+    let res = String::from_utf8(read_until_close(&mut stream)?)?;
+    assert!(res.ends_with("\r\n\r\n5\r\nhello\r\n"));
+
+    let probe = PROBE.lock().unwrap().take().unwrap();
+    assert_eq!(probe.sent(), 5);
+    assert!(probe.is_aborted());
+    assert!(!probe.is_complete());
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+static PROBE: Mutex<Option<BodySizeProbe>> = Mutex::new(None);
+
+async fn body_size_probe_handle(req: Request<RequestExt<h1::RequestBody>>) -> Result<Response<ResponseBody>, Error> {
+    let abort = req.uri().path() == "/abort";
+    let body = futures_util::stream::unfold(0, move |n| async move {
+        let item = match n {
+            0 => Ok(Bytes::from_static(b"hello")),
+            1 if abort => Err(std::io::Error::new(std::io::ErrorKind::Other, "upstream failed")),
+            1 => Ok(Bytes::from_static(b"world!")),
+            _ => return None,
+        };
+        Some((item, n + 1))
+    });
+
+    let probe = BodySizeProbe::new();
+    *PROBE.lock().unwrap() = Some(probe.clone());
+
+    let mut res = Response::new(ResponseBody::box_stream(body));
+    res.extensions_mut().insert(probe);
+    Ok(res)
+}
+
+#[tokio::test]
+async fn h1_chunked_upload_read_line() -> Result<(), Error> {
+    let mut handle = test_h1_server(|| fn_service(read_line_handle))?;
+
+    let mut stream = TcpStream::connect(handle.addr())?;
+    stream.write_all(b"POST / HTTP/1.1\r\ntransfer-encoding: chunked\r\n\r\n")?;
+    // lines are split across chunk boundaries.
+    for chunk in ["hel", "lo\nwor", "ld\n\nla", "st"] {
+        write!(stream, "{:X}\r\n{chunk}\r\n", chunk.len())?;
+        stream.flush()?;
+    }
+    stream.write_all(b"0\r\n\r\n")?;
+
+    let body = read_response(&mut BufReader::new(&mut stream))?;
+    assert_eq!(body, b"hello,world,,last");
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+async fn read_line_handle(req: Request<RequestExt<h1::RequestBody>>) -> Result<Response<ResponseBody>, Error> {
+    let (_, body) = req.into_body().replace_body(());
+    let mut reader = RequestBody::H1(body).into_async_read();
+
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    while reader.read_line(&mut line).await? != 0 {
+        lines.push(line.trim_end_matches('\n').to_owned());
+        line.clear();
+    }
+
+    Ok(Response::new(Bytes::from(lines.join(",")).into()))
+}
+
+#[tokio::test]
+async fn h1_incomplete_body() -> Result<(), Error> {
+    let mut handle = test_h1_server(|| fn_service(incomplete_body_handle))?;
+
+    let requests: [(&[u8], &str); 3] = [
+        // sized body truncated by client closing it's write half.
+        (
+            b"POST / HTTP/1.1\r\ncontent-length: 10\r\n\r\nhello",
+            "body ended after 5 bytes of expected 10 bytes",
+        ),
+        // chunked body missing the terminal chunk.
+        (
+            b"POST / HTTP/1.1\r\ntransfer-encoding: chunked\r\n\r\n5\r\nhello\r\n",
+            "chunked body ended after 5 bytes without terminal chunk",
+        ),
+        // completed body is not affected.
+        (
+            b"POST / HTTP/1.1\r\ncontent-length: 5\r\nconnection: close\r\n\r\nhello",
+            "hello",
+        ),
+    ];
+
+    for (req, expected) in requests {
+        let mut stream = TcpStream::connect(handle.addr())?;
+        stream.write_all(req)?;
+        stream.shutdown(std::net::Shutdown::Write)?;
+        let res = String::from_utf8(read_until_close(&mut stream)?)?;
+        assert!(res.ends_with(expected), "unexpected response: {res}");
+    }
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+// respond with collected body or the error of it.
+async fn incomplete_body_handle(req: Request<RequestExt<h1::RequestBody>>) -> Result<Response<ResponseBody>, Error> {
+    let mut body = req.into_body();
+    let mut buf = BytesMut::new();
+    while let Some(res) = body.next().await {
+        match res {
+            Ok(chunk) => buf.extend_from_slice(&chunk),
+            Err(e) => {
+                assert!(e.is_incomplete());
+                let mut res = Response::new(Bytes::from(e.to_string()).into());
+                *res.status_mut() = StatusCode::BAD_REQUEST;
+                return Ok(res);
+            }
+        }
+    }
+    Ok(Response::new(buf.freeze().into()))
+}
+
+#[tokio::test]
+async fn h1_drain() -> Result<(), Error> {
+    let drain = Drain::new();
+
+    let mut handle = {
+        let drain = drain.clone();
+        test_server::<_, _, (xitca_io::net::TcpStream, SocketAddr)>(move || {
+            let config = HttpServiceConfig::new().keep_alive_timeout(Duration::from_secs(60));
+            HttpServiceBuilder::h1(fn_service(drain_handle))
+                .config(config)
+                .drain(drain.clone())
+        })?
+    };
+
+    // idle keep-alive connection waiting for next request.
+    let mut idle = TcpStream::connect(handle.addr())?;
+    idle.write_all(SIMPLE_GET_REQ)?;
+    assert_eq!(read_response(&mut BufReader::new(&idle))?, b"GET Response");
+
+    // connection with in-flight request.
+    let mut busy = TcpStream::connect(handle.addr())?;
+    busy.write_all(b"GET /slow HTTP/1.1\r\ncontent-length: 0\r\n\r\n")?;
+    tokio::time::sleep(Duration::from_millis(100)).await;
+
+    drain.drain();
+    handle.try_handle()?.stop(true);
+
+    // idle connection is closed without waiting for keep-alive timeout.
+    let now = Instant::now();
+    assert!(read_until_close(&mut idle)?.is_empty());
+    assert!(now.elapsed() < Duration::from_secs(1));
+
+    // in-flight request is finished and it's response is the last one of connection.
+    let res = read_until_close(&mut busy)?;
+    assert!(res.starts_with(b"HTTP/1.1 200"));
+    assert!(res.windows(17).any(|w| w.eq_ignore_ascii_case(b"connection: close")));
+    assert!(res.ends_with(b"slow"));
+
+    handle.await?;
+
+    Ok(())
+}
+
+async fn drain_handle(req: Request<RequestExt<h1::RequestBody>>) -> Result<Response<ResponseBody>, Error> {
+    if req.uri().path() == "/slow" {
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        return Ok(Response::new(Bytes::from_static(b"slow").into()));
+    }
+    handle(req).await
+}
+
+#[tokio::test]
+async fn h1_readiness() -> Result<(), Error> {
+    static READY: AtomicBool = AtomicBool::new(false);
+
+    // readiness toggled by flag.
+    struct Flag;
+
+    impl ReadyService for Flag {
+        type Ready = ();
+        type Future<'f> = Pin<Box<dyn Future<Output = Self::Ready> + 'f>>;
+
+        fn ready(&self) -> Self::Future<'_> {
+            Box::pin(async {
+                while !READY.load(Ordering::Acquire) {
+                    tokio::time::sleep(Duration::from_millis(10)).await;
+                }
+            })
+        }
+    }
+
+    let mut handle = test_server::<_, _, (xitca_io::net::TcpStream, SocketAddr)>(|| {
+        HttpServiceBuilder::h1(fn_service(handle)).readiness(|| Flag)
+    })?;
+
+    // connection is left in listen backlog while service is not ready.
+    let mut stream = TcpStream::connect(handle.addr())?;
+    stream.write_all(SIMPLE_GET_REQ)?;
+    assert_pending_response(&mut stream)?;
+
+    READY.store(true, Ordering::Release);
+    assert_eq!(read_response(&mut BufReader::new(&stream))?, b"GET Response");
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn h1_max_connections() -> Result<(), Error> {
+    let mut handle = test_server::<_, _, (xitca_io::net::TcpStream, SocketAddr)>(|| {
+        let config = HttpServiceConfig::new()
+            .keep_alive_timeout(Duration::from_secs(60))
+            .max_connections(1);
+        HttpServiceBuilder::h1(fn_service(handle)).config(config)
+    })?;
+
+    let mut first = TcpStream::connect(handle.addr())?;
+    first.write_all(SIMPLE_GET_REQ)?;
+    assert_eq!(read_response(&mut BufReader::new(&first))?, b"GET Response");
+
+    // idle keep-alive connection holds the only slot.
+    let mut second = TcpStream::connect(handle.addr())?;
+    second.write_all(SIMPLE_GET_REQ)?;
+    assert_pending_response(&mut second)?;
+
+    // accepting resumes after first connection is finished.
+    drop(first);
+    assert_eq!(read_response(&mut BufReader::new(&second))?, b"GET Response");
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn h1_max_requests_per_second_delay() -> Result<(), Error> {
+    let mut handle = test_server::<_, _, (xitca_io::net::TcpStream, SocketAddr)>(|| {
+        let config = HttpServiceConfig::new().max_requests_per_second(100, RequestRatePolicy::Delay);
+        HttpServiceBuilder::h1(fn_service(handle)).config(config)
+    })?;
+
+    let stream = TcpStream::connect(handle.addr())?;
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut writer = stream;
+
+    let now = Instant::now();
+
+    // requests exceeding the rate are served in following windows.
+    writer.write_all(&SIMPLE_GET_REQ.repeat(1000))?;
+    for _ in 0..1000 {
+        assert_eq!(read_response(&mut reader)?, b"GET Response");
+    }
+
+    let elapsed = now.elapsed();
+    assert!(elapsed > Duration::from_secs(8), "{elapsed:?}");
+    assert!(elapsed < Duration::from_secs(12), "{elapsed:?}");
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn h1_max_requests_per_second_reject() -> Result<(), Error> {
+    let mut handle = test_server::<_, _, (xitca_io::net::TcpStream, SocketAddr)>(|| {
+        let config = HttpServiceConfig::new().max_requests_per_second(100, RequestRatePolicy::Reject);
+        HttpServiceBuilder::h1(fn_service(handle)).config(config)
+    })?;
+
+    // one request over the rate so server does not close with unread data.
+    let mut stream = TcpStream::connect(handle.addr())?;
+    stream.write_all(&SIMPLE_GET_REQ.repeat(101))?;
+    let res = String::from_utf8(read_until_close(&mut stream)?)?;
+
+    // requests within the rate are served and the first one exceeding it is rejected.
+    assert_eq!(res.matches("HTTP/1.1 200 OK\r\n").count(), 100);
+    let (_, rejected) = res.split_once("HTTP/1.1 429 Too Many Requests\r\n").unwrap();
+    assert!(rejected.contains("connection: close\r\n"));
+    assert!(rejected.ends_with("\r\n\r\n"));
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn h1_client_pool_reuse() -> Result<(), Error> {
+    static PEERS: Mutex<Vec<SocketAddr>> = Mutex::new(Vec::new());
+
+    async fn peer_handle(req: Request<RequestExt<h1::RequestBody>>) -> Result<Response<ResponseBody>, Error> {
+        record_peer(&PEERS, &req);
+        handle(req).await
+    }
+
+    let mut handle = test_h1_server(|| fn_service(peer_handle))?;
+
+    let server_url = format!("http://{}/", handle.ip_port_string());
+
+    let c = Client::new();
+
+    // connection is returned to pool after response body is read.
+    for _ in 0..3 {
+        let res = c.get(&server_url)?.send().await?;
+        assert_eq!(res.string().await?, "GET Response");
+    }
+    assert_eq!(PEERS.lock().unwrap().len(), 1);
+
+    // connection with unread response body is not returned to pool.
+    let res = c.get(&server_url)?.send().await?;
+    drop(res);
+    let res = c.get(&server_url)?.send().await?;
+    assert_eq!(res.string().await?, "GET Response");
+    assert_eq!(PEERS.lock().unwrap().len(), 2);
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn h1_client_pool_stale_retry() -> Result<(), Error> {
+    static PEERS: Mutex<Vec<SocketAddr>> = Mutex::new(Vec::new());
+
+    async fn peer_handle(req: Request<RequestExt<h1::RequestBody>>) -> Result<Response<ResponseBody>, Error> {
+        record_peer(&PEERS, &req);
+        handle(req).await
+    }
+
+    let mut handle = test_server::<_, _, (xitca_io::net::TcpStream, SocketAddr)>(|| {
+        // initial timer of connection is tls accept timeout. keep-alive can't be shorter than it.
+        let config = HttpServiceConfig::new()
+            .tls_accept_timeout(Duration::from_millis(100))
+            .keep_alive_timeout(Duration::from_millis(100))
+            .keep_alive_granularity(Duration::ZERO);
+        HttpServiceBuilder::h1(fn_service(peer_handle)).config(config)
+    })?;
+
+    let server_url = format!("http://{}/", handle.ip_port_string());
+
+    let c = Client::new();
+
+    let res = c.get(&server_url)?.send().await?;
+    assert_eq!(res.string().await?, "GET Response");
+
+    // server closes idle connection while it's in client pool.
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    // idempotent request is retried on a new connection.
+    let res = c.get(&server_url)?.send().await?;
+    assert_eq!(res.string().await?, "GET Response");
+    assert_eq!(PEERS.lock().unwrap().len(), 2);
+
+    tokio::time::sleep(Duration::from_millis(500)).await;
+
+    // request with body is not retried.
+    assert!(c.post(&server_url)?.text("996").send().await.is_err());
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn h1_client_pool_max_per_host() -> Result<(), Error> {
+    static PEERS: Mutex<Vec<SocketAddr>> = Mutex::new(Vec::new());
+    static IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+    static MAX_IN_FLIGHT: AtomicUsize = AtomicUsize::new(0);
+
+    async fn slow_handle(req: Request<RequestExt<h1::RequestBody>>) -> Result<Response<ResponseBody>, Error> {
+        record_peer(&PEERS, &req);
+        let n = IN_FLIGHT.fetch_add(1, Ordering::SeqCst) + 1;
+        MAX_IN_FLIGHT.fetch_max(n, Ordering::SeqCst);
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        IN_FLIGHT.fetch_sub(1, Ordering::SeqCst);
+        handle(req).await
+    }
+
+    let mut handle = test_h1_server(|| fn_service(slow_handle))?;
+
+    let server_url = format!("http://{}/", handle.ip_port_string());
+
+    let c = Client::builder().set_pool_max_per_host(2).finish();
+
+    let reqs = (0..6).map(|_| async {
+        let res = c.get(&server_url)?.send().await?;
+        res.string().await
+    });
+
+    for res in futures_util::future::join_all(reqs).await {
+        assert_eq!(res?, "GET Response");
+    }
+
+    assert_eq!(MAX_IN_FLIGHT.load(Ordering::SeqCst), 2);
+    assert_eq!(PEERS.lock().unwrap().len(), 2);
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+// record client address of connections request is received from.
+fn record_peer<B>(peers: &Mutex<Vec<SocketAddr>>, req: &Request<RequestExt<B>>) {
+    let addr = *req.body().socket_addr();
+    let mut peers = peers.lock().unwrap();
+    if !peers.contains(&addr) {
+        peers.push(addr);
+    }
+}
+
+// assert no response is received in a short period.
+fn assert_pending_response(stream: &mut TcpStream) -> Result<(), Error> {
+    stream.set_read_timeout(Some(Duration::from_millis(300)))?;
+    let err = stream.read(&mut [0; 1]).unwrap_err();
+    assert!(matches!(
+        err.kind(),
+        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+    ));
+    stream.set_read_timeout(None)?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn h1_uri() -> Result<(), Error> {
+    let mut handle = test_h1_server(|| fn_service(handle))?;
+
+    let authority = handle.ip_port_string();
+    let server_url = format!("http://{authority}/uri?foo=bar");
+
+    let c = Client::new();
+
+    let mut res = c.get(&server_url)?.send().await?;
+    assert_eq!(res.status().as_u16(), 200);
+    let body = res.string().await?;
+    assert_eq!(body, format!("HTTP/1.1 {server_url}"));
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+async fn handle(req: Request<RequestExt<h1::RequestBody>>) -> Result<Response<ResponseBody>, Error> {
+    // Some yield for testing h1 dispatcher's concurrent future handling.
+    tokio::task::yield_now().await;
+    tokio::task::yield_now().await;
+    tokio::task::yield_now().await;
+
+    match (req.method(), req.uri().path()) {
+        (&Method::GET, "/") => Ok(Response::new(Bytes::from("GET Response").into())),
+        (&Method::GET, "/uri") => {
+            let body = format!("{:?} {}", req.version(), req.uri());
+            Ok(Response::new(Bytes::from(body).into()))
+        }
         (&Method::POST, "/") => {
             let length = req.headers().get(header::CONTENT_LENGTH).unwrap().clone();
             let ty = req.headers().get(header::CONTENT_TYPE).unwrap().clone();
@@ -1,15 +1,32 @@
-use std::time::{Duration, Instant};
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
 
 use futures_util::StreamExt;
+use http_ws::{Codec, Message as WsMessage};
 use xitca_client::Client;
 use xitca_http::{
-    body::ResponseBody,
+    body::{BodyErrorHook, BodySizeProbe, Once, ResponseBody},
     bytes::{Bytes, BytesMut},
+    config::{ConnectionEvent, H2Refusal, HttpServiceConfig, StuckRequestAction},
     h2,
-    http::{header, Method, Request, RequestExt, Response, Version},
+    http::{
+        header, HeaderCaseMap, HeaderValue, Method, Protocol, Request, RequestBodyLimit, RequestExt, Response,
+        StatusCode, Version,
+    },
+    util::{
+        grpc,
+        service::{route::post, Router},
+    },
+    HttpServiceBuilder,
 };
 use xitca_service::fn_service;
-use xitca_test::{test_h2_server, Error};
+use xitca_test::{test_h2_server, test_server, Error};
 
 #[tokio::test]
 async fn h2_get() -> Result<(), Error> {
@@ -107,6 +124,718 @@ async fn h2_keepalive() -> Result<(), Error> {
     Ok(())
 }
 
+#[tokio::test]
+async fn h2_uri() -> Result<(), Error> {
+    let mut handle = test_h2_server(|| fn_service(handle))?;
+
+    let authority = handle.ip_port_string();
+    let server_url = format!("https://{authority}/uri?foo=bar");
+
+    let c = Client::new();
+
+    let mut res = c.get(&server_url)?.version(Version::HTTP_2).send().await?;
+    assert_eq!(res.status().as_u16(), 200);
+    let body = res.string().await?;
+    assert_eq!(body, format!("HTTP/2.0 {server_url}"));
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn h2_ignore_header_case() -> Result<(), Error> {
+    let mut handle = test_server::<_, _, (xitca_io::net::TcpStream, SocketAddr)>(|| {
+        let config = HttpServiceConfig::new().preserve_header_case();
+        HttpServiceBuilder::h2(fn_service(header_case_handle)).config(config)
+    })?;
+
+    let server_url = format!("https://{}/", handle.ip_port_string());
+
+    let c = Client::new();
+
+    // h2 header names are always lowercase and case map is not used.
+    let res = c.get(&server_url)?.version(Version::HTTP_2).send().await?;
+    assert_eq!(res.status().as_u16(), 200);
+    assert_eq!(res.headers().get("x-api-key").unwrap(), "996");
+    assert_eq!(res.string().await?, "hello");
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn h2_normalize_request_headers() -> Result<(), Error> {
+    let mut handle = test_h2_server(|| fn_service(header_names_handle))?;
+
+    let stream = tokio::net::TcpStream::connect(handle.addr()).await?;
+    let (client, conn) = ::h2::client::handshake(stream).await?;
+    tokio::spawn(conn);
+
+    let mut client = client.ready().await?;
+
+    let uri = format!("http://{}/", handle.ip_port_string());
+
+    // te is the only hop-by-hop header allowed by http/2 and it's removed like in http/1.
+    let req = Request::get(&uri)
+        .header(header::TE, "trailers")
+        .header(header::TRAILER, "x-sum")
+        .header("x-end", "2")
+        .body(())?;
+    let (res, _) = client.send_request(req, true)?;
+    let res = res.await?;
+    assert_eq!(res.status(), StatusCode::OK);
+    let mut body = res.into_body();
+    assert_eq!(body.data().await.unwrap()?, "x-end");
+
+    // the other ones are rejected by http/2 protocol and never reach service.
+    let req = Request::get(&uri).header(header::CONNECTION, "close").body(())?;
+    assert!(client.send_request(req, true).is_err());
+
+    // duplicate singleton headers are rejected like in http/1.
+    for (name, v1, v2) in [
+        (header::CONTENT_TYPE, "text/plain", "application/json"),
+        (header::HOST, "a", "b"),
+        (header::CONTENT_LENGTH, "0", "1"),
+    ] {
+        let req = Request::post(&uri).header(&name, v1).header(&name, v2).body(())?;
+        let (res, _) = client.send_request(req, false)?;
+        assert_eq!(res.await?.status(), StatusCode::BAD_REQUEST);
+    }
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn h2_max_concurrent_requests() -> Result<(), Error> {
+    const LIMIT: usize = 4;
+
+    static REFUSED: AtomicUsize = AtomicUsize::new(0);
+
+    let mut handle = test_server::<_, _, (xitca_io::net::TcpStream, SocketAddr)>(|| {
+        let config = HttpServiceConfig::new()
+            .h2_max_concurrent_requests(LIMIT, H2Refusal::RefusedStream)
+            .h2_refused_observer(|_, count| REFUSED.store(count, Ordering::SeqCst));
+        HttpServiceBuilder::h2(fn_service(slow_handle)).config(config)
+    })?;
+
+    let stream = tokio::net::TcpStream::connect(handle.addr()).await?;
+    let (client, conn) = ::h2::client::handshake(stream).await?;
+    tokio::spawn(conn);
+
+    let mut client = client.ready().await?;
+
+    // open more streams than the limit at once. all of them are accepted by server before the
+    // first response is sent.
+    let mut responses = Vec::new();
+    for _ in 0..LIMIT + 5 {
+        let req = Request::get(format!("http://{}/", handle.ip_port_string())).body(())?;
+        let (res, _) = client.send_request(req, true)?;
+        responses.push(res);
+    }
+
+    let (mut ok, mut refused) = (0, 0);
+    for res in responses {
+        match res.await {
+            Ok(res) => {
+                assert_eq!(res.status(), StatusCode::OK);
+                let mut body = res.into_body();
+                while let Some(chunk) = body.data().await {
+                    assert_eq!(chunk?, "hello");
+                }
+                ok += 1;
+            }
+            Err(e) => {
+                assert_eq!(e.reason(), Some(::h2::Reason::REFUSED_STREAM));
+                refused += 1;
+            }
+        }
+    }
+
+    assert_eq!(ok, LIMIT);
+    assert_eq!(refused, 5);
+    assert_eq!(REFUSED.load(Ordering::SeqCst), 5);
+
+    // in-flight count is released after response is finished.
+    let req = Request::get(format!("http://{}/", handle.ip_port_string())).body(())?;
+    let (res, _) = client.send_request(req, true)?;
+    assert_eq!(res.await?.status(), StatusCode::OK);
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn h2_max_header_list_size() -> Result<(), Error> {
+    const LIMIT: usize = 1024 * 1024;
+
+    let mut handle = test_server::<_, _, (xitca_io::net::TcpStream, SocketAddr)>(|| {
+        let config = HttpServiceConfig::new().h2_max_header_list_size(LIMIT);
+        HttpServiceBuilder::h2(fn_service(giant_header_handle)).config(config)
+    })?;
+
+    let stream = tokio::net::TcpStream::connect(handle.addr()).await?;
+    let (client, conn) = ::h2::client::handshake(stream).await?;
+    tokio::spawn(conn);
+
+    let mut client = client.ready().await?;
+
+    let uri = format!("http://{}/", handle.ip_port_string());
+
+    // oversized header block spread across CONTINUATION frames is answered on it's own stream.
+    let req = Request::get(&uri).header(header::COOKIE, "a".repeat(LIMIT)).body(())?;
+    let (res, _) = client.send_request(req, true)?;
+    assert_eq!(res.await?.status(), StatusCode::REQUEST_HEADER_FIELDS_TOO_LARGE);
+
+    // connection is still usable.
+    let req = Request::get(&uri).header(header::COOKIE, "a".repeat(1024)).body(())?;
+    let (res, _) = client.send_request(req, true)?;
+    let res = res.await?;
+    assert_eq!(res.status(), StatusCode::OK);
+    let mut body = res.into_body();
+    assert_eq!(body.data().await.unwrap()?, "hello");
+
+    // oversized response header is discarded.
+    let req = Request::get(format!("{uri}giant")).body(())?;
+    let (res, _) = client.send_request(req, true)?;
+    let res = res.await?;
+    assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert!(res.headers().get("x-giant").is_none());
+
+    let req = Request::get(&uri).body(())?;
+    let (res, _) = client.send_request(req, true)?;
+    assert_eq!(res.await?.status(), StatusCode::OK);
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn h2_body_too_large() -> Result<(), Error> {
+    let mut handle = test_server::<_, _, (xitca_io::net::TcpStream, SocketAddr)>(|| {
+        let config = HttpServiceConfig::new().max_request_body_size(16);
+        HttpServiceBuilder::h2(fn_service(|req: Request<RequestExt<h2::RequestBody>>| async move {
+            if req.uri().path() == "/raise" {
+                req.extensions().get::<RequestBodyLimit>().unwrap().set(1024);
+            }
+            body_len_handle(req).await
+        }))
+        .config(config)
+    })?;
+
+    let stream = tokio::net::TcpStream::connect(handle.addr()).await?;
+    let (client, conn) = ::h2::client::handshake(stream).await?;
+    tokio::spawn(conn);
+
+    let mut client = client.ready().await?;
+
+    let uri = format!("http://{}/", handle.ip_port_string());
+
+    // body without content-length is counted as it's received.
+    let req = Request::post(&uri).body(())?;
+    let (res, mut tx) = client.send_request(req, false)?;
+    tx.send_data(Bytes::from_static(b"Hello,Wo"), false)?;
+    tx.send_data(Bytes::from_static(b"rld!Hello,Wo"), true)?;
+    assert_eq!(res.await?.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+    // connection is still usable and body within limit is handled as usual.
+    let mut client = client.ready().await?;
+    let req = Request::post(&uri).body(())?;
+    let (res, mut tx) = client.send_request(req, false)?;
+    tx.send_data(Bytes::from_static(b"Hello,Wo"), false)?;
+    tx.send_data(Bytes::from_static(b"rld!"), true)?;
+    let res = res.await?;
+    assert_eq!(res.status(), StatusCode::OK);
+    let mut body = res.into_body();
+    assert_eq!(body.data().await.unwrap()?, "12");
+
+    // service raising the limit before reading body accepts body larger than the default one.
+    let mut client = client.ready().await?;
+    let req = Request::post(format!("{uri}raise"))
+        .header(header::CONTENT_LENGTH, 24)
+        .body(())?;
+    let (res, mut tx) = client.send_request(req, false)?;
+    tx.send_data(Bytes::from_static(b"Hello,World!Hello,World!"), true)?;
+    let res = res.await?;
+    assert_eq!(res.status(), StatusCode::OK);
+    let mut body = res.into_body();
+    assert_eq!(body.data().await.unwrap()?, "24");
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn h2_body_error_hook() -> Result<(), Error> {
+    static SENT: AtomicUsize = AtomicUsize::new(0);
+
+    let mut handle = test_server::<_, _, (xitca_io::net::TcpStream, SocketAddr)>(|| {
+        HttpServiceBuilder::h2(fn_service(|_: Request<RequestExt<h2::RequestBody>>| async {
+            let body = futures_util::stream::unfold(0, |n| async move {
+                let item = match n {
+                    0 => Ok(Bytes::from_static(b"hello")),
+                    1 => Ok(Bytes::from_static(b"world!")),
+                    2 => {
+                        // give client the chance to receive sent data before stream is reset.
+                        tokio::time::sleep(Duration::from_millis(100)).await;
+                        Err(std::io::Error::new(std::io::ErrorKind::Other, "upstream failed"))
+                    }
+                    _ => return None,
+                };
+                Some((item, n + 1))
+            });
+            let mut res = Response::new(ResponseBody::box_stream(body));
+            res.extensions_mut().insert(BodyErrorHook::new(|_, sent| {
+                SENT.store(sent as usize, Ordering::SeqCst);
+            }));
+            Ok::<_, Error>(res)
+        }))
+    })?;
+
+    let stream = tokio::net::TcpStream::connect(handle.addr()).await?;
+    let (client, conn) = ::h2::client::handshake(stream).await?;
+    tokio::spawn(conn);
+
+    let mut client = client.ready().await?;
+
+    let req = Request::get(format!("http://{}/", handle.ip_port_string())).body(())?;
+    let (res, _) = client.send_request(req, true)?;
+    let res = res.await?;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    // client observes stream reset instead of end of stream.
+    let mut body = res.into_body();
+    let mut buf = BytesMut::new();
+    let err = loop {
+        match body.data().await {
+            Some(Ok(chunk)) => buf.extend_from_slice(&chunk),
+            Some(Err(e)) => break e,
+            None => panic!("response body must not end cleanly"),
+        }
+    };
+    assert_eq!(err.reason(), Some(::h2::Reason::INTERNAL_ERROR));
+    assert_eq!(buf.as_ref(), b"helloworld!");
+    assert_eq!(SENT.load(Ordering::SeqCst), 11);
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn h2_sized_stream() -> Result<(), Error> {
+    let mut handle = test_server::<_, _, (xitca_io::net::TcpStream, SocketAddr)>(|| {
+        HttpServiceBuilder::h2(fn_service(|req: Request<RequestExt<h2::RequestBody>>| async move {
+            let len = match req.uri().path() {
+                "/over" => 8,
+                "/under" => 16,
+                _ => 11,
+            };
+            let body = futures_util::stream::unfold(0, |n| async move {
+                if n > 0 {
+                    // give client the chance to receive sent data before stream is reset.
+                    tokio::time::sleep(Duration::from_millis(100)).await;
+                }
+                let chunk = match n {
+                    0 => Bytes::from_static(b"hello"),
+                    1 => Bytes::from_static(b"world!"),
+                    _ => return None,
+                };
+                Some((Ok::<_, std::convert::Infallible>(chunk), n + 1))
+            });
+            Ok::<_, Error>(Response::new(ResponseBody::sized_stream(body, len)))
+        }))
+    })?;
+
+    let stream = tokio::net::TcpStream::connect(handle.addr()).await?;
+    let (client, conn) = ::h2::client::handshake(stream).await?;
+    tokio::spawn(conn);
+
+    for (path, len, expected, is_err) in [
+        ("exact", "11", "helloworld!", false),
+        ("over", "8", "hello", true),
+        ("under", "16", "helloworld!", true),
+    ] {
+        let mut client = client.clone().ready().await?;
+
+        let req = Request::get(format!("http://{}/{path}", handle.ip_port_string())).body(())?;
+        let (res, _) = client.send_request(req, true)?;
+        let res = res.await?;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get(header::CONTENT_LENGTH).unwrap(), len);
+
+        // size mismatch resets stream instead of ending it.
+        let mut body = res.into_body();
+        let mut buf = BytesMut::new();
+        let err = loop {
+            match body.data().await {
+                Some(Ok(chunk)) => buf.extend_from_slice(&chunk),
+                Some(Err(e)) => break Some(e),
+                None => break None,
+            }
+        };
+        assert_eq!(err.is_some(), is_err, "{path}: {err:?}");
+        assert_eq!(buf.as_ref(), expected.as_bytes());
+    }
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn h2_body_size_probe() -> Result<(), Error> {
+    static PROBES: Mutex<Vec<BodySizeProbe>> = Mutex::new(Vec::new());
+
+    let mut handle = test_server::<_, _, (xitca_io::net::TcpStream, SocketAddr)>(|| {
+        HttpServiceBuilder::h2(fn_service(|req: Request<RequestExt<h2::RequestBody>>| async move {
+            let mut res = match req.uri().path() {
+                "/empty" => Response::new(ResponseBody::None),
+                _ => {
+                    // unfold stream has no size hint and the body is sent without content-length.
+                    let body = futures_util::stream::unfold(0, |n| async move {
+                        let chunk: &'static [u8] = match n {
+                            0 => b"hello",
+                            1 => b"world!",
+                            _ => return None,
+                        };
+                        Some((Ok::<_, std::convert::Infallible>(Bytes::from_static(chunk)), n + 1))
+                    });
+                    Response::new(ResponseBody::box_stream(body))
+                }
+            };
+            let probe = BodySizeProbe::new();
+            PROBES.lock().unwrap().push(probe.clone());
+            res.extensions_mut().insert(probe);
+            Ok::<_, Error>(res)
+        }))
+    })?;
+
+    let stream = tokio::net::TcpStream::connect(handle.addr()).await?;
+    let (client, conn) = ::h2::client::handshake(stream).await?;
+    tokio::spawn(conn);
+
+    let mut client = client.ready().await?;
+
+    for path in ["/", "/empty"] {
+        let req = Request::get(format!("http://{}{path}", handle.ip_port_string())).body(())?;
+        let (res, _) = client.send_request(req, true)?;
+        let mut body = res.await?.into_body();
+        while let Some(chunk) = body.data().await {
+            chunk?;
+        }
+        client = client.ready().await?;
+    }
+
+    let probes = PROBES.lock().unwrap().drain(..).collect::<Vec<_>>();
+    assert_eq!(probes.len(), 2);
+    assert_eq!(probes[0].sent(), 11);
+    assert!(probes[0].is_complete());
+    assert_eq!(probes[1].sent(), 0);
+    assert!(probes[1].is_complete());
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn h2_extended_connect() -> Result<(), Error> {
+    // 1 for tunnel closed cleanly. 2 for tunnel closed with error.
+    static CLOSED: AtomicUsize = AtomicUsize::new(0);
+
+    let mut handle = test_server::<_, _, (xitca_io::net::TcpStream, SocketAddr)>(|| {
+        HttpServiceBuilder::h2(fn_service(|req: Request<RequestExt<h2::RequestBody>>| async move {
+            assert_eq!(req.method(), Method::CONNECT);
+            assert_eq!(
+                req.extensions().get::<Protocol>().map(Protocol::as_str),
+                Some("websocket")
+            );
+
+            let (parts, body) = req.into_parts();
+            let req = Request::from_parts(parts, ());
+            let (mut decode, res, tx) = http_ws::ws(&req, body)?;
+
+            tokio::task::spawn_local(async move {
+                loop {
+                    match decode.next().await {
+                        Some(Ok(WsMessage::Text(bytes))) => {
+                            let _ = tx.send(WsMessage::Text(bytes)).await;
+                        }
+                        Some(Ok(_)) => {}
+                        Some(Err(_)) => return CLOSED.store(2, Ordering::SeqCst),
+                        None => return CLOSED.store(1, Ordering::SeqCst),
+                    }
+                }
+            });
+
+            Ok::<_, Error>(res.map(ResponseBody::box_stream))
+        }))
+    })?;
+
+    let stream = tokio::net::TcpStream::connect(handle.addr()).await?;
+    let (client, conn) = ::h2::client::handshake(stream).await?;
+    tokio::spawn(conn);
+
+    let mut client = client.ready().await?;
+
+    // SETTINGS_ENABLE_CONNECT_PROTOCOL is advertised by server's first settings frame.
+    let deadline = Instant::now() + Duration::from_secs(3);
+    while !client.is_extended_connect_protocol_enabled() {
+        assert!(
+            Instant::now() < deadline,
+            "extended CONNECT is not advertised by server"
+        );
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    }
+
+    // RFC 8441 handshake.
+    let mut req = Request::connect(format!("http://{}/", handle.ip_port_string()))
+        .header(header::SEC_WEBSOCKET_VERSION, "13")
+        .body(())?;
+    req.extensions_mut().insert(::h2::ext::Protocol::from("websocket"));
+    let (res, mut tx) = client.send_request(req, false)?;
+    let res = res.await?;
+    assert_eq!(res.status(), StatusCode::OK);
+
+    let mut rx = res.into_body();
+    let mut codec = Codec::new().client_mode();
+    let mut read_buf = BytesMut::new();
+
+    for msg in ["hello", "world", "996"] {
+        let mut buf = BytesMut::new();
+        codec.encode(WsMessage::Text(Bytes::from(msg)), &mut buf)?;
+        tx.send_data(buf.freeze(), false)?;
+
+        let echo = loop {
+            if let Some(msg) = codec.decode(&mut read_buf)? {
+                break msg;
+            }
+            let chunk = rx.data().await.unwrap()?;
+            rx.flow_control().release_capacity(chunk.len())?;
+            read_buf.extend_from_slice(&chunk);
+        };
+        assert_eq!(echo, WsMessage::Text(Bytes::from(msg)));
+    }
+
+    // stream reset closes tunnel cleanly on server side.
+    tx.send_reset(::h2::Reason::CANCEL);
+
+    let deadline = Instant::now() + Duration::from_secs(3);
+    while CLOSED.load(Ordering::SeqCst) == 0 {
+        assert!(Instant::now() < deadline, "tunnel is not closed after stream reset");
+        tokio::time::sleep(Duration::from_millis(1)).await;
+    }
+    assert_eq!(CLOSED.load(Ordering::SeqCst), 1);
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn h2_grpc_unary() -> Result<(), Error> {
+    let mut handle = test_server::<_, _, (xitca_io::net::TcpStream, SocketAddr)>(|| {
+        let router = Router::new().insert(
+            "/echo.Echo/UnaryEcho",
+            post(fn_service(grpc_echo_handle)).guard(grpc::is_grpc),
+        );
+        HttpServiceBuilder::h2(router)
+    })?;
+
+    // raw h2 client speaking grpc wire format. it's what a grpc client library sends for unary call.
+    let stream = tokio::net::TcpStream::connect(handle.addr()).await?;
+    let (client, conn) = ::h2::client::handshake(stream).await?;
+    tokio::spawn(conn);
+
+    let mut client = client.ready().await?;
+
+    let uri = format!("http://{}/echo.Echo/UnaryEcho", handle.ip_port_string());
+    let req = |content_type| {
+        Request::post(&uri)
+            .header(header::CONTENT_TYPE, content_type)
+            .header(header::TE, "trailers")
+            .body(())
+    };
+
+    // message split across DATA frames.
+    let msg = grpc::encode(b"hello,world")?;
+    let (res, mut tx) = client.send_request(req("application/grpc+proto")?, false)?;
+    tx.send_data(msg.slice(..3), false)?;
+    tx.send_data(msg.slice(3..8), false)?;
+    tx.send_data(msg.slice(8..), true)?;
+
+    let res = res.await?;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers().get(header::CONTENT_TYPE).unwrap(), "application/grpc");
+    assert!(res.headers().get("grpc-status").is_none());
+
+    let mut body = res.into_body();
+    let mut buf = BytesMut::new();
+    while let Some(chunk) = body.data().await {
+        buf.extend_from_slice(&chunk?);
+    }
+    assert_eq!(buf, msg);
+
+    let trailers = body.trailers().await?.unwrap();
+    assert_eq!(trailers.get("grpc-status").unwrap(), "0");
+
+    // compressed message is rejected with trailers-only response.
+    let mut msg = msg.to_vec();
+    msg[0] = 1;
+    let (res, mut tx) = client.send_request(req("application/grpc")?, false)?;
+    tx.send_data(Bytes::from(msg), true)?;
+
+    let res = res.await?;
+    assert_eq!(res.status(), StatusCode::OK);
+    assert_eq!(res.headers().get("grpc-status").unwrap(), "12");
+    assert!(res.body().is_end_stream());
+
+    // request not being grpc call does not pass route guard. router error resets the stream.
+    let (res, _) = client.send_request(req("application/json")?, true)?;
+    assert!(res.await.is_err());
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn h2_stuck_request() -> Result<(), Error> {
+    static STUCK: AtomicUsize = AtomicUsize::new(0);
+
+    let mut handle = test_server::<_, _, (xitca_io::net::TcpStream, SocketAddr)>(|| {
+        let config = HttpServiceConfig::new()
+            .stuck_request_threshold(Duration::from_millis(500), StuckRequestAction::Abort)
+            .connection_observer(|_, event| {
+                if event == ConnectionEvent::StuckRequest {
+                    STUCK.fetch_add(1, Ordering::SeqCst);
+                }
+            });
+        HttpServiceBuilder::h2(fn_service(stuck_handle)).config(config)
+    })?;
+
+    let stream = tokio::net::TcpStream::connect(handle.addr()).await?;
+    let (client, conn) = ::h2::client::handshake(stream).await?;
+    tokio::spawn(conn);
+
+    let mut client = client.ready().await?;
+
+    let uri = format!("http://{}", handle.ip_port_string());
+
+    let req = Request::get(format!("{uri}/stuck")).body(())?;
+    let (stuck, _) = client.send_request(req, true)?;
+
+    // other streams of connection are not affected.
+    let req = Request::get(format!("{uri}/")).body(())?;
+    let (res, _) = client.send_request(req, true)?;
+    assert_eq!(res.await?.status(), StatusCode::OK);
+
+    let res = stuck.await?;
+    assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert!(res.body().is_end_stream());
+    assert_eq!(STUCK.load(Ordering::SeqCst), 1);
+
+    // connection is still usable.
+    let req = Request::get(format!("{uri}/")).body(())?;
+    let (res, _) = client.send_request(req, true)?;
+    assert_eq!(res.await?.status(), StatusCode::OK);
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+// never resolve request to /stuck path.
+async fn stuck_handle(req: Request<RequestExt<h2::RequestBody>>) -> Result<Response<ResponseBody>, Error> {
+    if req.uri().path() == "/stuck" {
+        std::future::pending::<()>().await;
+    }
+    Ok(Response::new(Bytes::from_static(b"hello").into()))
+}
+
+// respond with sorted header names of request.
+async fn header_names_handle(req: Request<RequestExt<h2::RequestBody>>) -> Result<Response<ResponseBody>, Error> {
+    let mut names = req.headers().keys().map(|name| name.as_str()).collect::<Vec<_>>();
+    names.sort();
+    Ok(Response::new(Bytes::from(names.join(",")).into()))
+}
+
+async fn slow_handle(_: Request<RequestExt<h2::RequestBody>>) -> Result<Response<ResponseBody>, Error> {
+    tokio::time::sleep(Duration::from_millis(200)).await;
+    Ok(Response::new(Bytes::from_static(b"hello").into()))
+}
+
+async fn giant_header_handle(req: Request<RequestExt<h2::RequestBody>>) -> Result<Response<ResponseBody>, Error> {
+    let mut res = Response::new(Bytes::from_static(b"hello").into());
+    if req.uri().path() == "/giant" {
+        let value = HeaderValue::try_from("a".repeat(1024 * 1024))?;
+        res.headers_mut().insert("x-giant", value);
+    }
+    Ok(res)
+}
+
+async fn grpc_echo_handle(
+    req: Request<RequestExt<h2::RequestBody>>,
+) -> Result<Response<ResponseBody<grpc::MessageBody<Once<Bytes>>>>, Error> {
+    let mut messages = grpc::MessageStream::new(req.into_body());
+    let res = match messages.next().await {
+        Some(Ok(msg)) => grpc::response(Once::new(msg.data), grpc::Status::ok()),
+        Some(Err(e)) => grpc::error_response(e.into()),
+        None => grpc::error_response(grpc::Status::new(grpc::Code::InvalidArgument, "missing message")),
+    };
+    Ok(res)
+}
+
+async fn body_len_handle(req: Request<RequestExt<h2::RequestBody>>) -> Result<Response<ResponseBody>, Error> {
+    let mut body = req.into_body();
+    let mut len = 0;
+    while let Some(bytes) = body.next().await {
+        len += bytes?.len();
+    }
+    Ok(Response::new(Bytes::from(len.to_string()).into()))
+}
+
+async fn header_case_handle(_: Request<RequestExt<h2::RequestBody>>) -> Result<Response<ResponseBody>, Error> {
+    let mut map = HeaderCaseMap::new();
+    map.insert("X-API-Key");
+
+    let mut res = Response::new(Bytes::from_static(b"hello").into());
+    res.headers_mut().insert("x-api-key", HeaderValue::from_static("996"));
+    res.extensions_mut().insert(map);
+    Ok(res)
+}
+
 async fn handle(req: Request<RequestExt<h2::RequestBody>>) -> Result<Response<ResponseBody>, Error> {
     // Some yield for testing h2 dispatcher's concurrent future handling.
     tokio::task::yield_now().await;
@@ -115,6 +844,10 @@ async fn handle(req: Request<RequestExt<h2::RequestBody>>) -> Result<Response<Re
 
     match (req.method(), req.uri().path()) {
         (&Method::GET, "/") => Ok(Response::new(Bytes::from("GET Response").into())),
+        (&Method::GET, "/uri") => {
+            let body = format!("{:?} {}", req.version(), req.uri());
+            Ok(Response::new(Bytes::from(body).into()))
+        }
         (&Method::POST, "/") => {
             let (parts, mut body) = req.into_parts();
 
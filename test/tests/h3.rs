@@ -1,13 +1,25 @@
+use std::{
+    future::poll_fn,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, SystemTime},
+};
+
 use futures_util::StreamExt;
+use h3_quinn::quinn::{ClientConfig, Connection, ConnectionError, Endpoint};
 use xitca_client::Client;
 use xitca_http::{
     body::ResponseBody,
-    bytes::{Bytes, BytesMut},
+    bytes::{Buf, Bytes, BytesMut},
     h3,
     http::{header, Method, Request, RequestExt, Response, Version},
 };
+use xitca_io::net::H3TransportConfig;
 use xitca_service::fn_service;
-use xitca_test::{test_h3_server, Error};
+use xitca_test::{test_h3_server, test_h3_server_with_early_data, test_h3_server_with_transport, Error};
 
 #[tokio::test]
 async fn h3_get() -> Result<(), Error> {
@@ -56,6 +68,225 @@ async fn h3_post() -> Result<(), Error> {
     Ok(())
 }
 
+#[tokio::test]
+async fn h3_uri() -> Result<(), Error> {
+    let mut handle = test_h3_server(|| fn_service(handle))?;
+
+    let authority = format!("localhost:{}", handle.addr().port());
+    let server_url = format!("https://{authority}/uri?foo=bar");
+
+    let c = Client::new();
+
+    let mut res = c.get(&server_url)?.version(Version::HTTP_3).send().await?;
+    assert_eq!(res.status().as_u16(), 200);
+    let body = res.string().await?;
+    assert_eq!(body, format!("HTTP/3.0 {server_url}"));
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn h3_early_data_reject() -> Result<(), Error> {
+    let mut handle = test_h3_server_with_early_data(h3::EarlyData::Reject, || fn_service(early_data_handle))?;
+
+    let endpoint = zero_rtt_endpoint()?;
+
+    // first connection does full handshake and obtains session ticket for resumption.
+    let conn = endpoint.connect(handle.addr(), "localhost")?.await?;
+    let (status, early) = early_data_request(conn, Method::GET).await?;
+    assert_eq!(status, 200);
+    assert_eq!(early, "");
+
+    // resumed connection sends request in 0-RTT early data.
+    // replayable POST request is rejected.
+    let conn = zero_rtt_connect(&endpoint, handle.addr())?;
+    let (status, _) = early_data_request(conn, Method::POST).await?;
+    assert_eq!(status, 425);
+
+    // idempotent GET request is passed to service with early-data header.
+    let conn = zero_rtt_connect(&endpoint, handle.addr())?;
+    let (status, early) = early_data_request(conn, Method::GET).await?;
+    assert_eq!(status, 200);
+    assert_eq!(early, "1");
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn h3_idle_timeout() -> Result<(), Error> {
+    let transport = H3TransportConfig::new().max_idle_timeout(Duration::from_millis(300));
+    let mut handle = test_h3_server_with_transport(transport, || fn_service(handle))?;
+
+    // idle timeout is negotiated to the smaller one of server and client.
+    let endpoint = zero_rtt_endpoint()?;
+    let conn = endpoint.connect(handle.addr(), "localhost")?.await?;
+
+    let err = tokio::time::timeout(Duration::from_secs(5), conn.closed()).await?;
+    assert!(matches!(err, ConnectionError::TimedOut));
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn h3_concurrent_large_body() -> Result<(), Error> {
+    let mut handle = test_h3_server(|| fn_service(large_body_handle))?;
+
+    let endpoint = zero_rtt_endpoint()?;
+    let conn = endpoint.connect(handle.addr(), "localhost")?.await?;
+
+    let (mut driver, send_request) = ::h3::client::new(h3_quinn::Connection::new(conn)).await?;
+
+    let driver = tokio::spawn(async move {
+        let _ = poll_fn(|cx| driver.poll_close(cx)).await;
+    });
+
+    // global order of data chunks received by both streams.
+    let order = AtomicUsize::new(0);
+
+    let (a, b) = futures_util::future::join(
+        large_body_request(send_request.clone(), &order),
+        large_body_request(send_request, &order),
+    )
+    .await;
+
+    driver.abort();
+
+    let (a, b) = (a?, b?);
+
+    // both responses make progress before the other one finishes.
+    assert!(a.first().unwrap() < b.last().unwrap());
+    assert!(b.first().unwrap() < a.last().unwrap());
+
+    handle.try_handle()?.stop(false);
+
+    handle.await?;
+
+    Ok(())
+}
+
+const LARGE_BODY_SIZE: usize = 8 * 1024 * 1024;
+
+// send a request for large body and return received order of every data chunk.
+async fn large_body_request<C>(
+    mut send_request: ::h3::client::SendRequest<C, Bytes>,
+    order: &AtomicUsize,
+) -> Result<Vec<usize>, Error>
+where
+    C: ::h3::quic::OpenStreams<Bytes>,
+{
+    let req = Request::builder().uri("https://localhost/large").body(())?;
+
+    let mut stream = send_request.send_request(req).await?;
+    stream.finish().await?;
+
+    let res = stream.recv_response().await?;
+    assert_eq!(res.status().as_u16(), 200);
+
+    let mut timeline = Vec::new();
+    let mut len = 0;
+    while let Some(bytes) = stream.recv_data().await? {
+        len += bytes.remaining();
+        timeline.push(order.fetch_add(1, Ordering::Relaxed));
+    }
+
+    assert_eq!(len, LARGE_BODY_SIZE);
+
+    Ok(timeline)
+}
+
+async fn large_body_handle(_: Request<RequestExt<h3::RequestBody>>) -> Result<Response<ResponseBody>, Error> {
+    // body is one large chunk and is split by dispatcher.
+    let body = Bytes::from(vec![b'a'; LARGE_BODY_SIZE]);
+    Ok(Response::new(body.into()))
+}
+
+fn zero_rtt_endpoint() -> Result<Endpoint, Error> {
+    struct SkipServerVerification;
+
+    impl rustls::client::ServerCertVerifier for SkipServerVerification {
+        fn verify_server_cert(
+            &self,
+            _: &rustls::Certificate,
+            _: &[rustls::Certificate],
+            _: &rustls::ServerName,
+            _: &mut dyn Iterator<Item = &[u8]>,
+            _: &[u8],
+            _: SystemTime,
+        ) -> Result<rustls::client::ServerCertVerified, rustls::Error> {
+            Ok(rustls::client::ServerCertVerified::assertion())
+        }
+    }
+
+    let mut crypto = rustls::ClientConfig::builder()
+        .with_safe_defaults()
+        .with_custom_certificate_verifier(Arc::new(SkipServerVerification))
+        .with_no_client_auth();
+    crypto.alpn_protocols = vec![b"h3".to_vec()];
+    crypto.enable_early_data = true;
+
+    let mut endpoint = Endpoint::client("127.0.0.1:0".parse()?)?;
+    endpoint.set_default_client_config(ClientConfig::new(Arc::new(crypto)));
+
+    Ok(endpoint)
+}
+
+fn zero_rtt_connect(endpoint: &Endpoint, addr: SocketAddr) -> Result<Connection, Error> {
+    endpoint
+        .connect(addr, "localhost")?
+        .into_0rtt()
+        .map(|(conn, _)| conn)
+        .map_err(|_| "0-RTT is not available for resumed connection".into())
+}
+
+// send a request without body and return response status and body.
+async fn early_data_request(conn: Connection, method: Method) -> Result<(u16, String), Error> {
+    let (mut driver, mut send_request) = ::h3::client::new(h3_quinn::Connection::new(conn)).await?;
+
+    let driver = tokio::spawn(async move {
+        let _ = poll_fn(|cx| driver.poll_close(cx)).await;
+    });
+
+    let req = Request::builder()
+        .method(method)
+        .uri("https://localhost/early_data")
+        .body(())?;
+
+    let mut stream = send_request.send_request(req).await?;
+    stream.finish().await?;
+
+    let res = stream.recv_response().await?;
+
+    let mut body = Vec::new();
+    while let Some(bytes) = stream.recv_data().await? {
+        body.extend_from_slice(bytes.chunk());
+    }
+
+    driver.abort();
+
+    Ok((res.status().as_u16(), String::from_utf8(body)?))
+}
+
+async fn early_data_handle(req: Request<RequestExt<h3::RequestBody>>) -> Result<Response<ResponseBody>, Error> {
+    let early = req
+        .headers()
+        .get("early-data")
+        .map(|v| v.as_bytes().to_vec())
+        .unwrap_or_default();
+    Ok(Response::new(early.into()))
+}
+
 async fn handle(req: Request<RequestExt<h3::RequestBody>>) -> Result<Response<ResponseBody>, Error> {
     // Some yield for testing h3 dispatcher's concurrent future handling.
     tokio::task::yield_now().await;
@@ -64,6 +295,10 @@ async fn handle(req: Request<RequestExt<h3::RequestBody>>) -> Result<Response<Re
 
     match (req.method(), req.uri().path()) {
         (&Method::GET, "/") => Ok(Response::new(Bytes::from("GET Response").into())),
+        (&Method::GET, "/uri") => {
+            let body = format!("{:?} {}", req.version(), req.uri());
+            Ok(Response::new(Bytes::from(body).into()))
+        }
         (&Method::POST, "/") => {
             let (parts, mut body) = req.into_parts();
 
@@ -0,0 +1,118 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+};
+
+use tokio::time::Instant;
+use xitca_http::{
+    body::ResponseBody,
+    bytes::{Bytes, BytesMut},
+    date::DateTime,
+    h1::proto::context::Context,
+    http::{
+        header::{HeaderName, HeaderValue, CONTENT_TYPE, SERVER},
+        Response,
+    },
+};
+
+// global allocator counting allocations of current thread so reuse of pooled header map can be
+// asserted without interference from other tests.
+struct Counting;
+
+thread_local! {
+    static ALLOCATIONS: Cell<usize> = const { Cell::new(0) };
+}
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let _ = ALLOCATIONS.try_with(|a| a.set(a.get() + 1));
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn allocations() -> usize {
+    ALLOCATIONS.with(Cell::get)
+}
+
+// static date so encoding does not depend on timer task.
+struct Date;
+
+impl DateTime for Date {
+    const DATE_VALUE_LENGTH: usize = 29;
+
+    fn with_date<F, O>(&self, f: F) -> O
+    where
+        F: FnOnce(&[u8]) -> O,
+    {
+        f(b"Sun, 06 Nov 1994 08:49:37 GMT")
+    }
+
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+const REQ: &[u8] = b"GET / HTTP/1.1\r\n\r\n";
+const REQ_HEADERS: &[u8] = b"GET / HTTP/1.1\r\naccept-encoding: gzip\r\naccept: */*\r\nuser-agent: b\r\n\r\n";
+
+const ROUNDS: usize = 1000;
+
+// decode a request and encode response for it like a keep-alive connection does.
+fn round(ctx: &mut Context<'_, Date, 64>, read_buf: &mut BytesMut, write_buf: &mut BytesMut, headers: usize) {
+    let (req, _) = ctx.decode_head::<4096>(read_buf).unwrap().unwrap();
+    drop(req);
+
+    let mut res = Response::<ResponseBody>::new(ResponseBody::from(Bytes::from_static(b"996")));
+    for i in 0..headers {
+        let name = HeaderName::from_bytes(format!("x-{i}").as_bytes()).unwrap();
+        res.headers_mut().insert(name, HeaderValue::from_static("996"));
+    }
+    res.headers_mut().insert(SERVER, HeaderValue::from_static("xitca-web"));
+    res.headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+
+    let (parts, body) = res.into_parts();
+    ctx.encode_head(parts, &body, write_buf).unwrap();
+    write_buf.clear();
+}
+
+// allocations of ROUNDS keep-alive requests after warm up.
+fn keep_alive(ctx: &mut Context<'_, Date, 64>, req: &[u8]) -> usize {
+    let mut read_buf = BytesMut::with_capacity(req.len() * (ROUNDS + 1));
+    let mut write_buf = BytesMut::with_capacity(4096);
+    for _ in 0..=ROUNDS {
+        read_buf.extend_from_slice(req);
+    }
+
+    round(ctx, &mut read_buf, &mut write_buf, 0);
+
+    let before = allocations();
+    for _ in 0..ROUNDS {
+        round(ctx, &mut read_buf, &mut write_buf, 0);
+    }
+    allocations() - before
+}
+
+#[test]
+fn h1_header_map_reuse() {
+    let mut ctx = Context::new(&Date);
+
+    // request headers are decoded into pooled map without allocating a new one.
+    let base = keep_alive(&mut ctx, REQ);
+    assert_eq!(keep_alive(&mut ctx, REQ_HEADERS), base);
+
+    // one response with large amount of headers does not grow the pooled map.
+    let mut read_buf = BytesMut::from(REQ);
+    round(&mut ctx, &mut read_buf, &mut BytesMut::new(), 200);
+    let headers = ctx.take_headers();
+    assert!(headers.capacity() <= Context::<Date, 64>::MAX_CACHED_HEADER_CAPACITY);
+    ctx.replace_headers(headers);
+    assert_eq!(keep_alive(&mut ctx, REQ_HEADERS), base);
+}
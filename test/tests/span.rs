@@ -0,0 +1,138 @@
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Id, Record},
+    Event, Subscriber,
+};
+use tracing_subscriber::{
+    layer::{Context, SubscriberExt},
+    registry::LookupSpan,
+    Layer, Registry,
+};
+use xitca_client::Client;
+use xitca_http::{
+    body::ResponseBody,
+    h1, h2,
+    http::{Request, RequestExt, Response, Version},
+};
+use xitca_service::fn_service;
+use xitca_test::{test_h1_server, test_h2_server, Error};
+
+// name and fields of spans an event is emitted in. ordered from leaf to root.
+type Scope = Vec<(&'static str, Fields)>;
+
+#[derive(Clone, Default)]
+struct Fields(Vec<(&'static str, String)>);
+
+impl Fields {
+    fn get(&self, name: &str) -> Option<&str> {
+        self.0.iter().find(|(n, _)| *n == name).map(|(_, v)| v.as_str())
+    }
+}
+
+impl Visit for Fields {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0.push((field.name(), value.to_owned()));
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        self.0.push((field.name(), format!("{value:?}")));
+    }
+}
+
+#[derive(Clone, Default)]
+struct Capture(Arc<Mutex<Vec<Scope>>>);
+
+impl<S> Layer<S> for Capture
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        let mut fields = Fields::default();
+        attrs.record(&mut fields);
+        ctx.span(id).unwrap().extensions_mut().insert(fields);
+    }
+
+    fn on_record(&self, id: &Id, values: &Record<'_>, ctx: Context<'_, S>) {
+        let span = ctx.span(id).unwrap();
+        let mut ext = span.extensions_mut();
+        values.record(ext.get_mut::<Fields>().unwrap());
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        if event.metadata().target() != module_path!() {
+            return;
+        }
+        let scope = ctx
+            .event_scope(event)
+            .into_iter()
+            .flatten()
+            .map(|span| (span.name(), span.extensions().get::<Fields>().unwrap().clone()))
+            .collect();
+        self.0.lock().unwrap().push(scope);
+    }
+}
+
+async fn handle<B>(_: Request<RequestExt<B>>) -> Result<Response<ResponseBody>, Error> {
+    tracing::info!("handler");
+    Ok(Response::new("span".into()))
+}
+
+fn assert_scope(scope: &Scope, protocol: &str, stream_id: Option<&str>) {
+    let [(req_name, req), (conn_name, conn)] = scope.as_slice() else {
+        panic!("event must be emitted in request span and connection span")
+    };
+
+    assert_eq!(*req_name, "request");
+    assert_eq!(req.get("method"), Some("GET"));
+    assert_eq!(req.get("path"), Some("/span"));
+    assert_eq!(req.get("stream_id"), stream_id);
+
+    assert_eq!(*conn_name, "connection");
+    assert_eq!(conn.get("protocol"), Some(protocol));
+    assert!(conn.get("peer_addr").unwrap().starts_with("127.0.0.1:"));
+    assert!(conn.get("id").is_some());
+}
+
+// subscriber is set globally for capturing spans in server threads. all cases must live in one
+// test.
+#[tokio::test]
+async fn request_span() -> Result<(), Error> {
+    let capture = Capture::default();
+    tracing::subscriber::set_global_default(Registry::default().with(capture.clone())).unwrap();
+
+    let c = Client::new();
+
+    let mut server = test_h1_server(|| fn_service(handle::<h1::RequestBody>))?;
+    let res = c
+        .get(&format!("http://{}/span", server.ip_port_string()))?
+        .send()
+        .await?;
+    assert_eq!(res.status().as_u16(), 200);
+    assert_eq!(res.string().await?, "span");
+    server.try_handle()?.stop(false);
+    server.await?;
+
+    let mut server = test_h2_server(|| fn_service(handle::<h2::RequestBody>))?;
+    let res = c
+        .get(&format!("https://{}/span", server.ip_port_string()))?
+        .version(Version::HTTP_2)
+        .send()
+        .await?;
+    assert_eq!(res.status().as_u16(), 200);
+    assert_eq!(res.string().await?, "span");
+    server.try_handle()?.stop(false);
+    server.await?;
+
+    let scopes = capture.0.lock().unwrap();
+    assert_eq!(scopes.len(), 2);
+    assert_scope(&scopes[0], "h1", None);
+    // first stream initiated by client.
+    assert_scope(&scopes[1], "h2", Some("1"));
+
+    Ok(())
+}
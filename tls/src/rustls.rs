@@ -56,6 +56,10 @@ where
         &self.conn
     }
 
+    pub fn session_mut(&mut self) -> &mut C {
+        &mut self.conn
+    }
+
     /// finish handshake with given io and connection type.
     /// # Examples:
     /// ```rust
@@ -1,5 +1,5 @@
 use core::{
-    cell::RefCell,
+    cell::{Ref, RefCell},
     future::Future,
     ops::{Deref, DerefMut},
     slice,
@@ -49,6 +49,13 @@ struct Session<C> {
     write_buf: Option<WriteBuf>,
 }
 
+impl<C, Io> TlsStream<C, Io> {
+    /// Access the tls session of stream.
+    pub fn session(&self) -> Ref<'_, C> {
+        Ref::map(self.session.borrow(), |session| &session.session)
+    }
+}
+
 impl<C, S> Session<C>
 where
     C: DerefMut + Deref<Target = ConnectionCommon<S>>,
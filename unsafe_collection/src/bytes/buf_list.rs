@@ -36,6 +36,44 @@ impl<B: Buf, const LEN: usize> BufList<B, LEN> {
         self.bufs.push_back(buf).expect("BufList overflown");
     }
 
+    /// Replace item at given index counting from the front of list and return the replaced one.
+    /// Order of items in list is kept.
+    ///
+    /// # Panic:
+    ///
+    /// when index is out of bound.
+    pub fn replace(&mut self, idx: usize, buf: B) -> B {
+        let len = self.bufs.len();
+        assert!(idx < len, "BufList index out of bound");
+
+        self.remaining += buf.remaining();
+
+        let mut buf = Some(buf);
+        let mut replaced = None;
+
+        // rotate the queue once and swap the item at index on the way.
+        for i in 0..len {
+            let mut item = self.bufs.pop_front().unwrap();
+            if i == idx {
+                replaced = Some(item);
+                item = buf.take().unwrap();
+            }
+            if self.bufs.push_back(item).is_err() {
+                unreachable!("BufList item is popped before pushed back");
+            }
+        }
+
+        let replaced = replaced.unwrap();
+        self.remaining -= replaced.remaining();
+        replaced
+    }
+
+    /// Iterate items of list from front to back.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = &B> {
+        self.bufs.iter()
+    }
+
     #[inline]
     pub fn is_full(&self) -> bool {
         self.bufs.is_full()
@@ -228,6 +266,16 @@ mod tests {
         assert_eq!(buf.chunk(), b" World");
     }
 
+    #[test]
+    fn replace() {
+        let mut bufs = hello_world_buf();
+        bufs.advance(2);
+        assert_eq!(bufs.replace(1, Bytes::from(", ")), " ");
+        assert_eq!(bufs.remaining(), 10);
+        assert_eq!(bufs.iter().collect::<Vec<_>>(), ["llo", ", ", "World"]);
+        assert_eq!(bufs.copy_to_bytes(10), "llo, World");
+    }
+
     #[test]
     #[should_panic(expected = "`len` greater than remaining")]
     fn buf_to_bytes_too_many() {
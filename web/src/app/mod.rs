@@ -10,7 +10,7 @@ use core::{
 use futures_core::stream::Stream;
 use xitca_http::util::service::{
     context::{Context, ContextBuilder},
-    router::{GenericRouter, PathGen},
+    router::{Describe, GenericRouter, Layer, LayerKind, Manifest, PathGen, RouteConflict},
 };
 
 use crate::{
@@ -22,12 +22,17 @@ use crate::{
             ServiceExt,
         },
     },
-    handler::Responder,
-    http::{Request, RequestExt},
-    request::WebRequest,
-    response::WebResponse,
+    handler::{redirect::Redirect, Responder},
+    http::{Request, RequestExt, StatusCode},
+    middleware::flow::{Expect, Upgrade},
+    request::{RequestBody, WebRequest},
+    response::{ResponseHooks, WebResponse},
+    test::TestService,
 };
 
+#[cfg(feature = "config")]
+use crate::config::{Config, ConfigSource};
+
 use self::object::WebObjectConstructor;
 
 pub struct App<CF = (), R = ()> {
@@ -66,6 +71,26 @@ impl App {
         Self::with_async_state(move || ready(Ok(state.clone())))
     }
 
+    /// Construct App with [Config] of given source as state. Typed sections of it can be extracted
+    /// by handlers with [Conf](crate::handler::config::Conf) extractor.
+    ///
+    /// Each worker thread has it's own [Config] sharing the source and caching deserialized
+    /// sections separately. Use [Reloadable](crate::config::Reloadable) source when configuration
+    /// has to be changed at runtime.
+    ///
+    /// Config can also be a part of custom state that impl `Borrow<Config>`. See
+    /// [config](crate::config) module for detail.
+    #[cfg(feature = "config")]
+    pub fn with_config<S, B, SF>(
+        source: S,
+    ) -> App<impl Fn() -> Ready<Result<Config, Infallible>>, Router<Config, B, SF>>
+    where
+        S: ConfigSource,
+    {
+        let source = std::sync::Arc::new(source);
+        Self::with_async_state(move || ready(Ok(Config::from_shared(source.clone()))))
+    }
+
     #[doc(hidden)]
     /// Construct App with async closure which it's output would be used as state.
     pub fn with_async_state<CF, Fut, E, C, B, SF>(ctx_factory: CF) -> App<CF, Router<C, B, SF>>
@@ -81,14 +106,96 @@ impl App {
 }
 
 impl<CF, C, B, SF> App<CF, Router<C, B, SF>> {
+    /// Register a service factory to given path.
+    ///
+    /// # Panics:
+    /// When path is conflicting with other registered path. The panic message contains both paths
+    /// and source locations of their registration. See [App::try_at] for non panic version.
+    #[track_caller]
     pub fn at<F>(mut self, path: &'static str, factory: F) -> App<CF, Router<C, B, SF>>
     where
-        F: PathGen,
+        F: PathGen + Describe,
         WebObjectConstructor<C, B>: ObjectConstructor<F, Object = SF>,
     {
         self.router = self.router.insert(path, factory);
         self
     }
+
+    /// Register a service factory to given path. Return [RouteConflict] when path is conflicting
+    /// with other registered path. See [GenericRouter::try_insert] for conflicting paths.
+    #[track_caller]
+    pub fn try_at<F>(mut self, path: &'static str, factory: F) -> Result<App<CF, Router<C, B, SF>>, RouteConflict>
+    where
+        F: PathGen + Describe,
+        WebObjectConstructor<C, B>: ObjectConstructor<F, Object = SF>,
+    {
+        self.router = self.router.try_insert(path, factory)?;
+        Ok(self)
+    }
+
+    /// Set a service to handle requests that do not match any path of App. It replaces the default
+    /// 404 response and receives the unmatched request as is. (including it's body)
+    ///
+    /// The service is enclosed by App's middlewares like other services and it must have the same
+    /// error type as them. [ServiceExt::map_err] can be used to align it when needed.
+    ///
+    /// # Examples:
+    /// ```rust
+    /// use xitca_web::{
+    ///     handler::{handler_service, html::Html},
+    ///     route::get,
+    ///     App,
+    /// };
+    ///
+    /// // serve index page of single page application for all unknown paths.
+    /// App::new()
+    ///     .at("/api", get(handler_service(|| async { "api" })))
+    ///     .default_service(get(handler_service(|| async { Html("<html></html>") })));
+    /// ```
+    pub fn default_service<F>(mut self, factory: F) -> App<CF, Router<C, B, SF>>
+    where
+        WebObjectConstructor<C, B>: ObjectConstructor<F, Object = SF>,
+    {
+        self.router = self.router.default_service(factory);
+        self
+    }
+
+    /// Allow overlapping paths registered with [App::at]. It must be called before registering
+    /// paths. See [GenericRouter::allow_shadowing] for detail.
+    pub fn allow_shadowing(mut self) -> App<CF, Router<C, B, SF>> {
+        self.router = self.router.allow_shadowing();
+        self
+    }
+
+    /// Register a route redirecting all requests to given path to location with given status
+    /// code. Location is used as is and relative location is not resolved.
+    ///
+    /// # Examples:
+    /// ```rust
+    /// # use xitca_web::{handler::handler_service, request::WebRequest, App};
+    /// App::new()
+    ///     .redirect("/old", "/new", 308)
+    ///     .at("/new", handler_service(handler));
+    ///
+    /// # async fn handler(_: &WebRequest<'_>) -> &'static str {
+    /// #   "new"
+    /// # }
+    /// ```
+    ///
+    /// # Panics:
+    /// When status is not a redirect(3xx) status code or location is not a valid header value.
+    #[track_caller]
+    pub fn redirect(self, path: &'static str, location: &'static str, status: u16) -> App<CF, Router<C, B, SF>>
+    where
+        WebObjectConstructor<C, B>: ObjectConstructor<Redirect, Object = SF>,
+    {
+        let status = StatusCode::from_u16(status)
+            .ok()
+            .filter(StatusCode::is_redirection)
+            .unwrap_or_else(|| panic!("{status} is not a redirect status code"));
+        let redirect = Redirect::new(status, location).expect("location must be valid header value");
+        self.at(path, redirect)
+    }
 }
 
 impl<CF, R> App<CF, R>
@@ -118,6 +225,69 @@ where
         }
     }
 
+    /// Handle request with `Expect: 100-continue` header with the service built by given factory
+    /// before it's passed to App's router and middlewares enclosed so far.
+    ///
+    /// The service responds with [Flow](crate::middleware::flow::Flow) where `Flow::Respond` rejects
+    /// the request without requesting it's body. See [Expect] for detail.
+    pub fn expect_service<F>(self, factory: F) -> App<CF, EnclosedFactory<R, Expect<F>>>
+    where
+        F: Service,
+    {
+        App {
+            ctx_factory: self.ctx_factory,
+            router: EnclosedFactory::new(self.router, Expect::new(factory)),
+        }
+    }
+
+    /// Handle request with `Upgrade` header with the service built by given factory before it's
+    /// passed to App's router and middlewares enclosed so far.
+    ///
+    /// The service responds with [Flow](crate::middleware::flow::Flow) where `Flow::Pass` declines
+    /// the upgrade and lets request fall through to router. See [Upgrade] for detail.
+    pub fn upgrade_service<F>(self, factory: F) -> App<CF, EnclosedFactory<R, Upgrade<F>>>
+    where
+        F: Service,
+    {
+        App {
+            ctx_factory: self.ctx_factory,
+            router: EnclosedFactory::new(self.router, Upgrade::new(factory)),
+        }
+    }
+
+    /// Describe service pipeline App would compose with [App::finish] without building it.
+    /// Layers are ordered from the outermost one receiving request first. Middlewares enclosed
+    /// later wrap the ones enclosed before them and nested routers are described with their
+    /// full path patterns.
+    ///
+    /// Middleware types are named after their type names without module path and generic
+    /// parameters.
+    ///
+    /// # Examples:
+    /// ```rust
+    /// use xitca_web::{handler::handler_service, route::get, App};
+    ///
+    /// let app = App::new()
+    ///     .at("/", get(handler_service(|| async { "hello" })))
+    ///     .at("/hi", get(handler_service(|| async { "hi" })).post(handler_service(|| async { "" })));
+    ///
+    /// // print manifest as an indented tree.
+    /// println!("{}", app.describe());
+    /// # let _ = app.finish_for_test();
+    /// ```
+    pub fn describe(&self) -> Manifest
+    where
+        R: Describe,
+    {
+        let mut manifest = Manifest::new();
+        // App converts http request to WebRequest with it's state here.
+        manifest.push(Layer::new("map_request", LayerKind::Protocol));
+        // App converts error of it's services to response here.
+        manifest.push(Layer::new("map_response", LayerKind::Protocol));
+        self.router.describe(&mut manifest);
+        manifest
+    }
+
     /// Finish App build. No other App method can be called afterwards.
     pub fn finish<C, Fut, CErr, ReqB, ResB, E, Err>(
         self,
@@ -142,6 +312,35 @@ where
 
         ContextBuilder::new(ctx_factory).service(service)
     }
+
+    /// Finish App build and construct an in process [TestService] from it. The service runs the
+    /// same pipeline as [App::finish] does without binding to any socket.
+    ///
+    /// See [test](crate::test) module for example.
+    ///
+    /// # Panics
+    ///
+    /// Panics when App state or service failed to construct.
+    pub async fn finish_for_test<C, Fut, CErr, ResB, E, Err>(
+        self,
+    ) -> TestService<
+        impl ReadyService
+            + Service<Request<RequestExt<RequestBody>>, Response = WebResponse<ResponseBody<ResB>>, Error = Err>,
+    >
+    where
+        CF: Fn() -> Fut,
+        Fut: Future<Output = Result<C, CErr>>,
+        C: 'static,
+        CErr: fmt::Debug,
+        R::Response:
+            ReadyService + for<'r> Service<WebRequest<'r, C, RequestBody>, Response = WebResponse<ResB>, Error = Err>,
+        R::Error: fmt::Debug,
+        Err: for<'r> Responder<WebRequest<'r, C, RequestBody>, Output = WebResponse>,
+        ResB: Stream<Item = Result<Bytes, E>>,
+    {
+        let service = self.finish().call(()).await.expect("failed to build test service");
+        TestService(service)
+    }
 }
 
 async fn map_response<B, C, S, ResB, E, Err>(
@@ -155,11 +354,13 @@ where
     Err: for<'r> Responder<WebRequest<'r, C, B>, Output = WebResponse>,
     ResB: Stream<Item = Result<Bytes, E>>,
 {
-    match service.call(req.reborrow()).await {
-        Ok(res) => Ok(res.map(|body| ResponseBody::stream(body))),
+    let res = match service.call(req.reborrow()).await {
+        Ok(res) => res.map(|body| ResponseBody::stream(body)),
         // TODO: mutate response header according to outcome of drop_stream_cast?
-        Err(e) => Ok(e.respond_to(req).await.map(|body| body.drop_stream_cast())),
-    }
+        Err(e) => e.respond_to(req.reborrow()).await.map(|body| body.drop_stream_cast()),
+    };
+
+    Ok(ResponseHooks::run(res, req.req_mut().extensions_mut()))
 }
 
 async fn map_request<B, C, S, Res, Err>(service: &S, req: Context<'_, Request<RequestExt<B>>, C>) -> Result<Res, Err>
@@ -188,14 +389,20 @@ mod test {
 
     use crate::{
         dev::service::Service,
+        error::RouteError,
         handler::{
             extension::ExtensionRef, extension::ExtensionsRef, handler_service, path::PathRef, state::StateRef,
             uri::UriRef, Responder,
         },
-        http::{const_header_value::TEXT_UTF8, header::CONTENT_TYPE, Method, Uri},
+        http::{
+            const_header_value::{TEXT_HTML_UTF8, TEXT_UTF8},
+            header::{HeaderValue, CONTENT_TYPE},
+            Method, StatusCode, Uri,
+        },
         middleware::UncheckedReady,
         request::RequestBody,
-        route::get,
+        route::{get, guard::header_eq, post},
+        test::TestRequest,
     };
 
     use super::*;
@@ -333,4 +540,176 @@ mod test {
     }
 
     struct Foo;
+
+    #[test]
+    fn route_guard() {
+        let service = App::new()
+            .at(
+                "/",
+                get(handler_service(|| async { "v2" }))
+                    .guard(header_eq("x-api-version", "2"))
+                    .next(get(handler_service(|| async { "v1" }))),
+            )
+            .at(
+                "/guarded",
+                get(handler_service(|| async { "v2" }))
+                    .guard(header_eq("x-api-version", "2"))
+                    .next(post(handler_service(|| async { "v1" }))),
+            )
+            .finish_for_test()
+            .now_or_panic();
+
+        let res = service
+            .call(TestRequest::get("/").header("x-api-version", "2"))
+            .now_or_panic()
+            .unwrap();
+        assert_eq!(res.string_body().now_or_panic().unwrap(), "v2");
+
+        let res = service.call(TestRequest::get("/")).now_or_panic().unwrap();
+        assert_eq!(res.string_body().now_or_panic().unwrap(), "v1");
+
+        let res = service
+            .call(TestRequest::get("/guarded").header("x-api-version", "1"))
+            .now_or_panic()
+            .unwrap();
+        res.assert_status(StatusCode::NOT_FOUND);
+
+        let req = TestRequest::get("/guarded").method(Method::PUT);
+        let res = service.call(req).now_or_panic().unwrap();
+        res.assert_status(StatusCode::METHOD_NOT_ALLOWED);
+    }
+
+    #[test]
+    fn route_conflict() {
+        let line = line!();
+        let app = App::new::<RequestBody, _>().at("/users/:id", get(handler_service(|| async { "get" })));
+        let msg = app
+            .try_at("/users/:name", post(handler_service(|| async { "post" })))
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(msg.contains(&format!("{}:{}:", file!(), line + 1)));
+        assert!(msg.contains(&format!("{}:{}:", file!(), line + 3)));
+    }
+
+    #[test]
+    fn default_service() {
+        async fn fallback(req: &WebRequest<'_>, body: String) -> WebResponse {
+            let mut res = match *req.req().method() {
+                Method::GET | Method::HEAD => {
+                    let mut res = WebResponse::new(ResponseBody::from("<html>index</html>"));
+                    res.headers_mut().insert(CONTENT_TYPE, TEXT_HTML_UTF8);
+                    res
+                }
+                _ => {
+                    let mut res = WebResponse::new(ResponseBody::from(r#"{"error":"not found"}"#));
+                    *res.status_mut() = StatusCode::NOT_FOUND;
+                    res.headers_mut()
+                        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+                    res
+                }
+            };
+            res.headers_mut().insert("x-body", body.parse().unwrap());
+            res
+        }
+
+        async fn middleware<S, C, B, ResB, Err>(
+            service: &S,
+            req: WebRequest<'_, C, B>,
+        ) -> Result<WebResponse<ResB>, Err>
+        where
+            S: for<'r> Service<WebRequest<'r, C, B>, Response = WebResponse<ResB>, Error = Err>,
+        {
+            let mut res = service.call(req).await?;
+            res.headers_mut().insert("x-middleware", HeaderValue::from_static("on"));
+            Ok(res)
+        }
+
+        let service = App::new()
+            .at("/api", get(handler_service(|| async { "api" })))
+            .default_service(handler_service(fallback).map_err(RouteError::Second))
+            .enclosed_fn(middleware)
+            .finish_for_test()
+            .now_or_panic();
+
+        let res = service.call(TestRequest::get("/api")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK).assert_header("x-middleware", "on");
+
+        let res = service.call(TestRequest::get("/foo/bar")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK)
+            .assert_header(CONTENT_TYPE, "text/html; charset=utf-8")
+            .assert_header("x-middleware", "on");
+        assert_eq!(res.string_body().now_or_panic().unwrap(), "<html>index</html>");
+
+        let req = TestRequest::get("/foo").method(Method::POST).body("foo");
+        let res = service.call(req).now_or_panic().unwrap();
+        res.assert_status(StatusCode::NOT_FOUND)
+            .assert_header(CONTENT_TYPE, "application/json")
+            .assert_header("x-body", "foo")
+            .assert_header("x-middleware", "on");
+        assert_eq!(res.string_body().now_or_panic().unwrap(), r#"{"error":"not found"}"#);
+    }
+
+    #[test]
+    fn describe() {
+        // services of App must share the same error type. only nested routers are registered for it.
+        let api = Router::<(), RequestBody, _>::new()
+            .insert("/users", get(handler_service(|| async { "users" })))
+            .insert(
+                "/posts/:id",
+                get(handler_service(|| async { "post" })).delete(handler_service(|| async { "" })),
+            );
+        let admin = Router::<(), RequestBody, _>::new().insert("/", get(handler_service(|| async { "admin" })));
+
+        let app = App::new()
+            .at("/api", api)
+            .at("/admin", admin)
+            .enclosed(Middleware)
+            .enclosed(UncheckedReady);
+
+        let manifest = app.describe();
+
+        let layers = manifest
+            .layers()
+            .iter()
+            .map(|l| (l.name(), l.kind()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            layers,
+            [
+                ("map_request", LayerKind::Protocol),
+                ("map_response", LayerKind::Protocol),
+                ("UncheckedReady", LayerKind::Middleware),
+                ("Middleware", LayerKind::Middleware),
+                ("Router", LayerKind::Router),
+            ]
+        );
+
+        let routes = manifest.layers()[4].routes();
+        let paths = routes.iter().map(|(path, _)| path.as_ref()).collect::<Vec<_>>();
+        assert_eq!(paths, ["/api/:r", "/admin/:r"]);
+
+        let nested = |idx: usize| {
+            let layers = routes[idx].1.layers();
+            assert_eq!(layers.len(), 1);
+            assert_eq!(layers[0].kind(), LayerKind::Router);
+            layers[0]
+                .routes()
+                .iter()
+                .map(|(path, m)| (path.to_string(), m.layers()[0].name().to_string()))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            nested(0),
+            [
+                ("/api/users".to_string(), "GET".to_string()),
+                ("/api/posts/:id".to_string(), "GET|DELETE".to_string())
+            ]
+        );
+        assert_eq!(nested(1), [("/admin/".to_string(), "GET".to_string())]);
+
+        let tree = manifest.to_string();
+        assert!(tree.starts_with("map_request (protocol)\nmap_response (protocol)\n"));
+        assert!(tree.contains("\n      /api/posts/:id\n        GET|DELETE (route)\n"));
+    }
 }
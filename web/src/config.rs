@@ -0,0 +1,631 @@
+//! Typed configuration used as App state and extracted by handlers.
+//!
+//! See [App::with_config](crate::App::with_config) and [Conf](crate::handler::config::Conf) for
+//! usage. [Config] can be a field of custom App state as well when the state impl `Borrow<Config>`.
+
+use core::{
+    any::{Any, TypeId},
+    cell::RefCell,
+    fmt,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use std::{
+    collections::HashMap,
+    env, error,
+    sync::{Arc, RwLock},
+};
+
+use serde::{
+    de::{
+        self,
+        value::{MapDeserializer, SeqDeserializer},
+        DeserializeOwned, Deserializer, IntoDeserializer, Unexpected, Visitor,
+    },
+    forward_to_deserialize_any,
+};
+
+pub use serde_json::Value;
+
+/// Source of configuration values. Values are looked up by the name of their section.
+pub trait ConfigSource: Send + Sync + 'static {
+    /// Get the value of given section.
+    fn get(&self, key: &str) -> Option<Value>;
+
+    /// Version of the values source returns. Sections deserialized from source are cached until
+    /// it's changed. Static source can keep the default.
+    fn version(&self) -> u64 {
+        0
+    }
+}
+
+impl ConfigSource for HashMap<String, Value> {
+    fn get(&self, key: &str) -> Option<Value> {
+        HashMap::get(self, key).cloned()
+    }
+}
+
+/// Type deserialized from a named section of [ConfigSource].
+pub trait ConfigSection: DeserializeOwned + Send + Sync + 'static {
+    /// Name of the section.
+    const SECTION: &'static str;
+}
+
+/// Layered [ConfigSource]. Layers are merged in the order they are added where later layer takes
+/// precedence. Overrides set with [LayeredSource::set] always take precedence over other layers.
+///
+/// Object values are merged recursively so a layer can override a single field of section.
+///
+/// # Examples:
+/// ```rust
+/// # use std::collections::HashMap;
+/// use xitca_web::config::LayeredSource;
+///
+/// let mut map = HashMap::new();
+/// map.insert("db".to_string(), serde_json::json!({ "url": "postgres://localhost", "pool": 4 }));
+///
+/// let source = LayeredSource::new()
+///     .map(map)
+///     // APP_DB__POOL=8 would override the pool field of db section.
+///     .env("APP_")
+///     .set("db.pool", 16);
+/// ```
+#[derive(Debug, Default)]
+pub struct LayeredSource {
+    layers: Vec<Value>,
+    overrides: Value,
+}
+
+impl LayeredSource {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add layer of sections keyed by their name.
+    pub fn map(mut self, map: HashMap<String, Value>) -> Self {
+        self.layers.push(Value::Object(map.into_iter().collect()));
+        self
+    }
+
+    /// Add layer of environment variables with given prefix. Variables are read when this method
+    /// is called.
+    ///
+    /// Name of variable with prefix stripped is lower cased and split by `__` into the path of
+    /// value. e.g. `APP_DB__POOL` with `APP_` prefix is the `pool` field of `db` section.
+    ///
+    /// Value of variable is kept as string. It's parsed when the field it's deserialized to is a
+    /// number or bool. e.g. `APP_DB__PASSWORD=123` is a valid value for both `String` and `u32`.
+    pub fn env(mut self, prefix: &str) -> Self {
+        let mut layer = Value::Object(Default::default());
+        for (name, value) in env::vars() {
+            let Some(path) = name.strip_prefix(prefix) else {
+                continue;
+            };
+            let path = path.to_lowercase();
+            insert(&mut layer, path.split("__"), Value::String(value));
+        }
+        self.layers.push(layer);
+        self
+    }
+
+    /// Override value of given dot separated path. e.g. `db.pool` is the `pool` field of `db`
+    /// section.
+    pub fn set(mut self, path: &str, value: impl Into<Value>) -> Self {
+        insert(&mut self.overrides, path.split('.'), value.into());
+        self
+    }
+}
+
+impl ConfigSource for LayeredSource {
+    fn get(&self, key: &str) -> Option<Value> {
+        self.layers
+            .iter()
+            .chain(Some(&self.overrides))
+            .filter_map(|layer| layer.get(key))
+            .fold(None, |value, layer| match value {
+                Some(mut value) => {
+                    merge(&mut value, layer);
+                    Some(value)
+                }
+                None => Some(layer.clone()),
+            })
+    }
+}
+
+fn insert<'a>(mut target: &mut Value, path: impl Iterator<Item = &'a str>, value: Value) {
+    for key in path.filter(|key| !key.is_empty()) {
+        if !target.is_object() {
+            *target = Value::Object(Default::default());
+        }
+        target = target
+            .as_object_mut()
+            .unwrap()
+            .entry(key)
+            .or_insert(Value::Object(Default::default()));
+    }
+    *target = value;
+}
+
+fn merge(target: &mut Value, layer: &Value) {
+    match (target, layer) {
+        (Value::Object(target), Value::Object(layer)) => {
+            for (key, value) in layer {
+                match target.get_mut(key) {
+                    Some(target) => merge(target, value),
+                    None => {
+                        target.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (target, layer) => *target = layer.clone(),
+    }
+}
+
+/// [ConfigSource] that can be swapped at runtime through it's [ReloadHandle].
+///
+/// # Examples:
+/// ```rust
+/// # use std::collections::HashMap;
+/// use xitca_web::config::{LayeredSource, Reloadable};
+///
+/// let source = Reloadable::new(LayeredSource::new().env("APP_"));
+/// let handle = source.handle();
+///
+/// // on SIGHUP or else. cached sections are deserialized again from new source.
+/// handle.reload(LayeredSource::new().env("APP_"));
+/// ```
+pub struct Reloadable(Arc<ReloadableInner>);
+
+struct ReloadableInner {
+    source: RwLock<Arc<dyn ConfigSource>>,
+    version: AtomicU64,
+}
+
+impl Reloadable {
+    pub fn new(source: impl ConfigSource) -> Self {
+        Self(Arc::new(ReloadableInner {
+            source: RwLock::new(Arc::new(source)),
+            version: AtomicU64::new(0),
+        }))
+    }
+
+    /// Get a handle for reloading source.
+    pub fn handle(&self) -> ReloadHandle {
+        ReloadHandle(self.0.clone())
+    }
+}
+
+impl ConfigSource for Reloadable {
+    fn get(&self, key: &str) -> Option<Value> {
+        let source = self.0.source.read().unwrap_or_else(|e| e.into_inner()).clone();
+        source.get(key)
+    }
+
+    fn version(&self) -> u64 {
+        self.0.version.load(Ordering::Acquire)
+    }
+}
+
+/// Handle for swapping the source of [Reloadable].
+#[derive(Clone)]
+pub struct ReloadHandle(Arc<ReloadableInner>);
+
+impl ReloadHandle {
+    /// Swap source and invalidate sections deserialized from the previous one.
+    pub fn reload(&self, source: impl ConfigSource) {
+        *self.0.source.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(source);
+        self.0.version.fetch_add(1, Ordering::AcqRel);
+    }
+}
+
+/// Configuration used as App state. See [App::with_config](crate::App::with_config).
+///
+/// Deserialized sections are cached by each Config. Cloning it shares the source with a new empty
+/// cache so clones used as state of different workers do not contend on the same cache.
+pub struct Config {
+    source: Arc<dyn ConfigSource>,
+    cache: RefCell<HashMap<TypeId, Cached>>,
+}
+
+// deserialized section and the version of source it's deserialized from.
+type Cached = (u64, Arc<dyn Any + Send + Sync>);
+
+impl Config {
+    pub fn new(source: impl ConfigSource) -> Self {
+        Self::from_shared(Arc::new(source))
+    }
+
+    pub(crate) fn from_shared(source: Arc<dyn ConfigSource>) -> Self {
+        Self {
+            source,
+            cache: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// Get section of type T. Section is deserialized once and cached until version of source is
+    /// changed.
+    pub fn section<T>(&self) -> Result<Arc<T>, ConfigError>
+    where
+        T: ConfigSection,
+    {
+        let id = TypeId::of::<T>();
+        // version is loaded before reading source. a concurrent reload would only cause a
+        // redundant deserialization in the next call.
+        let version = self.source.version();
+
+        if let Some((v, value)) = self.cache.borrow().get(&id) {
+            if *v == version {
+                return Ok(value.clone().downcast().unwrap());
+            }
+        }
+
+        let value = self.source.get(T::SECTION).ok_or(ConfigError {
+            section: T::SECTION,
+            kind: ConfigErrorKind::Missing,
+        })?;
+        let value = Arc::new(T::deserialize(Lenient(value)).map_err(|e| ConfigError {
+            section: T::SECTION,
+            kind: ConfigErrorKind::Deserialize(e),
+        })?);
+
+        self.cache.borrow_mut().insert(id, (version, value.clone()));
+
+        Ok(value)
+    }
+}
+
+impl Clone for Config {
+    fn clone(&self) -> Self {
+        Self::from_shared(self.source.clone())
+    }
+}
+
+impl fmt::Debug for Config {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Config").finish_non_exhaustive()
+    }
+}
+
+/// Error of getting section from [Config].
+#[derive(Debug)]
+pub struct ConfigError {
+    section: &'static str,
+    kind: ConfigErrorKind,
+}
+
+#[derive(Debug)]
+enum ConfigErrorKind {
+    Missing,
+    Deserialize(serde_json::Error),
+}
+
+impl ConfigError {
+    /// Name of the section failed to get.
+    pub fn section(&self) -> &'static str {
+        self.section
+    }
+
+    /// Returns true when section is absent from source.
+    pub fn is_missing(&self) -> bool {
+        matches!(self.kind, ConfigErrorKind::Missing)
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ConfigErrorKind::Missing => write!(f, "config section: {} is missing", self.section),
+            ConfigErrorKind::Deserialize(ref e) => {
+                write!(f, "config section: {} failed to deserialize: {e}", self.section)
+            }
+        }
+    }
+}
+
+impl error::Error for ConfigError {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        match self.kind {
+            ConfigErrorKind::Missing => None,
+            ConfigErrorKind::Deserialize(ref e) => Some(e),
+        }
+    }
+}
+
+// deserializer of Value parsing string to number or bool when they are expected. values from
+// string only source like environment variables can be deserialized to typed fields with it.
+struct Lenient(Value);
+
+impl<'de> IntoDeserializer<'de, serde_json::Error> for Lenient {
+    type Deserializer = Self;
+
+    fn into_deserializer(self) -> Self::Deserializer {
+        self
+    }
+}
+
+macro_rules! parse_str {
+    ($($method: ident => $visit: ident),*) => {
+        $(
+            fn $method<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                match self.0 {
+                    Value::String(s) => match s.parse() {
+                        Ok(v) => visitor.$visit(v),
+                        Err(_) => Err(de::Error::invalid_value(Unexpected::Str(&s), &visitor)),
+                    },
+                    value => value.$method(visitor),
+                }
+            }
+        )*
+    };
+}
+
+impl<'de> Deserializer<'de> for Lenient {
+    type Error = serde_json::Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0 {
+            Value::Array(arr) => {
+                let mut seq = SeqDeserializer::new(arr.into_iter().map(Lenient));
+                let value = visitor.visit_seq(&mut seq)?;
+                seq.end()?;
+                Ok(value)
+            }
+            Value::Object(map) => {
+                let mut map = MapDeserializer::new(map.into_iter().map(|(k, v)| (k, Lenient(v))));
+                let value = visitor.visit_map(&mut map)?;
+                map.end()?;
+                Ok(value)
+            }
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        match self.0 {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V>(self, _: &'static str, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        self.0.deserialize_enum(name, variants, visitor)
+    }
+
+    parse_str! {
+        deserialize_bool => visit_bool,
+        deserialize_i8 => visit_i8,
+        deserialize_i16 => visit_i16,
+        deserialize_i32 => visit_i32,
+        deserialize_i64 => visit_i64,
+        deserialize_u8 => visit_u8,
+        deserialize_u16 => visit_u16,
+        deserialize_u32 => visit_u32,
+        deserialize_u64 => visit_u64,
+        deserialize_f32 => visit_f32,
+        deserialize_f64 => visit_f64
+    }
+
+    forward_to_deserialize_any! {
+        i128 u128 char str string bytes byte_buf unit unit_struct seq tuple tuple_struct map struct
+        identifier ignored_any
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::sync::atomic::AtomicUsize;
+
+    use serde::Deserialize;
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::{
+        handler::{config::Conf, handler_service},
+        http::StatusCode,
+        test::TestRequest,
+        App,
+    };
+
+    use super::*;
+
+    #[derive(Debug, Deserialize, PartialEq)]
+    struct Db {
+        url: String,
+        pool: usize,
+        tls: bool,
+    }
+
+    impl ConfigSection for Db {
+        const SECTION: &'static str = "db";
+    }
+
+    #[derive(Deserialize)]
+    struct Absent {}
+
+    impl ConfigSection for Absent {
+        const SECTION: &'static str = "absent";
+    }
+
+    fn map() -> HashMap<String, Value> {
+        let mut map = HashMap::new();
+        map.insert(
+            "db".to_string(),
+            serde_json::json!({ "url": "postgres://localhost", "pool": 4, "tls": false }),
+        );
+        map
+    }
+
+    // source counting the lookups of it.
+    struct Counting(LayeredSource, Arc<AtomicUsize>);
+
+    impl ConfigSource for Counting {
+        fn get(&self, key: &str) -> Option<Value> {
+            self.1.fetch_add(1, Ordering::Relaxed);
+            self.0.get(key)
+        }
+    }
+
+    async fn handler(db: Conf<Db>) -> String {
+        format!("{}:{}:{}", db.url, db.pool, db.tls)
+    }
+
+    #[test]
+    fn layered() {
+        env::set_var("XITCA_WEB_CONFIG_TEST_DB__POOL", "8");
+        env::set_var("XITCA_WEB_CONFIG_TEST_DB__TLS", "true");
+
+        let source = LayeredSource::new()
+            .map(map())
+            .env("XITCA_WEB_CONFIG_TEST_")
+            .set("db.url", "postgres://remote");
+
+        let db = Config::new(source).section::<Db>().unwrap();
+        assert_eq!(
+            *db,
+            Db {
+                url: "postgres://remote".into(),
+                pool: 8,
+                tls: true
+            }
+        );
+
+        // later layer takes precedence.
+        let source = LayeredSource::new().env("XITCA_WEB_CONFIG_TEST_").map(map());
+        assert_eq!(Config::new(source).section::<Db>().unwrap().pool, 4);
+    }
+
+    #[test]
+    fn cached() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let source = Reloadable::new(Counting(LayeredSource::new().map(map()), count.clone()));
+        let handle = source.handle();
+
+        let service = App::with_config(source)
+            .at("/", handler_service(handler))
+            .finish_for_test()
+            .now_or_panic();
+
+        for _ in 0..2 {
+            let res = service.call(TestRequest::get("/")).now_or_panic().unwrap();
+            assert_eq!(
+                res.string_body().now_or_panic().unwrap(),
+                "postgres://localhost:4:false"
+            );
+        }
+        assert_eq!(count.load(Ordering::Relaxed), 1);
+
+        handle.reload(Counting(
+            LayeredSource::new().map(map()).set("db.pool", 16),
+            count.clone(),
+        ));
+
+        for _ in 0..2 {
+            let res = service.call(TestRequest::get("/")).now_or_panic().unwrap();
+            assert_eq!(
+                res.string_body().now_or_panic().unwrap(),
+                "postgres://localhost:16:false"
+            );
+        }
+        assert_eq!(count.load(Ordering::Relaxed), 2);
+    }
+
+    #[test]
+    fn missing() {
+        let err = Config::new(LayeredSource::new().map(map()))
+            .section::<Absent>()
+            .err()
+            .unwrap();
+        assert!(err.is_missing());
+        assert_eq!(err.section(), "absent");
+        assert_eq!(err.to_string(), "config section: absent is missing");
+
+        let err = Config::new(LayeredSource::new().set("db.pool", "many"))
+            .section::<Db>()
+            .err()
+            .unwrap();
+        assert!(!err.is_missing());
+
+        async fn absent(_: Conf<Absent>) -> &'static str {
+            unreachable!()
+        }
+
+        let service = App::with_config(LayeredSource::new())
+            .at("/", handler_service(absent))
+            .finish_for_test()
+            .now_or_panic();
+
+        let res = service.call(TestRequest::get("/")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[test]
+    fn env_string() {
+        #[derive(Deserialize)]
+        struct Auth {
+            password: String,
+            port: Option<u16>,
+            ids: Vec<u32>,
+        }
+
+        impl ConfigSection for Auth {
+            const SECTION: &'static str = "auth";
+        }
+
+        env::set_var("XITCA_WEB_CONFIG_ENV_AUTH__PASSWORD", "123");
+        env::set_var("XITCA_WEB_CONFIG_ENV_AUTH__PORT", "8080");
+
+        // env value stays string when string is expected and parsed when number is expected.
+        let source = LayeredSource::new()
+            .set("auth.ids", vec!["1", "2"])
+            .env("XITCA_WEB_CONFIG_ENV_");
+        let auth = Config::new(source).section::<Auth>().unwrap();
+        assert_eq!(auth.password, "123");
+        assert_eq!(auth.port, Some(8080));
+        assert_eq!(auth.ids, [1, 2]);
+
+        env::set_var("XITCA_WEB_CONFIG_ENV_AUTH__PORT", "http");
+        let source = LayeredSource::new()
+            .set("auth.ids", Value::Array(Vec::new()))
+            .env("XITCA_WEB_CONFIG_ENV_");
+        assert!(!Config::new(source).section::<Auth>().err().unwrap().is_missing());
+    }
+
+    #[test]
+    fn per_clone_cache() {
+        let count = Arc::new(AtomicUsize::new(0));
+        let config = Config::new(Counting(LayeredSource::new().map(map()), count.clone()));
+        let config2 = config.clone();
+
+        config.section::<Db>().unwrap();
+        config.section::<Db>().unwrap();
+        assert_eq!(count.load(Ordering::Relaxed), 1);
+
+        // clone has it's own cache.
+        config2.section::<Db>().unwrap();
+        assert_eq!(count.load(Ordering::Relaxed), 2);
+    }
+}
@@ -1,7 +1,7 @@
 pub use xitca_http::{
     error::BodyError,
     util::service::{
-        route::{MethodNotAllowed, RouteError},
+        route::{MethodNotAllowed, RouteError, RouteMatchError},
         router::{MatchError, RouterError},
     },
 };
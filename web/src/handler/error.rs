@@ -1,5 +1,7 @@
 use std::{convert::Infallible, error, fmt, future::Future, str::Utf8Error};
 
+use xitca_http::util::range::RangeError;
+
 use crate::{
     dev::bytes::Bytes,
     error::BodyError,
@@ -8,7 +10,7 @@ use crate::{
     response::WebResponse,
 };
 
-use super::Responder;
+use super::{negotiate::NotAcceptable, Responder};
 
 /// Collection of all default extract types's error.
 #[derive(Debug)]
@@ -20,8 +22,15 @@ pub enum ExtractError<E = BodyError> {
     ExtensionNotFound,
     /// Absent header value.
     HeaderNotFound(HeaderName),
+    /// Absent typed state of app state. Contains name of the type.
+    /// See [BorrowState](crate::handler::state::BorrowState).
+    StateNotFound(&'static str),
     /// Error of parsing bytes to Rust types.
     Parse(ParseError),
+    /// None of supported media types is accepted by request.
+    NotAcceptable(NotAcceptable),
+    /// Media type of request body is not supported by extractor.
+    UnsupportedMediaType,
     /// fallback boxed error type.
     Boxed(Box<dyn error::Error + Send + Sync + 'static>),
 }
@@ -32,7 +41,10 @@ impl<E: fmt::Display> fmt::Display for ExtractError<E> {
             Self::Body(ref e) => fmt::Display::fmt(e, f),
             Self::ExtensionNotFound => write!(f, "Extension can not be found"),
             Self::HeaderNotFound(ref name) => write!(f, "HeaderName: {name} not found."),
+            Self::StateNotFound(name) => write!(f, "State: {name} not found in app state."),
             Self::Parse(ref e) => fmt::Display::fmt(e, f),
+            Self::NotAcceptable(ref e) => fmt::Display::fmt(e, f),
+            Self::UnsupportedMediaType => f.write_str("Content-Type is not supported"),
             Self::Boxed(ref e) => fmt::Display::fmt(e, f),
         }
     }
@@ -51,8 +63,24 @@ impl<'r, C, B, E> Responder<WebRequest<'r, C, B>> for ExtractError<E> {
     type Future = impl Future<Output = Self::Output>;
 
     fn respond_to(self, req: WebRequest<'r, C, B>) -> Self::Future {
-        let mut res = req.into_response(Bytes::new());
-        *res.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+        let res = match self {
+            Self::NotAcceptable(ref e) => e.to_response(req),
+            _ => {
+                let status = match self {
+                    // path parameter not matching expected type is a bad request from client.
+                    #[cfg(feature = "params")]
+                    Self::Parse(ParseError(_ParseError::Params(_))) => StatusCode::BAD_REQUEST,
+                    Self::Parse(ParseError(
+                        _ParseError::Path(_) | _ParseError::Range(_) | _ParseError::RangeLength,
+                    )) => StatusCode::BAD_REQUEST,
+                    Self::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+                    _ => StatusCode::INTERNAL_SERVER_ERROR,
+                };
+                let mut res = req.into_response(Bytes::new());
+                *res.status_mut() = status;
+                res
+            }
+        };
         async { res }
     }
 }
@@ -64,6 +92,9 @@ impl fmt::Display for ParseError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self.0 {
             _ParseError::String(ref e) => fmt::Display::fmt(e, f),
+            _ParseError::Path(ref e) => write!(f, "invalid percent-encoded path: {e}"),
+            _ParseError::Range(ref e) => fmt::Display::fmt(e, f),
+            _ParseError::RangeLength => f.write_str("Content-Range does not match Content-Length"),
             #[cfg(feature = "params")]
             _ParseError::Params(ref e) => fmt::Display::fmt(e, f),
             #[cfg(feature = "json")]
@@ -78,6 +109,11 @@ impl fmt::Display for ParseError {
 #[derive(Debug)]
 pub(super) enum _ParseError {
     String(Utf8Error),
+    // percent-decoded request path is not valid utf-8.
+    Path(Utf8Error),
+    Range(RangeError),
+    // length of Content-Range does not match Content-Length.
+    RangeLength,
     #[cfg(feature = "params")]
     Params(serde::de::value::Error),
     #[cfg(feature = "json")]
@@ -86,6 +122,12 @@ pub(super) enum _ParseError {
     UrlEncoded(serde_urlencoded::de::Error),
 }
 
+impl From<RangeError> for _ParseError {
+    fn from(e: RangeError) -> Self {
+        Self::Range(e)
+    }
+}
+
 impl<E> From<_ParseError> for ExtractError<E> {
     fn from(e: _ParseError) -> Self {
         Self::Parse(ParseError(e))
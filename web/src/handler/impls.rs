@@ -5,7 +5,7 @@ use std::{error, io};
 use crate::{
     body::BodyStream,
     dev::bytes::Bytes,
-    error::{MatchError, MethodNotAllowed},
+    error::{MatchError, MethodNotAllowed, RouteMatchError},
     http::{
         const_header_value::TEXT_UTF8,
         header::{ALLOW, CONTENT_TYPE},
@@ -17,14 +17,16 @@ use crate::{
 
 use super::{error::ExtractError, FromRequest, Responder};
 
-impl<'a, 'r, C, B, T, E> FromRequest<'a, WebRequest<'r, C, B>> for Result<T, E>
+/// Extract `T` and hand it's outcome to handler as is. Handler would be called even when `T` failed
+/// to extract and it's up to the handler to decide what to do with the error.
+impl<'a, 'r, C, B, T> FromRequest<'a, WebRequest<'r, C, B>> for Result<T, T::Error>
 where
     B: BodyStream,
-    T: for<'a2, 'r2> FromRequest<'a2, WebRequest<'r2, C, B>, Error = E>,
+    T: FromRequest<'a, WebRequest<'r, C, B>>,
 {
-    type Type<'b> = Result<T, E>;
+    type Type<'b> = Result<T::Type<'b>, T::Error>;
     type Error = ExtractError<B::Error>;
-    type Future = impl Future<Output = Result<Self, Self::Error>> where WebRequest<'r,  C, B>: 'a;
+    type Future = impl Future<Output = Result<Self, Self::Error>> where WebRequest<'r, C, B>: 'a;
 
     #[inline]
     fn from_request(req: &'a WebRequest<'r, C, B>) -> Self::Future {
@@ -32,14 +34,15 @@ where
     }
 }
 
+/// Extract `T` and resolve to `None` when it failed to extract. (e.g. missing header, empty body)
 impl<'a, 'r, C, B, T> FromRequest<'a, WebRequest<'r, C, B>> for Option<T>
 where
     B: BodyStream,
-    T: for<'a2, 'r2> FromRequest<'a2, WebRequest<'r2, C, B>>,
+    T: FromRequest<'a, WebRequest<'r, C, B>>,
 {
-    type Type<'b> = Option<T>;
+    type Type<'b> = Option<T::Type<'b>>;
     type Error = ExtractError<B::Error>;
-    type Future = impl Future<Output = Result<Self, Self::Error>> where WebRequest<'r, C, B, >: 'a;
+    type Future = impl Future<Output = Result<Self, Self::Error>> where WebRequest<'r, C, B>: 'a;
 
     #[inline]
     fn from_request(req: &'a WebRequest<'r, C, B>) -> Self::Future {
@@ -180,6 +183,20 @@ impl<'r, C, B> Responder<WebRequest<'r, C, B>> for MethodNotAllowed {
     }
 }
 
+impl<'r, C, B> Responder<WebRequest<'r, C, B>> for RouteMatchError {
+    type Output = WebResponse;
+    type Future = impl Future<Output = Self::Output>;
+
+    fn respond_to(self, req: WebRequest<'r, C, B>) -> Self::Future {
+        async {
+            match self {
+                Self::MethodNotAllowed(e) => e.respond_to(req).await,
+                Self::GuardRejected => MatchError::NotFound.respond_to(req).await,
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod test {
     use xitca_unsafe_collection::futures::NowOrPanic;
@@ -204,4 +221,107 @@ mod test {
 
         <()>::from_request(&req).now_or_panic().unwrap();
     }
+
+    #[test]
+    fn extract_borrowed_option_result() {
+        use crate::handler::header::{self, HeaderRef};
+
+        let mut req = WebRequest::new_test(());
+        let mut req = req.as_web_req();
+
+        let header = Option::<HeaderRef<'_, { header::CONTENT_TYPE }>>::from_request(&req)
+            .now_or_panic()
+            .unwrap();
+        assert!(header.is_none());
+
+        let header = Result::<HeaderRef<'_, { header::CONTENT_TYPE }>, _>::from_request(&req)
+            .now_or_panic()
+            .unwrap();
+        assert!(matches!(header, Err(ExtractError::HeaderNotFound(_))));
+
+        req.req_mut().headers_mut().insert(CONTENT_TYPE, TEXT_UTF8);
+
+        let header = Option::<HeaderRef<'_, { header::CONTENT_TYPE }>>::from_request(&req)
+            .now_or_panic()
+            .unwrap();
+        assert_eq!(*header.unwrap(), TEXT_UTF8);
+    }
+
+    #[test]
+    fn body_extract_once() {
+        use crate::{handler::handler_service, test::TestRequest, App};
+
+        // extractors are polled concurrently in argument order and the first one consuming body
+        // takes all of it.
+        async fn handler(first: String, second: String) -> String {
+            format!("{first}|{second}")
+        }
+
+        let service = App::new()
+            .at("/", handler_service(handler))
+            .finish_for_test()
+            .now_or_panic();
+
+        let res = service
+            .call(TestRequest::default().body("hello"))
+            .now_or_panic()
+            .unwrap();
+        assert_eq!(res.string_body().now_or_panic().unwrap(), "hello|");
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_recover() {
+        use crate::{
+            handler::{
+                handler_service,
+                header::{self, HeaderRef},
+                json::Json,
+                path::PathRef,
+            },
+            http::const_header_value,
+            test::TestRequest,
+            App,
+        };
+
+        async fn handler(
+            PathRef(path): PathRef<'_>,
+            len: Option<HeaderRef<'_, { header::CONTENT_LENGTH }>>,
+            json: Result<Json<Vec<u32>>, ExtractError>,
+        ) -> String {
+            let len = len.map(|len| len.to_str().unwrap().to_owned()).unwrap_or_default();
+            match json {
+                Ok(Json(json)) => format!("{path} {len} {json:?}"),
+                Err(e) => format!("{path} {len} error: {e}"),
+            }
+        }
+
+        let service = App::new()
+            .at("/json", handler_service(handler))
+            .finish_for_test()
+            .now_or_panic();
+
+        let req = TestRequest::default().uri("/json").json(&[1, 2, 3]);
+        let res = service.call(req).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK);
+        assert_eq!(res.string_body().now_or_panic().unwrap(), "/json 7 [1, 2, 3]");
+
+        // malformed json is passed to handler instead of failing the request.
+        let req = TestRequest::default()
+            .uri("/json")
+            .header(CONTENT_TYPE, const_header_value::JSON)
+            .body("[1, 2,");
+        let res = service.call(req).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK);
+        let body = res.string_body().now_or_panic().unwrap();
+        assert!(body.starts_with("/json 6 error:"), "{body}");
+
+        // missing content-type header.
+        let res = service.call(TestRequest::get("/json")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK);
+        assert_eq!(
+            res.string_body().now_or_panic().unwrap(),
+            "/json  error: HeaderName: content-type not found."
+        );
+    }
 }
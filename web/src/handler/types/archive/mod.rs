@@ -0,0 +1,572 @@
+//! streaming zip and tar archive responder.
+
+#[cfg(feature = "archive-tar")]
+mod tar;
+#[cfg(feature = "archive-zip")]
+mod zip;
+
+use core::{
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use std::{error, time::SystemTime};
+
+use futures_core::stream::Stream;
+use pin_project_lite::pin_project;
+
+use crate::{
+    body::ResponseBody,
+    dev::{
+        bytes::{Bytes, BytesMut},
+        service::pipeline::PipelineE,
+    },
+    handler::Responder,
+    http::{
+        header::{CONTENT_DISPOSITION, CONTENT_TYPE},
+        HeaderValue,
+    },
+    request::WebRequest,
+    response::WebResponse,
+};
+
+// archive bytes are yielded in chunks of at most this size. smaller chunk is yielded when input
+// is pending.
+const CHUNK_SIZE: usize = 16 * 1024;
+
+/// A file inside [StreamingArchive].
+pub struct ArchiveEntry<S> {
+    name: String,
+    modified: Option<SystemTime>,
+    size: Option<u64>,
+    body: S,
+}
+
+impl<S> ArchiveEntry<S> {
+    /// Construct an entry with given path name inside archive and [Stream] of it's content.
+    ///
+    /// Name is used as is and `/` must be used as path separator.
+    pub fn new(name: impl Into<String>, body: S) -> Self {
+        Self {
+            name: name.into(),
+            modified: None,
+            size: None,
+            body,
+        }
+    }
+
+    /// Set last modification time of entry.
+    pub fn modified(mut self, modified: SystemTime) -> Self {
+        self.modified = Some(modified);
+        self
+    }
+
+    /// Set exact size of entry content in bytes. Content not matching the size is treated as
+    /// error.
+    ///
+    /// It's optional and tar archive uses it to stream content of entry without buffering.
+    pub fn size(mut self, size: u64) -> Self {
+        self.size = Some(size);
+        self
+    }
+}
+
+impl<S> fmt::Debug for ArchiveEntry<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ArchiveEntry")
+            .field("name", &self.name)
+            .field("modified", &self.modified)
+            .field("size", &self.size)
+            .finish()
+    }
+}
+
+/// Responder type for downloading multiple files as one archive. Archive is encoded while entries
+/// and their content are streamed so it's never fully buffered in memory. Response is sent with
+/// `Content-Type`, `Content-Disposition` and chunked transfer encoding.
+///
+/// Error from content of an entry aborts the response body so client would not receive a silently
+/// corrupted archive.
+///
+/// # Examples:
+/// ```rust
+/// # use std::convert::Infallible;
+/// # use futures_core::Stream;
+/// # use futures_util::{stream, StreamExt};
+/// # use xitca_web::{dev::bytes::Bytes, handler::{archive::{ArchiveEntry, StreamingArchive}, handler_service}, request::WebRequest, App};
+/// App::new().at("/download", handler_service(handler));
+///
+/// async fn handler(
+///     _: &WebRequest<'_>,
+/// ) -> StreamingArchive<impl Stream<Item = ArchiveEntry<impl Stream<Item = Result<Bytes, Infallible>>>>> {
+///     let entries = stream::iter(["a.txt", "b.txt"]).map(|name| {
+///         let content = stream::once(async { Ok(Bytes::from_static(b"996")) });
+///         ArchiveEntry::new(name, content)
+///     });
+///     StreamingArchive::zip(entries).file_name("files.zip")
+/// }
+/// ```
+pub struct StreamingArchive<St> {
+    entries: St,
+    format: Format,
+    file_name: Option<String>,
+}
+
+#[derive(Clone, Copy)]
+enum Format {
+    #[cfg(feature = "archive-zip")]
+    Zip { deflate: bool },
+    #[cfg(feature = "archive-tar")]
+    Tar,
+}
+
+impl<St> StreamingArchive<St> {
+    /// Construct a zip archive with content of entries compressed with deflate.
+    ///
+    /// Sizes of entries are written after their content so they don't have to be known ahead.
+    /// Entry and archive larger than 4GiB and more than 65535 entries are not supported.
+    #[cfg(feature = "archive-zip")]
+    pub fn zip(entries: St) -> Self {
+        Self::new(entries, Format::Zip { deflate: true })
+    }
+
+    /// Construct a zip archive with content of entries stored without compression. This is
+    /// useful when entries are already compressed.
+    ///
+    /// See [StreamingArchive::zip] for limitation.
+    #[cfg(feature = "archive-zip")]
+    pub fn zip_stored(entries: St) -> Self {
+        Self::new(entries, Format::Zip { deflate: false })
+    }
+
+    /// Construct a tar archive.
+    ///
+    /// Tar header has to carry size of entry. Content of entry is streamed when it's size is set
+    /// with [ArchiveEntry::size]. Otherwise it's buffered in memory before written to archive.
+    /// Entry larger than 8GiB is not supported.
+    #[cfg(feature = "archive-tar")]
+    pub fn tar(entries: St) -> Self {
+        Self::new(entries, Format::Tar)
+    }
+
+    /// Set file name of archive in `Content-Disposition` header. Default to `archive.zip` or
+    /// `archive.tar`.
+    pub fn file_name(mut self, name: impl Into<String>) -> Self {
+        self.file_name = Some(name.into());
+        self
+    }
+
+    fn new(entries: St, format: Format) -> Self {
+        Self {
+            entries,
+            format,
+            file_name: None,
+        }
+    }
+}
+
+impl<'r, C, B, St, S, E> Responder<WebRequest<'r, C, B>> for StreamingArchive<St>
+where
+    St: Stream<Item = ArchiveEntry<S>> + 'static,
+    S: Stream<Item = Result<Bytes, E>> + 'static,
+    E: error::Error + Send + Sync + 'static,
+{
+    type Output = WebResponse;
+    type Future = impl Future<Output = Self::Output>;
+
+    fn respond_to(self, req: WebRequest<'r, C, B>) -> Self::Future {
+        let (content_type, default_name, encoder) = match self.format {
+            #[cfg(feature = "archive-zip")]
+            Format::Zip { deflate } => ("application/zip", "archive.zip", Encoder::Zip(zip::Zip::new(deflate))),
+            #[cfg(feature = "archive-tar")]
+            Format::Tar => ("application/x-tar", "archive.tar", Encoder::Tar(tar::Tar::default())),
+        };
+
+        let disposition = content_disposition(self.file_name.as_deref().unwrap_or(default_name));
+
+        let body = ResponseBody::box_stream(ArchiveStream {
+            entries: self.entries,
+            entry: None,
+            encoder,
+            buf: BytesMut::new(),
+            done: false,
+        });
+        let mut res = req.into_response(body);
+        res.headers_mut()
+            .insert(CONTENT_TYPE, HeaderValue::from_static(content_type));
+        res.headers_mut().insert(CONTENT_DISPOSITION, disposition);
+        async { res }
+    }
+}
+
+// non ascii and special chars in file name are percent encoded with RFC 6266 extended notation.
+fn content_disposition(name: &str) -> HeaderValue {
+    let plain = name
+        .bytes()
+        .all(|b| b.is_ascii_graphic() && b != b'"' && b != b'\\' || b == b' ');
+    let value = if plain {
+        format!("attachment; filename=\"{name}\"")
+    } else {
+        let mut value = String::from("attachment; filename*=UTF-8''");
+        for b in name.bytes() {
+            if b.is_ascii_alphanumeric() || b"!#$&+-.^_`|~".contains(&b) {
+                value.push(b as char);
+            } else {
+                value.push_str(&format!("%{b:02X}"));
+            }
+        }
+        value
+    };
+    HeaderValue::try_from(value).expect("Content-Disposition must be valid HeaderValue")
+}
+
+/// Error type of archive encoding.
+#[derive(Debug)]
+pub enum ArchiveError {
+    /// Entry or archive exceeds size limit of archive format.
+    TooLarge,
+    /// Count of entries exceeds limit of archive format.
+    TooManyEntries,
+    /// Length of content differs from size set by [ArchiveEntry::size].
+    SizeMismatch,
+}
+
+impl fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::TooLarge => f.write_str("archive entry exceeds size limit of archive format"),
+            Self::TooManyEntries => f.write_str("archive entries exceed count limit of archive format"),
+            Self::SizeMismatch => f.write_str("archive entry content does not match it's size"),
+        }
+    }
+}
+
+impl error::Error for ArchiveError {}
+
+/// Error type of [StreamingArchive] response body.
+pub type StreamingArchiveError<E> = PipelineE<ArchiveError, E>;
+
+// metadata of entry passed to encoder before it's content.
+struct EntryHead<'a> {
+    name: &'a str,
+    modified: Option<SystemTime>,
+    size: Option<u64>,
+}
+
+enum Encoder {
+    #[cfg(feature = "archive-zip")]
+    Zip(zip::Zip),
+    #[cfg(feature = "archive-tar")]
+    Tar(tar::Tar),
+}
+
+impl Encoder {
+    fn begin(&mut self, head: EntryHead<'_>, buf: &mut BytesMut) -> Result<(), ArchiveError> {
+        match *self {
+            #[cfg(feature = "archive-zip")]
+            Self::Zip(ref mut zip) => zip.begin(head, buf),
+            #[cfg(feature = "archive-tar")]
+            Self::Tar(ref mut tar) => tar.begin(head, buf),
+        }
+    }
+
+    fn data(&mut self, chunk: &[u8], buf: &mut BytesMut) -> Result<(), ArchiveError> {
+        match *self {
+            #[cfg(feature = "archive-zip")]
+            Self::Zip(ref mut zip) => zip.data(chunk, buf),
+            #[cfg(feature = "archive-tar")]
+            Self::Tar(ref mut tar) => tar.data(chunk, buf),
+        }
+    }
+
+    fn end(&mut self, buf: &mut BytesMut) -> Result<(), ArchiveError> {
+        match *self {
+            #[cfg(feature = "archive-zip")]
+            Self::Zip(ref mut zip) => zip.end(buf),
+            #[cfg(feature = "archive-tar")]
+            Self::Tar(ref mut tar) => tar.end(buf),
+        }
+    }
+
+    fn finish(&mut self, buf: &mut BytesMut) -> Result<(), ArchiveError> {
+        match *self {
+            #[cfg(feature = "archive-zip")]
+            Self::Zip(ref mut zip) => zip.finish(buf),
+            #[cfg(feature = "archive-tar")]
+            Self::Tar(ref mut tar) => tar.finish(buf),
+        }
+    }
+}
+
+pin_project! {
+    #[project = ArchiveStreamProj]
+    struct ArchiveStream<St, S> {
+        #[pin]
+        entries: St,
+        entry: Option<Pin<Box<S>>>,
+        encoder: Encoder,
+        buf: BytesMut,
+        done: bool,
+    }
+}
+
+impl<St, S, E> Stream for ArchiveStream<St, S>
+where
+    St: Stream<Item = ArchiveEntry<S>>,
+    S: Stream<Item = Result<Bytes, E>>,
+{
+    type Item = Result<Bytes, StreamingArchiveError<E>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let mut this = self.project();
+
+        loop {
+            if *this.done {
+                return Poll::Ready((!this.buf.is_empty()).then(|| Ok(this.split())));
+            }
+
+            if this.buf.len() >= CHUNK_SIZE {
+                return Poll::Ready(Some(Ok(this.split())));
+            }
+
+            let res = match this.entry {
+                Some(ref mut body) => match body.as_mut().poll_next(cx) {
+                    Poll::Ready(Some(Ok(chunk))) => this.encoder.data(&chunk, this.buf),
+                    Poll::Ready(Some(Err(e))) => {
+                        *this.done = true;
+                        this.buf.clear();
+                        return Poll::Ready(Some(Err(StreamingArchiveError::Second(e))));
+                    }
+                    Poll::Ready(None) => {
+                        *this.entry = None;
+                        this.encoder.end(this.buf)
+                    }
+                    Poll::Pending => return this.flush(),
+                },
+                None => match this.entries.as_mut().poll_next(cx) {
+                    Poll::Ready(Some(entry)) => {
+                        let head = EntryHead {
+                            name: &entry.name,
+                            modified: entry.modified,
+                            size: entry.size,
+                        };
+                        let res = this.encoder.begin(head, this.buf);
+                        *this.entry = Some(Box::pin(entry.body));
+                        res
+                    }
+                    Poll::Ready(None) => {
+                        *this.done = true;
+                        this.encoder.finish(this.buf)
+                    }
+                    Poll::Pending => return this.flush(),
+                },
+            };
+
+            if let Err(e) = res {
+                *this.done = true;
+                this.buf.clear();
+                return Poll::Ready(Some(Err(StreamingArchiveError::First(e))));
+            }
+        }
+    }
+
+    // entry count and content size are unknown and chunked encoding is used.
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, None)
+    }
+}
+
+impl<St, S> ArchiveStreamProj<'_, St, S> {
+    // yield buffered bytes while waiting for input.
+    fn flush<E>(&mut self) -> Poll<Option<Result<Bytes, E>>> {
+        if self.buf.is_empty() {
+            Poll::Pending
+        } else {
+            Poll::Ready(Some(Ok(self.split())))
+        }
+    }
+
+    // large entry buffered by encoder is yielded in multiple chunks.
+    fn split(&mut self) -> Bytes {
+        let at = self.buf.len().min(CHUNK_SIZE);
+        self.buf.split_to(at).freeze()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{self, Read};
+
+    use futures_util::stream::{self, StreamExt};
+    use xitca_http::config::DEFAULT_WRITE_BUF_LIMIT;
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use super::*;
+
+    const LARGE: usize = DEFAULT_WRITE_BUF_LIMIT * 2 + 7;
+
+    // pseudo random content so deflate can not shrink it much.
+    fn content(len: usize) -> Vec<u8> {
+        let mut x = 0x2545_f491_u32;
+        (0..len)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 17;
+                x ^= x << 5;
+                x as u8
+            })
+            .collect()
+    }
+
+    type Chunked = stream::Iter<std::vec::IntoIter<Result<Bytes, io::Error>>>;
+
+    fn chunked(content: &[u8]) -> Chunked {
+        let chunks = content
+            .chunks(4096)
+            .map(|c| Ok(Bytes::copy_from_slice(c)))
+            .collect::<Vec<_>>();
+        stream::iter(chunks)
+    }
+
+    fn entries() -> Vec<ArchiveEntry<Chunked>> {
+        let mtime = SystemTime::UNIX_EPOCH + core::time::Duration::from_secs(1_684_935_931);
+        vec![
+            ArchiveEntry::new("a.txt", chunked(b"hello,world!")).modified(mtime),
+            ArchiveEntry::new("empty.txt", chunked(b"")),
+            ArchiveEntry::new("dir/large.bin", chunked(&content(LARGE))),
+        ]
+    }
+
+    // collect response body chunk by chunk.
+    fn respond<St>(archive: StreamingArchive<St>) -> (WebResponse, Vec<u8>)
+    where
+        StreamingArchive<St>: for<'r> Responder<WebRequest<'r, ()>, Output = WebResponse>,
+    {
+        let mut req = WebRequest::new_test(());
+        let mut res = archive.respond_to(req.as_web_req()).now_or_panic();
+
+        let mut archive = Vec::new();
+        let mut chunks = 0;
+        let body = res.body_mut();
+        while let Some(chunk) = body.next().now_or_panic() {
+            let chunk = chunk.unwrap();
+            assert!(chunk.len() <= CHUNK_SIZE);
+            archive.extend_from_slice(&chunk);
+            chunks += 1;
+        }
+        // archive is emitted incrementally.
+        assert!(chunks > 1);
+
+        (res, archive)
+    }
+
+    // read every entry of archive so checksum of each entry is verified by zip reader.
+    #[cfg(feature = "archive-zip")]
+    fn check_zip(archive: Vec<u8>) {
+        let mut archive = ::zip::ZipArchive::new(io::Cursor::new(archive)).unwrap();
+        let mut extract = |name| {
+            let mut buf = Vec::new();
+            archive.by_name(name).unwrap().read_to_end(&mut buf).unwrap();
+            buf
+        };
+
+        assert_eq!(extract("a.txt"), b"hello,world!");
+        assert_eq!(extract("empty.txt"), b"");
+        assert_eq!(extract("dir/large.bin"), content(LARGE));
+        assert_eq!(archive.len(), 3);
+    }
+
+    #[cfg(feature = "archive-zip")]
+    #[test]
+    fn zip() {
+        let archive = StreamingArchive::zip(stream::iter(entries())).file_name("files.zip");
+        let (res, archive) = respond(archive);
+        assert_eq!(res.headers().get(CONTENT_TYPE).unwrap(), "application/zip");
+        assert_eq!(
+            res.headers().get(CONTENT_DISPOSITION).unwrap(),
+            "attachment; filename=\"files.zip\""
+        );
+        check_zip(archive);
+
+        let (_, archive) = respond(StreamingArchive::zip_stored(stream::iter(entries())));
+        check_zip(archive);
+    }
+
+    #[cfg(feature = "archive-tar")]
+    #[test]
+    fn tar() {
+        let long = format!("{}/long.txt", "d".repeat(120));
+        let entries = entries().into_iter().chain([
+            ArchiveEntry::new(long.clone(), chunked(b"long name")),
+            ArchiveEntry::new("sized.bin", chunked(&content(LARGE))).size(LARGE as u64),
+        ]);
+
+        let (res, archive) = respond(StreamingArchive::tar(stream::iter(entries)));
+        assert_eq!(res.headers().get(CONTENT_TYPE).unwrap(), "application/x-tar");
+        assert_eq!(
+            res.headers().get(CONTENT_DISPOSITION).unwrap(),
+            "attachment; filename=\"archive.tar\""
+        );
+
+        let mut archive = ::tar::Archive::new(&archive[..]);
+        let entries = archive
+            .entries()
+            .unwrap()
+            .map(|entry| {
+                let mut entry = entry.unwrap();
+                let path = entry.path().unwrap().to_str().unwrap().to_owned();
+                let mut buf = Vec::new();
+                entry.read_to_end(&mut buf).unwrap();
+                (path, buf)
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            entries,
+            [
+                ("a.txt".to_owned(), b"hello,world!".to_vec()),
+                ("empty.txt".to_owned(), Vec::new()),
+                ("dir/large.bin".to_owned(), content(LARGE)),
+                (long, b"long name".to_vec()),
+                ("sized.bin".to_owned(), content(LARGE)),
+            ]
+        );
+    }
+
+    #[cfg(feature = "archive-zip")]
+    #[test]
+    fn entry_error() {
+        let body = stream::iter([
+            Ok(Bytes::from_static(b"996")),
+            Err(io::Error::other("disk failure")),
+        ]);
+        let entries = stream::iter([ArchiveEntry::new("a.txt", body)]);
+
+        let mut req = WebRequest::new_test(());
+        let res = StreamingArchive::zip(entries)
+            .respond_to(req.as_web_req())
+            .now_or_panic();
+
+        let mut body = res.into_body();
+        let mut items = Vec::new();
+        while let Some(item) = body.next().now_or_panic() {
+            items.push(item);
+        }
+
+        // body ends right after the error without end of archive.
+        assert!(items.last().unwrap().is_err());
+        assert!(items[..items.len() - 1].iter().all(Result::is_ok));
+    }
+
+    #[test]
+    fn disposition() {
+        assert_eq!(content_disposition("a b.zip"), "attachment; filename=\"a b.zip\"");
+        assert_eq!(
+            content_disposition("ファイル \".zip"),
+            "attachment; filename*=UTF-8''%E3%83%95%E3%82%A1%E3%82%A4%E3%83%AB%20%22.zip"
+        );
+    }
+}
@@ -0,0 +1,146 @@
+use std::time::SystemTime;
+
+use crate::dev::bytes::{BufMut, BytesMut};
+
+use super::{ArchiveError, EntryHead};
+
+const BLOCK: usize = 512;
+
+// max value of 11 octal digits.
+const MAX_SIZE: u64 = 0o77_777_777_777;
+
+#[derive(Default)]
+pub(super) struct Tar {
+    current: Option<Current>,
+}
+
+struct Current {
+    name: Box<str>,
+    mtime: u64,
+    expected: Option<u64>,
+    written: u64,
+    // content of entry without known size.
+    buffered: BytesMut,
+}
+
+impl Tar {
+    pub(super) fn begin(&mut self, head: EntryHead<'_>, buf: &mut BytesMut) -> Result<(), ArchiveError> {
+        let mtime = head
+            .modified
+            .and_then(|m| m.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs().min(MAX_SIZE))
+            .unwrap_or(0);
+
+        if let Some(size) = head.size {
+            put_header(head.name, mtime, size, buf)?;
+        }
+
+        self.current = Some(Current {
+            name: head.name.into(),
+            mtime,
+            expected: head.size,
+            written: 0,
+            buffered: BytesMut::new(),
+        });
+
+        Ok(())
+    }
+
+    pub(super) fn data(&mut self, chunk: &[u8], buf: &mut BytesMut) -> Result<(), ArchiveError> {
+        let current = self.current.as_mut().expect("data must be written after begin");
+        current.written += chunk.len() as u64;
+
+        match current.expected {
+            Some(size) if current.written > size => Err(ArchiveError::SizeMismatch),
+            Some(_) => {
+                buf.extend_from_slice(chunk);
+                Ok(())
+            }
+            None if current.written > MAX_SIZE => Err(ArchiveError::TooLarge),
+            None => {
+                current.buffered.extend_from_slice(chunk);
+                Ok(())
+            }
+        }
+    }
+
+    pub(super) fn end(&mut self, buf: &mut BytesMut) -> Result<(), ArchiveError> {
+        let current = self.current.take().expect("entry must be ended after begin");
+
+        match current.expected {
+            Some(size) if size != current.written => return Err(ArchiveError::SizeMismatch),
+            Some(_) => {}
+            None => {
+                put_header(&current.name, current.mtime, current.written, buf)?;
+                buf.extend_from_slice(&current.buffered);
+            }
+        }
+
+        put_padding(current.written, buf);
+
+        Ok(())
+    }
+
+    pub(super) fn finish(&mut self, buf: &mut BytesMut) -> Result<(), ArchiveError> {
+        // end of archive is marked by two zero blocks.
+        buf.put_bytes(0, BLOCK * 2);
+        Ok(())
+    }
+}
+
+fn put_header(name: &str, mtime: u64, size: u64, buf: &mut BytesMut) -> Result<(), ArchiveError> {
+    if size > MAX_SIZE {
+        return Err(ArchiveError::TooLarge);
+    }
+
+    // name not fitting in header is written as content of a gnu long name entry before it.
+    if name.len() > 100 {
+        let len = name.len() as u64 + 1;
+        put_block("././@LongLink", 0, len, b'L', buf);
+        buf.put_slice(name.as_bytes());
+        buf.put_u8(0);
+        put_padding(len, buf);
+    }
+
+    put_block(name, mtime, size, b'0', buf);
+
+    Ok(())
+}
+
+fn put_block(name: &str, mtime: u64, size: u64, kind: u8, buf: &mut BytesMut) {
+    let mut block = [0; BLOCK];
+
+    let name = name.as_bytes();
+    let len = name.len().min(100);
+    block[..len].copy_from_slice(&name[..len]);
+    octal(&mut block[100..108], 0o644);
+    octal(&mut block[108..116], 0);
+    octal(&mut block[116..124], 0);
+    octal(&mut block[124..136], size);
+    octal(&mut block[136..148], mtime);
+    block[156] = kind;
+    // gnu magic and version.
+    block[257..265].copy_from_slice(b"ustar  \0");
+
+    // checksum is computed with it's own field filled with spaces.
+    block[148..156].fill(b' ');
+    let sum = block.iter().map(|b| *b as u64).sum::<u64>();
+    octal(&mut block[148..155], sum);
+
+    buf.put_slice(&block);
+}
+
+// zero padded octal number terminated with nul.
+fn octal(field: &mut [u8], n: u64) {
+    let digits = field.len() - 1;
+    let s = format!("{n:0digits$o}");
+    field[..digits].copy_from_slice(s.as_bytes());
+    field[digits] = 0;
+}
+
+fn put_padding(len: u64, buf: &mut BytesMut) {
+    let rem = (len % BLOCK as u64) as usize;
+    if rem != 0 {
+        buf.put_bytes(0, BLOCK - rem);
+    }
+}
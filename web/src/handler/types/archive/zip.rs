@@ -0,0 +1,311 @@
+use std::{io::Write, time::SystemTime};
+
+use crc32fast::Hasher;
+use flate2::{write::DeflateEncoder, Compression};
+
+use crate::dev::bytes::{BufMut, BytesMut};
+
+use super::{ArchiveError, EntryHead};
+
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+const DATA_DESCRIPTOR: u32 = 0x0807_4b50;
+const CENTRAL_HEADER: u32 = 0x0201_4b50;
+const END_OF_CENTRAL: u32 = 0x0605_4b50;
+
+// zip 2.0 for deflate method and data descriptor.
+const VERSION: u16 = 20;
+// unix host for external file attributes.
+const VERSION_MADE_BY: u16 = 3 << 8 | VERSION;
+
+// sizes and crc are written in data descriptor after content.
+const FLAG_DATA_DESCRIPTOR: u16 = 1 << 3;
+const FLAG_UTF8: u16 = 1 << 11;
+
+const METHOD_STORED: u16 = 0;
+const METHOD_DEFLATE: u16 = 8;
+
+// extended timestamp extra field carrying unix mtime.
+const EXTRA_TIMESTAMP: u16 = 0x5455;
+
+// regular file with rw-r--r-- permission.
+const FILE_ATTRIBUTES: u32 = 0o100644 << 16;
+
+pub(super) struct Zip {
+    deflate: bool,
+    offset: u64,
+    entries: Vec<Entry>,
+    current: Option<Current>,
+}
+
+struct Entry {
+    name: Box<str>,
+    flags: u16,
+    method: u16,
+    time: u16,
+    date: u16,
+    mtime: Option<u32>,
+    crc: u32,
+    compressed: u32,
+    size: u32,
+    offset: u32,
+}
+
+struct Current {
+    entry: Entry,
+    expected: Option<u64>,
+    hasher: Hasher,
+    size: u64,
+    compressed: u64,
+    encoder: Option<DeflateEncoder<Vec<u8>>>,
+}
+
+impl Zip {
+    pub(super) fn new(deflate: bool) -> Self {
+        Self {
+            deflate,
+            offset: 0,
+            entries: Vec::new(),
+            current: None,
+        }
+    }
+
+    pub(super) fn begin(&mut self, head: EntryHead<'_>, buf: &mut BytesMut) -> Result<(), ArchiveError> {
+        if self.entries.len() == u16::MAX as usize {
+            return Err(ArchiveError::TooManyEntries);
+        }
+
+        let (time, date) = head.modified.map(dos_date_time).unwrap_or(DOS_EPOCH);
+        let mtime = head
+            .modified
+            .and_then(|m| m.duration_since(SystemTime::UNIX_EPOCH).ok())
+            .and_then(|d| u32::try_from(d.as_secs()).ok());
+
+        let entry = Entry {
+            name: head.name.into(),
+            flags: FLAG_DATA_DESCRIPTOR | if head.name.is_ascii() { 0 } else { FLAG_UTF8 },
+            method: if self.deflate { METHOD_DEFLATE } else { METHOD_STORED },
+            time,
+            date,
+            mtime,
+            crc: 0,
+            compressed: 0,
+            size: 0,
+            offset: to_u32(self.offset)?,
+        };
+
+        let start = buf.len();
+        buf.put_u32_le(LOCAL_HEADER);
+        buf.put_u16_le(VERSION);
+        buf.put_u16_le(entry.flags);
+        buf.put_u16_le(entry.method);
+        buf.put_u16_le(entry.time);
+        buf.put_u16_le(entry.date);
+        // crc and sizes are in data descriptor.
+        buf.put_bytes(0, 12);
+        buf.put_u16_le(to_u16(entry.name.len())?);
+        buf.put_u16_le(extra_len(&entry));
+        buf.put_slice(entry.name.as_bytes());
+        put_extra(&entry, buf);
+        self.offset += (buf.len() - start) as u64;
+
+        self.current = Some(Current {
+            entry,
+            expected: head.size,
+            hasher: Hasher::new(),
+            size: 0,
+            compressed: 0,
+            encoder: self
+                .deflate
+                .then(|| DeflateEncoder::new(Vec::new(), Compression::default())),
+        });
+
+        Ok(())
+    }
+
+    pub(super) fn data(&mut self, chunk: &[u8], buf: &mut BytesMut) -> Result<(), ArchiveError> {
+        let current = self.current.as_mut().expect("data must be written after begin");
+        current.hasher.update(chunk);
+        current.size += chunk.len() as u64;
+
+        match current.encoder {
+            Some(ref mut encoder) => {
+                encoder.write_all(chunk).expect("writing to Vec must not fail");
+                let out = encoder.get_mut();
+                current.compressed += out.len() as u64;
+                buf.extend_from_slice(out);
+                out.clear();
+            }
+            None => {
+                current.compressed += chunk.len() as u64;
+                buf.extend_from_slice(chunk);
+            }
+        }
+
+        if current.size > u32::MAX as u64 || current.compressed > u32::MAX as u64 {
+            return Err(ArchiveError::TooLarge);
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn end(&mut self, buf: &mut BytesMut) -> Result<(), ArchiveError> {
+        let mut current = self.current.take().expect("entry must be ended after begin");
+
+        if let Some(encoder) = current.encoder.take() {
+            let out = encoder.finish().expect("writing to Vec must not fail");
+            current.compressed += out.len() as u64;
+            buf.extend_from_slice(&out);
+        }
+
+        if current.expected.is_some_and(|size| size != current.size) {
+            return Err(ArchiveError::SizeMismatch);
+        }
+
+        let mut entry = current.entry;
+        entry.crc = current.hasher.finalize();
+        entry.compressed = to_u32(current.compressed)?;
+        entry.size = to_u32(current.size)?;
+
+        buf.put_u32_le(DATA_DESCRIPTOR);
+        buf.put_u32_le(entry.crc);
+        buf.put_u32_le(entry.compressed);
+        buf.put_u32_le(entry.size);
+        self.offset += current.compressed + 16;
+
+        self.entries.push(entry);
+
+        Ok(())
+    }
+
+    pub(super) fn finish(&mut self, buf: &mut BytesMut) -> Result<(), ArchiveError> {
+        let start = buf.len();
+        let central_offset = to_u32(self.offset)?;
+
+        for entry in self.entries.iter() {
+            buf.put_u32_le(CENTRAL_HEADER);
+            buf.put_u16_le(VERSION_MADE_BY);
+            buf.put_u16_le(VERSION);
+            buf.put_u16_le(entry.flags);
+            buf.put_u16_le(entry.method);
+            buf.put_u16_le(entry.time);
+            buf.put_u16_le(entry.date);
+            buf.put_u32_le(entry.crc);
+            buf.put_u32_le(entry.compressed);
+            buf.put_u32_le(entry.size);
+            buf.put_u16_le(entry.name.len() as u16);
+            buf.put_u16_le(extra_len(entry));
+            // comment length, disk number and internal attributes.
+            buf.put_bytes(0, 6);
+            buf.put_u32_le(FILE_ATTRIBUTES);
+            buf.put_u32_le(entry.offset);
+            buf.put_slice(entry.name.as_bytes());
+            put_extra(entry, buf);
+        }
+
+        let central_size = to_u32((buf.len() - start) as u64)?;
+        let count = self.entries.len() as u16;
+
+        buf.put_u32_le(END_OF_CENTRAL);
+        // disk numbers.
+        buf.put_bytes(0, 4);
+        buf.put_u16_le(count);
+        buf.put_u16_le(count);
+        buf.put_u32_le(central_size);
+        buf.put_u32_le(central_offset);
+        // comment length.
+        buf.put_u16_le(0);
+
+        Ok(())
+    }
+}
+
+fn extra_len(entry: &Entry) -> u16 {
+    if entry.mtime.is_some() {
+        9
+    } else {
+        0
+    }
+}
+
+fn put_extra(entry: &Entry, buf: &mut BytesMut) {
+    if let Some(mtime) = entry.mtime {
+        buf.put_u16_le(EXTRA_TIMESTAMP);
+        buf.put_u16_le(5);
+        // modification time flag.
+        buf.put_u8(1);
+        buf.put_u32_le(mtime);
+    }
+}
+
+fn to_u32(n: u64) -> Result<u32, ArchiveError> {
+    u32::try_from(n).map_err(|_| ArchiveError::TooLarge)
+}
+
+fn to_u16(n: usize) -> Result<u16, ArchiveError> {
+    u16::try_from(n).map_err(|_| ArchiveError::TooLarge)
+}
+
+// 1980-01-01 00:00:00
+const DOS_EPOCH: (u16, u16) = (0, 1 << 5 | 1);
+
+// ms-dos time and date in UTC. time out of it's 1980 to 2107 range is clamped.
+fn dos_date_time(time: SystemTime) -> (u16, u16) {
+    let Ok(dur) = time.duration_since(SystemTime::UNIX_EPOCH) else {
+        return DOS_EPOCH;
+    };
+    let secs = dur.as_secs();
+    let (year, month, day) = civil_from_days(secs / 86400);
+
+    if year < 1980 {
+        return DOS_EPOCH;
+    }
+    if year > 2107 {
+        return (23 << 11 | 59 << 5 | 29, 127 << 9 | 12 << 5 | 31);
+    }
+
+    let secs = secs % 86400;
+    let time = (secs / 3600) << 11 | (secs % 3600 / 60) << 5 | (secs % 60 / 2);
+    let date = (year - 1980) << 9 | month << 5 | day;
+    (time as u16, date as u16)
+}
+
+// days since unix epoch to (year, month, day) of proleptic gregorian calendar.
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod test {
+    use core::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn dos_time() {
+        let time = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+
+        // 2023-05-24 13:45:31 UTC
+        let (t, d) = dos_date_time(time(1_684_935_931));
+        assert_eq!((d >> 9) + 1980, 2023);
+        assert_eq!(d >> 5 & 0xf, 5);
+        assert_eq!(d & 0x1f, 24);
+        assert_eq!(t >> 11, 13);
+        assert_eq!(t >> 5 & 0x3f, 45);
+        assert_eq!((t & 0x1f) * 2, 30);
+
+        // 2000-02-29
+        let (_, d) = dos_date_time(time(951_782_400));
+        assert_eq!((d >> 9, d >> 5 & 0xf, d & 0x1f), (20, 2, 29));
+
+        assert_eq!(dos_date_time(time(0)), DOS_EPOCH);
+    }
+}
@@ -0,0 +1,49 @@
+use core::{fmt, future::Future, ops::Deref};
+
+use crate::{
+    body::BodyStream,
+    handler::{error::ExtractError, FromRequest},
+    request::WebRequest,
+};
+
+/// Principal of authenticated request.
+///
+/// [Auth](crate::middleware::auth::Auth) middleware must be enclosed for this extractor to
+/// function. `T` is the principal type produced by validator of the middleware.
+#[derive(Clone)]
+pub struct Principal<T>(pub T);
+
+impl<T: fmt::Debug> fmt::Debug for Principal<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Principal({:?})", self.0)
+    }
+}
+
+impl<T> Deref for Principal<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<'a, 'r, C, B, T> FromRequest<'a, WebRequest<'r, C, B>> for Principal<T>
+where
+    T: Clone + Send + Sync + 'static,
+    B: BodyStream,
+{
+    type Type<'b> = Principal<T>;
+    type Error = ExtractError<B::Error>;
+    type Future = impl Future<Output = Result<Self, Self::Error>> where WebRequest<'r, C, B>: 'a;
+
+    #[inline]
+    fn from_request(req: &'a WebRequest<'r, C, B>) -> Self::Future {
+        let res = req
+            .req()
+            .extensions()
+            .get::<Principal<T>>()
+            .cloned()
+            .ok_or(ExtractError::ExtensionNotFound);
+        async { res }
+    }
+}
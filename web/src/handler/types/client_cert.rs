@@ -0,0 +1,445 @@
+//! type extractor for identity of client certificate presented in tls handshake.
+
+use core::{fmt, future::Future, time::Duration};
+
+use std::{
+    error,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use x509_parser::{
+    extensions::GeneralName,
+    objects::{oid2abbrev, oid_registry},
+    x509::X509Name,
+};
+
+use crate::{
+    body::BodyStream,
+    dev::bytes::Bytes,
+    handler::{error::ExtractError, FromRequest},
+    http::PeerCertificates,
+    request::WebRequest,
+};
+
+/// Identity parsed from leaf certificate client presented in tls handshake.
+///
+/// The certificate is only present when tls acceptor requests it from client. For rustls that
+/// means configuring server with a client certificate verifier. Use
+/// `AllowAnyAnonymousOrAuthenticatedClient` verifier to keep routes without client certificate
+/// requirement accessible on the same listener.
+///
+/// [RequireClientCert](crate::middleware::client_cert::RequireClientCert) middleware can be
+/// enclosed to reject request without valid certificate before reaching the handler.
+#[derive(Clone, Debug)]
+pub struct ClientCertInfo {
+    der: Bytes,
+    subject: String,
+    common_name: Option<String>,
+    issuer: String,
+    serial: Vec<u8>,
+    not_before: SystemTime,
+    not_after: SystemTime,
+    subject_alt_names: Vec<SubjectAltName>,
+}
+
+/// Subject alternative name of [ClientCertInfo]. Name types not listed are ignored.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum SubjectAltName {
+    Dns(String),
+    Email(String),
+    Uri(String),
+    Ip(IpAddr),
+}
+
+impl ClientCertInfo {
+    /// Parse certificate from it's DER encoding.
+    pub fn from_der(der: Bytes) -> Result<Self, MalformedCertificate> {
+        let (_, cert) = x509_parser::parse_x509_certificate(&der).map_err(|_| MalformedCertificate)?;
+
+        let serial = match cert.raw_serial() {
+            [0, rest @ ..] if !rest.is_empty() => rest,
+            serial => serial,
+        }
+        .to_vec();
+
+        let (issuer, _) = name(cert.issuer());
+        let (subject, common_name) = name(cert.subject());
+
+        let validity = cert.validity();
+        let not_before = time(validity.not_before.timestamp());
+        let not_after = time(validity.not_after.timestamp());
+
+        let subject_alt_names = match cert.subject_alternative_name().map_err(|_| MalformedCertificate)? {
+            Some(ext) => ext
+                .value
+                .general_names
+                .iter()
+                .filter_map(|name| alt_name(name).transpose())
+                .collect::<Result<_, _>>()?,
+            None => Vec::new(),
+        };
+
+        Ok(Self {
+            der,
+            subject,
+            common_name,
+            issuer,
+            serial,
+            not_before,
+            not_after,
+            subject_alt_names,
+        })
+    }
+
+    /// DER encoding of certificate.
+    pub fn der(&self) -> &Bytes {
+        &self.der
+    }
+
+    /// Subject distinguished name in RFC 4514 string form. e.g. `CN=alice,O=example`.
+    pub fn subject(&self) -> &str {
+        &self.subject
+    }
+
+    /// Value of the first common name attribute of subject.
+    pub fn common_name(&self) -> Option<&str> {
+        self.common_name.as_deref()
+    }
+
+    /// Issuer distinguished name in RFC 4514 string form.
+    pub fn issuer(&self) -> &str {
+        &self.issuer
+    }
+
+    /// Big endian bytes of serial number with leading zero byte stripped.
+    pub fn serial(&self) -> &[u8] {
+        &self.serial
+    }
+
+    /// Serial number in colon separated hex form. e.g. `01:a2:ff`.
+    pub fn serial_hex(&self) -> String {
+        let mut hex = String::with_capacity(self.serial.len() * 3);
+        for (i, byte) in self.serial.iter().enumerate() {
+            if i != 0 {
+                hex.push(':');
+            }
+            hex.push_str(&format!("{byte:02x}"));
+        }
+        hex
+    }
+
+    /// Start of validity window.
+    pub fn not_before(&self) -> SystemTime {
+        self.not_before
+    }
+
+    /// End of validity window.
+    pub fn not_after(&self) -> SystemTime {
+        self.not_after
+    }
+
+    /// Check if given time is inside validity window.
+    pub fn is_valid_at(&self, time: SystemTime) -> bool {
+        self.not_before <= time && time <= self.not_after
+    }
+
+    /// Subject alternative names from certificate extension.
+    pub fn subject_alt_names(&self) -> &[SubjectAltName] {
+        &self.subject_alt_names
+    }
+}
+
+impl<'a, 'r, C, B> FromRequest<'a, WebRequest<'r, C, B>> for ClientCertInfo
+where
+    B: BodyStream,
+{
+    type Type<'b> = ClientCertInfo;
+    type Error = ExtractError<B::Error>;
+    type Future
+        = impl Future<Output = Result<Self, Self::Error>>
+    where
+        WebRequest<'r, C, B>: 'a;
+
+    #[inline]
+    fn from_request(req: &'a WebRequest<'r, C, B>) -> Self::Future {
+        let ext = req.req().extensions();
+        // RequireClientCert middleware inserts parsed info. fall back to parse leaf certificate.
+        let res = match ext.get::<ClientCertInfo>() {
+            Some(info) => Ok(info.clone()),
+            None => match ext.get::<PeerCertificates>() {
+                Some(certs) => Self::from_der(certs.leaf().clone()).map_err(|e| ExtractError::Boxed(Box::new(e))),
+                None => Err(ExtractError::ExtensionNotFound),
+            },
+        };
+        async { res }
+    }
+}
+
+/// Error type when certificate can not be parsed as X.509.
+#[derive(Debug)]
+pub struct MalformedCertificate;
+
+impl fmt::Display for MalformedCertificate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("malformed client certificate")
+    }
+}
+
+impl error::Error for MalformedCertificate {}
+
+// format Name into RFC 4514 string and the first common name. attribute value not in a string
+// type is written in hex form.
+fn name(name: &X509Name<'_>) -> (String, Option<String>) {
+    let registry = oid_registry();
+    let common_name = name
+        .iter_common_name()
+        .next()
+        .and_then(|cn| cn.as_str().ok())
+        .map(str::to_owned);
+
+    let mut parts = name
+        .iter_rdn()
+        .map(|rdn| {
+            rdn.iter()
+                .map(|attr| {
+                    let oid = attr.attr_type();
+                    let value = match attr.as_str() {
+                        Ok(value) => escape(value),
+                        Err(_) => {
+                            let mut hex = String::from("#");
+                            for byte in attr.as_slice() {
+                                hex.push_str(&format!("{byte:02x}"));
+                            }
+                            hex
+                        }
+                    };
+                    match oid2abbrev(oid, registry) {
+                        Ok(abbrev) => format!("{abbrev}={value}"),
+                        Err(_) => format!("{}={value}", oid.to_id_string()),
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join("+")
+        })
+        .collect::<Vec<_>>();
+
+    // RFC 4514 string starts from the last relative distinguished name.
+    parts.reverse();
+    (parts.join(","), common_name)
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    let last = value.chars().count().saturating_sub(1);
+    for (i, c) in value.chars().enumerate() {
+        let special = matches!(c, ',' | '+' | '"' | '\\' | '<' | '>' | ';')
+            || (i == 0 && matches!(c, '#' | ' '))
+            || (i == last && c == ' ');
+        if special {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn time(secs: i64) -> SystemTime {
+    if secs >= 0 {
+        UNIX_EPOCH + Duration::from_secs(secs as u64)
+    } else {
+        UNIX_EPOCH - Duration::from_secs(secs.unsigned_abs())
+    }
+}
+
+// name types not listed in SubjectAltName are skipped.
+fn alt_name(name: &GeneralName<'_>) -> Result<Option<SubjectAltName>, MalformedCertificate> {
+    let name = match *name {
+        GeneralName::RFC822Name(email) => SubjectAltName::Email(email.to_owned()),
+        GeneralName::DNSName(dns) => SubjectAltName::Dns(dns.to_owned()),
+        GeneralName::URI(uri) => SubjectAltName::Uri(uri.to_owned()),
+        GeneralName::IPAddress(ip) => match *ip {
+            [a, b, c, d] => SubjectAltName::Ip(IpAddr::V4(Ipv4Addr::new(a, b, c, d))),
+            _ => match <[u8; 16]>::try_from(ip) {
+                Ok(octets) => SubjectAltName::Ip(IpAddr::V6(Ipv6Addr::from(octets))),
+                Err(_) => return Err(MalformedCertificate),
+            },
+        },
+        _ => return Ok(None),
+    };
+    Ok(Some(name))
+}
+
+#[cfg(test)]
+mod test {
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::{handler::handler_service, http::StatusCode, route::get, test::TestRequest, App};
+
+    use super::*;
+
+    fn cert() -> rcgen::Certificate {
+        let mut params = rcgen::CertificateParams::new(vec![String::from("client.example.com")]);
+        params.distinguished_name.push(rcgen::DnType::CommonName, "alice");
+        params
+            .distinguished_name
+            .push(rcgen::DnType::OrganizationName, "Example, Inc");
+        params.serial_number = Some(0x01a2ff);
+        params.not_before = rcgen::date_time_ymd(2023, 1, 2);
+        params.not_after = rcgen::date_time_ymd(2051, 3, 4);
+        params.subject_alt_names.extend([
+            rcgen::SanType::IpAddress(IpAddr::V4(Ipv4Addr::LOCALHOST)),
+            rcgen::SanType::IpAddress(IpAddr::V6(Ipv6Addr::LOCALHOST)),
+            rcgen::SanType::Rfc822Name(String::from("alice@example.com")),
+            rcgen::SanType::URI(String::from("spiffe://example.com/alice")),
+        ]);
+        rcgen::Certificate::from_params(params).unwrap()
+    }
+
+    fn der() -> Vec<u8> {
+        cert().serialize_der().unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        let der = der();
+        let info = ClientCertInfo::from_der(Bytes::from(der.clone())).unwrap();
+
+        assert_eq!(info.der().as_ref(), der.as_slice());
+        assert_eq!(info.subject(), "O=Example\\, Inc,CN=alice");
+        assert_eq!(info.common_name(), Some("alice"));
+        assert_eq!(info.issuer(), info.subject());
+        assert_eq!(info.serial(), &[0x01, 0xa2, 0xff]);
+        assert_eq!(info.serial_hex(), "01:a2:ff");
+        assert_eq!(info.not_before(), UNIX_EPOCH + Duration::from_secs(1672617600));
+        assert_eq!(info.not_after(), UNIX_EPOCH + Duration::from_secs(2561500800));
+        assert!(info.is_valid_at(SystemTime::now()));
+        assert!(!info.is_valid_at(UNIX_EPOCH));
+        assert_eq!(
+            info.subject_alt_names(),
+            &[
+                SubjectAltName::Dns(String::from("client.example.com")),
+                SubjectAltName::Ip(IpAddr::V4(Ipv4Addr::LOCALHOST)),
+                SubjectAltName::Ip(IpAddr::V6(Ipv6Addr::LOCALHOST)),
+                SubjectAltName::Email(String::from("alice@example.com")),
+                SubjectAltName::Uri(String::from("spiffe://example.com/alice")),
+            ]
+        );
+
+        assert!(ClientCertInfo::from_der(Bytes::from_static(b"\x30\x03\x02\x01")).is_err());
+    }
+
+    #[test]
+    fn extract() {
+        async fn handler(cert: Option<ClientCertInfo>) -> String {
+            cert.and_then(|cert| cert.common_name().map(str::to_owned))
+                .unwrap_or_else(|| String::from("anonymous"))
+        }
+
+        let service = App::new()
+            .at("/", get(handler_service(handler)))
+            .finish_for_test()
+            .now_or_panic();
+
+        let res = service.call(TestRequest::get("/")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK);
+        assert_eq!(res.string_body().now_or_panic().unwrap(), "anonymous");
+
+        let req = TestRequest::get("/").extension(PeerCertificates::new(vec![Bytes::from(der())]));
+        let res = service.call(req).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK);
+        assert_eq!(res.string_body().now_or_panic().unwrap(), "alice");
+    }
+
+    #[cfg(feature = "rustls")]
+    #[tokio::test]
+    async fn rustls() {
+        use std::{
+            io::{Read, Write},
+            net::{TcpListener, TcpStream},
+            sync::Arc,
+            thread,
+        };
+
+        use rustls_crate::{
+            server::AllowAnyAnonymousOrAuthenticatedClient, Certificate, ClientConfig, ClientConnection, PrivateKey,
+            RootCertStore, ServerConfig, StreamOwned,
+        };
+        use xitca_http::HttpServiceBuilder;
+
+        use crate::dev::service::Service;
+
+        async fn handler(cert: Option<ClientCertInfo>) -> String {
+            cert.and_then(|cert| cert.common_name().map(str::to_owned))
+                .unwrap_or_else(|| String::from("anonymous"))
+        }
+
+        let server = rcgen::generate_simple_self_signed(vec![String::from("localhost")]).unwrap();
+        let server_cert = Certificate(server.serialize_der().unwrap());
+        let client = cert();
+        let client_cert = Certificate(client.serialize_der().unwrap());
+
+        let mut roots = RootCertStore::empty();
+        roots.add(&client_cert).unwrap();
+        let config = ServerConfig::builder()
+            .with_safe_defaults()
+            .with_client_cert_verifier(AllowAnyAnonymousOrAuthenticatedClient::new(roots).boxed())
+            .with_single_cert(
+                vec![server_cert.clone()],
+                PrivateKey(server.serialize_private_key_der()),
+            )
+            .unwrap();
+
+        let mut roots = RootCertStore::empty();
+        roots.add(&server_cert).unwrap();
+        let client_config = ClientConfig::builder()
+            .with_safe_defaults()
+            .with_root_certificates(roots);
+
+        // date service of http service is spawned as local task.
+        tokio::task::LocalSet::new()
+            .run_until(async {
+                let service = HttpServiceBuilder::h1(App::new().at("/", get(handler_service(handler))).finish())
+                    .rustls(Arc::new(config))
+                    .call(())
+                    .await
+                    .unwrap();
+
+                let anonymous = Arc::new(client_config.clone().with_no_client_auth());
+                let authenticated = Arc::new(
+                    client_config
+                        .with_client_auth_cert(vec![client_cert], PrivateKey(client.serialize_private_key_der()))
+                        .unwrap(),
+                );
+
+                for (config, name) in [(anonymous, "anonymous"), (authenticated, "alice")] {
+                    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+                    let addr = listener.local_addr().unwrap();
+
+                    let handle = thread::spawn(move || {
+                        let conn = ClientConnection::new(config, "localhost".try_into().unwrap()).unwrap();
+                        let mut stream = StreamOwned::new(conn, TcpStream::connect(addr).unwrap());
+                        stream
+                            .write_all(b"GET / HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\n\r\n")
+                            .unwrap();
+                        let mut res = Vec::new();
+                        // server closes connection without tls close notify.
+                        let _ = stream.read_to_end(&mut res);
+                        String::from_utf8(res).unwrap()
+                    });
+
+                    let (stream, addr) = listener.accept().unwrap();
+                    stream.set_nonblocking(true).unwrap();
+                    let stream = xitca_io::net::TcpStream::from_std(stream).unwrap();
+                    service.serve_connection(stream, Some(addr)).await.unwrap();
+
+                    let res = tokio::task::spawn_blocking(move || handle.join().unwrap())
+                        .await
+                        .unwrap();
+                    assert!(res.starts_with("HTTP/1.1 200 OK"));
+                    assert!(res.ends_with(name));
+                }
+            })
+            .await
+    }
+}
@@ -0,0 +1,90 @@
+use std::{borrow::Borrow, fmt, future::Future, ops::Deref, sync::Arc};
+
+use crate::{
+    body::BodyStream,
+    config::{Config, ConfigSection},
+    handler::{error::ExtractError, FromRequest},
+    request::WebRequest,
+};
+
+/// Extract typed section of [Config] used as App state. See [App::with_config](crate::App::with_config).
+///
+/// Section is deserialized once per worker and shared by it's requests until the source of config
+/// is reloaded. Extracting a missing or malformed section is an error converting to
+/// `500 Internal Server Error` response.
+///
+/// # Examples:
+/// ```rust
+/// # use std::collections::HashMap;
+/// use serde::Deserialize;
+/// use xitca_web::{
+///     config::{Config, ConfigSection, LayeredSource},
+///     handler::{config::Conf, handler_service},
+///     request::WebRequest,
+///     App,
+/// };
+///
+/// #[derive(Deserialize)]
+/// struct Limits {
+///     max_upload: usize,
+/// }
+///
+/// impl ConfigSection for Limits {
+///     const SECTION: &'static str = "limits";
+/// }
+///
+/// async fn handler(limits: Conf<Limits>) -> String {
+///     limits.max_upload.to_string()
+/// }
+///
+/// App::with_config(LayeredSource::new().set("limits.max_upload", 1024).env("APP_"))
+///     .at("/", handler_service(handler))
+/// #   .at("/nah", handler_service(nah));
+///
+/// # async fn nah(_: &WebRequest<'_, Config>) {
+/// #   // needed to infer the body type of request
+/// # }
+/// ```
+pub struct Conf<T>(pub Arc<T>);
+
+impl<T: fmt::Debug> fmt::Debug for Conf<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Conf({:?})", self.0)
+    }
+}
+
+impl<T> Clone for Conf<T> {
+    fn clone(&self) -> Self {
+        Self(self.0.clone())
+    }
+}
+
+impl<T> Deref for Conf<T> {
+    type Target = T;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<'a, 'r, C, B, T> FromRequest<'a, WebRequest<'r, C, B>> for Conf<T>
+where
+    C: Borrow<Config>,
+    B: BodyStream,
+    T: ConfigSection,
+{
+    type Type<'b> = Conf<T>;
+    type Error = ExtractError<B::Error>;
+    type Future = impl Future<Output = Result<Self, Self::Error>> where WebRequest<'r, C, B>: 'a;
+
+    #[inline]
+    fn from_request(req: &'a WebRequest<'r, C, B>) -> Self::Future {
+        let res = req
+            .state()
+            .borrow()
+            .section()
+            .map(Conf)
+            .map_err(|e| ExtractError::Boxed(Box::new(e)));
+        async { res }
+    }
+}
@@ -0,0 +1,61 @@
+use core::{future::Future, time::Duration};
+
+use tokio::time::Instant;
+
+use crate::{
+    body::BodyStream,
+    handler::{error::ExtractError, FromRequest},
+    request::WebRequest,
+};
+
+/// Instant when handling of request must be finished.
+///
+/// It's inserted into request extensions by [DeadlineHeader](crate::middleware::timeout::DeadlineHeader)
+/// from budget propagated by caller, or by [Timeout](crate::middleware::timeout::Timeout) from it's
+/// static duration. Handler can use it to shed work that can not be finished in time.
+///
+/// # Examples:
+/// ```rust
+/// # use core::time::Duration;
+/// # use xitca_web::handler::deadline::Deadline;
+/// async fn handler(deadline: Deadline) -> &'static str {
+///     if deadline.remaining() < Duration::from_millis(100) {
+///         return "skipped";
+///     }
+///     "done"
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Deadline(pub Instant);
+
+impl Deadline {
+    /// Duration left before deadline. Zero when deadline has passed.
+    pub fn remaining(&self) -> Duration {
+        self.0.saturating_duration_since(Instant::now())
+    }
+
+    /// Check if deadline has passed.
+    pub fn is_expired(&self) -> bool {
+        self.0 <= Instant::now()
+    }
+}
+
+impl<'a, 'r, C, B> FromRequest<'a, WebRequest<'r, C, B>> for Deadline
+where
+    B: BodyStream,
+{
+    type Type<'b> = Deadline;
+    type Error = ExtractError<B::Error>;
+    type Future = impl Future<Output = Result<Self, Self::Error>> where WebRequest<'r, C, B>: 'a;
+
+    #[inline]
+    fn from_request(req: &'a WebRequest<'r, C, B>) -> Self::Future {
+        let res = req
+            .req()
+            .extensions()
+            .get::<Deadline>()
+            .copied()
+            .ok_or(ExtractError::ExtensionNotFound);
+        async { res }
+    }
+}
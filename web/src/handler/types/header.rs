@@ -82,32 +82,36 @@ where
 mod test {
     use xitca_unsafe_collection::futures::NowOrPanic;
 
+    use crate::{handler::handler_service, http::StatusCode, test::TestRequest, App};
+
     use super::*;
 
+    async fn handler(
+        accept_encoding: HeaderRef<'_, { super::ACCEPT_ENCODING }>,
+        host: HeaderRef<'_, { super::HOST }>,
+    ) -> String {
+        assert_eq!(accept_encoding.deref(), &HeaderValue::from_static("251"));
+        assert_eq!(host.deref(), &HeaderValue::from_static("996"));
+        String::from("ok")
+    }
+
     #[test]
     fn extract_header() {
-        let mut req = WebRequest::new_test(());
-        let mut req = req.as_web_req();
-        req.req_mut()
-            .headers_mut()
-            .insert(header::HOST, header::HeaderValue::from_static("996"));
-        req.req_mut()
-            .headers_mut()
-            .insert(header::ACCEPT_ENCODING, header::HeaderValue::from_static("251"));
-
-        assert_eq!(
-            HeaderRef::<'_, { super::ACCEPT_ENCODING }>::from_request(&req)
-                .now_or_panic()
-                .unwrap()
-                .deref(),
-            &header::HeaderValue::from_static("251")
-        );
-        assert_eq!(
-            HeaderRef::<'_, { super::HOST }>::from_request(&req)
-                .now_or_panic()
-                .unwrap()
-                .deref(),
-            &header::HeaderValue::from_static("996")
-        );
+        let service = App::new()
+            .at("/", handler_service(handler))
+            .finish_for_test()
+            .now_or_panic();
+
+        let req = TestRequest::default()
+            .header(header::HOST, "996")
+            .header(header::ACCEPT_ENCODING, "251");
+        service.call(req).now_or_panic().unwrap().assert_status(StatusCode::OK);
+
+        let req = TestRequest::default().header(header::HOST, "996");
+        service
+            .call(req)
+            .now_or_panic()
+            .unwrap()
+            .assert_status(StatusCode::INTERNAL_SERVER_ERROR);
     }
 }
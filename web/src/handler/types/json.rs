@@ -14,7 +14,7 @@ use crate::{
         error::{ExtractError, _ParseError},
         FromRequest, Responder,
     },
-    http::{const_header_value::JSON, header::CONTENT_TYPE},
+    http::{const_header_value::JSON, header::CONTENT_TYPE, Scratch},
     request::WebRequest,
     response::WebResponse,
 };
@@ -82,7 +82,9 @@ where
 
             let mut body = pin!(body);
 
-            let mut buf = BytesMut::new();
+            // reuse buffer from scratch arena when it's available.
+            let scratch = req.req().extensions().get::<Scratch>();
+            let mut buf = scratch.map(Scratch::take_bytes).unwrap_or_default();
 
             while let Some(chunk) = poll_fn(|cx| body.as_mut().poll_next(cx)).await {
                 let chunk = chunk.map_err(ExtractError::Body)?;
@@ -92,9 +94,13 @@ where
                 }
             }
 
-            let json = serde_json::from_slice(&buf).map_err(_ParseError::JsonString)?;
+            let json = serde_json::from_slice(&buf);
 
-            Ok(Json(json))
+            if let Some(scratch) = scratch {
+                scratch.put_bytes(buf);
+            }
+
+            Ok(Json(json.map_err(_ParseError::JsonString)?))
         }
     }
 }
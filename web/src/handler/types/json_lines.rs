@@ -0,0 +1,392 @@
+//! newline delimited json (ndjson) type extractor and responder.
+
+use core::{
+    fmt,
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use std::error;
+
+use futures_core::stream::Stream;
+use pin_project_lite::pin_project;
+use serde::{de::DeserializeOwned, ser::Serialize};
+
+use crate::{
+    body::{BodyStream, RequestBody, ResponseBody},
+    dev::{
+        bytes::{BufMutWriter, Bytes, BytesMut},
+        service::pipeline::PipelineE,
+    },
+    handler::{error::ExtractError, FromRequest, Responder},
+    http::{
+        const_header_value::NDJSON,
+        header::{HeaderValue, CONTENT_TYPE},
+    },
+    request::WebRequest,
+    response::WebResponse,
+};
+
+use super::header::{self, HeaderRef};
+
+const DEFAULT_LIMIT: usize = 64 * 1024;
+
+/// Extract type for streaming newline delimited json. It's a [Stream] that lazily split request
+/// body on `\n` and deserialize every line to type T.
+///
+/// const generic param LIMIT is for max size of a single line in bytes. Default limit is
+/// [DEFAULT_LIMIT] in bytes.
+///
+/// Malformed and oversized lines are yielded as error items and the stream continue with next
+/// line. Use [JsonLines::strict] to end the stream on first error.
+///
+/// Request must have `Content-Type` of `application/x-ndjson` or `application/jsonl`. Other media
+/// types are rejected with `415 Unsupported Media Type`.
+pub struct JsonLines<T, const LIMIT: usize = DEFAULT_LIMIT, B = RequestBody> {
+    body: Pin<Box<B>>,
+    buf: BytesMut,
+    strict: bool,
+    skip: bool,
+    eof: bool,
+    done: bool,
+    _item: PhantomData<fn() -> T>,
+}
+
+impl<T, const LIMIT: usize, B> JsonLines<T, LIMIT, B> {
+    pub(crate) fn new(body: B) -> Self {
+        Self {
+            body: Box::pin(body),
+            buf: BytesMut::new(),
+            strict: false,
+            skip: false,
+            eof: false,
+            done: false,
+            _item: PhantomData,
+        }
+    }
+
+    /// End the stream after yielding the first error item.
+    pub fn strict(mut self) -> Self {
+        self.strict = true;
+        self
+    }
+}
+
+impl<T, const LIMIT: usize, B> fmt::Debug for JsonLines<T, LIMIT, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JsonLines")
+            .field("limit", &LIMIT)
+            .field("strict", &self.strict)
+            .finish()
+    }
+}
+
+impl<T, const LIMIT: usize, B> Stream for JsonLines<T, LIMIT, B>
+where
+    B: BodyStream,
+    T: DeserializeOwned,
+{
+    type Item = Result<T, JsonLinesError<B::Error>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if this.done {
+                return Poll::Ready(None);
+            }
+
+            let line = match this.buf.iter().position(|b| *b == b'\n') {
+                Some(idx) => {
+                    let line = this.buf.split_to(idx + 1);
+                    if core::mem::take(&mut this.skip) {
+                        continue;
+                    }
+                    line
+                }
+                None if this.eof => {
+                    this.done = true;
+                    if this.skip || this.buf.is_empty() {
+                        return Poll::Ready(None);
+                    }
+                    this.buf.split()
+                }
+                None => {
+                    if this.buf.len() > LIMIT {
+                        this.buf.clear();
+                        if !core::mem::replace(&mut this.skip, true) {
+                            this.done = this.strict;
+                            return Poll::Ready(Some(Err(JsonLinesError::Overflow)));
+                        }
+                    }
+
+                    match this.body.as_mut().poll_next(cx) {
+                        Poll::Ready(Some(Ok(chunk))) => this.buf.extend_from_slice(chunk.as_ref()),
+                        Poll::Ready(Some(Err(e))) => {
+                            this.done = true;
+                            return Poll::Ready(Some(Err(JsonLinesError::Body(e))));
+                        }
+                        Poll::Ready(None) => this.eof = true,
+                        Poll::Pending => return Poll::Pending,
+                    }
+                    continue;
+                }
+            };
+
+            let line = line.strip_suffix(b"\n").unwrap_or(&line);
+            let line = line.strip_suffix(b"\r").unwrap_or(line);
+
+            if line.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+
+            let res = if line.len() > LIMIT {
+                Err(JsonLinesError::Overflow)
+            } else {
+                serde_json::from_slice(line).map_err(JsonLinesError::Parse)
+            };
+
+            if res.is_err() {
+                this.done = this.strict;
+            }
+
+            return Poll::Ready(Some(res));
+        }
+    }
+}
+
+impl<'a, 'r, C, B, T, const LIMIT: usize> FromRequest<'a, WebRequest<'r, C, B>> for JsonLines<T, LIMIT, B>
+where
+    B: BodyStream + Default,
+{
+    type Type<'b> = JsonLines<T, LIMIT, B>;
+    type Error = ExtractError<B::Error>;
+    type Future = impl Future<Output = Result<Self, Self::Error>> where WebRequest<'r, C, B>: 'a;
+
+    fn from_request(req: &'a WebRequest<'r, C, B>) -> Self::Future {
+        async move {
+            let content_type = HeaderRef::<'a, { header::CONTENT_TYPE }>::from_request(req).await?;
+            if !is_json_lines(&content_type) {
+                return Err(ExtractError::UnsupportedMediaType);
+            }
+            Ok(JsonLines::new(req.take_body_ref()))
+        }
+    }
+}
+
+// media type with optional parameters. e.g: application/x-ndjson; charset=utf-8
+fn is_json_lines(value: &HeaderValue) -> bool {
+    value
+        .to_str()
+        .ok()
+        .and_then(|value| value.split(';').next())
+        .map(str::trim)
+        .is_some_and(|mime| {
+            mime.eq_ignore_ascii_case("application/x-ndjson") || mime.eq_ignore_ascii_case("application/jsonl")
+        })
+}
+
+/// Error type of [JsonLines] stream item.
+#[derive(Debug)]
+pub enum JsonLinesError<E> {
+    /// Request body error. The stream always end after it.
+    Body(E),
+    /// A line exceeds the size limit.
+    Overflow,
+    /// A line failed to deserialize.
+    Parse(serde_json::Error),
+}
+
+impl<E: fmt::Display> fmt::Display for JsonLinesError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Body(ref e) => fmt::Display::fmt(e, f),
+            Self::Overflow => f.write_str("json line exceeds size limit"),
+            Self::Parse(ref e) => fmt::Display::fmt(e, f),
+        }
+    }
+}
+
+impl<E> error::Error for JsonLinesError<E> where E: fmt::Debug + fmt::Display {}
+
+/// Responder type for streaming newline delimited json. Every item of the inner [Stream] is
+/// serialized and followed by `\n`. Response is sent with `Content-Type: application/x-ndjson` and
+/// chunked transfer encoding.
+pub struct JsonLinesStream<S>(pub S);
+
+impl<'r, C, B, S, T, E> Responder<WebRequest<'r, C, B>> for JsonLinesStream<S>
+where
+    S: Stream<Item = Result<T, E>> + 'static,
+    T: Serialize,
+    E: error::Error + Send + Sync + 'static,
+{
+    type Output = WebResponse;
+    type Future = impl Future<Output = Self::Output>;
+
+    fn respond_to(self, req: WebRequest<'r, C, B>) -> Self::Future {
+        let body = ResponseBody::box_stream(SerializeStream { stream: self.0 });
+        let mut res = req.into_response(body);
+        res.headers_mut().insert(CONTENT_TYPE, NDJSON);
+        async { res }
+    }
+}
+
+/// Error type of [JsonLinesStream] response body.
+pub type JsonLinesStreamError<E> = PipelineE<serde_json::Error, E>;
+
+pin_project! {
+    struct SerializeStream<S> {
+        #[pin]
+        stream: S,
+    }
+}
+
+impl<S, T, E> Stream for SerializeStream<S>
+where
+    S: Stream<Item = Result<T, E>>,
+    T: Serialize,
+    E: error::Error + Send + Sync + 'static,
+{
+    type Item = Result<Bytes, JsonLinesStreamError<E>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let item = match futures_core::ready!(self.project().stream.poll_next(cx)) {
+            Some(item) => item,
+            None => return Poll::Ready(None),
+        };
+
+        let res = item.map_err(JsonLinesStreamError::Second).and_then(|item| {
+            let mut buf = BytesMut::new();
+            serde_json::to_writer(BufMutWriter(&mut buf), &item).map_err(JsonLinesStreamError::First)?;
+            buf.extend_from_slice(b"\n");
+            Ok(buf.freeze())
+        });
+
+        Poll::Ready(Some(res))
+    }
+
+    // item count is unknown and chunked encoding is used.
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, None)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::convert::Infallible;
+
+    use futures_util::stream::{self, StreamExt};
+    use serde::Deserialize;
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use super::*;
+
+    #[derive(Debug, Deserialize, Eq, PartialEq)]
+    struct Item {
+        id: u32,
+    }
+
+    fn body(chunks: &[&'static str]) -> impl Stream<Item = Result<Bytes, Infallible>> {
+        stream::iter(chunks.to_vec()).map(|chunk| Ok(Bytes::from_static(chunk.as_bytes())))
+    }
+
+    #[test]
+    fn split_across_chunks() {
+        let chunks = ["{\"i", "d\":1}\n{\"id\"", ":2}\r", "\n\n{\"id\":3", "}"];
+        let lines = JsonLines::<Item, DEFAULT_LIMIT, _>::new(body(&chunks));
+
+        let items = lines.map(Result::unwrap).collect::<Vec<_>>().now_or_panic();
+
+        assert_eq!(items, vec![Item { id: 1 }, Item { id: 2 }, Item { id: 3 }]);
+    }
+
+    #[test]
+    fn malformed_line() {
+        let chunks = ["{\"id\":1}\nnot json\n{\"id\":3}\n"];
+
+        let items = JsonLines::<Item, DEFAULT_LIMIT, _>::new(body(&chunks))
+            .collect::<Vec<_>>()
+            .now_or_panic();
+        assert_eq!(items.len(), 3);
+        assert!(matches!(items[1], Err(JsonLinesError::Parse(_))));
+        assert_eq!(items[2].as_ref().unwrap(), &Item { id: 3 });
+
+        let items = JsonLines::<Item, DEFAULT_LIMIT, _>::new(body(&chunks))
+            .strict()
+            .collect::<Vec<_>>()
+            .now_or_panic();
+        assert_eq!(items.len(), 2);
+        assert!(matches!(items[1], Err(JsonLinesError::Parse(_))));
+    }
+
+    #[test]
+    fn line_overflow() {
+        let chunks = ["{\"id\":1}\n{\"id\":", "1234567", "8901}\n{\"id\":3}\n"];
+
+        let items = JsonLines::<Item, 12, _>::new(body(&chunks))
+            .collect::<Vec<_>>()
+            .now_or_panic();
+        assert_eq!(items.len(), 3);
+        assert!(matches!(items[1], Err(JsonLinesError::Overflow)));
+        assert_eq!(items[2].as_ref().unwrap(), &Item { id: 3 });
+
+        let items = JsonLines::<Item, 12, _>::new(body(&chunks))
+            .strict()
+            .collect::<Vec<_>>()
+            .now_or_panic();
+        assert_eq!(items.len(), 2);
+    }
+
+    #[test]
+    fn content_type() {
+        let mut req = WebRequest::new_test(());
+        let mut req = req.as_web_req();
+
+        let res = JsonLines::<Item>::from_request(&req).now_or_panic();
+        assert!(matches!(res, Err(ExtractError::HeaderNotFound(_))));
+
+        for value in [
+            "application/x-ndjson",
+            "application/jsonl",
+            "Application/X-NDJSON; charset=utf-8",
+        ] {
+            req.req_mut()
+                .headers_mut()
+                .insert(CONTENT_TYPE, HeaderValue::from_static(value));
+            assert!(JsonLines::<Item>::from_request(&req).now_or_panic().is_ok());
+        }
+
+        for value in ["application/json", "text/plain", "application/x-ndjsonx"] {
+            req.req_mut()
+                .headers_mut()
+                .insert(CONTENT_TYPE, HeaderValue::from_static(value));
+            let res = JsonLines::<Item>::from_request(&req).now_or_panic();
+            assert!(matches!(res, Err(ExtractError::UnsupportedMediaType)));
+        }
+    }
+
+    #[test]
+    fn respond_stream() {
+        const COUNT: u32 = 10_000;
+
+        let mut req = WebRequest::new_test(());
+        let items = stream::iter(0..COUNT).map(|id| Ok::<_, Infallible>(serde_json::json!({ "id": id })));
+        let res = JsonLinesStream(items).respond_to(req.as_web_req()).now_or_panic();
+
+        assert_eq!(res.headers().get(CONTENT_TYPE).unwrap(), NDJSON);
+
+        let mut body = res.into_body();
+        assert_eq!(body.size_hint(), (0, None));
+
+        // every chunk is exactly one serialized item so memory usage does not grow with stream.
+        let mut id = 0;
+        while let Some(chunk) = body.next().now_or_panic() {
+            assert_eq!(chunk.unwrap(), format!("{{\"id\":{id}}}\n").as_bytes());
+            id += 1;
+        }
+        assert_eq!(id, COUNT);
+    }
+}
@@ -0,0 +1,469 @@
+//! incremental json array type extractor.
+
+use core::{
+    fmt,
+    future::Future,
+    marker::PhantomData,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use std::error;
+
+use futures_core::stream::Stream;
+use serde::de::DeserializeOwned;
+
+use crate::{
+    body::{BodyStream, RequestBody},
+    dev::bytes::{Buf, BytesMut},
+    handler::{error::ExtractError, FromRequest},
+    request::WebRequest,
+};
+
+use super::header::{self, HeaderRef};
+
+const DEFAULT_LIMIT: usize = 64 * 1024;
+
+const DEFAULT_MAX_BODY: usize = 16 * 1024 * 1024;
+
+// the same as default recursion limit of serde_json.
+const DEFAULT_DEPTH: usize = 128;
+
+/// Extract type for streaming json array. It's a [Stream] that parse request body incrementally
+/// and deserialize every element of top level json array to type T as soon as the element is
+/// received. The whole array is never buffered and parsing overlaps with reading request body.
+///
+/// Only top level array is streamed. Each element is buffered until it's complete and then
+/// deserialized as a whole. Request body of other json value is rejected with
+/// [JsonStreamError::Syntax] and should be extracted with [Json](super::json::Json) instead.
+///
+/// const generic param LIMIT is for max size of a single element in bytes. Default limit is
+/// [DEFAULT_LIMIT] in bytes. See [JsonStream::max_body] and [JsonStream::max_depth] for other
+/// limits.
+///
+/// Element failed to deserialize is yielded as error item and the stream continue with next
+/// element. Other errors end the stream.
+///
+/// # Examples:
+/// ```rust
+/// # use xitca_web::{handler::{handler_service, json_stream::JsonStream}, route::post, App};
+/// use core::pin::pin;
+/// use futures_util::StreamExt;
+///
+/// #[derive(serde::Deserialize)]
+/// struct Item {
+///     id: u64,
+/// }
+///
+/// // sum of ids from a request body like `[{"id":1},{"id":2}]`
+/// async fn handler(stream: JsonStream<Item>) -> String {
+///     let mut stream = pin!(stream.max_body(64 * 1024 * 1024));
+///     let mut sum = 0;
+///     while let Some(Ok(item)) = stream.next().await {
+///         sum += item.id;
+///     }
+///     sum.to_string()
+/// }
+///
+/// App::new().at("/", post(handler_service(handler)));
+/// ```
+pub struct JsonStream<T, const LIMIT: usize = DEFAULT_LIMIT, B = RequestBody> {
+    body: Pin<Box<B>>,
+    // bytes of current element and bytes received after it.
+    buf: BytesMut,
+    state: State,
+    // scanned length of buf.
+    pos: usize,
+    depth: usize,
+    in_str: bool,
+    escape: bool,
+    read: usize,
+    max_body: usize,
+    max_depth: usize,
+    eof: bool,
+    _item: PhantomData<fn() -> T>,
+}
+
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum State {
+    // before top level `[`
+    Start,
+    // before an element. bool is true for first element where `]` is allowed.
+    BeforeElement(bool),
+    Element,
+    // after an element where `,` or `]` is expected.
+    AfterElement,
+    // after top level `]`
+    End,
+    Done,
+}
+
+impl<T, const LIMIT: usize, B> JsonStream<T, LIMIT, B> {
+    pub(crate) fn new(body: B) -> Self {
+        Self {
+            body: Box::pin(body),
+            buf: BytesMut::new(),
+            state: State::Start,
+            pos: 0,
+            depth: 0,
+            in_str: false,
+            escape: false,
+            read: 0,
+            max_body: DEFAULT_MAX_BODY,
+            max_depth: DEFAULT_DEPTH,
+            eof: false,
+            _item: PhantomData,
+        }
+    }
+
+    /// Set max size of request body in bytes. Stream ends with [JsonStreamError::Overflow] when
+    /// the body exceeds it. Default to 16MiB.
+    pub fn max_body(mut self, max: usize) -> Self {
+        self.max_body = max;
+        self
+    }
+
+    /// Set max nesting depth of element. Stream ends with [JsonStreamError::Depth] when an element
+    /// is nested deeper than it. Default to 128.
+    pub fn max_depth(mut self, max: usize) -> Self {
+        self.max_depth = max;
+        self
+    }
+
+    // scan buffered bytes and return length of the element when it's complete.
+    fn scan(&mut self) -> Result<Option<usize>, Malformed> {
+        while self.pos < self.buf.len() {
+            let b = self.buf[self.pos];
+
+            if self.state == State::Element {
+                if self.in_str {
+                    match b {
+                        _ if self.escape => self.escape = false,
+                        b'\\' => self.escape = true,
+                        b'"' => self.in_str = false,
+                        _ => {}
+                    }
+                } else {
+                    match b {
+                        b'"' => self.in_str = true,
+                        b'{' | b'[' => {
+                            self.depth += 1;
+                            if self.depth > self.max_depth {
+                                return Err(Malformed::Depth);
+                            }
+                        }
+                        b'}' | b']' if self.depth > 0 => {
+                            self.depth -= 1;
+                            if self.depth == 0 {
+                                self.state = State::AfterElement;
+                                return Ok(Some(self.pos + 1));
+                            }
+                        }
+                        // end of scalar element.
+                        b',' | b']' if self.depth == 0 => {
+                            self.state = State::AfterElement;
+                            return Ok(Some(self.pos));
+                        }
+                        _ => {}
+                    }
+                }
+                self.pos += 1;
+                continue;
+            }
+
+            // outside of element scanned bytes are dropped and pos stays at 0.
+            match self.state {
+                _ if b.is_ascii_whitespace() => {}
+                State::Start if b == b'[' => self.state = State::BeforeElement(true),
+                State::BeforeElement(true) | State::AfterElement if b == b']' => self.state = State::End,
+                State::BeforeElement(_) if b != b',' && b != b']' => {
+                    self.state = State::Element;
+                    continue;
+                }
+                State::AfterElement if b == b',' => self.state = State::BeforeElement(false),
+                _ => return Err(Malformed::Syntax),
+            }
+            self.buf.advance(1);
+        }
+
+        Ok(None)
+    }
+}
+
+enum Malformed {
+    Syntax,
+    Depth,
+}
+
+impl<T, const LIMIT: usize, B> fmt::Debug for JsonStream<T, LIMIT, B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("JsonStream")
+            .field("limit", &LIMIT)
+            .field("max_body", &self.max_body)
+            .field("max_depth", &self.max_depth)
+            .finish()
+    }
+}
+
+impl<T, const LIMIT: usize, B> Stream for JsonStream<T, LIMIT, B>
+where
+    B: BodyStream,
+    T: DeserializeOwned,
+{
+    type Item = Result<T, JsonStreamError<B::Error>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        loop {
+            if this.state == State::Done {
+                return Poll::Ready(None);
+            }
+
+            let err = match this.scan() {
+                // element arrived in one chunk is checked against limit before deserializing.
+                Ok(Some(len)) if len > LIMIT => JsonStreamError::Overflow,
+                Ok(Some(len)) => {
+                    let element = this.buf.split_to(len);
+                    this.pos = 0;
+                    let res = serde_json::from_slice(&element).map_err(JsonStreamError::Parse);
+                    return Poll::Ready(Some(res));
+                }
+                Ok(None) if this.state == State::Element && this.buf.len() > LIMIT => JsonStreamError::Overflow,
+                Ok(None) if this.eof => match this.state {
+                    State::End => {
+                        this.state = State::Done;
+                        return Poll::Ready(None);
+                    }
+                    _ => JsonStreamError::Syntax,
+                },
+                Ok(None) => match this.body.as_mut().poll_next(cx) {
+                    Poll::Ready(Some(Ok(chunk))) => {
+                        let chunk = chunk.as_ref();
+                        this.read += chunk.len();
+                        if this.read > this.max_body {
+                            JsonStreamError::Overflow
+                        } else {
+                            this.buf.extend_from_slice(chunk);
+                            continue;
+                        }
+                    }
+                    Poll::Ready(Some(Err(e))) => JsonStreamError::Body(e),
+                    Poll::Ready(None) => {
+                        this.eof = true;
+                        continue;
+                    }
+                    Poll::Pending => return Poll::Pending,
+                },
+                Err(Malformed::Syntax) => JsonStreamError::Syntax,
+                Err(Malformed::Depth) => JsonStreamError::Depth,
+            };
+
+            this.state = State::Done;
+            this.buf = BytesMut::new();
+            return Poll::Ready(Some(Err(err)));
+        }
+    }
+}
+
+impl<'a, 'r, C, B, T, const LIMIT: usize> FromRequest<'a, WebRequest<'r, C, B>> for JsonStream<T, LIMIT, B>
+where
+    B: BodyStream + Default,
+{
+    type Type<'b> = JsonStream<T, LIMIT, B>;
+    type Error = ExtractError<B::Error>;
+    type Future = impl Future<Output = Result<Self, Self::Error>> where WebRequest<'r, C, B>: 'a;
+
+    fn from_request(req: &'a WebRequest<'r, C, B>) -> Self::Future {
+        async move {
+            HeaderRef::<'a, { header::CONTENT_TYPE }>::from_request(req).await?;
+            Ok(JsonStream::new(req.take_body_ref()))
+        }
+    }
+}
+
+/// Error type of [JsonStream] stream item.
+#[derive(Debug)]
+pub enum JsonStreamError<E> {
+    /// Request body error.
+    Body(E),
+    /// An element or request body exceeds the size limit.
+    Overflow,
+    /// An element is nested deeper than the depth limit.
+    Depth,
+    /// Request body is not a well formed json array.
+    Syntax,
+    /// An element failed to deserialize. The stream continue with next element after it.
+    Parse(serde_json::Error),
+}
+
+impl<E: fmt::Display> fmt::Display for JsonStreamError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Body(ref e) => fmt::Display::fmt(e, f),
+            Self::Overflow => f.write_str("json stream exceeds size limit"),
+            Self::Depth => f.write_str("json stream exceeds depth limit"),
+            Self::Syntax => f.write_str("json stream is not a well formed array"),
+            Self::Parse(ref e) => fmt::Display::fmt(e, f),
+        }
+    }
+}
+
+impl<E> error::Error for JsonStreamError<E> where E: fmt::Debug + fmt::Display {}
+
+#[cfg(test)]
+mod test {
+    use core::{cell::Cell, convert::Infallible};
+
+    use std::rc::Rc;
+
+    use futures_util::stream::{self, StreamExt};
+    use serde::Deserialize;
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::dev::bytes::Bytes;
+
+    use super::*;
+
+    #[derive(Debug, Deserialize, Eq, PartialEq)]
+    struct Item {
+        id: u32,
+    }
+
+    fn body(chunks: &[&'static str]) -> impl Stream<Item = Result<Bytes, Infallible>> {
+        stream::iter(chunks.to_vec()).map(|chunk| Ok(Bytes::from_static(chunk.as_bytes())))
+    }
+
+    fn collect<T, const LIMIT: usize>(stream: JsonStream<T, LIMIT, impl BodyStream>) -> Vec<Result<T, String>>
+    where
+        T: DeserializeOwned,
+    {
+        stream
+            .map(|res| res.map_err(|e| e.to_string()))
+            .collect::<Vec<_>>()
+            .now_or_panic()
+    }
+
+    #[test]
+    fn split_across_chunks() {
+        let chunks = [" [ {\"i", "d\":1} ,{\"id\"", ":2}\n,", "{\"id\":3", "}]  "];
+        let items = JsonStream::<Item, DEFAULT_LIMIT, _>::new(body(&chunks))
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+            .now_or_panic();
+        assert_eq!(items, vec![Item { id: 1 }, Item { id: 2 }, Item { id: 3 }]);
+
+        let chunks = ["[\"a,]\\", "\"\", 1, [2, {\"b\": \"]\"}], null ,true]"];
+        let items = JsonStream::<serde_json::Value, DEFAULT_LIMIT, _>::new(body(&chunks))
+            .map(Result::unwrap)
+            .collect::<Vec<_>>()
+            .now_or_panic();
+        assert_eq!(
+            items,
+            vec![
+                serde_json::json!("a,]\""),
+                serde_json::json!(1),
+                serde_json::json!([2, { "b": "]" }]),
+                serde_json::json!(null),
+                serde_json::json!(true),
+            ]
+        );
+
+        let items = collect(JsonStream::<Item, DEFAULT_LIMIT, _>::new(body(&["[", " ]"])));
+        assert!(items.is_empty());
+    }
+
+    #[test]
+    fn malformed() {
+        let items = collect(JsonStream::<Item, DEFAULT_LIMIT, _>::new(body(&[
+            "[{\"id\":1},{\"id\":\"2\"},{\"id\":3}]",
+        ])));
+        assert_eq!(items.len(), 3);
+        assert!(items[1].is_err());
+        assert_eq!(items[2], Ok(Item { id: 3 }));
+
+        for chunks in [
+            &["{\"id\":1}"][..],
+            &["[{\"id\":1},]"],
+            &["[{\"id\":1}"],
+            &["[{\"id\":1}] x"],
+            &[""],
+        ] {
+            let items = collect(JsonStream::<Item, DEFAULT_LIMIT, _>::new(body(chunks)));
+            assert_eq!(
+                items.last().unwrap().as_ref().unwrap_err(),
+                "json stream is not a well formed array"
+            );
+        }
+    }
+
+    #[test]
+    fn limits() {
+        let chunks = ["[{\"id\":1},{\"id\":", "1234567", "8901},{\"id\":3}]"];
+        let items = collect(JsonStream::<Item, 12, _>::new(body(&chunks)));
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[1].as_ref().unwrap_err(), "json stream exceeds size limit");
+
+        let items = collect(JsonStream::<Item, DEFAULT_LIMIT, _>::new(body(&chunks)).max_body(20));
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[1].as_ref().unwrap_err(), "json stream exceeds size limit");
+
+        // oversized element received in a single chunk.
+        let chunks = ["[{\"id\":1},{\"id\":12345678901},{\"id\":3}]"];
+        let items = collect(JsonStream::<Item, 12, _>::new(body(&chunks)));
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0], Ok(Item { id: 1 }));
+        assert_eq!(items[1].as_ref().unwrap_err(), "json stream exceeds size limit");
+
+        // body size is limited by default.
+        let stream = JsonStream::<Item, DEFAULT_LIMIT, _>::new(body(&[]));
+        assert_eq!(stream.max_body, DEFAULT_MAX_BODY);
+
+        let chunks = ["[[1],[[2]],[[[3]]]]"];
+        let items = collect(JsonStream::<serde_json::Value, DEFAULT_LIMIT, _>::new(body(&chunks)).max_depth(2));
+        assert_eq!(items.len(), 3);
+        assert_eq!(items[2].as_ref().unwrap_err(), "json stream exceeds depth limit");
+    }
+
+    #[test]
+    fn large_array() {
+        const COUNT: u32 = 100_000;
+        const BATCH: u32 = 100;
+
+        // paced body producing a batch of elements for every chunk.
+        let sent = Rc::new(Cell::new(0));
+        let body = {
+            let sent = sent.clone();
+            stream::iter(0..COUNT / BATCH).map(move |batch| {
+                sent.set(sent.get() + 1);
+                let mut chunk = String::new();
+                if batch == 0 {
+                    chunk.push('[');
+                }
+                for id in batch * BATCH..(batch + 1) * BATCH {
+                    if id > 0 {
+                        chunk.push(',');
+                    }
+                    chunk.push_str(&format!("{{\"id\":{id}}}"));
+                }
+                if batch == COUNT / BATCH - 1 {
+                    chunk.push(']');
+                }
+                Ok::<_, Infallible>(Bytes::from(chunk))
+            })
+        };
+
+        let mut stream = JsonStream::<Item, DEFAULT_LIMIT, _>::new(body);
+
+        let mut id = 0;
+        while let Some(item) = stream.next().now_or_panic() {
+            assert_eq!(item.unwrap(), Item { id });
+            // item is yielded as soon as it's received.
+            assert_eq!(sent.get(), id / BATCH + 1);
+            // only current chunk is buffered.
+            assert!(stream.buf.capacity() < 4096);
+            id += 1;
+        }
+        assert_eq!(id, COUNT);
+    }
+}
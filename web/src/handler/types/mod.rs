@@ -2,13 +2,31 @@ pub mod body;
 pub mod extension;
 pub mod header;
 pub mod html;
+pub mod negotiate;
 pub mod path;
+pub mod range;
+pub mod redirect;
 pub mod request;
 pub mod state;
 pub mod string;
 pub mod uri;
 pub mod vec;
 
+#[cfg(any(feature = "archive-zip", feature = "archive-tar"))]
+pub mod archive;
+
+#[cfg(feature = "auth")]
+pub mod auth;
+
+#[cfg(feature = "client-cert")]
+pub mod client_cert;
+
+#[cfg(feature = "config")]
+pub mod config;
+
+#[cfg(feature = "timeout")]
+pub mod deadline;
+
 #[cfg(feature = "params")]
 pub mod params;
 
@@ -18,8 +36,20 @@ pub mod query;
 #[cfg(feature = "json")]
 pub mod json;
 
+#[cfg(feature = "json")]
+pub mod json_lines;
+
+#[cfg(feature = "json")]
+pub mod json_stream;
+
 #[cfg(feature = "multipart")]
 pub mod multipart;
 
+#[cfg(feature = "precondition")]
+pub mod precondition;
+
+#[cfg(feature = "session")]
+pub mod session;
+
 #[cfg(feature = "websocket")]
 pub mod websocket;
@@ -0,0 +1,454 @@
+//! content negotiation with `Accept` header.
+
+use core::{fmt, future::Future, marker::PhantomData};
+
+use std::error;
+
+use crate::{
+    body::BodyStream,
+    dev::bytes::Bytes,
+    handler::{error::ExtractError, FromRequest, Responder},
+    http::{
+        const_header_value::TEXT_UTF8,
+        header::{HeaderMap, HeaderValue, ACCEPT, CONTENT_TYPE, VARY},
+        StatusCode,
+    },
+    request::WebRequest,
+    response::{ResponseHeadersExt, WebResponse},
+};
+
+/// Media type of response body in the form of `type/subtype`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub struct MediaType(&'static str);
+
+impl MediaType {
+    pub const JSON: Self = Self("application/json");
+    pub const HTML: Self = Self("text/html");
+    pub const TEXT: Self = Self("text/plain");
+    pub const XML: Self = Self("application/xml");
+    pub const PROTOBUF: Self = Self("application/x-protobuf");
+
+    /// Construct media type from lower case `type/subtype` string without parameter.
+    pub const fn new(media_type: &'static str) -> Self {
+        Self(media_type)
+    }
+
+    pub const fn as_str(&self) -> &'static str {
+        self.0
+    }
+
+    fn split(&self) -> (&'static str, &'static str) {
+        self.0.split_once('/').unwrap_or((self.0, ""))
+    }
+}
+
+impl fmt::Display for MediaType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+/// Helper type for choosing media type of response from supported ones of route according to
+/// `Accept` header of request.
+///
+/// Media range in `Accept` header applies to a supported type when it's `*/*`, `type/*` or the
+/// exact type and the most specific one among them decides the quality of supported type. Type
+/// with the highest non zero quality is chosen and declaration order breaks the tie. Request
+/// without `Accept` header accepts any type.
+///
+/// # Examples:
+/// ```rust
+/// # use xitca_web::{handler::negotiate::{MediaType, Negotiate}, http::{header::{HeaderMap, HeaderValue, ACCEPT}}};
+/// const NEGOTIATE: Negotiate = Negotiate::new(&[MediaType::HTML, MediaType::JSON]);
+///
+/// let mut headers = HeaderMap::new();
+/// headers.insert(ACCEPT, HeaderValue::from_static("*/*;q=0.1, application/json"));
+/// assert_eq!(NEGOTIATE.negotiate(&headers).unwrap().media_type(), MediaType::JSON);
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Negotiate {
+    supported: &'static [MediaType],
+    fallback: bool,
+}
+
+impl Negotiate {
+    /// Construct with supported media types in preferred order.
+    ///
+    /// # Panics:
+    ///
+    /// When no media type is supported.
+    pub const fn new(supported: &'static [MediaType]) -> Self {
+        assert!(!supported.is_empty(), "Negotiate must support at least one media type");
+        Self {
+            supported,
+            fallback: false,
+        }
+    }
+
+    /// Fall back to the first supported media type instead of failing with [NotAcceptable] when
+    /// none of them is accepted.
+    pub const fn fallback(mut self) -> Self {
+        self.fallback = true;
+        self
+    }
+
+    /// Choose media type from `Accept` header of given request headers.
+    pub fn negotiate(&self, headers: &HeaderMap) -> Result<Negotiated, NotAcceptable> {
+        let ranges = headers
+            .get_all(ACCEPT)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .filter_map(MediaRange::parse)
+            .collect::<Vec<_>>();
+
+        if ranges.is_empty() {
+            return Ok(Negotiated::new(self.supported[0]));
+        }
+
+        let mut chosen = None;
+
+        for media_type in self.supported {
+            let quality = ranges
+                .iter()
+                .filter_map(|range| range.specificity(media_type).map(|s| (s, range.quality)))
+                // the most specific range wins. duplicate ranges take the highest quality.
+                .max()
+                .map_or(0, |(_, quality)| quality);
+
+            if quality > chosen.map_or(0, |(_, q)| q) {
+                chosen = Some((*media_type, quality));
+            }
+        }
+
+        match chosen {
+            Some((media_type, _)) => Ok(Negotiated::new(media_type)),
+            None if self.fallback => Ok(Negotiated::new(self.supported[0])),
+            None => Err(NotAcceptable {
+                supported: self.supported,
+            }),
+        }
+    }
+}
+
+// media range of Accept header with quality in thousandths.
+struct MediaRange<'a> {
+    ty: &'a str,
+    subtype: &'a str,
+    quality: u16,
+}
+
+impl<'a> MediaRange<'a> {
+    fn parse(range: &'a str) -> Option<Self> {
+        let mut params = range.split(';');
+        let (ty, subtype) = params.next()?.trim().split_once('/')?;
+        let (ty, subtype) = (ty.trim(), subtype.trim());
+        if ty.is_empty() || subtype.is_empty() || (ty == "*" && subtype != "*") {
+            return None;
+        }
+
+        let mut quality = 1000;
+        for param in params {
+            if let Some((name, value)) = param.split_once('=') {
+                if name.trim().eq_ignore_ascii_case("q") {
+                    // invalid quality makes the whole range ignored.
+                    quality = parse_quality(value.trim())?;
+                }
+            }
+        }
+
+        Some(Self { ty, subtype, quality })
+    }
+
+    // specificity of range when it applies to given media type.
+    fn specificity(&self, media_type: &MediaType) -> Option<u8> {
+        let (ty, subtype) = media_type.split();
+        if self.ty == "*" {
+            Some(0)
+        } else if !self.ty.eq_ignore_ascii_case(ty) {
+            None
+        } else if self.subtype == "*" {
+            Some(1)
+        } else if self.subtype.eq_ignore_ascii_case(subtype) {
+            Some(2)
+        } else {
+            None
+        }
+    }
+}
+
+// qvalue is 0 to 1 with at most 3 decimal digits.
+fn parse_quality(value: &str) -> Option<u16> {
+    let (int, frac) = value.split_once('.').unwrap_or((value, ""));
+    if frac.len() > 3 || !frac.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let int = match int {
+        "0" => 0,
+        "1" => 1000,
+        _ => return None,
+    };
+    let frac = frac.bytes().chain(core::iter::repeat(b'0')).take(3);
+    let frac = frac.fold(0, |n, b| n * 10 + u16::from(b - b'0'));
+    let quality = int + frac;
+    (quality <= 1000).then_some(quality)
+}
+
+/// Trait for declaring supported media types of route and using [Negotiated] as extractor.
+///
+/// # Examples:
+/// ```rust
+/// # use xitca_web::{handler::{handler_service, negotiate::{MediaType, Negotiate, Negotiated, Supported}}, request::WebRequest, App};
+/// struct Page;
+///
+/// impl Supported for Page {
+///     const NEGOTIATE: Negotiate = Negotiate::new(&[MediaType::HTML, MediaType::JSON]);
+/// }
+///
+/// // request not accepting html nor json is responded with 406 Not Acceptable.
+/// async fn handler(negotiated: Negotiated<Page>, _: &WebRequest<'_>) -> (Negotiated<Page>, String) {
+///     let body = match negotiated.media_type() {
+///         MediaType::JSON => String::from("{\"hello\":\"world\"}"),
+///         _ => String::from("<h1>hello,world</h1>"),
+///     };
+///     // Content-Type and Vary headers are set according to negotiated media type.
+///     (negotiated, body)
+/// }
+///
+/// App::new().at("/", handler_service(handler));
+/// ```
+pub trait Supported {
+    const NEGOTIATE: Negotiate;
+}
+
+/// Media type chosen by [Negotiate].
+///
+/// It's an extractor when `T` implements [Supported]. Responding with `(Negotiated, impl Responder)`
+/// tuple sets `Content-Type` header to the negotiated media type and adds `Accept` to `Vary` header.
+pub struct Negotiated<T = ()> {
+    media_type: MediaType,
+    _supported: PhantomData<fn() -> T>,
+}
+
+impl<T> Clone for Negotiated<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Negotiated<T> {}
+
+impl<T> fmt::Debug for Negotiated<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Negotiated").field(&self.media_type).finish()
+    }
+}
+
+impl<T> Negotiated<T> {
+    fn new(media_type: MediaType) -> Self {
+        Self {
+            media_type,
+            _supported: PhantomData,
+        }
+    }
+
+    pub fn media_type(&self) -> MediaType {
+        self.media_type
+    }
+}
+
+impl<'a, 'r, C, B, T> FromRequest<'a, WebRequest<'r, C, B>> for Negotiated<T>
+where
+    B: BodyStream,
+    T: Supported,
+{
+    type Type<'b> = Negotiated<T>;
+    type Error = ExtractError<B::Error>;
+    type Future = impl Future<Output = Result<Self, Self::Error>> where WebRequest<'r, C, B>: 'a;
+
+    #[inline]
+    fn from_request(req: &'a WebRequest<'r, C, B>) -> Self::Future {
+        let res = T::NEGOTIATE
+            .negotiate(req.req().headers())
+            .map(|n| Negotiated::new(n.media_type))
+            .map_err(ExtractError::NotAcceptable);
+        async { res }
+    }
+}
+
+impl<'r, C, B, T, R> Responder<WebRequest<'r, C, B>> for (Negotiated<T>, R)
+where
+    R: Responder<WebRequest<'r, C, B>, Output = WebResponse>,
+{
+    type Output = WebResponse;
+    type Future = impl Future<Output = Self::Output>;
+
+    fn respond_to(self, req: WebRequest<'r, C, B>) -> Self::Future {
+        let (negotiated, res) = self;
+        async move {
+            let mut res = res.respond_to(req).await;
+            let media_type = negotiated.media_type.as_str();
+
+            // keep parameters like charset set by inner responder.
+            let matched = res
+                .headers()
+                .get(CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.split(';').next())
+                .is_some_and(|v| v.trim().eq_ignore_ascii_case(media_type));
+            if !matched {
+                res.headers_mut()
+                    .insert(CONTENT_TYPE, HeaderValue::from_static(media_type));
+            }
+
+            res.append_or_merge(VARY, HeaderValue::from_static("accept"));
+            res
+        }
+    }
+}
+
+/// Error type when none of supported media types is accepted by request.
+///
+/// Responded with `406 Not Acceptable` and a body listing supported media types.
+#[derive(Debug)]
+pub struct NotAcceptable {
+    supported: &'static [MediaType],
+}
+
+impl NotAcceptable {
+    /// Media types supported by route.
+    pub fn supported(&self) -> &'static [MediaType] {
+        self.supported
+    }
+
+    pub(crate) fn to_response<C, B>(&self, req: WebRequest<'_, C, B>) -> WebResponse {
+        let body = self.to_string();
+        let mut res = req.into_response(Bytes::from(body));
+        *res.status_mut() = StatusCode::NOT_ACCEPTABLE;
+        res.headers_mut().insert(CONTENT_TYPE, TEXT_UTF8);
+        res.append_or_merge(VARY, HeaderValue::from_static("accept"));
+        res
+    }
+}
+
+impl fmt::Display for NotAcceptable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("not acceptable. supported media types: ")?;
+        for (i, media_type) in self.supported.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            f.write_str(media_type.as_str())?;
+        }
+        Ok(())
+    }
+}
+
+impl error::Error for NotAcceptable {}
+
+impl<'r, C, B> Responder<WebRequest<'r, C, B>> for NotAcceptable {
+    type Output = WebResponse;
+    type Future = impl Future<Output = Self::Output>;
+
+    fn respond_to(self, req: WebRequest<'r, C, B>) -> Self::Future {
+        let res = self.to_response(req);
+        async { res }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::{handler::handler_service, test::TestRequest, App};
+
+    use super::*;
+
+    struct Api;
+
+    impl Supported for Api {
+        const NEGOTIATE: Negotiate = Negotiate::new(&[MediaType::HTML, MediaType::JSON]);
+    }
+
+    async fn handler(negotiated: Negotiated<Api>) -> (Negotiated<Api>, &'static str) {
+        match negotiated.media_type() {
+            MediaType::JSON => (negotiated, "{}"),
+            _ => (negotiated, "<p></p>"),
+        }
+    }
+
+    fn negotiate(negotiate: Negotiate, accept: &'static str) -> Result<MediaType, NotAcceptable> {
+        let mut headers = HeaderMap::new();
+        headers.insert(ACCEPT, HeaderValue::from_static(accept));
+        negotiate.negotiate(&headers).map(|n| n.media_type())
+    }
+
+    #[test]
+    fn precedence() {
+        let n = Api::NEGOTIATE;
+        assert_eq!(negotiate(n, "*/*;q=0.1, application/json").unwrap(), MediaType::JSON);
+        // declaration order breaks the tie.
+        assert_eq!(negotiate(n, "application/json, text/html").unwrap(), MediaType::HTML);
+        // the most specific range decides quality.
+        assert_eq!(
+            negotiate(n, "text/*, text/html;q=0.2, */*;q=0.5").unwrap(),
+            MediaType::JSON
+        );
+        assert_eq!(
+            negotiate(n, "text/*;q=0.9, application/*;q=0.8").unwrap(),
+            MediaType::HTML
+        );
+        assert_eq!(
+            negotiate(n, "TEXT/HTML;q=0.500, application/json;q=0.4").unwrap(),
+            MediaType::HTML
+        );
+        // invalid range is ignored.
+        assert_eq!(
+            negotiate(n, "text/html;q=2, application/json;q=0.1").unwrap(),
+            MediaType::JSON
+        );
+
+        assert!(negotiate(n, "image/png, text/html;q=0").is_err());
+        assert_eq!(negotiate(n.fallback(), "image/png").unwrap(), MediaType::HTML);
+    }
+
+    #[test]
+    fn quality() {
+        assert_eq!(parse_quality("1"), Some(1000));
+        assert_eq!(parse_quality("1.000"), Some(1000));
+        assert_eq!(parse_quality("0.05"), Some(50));
+        assert_eq!(parse_quality("0."), Some(0));
+        assert_eq!(parse_quality("1.001"), None);
+        assert_eq!(parse_quality("0.0001"), None);
+        assert_eq!(parse_quality("-1"), None);
+    }
+
+    #[test]
+    fn extract_and_respond() {
+        let service = App::new()
+            .at("/", handler_service(handler))
+            .finish_for_test()
+            .now_or_panic();
+
+        let req = TestRequest::get("/").header(ACCEPT, "*/*;q=0.1, application/json");
+        let res = service.call(req).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK)
+            .assert_header(CONTENT_TYPE, "application/json")
+            .assert_header(VARY, "accept");
+
+        // missing Accept header is treated as */*.
+        let res = service.call(TestRequest::get("/")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK)
+            .assert_header(CONTENT_TYPE, "text/html");
+        assert_eq!(res.string_body().now_or_panic().unwrap(), "<p></p>");
+
+        let req = TestRequest::get("/").header(ACCEPT, "image/png");
+        let res = service.call(req).now_or_panic().unwrap();
+        res.assert_status(StatusCode::NOT_ACCEPTABLE)
+            .assert_header(VARY, "accept");
+        assert_eq!(
+            res.string_body().now_or_panic().unwrap(),
+            "not acceptable. supported media types: text/html, application/json"
+        );
+    }
+}
@@ -1,14 +1,14 @@
-use std::{future::Future, ops::Deref};
+use std::{borrow::Cow, future::Future, ops::Deref, str::FromStr};
 
 use serde::de::{self, Deserializer, Error as DeError, Visitor};
 use serde::{forward_to_deserialize_any, Deserialize};
 
-use xitca_http::util::service::router;
+use xitca_http::util::{percent, scratch::Scratch, service::router};
 
 use crate::{
     body::BodyStream,
     handler::{
-        error::{ExtractError, _ParseError},
+        error::{_ParseError, ExtractError},
         FromRequest,
     },
     request::WebRequest,
@@ -29,8 +29,9 @@ where
     #[inline]
     fn from_request(req: &'a WebRequest<'r, C, B>) -> Self::Future {
         async {
-            let params = req.req().body().params();
-            let params = T::deserialize(Params2::new(params)).map_err(_ParseError::Params)?;
+            let req = req.req();
+            let params = Params2::with_scratch(req.body().params(), req.extensions().get());
+            let params = T::deserialize(params).map_err(_ParseError::Params)?;
 
             Ok(Params(params))
         }
@@ -86,10 +87,7 @@ macro_rules! parse_single_value {
                 )));
             }
 
-            let param = self.params.iter().next().unwrap().1;
-            let v = param
-                .parse()
-                .map_err(|_| de::value::Error::custom(format!("can not parse {param:?} to a {}", $tp)))?;
+            let v = parse(self.params.iter().next().unwrap().1, self.scratch, $tp)?;
             visitor.$visit_fn(v)
         }
     };
@@ -97,12 +95,20 @@ macro_rules! parse_single_value {
 
 pub struct Params2<'de> {
     params: &'de router::Params,
+    scratch: Option<&'de Scratch>,
 }
 
 impl<'a> Params2<'a> {
     #[inline]
     pub fn new(params: &'a router::Params) -> Self {
-        Params2 { params }
+        Self::with_scratch(params, None)
+    }
+
+    /// Construct with optional [Scratch] arena. Percent-decoded values that are parsed into other
+    /// types are stored in the arena instead of heap allocated strings.
+    #[inline]
+    pub fn with_scratch(params: &'a router::Params, scratch: Option<&'a Scratch>) -> Self {
+        Params2 { params, scratch }
     }
 }
 
@@ -114,7 +120,7 @@ impl<'de> Deserializer<'de> for Params2<'de> {
         V: Visitor<'de>,
     {
         match self.params.iter().next() {
-            Some((_, v)) => visitor.visit_borrowed_str(v),
+            Some((_, v)) => visit_str(v, visitor),
             None => Err(de::value::Error::custom("expected at least one parameters")),
         }
     }
@@ -140,12 +146,17 @@ impl<'de> Deserializer<'de> for Params2<'de> {
         visitor.visit_newtype_struct(self)
     }
 
+    // segments of repeated parameter are flattened into the sequence.
     fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
         visitor.visit_seq(SeqAccess {
-            params: self.params.iter(),
+            params: self
+                .params
+                .iter()
+                .flat_map(|(key, value)| segments(value).map(move |value| (key, value))),
+            scratch: self.scratch,
         })
     }
 
@@ -160,6 +171,7 @@ impl<'de> Deserializer<'de> for Params2<'de> {
         } else {
             visitor.visit_seq(SeqAccess {
                 params: self.params.iter(),
+                scratch: self.scratch,
             })
         }
     }
@@ -175,6 +187,7 @@ impl<'de> Deserializer<'de> for Params2<'de> {
         } else {
             visitor.visit_seq(SeqAccess {
                 params: self.params.iter(),
+                scratch: self.scratch,
             })
         }
     }
@@ -186,6 +199,7 @@ impl<'de> Deserializer<'de> for Params2<'de> {
         visitor.visit_map(MapAccess {
             params: self.params.iter(),
             current: None,
+            scratch: self.scratch,
         })
     }
 
@@ -241,6 +255,7 @@ impl<'de> Deserializer<'de> for Params2<'de> {
 struct MapAccess<'de, I> {
     params: I,
     current: Option<(&'de str, &'de str)>,
+    scratch: Option<&'de Scratch>,
 }
 
 impl<'de, I> de::MapAccess<'de> for MapAccess<'de, I>
@@ -265,7 +280,10 @@ where
         V: de::DeserializeSeed<'de>,
     {
         if let Some((_, value)) = self.current.take() {
-            seed.deserialize(Value { value })
+            seed.deserialize(Value {
+                value,
+                scratch: self.scratch,
+            })
         } else {
             Err(de::value::Error::custom("unexpected item"))
         }
@@ -306,10 +324,7 @@ macro_rules! parse_value {
         where
             V: Visitor<'de>,
         {
-            let v = self
-                .value
-                .parse()
-                .map_err(|_| de::value::Error::custom(format!("can not parse {:?} to a {}", self.value, $tp)))?;
+            let v = parse(self.value, self.scratch, $tp)?;
             visitor.$visit_fn(v)
         }
     };
@@ -317,6 +332,7 @@ macro_rules! parse_value {
 
 struct Value<'de> {
     value: &'de str,
+    scratch: Option<&'de Scratch>,
 }
 
 impl<'de> Deserializer<'de> for Value<'de> {
@@ -341,14 +357,17 @@ impl<'de> Deserializer<'de> for Value<'de> {
     where
         V: Visitor<'de>,
     {
-        visitor.visit_borrowed_str(self.value)
+        visit_str(self.value, visitor)
     }
 
     fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value, Self::Error>
     where
         V: Visitor<'de>,
     {
-        visitor.visit_borrowed_bytes(self.value.as_bytes())
+        match percent::decode(self.value.as_bytes()) {
+            Cow::Borrowed(bytes) => visitor.visit_borrowed_bytes(bytes),
+            Cow::Owned(bytes) => visitor.visit_byte_buf(bytes),
+        }
     }
 
     fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
@@ -419,17 +438,71 @@ impl<'de> Deserializer<'de> for Value<'de> {
         visitor.visit_unit()
     }
 
+    // value of repeated parameter. ex: `/:revs+`
+    fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_seq(SeqAccess {
+            params: segments(self.value).map(|value| ("", value)),
+            scratch: self.scratch,
+        })
+    }
+
     unsupported_type!(deserialize_any, "any");
-    unsupported_type!(deserialize_seq, "seq");
     unsupported_type!(deserialize_map, "map");
     unsupported_type!(deserialize_identifier, "identifier");
 }
 
-struct SeqAccess<I> {
+// percent-decode param value. decoded bytes must be valid utf-8.
+fn decode(value: &str) -> Result<Cow<'_, str>, de::value::Error> {
+    percent::decode_utf8(value).map_err(|_| de::value::Error::custom(format!("invalid utf-8 in {value:?}")))
+}
+
+// percent-decode param value and parse it to given type. decoded value is stored in scratch arena
+// when it's available and the arena is not borrowed.
+fn parse<T>(value: &str, scratch: Option<&Scratch>, tp: &str) -> Result<T, de::value::Error>
+where
+    T: FromStr,
+{
+    let parse = |value: &str| {
+        value
+            .parse()
+            .map_err(|_| de::value::Error::custom(format!("can not parse {value:?} to a {tp}")))
+    };
+
+    match scratch.filter(|_| value.contains('%')).and_then(Scratch::arena) {
+        Some(mut arena) => {
+            let decoded = arena
+                .decode_utf8(value)
+                .map_err(|_| de::value::Error::custom(format!("invalid utf-8 in {value:?}")))?;
+            parse(arena.str(decoded))
+        }
+        None => parse(&decode(value)?),
+    }
+}
+
+// split value of repeated parameter into segments. zero segment match has empty value.
+fn segments(value: &str) -> impl Iterator<Item = &str> {
+    value.split('/').filter(|segment| !segment.is_empty())
+}
+
+fn visit_str<'de, V>(value: &'de str, visitor: V) -> Result<V::Value, de::value::Error>
+where
+    V: Visitor<'de>,
+{
+    match decode(value)? {
+        Cow::Borrowed(value) => visitor.visit_borrowed_str(value),
+        Cow::Owned(value) => visitor.visit_string(value),
+    }
+}
+
+struct SeqAccess<'de, I> {
     params: I,
+    scratch: Option<&'de Scratch>,
 }
 
-impl<'de, I> de::SeqAccess<'de> for SeqAccess<I>
+impl<'de, I> de::SeqAccess<'de> for SeqAccess<'de, I>
 where
     I: Iterator<Item = (&'de str, &'de str)>,
 {
@@ -440,7 +513,10 @@ where
         U: de::DeserializeSeed<'de>,
     {
         match self.params.next() {
-            Some((_, value)) => Ok(Some(seed.deserialize(Value { value })?)),
+            Some((_, value)) => Ok(Some(seed.deserialize(Value {
+                value,
+                scratch: self.scratch,
+            })?)),
             None => Ok(None),
         }
     }
@@ -506,8 +582,8 @@ mod tests {
 
     use crate::{
         dev::service::{fn_service, Service},
-        http::{Request, RequestExt, Uri},
-        test::collect_string_body,
+        http::{Request, RequestExt, StatusCode, Uri},
+        test::{collect_string_body, TestRequest},
         App,
     };
 
@@ -653,6 +729,52 @@ mod tests {
         assert_eq!(i.0, 32);
     }
 
+    #[test]
+    fn test_extract_percent_encoded() {
+        let service = Router::new()
+            .insert("/:key/:value/", fn_service(handler))
+            .call(())
+            .now_or_panic()
+            .unwrap();
+
+        let req = Request::builder()
+            .uri("/na%20me/%31%32/")
+            .body(())
+            .unwrap()
+            .map(|_| RequestExt::<()>::default());
+
+        let res = service.call(req).now_or_panic().unwrap();
+        let params = res.body().params();
+
+        let Test2 { key, value } = Deserialize::deserialize(Params2::new(params)).unwrap();
+        assert_eq!(key, "na me");
+        assert_eq!(value, 12);
+
+        let (key, value): (String, u8) = Deserialize::deserialize(Params2::new(params)).unwrap();
+        assert_eq!(key, "na me");
+        assert_eq!(value, 12);
+
+        // value is decoded into scratch arena before parsed.
+        let scratch = Scratch::new();
+        let Test2 { key, value } = Deserialize::deserialize(Params2::with_scratch(params, Some(&scratch))).unwrap();
+        assert_eq!(key, "na me");
+        assert_eq!(value, 12);
+        // arena is released after deserializing.
+        assert!(scratch.try_reset());
+
+        let req = Request::builder()
+            .uri("/%C0%AF/1/")
+            .body(())
+            .unwrap()
+            .map(|_| RequestExt::<()>::default());
+
+        let res = service.call(req).now_or_panic().unwrap();
+        let params = res.body().params();
+
+        let s: Result<Test2, de::value::Error> = Deserialize::deserialize(Params2::new(params));
+        assert!(format!("{s:?}").contains("invalid utf-8"));
+    }
+
     #[test]
     fn test_extract_enum() {
         let service = Router::new()
@@ -777,4 +899,52 @@ mod tests {
 
         assert_eq!(s, "996");
     }
+
+    #[test]
+    fn repeated_params() {
+        #[derive(Deserialize)]
+        struct Compare {
+            base: String,
+            revs: Vec<u32>,
+        }
+
+        async fn compare(Params(Compare { base, revs }): Params<Compare>) -> String {
+            format!("{base} {revs:?}")
+        }
+
+        async fn tags(Params(tags): Params<Vec<String>>) -> String {
+            tags.join(",")
+        }
+
+        async fn popular() -> &'static str {
+            "popular"
+        }
+
+        let service = App::new()
+            .at("/compare/:base/:revs+/summary", handler_service(compare))
+            .at("/tags/popular", handler_service(popular))
+            .at("/tags/:tags+", handler_service(tags))
+            .finish_for_test()
+            .now_or_panic();
+
+        let call = |uri: &'static str| {
+            let res = service.call(TestRequest::get(uri)).now_or_panic().unwrap();
+            let status = res.status();
+            (status, res.string_body().now_or_panic().unwrap())
+        };
+
+        // literal tail after repeated segments.
+        assert_eq!(
+            call("/compare/main/1/2/3/summary"),
+            (StatusCode::OK, "main [1, 2, 3]".into())
+        );
+        assert_eq!(call("/compare/main/summary").0, StatusCode::NOT_FOUND);
+
+        // static route has higher precedence.
+        assert_eq!(call("/tags/popular"), (StatusCode::OK, "popular".into()));
+        assert_eq!(call("/tags/popular/r%20s"), (StatusCode::OK, "popular,r s".into()));
+
+        // element failing to parse is a bad request.
+        assert_eq!(call("/compare/main/1/x/summary").0, StatusCode::BAD_REQUEST);
+    }
 }
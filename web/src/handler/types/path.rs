@@ -1,5 +1,7 @@
 use std::{future::Future, ops::Deref};
 
+use xitca_http::util::service::router::MatchedRoute;
+
 use crate::{
     body::BodyStream,
     handler::{error::ExtractError, FromRequest},
@@ -30,3 +32,87 @@ where
         async { Ok(PathRef(req.req().uri().path())) }
     }
 }
+
+/// Extract route pattern and route name matched by App's router. Useful as low cardinality label
+/// of metrics.
+///
+/// For request handled by [App::default_service](crate::App::default_service) the pattern is
+/// [MatchedRoute::NOT_FOUND]. See [MatchedRoute] for when the value is available to middlewares.
+///
+/// # Examples:
+/// ```rust
+/// # use xitca_web::{handler::{handler_service, path::MatchedPath}, request::WebRequest, route::get, App};
+/// async fn handler(path: MatchedPath<'_>) -> String {
+///     // "/users/:id get_user"
+///     format!("{} {}", path.pattern(), path.name().unwrap_or_default())
+/// }
+/// # async fn index(_: &WebRequest<'_>) -> &'static str { "" }
+///
+/// App::new()
+/// #   .at("/", get(handler_service(index)))
+///     .at("/users/:id", get(handler_service(handler)).name("get_user"));
+/// ```
+#[derive(Debug)]
+pub struct MatchedPath<'a>(pub &'a MatchedRoute);
+
+impl Deref for MatchedPath<'_> {
+    type Target = MatchedRoute;
+
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
+
+impl<'a, 'r, C, B> FromRequest<'a, WebRequest<'r, C, B>> for MatchedPath<'a>
+where
+    B: BodyStream,
+{
+    type Type<'b> = MatchedPath<'b>;
+    type Error = ExtractError<B::Error>;
+    type Future = impl Future<Output = Result<Self, Self::Error>> where WebRequest<'r, C, B>: 'a;
+
+    #[inline]
+    fn from_request(req: &'a WebRequest<'r, C, B>) -> Self::Future {
+        async move {
+            let matched = req
+                .req()
+                .extensions()
+                .get::<MatchedRoute>()
+                .ok_or(ExtractError::ExtensionNotFound)?;
+            Ok(MatchedPath(matched))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::{handler::handler_service, http::StatusCode, route::get, test::TestRequest, App};
+
+    use super::*;
+
+    async fn handler(path: MatchedPath<'_>) -> String {
+        format!("{} {}", path.pattern(), path.name().unwrap_or_default())
+    }
+
+    #[test]
+    fn matched_path() {
+        let service = App::new()
+            .at("/users/:id", get(handler_service(handler)).name("get_user"))
+            .at("/files/*path", get(handler_service(handler)))
+            .default_service(get(handler_service(handler)))
+            .finish_for_test()
+            .now_or_panic();
+
+        let call = |path| {
+            let res = service.call(TestRequest::get(path)).now_or_panic().unwrap();
+            res.assert_status(StatusCode::OK);
+            res.string_body().now_or_panic().unwrap()
+        };
+
+        assert_eq!(call("/users/12345"), "/users/:id get_user");
+        assert_eq!(call("/files/foo/bar.txt"), "/files/*path ");
+        assert_eq!(call("/foo"), "<not_found> ");
+    }
+}
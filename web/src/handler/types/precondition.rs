@@ -0,0 +1,575 @@
+//! Conditional request evaluation. (RFC 9110 section 13)
+
+use core::{fmt, future::Future, str::FromStr};
+
+use std::{error, time::SystemTime};
+
+use httpdate::HttpDate;
+
+use crate::{
+    body::BodyStream,
+    dev::bytes::Bytes,
+    handler::{error::ExtractError, FromRequest, Responder},
+    http::{
+        header::{
+            HeaderMap, HeaderName, HeaderValue, ETAG, IF_MATCH, IF_MODIFIED_SINCE, IF_NONE_MATCH, IF_UNMODIFIED_SINCE,
+            LAST_MODIFIED,
+        },
+        Method, StatusCode,
+    },
+    request::WebRequest,
+    response::WebResponse,
+};
+
+/// Entity tag of a resource representation.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ETag {
+    weak: bool,
+    tag: String,
+}
+
+impl ETag {
+    /// Construct a strong entity tag. Given tag must not be quoted.
+    pub fn strong(tag: impl Into<String>) -> Self {
+        Self {
+            weak: false,
+            tag: tag.into(),
+        }
+    }
+
+    /// Construct a weak entity tag. Given tag must not be quoted.
+    pub fn weak(tag: impl Into<String>) -> Self {
+        Self {
+            weak: true,
+            tag: tag.into(),
+        }
+    }
+
+    pub fn is_weak(&self) -> bool {
+        self.weak
+    }
+
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// Strong comparison. Both tags must be strong and identical.
+    pub fn strong_eq(&self, other: &Self) -> bool {
+        !self.weak && !other.weak && self.tag == other.tag
+    }
+
+    /// Weak comparison. Tags are identical regardless of their weakness.
+    pub fn weak_eq(&self, other: &Self) -> bool {
+        self.tag == other.tag
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        let (weak, value) = match value.strip_prefix("W/") {
+            Some(value) => (true, value),
+            None => (false, value),
+        };
+        let tag = value.strip_prefix('"')?.strip_suffix('"')?;
+        // etagc = %x21 / %x23-7E / obs-text
+        tag.bytes()
+            .all(|b| b == 0x21 || (0x23..=0x7e).contains(&b) || b >= 0x80)
+            .then(|| Self {
+                weak,
+                tag: tag.to_owned(),
+            })
+    }
+}
+
+impl fmt::Display for ETag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.weak {
+            f.write_str("W/")?;
+        }
+        write!(f, "\"{}\"", self.tag)
+    }
+}
+
+/// Value of `If-Match` and `If-None-Match` headers.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ETagMatch {
+    /// The `*` form matching any current representation.
+    Any,
+    /// List of entity tags.
+    Tags(Vec<ETag>),
+}
+
+impl ETagMatch {
+    fn from_headers(headers: &HeaderMap, name: HeaderName) -> Option<Self> {
+        let mut tags = Vec::new();
+        let mut present = false;
+
+        for value in headers.get_all(name) {
+            present = true;
+            let Ok(value) = value.to_str() else { continue };
+            for tag in value.split(',').map(str::trim).filter(|tag| !tag.is_empty()) {
+                if tag == "*" {
+                    return Some(Self::Any);
+                }
+                // invalid entity tag is skipped and can not match any resource.
+                tags.extend(ETag::parse(tag));
+            }
+        }
+
+        present.then_some(Self::Tags(tags))
+    }
+}
+
+/// Current validators of a resource used to evaluate [Preconditions].
+#[derive(Clone, Debug, Default)]
+pub struct ResourceVersion {
+    missing: bool,
+    etag: Option<ETag>,
+    last_modified: Option<HttpDate>,
+}
+
+impl ResourceVersion {
+    /// Construct version of an existing resource without any validator.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Construct version of a resource that does not exist.
+    ///
+    /// `If-Match: *` fails and `If-None-Match: *` passes against it.
+    pub fn missing() -> Self {
+        Self {
+            missing: true,
+            ..Self::default()
+        }
+    }
+
+    /// Set entity tag of current representation.
+    pub fn etag(mut self, etag: ETag) -> Self {
+        self.etag = Some(etag);
+        self
+    }
+
+    /// Set last modification date of resource. Sub-second precision is truncated.
+    pub fn last_modified(mut self, time: SystemTime) -> Self {
+        self.last_modified = Some(HttpDate::from(time));
+        self
+    }
+
+    fn exists(&self) -> bool {
+        !self.missing
+    }
+
+    fn headers(&self) -> impl Iterator<Item = (HeaderName, HeaderValue)> + '_ {
+        let etag = self.etag.as_ref().map(|etag| (ETAG, etag.to_string()));
+        let last_modified = self.last_modified.map(|date| (LAST_MODIFIED, date.to_string()));
+        etag.into_iter()
+            .chain(last_modified)
+            .filter_map(|(name, value)| HeaderValue::try_from(value).ok().map(|value| (name, value)))
+    }
+}
+
+/// Outcome of evaluating [Preconditions].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PreconditionResult {
+    /// All preconditions passed. Request method should be performed.
+    Proceed,
+    /// Request is a GET or HEAD and client has the current representation.
+    /// Respond with `304 Not Modified`.
+    NotModified,
+    /// A precondition failed. Respond with `412 Precondition Failed`.
+    Failed,
+}
+
+/// Extractor of conditional request headers.
+///
+/// `If-Match`, `If-None-Match`, `If-Modified-Since` and `If-Unmodified-Since` headers are parsed
+/// and evaluated against [ResourceVersion] of target resource with [Preconditions::evaluate]
+/// following the order of RFC 9110 section 13.2.2.
+///
+/// # Examples:
+/// ```rust
+/// # use xitca_web::handler::{handler_service, precondition::{Conditional, ETag, Preconditions, ResourceVersion}};
+/// # use xitca_web::{request::WebRequest, route::put, App};
+/// async fn update(pre: Preconditions, _: &WebRequest<'_>) -> Conditional<&'static str> {
+///     let current = ResourceVersion::new().etag(ETag::strong("v1"));
+///     // reject with 412 when client's If-Match does not match current version.
+///     if let Err(e) = pre.check(&current) {
+///         return e.into();
+///     }
+///     // perform update.
+///     Conditional::Proceed("updated")
+/// }
+///
+/// App::new().at("/", put(handler_service(update)));
+/// ```
+#[derive(Clone, Debug)]
+pub struct Preconditions {
+    method: Method,
+    if_match: Option<ETagMatch>,
+    if_none_match: Option<ETagMatch>,
+    if_modified_since: Option<HttpDate>,
+    if_unmodified_since: Option<HttpDate>,
+}
+
+impl Preconditions {
+    /// Parse preconditions from request method and headers.
+    pub fn from_parts(method: &Method, headers: &HeaderMap) -> Self {
+        Self {
+            method: method.clone(),
+            if_match: ETagMatch::from_headers(headers, IF_MATCH),
+            if_none_match: ETagMatch::from_headers(headers, IF_NONE_MATCH),
+            if_modified_since: to_http_date(headers, IF_MODIFIED_SINCE),
+            if_unmodified_since: to_http_date(headers, IF_UNMODIFIED_SINCE),
+        }
+    }
+
+    pub fn if_match(&self) -> Option<&ETagMatch> {
+        self.if_match.as_ref()
+    }
+
+    pub fn if_none_match(&self) -> Option<&ETagMatch> {
+        self.if_none_match.as_ref()
+    }
+
+    /// Evaluate preconditions against current version of resource.
+    pub fn evaluate(&self, current: &ResourceVersion) -> PreconditionResult {
+        let is_get = self.method == Method::GET || self.method == Method::HEAD;
+
+        // step 1 and 2. If-Unmodified-Since is ignored when If-Match is present.
+        match self.if_match {
+            Some(ref if_match) => {
+                let matched = match (if_match, current.etag.as_ref()) {
+                    (ETagMatch::Any, _) => current.exists(),
+                    (ETagMatch::Tags(tags), Some(etag)) => tags.iter().any(|tag| tag.strong_eq(etag)),
+                    (ETagMatch::Tags(_), None) => false,
+                };
+                if !matched {
+                    return PreconditionResult::Failed;
+                }
+            }
+            None => {
+                if let (Some(since), Some(modified)) = (self.if_unmodified_since, current.last_modified) {
+                    if modified > since {
+                        return PreconditionResult::Failed;
+                    }
+                }
+            }
+        }
+
+        // step 3 and 4. If-Modified-Since is ignored when If-None-Match is present.
+        match self.if_none_match {
+            Some(ref if_none_match) => {
+                let matched = match (if_none_match, current.etag.as_ref()) {
+                    (ETagMatch::Any, _) => current.exists(),
+                    (ETagMatch::Tags(tags), Some(etag)) => tags.iter().any(|tag| tag.weak_eq(etag)),
+                    (ETagMatch::Tags(_), None) => false,
+                };
+                if matched {
+                    return if is_get {
+                        PreconditionResult::NotModified
+                    } else {
+                        PreconditionResult::Failed
+                    };
+                }
+            }
+            None => {
+                if let (true, Some(since), Some(modified)) = (is_get, self.if_modified_since, current.last_modified) {
+                    if modified <= since {
+                        return PreconditionResult::NotModified;
+                    }
+                }
+            }
+        }
+
+        PreconditionResult::Proceed
+    }
+
+    /// Evaluate preconditions against current version of resource and return an error that can
+    /// be used as response when request should not proceed.
+    pub fn check(&self, current: &ResourceVersion) -> Result<(), PreconditionError> {
+        match self.evaluate(current) {
+            PreconditionResult::Proceed => Ok(()),
+            result => Err(PreconditionError {
+                result,
+                headers: current.headers().collect(),
+            }),
+        }
+    }
+}
+
+fn to_http_date(headers: &HeaderMap, name: HeaderName) -> Option<HttpDate> {
+    // invalid date is ignored.
+    headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| HttpDate::from_str(v).ok())
+}
+
+impl<'a, 'r, C, B> FromRequest<'a, WebRequest<'r, C, B>> for Preconditions
+where
+    B: BodyStream,
+{
+    type Type<'b> = Preconditions;
+    type Error = ExtractError<B::Error>;
+    type Future = impl Future<Output = Result<Self, Self::Error>> where WebRequest<'r, C, B>: 'a;
+
+    #[inline]
+    fn from_request(req: &'a WebRequest<'r, C, B>) -> Self::Future {
+        let req = req.req();
+        let pre = Preconditions::from_parts(req.method(), req.headers());
+        async { Ok(pre) }
+    }
+}
+
+/// Error type of request rejected by [Preconditions::check].
+///
+/// Responded with `304 Not Modified` or `412 Precondition Failed` along with `ETag` and
+/// `Last-Modified` headers of current resource version.
+#[derive(Debug)]
+pub struct PreconditionError {
+    result: PreconditionResult,
+    headers: Vec<(HeaderName, HeaderValue)>,
+}
+
+impl PreconditionError {
+    pub fn result(&self) -> PreconditionResult {
+        self.result
+    }
+}
+
+impl fmt::Display for PreconditionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.result {
+            PreconditionResult::NotModified => f.write_str("resource not modified"),
+            _ => f.write_str("precondition failed"),
+        }
+    }
+}
+
+impl error::Error for PreconditionError {}
+
+impl<'r, C, B> Responder<WebRequest<'r, C, B>> for PreconditionError {
+    type Output = WebResponse;
+    type Future = impl Future<Output = Self::Output>;
+
+    fn respond_to(self, req: WebRequest<'r, C, B>) -> Self::Future {
+        let mut res = req.into_response(Bytes::new());
+        *res.status_mut() = match self.result {
+            PreconditionResult::NotModified => StatusCode::NOT_MODIFIED,
+            _ => StatusCode::PRECONDITION_FAILED,
+        };
+        res.headers_mut().extend(self.headers);
+        async { res }
+    }
+}
+
+/// Responder for handler doing conditional request.
+pub enum Conditional<T> {
+    /// Preconditions passed and `T` is responded.
+    Proceed(T),
+    /// Preconditions did not pass.
+    Rejected(PreconditionError),
+}
+
+impl<T> From<PreconditionError> for Conditional<T> {
+    fn from(e: PreconditionError) -> Self {
+        Self::Rejected(e)
+    }
+}
+
+impl<'r, C, B, T> Responder<WebRequest<'r, C, B>> for Conditional<T>
+where
+    T: Responder<WebRequest<'r, C, B>, Output = WebResponse>,
+{
+    type Output = WebResponse;
+    type Future = impl Future<Output = Self::Output>;
+
+    fn respond_to(self, req: WebRequest<'r, C, B>) -> Self::Future {
+        async {
+            match self {
+                Self::Proceed(t) => t.respond_to(req).await,
+                Self::Rejected(e) => e.respond_to(req).await,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::{handler::handler_service, route::get, test::TestRequest, App};
+
+    use super::*;
+
+    fn pre(method: Method, headers: &[(HeaderName, &'static str)]) -> Preconditions {
+        let mut map = HeaderMap::new();
+        for (name, value) in headers {
+            map.append(name.clone(), HeaderValue::from_static(value));
+        }
+        Preconditions::from_parts(&method, &map)
+    }
+
+    fn time(secs: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn etag_parse() {
+        assert_eq!(ETag::parse("\"abc\""), Some(ETag::strong("abc")));
+        assert_eq!(ETag::parse("W/\"abc\""), Some(ETag::weak("abc")));
+        assert_eq!(ETag::parse("\"\""), Some(ETag::strong("")));
+        assert_eq!(ETag::parse("abc"), None);
+        assert_eq!(ETag::parse("w/\"abc\""), None);
+        assert_eq!(ETag::parse("\"a\"c\""), None);
+        assert_eq!(ETag::weak("abc").to_string(), "W/\"abc\"");
+    }
+
+    #[test]
+    fn if_match() {
+        let current = ResourceVersion::new().etag(ETag::strong("v2"));
+
+        let p = pre(Method::PUT, &[(IF_MATCH, "\"v1\", \"v2\"")]);
+        assert_eq!(p.evaluate(&current), PreconditionResult::Proceed);
+
+        let p = pre(Method::PUT, &[(IF_MATCH, "\"v1\""), (IF_MATCH, "\"v2\"")]);
+        assert_eq!(p.evaluate(&current), PreconditionResult::Proceed);
+
+        let p = pre(Method::PUT, &[(IF_MATCH, "\"v1\"")]);
+        assert_eq!(p.evaluate(&current), PreconditionResult::Failed);
+
+        // If-Match uses strong comparison.
+        let p = pre(Method::PUT, &[(IF_MATCH, "W/\"v2\"")]);
+        assert_eq!(p.evaluate(&current), PreconditionResult::Failed);
+        let weak = ResourceVersion::new().etag(ETag::weak("v2"));
+        let p = pre(Method::PUT, &[(IF_MATCH, "\"v2\"")]);
+        assert_eq!(p.evaluate(&weak), PreconditionResult::Failed);
+
+        let p = pre(Method::PUT, &[(IF_MATCH, "*")]);
+        assert_eq!(p.evaluate(&current), PreconditionResult::Proceed);
+        assert_eq!(p.evaluate(&ResourceVersion::missing()), PreconditionResult::Failed);
+
+        // If-Unmodified-Since is ignored when If-Match is present.
+        let current = current.last_modified(time(100));
+        let p = pre(
+            Method::PUT,
+            &[
+                (IF_MATCH, "\"v2\""),
+                (IF_UNMODIFIED_SINCE, "Thu, 01 Jan 1970 00:00:00 GMT"),
+            ],
+        );
+        assert_eq!(p.evaluate(&current), PreconditionResult::Proceed);
+    }
+
+    #[test]
+    fn if_none_match() {
+        let current = ResourceVersion::new().etag(ETag::strong("v2"));
+
+        // If-None-Match uses weak comparison.
+        let p = pre(Method::GET, &[(IF_NONE_MATCH, "W/\"v2\"")]);
+        assert_eq!(p.evaluate(&current), PreconditionResult::NotModified);
+
+        let p = pre(Method::HEAD, &[(IF_NONE_MATCH, "\"v1\", \"v2\"")]);
+        assert_eq!(p.evaluate(&current), PreconditionResult::NotModified);
+
+        let p = pre(Method::GET, &[(IF_NONE_MATCH, "\"v1\"")]);
+        assert_eq!(p.evaluate(&current), PreconditionResult::Proceed);
+
+        // unsafe method fails instead of not modified.
+        let p = pre(Method::PUT, &[(IF_NONE_MATCH, "\"v2\"")]);
+        assert_eq!(p.evaluate(&current), PreconditionResult::Failed);
+
+        // create only when resource does not exist.
+        let p = pre(Method::PUT, &[(IF_NONE_MATCH, "*")]);
+        assert_eq!(p.evaluate(&current), PreconditionResult::Failed);
+        assert_eq!(p.evaluate(&ResourceVersion::missing()), PreconditionResult::Proceed);
+
+        // If-Modified-Since is ignored when If-None-Match is present.
+        let current = current.last_modified(time(100));
+        let p = pre(
+            Method::GET,
+            &[
+                (IF_NONE_MATCH, "\"v1\""),
+                (IF_MODIFIED_SINCE, "Sun, 06 Nov 1994 08:49:37 GMT"),
+            ],
+        );
+        assert_eq!(p.evaluate(&current), PreconditionResult::Proceed);
+    }
+
+    #[test]
+    fn date() {
+        // Thu, 01 Jan 1970 00:01:40 GMT
+        let current = ResourceVersion::new().last_modified(time(100));
+
+        let p = pre(Method::GET, &[(IF_MODIFIED_SINCE, "Thu, 01 Jan 1970 00:01:40 GMT")]);
+        assert_eq!(p.evaluate(&current), PreconditionResult::NotModified);
+
+        let p = pre(Method::GET, &[(IF_MODIFIED_SINCE, "Thu, 01 Jan 1970 00:01:39 GMT")]);
+        assert_eq!(p.evaluate(&current), PreconditionResult::Proceed);
+
+        // If-Modified-Since only applies to GET and HEAD.
+        let p = pre(Method::POST, &[(IF_MODIFIED_SINCE, "Thu, 01 Jan 1970 00:01:40 GMT")]);
+        assert_eq!(p.evaluate(&current), PreconditionResult::Proceed);
+
+        let p = pre(
+            Method::DELETE,
+            &[(IF_UNMODIFIED_SINCE, "Thu, 01 Jan 1970 00:01:39 GMT")],
+        );
+        assert_eq!(p.evaluate(&current), PreconditionResult::Failed);
+
+        let p = pre(
+            Method::DELETE,
+            &[(IF_UNMODIFIED_SINCE, "Thu, 01 Jan 1970 00:01:40 GMT")],
+        );
+        assert_eq!(p.evaluate(&current), PreconditionResult::Proceed);
+
+        // invalid date is ignored.
+        let p = pre(Method::DELETE, &[(IF_UNMODIFIED_SINCE, "yesterday")]);
+        assert_eq!(p.evaluate(&current), PreconditionResult::Proceed);
+
+        // date is ignored when resource has no modification date.
+        let p = pre(
+            Method::DELETE,
+            &[(IF_UNMODIFIED_SINCE, "Thu, 01 Jan 1970 00:01:39 GMT")],
+        );
+        assert_eq!(p.evaluate(&ResourceVersion::new()), PreconditionResult::Proceed);
+    }
+
+    fn version() -> ResourceVersion {
+        ResourceVersion::new().etag(ETag::strong("v2")).last_modified(time(100))
+    }
+
+    async fn handler(pre: Preconditions, _: &WebRequest<'_>) -> Conditional<&'static str> {
+        if let Err(e) = pre.check(&version()) {
+            return e.into();
+        }
+        Conditional::Proceed("ok")
+    }
+
+    #[test]
+    fn respond() {
+        let service = App::new()
+            .at("/", get(handler_service(handler)).put(handler_service(handler)))
+            .finish_for_test()
+            .now_or_panic();
+
+        let req = TestRequest::get("/").method(Method::PUT).header(IF_MATCH, "\"v1\"");
+        let res = service.call(req).now_or_panic().unwrap();
+        res.assert_status(StatusCode::PRECONDITION_FAILED)
+            .assert_header(ETAG, "\"v2\"")
+            .assert_header(LAST_MODIFIED, "Thu, 01 Jan 1970 00:01:40 GMT");
+
+        let req = TestRequest::get("/").method(Method::PUT).header(IF_MATCH, "\"v2\"");
+        let res = service.call(req).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK);
+
+        let req = TestRequest::get("/").header(IF_NONE_MATCH, "\"v2\"");
+        let res = service.call(req).now_or_panic().unwrap();
+        res.assert_status(StatusCode::NOT_MODIFIED)
+            .assert_header(ETAG, "\"v2\"");
+
+        let res = service.call(TestRequest::get("/")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK);
+    }
+}
@@ -1,11 +1,13 @@
-use std::{fmt, future::Future};
+use std::{fmt, future::Future, str::Split};
 
-use serde::de::DeserializeOwned;
+use serde::de::{self, value::Error, DeserializeOwned, Deserializer, IntoDeserializer, Visitor};
+use serde::forward_to_deserialize_any;
+use xitca_http::util::scratch::{Scratch, ScratchArena};
 
 use crate::{
     body::BodyStream,
     handler::{
-        error::{ExtractError, _ParseError},
+        error::{_ParseError, ExtractError},
         FromRequest,
     },
     request::WebRequest,
@@ -34,18 +36,163 @@ where
     #[inline]
     fn from_request(req: &'a WebRequest<'r, C, B>) -> Self::Future {
         async move {
-            let value = serde_urlencoded::from_str(req.req().uri().query().unwrap_or_default())
-                .map_err(_ParseError::UrlEncoded)?;
+            let req = req.req();
+            let query = req.uri().query().unwrap_or_default();
+
+            // query without escape is deserialized without allocation. use scratch arena for
+            // decoded pairs when it's available.
+            let arena = req
+                .extensions()
+                .get::<Scratch>()
+                .filter(|_| query.contains(['%', '+']))
+                .and_then(Scratch::arena);
+
+            let value = match arena {
+                Some(mut arena) => T::deserialize(Form {
+                    pairs: query.split('&'),
+                    value: None,
+                    arena: &mut arena,
+                }),
+                None => serde_urlencoded::from_str(query),
+            }
+            .map_err(_ParseError::UrlEncoded)?;
+
             Ok(Query(value))
         }
     }
 }
 
+// urlencoded deserializer decoding pairs into scratch arena. it follows the behavior of
+// serde_urlencoded.
+struct Form<'q, 'a, 's> {
+    pairs: Split<'q, char>,
+    value: Option<&'q str>,
+    arena: &'a mut ScratchArena<'s>,
+}
+
+impl<'de> Deserializer<'de> for Form<'_, '_, '_> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_map(self)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes
+            byte_buf option unit unit_struct newtype_struct seq tuple
+            tuple_struct map struct enum identifier ignored_any
+    }
+}
+
+impl<'de> de::MapAccess<'de> for Form<'_, '_, '_> {
+    type Error = Error;
+
+    fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>, Self::Error>
+    where
+        K: de::DeserializeSeed<'de>,
+    {
+        for pair in self.pairs.by_ref() {
+            if pair.is_empty() {
+                continue;
+            }
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            self.value = Some(value);
+            let key = self.arena.decode_form(key);
+            return seed.deserialize(Part(self.arena.str(key))).map(Some);
+        }
+        Ok(None)
+    }
+
+    fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value, Self::Error>
+    where
+        V: de::DeserializeSeed<'de>,
+    {
+        let value = self.value.take().ok_or_else(|| de::Error::custom("unexpected item"))?;
+        let value = self.arena.decode_form(value);
+        seed.deserialize(Part(self.arena.str(value)))
+    }
+}
+
+macro_rules! parse_part {
+    ($($trait_fn:ident, $visit_fn:ident);*) => {
+        $(
+            fn $trait_fn<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+            where
+                V: Visitor<'de>,
+            {
+                visitor.$visit_fn(self.0.parse().map_err(de::Error::custom)?)
+            }
+        )*
+    };
+}
+
+// decoded key or value of pair.
+struct Part<'a>(&'a str);
+
+impl<'de> Deserializer<'de> for Part<'_> {
+    type Error = Error;
+
+    fn deserialize_any<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_str(self.0)
+    }
+
+    fn deserialize_option<V>(self, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_some(self)
+    }
+
+    fn deserialize_newtype_struct<V>(self, _: &'static str, visitor: V) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_newtype_struct(self)
+    }
+
+    fn deserialize_enum<V>(
+        self,
+        _: &'static str,
+        _: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error>
+    where
+        V: Visitor<'de>,
+    {
+        visitor.visit_enum(IntoDeserializer::<Error>::into_deserializer(self.0))
+    }
+
+    parse_part!(
+        deserialize_bool, visit_bool;
+        deserialize_i8, visit_i8;
+        deserialize_i16, visit_i16;
+        deserialize_i32, visit_i32;
+        deserialize_i64, visit_i64;
+        deserialize_u8, visit_u8;
+        deserialize_u16, visit_u16;
+        deserialize_u32, visit_u32;
+        deserialize_u64, visit_u64;
+        deserialize_f32, visit_f32;
+        deserialize_f64, visit_f64
+    );
+
+    forward_to_deserialize_any! {
+        char str string unit bytes byte_buf unit_struct tuple_struct struct identifier tuple
+            ignored_any seq map
+    }
+}
+
 #[cfg(test)]
 mod test {
     use xitca_unsafe_collection::futures::NowOrPanic;
 
-    use crate::http::Uri;
+    use crate::{handler::handler_service, test::TestRequest, App};
 
     use super::*;
 
@@ -54,15 +201,67 @@ mod test {
         id: String,
     }
 
+    async fn handler(Query(id): Query<Id>) -> String {
+        id.id
+    }
+
     #[test]
     fn query() {
-        let mut req = WebRequest::new_test(());
-        let mut req = req.as_web_req();
+        let service = App::new()
+            .at("/996/251/", handler_service(handler))
+            .finish_for_test()
+            .now_or_panic();
+
+        let res = service
+            .call(TestRequest::get("/996/251/?id=dagongren"))
+            .now_or_panic()
+            .unwrap();
 
-        *req.req_mut().uri_mut() = Uri::from_static("/996/251/?id=dagongren");
+        assert_eq!(res.string_body().now_or_panic().unwrap(), "dagongren");
+    }
+
+    #[test]
+    fn query_scratch() {
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        enum Sort {
+            #[serde(rename = "asc")]
+            Asc,
+        }
+
+        #[derive(Debug, PartialEq, serde::Deserialize)]
+        struct Search {
+            q: String,
+            page: u32,
+            sort: Option<Sort>,
+            tag: Option<String>,
+        }
 
-        let Query(id) = Query::<Id>::from_request(&req).now_or_panic().unwrap();
+        async fn handler(Query(search): Query<Search>) -> String {
+            format!("{search:?}")
+        }
+
+        let service = App::new()
+            .at("/", handler_service(handler))
+            .finish_for_test()
+            .now_or_panic();
+
+        let scratch = Scratch::new();
+
+        for query in [
+            "q=foo+bar%21&page=%31%32&&sort=asc&empty",
+            "sort=%61sc&page=12&q=foo%20bar!",
+            "q=%C0%AF&page=1&tag=a+b",
+        ] {
+            let expected = serde_urlencoded::from_str::<Search>(query).unwrap();
+
+            let req = TestRequest::get(format!("/?{query}").as_str()).extension(scratch.clone());
+            let res = service.call(req).now_or_panic().unwrap();
+            assert_eq!(res.string_body().now_or_panic().unwrap(), format!("{expected:?}"));
+            assert!(scratch.try_reset());
+        }
 
-        assert_eq!(id.id, "dagongren");
+        let req = TestRequest::get("/?q=%20&page=one").extension(scratch);
+        let res = service.call(req).now_or_panic().unwrap();
+        assert!(!res.status().is_success());
     }
 }
@@ -0,0 +1,224 @@
+//! Typed `Content-Range` of request carrying part of a resource. (RFC 9110 section 14.5)
+
+use core::future::Future;
+
+use xitca_http::util::range::{self, RangeError};
+
+use crate::{
+    body::BodyStream,
+    handler::{
+        error::{_ParseError, ExtractError},
+        FromRequest,
+    },
+    http::header::{CONTENT_LENGTH, CONTENT_RANGE},
+    request::WebRequest,
+};
+
+/// Extract `Content-Range` header of request writing a chunk of resource. (e.g. `PUT` or `PATCH`
+/// request of resumable upload)
+///
+/// Extraction fails with `400 Bad Request` when header value is malformed, not in `bytes` unit or
+/// when `Content-Length` of request does not equal to the length of range. Missing header fails
+/// with [ExtractError::HeaderNotFound] and `Option<ContentRange>` can be used when the header is
+/// optional.
+///
+/// Together with `Preconditions` extractor of `precondition` feature `If-Match` header can be
+/// used to make sure the chunk is written to the expected version of resource.
+///
+/// # Examples:
+/// ```rust,no_run
+/// # use xitca_web::{handler::{handler_service, range::ContentRange}, route::put, App, HttpServer};
+/// async fn upload(range: ContentRange) -> String {
+///     // write range.len() bytes of request body at range.first.
+///     format!("{}-{}", range.first, range.last)
+/// }
+///
+/// # fn doc_example() -> std::io::Result<()> {
+/// HttpServer::new(|| App::new().at("/upload", put(handler_service(upload))).finish())
+///     .bind("0.0.0.0:8080")?
+///     .run();
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct ContentRange {
+    /// Position of first byte of request body in resource.
+    pub first: u64,
+    /// Position of last byte of request body in resource. It's inclusive.
+    pub last: u64,
+    /// Complete length of resource. None when it's unknown to client.
+    pub complete: Option<u64>,
+}
+
+impl ContentRange {
+    /// Length of bytes in range.
+    #[allow(clippy::len_without_is_empty)]
+    pub fn len(&self) -> u64 {
+        self.last - self.first + 1
+    }
+
+    fn from_header(range: &str, content_length: Option<u64>) -> Result<Self, _ParseError> {
+        let range::ContentRange::Bytes { first, last, complete } = range::ContentRange::parse(range)? else {
+            // unsatisfied range is only valid in response.
+            return Err(RangeError::Malformed.into());
+        };
+
+        let range = Self { first, last, complete };
+
+        // range with length overflowing u64 can not match any body.
+        if last - first == u64::MAX || content_length.is_some_and(|len| len != range.len()) {
+            return Err(_ParseError::RangeLength);
+        }
+
+        Ok(range)
+    }
+}
+
+impl<'a, 'r, C, B> FromRequest<'a, WebRequest<'r, C, B>> for ContentRange
+where
+    B: BodyStream,
+{
+    type Type<'b> = ContentRange;
+    type Error = ExtractError<B::Error>;
+    type Future = impl Future<Output = Result<Self, Self::Error>> where WebRequest<'r, C, B>: 'a;
+
+    #[inline]
+    fn from_request(req: &'a WebRequest<'r, C, B>) -> Self::Future {
+        let headers = req.req().headers();
+        let res = match headers.get(CONTENT_RANGE) {
+            Some(range) => {
+                let content_length = headers
+                    .get(CONTENT_LENGTH)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.parse().ok());
+                range
+                    .to_str()
+                    .map_err(|_| RangeError::Malformed.into())
+                    .and_then(|range| Self::from_header(range, content_length))
+                    .map_err(ExtractError::from)
+            }
+            None => Err(ExtractError::HeaderNotFound(CONTENT_RANGE)),
+        };
+        async { res }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::{
+        handler::handler_service,
+        http::{Method, StatusCode},
+        route::put,
+        test::TestRequest,
+        App,
+    };
+
+    use super::*;
+
+    #[test]
+    fn from_header() {
+        let range = |first, last, complete| ContentRange { first, last, complete };
+
+        let cases = [
+            ("bytes 0-99/1000", Some(100), Ok(range(0, 99, Some(1000)))),
+            ("bytes 900-999/1000", Some(100), Ok(range(900, 999, Some(1000)))),
+            ("bytes 0-0/*", Some(1), Ok(range(0, 0, None))),
+            // chunked request body has no Content-Length to check against.
+            ("bytes 100-199/*", None, Ok(range(100, 199, None))),
+            ("bytes 0-99/1000", Some(99), Err(())),
+            ("bytes 0-99/1000", Some(101), Err(())),
+            ("bytes 0-99/1000", Some(0), Err(())),
+            ("bytes 0-18446744073709551615/*", None, Err(())),
+            ("bytes 0-18446744073709551615/*", Some(u64::MAX), Err(())),
+            ("bytes */1000", Some(0), Err(())),
+            ("bytes 99-0/1000", Some(100), Err(())),
+            ("bytes 0-99/99", Some(100), Err(())),
+            ("bytes=0-99/1000", Some(100), Err(())),
+            ("items 0-99/1000", Some(100), Err(())),
+        ];
+
+        for (value, content_length, expected) in cases {
+            let res = ContentRange::from_header(value, content_length).map_err(|_| ());
+            assert_eq!(res, expected, "{value:?} {content_length:?}");
+        }
+
+        assert_eq!(range(10, 19, None).len(), 10);
+    }
+
+    async fn handler(range: ContentRange, body: String) -> String {
+        assert_eq!(range.len(), body.len() as u64);
+        format!("{}-{}/{:?} {body}", range.first, range.last, range.complete)
+    }
+
+    #[test]
+    fn extract() {
+        let service = App::new()
+            .at("/", put(handler_service(handler)))
+            .finish_for_test()
+            .now_or_panic();
+
+        let req = || TestRequest::get("/").method(Method::PUT);
+
+        let res = service
+            .call(req().header(CONTENT_RANGE, "bytes 4-6/10").body("996"))
+            .now_or_panic()
+            .unwrap();
+        res.assert_status(StatusCode::OK);
+        assert_eq!(res.string_body().now_or_panic().unwrap(), "4-6/Some(10) 996");
+
+        for range in ["bytes 4-7/10", "bytes */10", "bytes 4-6/6", "items 4-6/10"] {
+            let res = service
+                .call(req().header(CONTENT_RANGE, range).body("996"))
+                .now_or_panic()
+                .unwrap();
+            res.assert_status(StatusCode::BAD_REQUEST);
+        }
+
+        let res = service.call(req().body("996")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[cfg(feature = "precondition")]
+    #[test]
+    fn partial_put() {
+        use crate::{
+            handler::precondition::{Conditional, ETag, Preconditions, ResourceVersion},
+            http::header::{ETAG, IF_MATCH},
+        };
+
+        async fn upload(pre: Preconditions, range: Option<ContentRange>) -> Conditional<String> {
+            if let Err(e) = pre.check(&ResourceVersion::new().etag(ETag::strong("v1"))) {
+                return e.into();
+            }
+            Conditional::Proceed(format!("{range:?}"))
+        }
+
+        let service = App::new()
+            .at("/", put(handler_service(upload)))
+            .finish_for_test()
+            .now_or_panic();
+
+        let req = TestRequest::get("/")
+            .method(Method::PUT)
+            .header(IF_MATCH, "\"v0\"")
+            .header(CONTENT_RANGE, "bytes 0-2/10")
+            .body("251");
+        let res = service.call(req).now_or_panic().unwrap();
+        res.assert_status(StatusCode::PRECONDITION_FAILED)
+            .assert_header(ETAG, "\"v1\"");
+
+        let req = TestRequest::get("/")
+            .method(Method::PUT)
+            .header(IF_MATCH, "\"v1\"")
+            .header(CONTENT_RANGE, "bytes 0-2/10")
+            .body("251");
+        let res = service.call(req).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK);
+        assert_eq!(
+            res.string_body().now_or_panic().unwrap(),
+            "Some(ContentRange { first: 0, last: 2, complete: Some(10) })"
+        );
+    }
+}
@@ -0,0 +1,254 @@
+//! redirect responder and service.
+
+use core::{convert::Infallible, future::Future};
+
+use xitca_http::util::service::router::{Describe, PathGen};
+
+use crate::{
+    body::BodyStream,
+    dev::{
+        bytes::Bytes,
+        service::{ready::ReadyService, Service},
+    },
+    handler::{ExtractError, Responder},
+    http::{
+        header::{InvalidHeaderValue, LOCATION},
+        HeaderValue, StatusCode,
+    },
+    request::WebRequest,
+    response::WebResponse,
+};
+
+/// Redirect response with empty body and `Location` header.
+///
+/// Location is used as is and relative location is not resolved against request uri.
+///
+/// # Examples:
+/// ```rust
+/// # use xitca_web::{handler::{handler_service, redirect::Redirect}, request::WebRequest, App};
+/// App::new().at("/old", handler_service(handler));
+///
+/// async fn handler(req: &WebRequest<'_>) -> Redirect {
+///     Redirect::see_other_preserving_query(req, "/new").unwrap()
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct Redirect {
+    status: StatusCode,
+    location: HeaderValue,
+}
+
+impl Redirect {
+    /// Redirect with `303 See Other`. Client follows it with a GET request.
+    pub fn to<L>(location: L) -> Result<Self, InvalidHeaderValue>
+    where
+        L: TryInto<HeaderValue, Error = InvalidHeaderValue>,
+    {
+        Self::new(StatusCode::SEE_OTHER, location)
+    }
+
+    /// Redirect with `308 Permanent Redirect`. Method and body of request are preserved.
+    pub fn permanent<L>(location: L) -> Result<Self, InvalidHeaderValue>
+    where
+        L: TryInto<HeaderValue, Error = InvalidHeaderValue>,
+    {
+        Self::new(StatusCode::PERMANENT_REDIRECT, location)
+    }
+
+    /// Redirect with `307 Temporary Redirect`. Method and body of request are preserved.
+    pub fn temporary<L>(location: L) -> Result<Self, InvalidHeaderValue>
+    where
+        L: TryInto<HeaderValue, Error = InvalidHeaderValue>,
+    {
+        Self::new(StatusCode::TEMPORARY_REDIRECT, location)
+    }
+
+    /// Redirect with `302 Found`.
+    pub fn found<L>(location: L) -> Result<Self, InvalidHeaderValue>
+    where
+        L: TryInto<HeaderValue, Error = InvalidHeaderValue>,
+    {
+        Self::new(StatusCode::FOUND, location)
+    }
+
+    /// Redirect with `303 See Other` to given path with query string of request appended to it.
+    pub fn see_other_preserving_query<C, B>(
+        req: &WebRequest<'_, C, B>,
+        path: &str,
+    ) -> Result<Self, InvalidHeaderValue> {
+        match req.req().uri().query() {
+            Some(query) => {
+                let sep = if path.contains('?') { '&' } else { '?' };
+                Self::to(format!("{path}{sep}{query}"))
+            }
+            None => Self::to(path),
+        }
+    }
+
+    /// Status code of redirect.
+    pub fn status(&self) -> StatusCode {
+        self.status
+    }
+
+    /// Location redirecting to.
+    pub fn location(&self) -> &HeaderValue {
+        &self.location
+    }
+
+    pub(crate) fn new<L>(status: StatusCode, location: L) -> Result<Self, InvalidHeaderValue>
+    where
+        L: TryInto<HeaderValue, Error = InvalidHeaderValue>,
+    {
+        location.try_into().map(|location| Self { status, location })
+    }
+
+    fn to_response<C, B>(&self, req: WebRequest<'_, C, B>) -> WebResponse {
+        let mut res = req.into_response(Bytes::new());
+        *res.status_mut() = self.status;
+        res.headers_mut().insert(LOCATION, self.location.clone());
+        res
+    }
+}
+
+impl<'r, C, B> Responder<WebRequest<'r, C, B>> for Redirect {
+    type Output = WebResponse;
+    type Future = impl Future<Output = Self::Output>;
+
+    fn respond_to(self, req: WebRequest<'r, C, B>) -> Self::Future {
+        let res = self.to_response(req);
+        async { res }
+    }
+}
+
+// Redirect is a service for App::redirect.
+impl Service for Redirect {
+    type Response = Self;
+    type Error = Infallible;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> where Self: 'f;
+
+    fn call<'s>(&self, _: ()) -> Self::Future<'s> {
+        let this = self.clone();
+        async { Ok(this) }
+    }
+}
+
+impl PathGen for Redirect {}
+
+impl Describe for Redirect {}
+
+// error type is the same as handler_service so it can be mixed with other handlers in App.
+impl<'r, C, B> Service<WebRequest<'r, C, B>> for Redirect
+where
+    C: 'r,
+    B: BodyStream + 'r,
+{
+    type Response = WebResponse;
+    type Error = ExtractError<B::Error>;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where Self: 'f, 'r: 'f;
+
+    fn call<'s>(&'s self, req: WebRequest<'r, C, B>) -> Self::Future<'s>
+    where
+        'r: 's,
+    {
+        let res = self.to_response(req);
+        async { Ok(res) }
+    }
+}
+
+impl ReadyService for Redirect {
+    type Ready = ();
+    type Future<'f> = impl Future<Output = Self::Ready> where Self: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::Future<'_> {
+        async {}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::{handler::handler_service, test::TestRequest, App};
+
+    use super::*;
+
+    #[test]
+    fn status() {
+        for (redirect, status) in [
+            (Redirect::to("/a"), StatusCode::SEE_OTHER),
+            (Redirect::permanent("/a"), StatusCode::PERMANENT_REDIRECT),
+            (Redirect::temporary("/a"), StatusCode::TEMPORARY_REDIRECT),
+            (Redirect::found("/a"), StatusCode::FOUND),
+        ] {
+            let redirect = redirect.unwrap();
+            let service = App::new()
+                .at("/", handler_service(move || core::future::ready(redirect.clone())))
+                .finish_for_test()
+                .now_or_panic();
+
+            let res = service.call(TestRequest::default()).now_or_panic().unwrap();
+            res.assert_status(status).assert_header(LOCATION, "/a");
+        }
+    }
+
+    #[test]
+    fn invalid_location() {
+        assert!(Redirect::to("/a\nb").is_err());
+        assert!(Redirect::permanent(String::from("/a\r\nset-cookie: a")).is_err());
+    }
+
+    #[test]
+    fn preserving_query() {
+        async fn handler(req: &WebRequest<'_>) -> Redirect {
+            let path = if req.req().uri().path() == "/nested" {
+                "../new?a=1"
+            } else {
+                "/new"
+            };
+            Redirect::see_other_preserving_query(req, path).unwrap()
+        }
+
+        let service = App::new()
+            .at("/", handler_service(handler))
+            .at("/nested", handler_service(handler))
+            .finish_for_test()
+            .now_or_panic();
+
+        let res = service.call(TestRequest::get("/?foo=bar")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::SEE_OTHER)
+            .assert_header(LOCATION, "/new?foo=bar");
+
+        // relative location is untouched and query is joined with the existing one.
+        let res = service
+            .call(TestRequest::get("/nested?foo=bar"))
+            .now_or_panic()
+            .unwrap();
+        res.assert_header(LOCATION, "../new?a=1&foo=bar");
+
+        let res = service.call(TestRequest::get("/")).now_or_panic().unwrap();
+        res.assert_header(LOCATION, "/new");
+    }
+
+    #[test]
+    fn app_redirect() {
+        let service = App::new()
+            .redirect("/old", "/new", 308)
+            .at("/new", handler_service(|| async { "new" }))
+            .finish_for_test()
+            .now_or_panic();
+
+        let res = service.call(TestRequest::get("/old")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::PERMANENT_REDIRECT)
+            .assert_header(LOCATION, "/new");
+
+        let res = service.call(TestRequest::get("/new")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK);
+    }
+
+    #[test]
+    #[should_panic(expected = "is not a redirect status code")]
+    fn app_redirect_invalid_status() {
+        App::new().redirect("/old", "/new", 200).finish_for_test().now_or_panic();
+    }
+}
@@ -31,3 +31,160 @@ where
         async { Ok(RequestRef(req.req())) }
     }
 }
+
+/// Owned request with it's body stream. Useful for forwarding the request as a whole. e.g. passing
+/// it through to an upstream server.
+///
+/// # Note:
+/// - The body stream is taken from request. Extractors consuming request body (e.g. [Body],
+///   [String]) would observe an empty body afterwards.
+/// - Request head is cloned with the exception of [Extensions] which is not cloneable and left
+///   empty in extracted request. Use [RequestRef] for accessing request extensions.
+///
+/// [Body]: crate::handler::body::Body
+/// [Extensions]: crate::http::Extensions
+impl<'a, 'r, C, B> FromRequest<'a, WebRequest<'r, C, B>> for Request<RequestExt<B>>
+where
+    B: BodyStream + Default,
+{
+    type Type<'b> = Request<RequestExt<B>>;
+    type Error = ExtractError<B::Error>;
+    type Future = impl Future<Output = Result<Self, Self::Error>> where WebRequest<'r, C, B>: 'a;
+
+    #[inline]
+    fn from_request(req: &'a WebRequest<'r, C, B>) -> Self::Future {
+        let head = req.req();
+        let body = req.take_body_ref();
+
+        let mut extract = Request::new(head.body().clone().map_body(|_| body));
+        *extract.method_mut() = head.method().clone();
+        *extract.uri_mut() = head.uri().clone();
+        *extract.version_mut() = head.version();
+        *extract.headers_mut() = head.headers().clone();
+
+        async { Ok(extract) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::{cell::Cell, convert::Infallible};
+
+    use std::rc::Rc;
+
+    use futures_core::future::LocalBoxFuture;
+    use futures_util::{stream, StreamExt};
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::{
+        body::{BodySize, RequestBody, ResponseBody, SizedStream},
+        dev::{bytes::Bytes, service::Service},
+        handler::{handler_service, state::StateRef},
+        http::{
+            header::{HeaderValue, CONNECTION, CONTENT_LENGTH, TE},
+            remove_hop_by_hop_headers, HeaderMap, StatusCode,
+        },
+        response::WebResponse,
+        test::TestRequest,
+        App,
+    };
+
+    use super::*;
+
+    type Upstream = Rc<dyn Fn(Request<RequestExt<RequestBody>>) -> LocalBoxFuture<'static, WebResponse>>;
+
+    fn body_size(headers: &HeaderMap) -> BodySize {
+        headers
+            .get(CONTENT_LENGTH)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse().ok())
+            .map(BodySize::Sized)
+            .unwrap_or(BodySize::Stream)
+    }
+
+    // upstream echos request body back without collecting it.
+    async fn echo(req: Request<RequestExt<RequestBody>>) -> WebResponse {
+        assert!(!req.headers().contains_key(CONNECTION));
+        assert!(!req.headers().contains_key(TE));
+        assert!(!req.headers().contains_key("x-hop"));
+        assert_eq!(req.headers().get("x-end").unwrap(), "996");
+
+        let size = body_size(req.headers());
+        let len = req.headers().get(CONTENT_LENGTH).cloned();
+
+        let mut res = WebResponse::new(ResponseBody::box_stream(SizedStream::new(req.into_body(), size)));
+        res.headers_mut().insert(CONTENT_LENGTH, len.unwrap());
+        res.headers_mut().insert(CONNECTION, HeaderValue::from_static("x-hop"));
+        res.headers_mut().insert("x-hop", HeaderValue::from_static("1"));
+        res
+    }
+
+    async fn proxy(
+        StateRef(upstream): StateRef<'_, Upstream>,
+        mut req: Request<RequestExt<RequestBody>>,
+    ) -> WebResponse {
+        remove_hop_by_hop_headers(req.headers_mut());
+        let mut res = upstream(req).await;
+        remove_hop_by_hop_headers(res.headers_mut());
+        let size = body_size(res.headers());
+        res.map(|body| ResponseBody::box_stream(SizedStream::new(body, size)))
+    }
+
+    #[test]
+    fn proxy_stream() {
+        let upstream = Rc::new(
+            App::new()
+                .at("/", handler_service(echo))
+                .finish_for_test()
+                .now_or_panic(),
+        );
+
+        let upstream: Upstream = Rc::new(move |req| {
+            let upstream = upstream.clone();
+            Box::pin(async move {
+                let res = upstream.0.call(req).await.unwrap();
+                res.map(ResponseBody::box_stream)
+            })
+        });
+
+        let service = App::with_current_thread_state(upstream)
+            .at("/", handler_service(proxy))
+            .finish_for_test()
+            .now_or_panic();
+
+        let chunks = ["hello", ",", "world"];
+
+        // count the chunks pulled from request body.
+        let pulled = Rc::new(Cell::new(0));
+        let pulled2 = pulled.clone();
+        let body = stream::iter(chunks).map(move |chunk| {
+            pulled2.set(pulled2.get() + 1);
+            Ok::<_, Infallible>(Bytes::from_static(chunk.as_bytes()))
+        });
+
+        let req = TestRequest::default()
+            .header(CONTENT_LENGTH, "11")
+            .header(CONNECTION, "keep-alive, x-hop")
+            .header("x-hop", "1")
+            .header(TE, "trailers")
+            .header("x-end", "996")
+            .stream(body);
+
+        let res = service.call(req).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK).assert_header(CONTENT_LENGTH, "11");
+        assert!(!res.headers().contains_key(CONNECTION));
+        assert!(!res.headers().contains_key("x-hop"));
+
+        let mut body = res.into_inner().into_body();
+        assert_eq!(BodySize::from_stream(&body), BodySize::Sized(11));
+        assert_eq!(pulled.get(), 0);
+
+        // request body is pulled one chunk at a time when response body is polled.
+        for (i, chunk) in chunks.into_iter().enumerate() {
+            assert_eq!(body.next().now_or_panic().unwrap().unwrap(), chunk);
+            assert_eq!(pulled.get(), i + 1);
+        }
+
+        assert!(body.next().now_or_panic().is_none());
+    }
+}
@@ -0,0 +1,190 @@
+use core::{fmt, future::Future};
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use serde::{de::DeserializeOwned, ser::Serialize};
+
+use crate::{
+    body::BodyStream,
+    dev::bytes::Bytes,
+    handler::{error::ExtractError, FromRequest, Responder},
+    http::StatusCode,
+    middleware::session::SessionState,
+    request::WebRequest,
+    response::WebResponse,
+};
+
+/// Session extractor. Give handlers typed access to a string keyed session map.
+///
+/// [SessionMiddleware](crate::middleware::session::SessionMiddleware) must be enclosed for this
+/// extractor to function. Session is only persisted(and Set-Cookie header written) when it's
+/// mutated by handler.
+#[derive(Clone)]
+pub struct Session(pub(crate) Arc<Mutex<SessionInner>>);
+
+pub(crate) struct SessionInner {
+    pub(crate) state: SessionState,
+    pub(crate) status: SessionStatus,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum SessionStatus {
+    Unchanged,
+    Changed,
+    Renewed,
+    Purged,
+}
+
+impl Session {
+    pub(crate) fn new(state: SessionState) -> Self {
+        Self(Arc::new(Mutex::new(SessionInner {
+            state,
+            status: SessionStatus::Unchanged,
+        })))
+    }
+
+    /// Get value of given key and deserialize it to type T.
+    pub fn get<T>(&self, key: &str) -> Result<Option<T>, SessionError>
+    where
+        T: DeserializeOwned,
+    {
+        match self.lock().state.data.get(key) {
+            Some(value) => serde_json::from_str(value).map(Some).map_err(SessionError),
+            None => Ok(None),
+        }
+    }
+
+    /// Serialize given value and insert it to session with given key.
+    pub fn insert<T>(&self, key: impl Into<String>, value: T) -> Result<(), SessionError>
+    where
+        T: Serialize,
+    {
+        let value = serde_json::to_string(&value).map_err(SessionError)?;
+        let mut inner = self.lock();
+        inner.state.data.insert(key.into(), value);
+        inner.mark_changed();
+        Ok(())
+    }
+
+    /// Remove value of given key from session and return it's serialized form.
+    pub fn remove(&self, key: &str) -> Option<String> {
+        let mut inner = self.lock();
+        let value = inner.state.data.remove(key);
+        if value.is_some() {
+            inner.mark_changed();
+        }
+        value
+    }
+
+    /// Remove all values from session.
+    pub fn clear(&self) {
+        let mut inner = self.lock();
+        if !inner.state.data.is_empty() {
+            inner.state.data.clear();
+            inner.mark_changed();
+        }
+    }
+
+    /// Regenerate the id of session while keeping it's values.
+    ///
+    /// This is used to prevent session fixation and should be called when privilege level of
+    /// session changed. (e.g. login/logout)
+    pub fn renew(&self) {
+        let mut inner = self.lock();
+        inner.state.id = crate::middleware::session::generate_id();
+        inner.status = SessionStatus::Renewed;
+    }
+
+    /// Remove all values from session and remove it from client and store.
+    ///
+    /// Mutating session after purge starts a new session with a fresh id.
+    pub fn purge(&self) {
+        let mut inner = self.lock();
+        inner.state.data.clear();
+        inner.status = SessionStatus::Purged;
+    }
+
+    /// Id of session.
+    pub fn id(&self) -> String {
+        self.lock().state.id.clone()
+    }
+
+    /// Get a copy of all session values in their serialized form.
+    pub fn entries(&self) -> HashMap<String, String> {
+        self.lock().state.data.clone()
+    }
+
+    pub(crate) fn lock(&self) -> MutexGuard<'_, SessionInner> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl SessionInner {
+    fn mark_changed(&mut self) {
+        match self.status {
+            SessionStatus::Unchanged => self.status = SessionStatus::Changed,
+            // mutating a purged session starts a new one. it must not reuse the purged id.
+            SessionStatus::Purged => {
+                self.state.id = crate::middleware::session::generate_id();
+                self.status = SessionStatus::Renewed;
+            }
+            SessionStatus::Changed | SessionStatus::Renewed => {}
+        }
+    }
+}
+
+impl fmt::Debug for Session {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let inner = self.lock();
+        f.debug_struct("Session")
+            .field("data", &inner.state.data)
+            .field("status", &inner.status)
+            .finish()
+    }
+}
+
+impl<'a, 'r, C, B> FromRequest<'a, WebRequest<'r, C, B>> for Session
+where
+    B: BodyStream,
+{
+    type Type<'b> = Session;
+    type Error = ExtractError<B::Error>;
+    type Future = impl Future<Output = Result<Self, Self::Error>> where WebRequest<'r, C, B>: 'a;
+
+    #[inline]
+    fn from_request(req: &'a WebRequest<'r, C, B>) -> Self::Future {
+        let res = req
+            .req()
+            .extensions()
+            .get::<Session>()
+            .cloned()
+            .ok_or(ExtractError::ExtensionNotFound);
+        async { res }
+    }
+}
+
+/// Error type of (de)serializing session values.
+#[derive(Debug)]
+pub struct SessionError(serde_json::Error);
+
+impl fmt::Display for SessionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl std::error::Error for SessionError {}
+
+impl<'r, C, B> Responder<WebRequest<'r, C, B>> for SessionError {
+    type Output = WebResponse;
+    type Future = impl Future<Output = Self::Output>;
+
+    fn respond_to(self, req: WebRequest<'r, C, B>) -> Self::Future {
+        let mut res = req.into_response(Bytes::new());
+        *res.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+        async { res }
+    }
+}
@@ -1,4 +1,4 @@
-use std::{borrow::Borrow, fmt, future::Future, ops::Deref};
+use std::{any::type_name, borrow::Borrow, fmt, future::Future, ops::Deref};
 
 use crate::{
     body::BodyStream,
@@ -6,8 +6,32 @@ use crate::{
     request::WebRequest,
 };
 
+/// Borrow typed state from app state passed to `App::with_xxx_state`.
+///
+/// It's implemented for every app state implementing [Borrow]. e.g. the app state itself and
+/// fields marked with `#[borrow]` attribute of [State](crate::codegen::State) derive macro. App
+/// state with optional component can implement it and return [None] when the component is absent.
+/// Extracting it then fails with [ExtractError::StateNotFound].
+///
+/// When app state can not be borrowed as the extracted type compiling fails with error like
+/// "the trait bound `AppState: Borrow<T>` is not satisfied".
+pub trait BorrowState<T> {
+    fn borrow_state(&self) -> Option<&T>;
+}
+
+impl<S, T> BorrowState<T> for S
+where
+    S: Borrow<T>,
+{
+    #[inline]
+    fn borrow_state(&self) -> Option<&T> {
+        Some(self.borrow())
+    }
+}
+
 /// App state extractor.
-/// S type must be the same with the type passed to App::with_xxx_state(<S>).
+/// S type must be the same with the type passed to App::with_xxx_state(<S>) or a type it can be
+/// borrowed as through [BorrowState].
 pub struct StateRef<'a, S>(pub &'a S);
 
 impl<S: fmt::Debug> fmt::Debug for StateRef<'_, S> {
@@ -32,7 +56,7 @@ impl<S> Deref for StateRef<'_, S> {
 
 impl<'a, 'r, C, B, T> FromRequest<'a, WebRequest<'r, C, B>> for StateRef<'a, T>
 where
-    C: Borrow<T>,
+    C: BorrowState<T>,
     B: BodyStream,
     T: 'static,
 {
@@ -42,10 +66,62 @@ where
 
     #[inline]
     fn from_request(req: &'a WebRequest<'r, C, B>) -> Self::Future {
-        async { Ok(StateRef(req.state().borrow())) }
+        async { borrow_state(req.state()).map(StateRef) }
     }
 }
 
+/// Owned app state extractor. Same as [StateRef] except the state is cloned.
+///
+/// It's useful for cheaply clonable state like `Arc<T>` when it has to be moved into other
+/// async task.
+pub struct State<S>(pub S);
+
+impl<S: fmt::Debug> fmt::Debug for State<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "State({:?})", self.0)
+    }
+}
+
+impl<S: fmt::Display> fmt::Display for State<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "State({})", self.0)
+    }
+}
+
+impl<S> Deref for State<S> {
+    type Target = S;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<'a, 'r, C, B, T> FromRequest<'a, WebRequest<'r, C, B>> for State<T>
+where
+    C: BorrowState<T>,
+    B: BodyStream,
+    T: Clone,
+{
+    type Type<'b> = State<T>;
+    type Error = ExtractError<B::Error>;
+    type Future = impl Future<Output = Result<Self, Self::Error>> where WebRequest<'r, C, B>: 'a;
+
+    #[inline]
+    fn from_request(req: &'a WebRequest<'r, C, B>) -> Self::Future {
+        let res = borrow_state(req.state()).map(|state| State(state.clone()));
+        async { res }
+    }
+}
+
+fn borrow_state<C, T, E>(state: &C) -> Result<&T, ExtractError<E>>
+where
+    C: BorrowState<T>,
+{
+    state
+        .borrow_state()
+        .ok_or_else(|| ExtractError::StateNotFound(type_name::<T>()))
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -54,10 +130,18 @@ mod test {
     use xitca_http::Request;
     use xitca_unsafe_collection::futures::NowOrPanic;
 
-    use crate::{dev::service::Service, handler::handler_service, request::WebRequest, route::get, App};
+    use crate::{
+        dev::service::Service,
+        handler::{handler_service, FromRequest},
+        http::StatusCode,
+        request::WebRequest,
+        route::get,
+        test::TestRequest,
+        App,
+    };
 
     #[derive(State, Clone, Debug, Eq, PartialEq)]
-    struct State {
+    struct TestState {
         #[borrow]
         field1: String,
         #[borrow]
@@ -67,8 +151,8 @@ mod test {
     async fn handler(
         StateRef(state): StateRef<'_, String>,
         StateRef(state2): StateRef<'_, u32>,
-        StateRef(state3): StateRef<'_, State>,
-        req: &WebRequest<'_, State>,
+        StateRef(state3): StateRef<'_, TestState>,
+        req: &WebRequest<'_, TestState>,
     ) -> String {
         assert_eq!("state", state);
         assert_eq!(&996, state2);
@@ -79,7 +163,7 @@ mod test {
 
     #[test]
     fn state_extract() {
-        let state = State {
+        let state = TestState {
             field1: String::from("state"),
             field2: 996,
         };
@@ -95,4 +179,107 @@ mod test {
             .now_or_panic()
             .unwrap();
     }
+
+    #[derive(State, Clone, Debug, Eq, PartialEq)]
+    struct AppState {
+        #[borrow]
+        db: Db,
+        #[borrow]
+        cache: Cache,
+        #[borrow]
+        cfg: Config,
+    }
+
+    #[derive(Clone, Debug, Eq, PartialEq)]
+    struct Db(&'static str);
+
+    #[derive(Clone, Debug, Eq, PartialEq)]
+    struct Cache(usize);
+
+    #[derive(Clone, Debug, Eq, PartialEq)]
+    struct Config {
+        name: &'static str,
+    }
+
+    async fn db(StateRef(db): StateRef<'_, Db>) -> &'static str {
+        db.0
+    }
+
+    async fn cache(State(cache): State<Cache>) -> String {
+        cache.0.to_string()
+    }
+
+    async fn cfg(StateRef(cfg): StateRef<'_, Config>, State(cfg2): State<Config>) -> &'static str {
+        assert_eq!(cfg, &cfg2);
+        cfg.name
+    }
+
+    async fn whole(StateRef(state): StateRef<'_, AppState>, State(state2): State<AppState>) -> String {
+        assert_eq!(state, &state2);
+        format!("{}-{}-{}", state.db.0, state.cache.0, state.cfg.name)
+    }
+
+    #[test]
+    fn state_extract_field() {
+        let state = AppState {
+            db: Db("db"),
+            cache: Cache(996),
+            cfg: Config { name: "cfg" },
+        };
+
+        let service = App::with_current_thread_state(state)
+            .at("/db", get(handler_service(db)))
+            .at("/cache", get(handler_service(cache)))
+            .at("/cfg", get(handler_service(cfg)))
+            .at("/", get(handler_service(whole)))
+            .finish_for_test()
+            .now_or_panic();
+
+        for (path, body) in [("/db", "db"), ("/cache", "996"), ("/cfg", "cfg"), ("/", "db-996-cfg")] {
+            let res = service.call(TestRequest::get(path)).now_or_panic().unwrap();
+            res.assert_status(StatusCode::OK);
+            assert_eq!(res.string_body().now_or_panic().unwrap(), body);
+        }
+    }
+
+    #[derive(Clone)]
+    struct Search;
+
+    #[derive(Clone)]
+    struct OptionalState {
+        search: Option<Search>,
+    }
+
+    impl BorrowState<Search> for OptionalState {
+        fn borrow_state(&self) -> Option<&Search> {
+            self.search.as_ref()
+        }
+    }
+
+    async fn search(_: State<Search>) -> &'static str {
+        "search"
+    }
+
+    #[test]
+    fn state_not_found() {
+        let mut req = WebRequest::new_test(OptionalState { search: None });
+        let req = req.as_web_req();
+        let err = State::<Search>::from_request(&req).now_or_panic().err().unwrap();
+        assert!(matches!(err, ExtractError::StateNotFound(name) if name.ends_with("Search")));
+        assert!(err.to_string().contains("Search"));
+
+        let service = App::with_current_thread_state(OptionalState { search: None })
+            .at("/", get(handler_service(search)))
+            .finish_for_test()
+            .now_or_panic();
+        let res = service.call(TestRequest::get("/")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::INTERNAL_SERVER_ERROR);
+
+        let service = App::with_current_thread_state(OptionalState { search: Some(Search) })
+            .at("/", get(handler_service(search)))
+            .finish_for_test()
+            .now_or_panic();
+        let res = service.call(TestRequest::get("/")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK);
+    }
 }
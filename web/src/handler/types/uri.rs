@@ -1,9 +1,14 @@
-use std::{future::Future, ops::Deref};
+use std::{borrow::Cow, future::Future, ops::Deref};
+
+use xitca_http::util::percent;
 
 use crate::{
     body::BodyStream,
-    handler::{error::ExtractError, FromRequest},
-    http::Uri,
+    handler::{
+        error::{ExtractError, _ParseError},
+        FromRequest,
+    },
+    http::{header::HOST, uri::Scheme, Uri},
     request::WebRequest,
 };
 
@@ -31,3 +36,191 @@ where
         async move { Ok(UriRef(req.req().uri())) }
     }
 }
+
+/// [Uri] of request as it's received from http layer.
+///
+/// It's stored in request's extensions by middlewares like
+/// [NormalizePath](crate::middleware::normalize_path::NormalizePath) when they rewrite request's
+/// [Uri]. Request not rewritten does not carry it and pays no cost of cloning [Uri].
+#[derive(Clone, Debug)]
+pub struct OriginalUri(pub Uri);
+
+/// Extract immutable reference of [OriginalUri].
+///
+/// Fall back to current [Uri] of request when [OriginalUri] is absent. (Request [Uri] is not
+/// rewritten)
+#[derive(Debug)]
+pub struct OriginalUriRef<'a>(pub &'a Uri);
+
+impl Deref for OriginalUriRef<'_> {
+    type Target = Uri;
+
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
+
+impl<'a, 'r, C, B> FromRequest<'a, WebRequest<'r, C, B>> for OriginalUriRef<'a>
+where
+    B: BodyStream,
+{
+    type Type<'b> = OriginalUriRef<'b>;
+    type Error = ExtractError<B::Error>;
+    type Future = impl Future<Output = Result<Self, Self::Error>> where WebRequest<'r, C, B>: 'a;
+
+    #[inline]
+    fn from_request(req: &'a WebRequest<'r, C, B>) -> Self::Future {
+        async move {
+            let req = req.req();
+            let uri = match req.extensions().get::<OriginalUri>() {
+                Some(OriginalUri(uri)) => uri,
+                None => req.uri(),
+            };
+            Ok(OriginalUriRef(uri))
+        }
+    }
+}
+
+/// Typed parts of request's current [Uri].
+///
+/// # Examples:
+/// ```rust
+/// # use xitca_web::handler::uri::UriParts;
+/// async fn handler(parts: UriParts<'_>) -> String {
+///     format!("{}://{}:{}{}", parts.scheme, parts.host.unwrap_or_default(), parts.port.unwrap_or_default(), parts.path)
+/// }
+/// ```
+#[derive(Debug)]
+pub struct UriParts<'a> {
+    /// Scheme of [Uri]. Default to [Scheme::HTTP] when [Uri] does not have one.
+    pub scheme: Scheme,
+    /// Host from authority of [Uri] or `Host` header when [Uri] does not have authority.
+    pub host: Option<&'a str>,
+    /// Port from authority of [Uri] or `Host` header. Default to the well known port of scheme
+    /// when absent.
+    pub port: Option<u16>,
+    /// Percent-decoded path. Path not decoding to valid utf-8 is rejected with 400 Bad Request.
+    pub path: Cow<'a, str>,
+    /// Query as it's received from client without decoding.
+    pub query: Option<&'a str>,
+}
+
+impl<'a, 'r, C, B> FromRequest<'a, WebRequest<'r, C, B>> for UriParts<'a>
+where
+    B: BodyStream,
+{
+    type Type<'b> = UriParts<'b>;
+    type Error = ExtractError<B::Error>;
+    type Future = impl Future<Output = Result<Self, Self::Error>> where WebRequest<'r, C, B>: 'a;
+
+    #[inline]
+    fn from_request(req: &'a WebRequest<'r, C, B>) -> Self::Future {
+        async move {
+            let req = req.req();
+            let uri = req.uri();
+
+            let scheme = uri.scheme().cloned().unwrap_or(Scheme::HTTP);
+
+            let (host, port) = match uri.authority() {
+                Some(authority) => (Some(authority.host()), authority.port_u16()),
+                None => match req.headers().get(HOST).and_then(|v| v.to_str().ok()) {
+                    Some(host) => split_host_port(host),
+                    None => (None, None),
+                },
+            };
+
+            let port = port.or_else(|| default_port(&scheme));
+
+            let path = percent::decode_utf8(uri.path()).map_err(_ParseError::Path)?;
+
+            Ok(UriParts {
+                scheme,
+                host,
+                port,
+                path,
+                query: uri.query(),
+            })
+        }
+    }
+}
+
+fn default_port(scheme: &Scheme) -> Option<u16> {
+    if *scheme == Scheme::HTTP {
+        Some(80)
+    } else if *scheme == Scheme::HTTPS {
+        Some(443)
+    } else {
+        match scheme.as_str() {
+            "ws" => Some(80),
+            "wss" => Some(443),
+            _ => None,
+        }
+    }
+}
+
+// split Host header value into host and port. ipv6 host is kept in it's bracketed form the same
+// way as Authority::host.
+fn split_host_port(host: &str) -> (Option<&str>, Option<u16>) {
+    let (h, port) = match host.rfind(':') {
+        Some(idx) if !host[idx..].contains(']') => (&host[..idx], host[idx + 1..].parse().ok()),
+        _ => (host, None),
+    };
+    ((!h.is_empty()).then_some(h), port)
+}
+
+#[cfg(test)]
+mod test {
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::{handler::handler_service, http::StatusCode, test::TestRequest, App};
+
+    use super::*;
+
+    #[test]
+    fn host_port() {
+        assert_eq!(split_host_port("localhost"), (Some("localhost"), None));
+        assert_eq!(split_host_port("localhost:8080"), (Some("localhost"), Some(8080)));
+        assert_eq!(split_host_port("[::1]"), (Some("[::1]"), None));
+        assert_eq!(split_host_port("[::1]:8080"), (Some("[::1]"), Some(8080)));
+        assert_eq!(split_host_port(""), (None, None));
+    }
+
+    #[test]
+    fn uri_parts() {
+        async fn handler(parts: UriParts<'_>) -> String {
+            format!(
+                "{} {:?} {:?} {} {:?}",
+                parts.scheme, parts.host, parts.port, parts.path, parts.query
+            )
+        }
+
+        let service = App::new()
+            .at("/*path", handler_service(handler))
+            .finish_for_test()
+            .now_or_panic();
+
+        let req = TestRequest::get("/a%20b?c=%20").header(HOST, "localhost:8080");
+        let res = service.call(req).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK);
+        assert_eq!(
+            res.string_body().now_or_panic().unwrap(),
+            "http Some(\"localhost\") Some(8080) /a b Some(\"c=%20\")"
+        );
+
+        let req = TestRequest::get("https://[::1]/foo");
+        let res = service.call(req).now_or_panic().unwrap();
+        assert_eq!(
+            res.string_body().now_or_panic().unwrap(),
+            "https Some(\"[::1]\") Some(443) /foo None"
+        );
+
+        let res = service.call(TestRequest::get("/foo")).now_or_panic().unwrap();
+        assert_eq!(
+            res.string_body().now_or_panic().unwrap(),
+            "http None Some(80) /foo None"
+        );
+
+        let res = service.call(TestRequest::get("/%ff")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::BAD_REQUEST);
+    }
+}
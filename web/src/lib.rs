@@ -6,6 +6,8 @@ mod app;
 mod server;
 
 pub mod body;
+#[cfg(feature = "config")]
+pub mod config;
 pub mod error;
 pub mod handler;
 pub mod middleware;
@@ -17,6 +19,7 @@ pub mod test;
 #[cfg(feature = "codegen")]
 pub mod codegen {
     /// Derive macro for individual struct field extractable through [StateRef](crate::handler::state::StateRef)
+    /// and [State](crate::handler::state::State)
     ///
     /// # Example:
     /// ```rust
@@ -48,7 +51,9 @@ pub mod codegen {
 }
 
 pub mod route {
+    pub use xitca_http::util::service::guard;
     pub use xitca_http::util::service::route::{connect, delete, get, head, options, patch, post, put, trace, Route};
+    pub use xitca_http::util::service::router::{Describe, Layer, LayerKind, Manifest, RouteConflict};
 }
 
 pub mod dev {
@@ -59,6 +64,6 @@ pub mod dev {
 pub use app::App;
 pub use body::BodyStream;
 #[cfg(feature = "__server")]
-pub use server::HttpServer;
+pub use server::{profile, DrainHandle, HttpServer, WriteBufStrategy};
 
 pub use xitca_http::http;
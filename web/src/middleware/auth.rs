@@ -0,0 +1,485 @@
+//! `Authorization` header middleware.
+//!
+//! See [Auth] and [Principal](crate::handler::auth::Principal) for usage.
+
+use core::{convert::Infallible, fmt, future::Future};
+
+use std::error;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+use crate::{
+    dev::{
+        bytes::Bytes,
+        service::{pipeline::PipelineE, ready::ReadyService, Service},
+    },
+    handler::{auth::Principal, Responder},
+    http::{
+        header::{HeaderMap, HeaderValue, AUTHORIZATION, WWW_AUTHENTICATE},
+        StatusCode,
+    },
+    request::WebRequest,
+    response::WebResponse,
+    route::guard::{Guard, Head},
+};
+
+/// Credentials parsed from `Authorization` header.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Credentials {
+    /// Token of `Bearer` scheme.
+    Bearer(String),
+    /// User id and password of `Basic` scheme.
+    Basic { user: String, password: String },
+}
+
+/// Authentication scheme accepted by [Auth] middleware and used in it's challenge.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Scheme {
+    Bearer,
+    Basic,
+}
+
+impl Scheme {
+    fn as_str(&self) -> &'static str {
+        match *self {
+            Self::Bearer => "Bearer",
+            Self::Basic => "Basic",
+        }
+    }
+}
+
+impl Credentials {
+    /// Parse credentials of given scheme from `Authorization` header. Scheme name is case
+    /// insensitive.
+    pub fn from_headers(headers: &HeaderMap, scheme: Scheme) -> Option<Self> {
+        let value = headers.get(AUTHORIZATION)?.to_str().ok()?;
+        let (name, param) = value.split_once(' ')?;
+        if !name.eq_ignore_ascii_case(scheme.as_str()) {
+            return None;
+        }
+
+        let param = param.trim();
+        if param.is_empty() {
+            return None;
+        }
+
+        match scheme {
+            Scheme::Bearer => Some(Self::Bearer(param.to_owned())),
+            Scheme::Basic => {
+                let decoded = STANDARD.decode(param).ok()?;
+                let decoded = String::from_utf8(decoded).ok()?;
+                let (user, password) = decoded.split_once(':')?;
+                Some(Self::Basic {
+                    user: user.to_owned(),
+                    password: password.to_owned(),
+                })
+            }
+        }
+    }
+}
+
+/// Error type returned by [Validator].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum AuthError {
+    /// Credentials are not valid. Responded with `401 Unauthorized` and a challenge.
+    Unauthorized,
+    /// Credentials are valid but not allowed to access the resource. Responded with
+    /// `403 Forbidden`.
+    Forbidden,
+}
+
+impl fmt::Display for AuthError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Unauthorized => f.write_str("unauthorized"),
+            Self::Forbidden => f.write_str("forbidden"),
+        }
+    }
+}
+
+impl error::Error for AuthError {}
+
+/// Trait for validating [Credentials] and producing principal of request.
+///
+/// It's implemented for closures returning a future that does not borrow from it's arguments.
+/// Implement it manually when borrowing is needed.
+pub trait Validator {
+    type Principal;
+
+    type Future<'f>: Future<Output = Result<Self::Principal, AuthError>>
+    where
+        Self: 'f;
+
+    fn validate<'s>(&'s self, credentials: &'s Credentials, head: &'s Head<'_>) -> Self::Future<'s>;
+}
+
+impl<F, Fut, P> Validator for F
+where
+    F: Fn(&Credentials, &Head<'_>) -> Fut,
+    Fut: Future<Output = Result<P, AuthError>>,
+{
+    type Principal = P;
+    type Future<'f> = Fut where Self: 'f;
+
+    #[inline]
+    fn validate<'s>(&'s self, credentials: &'s Credentials, head: &'s Head<'_>) -> Self::Future<'s> {
+        (self)(credentials, head)
+    }
+}
+
+/// Middleware for authenticating request with credentials from `Authorization` header.
+///
+/// Credentials are passed to [Validator] and the principal it produces is inserted into request
+/// extensions where it can be extracted with [Principal](crate::handler::auth::Principal).
+/// Request with missing or invalid credentials is rejected with `401 Unauthorized` and a
+/// `WWW-Authenticate` challenge. Request validator deems forbidden is rejected with
+/// `403 Forbidden`.
+///
+/// # Examples:
+/// ```rust
+/// # use std::future::{ready, Ready};
+/// # use xitca_web::{handler::{auth::Principal, handler_service}, request::WebRequest, route::{get, guard::Head}, App};
+/// use xitca_web::middleware::auth::{Auth, AuthError, Credentials};
+///
+/// fn validate(credentials: &Credentials, _: &Head<'_>) -> Ready<Result<String, AuthError>> {
+///     ready(match credentials {
+///         Credentials::Bearer(token) if token == "secret" => Ok(String::from("alice")),
+///         _ => Err(AuthError::Unauthorized),
+///     })
+/// }
+///
+/// async fn index(Principal(user): Principal<String>, _: &WebRequest<'_>) -> String {
+///     user
+/// }
+///
+/// App::new()
+///     .at("/", get(handler_service(index)))
+///     // skip authentication for health check.
+///     .enclosed(Auth::bearer(validate).realm("api").skip(|head: &Head<'_>| head.uri().path() == "/health"));
+/// ```
+#[derive(Clone)]
+pub struct Auth<V, G = ()> {
+    validator: V,
+    scheme: Scheme,
+    realm: String,
+    skip: Option<G>,
+}
+
+impl<V> Auth<V>
+where
+    V: Validator,
+{
+    /// Construct middleware accepting `Bearer` credentials.
+    pub fn bearer(validator: V) -> Self {
+        Self::new(validator, Scheme::Bearer)
+    }
+
+    /// Construct middleware accepting `Basic` credentials.
+    pub fn basic(validator: V) -> Self {
+        Self::new(validator, Scheme::Basic)
+    }
+
+    fn new(validator: V, scheme: Scheme) -> Self {
+        Self {
+            validator,
+            scheme,
+            realm: String::from("restricted"),
+            skip: None,
+        }
+    }
+}
+
+impl<V, G> Auth<V, G> {
+    /// Set realm of `WWW-Authenticate` challenge. Default to `restricted`.
+    pub fn realm(mut self, realm: impl Into<String>) -> Self {
+        self.realm = realm.into();
+        self
+    }
+
+    /// Skip authentication for request passing given [Guard].
+    pub fn skip<G2>(self, guard: G2) -> Auth<V, G2>
+    where
+        G2: Guard,
+    {
+        Auth {
+            validator: self.validator,
+            scheme: self.scheme,
+            realm: self.realm,
+            skip: Some(guard),
+        }
+    }
+
+    fn challenge(&self, error: Option<&str>) -> HeaderValue {
+        let mut challenge = format!("{} realm=\"{}\"", self.scheme.as_str(), self.realm);
+        if let Some(error) = error {
+            challenge.push_str(", error=\"");
+            challenge.push_str(error);
+            challenge.push('"');
+        }
+        HeaderValue::try_from(challenge).expect("realm must be valid header value")
+    }
+}
+
+impl<V, G, S> Service<S> for Auth<V, G>
+where
+    V: Clone,
+    G: Clone,
+{
+    type Response = AuthService<V, G, S>;
+    type Error = Infallible;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where Self: 'f, S: 'f;
+
+    fn call<'s>(&'s self, service: S) -> Self::Future<'s>
+    where
+        S: 's,
+    {
+        async {
+            // invalid_token error is only defined for bearer scheme. (RFC 6750)
+            let challenge_invalid = match self.scheme {
+                Scheme::Bearer => self.challenge(Some("invalid_token")),
+                Scheme::Basic => self.challenge(None),
+            };
+
+            Ok(AuthService {
+                service,
+                validator: self.validator.clone(),
+                scheme: self.scheme,
+                challenge: self.challenge(None),
+                challenge_invalid,
+                skip: self.skip.clone(),
+            })
+        }
+    }
+}
+
+pub struct AuthService<V, G, S> {
+    service: S,
+    validator: V,
+    scheme: Scheme,
+    challenge: HeaderValue,
+    challenge_invalid: HeaderValue,
+    skip: Option<G>,
+}
+
+pub type AuthServiceError<E> = PipelineE<AuthRejected, E>;
+
+impl<'r, V, G, S, C, B, Res, Err> Service<WebRequest<'r, C, B>> for AuthService<V, G, S>
+where
+    C: 'r,
+    B: 'r,
+    V: Validator,
+    V::Principal: Clone + Send + Sync + 'static,
+    G: Guard,
+    S: for<'rs> Service<WebRequest<'rs, C, B>, Response = Res, Error = Err>,
+{
+    type Response = Res;
+    type Error = AuthServiceError<Err>;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where Self: 'f, 'r: 'f;
+
+    fn call<'s>(&'s self, mut req: WebRequest<'r, C, B>) -> Self::Future<'s>
+    where
+        'r: 's,
+    {
+        async move {
+            let head = Head::new(req.req());
+
+            if !self.skip.as_ref().is_some_and(|guard| guard.check(&head)) {
+                let Some(credentials) = Credentials::from_headers(head.headers(), self.scheme) else {
+                    return Err(AuthServiceError::First(AuthRejected {
+                        error: AuthError::Unauthorized,
+                        challenge: self.challenge.clone(),
+                    }));
+                };
+
+                let principal = self.validator.validate(&credentials, &head).await.map_err(|error| {
+                    AuthServiceError::First(AuthRejected {
+                        error,
+                        challenge: self.challenge_invalid.clone(),
+                    })
+                })?;
+
+                req.req_mut().extensions_mut().insert(Principal(principal));
+            }
+
+            self.service.call(req).await.map_err(AuthServiceError::Second)
+        }
+    }
+}
+
+impl<V, G, S> ReadyService for AuthService<V, G, S>
+where
+    S: ReadyService,
+{
+    type Ready = S::Ready;
+    type Future<'f> = S::Future<'f> where Self: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::Future<'_> {
+        self.service.ready()
+    }
+}
+
+/// Error type for request rejected by [Auth] middleware.
+#[derive(Debug)]
+pub struct AuthRejected {
+    error: AuthError,
+    challenge: HeaderValue,
+}
+
+impl AuthRejected {
+    /// Reason of rejection.
+    pub fn error(&self) -> AuthError {
+        self.error
+    }
+}
+
+impl fmt::Display for AuthRejected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "request rejected: {}", self.error)
+    }
+}
+
+impl error::Error for AuthRejected {}
+
+impl<'r, C, B> Responder<WebRequest<'r, C, B>> for AuthRejected {
+    type Output = WebResponse;
+    type Future = impl Future<Output = Self::Output>;
+
+    fn respond_to(self, req: WebRequest<'r, C, B>) -> Self::Future {
+        let mut res = req.into_response(Bytes::new());
+        match self.error {
+            AuthError::Unauthorized => {
+                *res.status_mut() = StatusCode::UNAUTHORIZED;
+                res.headers_mut().insert(WWW_AUTHENTICATE, self.challenge);
+            }
+            AuthError::Forbidden => *res.status_mut() = StatusCode::FORBIDDEN,
+        }
+        async { res }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::future::{ready, Ready};
+
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::{handler::handler_service, route::get, test::TestRequest, App};
+
+    use super::*;
+
+    #[derive(Clone, Debug)]
+    struct User(String);
+
+    fn validate(credentials: &Credentials, _: &Head<'_>) -> Ready<Result<User, AuthError>> {
+        ready(match credentials {
+            Credentials::Bearer(token) if token == "good" => Ok(User(String::from("alice"))),
+            Credentials::Bearer(token) if token == "banned" => Err(AuthError::Forbidden),
+            Credentials::Basic { user, password } if password == "pass" => Ok(User(user.clone())),
+            _ => Err(AuthError::Unauthorized),
+        })
+    }
+
+    async fn index(Principal(User(name)): Principal<User>) -> String {
+        name
+    }
+
+    #[test]
+    fn bearer() {
+        let service = App::new()
+            .at("/", get(handler_service(index)))
+            .enclosed(Auth::bearer(validate).realm("api"))
+            .finish_for_test()
+            .now_or_panic();
+
+        let res = service.call(TestRequest::get("/")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::UNAUTHORIZED)
+            .assert_header(WWW_AUTHENTICATE, "Bearer realm=\"api\"");
+
+        let req = TestRequest::get("/").header(AUTHORIZATION, "Bearer bad");
+        let res = service.call(req).now_or_panic().unwrap();
+        res.assert_status(StatusCode::UNAUTHORIZED)
+            .assert_header(WWW_AUTHENTICATE, "Bearer realm=\"api\", error=\"invalid_token\"");
+
+        // credentials of other scheme are treated as missing.
+        let req = TestRequest::get("/").header(AUTHORIZATION, "Basic YWxpY2U6cGFzcw==");
+        let res = service.call(req).now_or_panic().unwrap();
+        res.assert_status(StatusCode::UNAUTHORIZED)
+            .assert_header(WWW_AUTHENTICATE, "Bearer realm=\"api\"");
+
+        let req = TestRequest::get("/").header(AUTHORIZATION, "Bearer banned");
+        let res = service.call(req).now_or_panic().unwrap();
+        res.assert_status(StatusCode::FORBIDDEN);
+        assert!(res.headers().get(WWW_AUTHENTICATE).is_none());
+
+        let req = TestRequest::get("/").header(AUTHORIZATION, "bearer good");
+        let res = service.call(req).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK);
+        assert_eq!(res.string_body().now_or_panic().unwrap(), "alice");
+    }
+
+    #[test]
+    fn basic() {
+        let service = App::new()
+            .at("/", get(handler_service(index)))
+            .enclosed(Auth::basic(validate))
+            .finish_for_test()
+            .now_or_panic();
+
+        // alice:wrong
+        let req = TestRequest::get("/").header(AUTHORIZATION, "Basic YWxpY2U6d3Jvbmc=");
+        let res = service.call(req).now_or_panic().unwrap();
+        res.assert_status(StatusCode::UNAUTHORIZED)
+            .assert_header(WWW_AUTHENTICATE, "Basic realm=\"restricted\"");
+
+        // alice:pass
+        let req = TestRequest::get("/").header(AUTHORIZATION, "Basic YWxpY2U6cGFzcw==");
+        let res = service.call(req).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK);
+        assert_eq!(res.string_body().now_or_panic().unwrap(), "alice");
+    }
+
+    #[test]
+    fn skip() {
+        let service = App::new()
+            .at("/", get(handler_service(index)))
+            .at("/health", get(handler_service(|| async { "ok" })))
+            .enclosed(Auth::bearer(validate).skip(|head: &Head<'_>| head.uri().path() == "/health"))
+            .finish_for_test()
+            .now_or_panic();
+
+        let res = service.call(TestRequest::get("/health")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK);
+
+        let res = service.call(TestRequest::get("/")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::UNAUTHORIZED);
+    }
+
+    #[test]
+    fn credentials() {
+        let mut headers = HeaderMap::new();
+        assert_eq!(Credentials::from_headers(&headers, Scheme::Bearer), None);
+
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer  token "));
+        assert_eq!(
+            Credentials::from_headers(&headers, Scheme::Bearer),
+            Some(Credentials::Bearer(String::from("token")))
+        );
+
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer "));
+        assert_eq!(Credentials::from_headers(&headers, Scheme::Bearer), None);
+
+        // user id can not contain colon while password can.
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Basic YTpiOmM="));
+        assert_eq!(
+            Credentials::from_headers(&headers, Scheme::Basic),
+            Some(Credentials::Basic {
+                user: String::from("a"),
+                password: String::from("b:c")
+            })
+        );
+
+        headers.insert(AUTHORIZATION, HeaderValue::from_static("Basic !!!"));
+        assert_eq!(Credentials::from_headers(&headers, Scheme::Basic), None);
+    }
+}
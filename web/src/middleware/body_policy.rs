@@ -0,0 +1,792 @@
+//! Request and response body policy middleware.
+//!
+//! See [BodyPolicy] for usage.
+
+use core::{
+    cell::RefCell,
+    convert::Infallible,
+    fmt,
+    future::Future,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+
+use std::{error, net::SocketAddr};
+
+use futures_core::stream::Stream;
+use pin_project_lite::pin_project;
+use tracing::warn;
+use xitca_http::{config::ConnectionEvent, Request};
+
+use crate::{
+    body::{BodyStream, BoxStream, ResponseBody},
+    dev::{
+        bytes::Bytes,
+        service::{pipeline::PipelineE, ready::ReadyService, Service},
+    },
+    error::BodyError,
+    handler::Responder,
+    http::{
+        const_header_value::TEXT_UTF8,
+        header::{HeaderMap, HeaderValue, CONTENT_LENGTH, CONTENT_TYPE, TRANSFER_ENCODING},
+        RequestBodyLimit, StatusCode,
+    },
+    request::WebRequest,
+    response::WebResponse,
+    route::guard::{Guard, Head},
+};
+
+/// Action taken on response body exceeding [BodyPolicy::response_max_size].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ResponseAction {
+    /// Respond with [BodyPolicy::response_error_status] when the size is known before response
+    /// head is sent. Otherwise abort the streaming body with [BodyError] once it goes past the
+    /// limit.
+    Reject,
+    /// Cut response body at the limit and emit a warning log.
+    Truncate,
+}
+
+pub use xitca_http::config::BodyViolation;
+
+type Observer = fn(SocketAddr, ConnectionEvent);
+
+/// Middleware enforcing size and content type policy of request and response body.
+///
+/// Request advertising a `Content-Length` larger than [BodyPolicy::request_max_size] is rejected
+/// with `413 Payload Too Large` and request body without length is aborted with error once it
+/// goes past the limit. Request with body and a content type not matching any pattern added by
+/// [BodyPolicy::allow_content_type] is rejected with `415 Unsupported Media Type`.
+///
+/// [BodyPolicy::request_max_size] overrides connection wide limit set by
+/// `HttpServiceConfig::max_request_body_size` for request in scope through [RequestBodyLimit]. It
+/// can be smaller or larger than the connection wide one.
+///
+/// # Examples:
+/// ```rust
+/// # use xitca_web::{handler::handler_service, request::WebRequest, route::{post, guard::Head}, App};
+/// use xitca_web::middleware::body_policy::BodyPolicy;
+///
+/// # async fn upload(_: &WebRequest<'_>) -> &'static str { "" }
+/// App::new()
+///     .at("/public/upload", post(handler_service(upload)))
+///     .enclosed(
+///         BodyPolicy::new()
+///             .request_max_size(1024 * 1024)
+///             .allow_content_type("image/*")
+///             // policy only applies to public endpoints.
+///             .scope(|head: &Head<'_>| head.uri().path().starts_with("/public/")),
+///     );
+/// ```
+#[derive(Clone)]
+pub struct BodyPolicy<G = ()> {
+    request_max_size: Option<u64>,
+    content_types: Vec<String>,
+    response_max_size: Option<usize>,
+    response_action: ResponseAction,
+    response_error_status: StatusCode,
+    observer: Option<Observer>,
+    scope: Option<G>,
+}
+
+impl Default for BodyPolicy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BodyPolicy {
+    /// Construct a policy without any limit.
+    pub fn new() -> Self {
+        Self {
+            request_max_size: None,
+            content_types: Vec::new(),
+            response_max_size: None,
+            response_action: ResponseAction::Reject,
+            response_error_status: StatusCode::INTERNAL_SERVER_ERROR,
+            observer: None,
+            scope: None,
+        }
+    }
+}
+
+impl<G> BodyPolicy<G> {
+    /// Set max size in bytes the request body can be.
+    pub fn request_max_size(mut self, size: u64) -> Self {
+        self.request_max_size = Some(size);
+        self
+    }
+
+    /// Allow request body with given content type. Pattern ending with `*` matches content type
+    /// starting with the part before it. e.g. `image/*` matches `image/png`. Parameters of
+    /// content type are ignored and matching is case insensitive.
+    ///
+    /// When no content type is added request body of any content type is allowed.
+    pub fn allow_content_type(mut self, pattern: impl Into<String>) -> Self {
+        self.content_types.push(pattern.into());
+        self
+    }
+
+    /// Set max size in bytes the response body can be.
+    pub fn response_max_size(mut self, size: usize) -> Self {
+        self.response_max_size = Some(size);
+        self
+    }
+
+    /// Set action taken on response body exceeding max size. Default to [ResponseAction::Reject].
+    pub fn response_action(mut self, action: ResponseAction) -> Self {
+        self.response_action = action;
+        self
+    }
+
+    /// Set status code of response replacing the rejected one. Default to
+    /// `500 Internal Server Error`.
+    pub fn response_error_status(mut self, status: StatusCode) -> Self {
+        self.response_error_status = status;
+        self
+    }
+
+    /// Set observer called with [ConnectionEvent::BodyViolation] on every violation of policy.
+    ///
+    /// It takes the same function as [HttpServer::connection_observer](crate::HttpServer::connection_observer)
+    /// so violations can be collected together with other connection events. e.g. by
+    /// [metrics::connection_observer](crate::middleware::metrics::connection_observer).
+    /// Observer is called on the worker thread serving the request and it should be cheap.
+    pub fn observer(mut self, observer: Observer) -> Self {
+        self.observer = Some(observer);
+        self
+    }
+
+    /// Only apply policy to request passing given [Guard].
+    pub fn scope<G2>(self, guard: G2) -> BodyPolicy<G2>
+    where
+        G2: Guard,
+    {
+        BodyPolicy {
+            request_max_size: self.request_max_size,
+            content_types: self.content_types,
+            response_max_size: self.response_max_size,
+            response_action: self.response_action,
+            response_error_status: self.response_error_status,
+            observer: self.observer,
+            scope: Some(guard),
+        }
+    }
+
+    fn notify(&self, addr: SocketAddr, violation: BodyViolation) {
+        notify(self.observer, addr, violation);
+    }
+
+    fn content_type_allowed(&self, headers: &HeaderMap) -> bool {
+        if self.content_types.is_empty() || !has_body(headers) {
+            return true;
+        }
+
+        let Some(content_type) = headers.get(CONTENT_TYPE).and_then(|v| v.to_str().ok()) else {
+            return false;
+        };
+        let essence = content_type.split(';').next().unwrap_or_default().trim();
+
+        self.content_types
+            .iter()
+            .any(|pattern| match pattern.strip_suffix('*') {
+                Some(prefix) => essence
+                    .get(..prefix.len())
+                    .is_some_and(|start| start.eq_ignore_ascii_case(prefix)),
+                None => essence.eq_ignore_ascii_case(pattern),
+            })
+    }
+}
+
+fn notify(observer: Option<Observer>, addr: SocketAddr, violation: BodyViolation) {
+    if let Some(observer) = observer {
+        observer(addr, ConnectionEvent::BodyViolation(violation));
+    }
+}
+
+fn has_body(headers: &HeaderMap) -> bool {
+    headers.contains_key(TRANSFER_ENCODING) || content_length(headers).is_some_and(|len| len > 0)
+}
+
+fn content_length(headers: &HeaderMap) -> Option<u64> {
+    headers.get(CONTENT_LENGTH)?.to_str().ok()?.parse().ok()
+}
+
+impl<G, S> Service<S> for BodyPolicy<G>
+where
+    G: Clone,
+{
+    type Response = BodyPolicyService<G, S>;
+    type Error = Infallible;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where Self: 'f, S: 'f;
+
+    fn call<'s>(&'s self, service: S) -> Self::Future<'s>
+    where
+        S: 's,
+    {
+        async {
+            Ok(BodyPolicyService {
+                service,
+                policy: self.clone(),
+            })
+        }
+    }
+}
+
+pub struct BodyPolicyService<G, S> {
+    service: S,
+    policy: BodyPolicy<G>,
+}
+
+pub type BodyPolicyServiceError<E> = PipelineE<BodyPolicyError, E>;
+
+impl<'r, G, S, C, B, Err> Service<WebRequest<'r, C, B>> for BodyPolicyService<G, S>
+where
+    C: 'r,
+    B: BodyStream + Default + 'r,
+    G: Guard,
+    S: for<'rs> Service<WebRequest<'rs, C, PolicyBody<B>>, Response = WebResponse, Error = Err>,
+{
+    type Response = WebResponse;
+    type Error = BodyPolicyServiceError<Err>;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where Self: 'f, 'r: 'f;
+
+    fn call<'s>(&'s self, mut req: WebRequest<'r, C, B>) -> Self::Future<'s>
+    where
+        'r: 's,
+    {
+        async move {
+            let policy = &self.policy;
+
+            let in_scope = match policy.scope {
+                Some(ref guard) => guard.check(&Head::new(req.req())),
+                None => true,
+            };
+
+            let addr = *req.req().body().socket_addr();
+            let mut limit = None;
+            let mut shared = None;
+
+            if in_scope {
+                let headers = req.req().headers();
+
+                if let Some(max) = policy.request_max_size {
+                    if content_length(headers).is_some_and(|len| len > max) {
+                        policy.notify(addr, BodyViolation::RequestTooLarge { limit: max });
+                        return Err(BodyPolicyServiceError::First(BodyPolicyError::PayloadTooLarge));
+                    }
+
+                    // dispatcher enforces the overridden limit while decoding. body is only counted
+                    // here when it does not come from one.
+                    match req.req().extensions().get::<RequestBodyLimit>() {
+                        Some(shared_limit) => {
+                            shared_limit.set(max);
+                            shared = Some(shared_limit.clone());
+                        }
+                        None => limit = Some(max),
+                    }
+                }
+
+                if !policy.content_type_allowed(headers) {
+                    policy.notify(addr, BodyViolation::UnsupportedMediaType);
+                    return Err(BodyPolicyServiceError::First(BodyPolicyError::UnsupportedMediaType));
+                }
+            }
+
+            let (parts, ext) = req.take_request().into_parts();
+            let ctx = req.ctx;
+            let (ext, body) = ext.replace_body(());
+            let mut body = RefCell::new(PolicyBody {
+                limit,
+                shared,
+                record: 0,
+                observer: policy.observer,
+                addr,
+                body,
+            });
+            let mut req = Request::from_parts(parts, ext);
+
+            let req = WebRequest::new(&mut req, &mut body, ctx);
+
+            let res = self.service.call(req).await.map_err(BodyPolicyServiceError::Second)?;
+
+            match policy.response_max_size {
+                Some(limit) if in_scope => self.limit_response(res, limit, addr),
+                _ => Ok(res),
+            }
+        }
+    }
+}
+
+impl<G, S> BodyPolicyService<G, S> {
+    fn limit_response<E>(
+        &self,
+        res: WebResponse,
+        limit: usize,
+        addr: SocketAddr,
+    ) -> Result<WebResponse, BodyPolicyServiceError<E>> {
+        let policy = &self.policy;
+        let action = policy.response_action;
+        let violation = BodyViolation::ResponseTooLarge {
+            limit,
+            truncated: action == ResponseAction::Truncate,
+        };
+
+        let (mut parts, body) = res.into_parts();
+
+        let body = match body {
+            ResponseBody::Bytes { bytes } if bytes.len() > limit => {
+                policy.notify(addr, violation);
+                match action {
+                    ResponseAction::Reject => {
+                        return Err(BodyPolicyServiceError::First(BodyPolicyError::ResponseTooLarge(
+                            policy.response_error_status,
+                        )))
+                    }
+                    ResponseAction::Truncate => {
+                        warn!("response body of {} bytes truncated to {limit} bytes", bytes.len());
+                        if parts.headers.contains_key(CONTENT_LENGTH) {
+                            parts.headers.insert(CONTENT_LENGTH, HeaderValue::from(limit));
+                        }
+                        ResponseBody::bytes(bytes.slice(..limit))
+                    }
+                }
+            }
+            ResponseBody::Stream { stream } => {
+                let size = match stream.size_hint() {
+                    (low, Some(up)) if low == up => Some(up),
+                    _ => None,
+                };
+
+                if size.is_some_and(|size| size > limit) {
+                    if action == ResponseAction::Reject {
+                        policy.notify(addr, violation);
+                        return Err(BodyPolicyServiceError::First(BodyPolicyError::ResponseTooLarge(
+                            policy.response_error_status,
+                        )));
+                    }
+                    if parts.headers.contains_key(CONTENT_LENGTH) {
+                        parts.headers.insert(CONTENT_LENGTH, HeaderValue::from(limit));
+                    }
+                }
+
+                ResponseBody::stream(BoxStream::new(CappedStream {
+                    stream,
+                    limit,
+                    record: 0,
+                    action,
+                    observer: policy.observer,
+                    addr,
+                }))
+            }
+            body => body,
+        };
+
+        Ok(WebResponse::from_parts(parts, body))
+    }
+}
+
+impl<G, S> ReadyService for BodyPolicyService<G, S>
+where
+    S: ReadyService,
+{
+    type Ready = S::Ready;
+    type Future<'f> = S::Future<'f> where Self: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::Future<'_> {
+        self.service.ready()
+    }
+}
+
+pin_project! {
+    /// Request body type counting bytes against limit of [BodyPolicy].
+    pub struct PolicyBody<B> {
+        limit: Option<u64>,
+        shared: Option<RequestBodyLimit>,
+        record: u64,
+        observer: Option<Observer>,
+        addr: SocketAddr,
+        #[pin]
+        body: B
+    }
+}
+
+impl<B: Default> Default for PolicyBody<B> {
+    fn default() -> Self {
+        Self {
+            limit: None,
+            shared: None,
+            record: 0,
+            observer: None,
+            addr: SocketAddr::from(([0, 0, 0, 0], 0)),
+            body: B::default(),
+        }
+    }
+}
+
+impl<B> Stream for PolicyBody<B>
+where
+    B: BodyStream,
+{
+    type Item = Result<B::Chunk, PolicyBodyError<B::Error>>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+
+        let Some(limit) = *this.limit else {
+            let res = ready!(this.body.poll_next(cx));
+            if let Some(shared) = this.shared.as_ref() {
+                if matches!(res, Some(Err(_))) && shared.is_exceeded() {
+                    notify(*this.observer, *this.addr, BodyViolation::RequestTooLarge { limit: shared.get() });
+                }
+            }
+            return Poll::Ready(res.map(|res| res.map_err(PolicyBodyError::Second)));
+        };
+
+        if *this.record > limit {
+            return Poll::Ready(Some(Err(PolicyBodyError::First(BodyPolicyError::PayloadTooLarge))));
+        }
+
+        match ready!(this.body.poll_next(cx)) {
+            Some(res) => {
+                let chunk = res.map_err(PolicyBodyError::Second)?;
+                *this.record += chunk.as_ref().len() as u64;
+                if *this.record > limit {
+                    notify(*this.observer, *this.addr, BodyViolation::RequestTooLarge { limit });
+                    return Poll::Ready(Some(Err(PolicyBodyError::First(BodyPolicyError::PayloadTooLarge))));
+                }
+                Poll::Ready(Some(Ok(chunk)))
+            }
+            None => Poll::Ready(None),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.body.size_hint()
+    }
+}
+
+pub type PolicyBodyError<E> = PipelineE<BodyPolicyError, E>;
+
+pin_project! {
+    struct CappedStream {
+        #[pin]
+        stream: BoxStream,
+        limit: usize,
+        record: usize,
+        action: ResponseAction,
+        observer: Option<Observer>,
+        addr: SocketAddr,
+    }
+}
+
+impl Stream for CappedStream {
+    type Item = Result<Bytes, BodyError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+
+        if *this.record >= *this.limit && *this.action == ResponseAction::Truncate {
+            return Poll::Ready(None);
+        }
+
+        let mut chunk = match ready!(this.stream.poll_next(cx)) {
+            Some(Ok(chunk)) => chunk,
+            res => return Poll::Ready(res),
+        };
+
+        let remaining = *this.limit - *this.record;
+        if chunk.len() > remaining {
+            let action = *this.action;
+            let violation = BodyViolation::ResponseTooLarge {
+                limit: *this.limit,
+                truncated: action == ResponseAction::Truncate,
+            };
+            notify(*this.observer, *this.addr, violation);
+
+            match action {
+                ResponseAction::Reject => {
+                    return Poll::Ready(Some(Err(BodyError::Overflow { limit: *this.limit })));
+                }
+                ResponseAction::Truncate => {
+                    warn!("streaming response body truncated to {} bytes", *this.limit);
+                    chunk.truncate(remaining);
+                }
+            }
+        }
+
+        *this.record += chunk.len();
+        Poll::Ready(Some(Ok(chunk)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (low, up) = self.stream.size_hint();
+        match self.action {
+            ResponseAction::Reject => (low, up),
+            ResponseAction::Truncate => (low.min(self.limit), Some(up.unwrap_or(self.limit).min(self.limit))),
+        }
+    }
+}
+
+/// Error type for request rejected by [BodyPolicy] middleware.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BodyPolicyError {
+    /// Request body is larger than limit. Responded with `413 Payload Too Large`.
+    PayloadTooLarge,
+    /// Request content type is not allowed. Responded with `415 Unsupported Media Type`.
+    UnsupportedMediaType,
+    /// Response body is larger than limit. Responded with the status code set by
+    /// [BodyPolicy::response_error_status].
+    ResponseTooLarge(StatusCode),
+}
+
+impl fmt::Display for BodyPolicyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::PayloadTooLarge => f.write_str("request body too large"),
+            Self::UnsupportedMediaType => f.write_str("request content type not allowed"),
+            Self::ResponseTooLarge(_) => f.write_str("response body too large"),
+        }
+    }
+}
+
+impl error::Error for BodyPolicyError {}
+
+impl<'r, C, B> Responder<WebRequest<'r, C, B>> for BodyPolicyError {
+    type Output = WebResponse;
+    type Future = impl Future<Output = Self::Output>;
+
+    fn respond_to(self, req: WebRequest<'r, C, B>) -> Self::Future {
+        let mut res = req.into_response(format!("{self}"));
+        res.headers_mut().insert(CONTENT_TYPE, TEXT_UTF8);
+        *res.status_mut() = match self {
+            Self::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::UnsupportedMediaType => StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            Self::ResponseTooLarge(status) => status,
+        };
+        async { res }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::cell::RefCell;
+
+    use futures_util::stream;
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::{
+        handler::handler_service,
+        http::Method,
+        route::{get, post},
+        test::TestRequest,
+        App,
+    };
+
+    use super::*;
+
+    thread_local! {
+        static VIOLATIONS: RefCell<Vec<BodyViolation>> = const { RefCell::new(Vec::new()) };
+    }
+
+    fn observer(_: SocketAddr, event: ConnectionEvent) {
+        if let ConnectionEvent::BodyViolation(violation) = event {
+            VIOLATIONS.with(|v| v.borrow_mut().push(violation));
+        }
+    }
+
+    fn violations() -> Vec<BodyViolation> {
+        VIOLATIONS.with(|v| v.take())
+    }
+
+    async fn echo(body: String) -> String {
+        body
+    }
+
+    async fn bytes() -> &'static str {
+        "hello,world"
+    }
+
+    async fn stream() -> WebResponse {
+        let chunks = (0..3).map(|_| Ok::<_, Infallible>(Bytes::from_static(b"abcd")));
+        WebResponse::new(ResponseBody::box_stream(stream::iter(chunks)))
+    }
+
+    #[test]
+    fn request_size() {
+        let service = App::new()
+            .at("/", post(handler_service(echo)))
+            .enclosed(BodyPolicy::new().request_max_size(4).observer(observer))
+            .finish_for_test()
+            .now_or_panic();
+
+        let req = TestRequest::get("/").method(Method::POST).body("abcd");
+        let res = service.call(req).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK);
+        assert_eq!(res.string_body().now_or_panic().unwrap(), "abcd");
+        assert!(violations().is_empty());
+
+        let req = TestRequest::get("/").method(Method::POST).body("abcde");
+        let res = service.call(req).now_or_panic().unwrap();
+        res.assert_status(StatusCode::PAYLOAD_TOO_LARGE);
+        assert_eq!(res.string_body().now_or_panic().unwrap(), "request body too large");
+        assert_eq!(violations(), [BodyViolation::RequestTooLarge { limit: 4 }]);
+
+        // body without content length is aborted when it goes past limit.
+        let chunks = [
+            Ok::<_, Infallible>(Bytes::from_static(b"abc")),
+            Ok(Bytes::from_static(b"de")),
+        ];
+        let req = TestRequest::get("/").method(Method::POST).stream(stream::iter(chunks));
+        let res = service.call(req).now_or_panic().unwrap();
+        res.assert_status(StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(violations(), [BodyViolation::RequestTooLarge { limit: 4 }]);
+    }
+
+    #[test]
+    fn content_type() {
+        let service = App::new()
+            .at("/public", post(handler_service(echo)).get(handler_service(bytes)))
+            .at("/private", post(handler_service(echo)))
+            .enclosed(
+                BodyPolicy::new()
+                    .allow_content_type("image/*")
+                    .allow_content_type("application/json")
+                    .observer(observer)
+                    .scope(|head: &Head<'_>| head.uri().path().starts_with("/public")),
+            )
+            .finish_for_test()
+            .now_or_panic();
+
+        let post = |path: &'static str, content_type: Option<&'static str>| {
+            let req = TestRequest::get(path).method(Method::POST).body("996");
+            match content_type {
+                Some(content_type) => req.header(CONTENT_TYPE, content_type),
+                None => req,
+            }
+        };
+
+        for content_type in ["image/png", "IMAGE/jpeg", "application/json; charset=utf-8"] {
+            let res = service
+                .call(post("/public", Some(content_type)))
+                .now_or_panic()
+                .unwrap();
+            res.assert_status(StatusCode::OK);
+        }
+
+        for content_type in [Some("text/plain"), Some("application/jsonp"), Some("image"), None] {
+            let res = service.call(post("/public", content_type)).now_or_panic().unwrap();
+            res.assert_status(StatusCode::UNSUPPORTED_MEDIA_TYPE);
+        }
+        assert_eq!(violations(), [BodyViolation::UnsupportedMediaType; 4]);
+
+        // request without body is not checked.
+        let res = service.call(TestRequest::get("/public")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK);
+
+        // request out of scope is not checked.
+        let res = service
+            .call(post("/private", Some("text/plain")))
+            .now_or_panic()
+            .unwrap();
+        res.assert_status(StatusCode::OK);
+        assert!(violations().is_empty());
+    }
+
+    #[test]
+    fn response_bytes() {
+        let service = App::new()
+            .at("/", get(handler_service(bytes)))
+            .enclosed(BodyPolicy::new().response_max_size(5).observer(observer))
+            .finish_for_test()
+            .now_or_panic();
+
+        let res = service.call(TestRequest::get("/")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(res.string_body().now_or_panic().unwrap(), "response body too large");
+        assert_eq!(
+            violations(),
+            [BodyViolation::ResponseTooLarge {
+                limit: 5,
+                truncated: false
+            }]
+        );
+
+        let service = App::new()
+            .at("/", get(handler_service(bytes)))
+            .enclosed(
+                BodyPolicy::new()
+                    .response_max_size(5)
+                    .response_error_status(StatusCode::BAD_GATEWAY),
+            )
+            .finish_for_test()
+            .now_or_panic();
+
+        let res = service.call(TestRequest::get("/")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::BAD_GATEWAY);
+
+        let service = App::new()
+            .at("/", get(handler_service(bytes)))
+            .enclosed(
+                BodyPolicy::new()
+                    .response_max_size(5)
+                    .response_action(ResponseAction::Truncate),
+            )
+            .finish_for_test()
+            .now_or_panic();
+
+        let res = service.call(TestRequest::get("/")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK);
+        assert_eq!(res.string_body().now_or_panic().unwrap(), "hello");
+    }
+
+    #[test]
+    fn response_stream() {
+        let service = App::new()
+            .at("/", get(handler_service(stream)))
+            .enclosed(BodyPolicy::new().response_max_size(6).observer(observer))
+            .finish_for_test()
+            .now_or_panic();
+
+        // response head is sent before body goes past limit.
+        let res = service.call(TestRequest::get("/")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK);
+        assert!(violations().is_empty());
+
+        let mut body = res.into_inner().into_body();
+        let chunk = stream_next(&mut body).unwrap().unwrap();
+        assert_eq!(chunk, "abcd");
+        assert!(violations().is_empty());
+        assert!(stream_next(&mut body).unwrap().is_err());
+        assert_eq!(
+            violations(),
+            [BodyViolation::ResponseTooLarge {
+                limit: 6,
+                truncated: false
+            }]
+        );
+
+        let service = App::new()
+            .at("/", get(handler_service(stream)))
+            .enclosed(
+                BodyPolicy::new()
+                    .response_max_size(6)
+                    .response_action(ResponseAction::Truncate),
+            )
+            .finish_for_test()
+            .now_or_panic();
+
+        let res = service.call(TestRequest::get("/")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK);
+        assert_eq!(res.string_body().now_or_panic().unwrap(), "abcdab");
+    }
+
+    fn stream_next<S>(stream: &mut S) -> Option<S::Item>
+    where
+        S: Stream + Unpin,
+    {
+        core::future::poll_fn(|cx| Pin::new(&mut *stream).poll_next(cx)).now_or_panic()
+    }
+}
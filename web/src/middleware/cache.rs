@@ -0,0 +1,540 @@
+//! bounded in-memory response caching middleware.
+
+use core::{convert::Infallible, future::Future, time::Duration};
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{Arc, Mutex},
+};
+
+use tokio::time::Instant;
+use xitca_http::{
+    date::{DateTime, DateTimeService},
+    util::header::int_header_value,
+};
+
+use crate::{
+    body::ResponseBody,
+    dev::{
+        bytes::Bytes,
+        service::{ready::ReadyService, Service},
+    },
+    http::{
+        header::{HeaderMap, HeaderName, HeaderValue, AGE, CACHE_CONTROL, DATE, SET_COOKIE, VARY},
+        Method, StatusCode,
+    },
+    request::WebRequest,
+    response::WebResponse,
+};
+
+/// In-memory response caching middleware for micro caching hot `GET` and `HEAD` responses.
+///
+/// Responses are keyed by method, path and query of request plus the values of request headers
+/// named in response's `Vary` header. A response is stored when:
+/// - it's status code is cacheable. (200, 301 and 404 by default)
+/// - it's body is in bytes form and not larger than max body size. Streaming body bypasses cache.
+/// - it has no `Set-Cookie` header and no `Cache-Control: no-store` directive.
+/// - it's `Vary` header is not `*`.
+///
+/// Request with `Cache-Control: no-store` directive bypasses cache entirely.
+///
+/// Cached response carries status, headers and body of stored response with an additional `Age`
+/// header. Age is computed from low resolution date service of worker thread so it's accurate to
+/// around half a second.
+///
+/// Entries are shared by all worker threads of server. Entry expires after TTL and least recently
+/// used entry is evicted when max entry count is reached.
+///
+/// # Examples:
+/// ```rust
+/// # use std::{convert::Infallible, time::Duration};
+/// # use xitca_web::{dev::service::fn_service, request::WebRequest, response::WebResponse, App};
+/// use xitca_web::middleware::cache::Cache;
+///
+/// # fn doc_example() {
+/// App::new()
+///     .at("/", fn_service(handler))
+///     .enclosed(Cache::new(Duration::from_secs(3)).max_entries(512));
+/// # }
+///
+/// # async fn handler(req: WebRequest<'_>) -> Result<WebResponse, Infallible> {
+/// #   todo!()
+/// # }
+/// ```
+#[derive(Clone)]
+pub struct Cache {
+    ttl: Duration,
+    max_entries: usize,
+    max_body_size: usize,
+    status: Vec<StatusCode>,
+    store: Arc<Mutex<Store>>,
+}
+
+impl Cache {
+    /// Construct middleware caching responses for given TTL.
+    ///
+    /// Default to max 1024 entries and max 64KiB body size.
+    ///
+    /// # Panics:
+    ///
+    /// When ttl is zero.
+    pub fn new(ttl: Duration) -> Self {
+        assert!(!ttl.is_zero(), "Cache ttl must not be zero");
+        Self {
+            ttl,
+            max_entries: 1024,
+            max_body_size: 64 * 1024,
+            status: vec![StatusCode::OK, StatusCode::MOVED_PERMANENTLY, StatusCode::NOT_FOUND],
+            store: Arc::new(Mutex::new(Store::default())),
+        }
+    }
+
+    /// Set max count of cached entries.
+    ///
+    /// # Panics:
+    ///
+    /// When max is zero.
+    pub fn max_entries(mut self, max: usize) -> Self {
+        assert!(max > 0, "Cache max_entries must not be zero");
+        self.max_entries = max;
+        self
+    }
+
+    /// Set max body size in bytes of cached response.
+    pub fn max_body_size(mut self, max: usize) -> Self {
+        self.max_body_size = max;
+        self
+    }
+
+    /// Set status codes of response that can be cached. Override the default 200, 301 and 404.
+    pub fn status(mut self, status: impl IntoIterator<Item = StatusCode>) -> Self {
+        self.status = status.into_iter().collect();
+        self
+    }
+}
+
+impl<S> Service<S> for Cache {
+    type Response = CacheService<S>;
+    type Error = Infallible;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where S: 'f;
+
+    fn call<'s>(&'s self, service: S) -> Self::Future<'s>
+    where
+        S: 's,
+    {
+        async {
+            Ok(CacheService {
+                service,
+                cache: self.clone(),
+                date: DateTimeService::current(),
+            })
+        }
+    }
+}
+
+pub struct CacheService<S> {
+    service: S,
+    cache: Cache,
+    date: DateTimeService,
+}
+
+impl<'r, S, C, B, ResB, Err> Service<WebRequest<'r, C, B>> for CacheService<S>
+where
+    C: 'r,
+    B: 'r,
+    S: for<'rs> Service<WebRequest<'rs, C, B>, Response = WebResponse<ResponseBody<ResB>>, Error = Err>,
+{
+    type Response = WebResponse<ResponseBody<ResB>>;
+    type Error = Err;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where Self: 'f, 'r: 'f;
+
+    fn call<'s>(&'s self, mut req: WebRequest<'r, C, B>) -> Self::Future<'s>
+    where
+        'r: 's,
+    {
+        async move {
+            let head = req.req();
+            if !matches!(*head.method(), Method::GET | Method::HEAD) || no_store(head.headers()) {
+                return self.service.call(req).await;
+            }
+
+            let key = (
+                head.method().clone(),
+                head.uri().path_and_query().map(|p| p.as_str()).unwrap_or("/").into(),
+            );
+            let now = self.date.get().now();
+
+            if let Some(res) = self.cache.get(&key, head.headers(), now) {
+                return Ok(res);
+            }
+
+            let res = self.service.call(req.reborrow()).await?;
+
+            let Some(vary) = self.cache.cacheable(&res) else {
+                return Ok(res);
+            };
+
+            let (parts, body) = res.into_parts();
+
+            let body = match body {
+                ResponseBody::None => None,
+                ResponseBody::Bytes { bytes } if bytes.len() <= self.cache.max_body_size => Some(bytes),
+                body => return Ok(WebResponse::from_parts(parts, body)),
+            };
+
+            let values = vary.iter().map(|name| req.req().headers().get(name).cloned()).collect();
+
+            let mut headers = parts.headers.clone();
+            headers.remove(DATE);
+            headers.remove(AGE);
+
+            let entry = Entry {
+                status: parts.status,
+                headers,
+                body: body.clone(),
+                stored_at: now,
+            };
+
+            self.cache
+                .store
+                .lock()
+                .unwrap()
+                .insert(key, vary, values, entry, self.cache.max_entries);
+
+            let body = body
+                .map(|bytes| ResponseBody::Bytes { bytes })
+                .unwrap_or(ResponseBody::None);
+            Ok(WebResponse::from_parts(parts, body))
+        }
+    }
+}
+
+impl<S> ReadyService for CacheService<S>
+where
+    S: ReadyService,
+{
+    type Ready = S::Ready;
+    type Future<'f> = S::Future<'f> where S: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::Future<'_> {
+        self.service.ready()
+    }
+}
+
+impl Cache {
+    fn get<B>(&self, key: &Key, headers: &HeaderMap, now: Instant) -> Option<WebResponse<ResponseBody<B>>> {
+        let mut store = self.store.lock().unwrap();
+        let entry = store.get(key, headers, now, self.ttl)?;
+
+        let mut res = WebResponse::new(match entry.body {
+            Some(ref bytes) => ResponseBody::Bytes { bytes: bytes.clone() },
+            None => ResponseBody::None,
+        });
+        *res.status_mut() = entry.status;
+        *res.headers_mut() = entry.headers.clone();
+        let age = now.saturating_duration_since(entry.stored_at).as_secs();
+        res.headers_mut().insert(AGE, int_header_value(age));
+
+        Some(res)
+    }
+
+    // return header names of Vary when response can be cached.
+    fn cacheable<B>(&self, res: &WebResponse<B>) -> Option<Box<[HeaderName]>> {
+        let headers = res.headers();
+
+        if !self.status.contains(&res.status()) || headers.contains_key(SET_COOKIE) || no_store(headers) {
+            return None;
+        }
+
+        let mut vary = Vec::new();
+        for name in directives(headers, VARY) {
+            if name == "*" {
+                return None;
+            }
+            vary.push(HeaderName::try_from(name).ok()?);
+        }
+
+        Some(vary.into_boxed_slice())
+    }
+}
+
+fn directives(headers: &HeaderMap, name: HeaderName) -> impl Iterator<Item = &str> {
+    headers
+        .get_all(name)
+        .into_iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(','))
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+}
+
+fn no_store(headers: &HeaderMap) -> bool {
+    directives(headers, CACHE_CONTROL).any(|d| d.eq_ignore_ascii_case("no-store"))
+}
+
+// method, path and query of request.
+type Key = (Method, Box<str>);
+
+struct Entry {
+    status: StatusCode,
+    headers: HeaderMap,
+    // None when response has no body.
+    body: Option<Bytes>,
+    stored_at: Instant,
+}
+
+// all variants of the same key share the header names of Vary. A new Vary from response of the
+// key drops all existing variants.
+struct Slot {
+    vary: Box<[HeaderName]>,
+    variants: Vec<Variant>,
+}
+
+struct Variant {
+    values: Box<[Option<HeaderValue>]>,
+    tick: u64,
+    entry: Entry,
+}
+
+#[derive(Default)]
+struct Store {
+    tick: u64,
+    slots: HashMap<Key, Slot>,
+    // key of variants ordered by their last access. least recently used first.
+    lru: BTreeMap<u64, Key>,
+}
+
+impl Store {
+    fn get(&mut self, key: &Key, headers: &HeaderMap, now: Instant, ttl: Duration) -> Option<&Entry> {
+        let slot = self.slots.get_mut(key)?;
+        let idx = slot.variants.iter().position(|v| {
+            slot.vary
+                .iter()
+                .zip(v.values.iter())
+                .all(|(n, v)| headers.get(n) == v.as_ref())
+        })?;
+
+        if now.saturating_duration_since(slot.variants[idx].entry.stored_at) >= ttl {
+            let variant = slot.variants.swap_remove(idx);
+            if slot.variants.is_empty() {
+                self.slots.remove(key);
+            }
+            self.lru.remove(&variant.tick);
+            return None;
+        }
+
+        self.tick += 1;
+        let variant = &mut self.slots.get_mut(key)?.variants[idx];
+        let key = self.lru.remove(&variant.tick).expect("variant must be tracked by lru");
+        variant.tick = self.tick;
+        self.lru.insert(self.tick, key);
+
+        Some(&variant.entry)
+    }
+
+    fn insert(
+        &mut self,
+        key: Key,
+        vary: Box<[HeaderName]>,
+        values: Box<[Option<HeaderValue>]>,
+        entry: Entry,
+        max: usize,
+    ) {
+        self.tick += 1;
+        let tick = self.tick;
+
+        let slot = self.slots.entry(key.clone()).or_insert_with(|| Slot {
+            vary: Box::new([]),
+            variants: Vec::new(),
+        });
+
+        if slot.vary != vary {
+            for v in slot.variants.drain(..) {
+                self.lru.remove(&v.tick);
+            }
+            slot.vary = vary;
+        }
+
+        if let Some(idx) = slot.variants.iter().position(|v| v.values == values) {
+            let v = slot.variants.swap_remove(idx);
+            self.lru.remove(&v.tick);
+        }
+
+        slot.variants.push(Variant { values, tick, entry });
+        self.lru.insert(tick, key);
+
+        while self.lru.len() > max {
+            self.evict();
+        }
+    }
+
+    fn evict(&mut self) {
+        let Some((tick, key)) = self.lru.pop_first() else {
+            return;
+        };
+        if let Some(slot) = self.slots.get_mut(&key) {
+            slot.variants.retain(|v| v.tick != tick);
+            if slot.variants.is_empty() {
+                self.slots.remove(&key);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::sync::atomic::{AtomicUsize, Ordering};
+
+    use tokio::task::LocalSet;
+
+    use crate::{handler::handler_service, http::header::ACCEPT_ENCODING, test::TestRequest, App};
+
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn hit() {
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        LocalSet::new()
+            .run_until(async {
+                let service = App::new()
+                    .at(
+                        "/",
+                        handler_service(|| async {
+                            CALLS.fetch_add(1, Ordering::Relaxed);
+                            "hello"
+                        }),
+                    )
+                    .enclosed(Cache::new(Duration::from_secs(3)))
+                    .finish_for_test()
+                    .await;
+
+                let res = service.call(TestRequest::get("/")).await.unwrap();
+                res.assert_status(StatusCode::OK);
+                assert!(res.headers().get(AGE).is_none());
+
+                // date service updates every 500 milliseconds.
+                tokio::time::sleep(Duration::from_millis(1500)).await;
+
+                let res = service.call(TestRequest::get("/")).await.unwrap();
+                res.assert_status(StatusCode::OK).assert_header(AGE, "1");
+                let body = res.string_body().await.unwrap();
+                assert_eq!(body, "hello");
+                assert_eq!(CALLS.load(Ordering::Relaxed), 1);
+
+                // query is part of key.
+                service.call(TestRequest::get("/?foo=bar")).await.unwrap();
+                assert_eq!(CALLS.load(Ordering::Relaxed), 2);
+
+                // request with no-store bypasses cache.
+                let req = TestRequest::get("/").header(CACHE_CONTROL, "max-age=0, no-store");
+                let res = service.call(req).await.unwrap();
+                assert!(res.headers().get(AGE).is_none());
+                assert_eq!(CALLS.load(Ordering::Relaxed), 3);
+            })
+            .await
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn vary() {
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        async fn handler(req: &WebRequest<'_>) -> WebResponse {
+            CALLS.fetch_add(1, Ordering::Relaxed);
+            let encoding = req.req().headers().get(ACCEPT_ENCODING).unwrap().to_str().unwrap();
+            let mut res = WebResponse::new(encoding.to_owned().into());
+            res.headers_mut()
+                .insert(VARY, HeaderValue::from_static("accept-encoding"));
+            res
+        }
+
+        LocalSet::new()
+            .run_until(async {
+                let service = App::new()
+                    .at("/", handler_service(handler))
+                    .enclosed(Cache::new(Duration::from_secs(3)))
+                    .finish_for_test()
+                    .await;
+
+                let req = |encoding| TestRequest::get("/").header(ACCEPT_ENCODING, encoding);
+
+                for _ in 0..2 {
+                    for encoding in ["gzip", "br"] {
+                        let res = service.call(req(encoding)).await.unwrap();
+                        res.assert_header(VARY, "accept-encoding");
+                        let body = res.string_body().await.unwrap();
+                        assert_eq!(body, encoding);
+                    }
+                }
+
+                assert_eq!(CALLS.load(Ordering::Relaxed), 2);
+            })
+            .await
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn expire() {
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        async fn handler() -> WebResponse {
+            CALLS.fetch_add(1, Ordering::Relaxed);
+            let mut res = WebResponse::new(ResponseBody::None);
+            *res.status_mut() = StatusCode::NOT_FOUND;
+            res
+        }
+
+        LocalSet::new()
+            .run_until(async {
+                let service = App::new()
+                    .at("/", handler_service(handler))
+                    .enclosed(Cache::new(Duration::from_secs(2)))
+                    .finish_for_test()
+                    .await;
+
+                for _ in 0..2 {
+                    let res = service.call(TestRequest::get("/")).await.unwrap();
+                    res.assert_status(StatusCode::NOT_FOUND);
+                }
+                assert_eq!(CALLS.load(Ordering::Relaxed), 1);
+
+                tokio::time::sleep(Duration::from_secs(3)).await;
+
+                let res = service.call(TestRequest::get("/")).await.unwrap();
+                assert!(res.headers().get(AGE).is_none());
+                assert_eq!(CALLS.load(Ordering::Relaxed), 2);
+            })
+            .await
+    }
+
+    #[test]
+    fn evict() {
+        let now = Instant::now();
+        let mut store = Store::default();
+
+        let key = |path: &str| (Method::GET, Box::<str>::from(path));
+        let entry = || Entry {
+            status: StatusCode::OK,
+            headers: HeaderMap::new(),
+            body: None,
+            stored_at: now,
+        };
+        let ttl = Duration::from_secs(1);
+
+        for path in ["/1", "/2", "/3"] {
+            store.insert(key(path), Box::new([]), Box::new([]), entry(), 2);
+        }
+        assert!(store.get(&key("/1"), &HeaderMap::new(), now, ttl).is_none());
+
+        // access makes entry most recently used.
+        assert!(store.get(&key("/2"), &HeaderMap::new(), now, ttl).is_some());
+        store.insert(key("/4"), Box::new([]), Box::new([]), entry(), 2);
+        assert!(store.get(&key("/3"), &HeaderMap::new(), now, ttl).is_none());
+        assert!(store.get(&key("/2"), &HeaderMap::new(), now, ttl).is_some());
+
+        // expired entry is removed on access.
+        assert!(store.get(&key("/4"), &HeaderMap::new(), now + ttl, ttl).is_none());
+        assert_eq!(store.lru.len(), 1);
+        assert_eq!(store.slots.len(), 1);
+    }
+}
@@ -0,0 +1,328 @@
+//! Client certificate middleware.
+//!
+//! See [RequireClientCert] and [ClientCertInfo] for usage.
+
+use core::{convert::Infallible, fmt, future::Future};
+
+use std::{error, time::SystemTime};
+
+use crate::{
+    dev::service::{pipeline::PipelineE, ready::ReadyService, Service},
+    handler::{client_cert::ClientCertInfo, Responder},
+    http::{const_header_value::TEXT_UTF8, header::CONTENT_TYPE, PeerCertificates, StatusCode},
+    request::WebRequest,
+    response::WebResponse,
+    route::guard::{Guard, Head},
+};
+
+/// Middleware for rejecting request without a valid client certificate.
+///
+/// The leaf certificate client presented in tls handshake is parsed and it's validity window is
+/// checked against current time for every request. This makes long lived connection stop
+/// passing the middleware once it's certificate is expired. Parsed [ClientCertInfo] is inserted
+/// into request extensions. Request without certificate or with invalid one is rejected with
+/// `403 Forbidden`.
+///
+/// Tls acceptor must request certificate from client. For rustls with
+/// `AllowAnyAnonymousOrAuthenticatedClient` verifier routes skipped by [RequireClientCert::skip]
+/// stay accessible without certificate on the same listener.
+///
+/// # Examples:
+/// ```rust
+/// # use xitca_web::{handler::{client_cert::ClientCertInfo, handler_service}, request::WebRequest, route::{get, guard::Head}, App};
+/// use xitca_web::middleware::client_cert::RequireClientCert;
+///
+/// async fn admin(cert: ClientCertInfo, _: &WebRequest<'_>) -> String {
+///     cert.subject().to_owned()
+/// }
+///
+/// App::new()
+///     .at("/", get(handler_service(|| async { "public" })))
+///     .at("/admin", get(handler_service(admin)))
+///     .enclosed(
+///         RequireClientCert::new()
+///             // only certificate of admin is allowed.
+///             .allow(|cert: &ClientCertInfo| cert.common_name() == Some("admin"))
+///             // certificate is only required for admin route.
+///             .skip(|head: &Head<'_>| !head.uri().path().starts_with("/admin")),
+///     );
+/// ```
+#[derive(Clone)]
+pub struct RequireClientCert<P = fn(&ClientCertInfo) -> bool, G = ()> {
+    allow: Option<P>,
+    skip: Option<G>,
+}
+
+impl RequireClientCert {
+    /// Construct middleware accepting any valid client certificate.
+    pub fn new() -> Self {
+        Self {
+            allow: None,
+            skip: None,
+        }
+    }
+}
+
+impl Default for RequireClientCert {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P, G> RequireClientCert<P, G> {
+    /// Only accept certificate passing given predicate. Certificate failed it is rejected with
+    /// `403 Forbidden`.
+    pub fn allow<P2>(self, predicate: P2) -> RequireClientCert<P2, G>
+    where
+        P2: Fn(&ClientCertInfo) -> bool,
+    {
+        RequireClientCert {
+            allow: Some(predicate),
+            skip: self.skip,
+        }
+    }
+
+    /// Skip certificate check for request passing given [Guard].
+    pub fn skip<G2>(self, guard: G2) -> RequireClientCert<P, G2>
+    where
+        G2: Guard,
+    {
+        RequireClientCert {
+            allow: self.allow,
+            skip: Some(guard),
+        }
+    }
+}
+
+impl<P, G, S> Service<S> for RequireClientCert<P, G>
+where
+    P: Clone,
+    G: Clone,
+{
+    type Response = RequireClientCertService<P, G, S>;
+    type Error = Infallible;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where Self: 'f, S: 'f;
+
+    fn call<'s>(&'s self, service: S) -> Self::Future<'s>
+    where
+        S: 's,
+    {
+        async {
+            Ok(RequireClientCertService {
+                service,
+                allow: self.allow.clone(),
+                skip: self.skip.clone(),
+            })
+        }
+    }
+}
+
+pub struct RequireClientCertService<P, G, S> {
+    service: S,
+    allow: Option<P>,
+    skip: Option<G>,
+}
+
+pub type RequireClientCertServiceError<E> = PipelineE<ClientCertRejected, E>;
+
+impl<'r, P, G, S, C, B, Res, Err> Service<WebRequest<'r, C, B>> for RequireClientCertService<P, G, S>
+where
+    C: 'r,
+    B: 'r,
+    P: Fn(&ClientCertInfo) -> bool,
+    G: Guard,
+    S: for<'rs> Service<WebRequest<'rs, C, B>, Response = Res, Error = Err>,
+{
+    type Response = Res;
+    type Error = RequireClientCertServiceError<Err>;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where Self: 'f, 'r: 'f;
+
+    fn call<'s>(&'s self, mut req: WebRequest<'r, C, B>) -> Self::Future<'s>
+    where
+        'r: 's,
+    {
+        async move {
+            if !self
+                .skip
+                .as_ref()
+                .is_some_and(|guard| guard.check(&Head::new(req.req())))
+            {
+                let info = self.verify(req.req().extensions().get::<PeerCertificates>());
+                let info = info.map_err(RequireClientCertServiceError::First)?;
+                req.req_mut().extensions_mut().insert(info);
+            }
+
+            self.service
+                .call(req)
+                .await
+                .map_err(RequireClientCertServiceError::Second)
+        }
+    }
+}
+
+impl<P, G, S> RequireClientCertService<P, G, S>
+where
+    P: Fn(&ClientCertInfo) -> bool,
+{
+    fn verify(&self, certs: Option<&PeerCertificates>) -> Result<ClientCertInfo, ClientCertRejected> {
+        let certs = certs.ok_or(ClientCertRejected::Missing)?;
+        let info = ClientCertInfo::from_der(certs.leaf().clone()).map_err(|_| ClientCertRejected::Malformed)?;
+
+        if !info.is_valid_at(SystemTime::now()) {
+            return Err(ClientCertRejected::Expired);
+        }
+
+        if self.allow.as_ref().is_some_and(|allow| !allow(&info)) {
+            return Err(ClientCertRejected::NotAllowed);
+        }
+
+        Ok(info)
+    }
+}
+
+impl<P, G, S> ReadyService for RequireClientCertService<P, G, S>
+where
+    S: ReadyService,
+{
+    type Ready = S::Ready;
+    type Future<'f> = S::Future<'f> where Self: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::Future<'_> {
+        self.service.ready()
+    }
+}
+
+/// Error type for request rejected by [RequireClientCert] middleware.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ClientCertRejected {
+    /// Client did not present certificate.
+    Missing,
+    /// Certificate can not be parsed.
+    Malformed,
+    /// Current time is outside of certificate's validity window.
+    Expired,
+    /// Certificate is rejected by predicate passed to [RequireClientCert::allow].
+    NotAllowed,
+}
+
+impl fmt::Display for ClientCertRejected {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Missing => f.write_str("client certificate required"),
+            Self::Malformed => f.write_str("client certificate malformed"),
+            Self::Expired => f.write_str("client certificate expired or not yet valid"),
+            Self::NotAllowed => f.write_str("client certificate not allowed"),
+        }
+    }
+}
+
+impl error::Error for ClientCertRejected {}
+
+impl<'r, C, B> Responder<WebRequest<'r, C, B>> for ClientCertRejected {
+    type Output = WebResponse;
+    type Future = impl Future<Output = Self::Output>;
+
+    fn respond_to(self, req: WebRequest<'r, C, B>) -> Self::Future {
+        let mut res = req.into_response(format!("{self}"));
+        res.headers_mut().insert(CONTENT_TYPE, TEXT_UTF8);
+        *res.status_mut() = StatusCode::FORBIDDEN;
+        async { res }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::{dev::bytes::Bytes, handler::handler_service, route::get, test::TestRequest, App};
+
+    use super::*;
+
+    fn cert(name: &str, not_after: (i32, u8, u8)) -> PeerCertificates {
+        let mut params = rcgen::CertificateParams::new(Vec::new());
+        params.distinguished_name.push(rcgen::DnType::CommonName, name);
+        params.not_after = rcgen::date_time_ymd(not_after.0, not_after.1, not_after.2);
+        let der = rcgen::Certificate::from_params(params)
+            .unwrap()
+            .serialize_der()
+            .unwrap();
+        PeerCertificates::new(vec![Bytes::from(der)])
+    }
+
+    async fn admin(cert: ClientCertInfo) -> String {
+        cert.common_name().unwrap().to_owned()
+    }
+
+    #[test]
+    fn route() {
+        let service = App::new()
+            .at("/", get(handler_service(|| async { "public" })))
+            .at("/admin", get(handler_service(admin)))
+            .enclosed(
+                RequireClientCert::new()
+                    .allow(|cert: &ClientCertInfo| cert.common_name() == Some("admin"))
+                    .skip(|head: &Head<'_>| head.uri().path() != "/admin"),
+            )
+            .finish_for_test()
+            .now_or_panic();
+
+        let res = service.call(TestRequest::get("/")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK);
+
+        let req = TestRequest::get("/").extension(cert("alice", (4096, 1, 1)));
+        let res = service.call(req).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK);
+
+        let res = service.call(TestRequest::get("/admin")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::FORBIDDEN)
+            .assert_header(CONTENT_TYPE, "text/plain; charset=utf-8");
+        assert_eq!(res.string_body().now_or_panic().unwrap(), "client certificate required");
+
+        let req = TestRequest::get("/admin").extension(cert("alice", (4096, 1, 1)));
+        let res = service.call(req).now_or_panic().unwrap();
+        res.assert_status(StatusCode::FORBIDDEN);
+        assert_eq!(
+            res.string_body().now_or_panic().unwrap(),
+            "client certificate not allowed"
+        );
+
+        let req = TestRequest::get("/admin").extension(cert("admin", (2000, 1, 1)));
+        let res = service.call(req).now_or_panic().unwrap();
+        res.assert_status(StatusCode::FORBIDDEN);
+        assert_eq!(
+            res.string_body().now_or_panic().unwrap(),
+            "client certificate expired or not yet valid"
+        );
+
+        let req = TestRequest::get("/admin").extension(PeerCertificates::new(vec![Bytes::from_static(b"bad")]));
+        let res = service.call(req).now_or_panic().unwrap();
+        res.assert_status(StatusCode::FORBIDDEN);
+
+        let req = TestRequest::get("/admin").extension(cert("admin", (4096, 1, 1)));
+        let res = service.call(req).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK);
+        assert_eq!(res.string_body().now_or_panic().unwrap(), "admin");
+    }
+
+    #[test]
+    fn skip() {
+        let service = App::new()
+            .at("/", get(handler_service(admin)))
+            .at("/health", get(handler_service(|| async { "ok" })))
+            .enclosed(RequireClientCert::new().skip(|head: &Head<'_>| head.uri().path() == "/health"))
+            .finish_for_test()
+            .now_or_panic();
+
+        let res = service.call(TestRequest::get("/health")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK);
+
+        let res = service.call(TestRequest::get("/")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::FORBIDDEN);
+
+        let req = TestRequest::get("/").extension(cert("bob", (4096, 1, 1)));
+        let res = service.call(req).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK);
+        assert_eq!(res.string_body().now_or_panic().unwrap(), "bob");
+    }
+}
@@ -5,8 +5,9 @@ use http_encoding::{encoder, Coder, ContentEncoding};
 use crate::{
     body::BodyStream,
     dev::service::{ready::ReadyService, Service},
+    http::header::{HeaderValue, CONTENT_ENCODING, VARY},
     request::WebRequest,
-    response::WebResponse,
+    response::{ResponseHeadersExt, WebResponse},
 };
 
 /// A compress middleware look into [WebRequest]'s `Accept-Encoding` header and
@@ -59,7 +60,15 @@ where
                 _ => {}
             }
 
-            Ok(encoder(res, encoding))
+            let encoded = res.headers().contains_key(CONTENT_ENCODING);
+            let mut res = encoder(res, encoding);
+
+            // response is encoded according to request's Accept-Encoding header.
+            if !encoded && res.headers().contains_key(CONTENT_ENCODING) {
+                res.append_or_merge(VARY, HeaderValue::from_static("accept-encoding"));
+            }
+
+            Ok(res)
         }
     }
 }
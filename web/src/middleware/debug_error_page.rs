@@ -0,0 +1,560 @@
+//! development error page middleware.
+//!
+//! See [DebugErrorPage] for usage.
+
+use core::{
+    any::Any,
+    cell::RefCell,
+    convert::Infallible,
+    fmt::{self, Write},
+    future::{poll_fn, Future},
+    pin::pin,
+    task::Poll,
+};
+
+use std::{
+    backtrace::{Backtrace, BacktraceStatus},
+    panic::{self, AssertUnwindSafe},
+    sync::Once,
+};
+
+use xitca_http::util::service::router::MatchedRoute;
+
+use crate::{
+    body::ResponseBody,
+    dev::{
+        bytes::Bytes,
+        service::{ready::ReadyService, Service},
+    },
+    handler::{
+        negotiate::{MediaType, Negotiate},
+        Responder,
+    },
+    http::{
+        const_header_value::{JSON, TEXT_HTML_UTF8},
+        header::{
+            Entry, HeaderMap, HeaderValue, AUTHORIZATION, CONTENT_ENCODING, CONTENT_LENGTH, CONTENT_TYPE, COOKIE,
+            PROXY_AUTHORIZATION,
+        },
+        Method, StatusCode,
+    },
+    request::WebRequest,
+    response::WebResponse,
+};
+
+/// Middleware rendering detail of failed request as HTML page for debugging.
+///
+/// **It's for development only.** The page exposes internal error messages and request headers to
+/// client and must not be enabled in production.
+///
+/// Error returned by enclosed services and response with `4xx` or `5xx` status and empty body
+/// are rendered into a page containing:
+/// - Debug format of the error.
+/// - Method, path and headers of request. Values of `Authorization`, `Proxy-Authorization` and
+///   `Cookie` headers are redacted.
+/// - Route pattern matched by App's router. (See [MatchedRoute] for when it's available)
+/// - Message of panic when enclosed services panicked. Panicked request is answered with
+///   `500 Internal Server Error`. Backtrace of the panic is included when enabled with
+///   [DebugErrorPage::backtrace] and `RUST_BACKTRACE` environment variable is set.
+///
+/// Client accepting `application/json` but not `text/html` receives JSON document of the same
+/// content. Status and headers of error response are kept while it's body is replaced. Response
+/// with other status or non-empty body is passed through untouched and response body is never
+/// buffered.
+///
+/// Panics can only be caught when binary is built with `panic = "unwind"`.
+///
+/// # Examples:
+/// ```rust
+/// # use xitca_web::{handler::handler_service, middleware::debug_error_page::DebugErrorPage, request::WebRequest, route::get, App};
+/// # async fn index(_: &WebRequest<'_>) -> &'static str { "" }
+/// App::new()
+///     .at("/", get(handler_service(index)))
+///     .enclosed(DebugErrorPage::new());
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct DebugErrorPage {
+    backtrace: bool,
+}
+
+impl DebugErrorPage {
+    pub const fn new() -> Self {
+        Self { backtrace: false }
+    }
+
+    /// Include backtrace of panic in rendered page.
+    ///
+    /// Backtrace must be captured before the stack is unwound so a process wide panic hook is
+    /// installed when middleware is constructed. The hook records backtrace of every panic happening
+    /// afterwards and calls previously installed hook. It's never uninstalled.
+    pub const fn backtrace(mut self) -> Self {
+        self.backtrace = true;
+        self
+    }
+}
+
+impl<S> Service<S> for DebugErrorPage {
+    type Response = DebugErrorPageService<S>;
+    type Error = Infallible;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where S: 'f;
+
+    fn call<'s>(&'s self, service: S) -> Self::Future<'s>
+    where
+        S: 's,
+    {
+        if self.backtrace {
+            install_panic_hook();
+        }
+        async { Ok(DebugErrorPageService { service }) }
+    }
+}
+
+pub struct DebugErrorPageService<S> {
+    service: S,
+}
+
+impl<'r, S, C, B, ResB, Err> Service<WebRequest<'r, C, B>> for DebugErrorPageService<S>
+where
+    C: 'r,
+    B: 'r,
+    S: for<'rs> Service<WebRequest<'rs, C, B>, Response = WebResponse<ResponseBody<ResB>>, Error = Err>,
+    Err: fmt::Debug + for<'rs> Responder<WebRequest<'rs, C, B>, Output = WebResponse>,
+{
+    type Response = WebResponse<ResponseBody<ResB>>;
+    type Error = Infallible;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where Self: 'f, 'r: 'f;
+
+    fn call<'s>(&'s self, mut req: WebRequest<'r, C, B>) -> Self::Future<'s>
+    where
+        'r: 's,
+    {
+        async move {
+            // request can be turned into response by service. keep what's rendered beforehand.
+            let head = req.req();
+            let mut report = Report {
+                status: StatusCode::INTERNAL_SERVER_ERROR,
+                error: None,
+                panic: None,
+                method: head.method().clone(),
+                path: head.uri().path_and_query().map(|p| p.as_str()).unwrap_or("/").into(),
+                route: None,
+                headers: redact(head.headers()),
+            };
+
+            let res = {
+                let mut fut = pin!(self.service.call(req.reborrow()));
+                poll_fn(
+                    |cx| match panic::catch_unwind(AssertUnwindSafe(|| fut.as_mut().poll(cx))) {
+                        Ok(res) => res.map(Ok),
+                        Err(payload) => Poll::Ready(Err(payload)),
+                    },
+                )
+                .await
+            };
+
+            report.route = req.req().extensions().get::<MatchedRoute>().map(|m| m.pattern().into());
+
+            let mut res = match res {
+                Ok(Ok(res)) => {
+                    let is_empty = match res.body() {
+                        ResponseBody::None => true,
+                        ResponseBody::Bytes { bytes } => bytes.is_empty(),
+                        ResponseBody::Stream { .. } => false,
+                    };
+                    if !is_error(res.status()) || !is_empty {
+                        return Ok(res);
+                    }
+                    if report.route.is_none() {
+                        report.route = res.extensions().get::<MatchedRoute>().map(|m| m.pattern().into());
+                    }
+                    res
+                }
+                Ok(Err(e)) => {
+                    report.error = Some(format!("{e:?}"));
+                    let res = e.respond_to(req.reborrow()).await.map(|body| body.drop_stream_cast());
+                    if !is_error(res.status()) {
+                        return Ok(res);
+                    }
+                    res
+                }
+                Err(payload) => {
+                    report.panic = Some(Panic::new(payload));
+                    let mut res = req.as_response(Bytes::new());
+                    *res.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+                    res.map(|body| body.drop_stream_cast())
+                }
+            };
+
+            report.status = res.status();
+
+            const NEGOTIATE: Negotiate = Negotiate::new(&[MediaType::HTML, MediaType::JSON]).fallback();
+
+            let (body, content_type) = match NEGOTIATE.negotiate(&report.headers) {
+                Ok(n) if n.media_type() == MediaType::JSON => (report.json(), JSON),
+                _ => (report.html(), TEXT_HTML_UTF8),
+            };
+
+            let headers = res.headers_mut();
+            headers.remove(CONTENT_LENGTH);
+            headers.remove(CONTENT_ENCODING);
+            headers.insert(CONTENT_TYPE, content_type);
+            *res.body_mut() = ResponseBody::bytes(body);
+
+            Ok(res)
+        }
+    }
+}
+
+impl<S> ReadyService for DebugErrorPageService<S>
+where
+    S: ReadyService,
+{
+    type Ready = S::Ready;
+    type Future<'f> = S::Future<'f> where S: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::Future<'_> {
+        self.service.ready()
+    }
+}
+
+fn is_error(status: StatusCode) -> bool {
+    status.is_client_error() || status.is_server_error()
+}
+
+fn redact(headers: &HeaderMap) -> HeaderMap {
+    let mut headers = headers.clone();
+    for name in [AUTHORIZATION, PROXY_AUTHORIZATION, COOKIE] {
+        if let Entry::Occupied(mut entry) = headers.entry(name) {
+            for value in entry.iter_mut() {
+                *value = HeaderValue::from_static("[redacted]");
+            }
+        }
+    }
+    headers
+}
+
+thread_local! {
+    // backtrace of the last panic happened on current thread.
+    static BACKTRACE: RefCell<Option<Backtrace>> = const { RefCell::new(None) };
+}
+
+// backtrace must be captured by panic hook before the stack is unwound. previous hook is still
+// called afterwards.
+fn install_panic_hook() {
+    static HOOK: Once = Once::new();
+    HOOK.call_once(|| {
+        let prev = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            BACKTRACE.with(|bt| *bt.borrow_mut() = Some(Backtrace::capture()));
+            prev(info);
+        }));
+    });
+}
+
+struct Panic {
+    message: String,
+    backtrace: Option<String>,
+}
+
+impl Panic {
+    fn new(payload: Box<dyn Any + Send>) -> Self {
+        let message = match payload.downcast::<String>() {
+            Ok(msg) => *msg,
+            Err(payload) => match payload.downcast::<&'static str>() {
+                Ok(msg) => (*msg).into(),
+                Err(_) => "Box<dyn Any>".into(),
+            },
+        };
+
+        let backtrace = BACKTRACE
+            .with(|bt| bt.borrow_mut().take())
+            .filter(|bt| bt.status() == BacktraceStatus::Captured)
+            .map(|bt| bt.to_string());
+
+        Self { message, backtrace }
+    }
+}
+
+struct Report {
+    status: StatusCode,
+    error: Option<String>,
+    panic: Option<Panic>,
+    method: Method,
+    path: String,
+    route: Option<String>,
+    headers: HeaderMap,
+}
+
+impl Report {
+    fn html(&self) -> String {
+        let mut buf = String::new();
+
+        let _ = write!(
+            buf,
+            "<!DOCTYPE html><html><head><meta charset=\"utf-8\"><title>{status}</title></head><body>\
+            <h1>{status}</h1>",
+            status = self.status
+        );
+
+        if let Some(ref error) = self.error {
+            let _ = write!(buf, "<h2>Error</h2><pre>{}</pre>", Html(error));
+        }
+
+        if let Some(ref panic) = self.panic {
+            let _ = write!(buf, "<h2>Panic</h2><pre>{}</pre>", Html(&panic.message));
+            if let Some(ref backtrace) = panic.backtrace {
+                let _ = write!(buf, "<h2>Backtrace</h2><pre>{}</pre>", Html(backtrace));
+            }
+        }
+
+        let _ = write!(
+            buf,
+            "<h2>Request</h2><table><tr><th>Method</th><td>{}</td></tr><tr><th>Path</th><td>{}</td></tr>\
+            <tr><th>Route</th><td>{}</td></tr></table><h2>Headers</h2><table>",
+            self.method,
+            Html(&self.path),
+            Html(self.route.as_deref().unwrap_or_default())
+        );
+
+        for (name, value) in self.headers.iter() {
+            let value = String::from_utf8_lossy(value.as_bytes());
+            let _ = write!(buf, "<tr><th>{name}</th><td>{}</td></tr>", Html(&value));
+        }
+
+        buf.push_str("</table></body></html>");
+
+        buf
+    }
+
+    fn json(&self) -> String {
+        let mut buf = String::new();
+
+        let _ = write!(buf, "{{\"status\":{},\"error\":", self.status.as_u16());
+        match self.error {
+            Some(ref error) => write_json_str(&mut buf, error),
+            None => buf.push_str("null"),
+        }
+
+        buf.push_str(",\"panic\":");
+        match self.panic {
+            Some(ref panic) => {
+                buf.push_str("{\"message\":");
+                write_json_str(&mut buf, &panic.message);
+                buf.push_str(",\"backtrace\":");
+                match panic.backtrace {
+                    Some(ref backtrace) => write_json_str(&mut buf, backtrace),
+                    None => buf.push_str("null"),
+                }
+                buf.push('}');
+            }
+            None => buf.push_str("null"),
+        }
+
+        buf.push_str(",\"request\":{\"method\":");
+        write_json_str(&mut buf, self.method.as_str());
+        buf.push_str(",\"path\":");
+        write_json_str(&mut buf, &self.path);
+        buf.push_str(",\"route\":");
+        match self.route {
+            Some(ref route) => write_json_str(&mut buf, route),
+            None => buf.push_str("null"),
+        }
+
+        // header names can repeat so they are listed as pairs.
+        buf.push_str(",\"headers\":[");
+        for (i, (name, value)) in self.headers.iter().enumerate() {
+            if i > 0 {
+                buf.push(',');
+            }
+            buf.push('[');
+            write_json_str(&mut buf, name.as_str());
+            buf.push(',');
+            write_json_str(&mut buf, &String::from_utf8_lossy(value.as_bytes()));
+            buf.push(']');
+        }
+        buf.push_str("]}}");
+
+        buf
+    }
+}
+
+// escape text for html element content.
+struct Html<'a>(&'a str);
+
+impl fmt::Display for Html<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for c in self.0.chars() {
+            match c {
+                '&' => f.write_str("&amp;")?,
+                '<' => f.write_str("&lt;")?,
+                '>' => f.write_str("&gt;")?,
+                '"' => f.write_str("&quot;")?,
+                '\'' => f.write_str("&#39;")?,
+                c => f.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+fn write_json_str(buf: &mut String, s: &str) {
+    buf.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => buf.push_str("\\\""),
+            '\\' => buf.push_str("\\\\"),
+            '\n' => buf.push_str("\\n"),
+            '\r' => buf.push_str("\\r"),
+            '\t' => buf.push_str("\\t"),
+            c if c.is_control() => {
+                let _ = write!(buf, "\\u{:04x}", c as u32);
+            }
+            c => buf.push(c),
+        }
+    }
+    buf.push('"');
+}
+
+#[cfg(test)]
+mod test {
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::{
+        body::RequestBody,
+        handler::handler_service,
+        http::{
+            header::{ACCEPT, ALLOW},
+            Request, RequestExt,
+        },
+        route::get,
+        test::{TestRequest, TestService},
+        App,
+    };
+
+    use super::*;
+
+    const INVALID_UTF8: &[u8] = b"\xff";
+
+    async fn index() -> &'static str {
+        "index"
+    }
+
+    async fn fail(_: String) -> &'static str {
+        unreachable!("extractor must fail")
+    }
+
+    async fn panic() -> &'static str {
+        panic!("handler panicked with 996")
+    }
+
+    fn service() -> TestService<
+        impl ReadyService
+            + Service<Request<RequestExt<RequestBody>>, Response = WebResponse<ResponseBody<ResponseBody>>, Error = Infallible>,
+    > {
+        App::new()
+            .at("/", get(handler_service(index)))
+            .at("/users/:id", get(handler_service(fail)))
+            .at("/panic", get(handler_service(panic)))
+            .enclosed(DebugErrorPage::new())
+            .finish_for_test()
+            .now_or_panic()
+    }
+
+    #[test]
+    fn html() {
+        let service = service();
+
+        let res = service
+            .call(
+                TestRequest::get("/users/996?q=1")
+                    .header(AUTHORIZATION, "Bearer secret-token")
+                    .header(COOKIE, "id=secret-cookie")
+                    .header("x-trace", "<abc>")
+                    .body(INVALID_UTF8),
+            )
+            .now_or_panic()
+            .unwrap();
+        res.assert_status(StatusCode::INTERNAL_SERVER_ERROR)
+            .assert_header(CONTENT_TYPE, "text/html; charset=utf-8");
+
+        let body = res.string_body().now_or_panic().unwrap();
+        assert!(body.contains("<pre>Parse(ParseError(String(Utf8Error"));
+        assert!(body.contains("/users/996?q=1"));
+        assert!(body.contains("/users/:id"));
+        assert!(body.contains("<tr><th>x-trace</th><td>&lt;abc&gt;</td></tr>"));
+        assert!(body.contains("[redacted]"));
+        assert!(!body.contains("secret"));
+    }
+
+    #[test]
+    fn json() {
+        let service = service();
+
+        let res = service
+            .call(
+                TestRequest::get("/users/996")
+                    .header(ACCEPT, "application/json")
+                    .body(INVALID_UTF8),
+            )
+            .now_or_panic()
+            .unwrap();
+        res.assert_status(StatusCode::INTERNAL_SERVER_ERROR)
+            .assert_header(CONTENT_TYPE, "application/json");
+
+        let body = res.string_body().now_or_panic().unwrap();
+        assert!(body.starts_with("{\"status\":500,\"error\":\""));
+        assert!(body.contains("Utf8Error { valid_up_to: 0, error_len: Some(1) }"));
+        assert!(body.contains("\"route\":\"/users/:id\""));
+        assert!(body.contains("[\"accept\",\"application/json\"]"));
+
+        // html is preferred when both are accepted.
+        let res = service
+            .call(
+                TestRequest::get("/users/996")
+                    .header(ACCEPT, "text/html, application/json")
+                    .body(INVALID_UTF8),
+            )
+            .now_or_panic()
+            .unwrap();
+        res.assert_header(CONTENT_TYPE, "text/html; charset=utf-8");
+    }
+
+    #[test]
+    fn pass_through() {
+        let service = service();
+
+        let res = service.call(TestRequest::get("/")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK)
+            .assert_header(CONTENT_TYPE, "text/plain; charset=utf-8");
+        assert_eq!(res.string_body().now_or_panic().unwrap(), "index");
+
+        // error response keeps it's status and headers.
+        let res = service
+            .call(TestRequest::get("/").method(Method::POST))
+            .now_or_panic()
+            .unwrap();
+        res.assert_status(StatusCode::METHOD_NOT_ALLOWED);
+        assert!(res.headers().contains_key(ALLOW));
+        assert!(res
+            .string_body()
+            .now_or_panic()
+            .unwrap()
+            .contains("405 Method Not Allowed"));
+    }
+
+    #[test]
+    fn panic_message() {
+        let service = service();
+
+        let res = service
+            .call(TestRequest::get("/panic").header(ACCEPT, "application/json"))
+            .now_or_panic()
+            .unwrap();
+        res.assert_status(StatusCode::INTERNAL_SERVER_ERROR);
+
+        let body = res.string_body().now_or_panic().unwrap();
+        assert!(body.contains("\"panic\":{\"message\":\"handler panicked with 996\""));
+        assert!(body.contains("\"route\":\"/panic\""));
+    }
+}
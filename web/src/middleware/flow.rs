@@ -0,0 +1,337 @@
+//! middlewares intercepting `Expect` and `Upgrade` requests before they are routed.
+//!
+//! See [Expect] and [Upgrade] for usage. They are also available as
+//! [App::expect_service](crate::App::expect_service) and
+//! [App::upgrade_service](crate::App::upgrade_service).
+
+use core::future::Future;
+
+use crate::{
+    dev::service::{pipeline::PipelineE, ready::ReadyService, Service},
+    handler::Responder,
+    http::header::{HeaderMap, EXPECT, UPGRADE},
+    request::WebRequest,
+};
+
+/// Outcome of service passed to [Expect] and [Upgrade] middleware.
+#[derive(Debug)]
+pub enum Flow<T> {
+    /// Respond to request with given response. Enclosed service is not called.
+    Respond(T),
+    /// Pass request to enclosed service.
+    Pass,
+}
+
+impl<R, T> Responder<R> for Flow<T>
+where
+    T: Responder<R>,
+{
+    type Output = Flow<T::Output>;
+    type Future = impl Future<Output = Self::Output>;
+
+    #[inline]
+    fn respond_to(self, req: R) -> Self::Future {
+        async {
+            match self {
+                Self::Respond(t) => Flow::Respond(t.respond_to(req).await),
+                Self::Pass => Flow::Pass,
+            }
+        }
+    }
+}
+
+/// Middleware passing request with `Expect: 100-continue` header to the service built by given
+/// factory before it reaches enclosed service.
+///
+/// The service responds with [Flow]. [Flow::Respond] is sent to client as final response of
+/// request and [Flow::Pass] lets request continue to enclosed service. `100 Continue` is only sent
+/// when request body is read so a request rejected by the service never has it's body requested.
+///
+/// # Examples:
+/// ```rust
+/// # use xitca_web::{
+/// #     body::ResponseBody, handler::handler_service, http::{header::AUTHORIZATION, StatusCode},
+/// #     request::WebRequest, response::WebResponse, App,
+/// # };
+/// use xitca_web::middleware::flow::Flow;
+///
+/// // reject upload without credentials before client sends the body.
+/// async fn expect(req: &WebRequest<'_>) -> Flow<WebResponse> {
+///     if req.req().headers().contains_key(AUTHORIZATION) {
+///         return Flow::Pass;
+///     }
+///     let mut res = WebResponse::new(ResponseBody::None);
+///     *res.status_mut() = StatusCode::UNAUTHORIZED;
+///     Flow::Respond(res)
+/// }
+///
+/// # async fn upload(_: &WebRequest<'_>) -> &'static str {
+/// #   "uploaded"
+/// # }
+/// App::new()
+///     .at("/upload", handler_service(upload))
+///     .expect_service(handler_service(expect))
+///     .finish();
+/// ```
+#[derive(Clone)]
+pub struct Expect<F> {
+    factory: F,
+}
+
+impl<F> Expect<F> {
+    pub fn new(factory: F) -> Self {
+        Self { factory }
+    }
+}
+
+impl<F, S> Service<S> for Expect<F>
+where
+    F: Service,
+{
+    type Response = FlowService<F::Response, S>;
+    type Error = F::Error;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where Self: 'f, S: 'f;
+
+    fn call<'s>(&'s self, service: S) -> Self::Future<'s>
+    where
+        S: 's,
+    {
+        async {
+            let flow = self.factory.call(()).await?;
+            Ok(FlowService {
+                service,
+                flow,
+                filter: is_expect_continue,
+            })
+        }
+    }
+}
+
+/// Middleware passing request with `Upgrade` header to the service built by given factory before
+/// it reaches enclosed service.
+///
+/// The service responds with [Flow]. [Flow::Respond] is sent to client as response of request and
+/// [Flow::Pass] declines the upgrade and lets request fall through to enclosed service.
+///
+/// # Examples:
+/// ```rust
+/// # use xitca_web::{
+/// #     body::ResponseBody, handler::handler_service, http::{header::UPGRADE, StatusCode},
+/// #     request::WebRequest, response::WebResponse, App,
+/// # };
+/// use xitca_web::middleware::flow::Flow;
+///
+/// async fn upgrade(req: &WebRequest<'_>) -> Flow<WebResponse> {
+///     // other protocols are handled by routes.
+///     if req.req().headers().get(UPGRADE).map_or(true, |v| v != "custom-proto") {
+///         return Flow::Pass;
+///     }
+///     let mut res = WebResponse::new(ResponseBody::None);
+///     *res.status_mut() = StatusCode::SWITCHING_PROTOCOLS;
+///     Flow::Respond(res)
+/// }
+///
+/// # async fn index(_: &WebRequest<'_>) -> &'static str {
+/// #   "index"
+/// # }
+/// App::new()
+///     .at("/", handler_service(index))
+///     .upgrade_service(handler_service(upgrade))
+///     .finish();
+/// ```
+#[derive(Clone)]
+pub struct Upgrade<F> {
+    factory: F,
+}
+
+impl<F> Upgrade<F> {
+    pub fn new(factory: F) -> Self {
+        Self { factory }
+    }
+}
+
+impl<F, S> Service<S> for Upgrade<F>
+where
+    F: Service,
+{
+    type Response = FlowService<F::Response, S>;
+    type Error = F::Error;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where Self: 'f, S: 'f;
+
+    fn call<'s>(&'s self, service: S) -> Self::Future<'s>
+    where
+        S: 's,
+    {
+        async {
+            let flow = self.factory.call(()).await?;
+            Ok(FlowService {
+                service,
+                flow,
+                filter: is_upgrade,
+            })
+        }
+    }
+}
+
+pub struct FlowService<F, S> {
+    service: S,
+    flow: F,
+    filter: fn(&HeaderMap) -> bool,
+}
+
+pub type FlowServiceError<FE, E> = PipelineE<FE, E>;
+
+impl<'r, F, S, C, B, Res, FErr, Err> Service<WebRequest<'r, C, B>> for FlowService<F, S>
+where
+    C: 'r,
+    B: 'r,
+    F: for<'rs> Service<WebRequest<'rs, C, B>, Response = Flow<Res>, Error = FErr>,
+    S: for<'rs> Service<WebRequest<'rs, C, B>, Response = Res, Error = Err>,
+{
+    type Response = Res;
+    type Error = FlowServiceError<FErr, Err>;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where Self: 'f, 'r: 'f;
+
+    fn call<'s>(&'s self, mut req: WebRequest<'r, C, B>) -> Self::Future<'s>
+    where
+        'r: 's,
+    {
+        async move {
+            if (self.filter)(req.req().headers()) {
+                let flow = self.flow.call(req.reborrow()).await.map_err(FlowServiceError::First)?;
+                if let Flow::Respond(res) = flow {
+                    return Ok(res);
+                }
+            }
+            self.service.call(req).await.map_err(FlowServiceError::Second)
+        }
+    }
+}
+
+impl<F, S> ReadyService for FlowService<F, S>
+where
+    S: ReadyService,
+{
+    type Ready = S::Ready;
+    type Future<'f> = S::Future<'f> where Self: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::Future<'_> {
+        self.service.ready()
+    }
+}
+
+fn is_expect_continue(headers: &HeaderMap) -> bool {
+    headers
+        .get(EXPECT)
+        .is_some_and(|v| v.as_bytes().eq_ignore_ascii_case(b"100-continue"))
+}
+
+fn is_upgrade(headers: &HeaderMap) -> bool {
+    headers.contains_key(UPGRADE)
+}
+
+#[cfg(test)]
+mod test {
+    use core::sync::atomic::{AtomicBool, Ordering};
+
+    use crate::{
+        body::ResponseBody,
+        handler::handler_service,
+        http::{header::AUTHORIZATION, Method, StatusCode},
+        response::WebResponse,
+        test::TestRequest,
+        App,
+    };
+
+    use super::*;
+
+    fn status(status: StatusCode) -> WebResponse {
+        let mut res = WebResponse::new(ResponseBody::None);
+        *res.status_mut() = status;
+        res
+    }
+
+    #[tokio::test]
+    async fn expect() {
+        static BODY_READ: AtomicBool = AtomicBool::new(false);
+
+        async fn upload(body: String) -> String {
+            BODY_READ.store(true, Ordering::SeqCst);
+            body
+        }
+
+        async fn expect(req: &WebRequest<'_>) -> Flow<WebResponse> {
+            if req.req().headers().contains_key(AUTHORIZATION) {
+                return Flow::Pass;
+            }
+            Flow::Respond(status(StatusCode::UNAUTHORIZED))
+        }
+
+        let service = App::new()
+            .at("/", handler_service(upload))
+            .expect_service(handler_service(expect))
+            .finish_for_test()
+            .await;
+
+        let req = TestRequest::get("/")
+            .method(Method::POST)
+            .header(EXPECT, "100-continue")
+            .body("996");
+        service.call(req).await.unwrap().assert_status(StatusCode::UNAUTHORIZED);
+        assert!(!BODY_READ.load(Ordering::SeqCst));
+
+        let req = TestRequest::get("/")
+            .method(Method::POST)
+            .header(EXPECT, "100-continue")
+            .header(AUTHORIZATION, "Bearer 996")
+            .body("996");
+        let res = service.call(req).await.unwrap();
+        res.assert_status(StatusCode::OK);
+        assert_eq!(res.string_body().await.unwrap(), "996");
+        assert!(BODY_READ.load(Ordering::SeqCst));
+
+        // request without expect header is not passed to expect service.
+        let res = service
+            .call(TestRequest::get("/").method(Method::POST).body("251"))
+            .await
+            .unwrap();
+        res.assert_status(StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn upgrade() {
+        async fn index() -> &'static str {
+            "index"
+        }
+
+        async fn upgrade(req: &WebRequest<'_>) -> Flow<WebResponse> {
+            match req.req().headers().get(UPGRADE) {
+                Some(proto) if proto == "custom-proto" => Flow::Respond(status(StatusCode::SWITCHING_PROTOCOLS)),
+                _ => Flow::Pass,
+            }
+        }
+
+        let service = App::new()
+            .at("/", handler_service(index))
+            .upgrade_service(handler_service(upgrade))
+            .finish_for_test()
+            .await;
+
+        let req = TestRequest::get("/").header(UPGRADE, "custom-proto");
+        let res = service.call(req).await.unwrap();
+        res.assert_status(StatusCode::SWITCHING_PROTOCOLS);
+
+        // declined upgrade and normal request are routed.
+        for req in [TestRequest::get("/").header(UPGRADE, "h2c"), TestRequest::get("/")] {
+            let res = service.call(req).await.unwrap();
+            res.assert_status(StatusCode::OK);
+            assert_eq!(res.string_body().await.unwrap(), "index");
+        }
+
+        // upgrade request to unknown path is still handled by upgrade service.
+        let req = TestRequest::get("/unknown").header(UPGRADE, "custom-proto");
+        let res = service.call(req).await.unwrap();
+        res.assert_status(StatusCode::SWITCHING_PROTOCOLS);
+    }
+}
@@ -0,0 +1,334 @@
+//! http method override middleware.
+
+use core::{
+    convert::Infallible,
+    future::{poll_fn, Future},
+    mem,
+    pin::pin,
+};
+
+use xitca_http::{body::Once, util::percent::query_pairs};
+
+use crate::{
+    body::{BodyStream, BoxStream},
+    dev::{
+        bytes::{Bytes, BytesMut},
+        service::{pipeline::PipelineE, ready::ReadyService, Service},
+    },
+    handler::ExtractError,
+    http::{
+        header::{HeaderName, CONTENT_LENGTH, CONTENT_TYPE},
+        Method, Request, RequestExt,
+    },
+    request::WebRequest,
+};
+
+/// Header name of method override.
+pub const X_HTTP_METHOD_OVERRIDE: HeaderName = HeaderName::from_static("x-http-method-override");
+
+const FORM_FIELD: &str = "_method";
+
+const FORM_CONTENT_TYPE: &str = "application/x-www-form-urlencoded";
+
+/// Middleware for overriding http method of POST request before it's passed to router.
+///
+/// Method is overridden by `X-HTTP-Method-Override` header and optionally by `_method` field of
+/// urlencoded form body. See [MethodOverride::form_field] for detail. Override is ignored when
+/// request method is not POST or the override target is not in allow list.
+///
+/// When method is overridden the original method is stored in request's extensions as
+/// [OriginalMethod].
+///
+/// # Examples:
+/// ```rust
+/// # use xitca_web::{handler::handler_service, middleware::method_override::MethodOverride, request::WebRequest, route::delete, App};
+/// # async fn remove(_: &WebRequest<'_>) -> &'static str { "" }
+/// App::new()
+///     // a POST request with `_method=DELETE` form field is routed as DELETE.
+///     .at("/user", delete(handler_service(remove)))
+///     .enclosed(MethodOverride::new().form_field(1024));
+/// ```
+#[derive(Clone, Debug)]
+pub struct MethodOverride {
+    allow: Vec<Method>,
+    form_limit: Option<usize>,
+}
+
+impl Default for MethodOverride {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MethodOverride {
+    /// Construct middleware with header override only and PUT, PATCH and DELETE as allow list.
+    pub fn new() -> Self {
+        Self {
+            allow: vec![Method::PUT, Method::PATCH, Method::DELETE],
+            form_limit: None,
+        }
+    }
+
+    /// Replace allow list of override target methods.
+    pub fn allow_methods<I>(mut self, methods: I) -> Self
+    where
+        I: IntoIterator<Item = Method>,
+    {
+        self.allow = methods.into_iter().collect();
+        self
+    }
+
+    /// Enable override by `_method` field of `application/x-www-form-urlencoded` request body.
+    ///
+    /// Body is only peeked when request has `Content-Length` header with value no larger than
+    /// given limit in bytes. Peeked body is restored and remain readable for later extractors.
+    /// Header override takes precedence when both present.
+    pub fn form_field(mut self, limit: usize) -> Self {
+        self.form_limit = Some(limit);
+        self
+    }
+}
+
+/// Original [Method] of request before it's overridden by [MethodOverride].
+#[derive(Clone, Debug)]
+pub struct OriginalMethod(pub Method);
+
+impl<S> Service<S> for MethodOverride {
+    type Response = MethodOverrideService<S>;
+    type Error = Infallible;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where S: 'f;
+
+    fn call<'s>(&'s self, service: S) -> Self::Future<'s>
+    where
+        S: 's,
+    {
+        async {
+            Ok(MethodOverrideService {
+                service,
+                allow: self.allow.clone(),
+                form_limit: self.form_limit,
+            })
+        }
+    }
+}
+
+pub struct MethodOverrideService<S> {
+    service: S,
+    allow: Vec<Method>,
+    form_limit: Option<usize>,
+}
+
+pub type MethodOverrideServiceError<E, BE> = PipelineE<ExtractError<BE>, E>;
+
+impl<'r, S, C, B, Res, Err> Service<WebRequest<'r, C, B>> for MethodOverrideService<S>
+where
+    C: 'r,
+    B: BodyStream + Default + From<BoxStream> + 'r,
+    S: for<'rs> Service<WebRequest<'rs, C, B>, Response = Res, Error = Err>,
+{
+    type Response = Res;
+    type Error = MethodOverrideServiceError<Err, B::Error>;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where Self: 'f, 'r: 'f;
+
+    fn call<'s>(&'s self, mut req: WebRequest<'r, C, B>) -> Self::Future<'s>
+    where
+        'r: 's,
+    {
+        async move {
+            if req.req().method() == Method::POST {
+                let method = match req.req().headers().get(X_HTTP_METHOD_OVERRIDE) {
+                    Some(value) => parse_method(value.as_bytes()),
+                    None => match self.form_limit {
+                        Some(limit) if is_small_form(req.req(), limit) => {
+                            let body = collect(req.take_body_mut())
+                                .await
+                                .map_err(MethodOverrideServiceError::First)?;
+
+                            let method = core::str::from_utf8(&body)
+                                .ok()
+                                .and_then(|form| query_pairs(form).find(|(key, _)| key == FORM_FIELD))
+                                .and_then(|(_, value)| parse_method(value.as_bytes()));
+
+                            // restore body for later extractors.
+                            *req.body_get_mut() = B::from(BoxStream::new(Once::new(body)));
+
+                            method
+                        }
+                        _ => None,
+                    },
+                };
+
+                if let Some(method) = method.filter(|method| self.allow.contains(method)) {
+                    let original = mem::replace(req.req_mut().method_mut(), method);
+                    req.req_mut().extensions_mut().insert(OriginalMethod(original));
+                }
+            }
+
+            self.service.call(req).await.map_err(MethodOverrideServiceError::Second)
+        }
+    }
+}
+
+impl<S> ReadyService for MethodOverrideService<S>
+where
+    S: ReadyService,
+{
+    type Ready = S::Ready;
+    type Future<'f> = S::Future<'f> where S: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::Future<'_> {
+        self.service.ready()
+    }
+}
+
+fn parse_method(value: &[u8]) -> Option<Method> {
+    Method::from_bytes(&value.to_ascii_uppercase()).ok()
+}
+
+fn is_small_form(req: &Request<RequestExt<()>>, limit: usize) -> bool {
+    let headers = req.headers();
+
+    let is_form = headers
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.split(';').next())
+        .is_some_and(|mime| mime.trim().eq_ignore_ascii_case(FORM_CONTENT_TYPE));
+
+    is_form
+        && headers
+            .get(CONTENT_LENGTH)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.parse::<usize>().ok())
+            .is_some_and(|len| len <= limit)
+}
+
+async fn collect<B>(body: B) -> Result<Bytes, ExtractError<B::Error>>
+where
+    B: BodyStream,
+{
+    let mut body = pin!(body);
+    let mut buf = BytesMut::new();
+
+    while let Some(chunk) = poll_fn(|cx| body.as_mut().poll_next(cx)).await {
+        let chunk = chunk.map_err(ExtractError::Body)?;
+        buf.extend_from_slice(chunk.as_ref());
+    }
+
+    Ok(buf.freeze())
+}
+
+#[cfg(test)]
+mod test {
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::{
+        handler::handler_service,
+        http::{header::HeaderValue, StatusCode},
+        route::{delete, post, put},
+        test::TestRequest,
+        App,
+    };
+
+    use super::*;
+
+    async fn handler(req: &WebRequest<'_>, body: String) -> String {
+        let original = req.req().extensions().get::<OriginalMethod>().map(|m| m.0.to_string());
+        format!("{} {} {body}", req.req().method(), original.unwrap_or_default())
+    }
+
+    fn form(body: &'static str) -> TestRequest {
+        TestRequest::get("/")
+            .method(Method::POST)
+            .header(CONTENT_TYPE, HeaderValue::from_static(FORM_CONTENT_TYPE))
+            .body(body)
+    }
+
+    #[test]
+    fn header() {
+        let service = App::new()
+            .at("/", delete(handler_service(handler)).post(handler_service(handler)))
+            .enclosed(MethodOverride::new())
+            .finish_for_test()
+            .now_or_panic();
+
+        let req = TestRequest::get("/")
+            .method(Method::POST)
+            .header(X_HTTP_METHOD_OVERRIDE, HeaderValue::from_static("delete"));
+        let res = service.call(req).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK);
+        assert_eq!(res.string_body().now_or_panic().unwrap(), "DELETE POST ");
+
+        // override on non POST method is ignored.
+        let req = TestRequest::get("/")
+            .method(Method::PUT)
+            .header(X_HTTP_METHOD_OVERRIDE, HeaderValue::from_static("DELETE"));
+        let res = service.call(req).now_or_panic().unwrap();
+        res.assert_status(StatusCode::METHOD_NOT_ALLOWED);
+
+        // form field is not checked without opt-in.
+        let res = service.call(form("_method=DELETE")).now_or_panic().unwrap();
+        assert_eq!(res.string_body().now_or_panic().unwrap(), "POST  _method=DELETE");
+    }
+
+    #[test]
+    fn form_field() {
+        let service = App::new()
+            .at("/", put(handler_service(handler)).post(handler_service(handler)))
+            .enclosed(MethodOverride::new().form_field(32))
+            .finish_for_test()
+            .now_or_panic();
+
+        // body is still readable after override.
+        let res = service.call(form("name=foo&_method=put")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK);
+        assert_eq!(
+            res.string_body().now_or_panic().unwrap(),
+            "PUT POST name=foo&_method=put"
+        );
+
+        // body larger than limit is not peeked.
+        let body = "_method=PUT&name=foooooooooooooooooooooooo";
+        let res = service.call(form(body)).now_or_panic().unwrap();
+        assert_eq!(res.string_body().now_or_panic().unwrap(), format!("POST  {body}"));
+
+        // body with other content type is not peeked.
+        let req = TestRequest::get("/").method(Method::POST).body("_method=PUT");
+        let res = service.call(req).now_or_panic().unwrap();
+        assert_eq!(res.string_body().now_or_panic().unwrap(), "POST  _method=PUT");
+    }
+
+    #[test]
+    fn disallowed() {
+        let service = App::new()
+            .at("/", post(handler_service(handler)))
+            .enclosed(MethodOverride::new().form_field(32))
+            .finish_for_test()
+            .now_or_panic();
+
+        for req in [
+            form("_method=GET"),
+            form("_method=CONNECT"),
+            TestRequest::get("/")
+                .method(Method::POST)
+                .header(X_HTTP_METHOD_OVERRIDE, HeaderValue::from_static("GET")),
+        ] {
+            let res = service.call(req).now_or_panic().unwrap();
+            res.assert_status(StatusCode::OK);
+            assert!(res.string_body().now_or_panic().unwrap().starts_with("POST  "));
+        }
+
+        // disallowed by custom allow list.
+        let service = App::new()
+            .at("/", post(handler_service(handler)))
+            .enclosed(MethodOverride::new().allow_methods([Method::PUT]))
+            .finish_for_test()
+            .now_or_panic();
+
+        let req = TestRequest::get("/")
+            .method(Method::POST)
+            .header(X_HTTP_METHOD_OVERRIDE, HeaderValue::from_static("DELETE"));
+        let res = service.call(req).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK);
+    }
+}
@@ -0,0 +1,547 @@
+//! request and connection metrics rendered in Prometheus text format.
+//!
+//! Counters are stored in a cell owned by every worker thread and updated without any lock.
+//! Cells are registered to a process wide registry on first use and summed up when the metrics
+//! endpoint is scraped. Because of that the endpoint always reports metrics of the whole process
+//! and it can be served by another [App](crate::App) bound to a separate admin address.
+//!
+//! # Examples:
+//! ```rust
+//! # use xitca_web::{handler::handler_service, request::WebRequest, route::get, App};
+//! use xitca_web::middleware::metrics::{metrics_handler, Metrics};
+//!
+//! # async fn index(_: &WebRequest<'_>) -> &'static str { "" }
+//! App::new()
+//!     .at("/", get(handler_service(index)))
+//!     .at("/metrics", get(metrics_handler()))
+//!     .enclosed(Metrics);
+//! ```
+//!
+//! Connection metrics are collected by passing [connection_observer] to
+//! [HttpServer::connection_observer](crate::HttpServer::connection_observer).
+
+use core::{
+    convert::Infallible,
+    fmt,
+    future::Future,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use std::{
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+use futures_core::stream::Stream;
+use xitca_http::{
+    config::ConnectionEvent,
+    util::service::router::{Describe, PathGen},
+};
+
+use crate::{
+    body::{BodySize, BodyStream},
+    dev::service::{ready::ReadyService, Service},
+    handler::ExtractError,
+    http::{
+        header::{HeaderValue, CONTENT_LENGTH, CONTENT_TYPE},
+        StatusCode, Version,
+    },
+    request::WebRequest,
+    response::WebResponse,
+};
+
+// upper bounds of request duration histogram buckets in seconds.
+const BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+const VERSIONS: [&str; 5] = ["HTTP/0.9", "HTTP/1.0", "HTTP/1.1", "HTTP/2.0", "HTTP/3.0"];
+
+const STATUS_CLASSES: [&str; 5] = ["1xx", "2xx", "3xx", "4xx", "5xx"];
+
+const HANDSHAKES: [&str; 3] = ["full", "resumed", "unknown"];
+
+// counters of one worker thread. only the owning thread writes to it.
+#[derive(Default)]
+struct WorkerCell {
+    connections_opened: AtomicU64,
+    connections_closed: AtomicU64,
+    tls_failures: AtomicU64,
+    stuck_requests: AtomicU64,
+    body_violations: AtomicU64,
+    tls_handshakes: [AtomicU64; HANDSHAKES.len()],
+    tls_early_data: AtomicU64,
+    requests: [[AtomicU64; STATUS_CLASSES.len()]; VERSIONS.len()],
+    // non cumulative count of requests per bucket. the last one is +Inf.
+    durations: [AtomicU64; BUCKETS.len() + 1],
+    duration_sum_micros: AtomicU64,
+    bytes_read: AtomicU64,
+    bytes_written: AtomicU64,
+}
+
+// cells stay in registry after their worker thread exits so counters never go backwards.
+static REGISTRY: Mutex<Vec<Arc<WorkerCell>>> = Mutex::new(Vec::new());
+
+thread_local! {
+    static CELL: Arc<WorkerCell> = {
+        let cell = Arc::new(WorkerCell::default());
+        REGISTRY.lock().unwrap().push(cell.clone());
+        cell
+    };
+}
+
+fn with_cell<F>(func: F)
+where
+    F: FnOnce(&WorkerCell),
+{
+    CELL.with(|cell| func(cell))
+}
+
+#[inline]
+fn incr(counter: &AtomicU64, n: u64) {
+    counter.fetch_add(n, Ordering::Relaxed);
+}
+
+/// Connection observer collecting active connections, tls handshakes, their failures, stuck requests
+/// and body policy violations.
+///
+/// It can be passed to [BodyPolicy::observer](crate::middleware::body_policy::BodyPolicy::observer)
+/// too for counting violations reported by the middleware.
+///
+/// # Examples:
+/// ```rust,no_run
+/// # use xitca_web::{handler::handler_service, request::WebRequest, route::get, App, HttpServer};
+/// use xitca_web::middleware::metrics::{connection_observer, metrics_handler, Metrics};
+///
+/// # async fn index(_: &WebRequest<'_>) -> &'static str { "" }
+/// # fn doc_example() -> std::io::Result<()> {
+/// HttpServer::new(|| App::new().at("/", get(handler_service(index))).enclosed(Metrics).finish())
+///     .connection_observer(connection_observer)
+///     .bind("0.0.0.0:8080")?
+///     .run();
+///
+/// // metrics of server above are served on a separate address.
+/// HttpServer::new(|| App::new().at("/metrics", get(metrics_handler())).finish())
+///     .bind("127.0.0.1:9090")?
+///     .run();
+/// # Ok(())
+/// # }
+/// ```
+pub fn connection_observer(_: SocketAddr, event: ConnectionEvent) {
+    with_cell(|cell| match event {
+        ConnectionEvent::Open => incr(&cell.connections_opened, 1),
+        ConnectionEvent::TlsAcceptFailed => incr(&cell.tls_failures, 1),
+        ConnectionEvent::TlsHandshake(info) => {
+            let kind = match info.resumed() {
+                Some(false) => 0,
+                Some(true) => 1,
+                None => 2,
+            };
+            incr(&cell.tls_handshakes[kind], 1);
+            if info.early_data() {
+                incr(&cell.tls_early_data, 1);
+            }
+        }
+        ConnectionEvent::StuckRequest => incr(&cell.stuck_requests, 1),
+        ConnectionEvent::BodyViolation(_) => incr(&cell.body_violations, 1),
+        ConnectionEvent::Close => incr(&cell.connections_closed, 1),
+    })
+}
+
+/// Middleware collecting request metrics.
+///
+/// Requests are counted by http version and status class of response. Request duration is
+/// measured from the middleware receiving request to the response head being returned from App,
+/// including request handling ends with error converted into response.
+///
+/// Bytes read and written are counted from request `Content-Length` header and size of response
+/// body when it's known ahead. Streaming body without known size is not counted.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Metrics;
+
+impl<S> Service<S> for Metrics {
+    type Response = MetricsService<S>;
+    type Error = Infallible;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where S: 'f;
+
+    fn call<'s>(&'s self, service: S) -> Self::Future<'s>
+    where
+        S: 's,
+    {
+        async { Ok(MetricsService { service }) }
+    }
+}
+
+pub struct MetricsService<S> {
+    service: S,
+}
+
+impl<'r, S, C, B, ResB, Err> Service<WebRequest<'r, C, B>> for MetricsService<S>
+where
+    C: 'r,
+    B: 'r,
+    S: for<'rs> Service<WebRequest<'rs, C, B>, Response = WebResponse<ResB>, Error = Err>,
+    ResB: Stream,
+{
+    type Response = WebResponse<ResB>;
+    type Error = Err;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where Self: 'f, 'r: 'f;
+
+    fn call<'s>(&'s self, mut req: WebRequest<'r, C, B>) -> Self::Future<'s>
+    where
+        'r: 's,
+    {
+        async move {
+            let start = Instant::now();
+            let version = version_idx(req.req().version());
+
+            let read = req
+                .req()
+                .headers()
+                .get(CONTENT_LENGTH)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0);
+
+            // status is observed from response head hook so error converted into response is
+            // counted with it's final status.
+            req.on_response(move |head| {
+                let elapsed = start.elapsed();
+                let secs = elapsed.as_secs_f64();
+                let bucket = BUCKETS.iter().position(|b| secs <= *b).unwrap_or(BUCKETS.len());
+                with_cell(|cell| {
+                    incr(&cell.requests[version][status_idx(head.status)], 1);
+                    incr(&cell.durations[bucket], 1);
+                    incr(&cell.duration_sum_micros, elapsed.as_micros() as u64);
+                    incr(&cell.bytes_read, read);
+                });
+            });
+
+            let res = self.service.call(req).await?;
+
+            if let BodySize::Sized(size) = BodySize::from_stream(res.body()) {
+                with_cell(|cell| incr(&cell.bytes_written, size as u64));
+            }
+
+            Ok(res)
+        }
+    }
+}
+
+impl<S> ReadyService for MetricsService<S>
+where
+    S: ReadyService,
+{
+    type Ready = S::Ready;
+    type Future<'f> = S::Future<'f> where S: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::Future<'_> {
+        self.service.ready()
+    }
+}
+
+fn version_idx(version: Version) -> usize {
+    match version {
+        Version::HTTP_09 => 0,
+        Version::HTTP_10 => 1,
+        Version::HTTP_2 => 3,
+        Version::HTTP_3 => 4,
+        _ => 2,
+    }
+}
+
+fn status_idx(status: StatusCode) -> usize {
+    (status.as_u16() as usize / 100).clamp(1, STATUS_CLASSES.len()) - 1
+}
+
+/// Construct a service rendering snapshot of collected metrics in Prometheus text format.
+///
+/// It can be mounted at any route of [App](crate::App). See [module level](self) doc for detail.
+pub fn metrics_handler() -> MetricsHandler {
+    MetricsHandler
+}
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct MetricsHandler;
+
+impl Service for MetricsHandler {
+    type Response = MetricsHandlerService;
+    type Error = Infallible;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> where Self: 'f;
+
+    fn call<'s>(&self, _: ()) -> Self::Future<'s> {
+        async { Ok(MetricsHandlerService) }
+    }
+}
+
+impl PathGen for MetricsHandler {}
+
+impl Describe for MetricsHandler {}
+
+pub struct MetricsHandlerService;
+
+// error type is the same as handler_service so it can be mixed with other handlers in App.
+impl<'r, C, B> Service<WebRequest<'r, C, B>> for MetricsHandlerService
+where
+    C: 'r,
+    B: BodyStream + 'r,
+{
+    type Response = WebResponse;
+    type Error = ExtractError<B::Error>;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where Self: 'f, 'r: 'f;
+
+    fn call<'s>(&'s self, req: WebRequest<'r, C, B>) -> Self::Future<'s>
+    where
+        'r: 's,
+    {
+        let body = Snapshot::collect().to_string();
+        let mut res = req.into_response(body);
+        res.headers_mut().insert(
+            CONTENT_TYPE,
+            HeaderValue::from_static("text/plain; version=0.0.4; charset=utf-8"),
+        );
+        async { Ok(res) }
+    }
+}
+
+impl ReadyService for MetricsHandlerService {
+    type Ready = ();
+    type Future<'f> = impl Future<Output = Self::Ready> where Self: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::Future<'_> {
+        async {}
+    }
+}
+
+// sum of all worker cells.
+#[derive(Default)]
+struct Snapshot {
+    connections_opened: u64,
+    connections_closed: u64,
+    tls_failures: u64,
+    stuck_requests: u64,
+    body_violations: u64,
+    tls_handshakes: [u64; HANDSHAKES.len()],
+    tls_early_data: u64,
+    requests: [[u64; STATUS_CLASSES.len()]; VERSIONS.len()],
+    durations: [u64; BUCKETS.len() + 1],
+    duration_sum_micros: u64,
+    bytes_read: u64,
+    bytes_written: u64,
+}
+
+impl Snapshot {
+    fn collect() -> Self {
+        fn add(sum: &mut u64, counter: &AtomicU64) {
+            *sum += counter.load(Ordering::Relaxed);
+        }
+
+        let mut snap = Snapshot::default();
+
+        for cell in REGISTRY.lock().unwrap().iter() {
+            add(&mut snap.connections_opened, &cell.connections_opened);
+            add(&mut snap.connections_closed, &cell.connections_closed);
+            add(&mut snap.tls_failures, &cell.tls_failures);
+            add(&mut snap.stuck_requests, &cell.stuck_requests);
+            add(&mut snap.body_violations, &cell.body_violations);
+            for (sum, counter) in snap.tls_handshakes.iter_mut().zip(cell.tls_handshakes.iter()) {
+                add(sum, counter);
+            }
+            add(&mut snap.tls_early_data, &cell.tls_early_data);
+            for (sums, counters) in snap.requests.iter_mut().zip(cell.requests.iter()) {
+                for (sum, counter) in sums.iter_mut().zip(counters.iter()) {
+                    add(sum, counter);
+                }
+            }
+            for (sum, counter) in snap.durations.iter_mut().zip(cell.durations.iter()) {
+                add(sum, counter);
+            }
+            add(&mut snap.duration_sum_micros, &cell.duration_sum_micros);
+            add(&mut snap.bytes_read, &cell.bytes_read);
+            add(&mut snap.bytes_written, &cell.bytes_written);
+        }
+
+        snap
+    }
+}
+
+impl fmt::Display for Snapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fn head(f: &mut fmt::Formatter<'_>, name: &str, ty: &str, help: &str) -> fmt::Result {
+            writeln!(f, "# HELP {name} {help}")?;
+            writeln!(f, "# TYPE {name} {ty}")
+        }
+
+        let name = "xitca_active_connections";
+        head(f, name, "gauge", "Number of connections currently served.")?;
+        // close and open are loaded separately. clamp for close observed without it's open.
+        let active = self.connections_opened.saturating_sub(self.connections_closed);
+        writeln!(f, "{name} {active}")?;
+
+        let name = "xitca_requests_total";
+        head(
+            f,
+            name,
+            "counter",
+            "Number of requests by http version and response status class.",
+        )?;
+        for (version, counts) in VERSIONS.iter().zip(self.requests.iter()) {
+            for (status, count) in STATUS_CLASSES.iter().zip(counts.iter()) {
+                if *count > 0 {
+                    writeln!(f, "{name}{{version=\"{version}\",status=\"{status}\"}} {count}")?;
+                }
+            }
+        }
+
+        let name = "xitca_request_duration_seconds";
+        head(f, name, "histogram", "Duration of request handling.")?;
+        let mut cumulative = 0;
+        for (le, count) in BUCKETS.iter().zip(self.durations.iter()) {
+            cumulative += count;
+            writeln!(f, "{name}_bucket{{le=\"{le}\"}} {cumulative}")?;
+        }
+        cumulative += self.durations[BUCKETS.len()];
+        writeln!(f, "{name}_bucket{{le=\"+Inf\"}} {cumulative}")?;
+        writeln!(f, "{name}_sum {}", self.duration_sum_micros as f64 / 1_000_000.0)?;
+        writeln!(f, "{name}_count {cumulative}")?;
+
+        let name = "xitca_read_bytes_total";
+        head(f, name, "counter", "Bytes of request body read.")?;
+        writeln!(f, "{name} {}", self.bytes_read)?;
+
+        let name = "xitca_written_bytes_total";
+        head(f, name, "counter", "Bytes of response body written.")?;
+        writeln!(f, "{name} {}", self.bytes_written)?;
+
+        let name = "xitca_tls_handshake_failures_total";
+        head(f, name, "counter", "Number of failed or timed out tls handshakes.")?;
+        writeln!(f, "{name} {}", self.tls_failures)?;
+
+        let name = "xitca_stuck_requests_total";
+        head(
+            f,
+            name,
+            "counter",
+            "Number of service calls stuck longer than the threshold.",
+        )?;
+        writeln!(f, "{name} {}", self.stuck_requests)?;
+
+        let name = "xitca_body_violations_total";
+        head(f, name, "counter", "Number of request and response body policy violations.")?;
+        writeln!(f, "{name} {}", self.body_violations)?;
+
+        let name = "xitca_tls_handshakes_total";
+        head(f, name, "counter", "Number of finished tls handshakes by kind.")?;
+        for (kind, count) in HANDSHAKES.iter().zip(self.tls_handshakes.iter()) {
+            writeln!(f, "{name}{{kind=\"{kind}\"}} {count}")?;
+        }
+
+        let name = "xitca_tls_early_data_total";
+        head(f, name, "counter", "Number of tls handshakes with 0-RTT early data accepted.")?;
+        writeln!(f, "{name} {}", self.tls_early_data)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use xitca_http::config::BodyViolation;
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::{
+        handler::handler_service,
+        http::{Method, StatusCode, TlsConnectionInfo},
+        route::get,
+        test::TestRequest,
+        App,
+    };
+
+    use super::*;
+
+    async fn index(_: &WebRequest<'_>) -> &'static str {
+        "hello"
+    }
+
+    async fn create(body: String) -> String {
+        body
+    }
+
+    fn value<'a>(body: &'a str, series: &str) -> &'a str {
+        body.lines()
+            .find_map(|line| line.strip_prefix(series)?.strip_prefix(' '))
+            .unwrap_or_else(|| panic!("series {series} not found in: {body}"))
+    }
+
+    #[test]
+    fn scrape() {
+        let service = App::new()
+            .at("/", get(handler_service(index)).post(handler_service(create)))
+            .at("/metrics", get(metrics_handler()))
+            .enclosed(Metrics)
+            .finish_for_test()
+            .now_or_panic();
+
+        connection_observer(([127, 0, 0, 1], 8080).into(), ConnectionEvent::Open);
+        connection_observer(([127, 0, 0, 1], 8080).into(), ConnectionEvent::Open);
+        connection_observer(([127, 0, 0, 1], 8080).into(), ConnectionEvent::TlsAcceptFailed);
+        connection_observer(([127, 0, 0, 1], 8080).into(), ConnectionEvent::StuckRequest);
+        let violation = BodyViolation::UnsupportedMediaType;
+        connection_observer(([127, 0, 0, 1], 8080).into(), ConnectionEvent::BodyViolation(violation));
+        connection_observer(([127, 0, 0, 1], 8080).into(), ConnectionEvent::Close);
+
+        let full = TlsConnectionInfo::new("TLSv1.3", "TLS13_AES_128_GCM_SHA256", Some(false), false);
+        let resumed = TlsConnectionInfo::new("TLSv1.3", "TLS13_AES_128_GCM_SHA256", Some(true), true);
+        connection_observer(([127, 0, 0, 1], 8080).into(), ConnectionEvent::TlsHandshake(full));
+        connection_observer(([127, 0, 0, 1], 8080).into(), ConnectionEvent::TlsHandshake(resumed));
+
+        for _ in 0..3 {
+            let res = service.call(TestRequest::get("/")).now_or_panic().unwrap();
+            res.assert_status(StatusCode::OK);
+        }
+
+        let req = TestRequest::get("/")
+            .method(Method::POST)
+            .header(CONTENT_LENGTH, "4")
+            .body("1234");
+        service.call(req).now_or_panic().unwrap();
+
+        let res = service.call(TestRequest::get("/404")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::NOT_FOUND);
+
+        let res = service.call(TestRequest::get("/metrics")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK)
+            .assert_header(CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8");
+        let body = res.string_body().now_or_panic().unwrap();
+
+        assert_eq!(value(&body, "xitca_active_connections"), "1");
+        assert_eq!(value(&body, "xitca_tls_handshake_failures_total"), "1");
+        assert_eq!(value(&body, "xitca_stuck_requests_total"), "1");
+        assert_eq!(value(&body, "xitca_body_violations_total"), "1");
+        assert_eq!(value(&body, "xitca_tls_handshakes_total{kind=\"full\"}"), "1");
+        assert_eq!(value(&body, "xitca_tls_handshakes_total{kind=\"resumed\"}"), "1");
+        assert_eq!(value(&body, "xitca_tls_handshakes_total{kind=\"unknown\"}"), "0");
+        assert_eq!(value(&body, "xitca_tls_early_data_total"), "1");
+        assert_eq!(
+            value(&body, "xitca_requests_total{version=\"HTTP/1.1\",status=\"2xx\"}"),
+            "4"
+        );
+        assert_eq!(
+            value(&body, "xitca_requests_total{version=\"HTTP/1.1\",status=\"4xx\"}"),
+            "1"
+        );
+        assert_eq!(value(&body, "xitca_read_bytes_total"), "4");
+        // 3 "hello" and 1 "1234". error response is not counted.
+        assert_eq!(value(&body, "xitca_written_bytes_total"), "19");
+
+        // metrics request itself is observed after it's response is rendered.
+        let buckets = body
+            .lines()
+            .filter_map(|line| line.strip_prefix("xitca_request_duration_seconds_bucket"))
+            .map(|line| line.rsplit(' ').next().unwrap().parse::<u64>().unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(buckets.len(), BUCKETS.len() + 1);
+        assert!(buckets.windows(2).all(|w| w[0] <= w[1]));
+        assert_eq!(*buckets.last().unwrap(), 5);
+        assert_eq!(value(&body, "xitca_request_duration_seconds_count"), "5");
+    }
+}
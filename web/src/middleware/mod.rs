@@ -1,12 +1,34 @@
+#[cfg(feature = "auth")]
+pub mod auth;
+#[cfg(feature = "cache")]
+pub mod cache;
+#[cfg(feature = "client-cert")]
+pub mod client_cert;
 #[cfg(any(feature = "compress-br", feature = "compress-gz", feature = "compress-de"))]
 pub mod compress;
+#[cfg(feature = "debug-error-page")]
+pub mod debug_error_page;
 #[cfg(any(feature = "compress-br", feature = "compress-gz", feature = "compress-de"))]
 pub mod decompress;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+#[cfg(feature = "rate-limit")]
+pub mod rate_limit;
+#[cfg(feature = "session")]
+pub mod session;
+#[cfg(feature = "timeout")]
+pub mod timeout;
 #[cfg(feature = "tower-http-compat")]
 pub mod tower_http_compat;
 
+pub mod body_policy;
 pub mod eraser;
+pub mod flow;
 pub mod limit;
+pub mod method_override;
+pub mod normalize_path;
+pub mod preload;
+pub mod server_timing;
 
 pub use xitca_http::util::middleware::{Extension, Logger};
 pub use xitca_service::middleware::UncheckedReady;
@@ -48,4 +70,46 @@ mod test {
         let string = collect_string_body(body).now_or_panic().unwrap();
         assert_eq!(string, "hello");
     }
+
+    #[cfg(all(feature = "session", feature = "compress-gz"))]
+    #[test]
+    fn stacked_response_headers() {
+        use crate::{
+            handler::session::Session,
+            http::header::{HeaderValue, ACCEPT_ENCODING, CONTENT_ENCODING, SET_COOKIE, VARY},
+            response::{ResponseHeadersExt, WebResponse},
+            test::TestRequest,
+        };
+
+        async fn handler(session: Session) -> WebResponse {
+            session.insert("user", "foo").unwrap();
+
+            let mut res = WebResponse::new("996".repeat(32).into());
+            res.headers_mut()
+                .append(SET_COOKIE, HeaderValue::from_static("foo=bar"));
+            res.append_or_merge(VARY, HeaderValue::from_static("origin"));
+            res
+        }
+
+        let service = App::new()
+            .at("/", handler_service(handler))
+            .enclosed(session::SessionMiddleware::new(session::MemoryStore::new()))
+            .enclosed(compress::Compress)
+            .finish_for_test()
+            .now_or_panic();
+
+        let req = TestRequest::get("/").header(ACCEPT_ENCODING, "gzip");
+        let res = service.call(req).now_or_panic().unwrap();
+
+        res.assert_header(CONTENT_ENCODING, "gzip");
+
+        let cookies = res.headers().get_all(SET_COOKIE).iter().collect::<Vec<_>>();
+        assert_eq!(cookies.len(), 2);
+        assert_eq!(cookies[0], "foo=bar");
+        assert!(cookies[1].to_str().unwrap().starts_with("id="));
+
+        let mut vary = res.headers().get_all(VARY).iter();
+        assert_eq!(vary.next().unwrap(), "origin, accept-encoding");
+        assert!(vary.next().is_none());
+    }
 }
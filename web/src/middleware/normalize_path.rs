@@ -0,0 +1,332 @@
+//! path normalization middleware.
+
+use core::{convert::Infallible, fmt, future::Future};
+
+use std::error;
+
+use crate::{
+    dev::{
+        bytes::Bytes,
+        service::{pipeline::PipelineE, ready::ReadyService, Service},
+    },
+    handler::Responder,
+    http::{
+        header::{HeaderValue, LOCATION},
+        uri::PathAndQuery,
+        Method, StatusCode, Uri,
+    },
+    request::WebRequest,
+    response::WebResponse,
+};
+
+pub use crate::handler::uri::OriginalUri;
+
+/// Policy of handling trailing slash of request path.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub enum TrailingSlash {
+    /// Remove trailing slash from path. Root path `/` is kept as is.
+    #[default]
+    Trim,
+    /// Add trailing slash to path when it's absent.
+    Append,
+    /// Redirect client to the trimmed form of path. `301 Moved Permanently` is used for GET and
+    /// HEAD method and `308 Permanent Redirect` for all the others so that method and body of
+    /// request are preserved by client.
+    Redirect,
+}
+
+/// Middleware for normalizing request path before it's passed to router.
+///
+/// Consecutive slashes are always collapsed to one and trailing slash is handled according to
+/// [TrailingSlash] policy. Percent-encoded slash(`%2F`) is not treated as path separator and
+/// would not be collapsed.
+///
+/// When path is rewritten the original [Uri] is stored in request's extensions as [OriginalUri].
+/// An [OriginalUri] inserted by prior middleware is kept as is.
+/// CONNECT method and asterisk-form(`*`) request target are never rewritten.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct NormalizePath {
+    trailing_slash: TrailingSlash,
+}
+
+impl NormalizePath {
+    /// Construct middleware with [TrailingSlash::Trim] policy.
+    pub const fn new() -> Self {
+        Self {
+            trailing_slash: TrailingSlash::Trim,
+        }
+    }
+
+    /// Set policy of handling trailing slash.
+    pub const fn trailing_slash(mut self, trailing_slash: TrailingSlash) -> Self {
+        self.trailing_slash = trailing_slash;
+        self
+    }
+}
+
+impl<S> Service<S> for NormalizePath {
+    type Response = NormalizePathService<S>;
+    type Error = Infallible;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where S: 'f;
+
+    fn call<'s>(&'s self, service: S) -> Self::Future<'s>
+    where
+        S: 's,
+    {
+        async {
+            Ok(NormalizePathService {
+                service,
+                trailing_slash: self.trailing_slash,
+            })
+        }
+    }
+}
+
+pub struct NormalizePathService<S> {
+    service: S,
+    trailing_slash: TrailingSlash,
+}
+
+pub type NormalizePathServiceError<E> = PipelineE<PathRedirect, E>;
+
+impl<'r, S, C, B, Res, Err> Service<WebRequest<'r, C, B>> for NormalizePathService<S>
+where
+    C: 'r,
+    B: 'r,
+    S: for<'rs> Service<WebRequest<'rs, C, B>, Response = Res, Error = Err>,
+{
+    type Response = Res;
+    type Error = NormalizePathServiceError<Err>;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where Self: 'f, 'r: 'f;
+
+    fn call<'s>(&'s self, mut req: WebRequest<'r, C, B>) -> Self::Future<'s>
+    where
+        'r: 's,
+    {
+        async move {
+            if req.req().method() != Method::CONNECT {
+                if let Some(path) = normalize(req.req().uri().path(), self.trailing_slash) {
+                    let uri = req.req().uri();
+                    let path_and_query = match uri.query() {
+                        Some(query) => format!("{path}?{query}"),
+                        None => path,
+                    };
+
+                    if self.trailing_slash == TrailingSlash::Redirect {
+                        let status = match *req.req().method() {
+                            Method::GET | Method::HEAD => StatusCode::MOVED_PERMANENTLY,
+                            _ => StatusCode::PERMANENT_REDIRECT,
+                        };
+                        // normalized path is built from valid path and query so it's always a valid header value.
+                        let location = HeaderValue::try_from(path_and_query).unwrap();
+                        return Err(NormalizePathServiceError::First(PathRedirect { status, location }));
+                    }
+
+                    if let Some(uri) = replace_path(uri, path_and_query) {
+                        let original = core::mem::replace(req.req_mut().uri_mut(), uri);
+                        let ext = req.req_mut().extensions_mut();
+                        if ext.get::<OriginalUri>().is_none() {
+                            ext.insert(OriginalUri(original));
+                        }
+                    }
+                }
+            }
+
+            self.service.call(req).await.map_err(NormalizePathServiceError::Second)
+        }
+    }
+}
+
+impl<S> ReadyService for NormalizePathService<S>
+where
+    S: ReadyService,
+{
+    type Ready = S::Ready;
+    type Future<'f> = S::Future<'f> where S: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::Future<'_> {
+        self.service.ready()
+    }
+}
+
+// return normalized path when it's different from the given one.
+fn normalize(path: &str, trailing_slash: TrailingSlash) -> Option<String> {
+    // asterisk-form and authority-form targets are not paths.
+    if !path.starts_with('/') {
+        return None;
+    }
+
+    let mut normalized = String::with_capacity(path.len() + 1);
+    for c in path.chars() {
+        if c == '/' && normalized.ends_with('/') {
+            continue;
+        }
+        normalized.push(c);
+    }
+
+    match trailing_slash {
+        TrailingSlash::Trim | TrailingSlash::Redirect => {
+            if normalized.len() > 1 && normalized.ends_with('/') {
+                normalized.pop();
+            }
+        }
+        TrailingSlash::Append => {
+            if !normalized.ends_with('/') {
+                normalized.push('/');
+            }
+        }
+    }
+
+    (normalized != path).then_some(normalized)
+}
+
+fn replace_path(uri: &Uri, path_and_query: String) -> Option<Uri> {
+    let mut parts = uri.clone().into_parts();
+    parts.path_and_query = Some(PathAndQuery::try_from(path_and_query).ok()?);
+    Uri::from_parts(parts).ok()
+}
+
+/// Error type for redirecting client to normalized path. See [TrailingSlash::Redirect] for detail.
+#[derive(Debug)]
+pub struct PathRedirect {
+    status: StatusCode,
+    location: HeaderValue,
+}
+
+impl fmt::Display for PathRedirect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "request path is redirected to {:?}", self.location)
+    }
+}
+
+impl error::Error for PathRedirect {}
+
+impl<'r, C, B> Responder<WebRequest<'r, C, B>> for PathRedirect {
+    type Output = WebResponse;
+    type Future = impl Future<Output = Self::Output>;
+
+    fn respond_to(self, req: WebRequest<'r, C, B>) -> Self::Future {
+        let mut res = req.into_response(Bytes::new());
+        *res.status_mut() = self.status;
+        res.headers_mut().insert(LOCATION, self.location);
+        async { res }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::{
+        handler::{
+            handler_service,
+            uri::{OriginalUriRef, UriRef},
+        },
+        test::TestRequest,
+        App,
+    };
+
+    use super::*;
+
+    async fn handler(req: &WebRequest<'_>) -> String {
+        let original = req.req().extensions().get::<OriginalUri>().map(|uri| uri.0.to_string());
+        format!("{} {}", req.req().uri(), original.unwrap_or_default())
+    }
+
+    async fn uri(UriRef(uri): UriRef<'_>) -> String {
+        uri.to_string()
+    }
+
+    #[test]
+    fn normalize_path() {
+        assert_eq!(normalize("/", TrailingSlash::Trim), None);
+        assert_eq!(normalize("*", TrailingSlash::Trim), None);
+        assert_eq!(normalize("//", TrailingSlash::Trim).as_deref(), Some("/"));
+        assert_eq!(normalize("//a//b//", TrailingSlash::Trim).as_deref(), Some("/a/b"));
+        assert_eq!(normalize("/a/b", TrailingSlash::Append).as_deref(), Some("/a/b/"));
+        assert_eq!(normalize("/a/b/", TrailingSlash::Append), None);
+        assert_eq!(normalize("/a/%2F%2F/b", TrailingSlash::Redirect), None);
+    }
+
+    #[test]
+    fn trim() {
+        let service = App::new()
+            .at("/users", handler_service(handler))
+            .at("/a/%2F%2F/b", handler_service(uri))
+            .enclosed(NormalizePath::new())
+            .finish_for_test()
+            .now_or_panic();
+
+        let res = service.call(TestRequest::get("//users/?id=1")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK);
+        let body = res.string_body().now_or_panic().unwrap();
+        assert_eq!(body, "/users?id=1 //users/?id=1");
+
+        let res = service.call(TestRequest::get("/users")).now_or_panic().unwrap();
+        assert_eq!(res.string_body().now_or_panic().unwrap(), "/users ");
+
+        // encoded slash is not collapsed.
+        let res = service.call(TestRequest::get("/a//%2F%2F/b/")).now_or_panic().unwrap();
+        assert_eq!(res.string_body().now_or_panic().unwrap(), "/a/%2F%2F/b");
+
+        // CONNECT method is not rewritten.
+        let req = TestRequest::get("//users/").method(Method::CONNECT);
+        service
+            .call(req)
+            .now_or_panic()
+            .unwrap()
+            .assert_status(StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn original_uri() {
+        async fn handler(UriRef(uri): UriRef<'_>, OriginalUriRef(original): OriginalUriRef<'_>) -> String {
+            format!("{uri} {original}")
+        }
+
+        let service = App::new()
+            .at("/a/b", handler_service(handler))
+            .enclosed(NormalizePath::new())
+            .finish_for_test()
+            .now_or_panic();
+
+        let res = service.call(TestRequest::get("//a/b/")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK);
+        assert_eq!(res.string_body().now_or_panic().unwrap(), "/a/b //a/b/");
+    }
+
+    #[test]
+    fn append() {
+        let service = App::new()
+            .at("/users/", handler_service(handler))
+            .enclosed(NormalizePath::new().trailing_slash(TrailingSlash::Append))
+            .finish_for_test()
+            .now_or_panic();
+
+        let res = service.call(TestRequest::get("//users")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK);
+        assert_eq!(res.string_body().now_or_panic().unwrap(), "/users/ //users");
+    }
+
+    #[test]
+    fn redirect() {
+        let service = App::new()
+            .at("/users", handler_service(handler))
+            .enclosed(NormalizePath::new().trailing_slash(TrailingSlash::Redirect))
+            .finish_for_test()
+            .now_or_panic();
+
+        let res = service.call(TestRequest::get("/users")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK);
+
+        let res = service.call(TestRequest::get("//users/?id=1")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::MOVED_PERMANENTLY)
+            .assert_header(LOCATION, "/users?id=1");
+
+        let req = TestRequest::get("/users/").method(Method::POST).body("hello");
+        let res = service.call(req).now_or_panic().unwrap();
+        res.assert_status(StatusCode::PERMANENT_REDIRECT)
+            .assert_header(LOCATION, "/users");
+    }
+}
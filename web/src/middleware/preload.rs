@@ -0,0 +1,398 @@
+//! `Link` preload hint middleware.
+
+use core::{convert::Infallible, future::Future};
+
+use std::sync::Arc;
+
+use crate::{
+    dev::service::{ready::ReadyService, EnclosedFactory, Service, ServiceExt},
+    http::{
+        header::{HeaderMap, HeaderValue, CONTENT_TYPE, LINK},
+        Informational, StatusCode,
+    },
+    request::WebRequest,
+    response::{ResponseHead, ResponseHeadersExt},
+};
+
+/// Construct [PreloadHints] middleware with given hints in `(href, as)` form. Each hint is
+/// written as `Link: <href>; rel=preload; as=<as>` header line in given order.
+///
+/// # Panics
+/// When hint can not be written as header value.
+pub fn preload_hints<I>(hints: I) -> PreloadHints
+where
+    I: IntoIterator<Item = (&'static str, &'static str)>,
+{
+    let hints = hints
+        .into_iter()
+        .map(|(href, as_)| {
+            HeaderValue::try_from(format!("<{href}>; rel=preload; as={as_}"))
+                .unwrap_or_else(|_| panic!("preload hint {href:?} as {as_:?} is not a valid header value"))
+        })
+        .collect();
+
+    PreloadHints(Arc::new(Inner {
+        hints,
+        all_content_types: false,
+        error_responses: false,
+        early_hints: false,
+    }))
+}
+
+/// Extension trait for attaching preload hints to a route or a nested router in builder style.
+///
+/// # Examples:
+/// ```rust
+/// # use xitca_web::{handler::{handler_service, html::Html}, middleware::preload::PreloadHintsExt, route::get, App};
+/// # async fn index() -> Html<&'static str> { Html("") }
+/// App::new().at("/", get(handler_service(index)).preload_hints([("/style.css", "style")]));
+/// ```
+pub trait PreloadHintsExt<Arg>: Service<Arg> + Sized {
+    /// Enclose Self with [PreloadHints] middleware constructed from given hints. See [preload_hints]
+    /// for detail.
+    fn preload_hints<I>(self, hints: I) -> EnclosedFactory<Self, PreloadHints>
+    where
+        I: IntoIterator<Item = (&'static str, &'static str)>,
+    {
+        self.enclosed(preload_hints(hints))
+    }
+}
+
+impl<S, Arg> PreloadHintsExt<Arg> for S where S: Service<Arg> {}
+
+/// Middleware for writing `Link` headers with preload hints so clients can start fetching critical
+/// resources before parsing response body.
+///
+/// Hints are only attached to `text/html` responses with non error status code by default.
+/// A hint equal to one of `Link` headers written by handler or other middlewares is skipped and
+/// the others are appended as separate header lines.
+///
+/// It can enclose a single route or a nested router to apply hints to certain paths of App.
+///
+/// Hints can also be sent as `103 Early Hints` informational response before handler runs. See
+/// [PreloadHints::early_hints] for detail.
+///
+/// # Examples:
+/// ```rust
+/// # use xitca_web::{handler::{handler_service, html::Html}, middleware::preload::preload_hints, route::get, App};
+/// # use xitca_web::dev::service::ServiceExt;
+/// # async fn index() -> Html<&'static str> { Html("") }
+/// App::new()
+///     // responses of index page carry header like "link: </style.css>; rel=preload; as=style"
+///     .at(
+///         "/",
+///         get(handler_service(index)).enclosed(preload_hints([("/style.css", "style"), ("/app.js", "script")])),
+///     );
+/// ```
+#[derive(Clone)]
+pub struct PreloadHints(Arc<Inner>);
+
+struct Inner {
+    hints: Vec<HeaderValue>,
+    all_content_types: bool,
+    error_responses: bool,
+    early_hints: bool,
+}
+
+impl PreloadHints {
+    /// Attach hints to responses of any content type.
+    pub fn all_content_types(mut self) -> Self {
+        self.inner_mut().all_content_types = true;
+        self
+    }
+
+    /// Attach hints to responses with 4xx and 5xx status code.
+    pub fn error_responses(mut self) -> Self {
+        self.inner_mut().error_responses = true;
+        self
+    }
+
+    /// Send hints as `103 Early Hints` informational response before handler runs. The final
+    /// response still carries hints as `Link` headers.
+    ///
+    /// It only takes effect when request carries [Informational] handle. See
+    /// [HttpServiceConfig::informational_responses](xitca_http::config::HttpServiceConfig::informational_responses)
+    /// for detail.
+    pub fn early_hints(mut self) -> Self {
+        self.inner_mut().early_hints = true;
+        self
+    }
+
+    fn inner_mut(&mut self) -> &mut Inner {
+        Arc::get_mut(&mut self.0).expect("PreloadHints must be configured before it's cloned")
+    }
+}
+
+impl Inner {
+    // 103 is sent before status and content type of final response are known so they are not
+    // checked.
+    fn send_early_hints(&self, informational: &Informational) {
+        let mut headers = HeaderMap::with_capacity(self.hints.len());
+        for hint in self.hints.iter() {
+            headers.append(LINK, hint.clone());
+        }
+        informational.send(StatusCode::from_u16(103).unwrap(), headers);
+    }
+
+    fn write(&self, head: &mut ResponseHead) {
+        if !self.error_responses && (head.status.is_client_error() || head.status.is_server_error()) {
+            return;
+        }
+
+        if !self.all_content_types && !is_html(head) {
+            return;
+        }
+
+        for hint in self.hints.iter() {
+            if !head.headers.get_all(LINK).iter().any(|link| link == hint) {
+                head.headers.append_or_merge(LINK, hint.clone());
+            }
+        }
+    }
+}
+
+fn is_html(head: &ResponseHead) -> bool {
+    head.headers
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.split(';').next())
+        .is_some_and(|v| v.trim().eq_ignore_ascii_case("text/html"))
+}
+
+impl<S> Service<S> for PreloadHints {
+    type Response = PreloadHintsService<S>;
+    type Error = Infallible;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where S: 'f;
+
+    fn call<'s>(&'s self, service: S) -> Self::Future<'s>
+    where
+        S: 's,
+    {
+        async {
+            Ok(PreloadHintsService {
+                service,
+                hints: self.0.clone(),
+            })
+        }
+    }
+}
+
+pub struct PreloadHintsService<S> {
+    service: S,
+    hints: Arc<Inner>,
+}
+
+impl<'r, S, C, B, Res, Err> Service<WebRequest<'r, C, B>> for PreloadHintsService<S>
+where
+    C: 'r,
+    B: 'r,
+    S: for<'rs> Service<WebRequest<'rs, C, B>, Response = Res, Error = Err>,
+{
+    type Response = Res;
+    type Error = Err;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where Self: 'f, 'r: 'f;
+
+    fn call<'s>(&'s self, mut req: WebRequest<'r, C, B>) -> Self::Future<'s>
+    where
+        'r: 's,
+    {
+        async move {
+            if self.hints.early_hints {
+                if let Some(informational) = req.req().extensions().get::<Informational>() {
+                    self.hints.send_early_hints(informational);
+                }
+            }
+            let hints = self.hints.clone();
+            req.on_response(move |head| hints.write(head));
+            self.service.call(req).await
+        }
+    }
+}
+
+impl<S> ReadyService for PreloadHintsService<S>
+where
+    S: ReadyService,
+{
+    type Ready = S::Ready;
+    type Future<'f> = S::Future<'f> where S: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::Future<'_> {
+        self.service.ready()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::{
+        body::ResponseBody,
+        dev::service::ServiceExt,
+        handler::{handler_service, html::Html},
+        http::{const_header_value::TEXT_HTML_UTF8, header::HeaderMap, StatusCode},
+        middleware::UncheckedReady,
+        response::WebResponse,
+        route::get,
+        test::TestRequest,
+        App,
+    };
+
+    use super::*;
+
+    async fn index() -> Html<&'static str> {
+        Html("<html></html>")
+    }
+
+    async fn linked() -> WebResponse {
+        let mut res = WebResponse::new(ResponseBody::from("<html></html>"));
+        res.headers_mut().insert(CONTENT_TYPE, TEXT_HTML_UTF8);
+        res.headers_mut()
+            .append(LINK, HeaderValue::from_static("</style.css>; rel=preload; as=style"));
+        res.headers_mut()
+            .append(LINK, HeaderValue::from_static("</next>; rel=prefetch"));
+        res
+    }
+
+    async fn text() -> &'static str {
+        "text"
+    }
+
+    async fn error(_: String) -> Html<&'static str> {
+        unreachable!("extractor must fail")
+    }
+
+    fn links(headers: &HeaderMap) -> Vec<&str> {
+        headers.get_all(LINK).iter().map(|v| v.to_str().unwrap()).collect()
+    }
+
+    const STYLE: &str = "</style.css>; rel=preload; as=style";
+    const SCRIPT: &str = "</app.js>; rel=preload; as=script";
+
+    #[test]
+    fn preload() {
+        let hints = || preload_hints([("/style.css", "style"), ("/app.js", "script")]);
+
+        let service = App::new()
+            .at(
+                "/",
+                get(handler_service(index)).preload_hints([("/style.css", "style"), ("/app.js", "script")]),
+            )
+            .at("/linked", get(handler_service(linked)).enclosed(hints()))
+            .at("/text", get(handler_service(text)).enclosed(hints()))
+            .at("/all", get(handler_service(text)).enclosed(hints().all_content_types()))
+            .at("/error", get(handler_service(error)).enclosed(hints()))
+            // enclosed by no-op middleware to share the same builder error type with other routes.
+            .at("/other", get(handler_service(index)).enclosed(UncheckedReady))
+            .finish_for_test()
+            .now_or_panic();
+
+        let res = service.call(TestRequest::get("/")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK);
+        assert_eq!(links(res.headers()), [STYLE, SCRIPT]);
+
+        // hints written by handler are kept and not duplicated.
+        let res = service.call(TestRequest::get("/linked")).now_or_panic().unwrap();
+        assert_eq!(links(res.headers()), [STYLE, "</next>; rel=prefetch", SCRIPT]);
+
+        let res = service.call(TestRequest::get("/text")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK);
+        assert!(links(res.headers()).is_empty());
+
+        let res = service.call(TestRequest::get("/all")).now_or_panic().unwrap();
+        assert_eq!(links(res.headers()), [STYLE, SCRIPT]);
+
+        let res = service
+            .call(TestRequest::get("/error").body(vec![0xff]))
+            .now_or_panic()
+            .unwrap();
+        res.assert_status(StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(links(res.headers()).is_empty());
+
+        let res = service.call(TestRequest::get("/other")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK);
+        assert!(links(res.headers()).is_empty());
+    }
+
+    #[test]
+    fn error_responses() {
+        async fn not_found() -> WebResponse {
+            let mut res = WebResponse::new(ResponseBody::from("missing"));
+            *res.status_mut() = StatusCode::NOT_FOUND;
+            res
+        }
+
+        let service = App::new()
+            .at("/", get(handler_service(not_found)))
+            .enclosed(
+                preload_hints([("/style.css", "style")])
+                    .all_content_types()
+                    .error_responses(),
+            )
+            .finish_for_test()
+            .now_or_panic();
+
+        let res = service.call(TestRequest::get("/")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::NOT_FOUND);
+        assert_eq!(links(res.headers()), [STYLE]);
+    }
+
+    #[tokio::test]
+    async fn early_hints() {
+        use tokio::{
+            io::{duplex, AsyncReadExt, AsyncWriteExt},
+            task::{spawn_local, LocalSet},
+        };
+        use xitca_http::{config::HttpServiceConfig, HttpServiceBuilder};
+        use xitca_io::io::PollIoAdapter;
+
+        async fn serve(req: &[u8]) -> String {
+            let app = App::new()
+                .at("/", get(handler_service(index)))
+                .at("/text", get(handler_service(text)))
+                .enclosed(preload_hints([("/style.css", "style"), ("/app.js", "script")]).early_hints());
+            let config = HttpServiceConfig::new().informational_responses();
+            let service = HttpServiceBuilder::with_config(app.finish(), config)
+                .call(())
+                .await
+                .unwrap();
+
+            let (mut client, server) = duplex(4096);
+            let handle = spawn_local(async move { service.serve_connection(PollIoAdapter::new(server), None).await });
+
+            client.write_all(req).await.unwrap();
+
+            let mut res = String::new();
+            client.read_to_string(&mut res).await.unwrap();
+            handle.await.unwrap().unwrap();
+            res
+        }
+
+        const HINT: &str = "HTTP/1.1 103 Early Hints\r\n\
+                            link: </style.css>; rel=preload; as=style\r\n\
+                            link: </app.js>; rel=preload; as=script\r\n\r\n";
+
+        // date service of http service is spawned as local task.
+        LocalSet::new()
+            .run_until(async {
+                // 103 is written before final response which carries the same hints. multiple
+                // values of final response are written in one line by http/1 encoder.
+                let res = serve(b"GET / HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\n\r\n").await;
+                let res = res.strip_prefix(HINT).unwrap();
+                assert!(res.starts_with("HTTP/1.1 200 OK\r\n"));
+                assert!(res.contains(&format!("link: {STYLE},{SCRIPT}\r\n")));
+
+                // 103 is sent before content type of final response is known.
+                let res = serve(b"GET /text HTTP/1.1\r\nhost: localhost\r\nconnection: close\r\n\r\n").await;
+                let res = res.strip_prefix(HINT).unwrap();
+                assert!(res.starts_with("HTTP/1.1 200 OK\r\n"));
+                assert!(!res.contains("link:"));
+
+                // http/1.0 client does not understand 103.
+                let res = serve(b"GET / HTTP/1.0\r\nhost: localhost\r\n\r\n").await;
+                assert!(res.starts_with("HTTP/1.1 200 OK\r\n"));
+                assert!(res.contains(&format!("link: {STYLE},{SCRIPT}\r\n")));
+            })
+            .await
+    }
+}
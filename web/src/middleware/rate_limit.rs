@@ -0,0 +1,469 @@
+//! token bucket rate limiting middleware.
+
+use core::{
+    convert::Infallible,
+    fmt,
+    future::Future,
+    hash::{BuildHasher, Hash},
+    time::Duration,
+};
+
+use std::{
+    collections::{hash_map::RandomState, HashMap},
+    error,
+    net::IpAddr,
+    sync::{Arc, Mutex},
+};
+
+use tokio::time::Instant;
+use xitca_http::util::header::duration_header_value;
+
+use crate::{
+    dev::{
+        bytes::Bytes,
+        service::{pipeline::PipelineE, ready::ReadyService, Service},
+    },
+    handler::Responder,
+    http::{header::RETRY_AFTER, Request, RequestExt, StatusCode},
+    request::WebRequest,
+    response::WebResponse,
+};
+
+// number of independently locked shards of buckets.
+const SHARDS: usize = 16;
+
+/// Trait for extracting the key a request is rate limited by.
+///
+/// Request with no key is not rate limited.
+pub trait KeyExtractor {
+    type Key: Hash + Eq;
+
+    fn extract(&self, req: &Request<RequestExt<()>>) -> Option<Self::Key>;
+}
+
+impl<F, K> KeyExtractor for F
+where
+    F: Fn(&Request<RequestExt<()>>) -> Option<K>,
+    K: Hash + Eq,
+{
+    type Key = K;
+
+    #[inline]
+    fn extract(&self, req: &Request<RequestExt<()>>) -> Option<Self::Key> {
+        (self)(req)
+    }
+}
+
+/// Key requests by ip address of connected peer.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PeerIp;
+
+impl KeyExtractor for PeerIp {
+    type Key = IpAddr;
+
+    #[inline]
+    fn extract(&self, req: &Request<RequestExt<()>>) -> Option<Self::Key> {
+        Some(req.body().socket_addr().ip())
+    }
+}
+
+/// Key requests by the first ip address of `X-Forwarded-For` header. Fall back to [PeerIp]
+/// when the header is absent or invalid.
+///
+/// Only use it when the server sits behind trusted proxy as the header can be set to arbitrary
+/// value by client.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ForwardedFor;
+
+impl KeyExtractor for ForwardedFor {
+    type Key = IpAddr;
+
+    fn extract(&self, req: &Request<RequestExt<()>>) -> Option<Self::Key> {
+        req.headers()
+            .get("x-forwarded-for")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.split(',').next())
+            .and_then(|ip| ip.trim().parse().ok())
+            .or_else(|| PeerIp.extract(req))
+    }
+}
+
+/// Token bucket rate limiting middleware.
+///
+/// Every key owns a bucket holding up to `capacity` tokens and each request takes one token
+/// from it. Bucket is refilled at the rate of `capacity` tokens per `period`. Request arriving
+/// at an empty bucket is rejected with `429 Too Many Requests` and a `Retry-After` header
+/// telling client when the next token is available.
+///
+/// Buckets are shared by all worker threads of server and stored in a sharded map so requests
+/// with different keys are not likely to contend on the same lock. Full buckets are evicted
+/// from map periodically.
+///
+/// # Examples:
+/// ```rust
+/// # use std::{convert::Infallible, time::Duration};
+/// # use xitca_web::{dev::service::fn_service, request::WebRequest, response::WebResponse, App};
+/// use xitca_web::middleware::rate_limit::RateLimit;
+///
+/// # fn doc_example() {
+/// // 100 requests per minute for every client ip.
+/// App::new()
+///     .at("/", fn_service(handler))
+///     .enclosed(RateLimit::new(100, Duration::from_secs(60)));
+/// # }
+///
+/// # async fn handler(req: WebRequest<'_>) -> Result<WebResponse, Infallible> {
+/// #   todo!()
+/// # }
+/// ```
+pub struct RateLimit<K = PeerIp>
+where
+    K: KeyExtractor,
+{
+    key: K,
+    buckets: Arc<Buckets<K::Key>>,
+}
+
+impl<K> Clone for RateLimit<K>
+where
+    K: KeyExtractor + Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            key: self.key.clone(),
+            buckets: self.buckets.clone(),
+        }
+    }
+}
+
+impl RateLimit {
+    /// Construct middleware allowing `capacity` requests per `period` for every client ip.
+    ///
+    /// # Panics:
+    ///
+    /// When capacity or period is zero.
+    pub fn new(capacity: u32, period: Duration) -> Self {
+        assert!(capacity > 0, "RateLimit capacity must not be zero");
+        assert!(!period.is_zero(), "RateLimit period must not be zero");
+        Self {
+            key: PeerIp,
+            buckets: Arc::new(Buckets::new(capacity, period)),
+        }
+    }
+
+    /// Key requests by client ip from `X-Forwarded-For` header. See [ForwardedFor] for detail.
+    pub fn forwarded_for(self) -> RateLimit<ForwardedFor> {
+        self.key(ForwardedFor)
+    }
+
+    /// Key requests by given [KeyExtractor]. A closure can be used for custom key.
+    ///
+    /// # Examples:
+    /// ```rust
+    /// # use std::time::Duration;
+    /// # use xitca_web::{http::{Request, RequestExt}, middleware::rate_limit::RateLimit};
+    /// // rate limit by api key header.
+    /// RateLimit::new(10, Duration::from_secs(1)).key(|req: &Request<RequestExt<()>>| {
+    ///     req.headers().get("x-api-key").map(|v| v.as_bytes().to_vec())
+    /// });
+    /// ```
+    pub fn key<K>(self, key: K) -> RateLimit<K>
+    where
+        K: KeyExtractor,
+    {
+        RateLimit {
+            key,
+            buckets: Arc::new(Buckets::new(self.buckets.capacity, self.buckets.period)),
+        }
+    }
+}
+
+impl<S, K> Service<S> for RateLimit<K>
+where
+    K: KeyExtractor + Clone,
+{
+    type Response = RateLimitService<S, K>;
+    type Error = Infallible;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where Self: 'f, S: 'f;
+
+    fn call<'s>(&'s self, service: S) -> Self::Future<'s>
+    where
+        S: 's,
+    {
+        async {
+            Ok(RateLimitService {
+                service,
+                key: self.key.clone(),
+                buckets: self.buckets.clone(),
+            })
+        }
+    }
+}
+
+pub struct RateLimitService<S, K>
+where
+    K: KeyExtractor,
+{
+    service: S,
+    key: K,
+    buckets: Arc<Buckets<K::Key>>,
+}
+
+pub type RateLimitServiceError<E> = PipelineE<RateLimited, E>;
+
+impl<'r, S, K, C, B, Res, Err> Service<WebRequest<'r, C, B>> for RateLimitService<S, K>
+where
+    C: 'r,
+    B: 'r,
+    K: KeyExtractor,
+    S: for<'rs> Service<WebRequest<'rs, C, B>, Response = Res, Error = Err>,
+{
+    type Response = Res;
+    type Error = RateLimitServiceError<Err>;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where Self: 'f, 'r: 'f;
+
+    fn call<'s>(&'s self, req: WebRequest<'r, C, B>) -> Self::Future<'s>
+    where
+        'r: 's,
+    {
+        async move {
+            if let Some(key) = self.key.extract(req.req()) {
+                self.buckets
+                    .acquire(key, Instant::now())
+                    .map_err(RateLimitServiceError::First)?;
+            }
+            self.service.call(req).await.map_err(RateLimitServiceError::Second)
+        }
+    }
+}
+
+impl<S, K> ReadyService for RateLimitService<S, K>
+where
+    S: ReadyService,
+    K: KeyExtractor,
+{
+    type Ready = S::Ready;
+    type Future<'f> = S::Future<'f> where Self: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::Future<'_> {
+        self.service.ready()
+    }
+}
+
+// state of buckets is tracked as the instant when bucket would be full again. (a.k.a generic
+// cell rate algorithm) this way a bucket only costs one Instant and refill is computed lazily.
+struct Buckets<K> {
+    capacity: u32,
+    period: Duration,
+    // time it takes to refill one token.
+    interval: Duration,
+    hasher: RandomState,
+    shards: Box<[Mutex<Shard<K>>]>,
+}
+
+struct Shard<K> {
+    buckets: HashMap<K, Instant>,
+    last_evict: Instant,
+}
+
+impl<K> Buckets<K>
+where
+    K: Hash + Eq,
+{
+    fn new(capacity: u32, period: Duration) -> Self {
+        let now = Instant::now();
+        Self {
+            capacity,
+            period,
+            interval: period / capacity,
+            hasher: RandomState::new(),
+            shards: (0..SHARDS)
+                .map(|_| {
+                    Mutex::new(Shard {
+                        buckets: HashMap::new(),
+                        last_evict: now,
+                    })
+                })
+                .collect(),
+        }
+    }
+
+    fn acquire(&self, key: K, now: Instant) -> Result<(), RateLimited> {
+        let idx = self.hasher.hash_one(&key) as usize % self.shards.len();
+        let mut shard = self.shards[idx].lock().unwrap();
+
+        // full buckets carry no state and can be removed.
+        if now.duration_since(shard.last_evict) >= self.period {
+            shard.buckets.retain(|_, full_at| *full_at > now);
+            shard.last_evict = now;
+        }
+
+        let full_at = shard.buckets.entry(key).or_insert(now);
+        let full_at_next = (*full_at).max(now) + self.interval;
+
+        // bucket is empty when it needs more than a whole period to refill after taking a token.
+        match (full_at_next - now).checked_sub(self.period) {
+            Some(wait) if !wait.is_zero() => Err(RateLimited { retry_after: wait }),
+            _ => {
+                *full_at = full_at_next;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Error type for request exceeding rate limit.
+#[derive(Debug)]
+pub struct RateLimited {
+    retry_after: Duration,
+}
+
+impl RateLimited {
+    /// Duration until the next request can be accepted.
+    pub fn retry_after(&self) -> Duration {
+        self.retry_after
+    }
+}
+
+impl fmt::Display for RateLimited {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "rate limit exceeded. retry after {:?}", self.retry_after)
+    }
+}
+
+impl error::Error for RateLimited {}
+
+impl<'r, C, B> Responder<WebRequest<'r, C, B>> for RateLimited {
+    type Output = WebResponse;
+    type Future = impl Future<Output = Self::Output>;
+
+    fn respond_to(self, req: WebRequest<'r, C, B>) -> Self::Future {
+        let mut res = req.into_response(Bytes::new());
+        *res.status_mut() = StatusCode::TOO_MANY_REQUESTS;
+        // Retry-After is in seconds and rounded up so client never retries too early.
+        res.headers_mut()
+            .insert(RETRY_AFTER, duration_header_value(self.retry_after));
+        async { res }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::net::SocketAddr;
+
+    use crate::{handler::handler_service, test::TestRequest, App};
+
+    use super::*;
+
+    fn addr(ip: &str) -> SocketAddr {
+        SocketAddr::new(ip.parse().unwrap(), 8080)
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn peer_ip() {
+        let service = App::new()
+            .at("/", handler_service(|| async { "hello" }))
+            .enclosed(RateLimit::new(3, Duration::from_secs(6)))
+            .finish_for_test()
+            .await;
+
+        let req = || TestRequest::get("/").socket_addr(addr("127.0.0.1"));
+
+        for _ in 0..3 {
+            service.call(req()).await.unwrap().assert_status(StatusCode::OK);
+        }
+
+        // one token is refilled every 2 seconds.
+        let res = service.call(req()).await.unwrap();
+        res.assert_status(StatusCode::TOO_MANY_REQUESTS)
+            .assert_header(RETRY_AFTER, "2");
+
+        // other client has it's own bucket.
+        let res = service.call(TestRequest::get("/").socket_addr(addr("127.0.0.2"))).await;
+        res.unwrap().assert_status(StatusCode::OK);
+
+        tokio::time::advance(Duration::from_millis(500)).await;
+        let res = service.call(req()).await.unwrap();
+        res.assert_status(StatusCode::TOO_MANY_REQUESTS)
+            .assert_header(RETRY_AFTER, "2");
+
+        tokio::time::advance(Duration::from_millis(1500)).await;
+        service.call(req()).await.unwrap().assert_status(StatusCode::OK);
+        let res = service.call(req()).await.unwrap();
+        res.assert_status(StatusCode::TOO_MANY_REQUESTS);
+
+        // bucket is full again after a whole period.
+        tokio::time::advance(Duration::from_secs(6)).await;
+        for _ in 0..3 {
+            service.call(req()).await.unwrap().assert_status(StatusCode::OK);
+        }
+        let res = service.call(req()).await.unwrap();
+        res.assert_status(StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn forwarded_for() {
+        let service = App::new()
+            .at("/", handler_service(|| async { "hello" }))
+            .enclosed(RateLimit::new(1, Duration::from_secs(1)).forwarded_for())
+            .finish_for_test()
+            .await;
+
+        let req = |ip: &'static str| TestRequest::get("/").header("x-forwarded-for", ip);
+
+        service.call(req("10.0.0.1, 127.0.0.1")).await.unwrap();
+        let res = service.call(req("10.0.0.1")).await.unwrap();
+        res.assert_status(StatusCode::TOO_MANY_REQUESTS)
+            .assert_header(RETRY_AFTER, "1");
+
+        let res = service.call(req("10.0.0.2, 10.0.0.1")).await.unwrap();
+        res.assert_status(StatusCode::OK);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn custom_key() {
+        let service = App::new()
+            .at("/", handler_service(|| async { "hello" }))
+            .enclosed(
+                RateLimit::new(1, Duration::from_secs(1))
+                    .key(|req: &Request<RequestExt<()>>| req.headers().get("x-api-key").cloned()),
+            )
+            .finish_for_test()
+            .await;
+
+        let req = || TestRequest::get("/").header("x-api-key", "996");
+
+        service.call(req()).await.unwrap().assert_status(StatusCode::OK);
+        let res = service.call(req()).await.unwrap();
+        res.assert_status(StatusCode::TOO_MANY_REQUESTS);
+
+        // request without key is not limited.
+        for _ in 0..3 {
+            let res = service.call(TestRequest::get("/")).await.unwrap();
+            res.assert_status(StatusCode::OK);
+        }
+    }
+
+    #[test]
+    fn evict() {
+        let period = Duration::from_secs(1);
+        let buckets = Buckets::new(2, period);
+        let now = Instant::now();
+
+        for key in 0..64 {
+            buckets.acquire(key, now).unwrap();
+        }
+        let len = buckets.shards.iter().map(|s| s.lock().unwrap().buckets.len());
+        assert_eq!(len.sum::<usize>(), 64);
+
+        buckets.acquire(0, now + period).unwrap();
+        buckets.acquire(0, now + period).unwrap();
+        assert!(buckets.acquire(0, now + period).is_err());
+
+        // shard evicts all full buckets on it's next access after a period.
+        let idx = buckets.hasher.hash_one(0) as usize % buckets.shards.len();
+        buckets.acquire(0, now + period * 2).unwrap();
+        assert_eq!(buckets.shards[idx].lock().unwrap().buckets.len(), 1);
+    }
+}
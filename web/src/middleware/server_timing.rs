@@ -0,0 +1,234 @@
+//! `Server-Timing` response header middleware.
+
+use core::{convert::Infallible, future::Future, time::Duration};
+
+use std::time::Instant;
+
+use crate::{
+    dev::service::{ready::ReadyService, Service},
+    http::{
+        header::{HeaderName, HeaderValue},
+        RequestArrival,
+    },
+    request::WebRequest,
+};
+
+/// Header name of server timing.
+pub const SERVER_TIMING: HeaderName = HeaderName::from_static("server-timing");
+
+/// Middleware for writing `Server-Timing` header with duration of request handling as `total`
+/// metric.
+///
+/// The duration is measured from the middleware receiving request to the response head being
+/// returned from App. It's written even when request handling ends with error converted into
+/// response. Other middlewares and services can add their own metrics with
+/// [ServerTiming::metric].
+///
+/// When request carries [RequestArrival] (see
+/// [HttpServiceConfig::request_arrival_time](xitca_http::config::HttpServiceConfig::request_arrival_time))
+/// the time request waited between being decoded by server and reaching the middleware is written as
+/// `queue` metric.
+///
+/// # Examples:
+/// ```rust
+/// # use xitca_web::{handler::handler_service, middleware::server_timing::ServerTiming, request::WebRequest, route::get, App};
+/// # async fn index(_: &WebRequest<'_>) -> &'static str { "" }
+/// App::new()
+///     .at("/", get(handler_service(index)))
+///     // responses carry header like "server-timing: total;dur=0.123"
+///     .enclosed(ServerTiming);
+/// ```
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ServerTiming;
+
+impl ServerTiming {
+    /// Register a metric with given name and duration to be written as `Server-Timing` header.
+    ///
+    /// Metrics are written as separate header lines in registration order.
+    pub fn metric<C, B>(req: &mut WebRequest<'_, C, B>, name: &'static str, dur: Duration) {
+        req.on_response(move |head| {
+            let value = format!("{name};dur={:.3}", dur.as_secs_f64() * 1000.0);
+            if let Ok(value) = HeaderValue::try_from(value) {
+                head.headers.append(SERVER_TIMING, value);
+            }
+        });
+    }
+}
+
+impl<S> Service<S> for ServerTiming {
+    type Response = ServerTimingService<S>;
+    type Error = Infallible;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where S: 'f;
+
+    fn call<'s>(&'s self, service: S) -> Self::Future<'s>
+    where
+        S: 's,
+    {
+        async { Ok(ServerTimingService { service }) }
+    }
+}
+
+pub struct ServerTimingService<S> {
+    service: S,
+}
+
+impl<'r, S, C, B, Res, Err> Service<WebRequest<'r, C, B>> for ServerTimingService<S>
+where
+    C: 'r,
+    B: 'r,
+    S: for<'rs> Service<WebRequest<'rs, C, B>, Response = Res, Error = Err>,
+{
+    type Response = Res;
+    type Error = Err;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where Self: 'f, 'r: 'f;
+
+    fn call<'s>(&'s self, mut req: WebRequest<'r, C, B>) -> Self::Future<'s>
+    where
+        'r: 's,
+    {
+        async move {
+            let start = Instant::now();
+            req.on_response(move |head| {
+                let value = format!("total;dur={:.3}", start.elapsed().as_secs_f64() * 1000.0);
+                if let Ok(value) = HeaderValue::try_from(value) {
+                    head.headers.append(SERVER_TIMING, value);
+                }
+            });
+            if let Some(arrival) = req.req().extensions().get::<RequestArrival>() {
+                let queue = start.saturating_duration_since(arrival.0);
+                ServerTiming::metric(&mut req, "queue", queue);
+            }
+            self.service.call(req).await
+        }
+    }
+}
+
+impl<S> ReadyService for ServerTimingService<S>
+where
+    S: ReadyService,
+{
+    type Ready = S::Ready;
+    type Future<'f> = S::Future<'f> where S: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::Future<'_> {
+        self.service.ready()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::{
+        handler::handler_service, http::StatusCode, response::ResponseHooks, route::get, test::TestRequest, App,
+    };
+
+    use super::*;
+
+    const ORDER: HeaderName = HeaderName::from_static("x-order");
+
+    async fn hook<S, C, B, Res, Err>(service: &S, mut req: WebRequest<'_, C, B>) -> Result<Res, Err>
+    where
+        S: for<'r> Service<WebRequest<'r, C, B>, Response = Res, Error = Err>,
+    {
+        let path = req.req().uri().path().to_owned();
+        req.on_response(move |head| {
+            head.headers.insert(ORDER, HeaderValue::try_from(path).unwrap());
+        });
+        ServerTiming::metric(&mut req, "db", Duration::from_millis(2));
+        service.call(req).await
+    }
+
+    async fn index() -> &'static str {
+        "index"
+    }
+
+    async fn error(_: String) -> &'static str {
+        unreachable!("extractor must fail")
+    }
+
+    #[test]
+    fn server_timing() {
+        let service = App::new()
+            .at("/", get(handler_service(index)))
+            .at("/error", get(handler_service(error)))
+            .enclosed_fn(hook)
+            .enclosed(ServerTiming)
+            .finish_for_test()
+            .now_or_panic();
+
+        let res = service.call(TestRequest::get("/")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK);
+
+        let timings = res.headers().get_all(SERVER_TIMING).iter().collect::<Vec<_>>();
+        assert_eq!(timings.len(), 2);
+        // hooks run in registration order.
+        assert!(timings[0].to_str().unwrap().starts_with("total;dur="));
+        assert_eq!(timings[1], "db;dur=2.000");
+        assert_eq!(res.headers().get(ORDER).unwrap(), "/");
+
+        // hooks run when error is converted into response.
+        let res = service
+            .call(TestRequest::get("/error").body(vec![0xff]))
+            .now_or_panic()
+            .unwrap();
+        res.assert_status(StatusCode::INTERNAL_SERVER_ERROR);
+        assert_eq!(res.headers().get_all(SERVER_TIMING).iter().count(), 2);
+        assert_eq!(res.headers().get(ORDER).unwrap(), "/error");
+
+        let res = service.call(TestRequest::get("/foo")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::NOT_FOUND);
+        assert_eq!(res.headers().get_all(SERVER_TIMING).iter().count(), 2);
+    }
+
+    #[tokio::test]
+    async fn precise_duration() {
+        async fn slow() -> &'static str {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            "slow"
+        }
+
+        fn dur(value: &HeaderValue) -> f64 {
+            let (_, dur) = value.to_str().unwrap().split_once(";dur=").unwrap();
+            dur.parse().unwrap()
+        }
+
+        let service = App::new()
+            .at("/", get(handler_service(slow)))
+            .enclosed(ServerTiming)
+            .finish_for_test()
+            .await;
+
+        let arrival = RequestArrival(Instant::now() - Duration::from_millis(10));
+        let res = service.call(TestRequest::get("/").extension(arrival)).await.unwrap();
+        res.assert_status(StatusCode::OK);
+
+        let timings = res.headers().get_all(SERVER_TIMING).iter().collect::<Vec<_>>();
+        assert_eq!(timings.len(), 2);
+        let total = dur(timings[0]);
+        assert!((50.0..65.0).contains(&total), "total duration: {total}");
+        assert!(timings[1].to_str().unwrap().starts_with("queue;dur="));
+        let queue = dur(timings[1]);
+        assert!((10.0..20.0).contains(&queue), "queue duration: {queue}");
+    }
+
+    #[test]
+    fn no_hook() {
+        async fn index(req: &WebRequest<'_>) -> &'static str {
+            // nothing is stored in request when no hook is registered.
+            assert!(req.req().extensions().get::<ResponseHooks>().is_none());
+            "index"
+        }
+
+        let service = App::new()
+            .at("/", get(handler_service(index)))
+            .finish_for_test()
+            .now_or_panic();
+
+        let res = service.call(TestRequest::get("/")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK);
+        assert!(res.headers().get(SERVER_TIMING).is_none());
+        assert!(res.extensions().get::<ResponseHooks>().is_none());
+    }
+}
@@ -0,0 +1,724 @@
+//! Cookie based session middleware with pluggable storage backend.
+//!
+//! See [SessionMiddleware] and [Session](crate::handler::session::Session) for usage.
+
+use core::{convert::Infallible, fmt, future::Future, time::Duration};
+
+use std::{
+    collections::HashMap,
+    error,
+    sync::{Arc, Mutex},
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use cookie::{Cookie, CookieJar, Key, SameSite};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    dev::{
+        bytes::Bytes,
+        service::{pipeline::PipelineE, ready::ReadyService, Service},
+    },
+    handler::{
+        session::{Session, SessionStatus},
+        Responder,
+    },
+    http::{
+        header::{HeaderValue, InvalidHeaderValue, COOKIE, SET_COOKIE},
+        StatusCode,
+    },
+    request::WebRequest,
+    response::{ResponseHeadersExt, WebResponse},
+};
+
+/// Data of a session.
+#[derive(Clone, Debug, Default)]
+pub struct SessionState {
+    pub(crate) id: String,
+    pub(crate) data: HashMap<String, String>,
+}
+
+impl SessionState {
+    /// Construct an empty state with a random generated id.
+    pub fn new() -> Self {
+        Self {
+            id: generate_id(),
+            data: HashMap::new(),
+        }
+    }
+
+    /// Construct state with given id and serialized session values.
+    pub fn from_parts(id: String, data: HashMap<String, String>) -> Self {
+        Self { id, data }
+    }
+
+    /// Id of session.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Serialized session values.
+    pub fn data(&self) -> &HashMap<String, String> {
+        &self.data
+    }
+}
+
+pub(crate) fn generate_id() -> String {
+    let mut bytes = [0; 32];
+    rand::thread_rng().fill_bytes(&mut bytes);
+    URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// Storage backend of [SessionMiddleware].
+///
+/// `key` is the value of session cookie received from and sent to client.
+pub trait SessionStore {
+    type Error: error::Error + Send + Sync + 'static;
+
+    type LoadFuture<'f>: Future<Output = Result<Option<SessionState>, Self::Error>>
+    where
+        Self: 'f;
+
+    type SaveFuture<'f>: Future<Output = Result<String, Self::Error>>
+    where
+        Self: 'f;
+
+    type DeleteFuture<'f>: Future<Output = Result<(), Self::Error>>
+    where
+        Self: 'f;
+
+    /// Load session state with key. Absent or expired session must be returned as `None`.
+    fn load<'s>(&'s self, key: &'s str) -> Self::LoadFuture<'s>;
+
+    /// Save session state that would expire after ttl duration and return a new key of it.
+    fn save<'s>(&'s self, state: &'s SessionState, ttl: Duration) -> Self::SaveFuture<'s>;
+
+    /// Delete session state with key.
+    fn delete<'s>(&'s self, key: &'s str) -> Self::DeleteFuture<'s>;
+}
+
+/// Store keep session state inside client cookie.
+///
+/// Session state is serialized and signed so it can not be tampered by client. It's not encrypted
+/// and sensitive data must not be stored with it.
+#[derive(Clone)]
+pub struct CookieStore {
+    key: Key,
+}
+
+impl CookieStore {
+    /// Construct store with given master key in bytes.
+    ///
+    /// # Panic:
+    /// When key is shorter than 64 bytes.
+    pub fn new(key: &[u8]) -> Self {
+        Self { key: Key::from(key) }
+    }
+
+    /// Construct store with a random generated key.
+    ///
+    /// Session would not be valid across process restarts or multiple processes with this store.
+    pub fn generate() -> Self {
+        Self { key: Key::generate() }
+    }
+}
+
+// internal cookie name used for signing.
+const SIGN_NAME: &str = "session";
+
+#[derive(Serialize, Deserialize)]
+struct CookiePayload {
+    id: String,
+    exp: u64,
+    data: HashMap<String, String>,
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|dur| dur.as_secs())
+        .unwrap_or(0)
+}
+
+impl SessionStore for CookieStore {
+    type Error = Infallible;
+    type LoadFuture<'f> = impl Future<Output = Result<Option<SessionState>, Self::Error>> + 'f;
+    type SaveFuture<'f> = impl Future<Output = Result<String, Self::Error>> + 'f;
+    type DeleteFuture<'f> = impl Future<Output = Result<(), Self::Error>> + 'f;
+
+    fn load<'s>(&'s self, key: &'s str) -> Self::LoadFuture<'s> {
+        async move {
+            let mut jar = CookieJar::new();
+            jar.add_original(Cookie::new(SIGN_NAME, key.to_owned()));
+
+            let state = jar
+                .signed(&self.key)
+                .get(SIGN_NAME)
+                .and_then(|cookie| URL_SAFE_NO_PAD.decode(cookie.value()).ok())
+                .and_then(|payload| serde_json::from_slice::<CookiePayload>(&payload).ok())
+                .filter(|payload| payload.exp > unix_now())
+                .map(|payload| SessionState::from_parts(payload.id, payload.data));
+
+            Ok(state)
+        }
+    }
+
+    fn save<'s>(&'s self, state: &'s SessionState, ttl: Duration) -> Self::SaveFuture<'s> {
+        async move {
+            let payload = CookiePayload {
+                id: state.id.clone(),
+                exp: unix_now().saturating_add(ttl.as_secs()),
+                data: state.data.clone(),
+            };
+            let payload = serde_json::to_vec(&payload).expect("session payload must be serializable");
+
+            let mut jar = CookieJar::new();
+            jar.signed_mut(&self.key)
+                .add(Cookie::new(SIGN_NAME, URL_SAFE_NO_PAD.encode(payload)));
+
+            Ok(jar.get(SIGN_NAME).unwrap().value().to_owned())
+        }
+    }
+
+    fn delete<'s>(&'s self, _: &'s str) -> Self::DeleteFuture<'s> {
+        async { Ok(()) }
+    }
+}
+
+/// Store keep session state in process memory. Mostly useful for testing and single process
+/// deployment.
+#[derive(Clone, Default)]
+pub struct MemoryStore {
+    sessions: Arc<Mutex<HashMap<String, (SessionState, Instant)>>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl SessionStore for MemoryStore {
+    type Error = Infallible;
+    type LoadFuture<'f> = impl Future<Output = Result<Option<SessionState>, Self::Error>> + 'f;
+    type SaveFuture<'f> = impl Future<Output = Result<String, Self::Error>> + 'f;
+    type DeleteFuture<'f> = impl Future<Output = Result<(), Self::Error>> + 'f;
+
+    fn load<'s>(&'s self, key: &'s str) -> Self::LoadFuture<'s> {
+        async move {
+            let mut sessions = self.sessions.lock().unwrap();
+            match sessions.get(key) {
+                Some((_, exp)) if *exp <= Instant::now() => {
+                    sessions.remove(key);
+                    Ok(None)
+                }
+                res => Ok(res.map(|(state, _)| state.clone())),
+            }
+        }
+    }
+
+    fn save<'s>(&'s self, state: &'s SessionState, ttl: Duration) -> Self::SaveFuture<'s> {
+        async move {
+            let exp = Instant::now() + ttl;
+            self.sessions
+                .lock()
+                .unwrap()
+                .insert(state.id.clone(), (state.clone(), exp));
+            Ok(state.id.clone())
+        }
+    }
+
+    fn delete<'s>(&'s self, key: &'s str) -> Self::DeleteFuture<'s> {
+        async move {
+            self.sessions.lock().unwrap().remove(key);
+            Ok(())
+        }
+    }
+}
+
+/// Middleware load session from cookie and store before calling inner service and persist it
+/// after inner service returns when session is mutated.
+pub struct SessionMiddleware<St> {
+    store: Arc<St>,
+    config: Arc<SessionConfig>,
+}
+
+impl<St> Clone for SessionMiddleware<St> {
+    fn clone(&self) -> Self {
+        Self {
+            store: self.store.clone(),
+            config: self.config.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct SessionConfig {
+    name: String,
+    path: String,
+    domain: Option<String>,
+    secure: bool,
+    http_only: bool,
+    same_site: SameSite,
+    ttl: Duration,
+}
+
+impl<St> SessionMiddleware<St>
+where
+    St: SessionStore,
+{
+    /// Construct middleware with given store.
+    ///
+    /// Default cookie attributes are: name `id`, path `/`, `Secure`, `HttpOnly`, `SameSite=Lax`
+    /// and 1 day of session ttl.
+    pub fn new(store: St) -> Self {
+        Self {
+            store: Arc::new(store),
+            config: Arc::new(SessionConfig {
+                name: String::from("id"),
+                path: String::from("/"),
+                domain: None,
+                secure: true,
+                http_only: true,
+                same_site: SameSite::Lax,
+                ttl: Duration::from_secs(60 * 60 * 24),
+            }),
+        }
+    }
+
+    /// Set name of session cookie.
+    pub fn cookie_name(mut self, name: impl Into<String>) -> Self {
+        self.config_mut().name = name.into();
+        self
+    }
+
+    /// Set Path attribute of session cookie.
+    pub fn cookie_path(mut self, path: impl Into<String>) -> Self {
+        self.config_mut().path = path.into();
+        self
+    }
+
+    /// Set Domain attribute of session cookie.
+    pub fn cookie_domain(mut self, domain: impl Into<String>) -> Self {
+        self.config_mut().domain = Some(domain.into());
+        self
+    }
+
+    /// Set Secure attribute of session cookie.
+    pub fn cookie_secure(mut self, secure: bool) -> Self {
+        self.config_mut().secure = secure;
+        self
+    }
+
+    /// Set HttpOnly attribute of session cookie.
+    pub fn cookie_http_only(mut self, http_only: bool) -> Self {
+        self.config_mut().http_only = http_only;
+        self
+    }
+
+    /// Set SameSite attribute of session cookie.
+    pub fn cookie_same_site(mut self, same_site: SameSite) -> Self {
+        self.config_mut().same_site = same_site;
+        self
+    }
+
+    /// Set the duration session would live after it's last mutation. This value is used for both
+    /// Max-Age attribute of session cookie and the expiration of session inside store.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.config_mut().ttl = ttl;
+        self
+    }
+
+    fn config_mut(&mut self) -> &mut SessionConfig {
+        Arc::make_mut(&mut self.config)
+    }
+}
+
+impl<St, S> Service<S> for SessionMiddleware<St> {
+    type Response = SessionService<St, S>;
+    type Error = Infallible;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where Self: 'f, S: 'f;
+
+    fn call<'s>(&'s self, service: S) -> Self::Future<'s>
+    where
+        S: 's,
+    {
+        async {
+            Ok(SessionService {
+                service,
+                store: self.store.clone(),
+                config: self.config.clone(),
+            })
+        }
+    }
+}
+
+pub struct SessionService<St, S> {
+    service: S,
+    store: Arc<St>,
+    config: Arc<SessionConfig>,
+}
+
+pub type SessionServiceError<E> = PipelineE<SessionMiddlewareError, E>;
+
+impl<'r, St, S, C, B, ResB, Err> Service<WebRequest<'r, C, B>> for SessionService<St, S>
+where
+    C: 'r,
+    B: 'r,
+    St: SessionStore,
+    S: for<'rs> Service<WebRequest<'rs, C, B>, Response = WebResponse<ResB>, Error = Err>,
+{
+    type Response = WebResponse<ResB>;
+    type Error = SessionServiceError<Err>;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where Self: 'f, 'r: 'f;
+
+    fn call<'s>(&'s self, mut req: WebRequest<'r, C, B>) -> Self::Future<'s>
+    where
+        'r: 's,
+    {
+        async move {
+            let key = cookie_value(&req, &self.config.name);
+
+            let state = match key {
+                Some(ref key) => self.store.load(key).await.map_err(store_err)?,
+                None => None,
+            };
+
+            let session = Session::new(state.unwrap_or_else(SessionState::new));
+            req.req_mut().extensions_mut().insert(session.clone());
+
+            let mut res = self.service.call(req).await.map_err(SessionServiceError::Second)?;
+
+            let (status, state) = {
+                let mut inner = session.lock();
+                let state = core::mem::take(&mut inner.state);
+                (inner.status, state)
+            };
+
+            let cookie = match status {
+                SessionStatus::Unchanged => return Ok(res),
+                SessionStatus::Purged => {
+                    if let Some(ref key) = key {
+                        self.store.delete(key).await.map_err(store_err)?;
+                    }
+                    let mut cookie = self.cookie(String::new());
+                    cookie.make_removal();
+                    cookie
+                }
+                SessionStatus::Changed | SessionStatus::Renewed => {
+                    if let (SessionStatus::Renewed, Some(key)) = (status, key.as_ref()) {
+                        self.store.delete(key).await.map_err(store_err)?;
+                    }
+                    let value = self.store.save(&state, self.config.ttl).await.map_err(store_err)?;
+                    self.cookie(value)
+                }
+            };
+
+            let value = HeaderValue::try_from(cookie.to_string())
+                .map_err(|e| SessionServiceError::First(SessionMiddlewareError::InvalidCookie(e)))?;
+            res.append_or_merge(SET_COOKIE, value);
+
+            Ok(res)
+        }
+    }
+}
+
+impl<St, S> SessionService<St, S> {
+    fn cookie(&self, value: String) -> Cookie<'static> {
+        let config = &*self.config;
+        let mut cookie = Cookie::new(config.name.clone(), value);
+        cookie.set_path(config.path.clone());
+        if let Some(ref domain) = config.domain {
+            cookie.set_domain(domain.clone());
+        }
+        cookie.set_secure(config.secure);
+        cookie.set_http_only(config.http_only);
+        cookie.set_same_site(config.same_site);
+        cookie.set_max_age(cookie::time::Duration::try_from(config.ttl).ok());
+        cookie
+    }
+}
+
+fn cookie_value<C, B>(req: &WebRequest<'_, C, B>, name: &str) -> Option<String> {
+    req.req()
+        .headers()
+        .get_all(COOKIE)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(';'))
+        .filter_map(|pair| Cookie::parse(pair.trim()).ok())
+        .find(|cookie| cookie.name() == name)
+        .map(|cookie| cookie.value().to_owned())
+}
+
+impl<St, S> ReadyService for SessionService<St, S>
+where
+    S: ReadyService,
+{
+    type Ready = S::Ready;
+    type Future<'f> = S::Future<'f> where Self: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::Future<'_> {
+        self.service.ready()
+    }
+}
+
+/// Error type of [SessionStore].
+pub struct SessionStoreError(Box<dyn error::Error + Send + Sync>);
+
+fn store_err<E, Err>(e: E) -> SessionServiceError<Err>
+where
+    E: error::Error + Send + Sync + 'static,
+{
+    SessionServiceError::First(SessionMiddlewareError::Store(SessionStoreError(Box::new(e))))
+}
+
+impl fmt::Debug for SessionStoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&self.0, f)
+    }
+}
+
+impl fmt::Display for SessionStoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl error::Error for SessionStoreError {}
+
+impl<'r, C, B> Responder<WebRequest<'r, C, B>> for SessionStoreError {
+    type Output = WebResponse;
+    type Future = impl Future<Output = Self::Output>;
+
+    fn respond_to(self, req: WebRequest<'r, C, B>) -> Self::Future {
+        let mut res = req.into_response(Bytes::new());
+        *res.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+        async { res }
+    }
+}
+
+/// Error type of [SessionMiddleware].
+#[derive(Debug)]
+pub enum SessionMiddlewareError {
+    /// Error produced by [SessionStore].
+    Store(SessionStoreError),
+    /// Session cookie can not be encoded as header value. Caused by invalid cookie name, path or
+    /// domain of [SessionMiddleware].
+    InvalidCookie(InvalidHeaderValue),
+}
+
+impl fmt::Display for SessionMiddlewareError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Self::Store(ref e) => fmt::Display::fmt(e, f),
+            Self::InvalidCookie(ref e) => write!(f, "invalid session cookie: {e}"),
+        }
+    }
+}
+
+impl error::Error for SessionMiddlewareError {}
+
+impl<'r, C, B> Responder<WebRequest<'r, C, B>> for SessionMiddlewareError {
+    type Output = WebResponse;
+    type Future = impl Future<Output = Self::Output>;
+
+    fn respond_to(self, req: WebRequest<'r, C, B>) -> Self::Future {
+        let mut res = req.into_response(Bytes::new());
+        *res.status_mut() = StatusCode::INTERNAL_SERVER_ERROR;
+        async { res }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::{
+        handler::handler_service,
+        http::{Request, RequestExt},
+        test::collect_string_body,
+        App,
+    };
+
+    use super::*;
+
+    async fn handler(session: Session, req: &WebRequest<'_>) -> String {
+        if req.req().uri().path() == "/write" {
+            let count = session.get::<u32>("count").unwrap().unwrap_or(0);
+            session.insert("count", count + 1).unwrap();
+        }
+        session.get::<u32>("count").unwrap().unwrap_or(0).to_string()
+    }
+
+    fn request(path: &'static str, cookie: Option<&HeaderValue>) -> Request<RequestExt<crate::body::RequestBody>> {
+        let mut req = Request::new(RequestExt::default());
+        *req.uri_mut() = crate::http::Uri::from_static(path);
+        if let Some(cookie) = cookie {
+            let value = cookie.to_str().unwrap().split(';').next().unwrap();
+            req.headers_mut().insert(COOKIE, HeaderValue::from_str(value).unwrap());
+        }
+        req
+    }
+
+    fn persist<St>(store: St)
+    where
+        St: SessionStore + 'static,
+    {
+        let service = App::new()
+            .at("/write", handler_service(handler))
+            .at("/read", handler_service(handler))
+            .enclosed(SessionMiddleware::new(store))
+            .finish()
+            .call(())
+            .now_or_panic()
+            .unwrap();
+
+        let res = service.call(request("/write", None)).now_or_panic().unwrap();
+        let (parts, body) = res.into_parts();
+        let (headers, body) = (parts.headers, collect_string_body(body).now_or_panic().unwrap());
+        assert_eq!(body, "1");
+        let cookie = headers.get(SET_COOKIE).unwrap().clone();
+
+        let res = service.call(request("/write", Some(&cookie))).now_or_panic().unwrap();
+        let (parts, body) = res.into_parts();
+        let (headers, body) = (parts.headers, collect_string_body(body).now_or_panic().unwrap());
+        assert_eq!(body, "2");
+        let cookie = headers.get(SET_COOKIE).unwrap().clone();
+
+        // unmodified session must not write Set-Cookie header.
+        let res = service.call(request("/read", Some(&cookie))).now_or_panic().unwrap();
+        let (parts, body) = res.into_parts();
+        let (headers, body) = (parts.headers, collect_string_body(body).now_or_panic().unwrap());
+        assert_eq!(body, "2");
+        assert!(headers.get(SET_COOKIE).is_none());
+    }
+
+    #[test]
+    fn memory_store_persist() {
+        persist(MemoryStore::new());
+    }
+
+    #[test]
+    fn cookie_store_persist() {
+        persist(CookieStore::generate());
+    }
+
+    #[test]
+    fn expired_session() {
+        let service = App::new()
+            .at("/write", handler_service(handler))
+            .at("/read", handler_service(handler))
+            .enclosed(SessionMiddleware::new(MemoryStore::new()).ttl(Duration::ZERO))
+            .finish()
+            .call(())
+            .now_or_panic()
+            .unwrap();
+
+        let res = service.call(request("/write", None)).now_or_panic().unwrap();
+        let cookie = res.headers().get(SET_COOKIE).unwrap().clone();
+
+        let res = service.call(request("/read", Some(&cookie))).now_or_panic().unwrap();
+        let body = res.into_body();
+        let body = collect_string_body(body).now_or_panic().unwrap();
+        assert_eq!(body, "0");
+    }
+
+    #[test]
+    fn invalid_cookie() {
+        let service = App::new()
+            .at("/write", handler_service(handler))
+            .enclosed(SessionMiddleware::new(MemoryStore::new()).cookie_path("/\n"))
+            .finish()
+            .call(())
+            .now_or_panic()
+            .unwrap();
+
+        let res = service.call(request("/write", None)).now_or_panic().unwrap();
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        assert!(res.headers().get(SET_COOKIE).is_none());
+    }
+
+    #[test]
+    fn tampered_cookie() {
+        let store = CookieStore::generate();
+        let state = SessionState::new();
+        let key = store.save(&state, Duration::from_secs(60)).now_or_panic().unwrap();
+        assert!(store.load(&key).now_or_panic().unwrap().is_some());
+
+        let mut tampered = key.clone();
+        tampered.push('a');
+        assert!(store.load(&tampered).now_or_panic().unwrap().is_none());
+    }
+
+    #[test]
+    fn renew() {
+        async fn login(session: Session) -> &'static str {
+            session.renew();
+            "ok"
+        }
+
+        let store = MemoryStore::new();
+        let mut state = SessionState::new();
+        state.data.insert("user".into(), "\"foo\"".into());
+        let old_id = store.save(&state, Duration::from_secs(60)).now_or_panic().unwrap();
+
+        let service = App::new()
+            .at("/", handler_service(login))
+            .enclosed(SessionMiddleware::new(store.clone()))
+            .finish()
+            .call(())
+            .now_or_panic()
+            .unwrap();
+
+        let cookie = HeaderValue::from_str(&format!("id={old_id}")).unwrap();
+        let res = service.call(request("/", Some(&cookie))).now_or_panic().unwrap();
+
+        let cookie = res.headers().get(SET_COOKIE).unwrap().to_str().unwrap();
+        let new_id = Cookie::parse(cookie).unwrap().value().to_owned();
+        assert_ne!(old_id, new_id);
+
+        assert!(store.load(&old_id).now_or_panic().unwrap().is_none());
+        let state = store.load(&new_id).now_or_panic().unwrap().unwrap();
+        assert_eq!(state.data().get("user").unwrap(), "\"foo\"");
+    }
+
+    #[test]
+    fn purge_then_insert() {
+        async fn switch(session: Session) -> &'static str {
+            session.purge();
+            session.insert("user", "bar").unwrap();
+            "ok"
+        }
+
+        let store = MemoryStore::new();
+        let mut state = SessionState::new();
+        state.data.insert("user".into(), "\"foo\"".into());
+        state.data.insert("cart".into(), "3".into());
+        let old_id = store.save(&state, Duration::from_secs(60)).now_or_panic().unwrap();
+
+        let service = App::new()
+            .at("/", handler_service(switch))
+            .enclosed(SessionMiddleware::new(store.clone()))
+            .finish()
+            .call(())
+            .now_or_panic()
+            .unwrap();
+
+        let cookie = HeaderValue::from_str(&format!("id={old_id}")).unwrap();
+        let res = service.call(request("/", Some(&cookie))).now_or_panic().unwrap();
+
+        let cookie = Cookie::parse(res.headers().get(SET_COOKIE).unwrap().to_str().unwrap()).unwrap();
+        let new_id = cookie.value().to_owned();
+        assert!(!new_id.is_empty());
+        assert_ne!(old_id, new_id);
+
+        assert!(store.load(&old_id).now_or_panic().unwrap().is_none());
+        let state = store.load(&new_id).now_or_panic().unwrap().unwrap();
+        assert_eq!(state.data().len(), 1);
+        assert_eq!(state.data().get("user").unwrap(), "\"bar\"");
+    }
+}
@@ -0,0 +1,391 @@
+//! request timeout middleware and deadline propagation from caller.
+//!
+//! See [Timeout] and [DeadlineHeader] for usage.
+
+use core::{convert::Infallible, fmt, future::Future, time::Duration};
+
+use std::error;
+
+use tokio::time::{timeout_at, Instant};
+use xitca_http::date::{DateTime, DateTimeService};
+
+use crate::{
+    dev::{
+        bytes::Bytes,
+        service::{pipeline::PipelineE, ready::ReadyService, Service},
+    },
+    handler::{deadline::Deadline, Responder},
+    http::{
+        header::{HeaderName, HeaderValue},
+        StatusCode,
+    },
+    request::WebRequest,
+    response::WebResponse,
+};
+
+/// Middleware failing request with `503 Service Unavailable` when it's not handled before deadline.
+///
+/// Deadline is taken from [Deadline] extension of request when present. (See [DeadlineHeader])
+/// Otherwise it's the static duration counting from the middleware receiving request and it's
+/// inserted into request extensions so handler can extract it.
+///
+/// # Examples:
+/// ```rust
+/// # use std::{convert::Infallible, time::Duration};
+/// # use xitca_web::{dev::service::fn_service, request::WebRequest, response::WebResponse, App};
+/// use xitca_web::middleware::timeout::{DeadlineHeader, Timeout};
+///
+/// # fn doc_example() {
+/// App::new()
+///     .at("/", fn_service(handler))
+///     .enclosed(Timeout::new(Duration::from_secs(5)))
+///     // caller can extend timeout up to 30 seconds with x-request-timeout-ms header.
+///     .enclosed(DeadlineHeader::new(Duration::from_secs(30)));
+/// # }
+///
+/// # async fn handler(req: WebRequest<'_>) -> Result<WebResponse, Infallible> {
+/// #   todo!()
+/// # }
+/// ```
+#[derive(Clone, Copy, Debug)]
+pub struct Timeout {
+    dur: Duration,
+}
+
+impl Timeout {
+    /// Construct middleware with given static duration.
+    pub fn new(dur: Duration) -> Self {
+        Self { dur }
+    }
+}
+
+impl<S> Service<S> for Timeout {
+    type Response = TimeoutService<S>;
+    type Error = Infallible;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where S: 'f;
+
+    fn call<'s>(&'s self, service: S) -> Self::Future<'s>
+    where
+        S: 's,
+    {
+        async { Ok(TimeoutService { service, dur: self.dur }) }
+    }
+}
+
+pub struct TimeoutService<S> {
+    service: S,
+    dur: Duration,
+}
+
+pub type TimeoutServiceError<E> = PipelineE<TimedOut, E>;
+
+impl<'r, S, C, B, Res, Err> Service<WebRequest<'r, C, B>> for TimeoutService<S>
+where
+    C: 'r,
+    B: 'r,
+    S: for<'rs> Service<WebRequest<'rs, C, B>, Response = Res, Error = Err>,
+{
+    type Response = Res;
+    type Error = TimeoutServiceError<Err>;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where Self: 'f, 'r: 'f;
+
+    fn call<'s>(&'s self, mut req: WebRequest<'r, C, B>) -> Self::Future<'s>
+    where
+        'r: 's,
+    {
+        async move {
+            let deadline = match req.req().extensions().get::<Deadline>() {
+                Some(deadline) => deadline.0,
+                None => {
+                    let deadline = Instant::now() + self.dur;
+                    req.req_mut().extensions_mut().insert(Deadline(deadline));
+                    deadline
+                }
+            };
+
+            match timeout_at(deadline, self.service.call(req)).await {
+                Ok(res) => res.map_err(TimeoutServiceError::Second),
+                Err(_) => Err(TimeoutServiceError::First(TimedOut)),
+            }
+        }
+    }
+}
+
+impl<S> ReadyService for TimeoutService<S>
+where
+    S: ReadyService,
+{
+    type Ready = S::Ready;
+    type Future<'f> = S::Future<'f> where Self: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::Future<'_> {
+        self.service.ready()
+    }
+}
+
+/// Error type for request not handled before it's deadline.
+#[derive(Debug)]
+pub struct TimedOut;
+
+impl fmt::Display for TimedOut {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("request is not handled before deadline")
+    }
+}
+
+impl error::Error for TimedOut {}
+
+impl<'r, C, B> Responder<WebRequest<'r, C, B>> for TimedOut {
+    type Output = WebResponse;
+    type Future = impl Future<Output = Self::Output>;
+
+    fn respond_to(self, req: WebRequest<'r, C, B>) -> Self::Future {
+        let mut res = req.into_response(Bytes::new());
+        *res.status_mut() = StatusCode::SERVICE_UNAVAILABLE;
+        async { res }
+    }
+}
+
+/// Middleware parsing remaining time budget propagated by caller from request header and inserting
+/// it into request extensions as [Deadline].
+///
+/// Budget is clamped to the max duration middleware is constructed with so caller can not hold
+/// server resource for arbitrary long time. Request with missing or malformed header passes
+/// through without [Deadline] and [Timeout] falls back to it's static duration.
+///
+/// Deadline is computed from low resolution date service of worker thread so it can be earlier
+/// than expected by around half a second at most.
+///
+/// It should be enclosed after(outside) [Timeout] and any other middleware reading [Deadline].
+#[derive(Clone)]
+pub struct DeadlineHeader {
+    name: HeaderName,
+    parse: fn(&HeaderValue) -> Option<Duration>,
+    max: Duration,
+}
+
+impl DeadlineHeader {
+    /// Construct middleware reading budget from `x-request-timeout-ms` header in milliseconds.
+    pub fn new(max: Duration) -> Self {
+        Self {
+            name: HeaderName::from_static("x-request-timeout-ms"),
+            parse: parse_millis,
+            max,
+        }
+    }
+
+    /// Construct middleware reading budget from `grpc-timeout` header in it's unit suffixed
+    /// format. (`100m` for 100 milliseconds, `5S` for 5 seconds, etc)
+    pub fn grpc_timeout(max: Duration) -> Self {
+        Self {
+            name: HeaderName::from_static("grpc-timeout"),
+            parse: parse_grpc_timeout,
+            max,
+        }
+    }
+
+    /// Change name of header budget is read from. Format of header value is not changed.
+    pub fn header(mut self, name: HeaderName) -> Self {
+        self.name = name;
+        self
+    }
+
+    fn budget(&self, value: &HeaderValue) -> Option<Duration> {
+        (self.parse)(value).map(|dur| dur.min(self.max))
+    }
+}
+
+impl fmt::Debug for DeadlineHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeadlineHeader")
+            .field("name", &self.name)
+            .field("max", &self.max)
+            .finish()
+    }
+}
+
+impl<S> Service<S> for DeadlineHeader {
+    type Response = DeadlineHeaderService<S>;
+    type Error = Infallible;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where S: 'f;
+
+    fn call<'s>(&'s self, service: S) -> Self::Future<'s>
+    where
+        S: 's,
+    {
+        async {
+            Ok(DeadlineHeaderService {
+                service,
+                header: self.clone(),
+                date: DateTimeService::current(),
+            })
+        }
+    }
+}
+
+pub struct DeadlineHeaderService<S> {
+    service: S,
+    header: DeadlineHeader,
+    date: DateTimeService,
+}
+
+impl<'r, S, C, B, Res, Err> Service<WebRequest<'r, C, B>> for DeadlineHeaderService<S>
+where
+    C: 'r,
+    B: 'r,
+    S: for<'rs> Service<WebRequest<'rs, C, B>, Response = Res, Error = Err>,
+{
+    type Response = Res;
+    type Error = Err;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where Self: 'f, 'r: 'f;
+
+    fn call<'s>(&'s self, mut req: WebRequest<'r, C, B>) -> Self::Future<'s>
+    where
+        'r: 's,
+    {
+        if let Some(budget) = req
+            .req()
+            .headers()
+            .get(&self.header.name)
+            .and_then(|v| self.header.budget(v))
+        {
+            let deadline = self.date.get().now() + budget;
+            req.req_mut().extensions_mut().insert(Deadline(deadline));
+        }
+        self.service.call(req)
+    }
+}
+
+impl<S> ReadyService for DeadlineHeaderService<S>
+where
+    S: ReadyService,
+{
+    type Ready = S::Ready;
+    type Future<'f> = S::Future<'f> where Self: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::Future<'_> {
+        self.service.ready()
+    }
+}
+
+fn parse_millis(value: &HeaderValue) -> Option<Duration> {
+    value.to_str().ok()?.trim().parse().ok().map(Duration::from_millis)
+}
+
+// TimeoutValue is a positive integer of at most 8 digits followed by a single unit char.
+fn parse_grpc_timeout(value: &HeaderValue) -> Option<Duration> {
+    let value = value.as_bytes();
+    let (unit, digits) = value.split_last()?;
+    if digits.is_empty() || digits.len() > 8 || !digits.iter().all(u8::is_ascii_digit) {
+        return None;
+    }
+    let n = core::str::from_utf8(digits).ok()?.parse().ok()?;
+    let dur = match unit {
+        b'H' => Duration::from_secs(n * 60 * 60),
+        b'M' => Duration::from_secs(n * 60),
+        b'S' => Duration::from_secs(n),
+        b'm' => Duration::from_millis(n),
+        b'u' => Duration::from_micros(n),
+        b'n' => Duration::from_nanos(n),
+        _ => return None,
+    };
+    Some(dur)
+}
+
+#[cfg(test)]
+mod test {
+    use tokio::task::LocalSet;
+
+    use crate::{handler::handler_service, test::TestRequest, App};
+
+    use super::*;
+
+    #[test]
+    fn grpc_timeout() {
+        let parse = |v| parse_grpc_timeout(&HeaderValue::from_static(v));
+        assert_eq!(parse("50m"), Some(Duration::from_millis(50)));
+        assert_eq!(parse("2H"), Some(Duration::from_secs(7200)));
+        assert_eq!(parse("3M"), Some(Duration::from_secs(180)));
+        assert_eq!(parse("99999999n"), Some(Duration::from_nanos(99999999)));
+        assert_eq!(parse("10u"), Some(Duration::from_micros(10)));
+        assert_eq!(parse("1S"), Some(Duration::from_secs(1)));
+        assert_eq!(parse("123456789S"), None);
+        assert_eq!(parse("m"), None);
+        assert_eq!(parse("-1S"), None);
+        assert_eq!(parse("1s"), None);
+        assert_eq!(parse("1"), None);
+    }
+
+    async fn slow() -> &'static str {
+        tokio::time::sleep(Duration::from_secs(1)).await;
+        "slow"
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn header_deadline() {
+        LocalSet::new()
+            .run_until(async {
+                let service = App::new()
+                    .at("/", handler_service(slow))
+                    .enclosed(Timeout::new(Duration::from_secs(10)))
+                    .enclosed(DeadlineHeader::new(Duration::from_secs(30)))
+                    .finish_for_test()
+                    .await;
+
+                let req = TestRequest::get("/").header("x-request-timeout-ms", "50");
+                let res = service.call(req).await.unwrap();
+                res.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+
+                // missing or malformed header falls back to static duration.
+                for req in [
+                    TestRequest::get("/"),
+                    TestRequest::get("/").header("x-request-timeout-ms", "50ms"),
+                ] {
+                    service.call(req).await.unwrap().assert_status(StatusCode::OK);
+                }
+            })
+            .await
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn static_deadline() {
+        let service = App::new()
+            .at("/", handler_service(slow))
+            .enclosed(Timeout::new(Duration::from_millis(50)))
+            .finish_for_test()
+            .await;
+
+        let res = service.call(TestRequest::get("/")).await.unwrap();
+        res.assert_status(StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn clamp() {
+        async fn remaining(deadline: Deadline) -> String {
+            deadline.remaining().as_secs().to_string()
+        }
+
+        LocalSet::new()
+            .run_until(async {
+                let service = App::new()
+                    .at("/", handler_service(remaining))
+                    .enclosed(Timeout::new(Duration::from_secs(1)))
+                    .enclosed(DeadlineHeader::grpc_timeout(Duration::from_secs(60)))
+                    .finish_for_test()
+                    .await;
+
+                // deadline from header is preferred over static duration and clamped to max.
+                for (timeout, secs) in [("10H", "60"), ("30S", "30")] {
+                    let req = TestRequest::get("/").header("grpc-timeout", timeout);
+                    let res = service.call(req).await.unwrap();
+                    assert_eq!(res.string_body().await.unwrap(), secs);
+                }
+
+                let res = service.call(TestRequest::get("/")).await.unwrap();
+                assert_eq!(res.string_body().await.unwrap(), "1");
+            })
+            .await
+    }
+}
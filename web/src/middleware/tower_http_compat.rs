@@ -12,7 +12,7 @@ use tower_layer::Layer;
 use xitca_unsafe_collection::fake_send_sync::{FakeSend, FakeSync};
 
 use crate::{
-    dev::service::Service,
+    dev::service::{ready::ReadyService, Service},
     http::{Request, RequestExt, Response},
     request::WebRequest,
     response::WebResponse,
@@ -41,9 +41,7 @@ where
 impl<L, C, ReqB, ResB, Err> TowerHttpCompat<L, C, ReqB, ResB, Err> {
     /// Construct a new xitca-web middleware from tower-http layer type.
     ///
-    /// # Limitation:
-    /// tower::Service::poll_ready implementation is ingorend by TowerHttpCompat.
-    /// if a tower Service is sensitive to poll_ready implemetnation it should not be used.
+    /// tower::Service::poll_ready is polled until the service is ready before every call to it.
     ///
     /// # Example:
     /// ```rust
@@ -76,7 +74,7 @@ where
     L: Layer<CompatLayer<S, C, ReqB, ResB, Err>>,
     S: for<'r> Service<WebRequest<'r, C, ReqB>, Response = WebResponse<ResB>, Error = Err>,
 {
-    type Response = TowerCompatService<L::Service>;
+    type Response = TowerHttpCompatService<S, L::Service>;
     type Error = Infallible;
     type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where Self: 'f, S: 'f;
 
@@ -84,11 +82,54 @@ where
     where
         S: 's,
     {
-        let service = self.layer.layer(CompatLayer {
-            service: Rc::new(service),
+        let service = Rc::new(service);
+        let tower = self.layer.layer(CompatLayer {
+            service: service.clone(),
             _phantom: PhantomData,
         });
-        async { Ok(TowerCompatService::new(service)) }
+        async {
+            Ok(TowerHttpCompatService {
+                service,
+                tower: TowerCompatService::new(tower),
+            })
+        }
+    }
+}
+
+/// Service produced by [TowerHttpCompat] middleware. Readiness is delegated to the wrapped
+/// xitca service while requests are passed through the tower layer.
+pub struct TowerHttpCompatService<S, T> {
+    service: Rc<S>,
+    tower: TowerCompatService<T>,
+}
+
+impl<'r, C, ReqB, S, T> Service<WebRequest<'r, C, ReqB>> for TowerHttpCompatService<S, T>
+where
+    TowerCompatService<T>: Service<WebRequest<'r, C, ReqB>>,
+{
+    type Response = <TowerCompatService<T> as Service<WebRequest<'r, C, ReqB>>>::Response;
+    type Error = <TowerCompatService<T> as Service<WebRequest<'r, C, ReqB>>>::Error;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where Self: 'f, 'r: 'f;
+
+    #[inline]
+    fn call<'s>(&'s self, req: WebRequest<'r, C, ReqB>) -> Self::Future<'s>
+    where
+        'r: 's,
+    {
+        self.tower.call(req)
+    }
+}
+
+impl<S, T> ReadyService for TowerHttpCompatService<S, T>
+where
+    S: ReadyService,
+{
+    type Ready = S::Ready;
+    type Future<'f> = S::Future<'f> where Self: 'f;
+
+    #[inline]
+    fn ready(&self) -> Self::Future<'_> {
+        self.service.ready()
     }
 }
 
@@ -168,4 +209,111 @@ mod test {
 
         assert_eq!(res.status(), StatusCode::NOT_FOUND);
     }
+
+    struct Inner(Rc<core::cell::Cell<usize>>);
+
+    impl<'r> Service<WebRequest<'r, &'static str>> for Inner {
+        type Response = WebResponse;
+        type Error = Infallible;
+        type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where Self: 'f, 'r: 'f;
+
+        fn call<'s>(&'s self, req: WebRequest<'r, &'static str>) -> Self::Future<'s>
+        where
+            'r: 's,
+        {
+            handler(req)
+        }
+    }
+
+    impl ReadyService for Inner {
+        type Ready = ();
+        type Future<'f> = impl Future<Output = Self::Ready> where Self: 'f;
+
+        fn ready(&self) -> Self::Future<'_> {
+            self.0.set(self.0.get() + 1);
+            async {}
+        }
+    }
+
+    #[test]
+    fn tower_delegate_ready() {
+        let count = Rc::new(core::cell::Cell::new(0));
+
+        let layer = SetStatusLayer::new(StatusCode::NOT_FOUND);
+        let service = TowerHttpCompat::<_, &'static str, _, _, Infallible>::new(layer)
+            .call(Inner(count.clone()))
+            .now_or_panic()
+            .unwrap();
+
+        service.ready().now_or_panic();
+        assert_eq!(count.get(), 1);
+    }
+
+    #[derive(Clone)]
+    struct ReadyCountLayer(Rc<core::cell::Cell<usize>>);
+
+    struct ReadyCount<S> {
+        service: S,
+        count: Rc<core::cell::Cell<usize>>,
+    }
+
+    impl<S> Layer<S> for ReadyCountLayer {
+        type Service = ReadyCount<S>;
+
+        fn layer(&self, service: S) -> Self::Service {
+            ReadyCount {
+                service,
+                count: self.0.clone(),
+            }
+        }
+    }
+
+    impl<S, Req> tower_service::Service<Req> for ReadyCount<S>
+    where
+        S: tower_service::Service<Req>,
+    {
+        type Response = S::Response;
+        type Error = S::Error;
+        type Future = S::Future;
+
+        fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            // pending on first poll to make sure readiness is awaited.
+            let count = self.count.get() + 1;
+            self.count.set(count);
+            if count % 2 == 1 {
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            self.service.poll_ready(cx)
+        }
+
+        fn call(&mut self, req: Req) -> Self::Future {
+            assert_eq!(self.count.get() % 2, 0, "service called before it's ready");
+            self.service.call(req)
+        }
+    }
+
+    #[test]
+    fn tower_poll_ready() {
+        let count = Rc::new(core::cell::Cell::new(0));
+
+        let service = App::with_current_thread_state("996")
+            .at("/", fn_service(handler))
+            .enclosed(TowerHttpCompat::new(ReadyCountLayer(count.clone())))
+            .finish()
+            .call(())
+            .now_or_panic()
+            .unwrap();
+
+        for _ in 0..2 {
+            let res = tokio::runtime::Builder::new_current_thread()
+                .build()
+                .unwrap()
+                .block_on(service.call(Request::default()))
+                .unwrap();
+            assert_eq!(res.status(), StatusCode::OK);
+        }
+
+        assert_eq!(count.get(), 4);
+    }
 }
@@ -7,7 +7,10 @@ use core::{
 
 use crate::http::{BorrowReq, BorrowReqMut, IntoResponse, Request, RequestExt};
 
-use super::{body::ResponseBody, response::WebResponse};
+use super::{
+    body::ResponseBody,
+    response::{ResponseHead, ResponseHooks, WebResponse},
+};
 
 pub struct WebRequest<'a, C = (), B = RequestBody> {
     pub(crate) req: &'a mut Request<RequestExt<()>>,
@@ -90,6 +93,39 @@ impl<'a, C, B> WebRequest<'a, C, B> {
         self.body.get_mut()
     }
 
+    /// Register a closure to be called with head of response right before it's returned from App.
+    ///
+    /// Closures are called in registration order after request is handled, including when handler
+    /// or middleware returned an error and it's converted into response. This is useful for
+    /// middleware adding headers depending on data captured before request is handled.
+    ///
+    /// Nothing is stored and no extra cost is paid when no closure is registered.
+    ///
+    /// # Examples:
+    /// ```rust
+    /// # use xitca_web::{http::header::{HeaderValue, HeaderName}, request::WebRequest};
+    /// fn cache_status(req: &mut WebRequest<'_>, hit: bool) {
+    ///     req.on_response(move |head| {
+    ///         let value = HeaderValue::from_static(if hit { "HIT" } else { "MISS" });
+    ///         head.headers.insert(HeaderName::from_static("x-cache"), value);
+    ///     });
+    /// }
+    /// ```
+    pub fn on_response<F>(&mut self, func: F)
+    where
+        F: FnOnce(&mut ResponseHead) + Send + Sync + 'static,
+    {
+        let ext = self.req.extensions_mut();
+        match ext.get_mut::<ResponseHooks>() {
+            Some(hooks) => hooks.push(Box::new(func)),
+            None => {
+                let mut hooks = ResponseHooks::default();
+                hooks.push(Box::new(func));
+                ext.insert(hooks);
+            }
+        }
+    }
+
     pub fn take_request(&mut self) -> Request<RequestExt<B>>
     where
         B: Default,
@@ -1,7 +1,191 @@
 pub use xitca_http::http::response::Builder as WebResponseBuilder;
 
-use xitca_http::http::Response;
+use xitca_http::http::{
+    header::{self, HeaderMap, HeaderName, HeaderValue},
+    response::Parts,
+    Extensions, Response,
+};
 
 use super::body::ResponseBody;
 
 pub type WebResponse<B = ResponseBody> = Response<B>;
+
+/// Head of response passed to closures registered by [WebRequest::on_response].
+///
+/// [WebRequest::on_response]: crate::request::WebRequest::on_response
+pub type ResponseHead = Parts;
+
+type ResponseHook = Box<dyn FnOnce(&mut ResponseHead) + Send + Sync>;
+
+// closures registered by WebRequest::on_response. stored in request extensions.
+#[derive(Default)]
+pub(crate) struct ResponseHooks(Vec<ResponseHook>);
+
+impl ResponseHooks {
+    pub(crate) fn push(&mut self, hook: ResponseHook) {
+        self.0.push(hook);
+    }
+
+    // run hooks found in response's and request's extensions in registration order.
+    // response extensions come first as they are moved from request when response is constructed
+    // from it.
+    pub(crate) fn run<B>(mut res: WebResponse<B>, req: &mut Extensions) -> WebResponse<B> {
+        let hooks = (res.extensions_mut().remove::<Self>(), req.remove::<Self>());
+
+        if let (None, None) = hooks {
+            return res;
+        }
+
+        let (mut head, body) = res.into_parts();
+
+        for hook in hooks.0.into_iter().chain(hooks.1).flat_map(|hooks| hooks.0) {
+            hook(&mut head);
+        }
+
+        Response::from_parts(head, body)
+    }
+}
+
+/// Policy of writing a header to response when the header name is already present.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HeaderPolicy {
+    /// Value is added as a separate header line. (e.g. `Set-Cookie` which must never be merged)
+    Append,
+    /// Value is merged into existing value as comma separated list with duplicate elements
+    /// removed. (e.g. `Cache-Control`, `Vary` and `Accept-*`)
+    Merge,
+    /// Value replaces existing value. (e.g. `Content-Length` and `Content-Type`)
+    Replace,
+}
+
+impl HeaderPolicy {
+    /// Policy of given header name. Header not known to be mergeable or singleton is appended.
+    pub fn from_name(name: &HeaderName) -> Self {
+        match *name {
+            header::CACHE_CONTROL
+            | header::VARY
+            | header::ALLOW
+            | header::CONTENT_LANGUAGE
+            | header::ACCESS_CONTROL_ALLOW_HEADERS
+            | header::ACCESS_CONTROL_ALLOW_METHODS
+            | header::ACCESS_CONTROL_EXPOSE_HEADERS => Self::Merge,
+            header::CONTENT_LENGTH
+            | header::CONTENT_TYPE
+            | header::CONTENT_ENCODING
+            | header::CONTENT_RANGE
+            | header::LOCATION
+            | header::ETAG
+            | header::LAST_MODIFIED
+            | header::EXPIRES
+            | header::DATE
+            | header::RETRY_AFTER
+            | header::ACCESS_CONTROL_ALLOW_ORIGIN
+            | header::ACCESS_CONTROL_ALLOW_CREDENTIALS
+            | header::ACCESS_CONTROL_MAX_AGE => Self::Replace,
+            _ if name.as_str().starts_with("accept-") => Self::Merge,
+            _ => Self::Append,
+        }
+    }
+}
+
+/// Extension trait for writing response headers according to [HeaderPolicy] of header name.
+///
+/// Middlewares stacked on top of each other can use it to add headers without clobbering or
+/// duplicating the ones already written by handler or other middlewares.
+///
+/// # Examples:
+/// ```rust
+/// use xitca_web::{
+///     http::header::{HeaderValue, SET_COOKIE, VARY},
+///     response::{ResponseHeadersExt, WebResponse},
+/// };
+///
+/// let mut res = WebResponse::new(());
+///
+/// res.append_or_merge(VARY, HeaderValue::from_static("origin"));
+/// res.append_or_merge(VARY, HeaderValue::from_static("accept-encoding, Origin"));
+/// assert_eq!(res.headers().get(VARY).unwrap(), "origin, accept-encoding");
+///
+/// res.append_or_merge(SET_COOKIE, HeaderValue::from_static("foo=1"));
+/// res.append_or_merge(SET_COOKIE, HeaderValue::from_static("bar=2"));
+/// assert_eq!(res.headers().get_all(SET_COOKIE).iter().count(), 2);
+/// ```
+pub trait ResponseHeadersExt {
+    /// Write header with given name and value following [HeaderPolicy::from_name].
+    fn append_or_merge(&mut self, name: HeaderName, value: HeaderValue);
+}
+
+impl ResponseHeadersExt for HeaderMap {
+    fn append_or_merge(&mut self, name: HeaderName, value: HeaderValue) {
+        match HeaderPolicy::from_name(&name) {
+            HeaderPolicy::Append => {
+                self.append(name, value);
+            }
+            HeaderPolicy::Replace => {
+                self.insert(name, value);
+            }
+            HeaderPolicy::Merge => match merge(self, &name, &value) {
+                Some(value) => {
+                    self.insert(name, value);
+                }
+                // fall back to append when existing values are not mergeable.
+                None => {
+                    self.append(name, value);
+                }
+            },
+        }
+    }
+}
+
+impl<B> ResponseHeadersExt for Response<B> {
+    #[inline]
+    fn append_or_merge(&mut self, name: HeaderName, value: HeaderValue) {
+        self.headers_mut().append_or_merge(name, value)
+    }
+}
+
+fn merge(headers: &HeaderMap, name: &HeaderName, value: &HeaderValue) -> Option<HeaderValue> {
+    let mut elements = Vec::new();
+
+    for value in headers.get_all(name).iter().chain(Some(value)) {
+        let value = value.to_str().ok()?;
+        for element in value.split(',').map(str::trim).filter(|e| !e.is_empty()) {
+            if !elements.iter().any(|e: &&str| e.eq_ignore_ascii_case(element)) {
+                elements.push(element);
+            }
+        }
+    }
+
+    HeaderValue::from_str(&elements.join(", ")).ok()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn append_or_merge() {
+        let mut headers = HeaderMap::new();
+
+        headers.append(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"));
+        headers.append(header::CACHE_CONTROL, HeaderValue::from_static("private"));
+        headers.append_or_merge(header::CACHE_CONTROL, HeaderValue::from_static("No-Cache, max-age=0"));
+        let mut values = headers.get_all(header::CACHE_CONTROL).iter();
+        assert_eq!(values.next().unwrap(), "no-cache, private, max-age=0");
+        assert!(values.next().is_none());
+
+        headers.append_or_merge(header::CONTENT_TYPE, HeaderValue::from_static("text/plain"));
+        headers.append_or_merge(header::CONTENT_TYPE, HeaderValue::from_static("text/html"));
+        let mut values = headers.get_all(header::CONTENT_TYPE).iter();
+        assert_eq!(values.next().unwrap(), "text/html");
+        assert!(values.next().is_none());
+
+        headers.append_or_merge(header::SET_COOKIE, HeaderValue::from_static("foo=1; Path=/"));
+        headers.append_or_merge(header::SET_COOKIE, HeaderValue::from_static("foo=1; Path=/"));
+        assert_eq!(headers.get_all(header::SET_COOKIE).iter().count(), 2);
+
+        headers.append_or_merge(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+        headers.append_or_merge(header::ACCEPT_RANGES, HeaderValue::from_static("bytes"));
+        assert_eq!(headers.get_all(header::ACCEPT_RANGES).iter().count(), 1);
+    }
+}
@@ -1,12 +1,24 @@
-use std::{fmt, future::Future, time::Duration};
+pub use xitca_http::config::{ConnectionEvent, HttpServiceConfig, WriteBufStrategy};
+
+use std::{
+    fmt,
+    future::Future,
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
 
 use futures_core::stream::Stream;
 use xitca_http::{
     body::RequestBody,
-    config::{HttpServiceConfig, DEFAULT_HEADER_LIMIT, DEFAULT_READ_BUF_LIMIT, DEFAULT_WRITE_BUF_LIMIT},
+    config::{DEFAULT_HEADER_LIMIT, DEFAULT_READ_BUF_LIMIT, DEFAULT_WRITE_BUF_LIMIT},
+    util::drain::Drain,
     HttpServiceBuilder,
 };
-use xitca_server::{Builder, ServerFuture};
+use xitca_server::{Builder, ServerFuture, ServerHandle};
 
 use crate::{
     dev::{
@@ -25,6 +37,7 @@ pub struct HttpServer<
     factory: F,
     builder: Builder,
     config: HttpServiceConfig<HEADER_LIMIT, READ_BUF_LIMIT, WRITE_BUF_LIMIT>,
+    drain: DrainHandle,
 }
 
 impl<F, I> HttpServer<F>
@@ -36,6 +49,7 @@ where
             factory,
             builder: Builder::new(),
             config: HttpServiceConfig::default(),
+            drain: DrainHandle::new(),
         }
     }
 }
@@ -97,6 +111,14 @@ where
         self
     }
 
+    /// Change strategy of Http/1 response write buffer.
+    ///
+    /// See [WriteBufStrategy] for detail.
+    pub fn write_buf_strategy(mut self, strategy: WriteBufStrategy) -> Self {
+        self.config = self.config.write_buf_strategy(strategy);
+        self
+    }
+
     /// Change keep alive duration for Http/1 connection.
     ///
     /// Connection kept idle for this duration would be closed.
@@ -105,6 +127,14 @@ where
         self
     }
 
+    /// Change granularity of connection timers.
+    ///
+    /// See [HttpServiceConfig::keep_alive_granularity] for detail.
+    pub fn keep_alive_granularity(mut self, dur: Duration) -> Self {
+        self.config = self.config.keep_alive_granularity(dur);
+        self
+    }
+
     /// Change request timeout for Http/1 connection.
     ///
     /// Connection can not finish it's request for this duration would be closed.
@@ -123,6 +153,74 @@ where
         self
     }
 
+    /// Set observer function called on [ConnectionEvent] of every served connection.
+    ///
+    /// See [HttpServiceConfig::connection_observer] for detail.
+    pub fn connection_observer(mut self, observer: fn(SocketAddr, ConnectionEvent)) -> Self {
+        self.config = self.config.connection_observer(observer);
+        self
+    }
+
+    /// Change runtime part of [HttpServiceConfig] used by server with given function.
+    ///
+    /// # Examples:
+    /// ```rust,no_run
+    /// # use std::time::Duration;
+    /// # use xitca_web::{handler::handler_service, App, HttpServer};
+    /// # fn doc_example() -> std::io::Result<()> {
+    /// HttpServer::new(|| App::new().at("/", handler_service(|| async { "" })).finish())
+    ///     .configure_http(|config| {
+    ///         config
+    ///             .keep_alive_timeout(Duration::from_secs(30))
+    ///             .max_uri_length(16 * 1024)
+    ///     })
+    ///     .bind("0.0.0.0:8080")?
+    ///     .run();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn configure_http<FC>(mut self, func: FC) -> Self
+    where
+        FC: FnOnce(
+            HttpServiceConfig<HEADER_LIMIT, READ_BUF_LIMIT, WRITE_BUF_LIMIT>,
+        ) -> HttpServiceConfig<HEADER_LIMIT, READ_BUF_LIMIT, WRITE_BUF_LIMIT>,
+    {
+        self.config = func(self.config);
+        self
+    }
+
+    /// Change const generic limits of server to a preset of [profile] module.
+    ///
+    /// Runtime part of config is kept as is.
+    ///
+    /// # Examples:
+    /// ```rust,no_run
+    /// # use xitca_web::{handler::handler_service, profile, App, HttpServer};
+    /// # fn doc_example() -> std::io::Result<()> {
+    /// // accept request with up to 128 header fields.
+    /// HttpServer::new(|| App::new().at("/", handler_service(|| async { "" })).finish())
+    ///     .http_config_profile(profile::LargeHeaders)
+    ///     .bind("0.0.0.0:8080")?
+    ///     .run();
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn http_config_profile<P>(self, _: P) -> P::Server<F>
+    where
+        P: profile::Profile,
+    {
+        P::apply(self)
+    }
+
+    /// Obtain a handle for draining connections of server. See [DrainHandle] for detail.
+    ///
+    /// It can be called at any point before [HttpServer::run] and connections of all listeners are
+    /// drained. Connections are not watched for draining when it's never called.
+    pub fn drain_handle(&self) -> DrainHandle {
+        self.drain.shared.enabled.store(true, Ordering::Release);
+        self.drain.clone()
+    }
+
     /// Change max size for request head.
     ///
     /// Request has a bigger head than it would be reject with error.
@@ -178,9 +276,12 @@ where
     {
         let factory = self.factory.clone();
         let config = self.config;
+        let drain = self.drain.clone();
         self.builder = self.builder.bind("xitca-web", addr, move || {
             let factory = factory();
-            HttpServiceBuilder::with_config(factory, config).with_logger()
+            drain
+                .attach(HttpServiceBuilder::with_config(factory, config))
+                .with_logger()
         })?;
 
         Ok(self)
@@ -197,9 +298,12 @@ where
     {
         let factory = self.factory.clone();
         let config = self.config;
+        let drain = self.drain.clone();
         self.builder = self.builder.listen("xitca-web", listener, move || {
             let factory = factory();
-            HttpServiceBuilder::with_config(factory, config).with_logger()
+            drain
+                .attach(HttpServiceBuilder::with_config(factory, config))
+                .with_logger()
         });
 
         Ok(self)
@@ -221,6 +325,7 @@ where
     {
         let factory = self.factory.clone();
         let config = self.config;
+        let drain = self.drain.clone();
 
         const H11: &[u8] = b"\x08http/1.1";
 
@@ -253,7 +358,8 @@ where
 
         self.builder = self.builder.bind("xitca-web-openssl", addr, move || {
             let factory = factory();
-            HttpServiceBuilder::with_config(factory, config)
+            drain
+                .attach(HttpServiceBuilder::with_config(factory, config))
                 .openssl(acceptor.clone())
                 .with_logger()
         })?;
@@ -278,6 +384,7 @@ where
     {
         let factory = self.factory.clone();
         let service_config = self.config;
+        let drain = self.drain.clone();
 
         #[cfg(feature = "http2")]
         config.alpn_protocols.push("h2".into());
@@ -289,7 +396,8 @@ where
 
         self.builder = self.builder.bind("xitca-web-rustls", addr, move || {
             let factory = factory();
-            HttpServiceBuilder::with_config(factory, service_config)
+            drain
+                .attach(HttpServiceBuilder::with_config(factory, service_config))
                 .rustls(config.clone())
                 .with_logger()
         })?;
@@ -297,6 +405,40 @@ where
         Ok(self)
     }
 
+    /// Bind to address with a rustls config that can be reloaded at runtime.
+    ///
+    /// # Note:
+    /// Unlike [HttpServer::bind_rustls] alpn protocols are not added to the config. Configs given
+    /// to acceptor must have `h2` and/or `http/1.1` alpn protocols set for http version negotiation.
+    #[cfg(feature = "rustls")]
+    pub fn bind_rustls_reloadable<A: std::net::ToSocketAddrs, ResB, BE>(
+        mut self,
+        addr: A,
+        acceptor: xitca_http::ReloadableTlsAcceptor,
+    ) -> std::io::Result<Self>
+    where
+        I: Service + 'static,
+        I::Response: ReadyService + Service<Request<RequestExt<RequestBody>>, Response = Response<ResB>> + 'static,
+        <I::Response as Service<Request<RequestExt<RequestBody>>>>::Error: fmt::Debug,
+
+        ResB: Stream<Item = Result<Bytes, BE>> + 'static,
+        BE: fmt::Debug + 'static,
+    {
+        let factory = self.factory.clone();
+        let config = self.config;
+        let drain = self.drain.clone();
+
+        self.builder = self.builder.bind("xitca-web-rustls", addr, move || {
+            let factory = factory();
+            drain
+                .attach(HttpServiceBuilder::with_config(factory, config))
+                .rustls_reloadable(acceptor.clone())
+                .with_logger()
+        })?;
+
+        Ok(self)
+    }
+
     #[cfg(unix)]
     pub fn bind_unix<P: AsRef<std::path::Path>, ResB, BE>(mut self, path: P) -> std::io::Result<Self>
     where
@@ -309,17 +451,29 @@ where
     {
         let factory = self.factory.clone();
         let config = self.config;
+        let drain = self.drain.clone();
 
         self.builder = self.builder.bind_unix("xitca-web", path, move || {
             let factory = factory();
-            HttpServiceBuilder::with_config(factory, config).with_logger()
+            drain
+                .attach(HttpServiceBuilder::with_config(factory, config))
+                .with_logger()
         })?;
 
         Ok(self)
     }
 
     pub fn run(self) -> ServerFuture {
-        self.builder.build()
+        let mut fut = self.builder.build();
+        if let ServerFuture::Init { .. } = fut {
+            let handle = fut.handle().unwrap();
+            // drain could be started before server runs.
+            if self.drain.is_draining() {
+                handle.stop(true);
+            }
+            *self.drain.shared.server.lock().unwrap() = Some(handle);
+        }
+        fut
     }
 
     fn mutate_const_generic<const HEADER_LIMIT2: usize, const READ_BUF_LIMIT2: usize, const WRITE_BUF_LIMIT2: usize>(
@@ -331,6 +485,238 @@ where
             config: self
                 .config
                 .mutate_const_generic::<HEADER_LIMIT2, READ_BUF_LIMIT2, WRITE_BUF_LIMIT2>(),
+            drain: self.drain,
+        }
+    }
+}
+
+/// Preset combinations of const generic limits for [HttpServer::http_config_profile].
+///
+/// Const generic limits can not be chosen at runtime. Use [HttpServer::max_request_headers],
+/// [HttpServer::max_read_buf_size] and [HttpServer::max_write_buf_size] for limits not covered
+/// by presets.
+pub mod profile {
+    use xitca_http::config::{DEFAULT_HEADER_LIMIT, DEFAULT_READ_BUF_LIMIT, DEFAULT_WRITE_BUF_LIMIT};
+
+    use super::HttpServer;
+
+    /// Trait for preset of const generic limits.
+    pub trait Profile {
+        /// Type of server with limits of preset.
+        type Server<F>;
+
+        #[doc(hidden)]
+        fn apply<F, I, const HEADER_LIMIT: usize, const READ_BUF_LIMIT: usize, const WRITE_BUF_LIMIT: usize>(
+            server: HttpServer<F, HEADER_LIMIT, READ_BUF_LIMIT, WRITE_BUF_LIMIT>,
+        ) -> Self::Server<F>
+        where
+            F: Fn() -> I + Send + Sync + Clone + 'static;
+    }
+
+    macro_rules! profile {
+        ($(#[$meta:meta])* $name: ident, $header: expr, $read: expr, $write: expr) => {
+            $(#[$meta])*
+            #[derive(Clone, Copy, Debug, Default)]
+            pub struct $name;
+
+            impl Profile for $name {
+                type Server<F> = HttpServer<F, { $header }, { $read }, { $write }>;
+
+                fn apply<F, I, const HEADER_LIMIT: usize, const READ_BUF_LIMIT: usize, const WRITE_BUF_LIMIT: usize>(
+                    server: HttpServer<F, HEADER_LIMIT, READ_BUF_LIMIT, WRITE_BUF_LIMIT>,
+                ) -> Self::Server<F>
+                where
+                    F: Fn() -> I + Send + Sync + Clone + 'static,
+                {
+                    server.mutate_const_generic()
+                }
+            }
+        };
+    }
+
+    profile!(
+        /// Default limits. 64 header fields, 1mb read buffer and 408kb write buffer.
+        Standard,
+        DEFAULT_HEADER_LIMIT,
+        DEFAULT_READ_BUF_LIMIT,
+        DEFAULT_WRITE_BUF_LIMIT
+    );
+
+    profile!(
+        /// Limits for requests with large amount of header fields. 128 header fields, 1mb read
+        /// buffer and 408kb write buffer.
+        ///
+        /// Large header values like big cookies are limited by read buffer size instead.
+        LargeHeaders,
+        128,
+        DEFAULT_READ_BUF_LIMIT,
+        DEFAULT_WRITE_BUF_LIMIT
+    );
+
+    profile!(
+        /// Limits for memory constrained environment. 16 header fields, 64kb read buffer and 16kb
+        /// write buffer.
+        Embedded,
+        16,
+        64 * 1024,
+        16 * 1024
+    );
+}
+
+/// Handle for draining connections of [HttpServer] on graceful shutdown. For example when the
+/// server is replaced by a new process in a zero downtime upgrade.
+///
+/// [DrainHandle::drain] stops server from accepting new connections and:
+/// - Http/1 connections waiting for next request are closed immediately. In-flight requests are
+///   served and their responses are sent with `connection: close` header so clients reconnect.
+/// - Http/2 connections are shut down gracefully with `GOAWAY` frame.
+///
+/// Server is stopped when all connections are closed or shutdown timeout is reached. Calling
+/// [DrainHandle::drain] before [HttpServer::run] stops server right after it starts.
+#[derive(Clone)]
+pub struct DrainHandle {
+    drain: Drain,
+    shared: Arc<DrainShared>,
+}
+
+struct DrainShared {
+    // set when handle is obtained from HttpServer.
+    enabled: AtomicBool,
+    server: Mutex<Option<ServerHandle>>,
+}
+
+impl DrainHandle {
+    fn new() -> Self {
+        Self {
+            drain: Drain::new(),
+            shared: Arc::new(DrainShared {
+                enabled: AtomicBool::new(false),
+                server: Mutex::new(None),
+            }),
         }
     }
+
+    // attach drain signal to builder when handle is obtained. it's called when service is built
+    // on worker thread after server runs.
+    fn attach<V, St, SF, FA, const HEADER_LIMIT: usize, const READ_BUF_LIMIT: usize, const WRITE_BUF_LIMIT: usize>(
+        &self,
+        builder: HttpServiceBuilder<V, St, SF, FA, HEADER_LIMIT, READ_BUF_LIMIT, WRITE_BUF_LIMIT>,
+    ) -> HttpServiceBuilder<V, St, SF, FA, HEADER_LIMIT, READ_BUF_LIMIT, WRITE_BUF_LIMIT> {
+        if self.shared.enabled.load(Ordering::Acquire) {
+            builder.drain(self.drain.clone())
+        } else {
+            builder
+        }
+    }
+
+    /// Start draining connections and stop server gracefully.
+    pub fn drain(&self) {
+        self.drain.drain();
+        if let Some(ref handle) = *self.shared.server.lock().unwrap() {
+            handle.stop(true);
+        }
+    }
+
+    /// Check if draining is started.
+    pub fn is_draining(&self) -> bool {
+        self.drain.is_draining()
+    }
+}
+
+#[cfg(all(test, feature = "http1"))]
+mod test {
+    use std::{
+        io::{Read, Write},
+        net::{TcpListener, TcpStream},
+    };
+
+    use crate::{handler::handler_service, App};
+
+    use super::*;
+
+    // send request with given number of header fields to server and return it's response.
+    fn request(addr: SocketAddr, mut server: ServerFuture, headers: usize) -> String {
+        let handle = server.handle().unwrap();
+        let thread = std::thread::spawn(move || server.wait());
+
+        let mut req = String::from("GET / HTTP/1.1\r\nconnection: close\r\n");
+        for i in 0..headers {
+            req.push_str(&format!("x-header-{i}: foo\r\n"));
+        }
+        req.push_str("\r\n");
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(req.as_bytes()).unwrap();
+        let mut res = String::new();
+        stream.read_to_string(&mut res).unwrap();
+
+        handle.stop(false);
+        thread.join().unwrap().unwrap();
+
+        res
+    }
+
+    #[test]
+    fn http_config_profile() {
+        let app = || App::new().at("/", handler_service(|| async { "996" })).finish();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = HttpServer::new(app)
+            .disable_signal()
+            .worker_threads(1)
+            .listen(listener)
+            .unwrap()
+            .run();
+        let res = request(addr, server, 100);
+        assert!(res.starts_with("HTTP/1.1 431"), "{res}");
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = HttpServer::new(app)
+            .disable_signal()
+            .worker_threads(1)
+            .http_config_profile(profile::LargeHeaders)
+            .configure_http(|config| config.max_uri_length(1024))
+            .listen(listener)
+            .unwrap()
+            .run();
+        let res = request(addr, server, 100);
+        assert!(res.starts_with("HTTP/1.1 200"), "{res}");
+        assert!(res.ends_with("996"));
+    }
+
+    #[test]
+    fn drain_handle_after_bind() {
+        let app = || App::new().at("/", handler_service(|| async { "996" })).finish();
+
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = HttpServer::new(app)
+            .disable_signal()
+            .worker_threads(1)
+            .listen(listener)
+            .unwrap();
+
+        // handle obtained after listener is bound still drains it's connections.
+        let drain = server.drain_handle();
+        let server = server.run();
+        let thread = std::thread::spawn(move || server.wait());
+
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(b"GET / HTTP/1.1\r\n\r\n").unwrap();
+        let mut res = Vec::new();
+        let mut buf = [0; 1024];
+        while !res.ends_with(b"996") {
+            let n = stream.read(&mut buf).unwrap();
+            assert_ne!(n, 0);
+            res.extend_from_slice(&buf[..n]);
+        }
+
+        // idle keep-alive connection is closed on draining and server stops after it.
+        drain.drain();
+        assert!(drain.is_draining());
+        assert_eq!(stream.read(&mut buf).unwrap(), 0);
+        thread.join().unwrap().unwrap();
+    }
 }
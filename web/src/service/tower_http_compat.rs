@@ -1,8 +1,9 @@
 use std::{
     cell::RefCell,
     convert::Infallible,
-    future::Future,
+    future::{poll_fn, Future},
     pin::Pin,
+    rc::Rc,
     task::{Context, Poll},
 };
 
@@ -11,7 +12,7 @@ use http_body::{Body, SizeHint};
 use pin_project_lite::pin_project;
 use xitca_http::{
     body::{none_body_hint, BodySize},
-    util::service::router::PathGen,
+    util::service::router::{Describe, PathGen},
 };
 use xitca_unsafe_collection::fake_send_sync::{FakeSend, FakeSync};
 
@@ -58,6 +59,8 @@ where
 
 impl<S> PathGen for TowerHttpCompat<S> {}
 
+impl<S> Describe for TowerHttpCompat<S> {}
+
 pub struct TowerCompatService<S> {
     service: RefCell<S>,
 }
@@ -79,9 +82,7 @@ where
 {
     type Response = WebResponse<CompatBody<ResB>>;
     type Error = S::Error;
-    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f
-    where
-    Self: 'f, 'r: 'f;
+    type Future<'f> = impl Future<Output = Result<Self::Response, Self::Error>> + 'f where Self: 'f, 'r: 'f;
 
     fn call<'s>(&'s self, mut req: WebRequest<'r, C, ReqB>) -> Self::Future<'s>
     where
@@ -92,6 +93,10 @@ where
             let (mut parts, ext) = req.take_request().into_parts();
             parts.extensions.insert(FakeSync::new(FakeSend::new(ctx)));
             let req = Request::from_parts(parts, CompatBody::new(FakeSend::new(ext)));
+
+            // tower service must be ready before it's called. the borrow is not held across await
+            // point so concurrent requests wait for readiness independently.
+            poll_fn(|cx| tower_service::Service::poll_ready(&mut *self.service.borrow_mut(), cx)).await?;
             let fut = tower_service::Service::call(&mut *self.service.borrow_mut(), req);
             fut.await.map(|res| res.map(CompatBody::new))
         }
@@ -101,9 +106,7 @@ where
 impl<S> ReadyService for TowerCompatService<S> {
     type Ready = ();
 
-    type Future<'f> = impl Future<Output = Self::Ready>
-    where
-        Self: 'f;
+    type Future<'f> = impl Future<Output = Self::Ready> where Self: 'f;
 
     #[inline]
     fn ready(&self) -> Self::Future<'_> {
@@ -111,6 +114,76 @@ impl<S> ReadyService for TowerCompatService<S> {
     }
 }
 
+/// Adapter for running xitca service as [tower_service::Service]. It's the reverse of
+/// [TowerCompatService] and can be mounted in a tower stack.
+///
+/// Given service must accept [Request] with [RequestExt] and [CompatBody] wrapping request body of
+/// tower service. e.g. the service produced by [App::finish](crate::App::finish).
+///
+/// # Example:
+/// ```rust
+/// # use xitca_web::{dev::bytes::Bytes, handler::handler_service, App};
+/// use xitca_web::{
+///     dev::service::Service,
+///     service::tower_http_compat::{CompatBody, XitcaCompat},
+/// };
+///
+/// # async fn doc_example() {
+/// // request body type of tower service is wrapped in CompatBody.
+/// let service = App::new::<CompatBody<http_body::Full<Bytes>>, _>()
+///     .at("/", handler_service(|| async { "hello" }))
+///     .finish()
+///     .call(())
+///     .await
+///     .unwrap();
+///
+/// // service implements tower_service::Service<http::Request<http_body::Full<Bytes>>>.
+/// let service = XitcaCompat::new(service);
+/// # }
+/// ```
+pub struct XitcaCompat<S> {
+    service: Rc<S>,
+}
+
+impl<S> Clone for XitcaCompat<S> {
+    fn clone(&self) -> Self {
+        Self {
+            service: self.service.clone(),
+        }
+    }
+}
+
+impl<S> XitcaCompat<S> {
+    pub fn new(service: S) -> Self {
+        Self {
+            service: Rc::new(service),
+        }
+    }
+}
+
+impl<S, B, ResB> tower_service::Service<Request<B>> for XitcaCompat<S>
+where
+    S: Service<Request<RequestExt<CompatBody<B>>>, Response = Response<ResB>> + 'static,
+    B: 'static,
+{
+    type Response = Response<CompatBody<ResB>>;
+    type Error = S::Error;
+    type Future = impl Future<Output = Result<Self::Response, Self::Error>>;
+
+    #[inline]
+    fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, req: Request<B>) -> Self::Future {
+        let service = self.service.clone();
+        async move {
+            let req = req.map(|body| RequestExt::<()>::default().map_body(|_| CompatBody::new(body)));
+            service.call(req).await.map(|res| res.map(CompatBody::new))
+        }
+    }
+}
+
 pin_project! {
     pub struct CompatBody<B> {
         #[pin]
@@ -128,6 +201,16 @@ impl<B> CompatBody<B> {
     }
 }
 
+// body extractors take ownership of request body by replacing it with default value.
+impl<B> Default for CompatBody<B>
+where
+    B: Default,
+{
+    fn default() -> Self {
+        Self::new(B::default())
+    }
+}
+
 impl<B, T, E> Body for CompatBody<B>
 where
     B: Stream<Item = Result<T, E>>,
@@ -206,4 +289,44 @@ mod test {
 
         assert_eq!(size, exact_body_hint(len));
     }
+
+    #[test]
+    fn xitca_compat() {
+        use xitca_unsafe_collection::futures::NowOrPanic;
+
+        use crate::{dev::service::Service, handler::handler_service, http::StatusCode, App};
+
+        let service = App::new()
+            .at("/", handler_service(|body: String| async move { body }))
+            .finish()
+            .call(())
+            .now_or_panic()
+            .unwrap();
+
+        let mut service = XitcaCompat::new(service);
+
+        let req = Request::builder()
+            .uri("/")
+            .body(http_body::Full::new(Bytes::from_static(b"251")))
+            .unwrap();
+
+        let res = tower_service::Service::call(&mut service.clone(), req)
+            .now_or_panic()
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let mut body = core::pin::pin!(res.into_body());
+        let mut collected = Vec::new();
+        while let Some(chunk) = core::future::poll_fn(|cx| Body::poll_data(body.as_mut(), cx)).now_or_panic() {
+            collected.extend_from_slice(&chunk.unwrap());
+        }
+        assert_eq!(collected, b"251");
+
+        let req = Request::builder()
+            .uri("/none")
+            .body(http_body::Full::new(Bytes::new()))
+            .unwrap();
+        let res = tower_service::Service::call(&mut service, req).now_or_panic().unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
 }
@@ -1,8 +1,55 @@
-use core::{future::poll_fn, pin::pin};
+//! utilities for testing.
+//!
+//! # Examples:
+//! ```rust
+//! use xitca_web::{
+//!     handler::handler_service,
+//!     http::StatusCode,
+//!     test::TestRequest,
+//!     App,
+//! };
+//!
+//! async fn index(body: String) -> String {
+//!     body
+//! }
+//!
+//! # async fn test() {
+//! let service = App::new().at("/", handler_service(index)).finish_for_test().await;
+//!
+//! let res = service.call(TestRequest::get("/").body("hello")).await.unwrap();
+//! res.assert_status(StatusCode::OK);
+//! assert_eq!(res.string_body().await.unwrap(), "hello");
+//! # }
+//! ```
+
+use core::{
+    fmt,
+    future::poll_fn,
+    mem,
+    ops::{Deref, DerefMut},
+    pin::pin,
+};
+
+use std::{error, net::SocketAddr};
 
 use futures_core::stream::Stream;
+use xitca_http::body::Once;
+
+use crate::{
+    body::{BoxStream, RequestBody, ResponseBody},
+    dev::{
+        bytes::Bytes,
+        service::{pipeline::PipelineE, ready::ReadyService, Service},
+    },
+    http::{
+        header::{AsHeaderName, HeaderValue, IntoHeaderName, CONTENT_LENGTH},
+        Method, Request, RequestExt, StatusCode, Uri,
+    },
+    response::WebResponse,
+};
 
-use crate::dev::service::pipeline::PipelineE;
+#[cfg(feature = "json")]
+use crate::http::{const_header_value, header::CONTENT_TYPE};
 
 /// Collect request or response body to Vec.
 pub async fn collect_body<B, T, E>(body: B) -> Result<Vec<u8>, E>
@@ -32,3 +79,307 @@ where
     let body = collect_body(body).await.map_err(CollectStringError::Second)?;
     String::from_utf8(body).map_err(CollectStringError::First)
 }
+
+/// Builder type for [Request] used by [TestService]. It's created with [Method::GET] and `/` uri
+/// path by default.
+///
+/// # Examples:
+/// ```rust
+/// use xitca_web::{http::{header::ACCEPT, Method}, test::TestRequest};
+///
+/// let req = TestRequest::default()
+///     .method(Method::POST)
+///     .uri("/users?id=996")
+///     .header(ACCEPT, "text/plain")
+///     .body("hello,world!");
+/// ```
+#[derive(Default)]
+pub struct TestRequest {
+    req: Request<RequestExt<RequestBody>>,
+}
+
+impl TestRequest {
+    /// Construct a [Method::GET] request with given uri.
+    ///
+    /// # Panics
+    ///
+    /// Panics when given uri is not valid.
+    pub fn get<U>(uri: U) -> Self
+    where
+        Uri: TryFrom<U>,
+        <Uri as TryFrom<U>>::Error: fmt::Debug,
+    {
+        Self::default().uri(uri)
+    }
+
+    /// Set method of request.
+    pub fn method(mut self, method: Method) -> Self {
+        *self.req.method_mut() = method;
+        self
+    }
+
+    /// Set uri of request.
+    ///
+    /// # Panics
+    ///
+    /// Panics when given uri is not valid.
+    pub fn uri<U>(mut self, uri: U) -> Self
+    where
+        Uri: TryFrom<U>,
+        <Uri as TryFrom<U>>::Error: fmt::Debug,
+    {
+        *self.req.uri_mut() = Uri::try_from(uri).expect("invalid uri");
+        self
+    }
+
+    /// Append a header to request.
+    ///
+    /// # Panics
+    ///
+    /// Panics when given value is not a valid header value.
+    pub fn header<K, V>(mut self, key: K, value: V) -> Self
+    where
+        K: IntoHeaderName,
+        HeaderValue: TryFrom<V>,
+        <HeaderValue as TryFrom<V>>::Error: fmt::Debug,
+    {
+        let value = HeaderValue::try_from(value).expect("invalid header value");
+        self.req.headers_mut().append(key, value);
+        self
+    }
+
+    /// Set socket address of connected peer.
+    pub fn socket_addr(mut self, addr: SocketAddr) -> Self {
+        *self.req.body_mut().socket_addr_mut() = addr;
+        self
+    }
+
+    /// Insert a typed value to request's [Extensions](crate::http::Extensions).
+    pub fn extension<T>(mut self, ext: T) -> Self
+    where
+        T: Send + Sync + 'static,
+    {
+        self.req.extensions_mut().insert(ext);
+        self
+    }
+
+    /// Set body of request. `Content-Length` header is set according to the length of body.
+    pub fn body(mut self, body: impl Into<Bytes>) -> Self {
+        let body = body.into();
+        self.req
+            .headers_mut()
+            .insert(CONTENT_LENGTH, HeaderValue::from(body.len()));
+        self.stream(Once::new(body))
+    }
+
+    /// Set a streaming body of request. Chunks of body are yield to service as they are produced
+    /// by stream and no buffering is happening in between.
+    pub fn stream<S, E>(mut self, stream: S) -> Self
+    where
+        S: Stream<Item = Result<Bytes, E>> + 'static,
+        E: error::Error + Send + Sync + 'static,
+    {
+        let body = RequestBody::from(BoxStream::new(stream));
+        let (ext, _) = mem::take(self.req.body_mut()).replace_body(body);
+        *self.req.body_mut() = ext;
+        self
+    }
+
+    /// Serialize given value as json body of request and set `Content-Type: application/json`
+    /// header.
+    ///
+    /// # Panics
+    ///
+    /// Panics when serialization failed.
+    #[cfg(feature = "json")]
+    pub fn json<T>(self, value: &T) -> Self
+    where
+        T: serde::ser::Serialize,
+    {
+        let body = serde_json::to_vec(value).expect("failed to serialize json body");
+        self.header(CONTENT_TYPE, const_header_value::JSON).body(body)
+    }
+
+    /// Finish the builder and return the request.
+    pub fn finish(self) -> Request<RequestExt<RequestBody>> {
+        self.req
+    }
+}
+
+/// In process service produced by [App::finish_for_test](crate::App::finish_for_test).
+///
+/// It runs the same service pipeline(router, middlewares and app state) as `HttpServer` does with
+/// the exception of http protocol layer and socket.
+pub struct TestService<S>(pub(crate) S);
+
+impl<S, ResB> TestService<S>
+where
+    S: ReadyService + Service<Request<RequestExt<RequestBody>>, Response = WebResponse<ResponseBody<ResB>>>,
+{
+    /// Call service with given [TestRequest].
+    pub async fn call(&self, req: TestRequest) -> Result<TestResponse<ResB>, S::Error> {
+        self.0.ready().await;
+        self.0.call(req.finish()).await.map(TestResponse)
+    }
+}
+
+/// Response type of [TestService].
+///
+/// It dereference to [WebResponse] and offers asserting and body collecting helpers.
+pub struct TestResponse<B>(WebResponse<ResponseBody<B>>);
+
+impl<B> TestResponse<B> {
+    /// Assert the status code of response.
+    ///
+    /// # Panics
+    ///
+    /// Panics when status code does not match.
+    #[track_caller]
+    pub fn assert_status(&self, status: StatusCode) -> &Self {
+        assert_eq!(self.0.status(), status, "unexpected response status code");
+        self
+    }
+
+    /// Assert the value of given header name of response.
+    ///
+    /// # Panics
+    ///
+    /// Panics when header is missing or it's value does not match.
+    #[track_caller]
+    pub fn assert_header<K>(&self, key: K, value: &str) -> &Self
+    where
+        K: AsHeaderName + fmt::Display + Clone,
+    {
+        match self.0.headers().get(key.clone()) {
+            Some(v) => assert_eq!(v, value, "unexpected value of header: {key}"),
+            None => panic!("header not found: {key}"),
+        }
+        self
+    }
+
+    /// Consume self and return the inner response.
+    pub fn into_inner(self) -> WebResponse<ResponseBody<B>> {
+        self.0
+    }
+
+    /// Collect response body to Vec.
+    pub async fn body<E>(self) -> Result<Vec<u8>, E>
+    where
+        B: Stream<Item = Result<Bytes, E>>,
+    {
+        collect_body(self.0.into_body()).await
+    }
+
+    /// Collect response body and parse it to String.
+    pub async fn string_body<E>(self) -> Result<String, CollectStringError<E>>
+    where
+        B: Stream<Item = Result<Bytes, E>>,
+    {
+        collect_string_body(self.0.into_body()).await
+    }
+}
+
+impl<B> Deref for TestResponse<B> {
+    type Target = WebResponse<ResponseBody<B>>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl<B> DerefMut for TestResponse<B> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::Infallible;
+
+    use futures_util::stream;
+    use xitca_unsafe_collection::futures::NowOrPanic;
+
+    use crate::{
+        handler::{extension::ExtensionRef, handler_service, header::HeaderRef},
+        http::header::{CONTENT_TYPE, HOST},
+        App,
+    };
+
+    use super::*;
+
+    #[test]
+    fn request_builder() {
+        async fn handler(
+            ExtensionRef(ext): ExtensionRef<'_, &'static str>,
+            host: HeaderRef<'_, { crate::handler::header::HOST }>,
+            body: String,
+        ) -> String {
+            format!("{ext}-{}-{body}", host.to_str().unwrap())
+        }
+
+        let service = App::new()
+            .at("/", handler_service(handler))
+            .finish_for_test()
+            .now_or_panic();
+
+        let req = TestRequest::default()
+            .method(Method::POST)
+            .header(HOST, "996")
+            .extension("ext")
+            .body("body");
+
+        let res = service.call(req).now_or_panic().unwrap();
+        res.assert_status(StatusCode::OK)
+            .assert_header(CONTENT_TYPE, "text/plain; charset=utf-8");
+        assert_eq!(res.string_body().now_or_panic().unwrap(), "ext-996-body");
+
+        let res = service.call(TestRequest::get("/nah")).now_or_panic().unwrap();
+        res.assert_status(StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn stream_body() {
+        async fn handler(body: String) -> String {
+            body
+        }
+
+        let service = App::new()
+            .at("/", handler_service(handler))
+            .finish_for_test()
+            .now_or_panic();
+
+        let chunks =
+            ["hello", ",", "world", "!"].map(|chunk| Ok::<_, Infallible>(Bytes::from_static(chunk.as_bytes())));
+        let req = TestRequest::default().stream(stream::iter(chunks));
+
+        let res = service.call(req).now_or_panic().unwrap();
+        assert_eq!(res.string_body().now_or_panic().unwrap(), "hello,world!");
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_body() {
+        use crate::handler::json::Json;
+
+        #[derive(serde::Deserialize, serde::Serialize)]
+        struct Item {
+            id: u32,
+        }
+
+        async fn handler(Json(item): Json<Item>) -> String {
+            item.id.to_string()
+        }
+
+        let service = App::new()
+            .at("/", handler_service(handler))
+            .finish_for_test()
+            .now_or_panic();
+
+        let res = service
+            .call(TestRequest::default().json(&Item { id: 996 }))
+            .now_or_panic()
+            .unwrap();
+        assert_eq!(res.string_body().now_or_panic().unwrap(), "996");
+    }
+}
@@ -0,0 +1,116 @@
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use serde::{Deserialize, Serialize};
+use xitca_unsafe_collection::futures::NowOrPanic;
+use xitca_web::{
+    handler::{handler_service, json::Json, params::Params, query::Query},
+    http::{Method, Scratch},
+    test::TestRequest,
+    App,
+};
+
+// global allocator counting allocations so reduction from scratch arena can be asserted.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+#[derive(Deserialize)]
+struct Item {
+    id: u32,
+}
+
+#[derive(Deserialize)]
+struct Search {
+    page: u32,
+    size: u8,
+}
+
+#[derive(Deserialize, Serialize)]
+struct Payload {
+    name: String,
+    tags: Vec<u32>,
+}
+
+async fn handler(
+    Params(item): Params<Item>,
+    Query(search): Query<Search>,
+    Json(payload): Json<Payload>,
+) -> &'static str {
+    assert_eq!(item.id, 996);
+    assert_eq!(search.page, 12);
+    assert_eq!(search.size, 25);
+    assert_eq!(payload.tags.len(), 128);
+    "ok"
+}
+
+const ROUNDS: usize = 100;
+
+#[test]
+fn scratch_allocations() {
+    let service = App::new()
+        .at("/items/:id", handler_service(handler))
+        .finish_for_test()
+        .now_or_panic();
+
+    let payload = Payload {
+        name: "dagongren".repeat(64),
+        tags: (0..128).collect(),
+    };
+
+    // allocations of handling ROUNDS requests with percent-encoded path, query and json body.
+    let count = |scratch: Option<&Scratch>| {
+        let reqs = (0..ROUNDS)
+            .map(|_| {
+                let req = TestRequest::get("/items/%39%39%36?page=%31%32&size=%32%35")
+                    .method(Method::POST)
+                    .json(&payload);
+                match scratch {
+                    Some(scratch) => req.extension(scratch.clone()),
+                    None => req,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        let start = ALLOCATIONS.load(Ordering::Relaxed);
+        for req in reqs {
+            let res = service.call(req).now_or_panic().unwrap();
+            drop(res);
+            // connection resets it's arena for next request.
+            if let Some(scratch) = scratch {
+                assert!(scratch.try_reset());
+            }
+        }
+        ALLOCATIONS.load(Ordering::Relaxed) - start
+    };
+
+    let scratch = Scratch::new();
+
+    // warm up arena.
+    count(Some(&scratch));
+
+    let heap = count(None);
+    let arena = count(Some(&scratch));
+
+    // decoded path param, decoded query value and collected json body are not heap allocated.
+    assert!(
+        heap - arena >= ROUNDS * 3,
+        "allocations without scratch: {heap}, with scratch: {arena}"
+    );
+}